@@ -0,0 +1,278 @@
+//! What kind of participant an account is, which decides what it may do
+//! with tokens. Only food banks earn contribution rewards and redeem
+//! program options; suppliers receive transfers and will get an option
+//! category of their own; donors fund matching pools. The type is chosen
+//! when the account first registers a profile and only the owner can
+//! change it afterwards. Accounts that never registered, and registrations
+//! that name no type, get `DEFAULT_ACCOUNT_TYPE`. Reward and redemption
+//! events carry the type the account had when they were emitted.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{AccountTypeSet, Event};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// Food bank, because every participant was one before types existed and
+/// recorders still credit accounts that never registered a profile. A
+/// supplier or donor has to say so when it registers.
+pub const DEFAULT_ACCOUNT_TYPE: AccountType = AccountType::FoodBank;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AccountType {
+    FoodBank,
+    Supplier,
+    Donor,
+}
+
+impl AccountType {
+    pub(crate) fn can_earn_rewards(self) -> bool {
+        self == AccountType::FoodBank
+    }
+
+    pub(crate) fn can_redeem(self) -> bool {
+        self == AccountType::FoodBank
+    }
+
+    pub(crate) fn can_receive_transfers(self) -> bool {
+        self != AccountType::Donor
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn set_account_type(&mut self, account_id: AccountId, account_type: AccountType) {
+        self.assert_owner_action();
+        let old_type = self.account_type(&account_id);
+        self.account_types.insert(&account_id, &account_type);
+        AccountTypeSet {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+            old_type,
+            account_type,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    // View functions
+    /// `DEFAULT_ACCOUNT_TYPE` for accounts that never registered one.
+    pub fn get_account_type(&self, account_id: AccountId) -> AccountType {
+        self.account_type(&account_id)
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn account_type(&self, account_id: &AccountId) -> AccountType {
+        self.account_types.get(account_id).unwrap_or(DEFAULT_ACCOUNT_TYPE)
+    }
+
+    /// Stores the type picked at the account's first registration. Later
+    /// registrations may only repeat it.
+    pub(crate) fn register_account_type(&mut self, account_id: &AccountId, requested: Option<AccountType>) -> AccountType {
+        match self.account_types.get(account_id) {
+            Some(current) => {
+                assert!(
+                    requested.is_none_or(|requested| requested == current),
+                    "Only the owner can change the account type"
+                );
+                current
+            }
+            None => {
+                let account_type = requested.unwrap_or(DEFAULT_ACCOUNT_TYPE);
+                self.account_types.insert(account_id, &account_type);
+                account_type
+            }
+        }
+    }
+
+    pub(crate) fn assert_can_earn_rewards(&self, account_id: &AccountId) {
+        let account_type = self.account_type(account_id);
+        assert!(
+            account_type.can_earn_rewards(),
+            "{:?} accounts cannot earn contribution rewards",
+            account_type
+        );
+    }
+
+    pub(crate) fn assert_can_receive_transfers(&self, account_id: &AccountId) {
+        let account_type = self.account_type(account_id);
+        assert!(
+            account_type.can_receive_transfers(),
+            "{:?} accounts cannot receive transfers",
+            account_type
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::OrgType;
+    use crate::redemptions::RedeemCheck;
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    fn treasury() -> AccountId {
+        accounts(3)
+    }
+
+    fn register(contract: &mut FoodBankToken, account_id: AccountId, account_type: Option<AccountType>) {
+        set_caller(account_id, ONE_NEAR);
+        contract.register_profile("Harbor".to_string(), "north".to_string(), OrgType::Other, account_type);
+    }
+
+    fn typed(account_type: AccountType) -> FoodBankToken {
+        let mut contract = setup_contract();
+        register(&mut contract, food_bank(), Some(account_type));
+        contract
+    }
+
+    #[test]
+    fn accounts_without_a_type_are_food_banks() {
+        let mut contract = setup_contract();
+        assert_eq!(contract.get_account_type(food_bank()), AccountType::FoodBank);
+        register(&mut contract, food_bank(), None);
+        assert_eq!(contract.get_account_type(food_bank()), AccountType::FoodBank);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+    }
+
+    #[test]
+    fn the_type_is_in_the_profile_event() {
+        let mut contract = setup_contract();
+        register(&mut contract, food_bank(), Some(AccountType::Supplier));
+        assert!(get_logs()[0].contains(r#""account_type":"Supplier""#));
+        assert_eq!(contract.get_account_type(food_bank()), AccountType::Supplier);
+    }
+
+    fn logged(name: &str) -> String {
+        get_logs().into_iter().find(|log| log.contains(name)).unwrap()
+    }
+
+    #[test]
+    fn reward_and_redemption_events_carry_the_type() {
+        let mut contract = typed(AccountType::Supplier);
+        set_caller(owner(), 0);
+        contract.set_account_type(food_bank(), AccountType::FoodBank);
+        contribute(&mut contract, &food_bank(), 100);
+        assert!(logged("contribution_recorded").contains(r#""account_type":"FoodBank""#));
+
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        let receipt_id = last_receipt_id(&contract);
+        resolve_callback(near_sdk::PromiseResult::Successful(Vec::new()));
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            receipt_id,
+            token_charge(100),
+        );
+        assert!(logged("redemption_completed").contains(r#""account_type":"FoodBank""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Supplier accounts cannot earn contribution rewards")]
+    fn suppliers_cannot_earn_rewards() {
+        let mut contract = typed(AccountType::Supplier);
+        contribute(&mut contract, &food_bank(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Donor accounts cannot earn contribution rewards")]
+    fn donors_cannot_earn_rewards() {
+        let mut contract = typed(AccountType::Donor);
+        contribute(&mut contract, &food_bank(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Supplier accounts cannot redeem program options")]
+    fn suppliers_cannot_redeem() {
+        let mut contract = typed(AccountType::Supplier);
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Donor accounts cannot redeem program options")]
+    fn donors_cannot_redeem() {
+        let mut contract = typed(AccountType::Donor);
+        contract.mint(food_bank(), 100);
+        assert_eq!(
+            contract.can_redeem(food_bank(), "supplier_discount".to_string()),
+            RedeemCheck::AccountTypeNotAllowed {
+                account_type: AccountType::Donor
+            }
+        );
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    fn suppliers_receive_transfers() {
+        let mut contract = typed(AccountType::Supplier);
+        set_caller(owner(), 0);
+        contract.set_treasury(Some(treasury()), 0);
+        contract.mint(treasury(), 100);
+        set_caller(owner(), 1);
+        contract.treasury_transfer(food_bank(), U128(40));
+        assert_eq!(contract.get_balance(food_bank()).0, 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "Donor accounts cannot receive transfers")]
+    fn donors_cannot_receive_transfers() {
+        let mut contract = typed(AccountType::Donor);
+        set_caller(owner(), 0);
+        contract.set_treasury(Some(treasury()), 0);
+        contract.mint(treasury(), 100);
+        set_caller(owner(), 1);
+        contract.treasury_transfer(food_bank(), U128(40));
+    }
+
+    #[test]
+    fn donors_fund_matching_pools() {
+        let mut contract = typed(AccountType::Donor);
+        set_caller(owner(), 0);
+        contract.set_tokens_per_near(U128(100));
+        set_caller(food_bank(), ONE_NEAR);
+        contract.fund_matching_pool();
+        assert_eq!(contract.get_matching_pool().unwrap().donor, food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can change the account type")]
+    fn re_registering_cannot_change_the_type() {
+        let mut contract = typed(AccountType::Donor);
+        register(&mut contract, food_bank(), Some(AccountType::FoodBank));
+    }
+
+    #[test]
+    fn clearing_the_profile_keeps_the_type() {
+        let mut contract = typed(AccountType::Donor);
+        set_caller(food_bank(), 0);
+        contract.clear_profile();
+        assert_eq!(contract.get_account_type(food_bank()), AccountType::Donor);
+    }
+
+    #[test]
+    fn the_owner_changes_the_type() {
+        let mut contract = typed(AccountType::Supplier);
+        set_caller(owner(), 0);
+        contract.set_account_type(food_bank(), AccountType::FoodBank);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn others_cannot_change_the_type() {
+        let mut contract = typed(AccountType::Donor);
+        set_caller(food_bank(), 0);
+        contract.set_account_type(food_bank(), AccountType::FoodBank);
+    }
+}
@@ -0,0 +1,324 @@
+//! Optional sign-off by food banks on the scores recorded for them. With
+//! acknowledgment required, a recorded contribution's reward is calculated
+//! as usual but held back until the food bank acknowledges it. A rejection
+//! keeps it held and flags it for the recorder to review. Contributions the
+//! food bank leaves alone are accepted once the window passes, via `tick`.
+//!
+//! Contributions in a category that needs verification, and those recorded
+//! in epoch mode, are paid through their own flows and are not held.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{ContributionAcknowledged, Event};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_ACKNOWLEDGMENT_WINDOW: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+const MAX_REJECTION_REASON_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AcknowledgmentStatus {
+    PendingAck,
+    Acknowledged,
+    /// The window passed without a response.
+    AutoAccepted,
+    Rejected,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingAcknowledgment {
+    pub account_id: AccountId,
+    pub contribution_id: u64,
+    /// Calculated when the contribution was recorded and paid on release.
+    pub reward: U128,
+    /// Accepted automatically from this timestamp on.
+    pub deadline: u64,
+    pub status: AcknowledgmentStatus,
+    pub rejection_reason: Option<String>,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Contributions recorded while acknowledgment is off, or before a
+    /// window change, keep the terms they were recorded under.
+    pub fn set_acknowledgment_mode(&mut self, required: bool, window_ns: u64) {
+        self.assert_owner_action();
+        assert!(window_ns > 0, "Acknowledgment window must be positive");
+        self.acknowledgment_required = required;
+        self.acknowledgment_window = window_ns;
+    }
+
+    /// Accepts the caller's contribution and pays the reward held for it.
+    pub fn acknowledge_contribution(&mut self, contribution_id: u64) {
+        self.internal_acknowledge_contribution(&env::predecessor_account_id(), contribution_id);
+    }
+
+    /// Flags the caller's contribution for the recorder to review. Its
+    /// reward stays held.
+    pub fn reject_contribution(&mut self, contribution_id: u64, reason: String) {
+        self.internal_reject_contribution(&env::predecessor_account_id(), contribution_id, reason);
+    }
+
+    // View functions
+    pub fn get_acknowledgment_mode(&self) -> (bool, u64) {
+        (self.acknowledgment_required, self.acknowledgment_window)
+    }
+
+    pub fn get_acknowledgment(&self, account_id: AccountId, contribution_id: u64) -> Option<PendingAcknowledgment> {
+        self.acknowledgments.get(&(account_id, contribution_id))
+    }
+
+    /// The account's contributions still waiting for its response, oldest
+    /// first.
+    pub fn get_pending_acknowledgments(&self, account_id: AccountId) -> Vec<PendingAcknowledgment> {
+        self.pending_acknowledgments
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|contribution_id| self.acknowledgments.get(&(account_id.clone(), contribution_id)).unwrap())
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    /// `account_id` is the food bank, whether it called directly or through
+    /// `relay_call`.
+    pub(crate) fn internal_acknowledge_contribution(&mut self, account_id: &AccountId, contribution_id: u64) {
+        self.assert_not_paused();
+        let mut pending = self.open_acknowledgment(account_id, contribution_id);
+        self.release_acknowledgment(&mut pending, AcknowledgmentStatus::Acknowledged);
+    }
+
+    pub(crate) fn internal_reject_contribution(&mut self, account_id: &AccountId, contribution_id: u64, reason: String) {
+        self.assert_not_paused();
+        assert!(
+            reason.len() <= MAX_REJECTION_REASON_LENGTH,
+            "Reason exceeds {} bytes",
+            MAX_REJECTION_REASON_LENGTH
+        );
+        let mut pending = self.open_acknowledgment(account_id, contribution_id);
+        pending.status = AcknowledgmentStatus::Rejected;
+        pending.rejection_reason = Some(reason);
+        self.close_acknowledgment(&pending);
+    }
+
+    /// Whether a contribution recorded now would have its reward held.
+    pub(crate) fn holds_for_acknowledgment(&self, verified: bool) -> bool {
+        self.acknowledgment_required && !verified && !self.in_epoch_mode()
+    }
+
+    pub(crate) fn queue_acknowledgment(&mut self, account_id: &AccountId, contribution_id: u64, reward: U128) {
+        let key = (account_id.clone(), contribution_id);
+        self.acknowledgments.insert(
+            &key,
+            &PendingAcknowledgment {
+                account_id: account_id.clone(),
+                contribution_id,
+                reward,
+                deadline: env::block_timestamp() + self.acknowledgment_window,
+                status: AcknowledgmentStatus::PendingAck,
+                rejection_reason: None,
+            },
+        );
+        self.acknowledgment_order.insert(&self.next_acknowledgment_seq, &key);
+        self.next_acknowledgment_seq += 1;
+        let mut pending = self.pending_acknowledgments.get(account_id).unwrap_or_default();
+        pending.push(contribution_id);
+        self.pending_acknowledgments.insert(account_id, &pending);
+    }
+
+    /// False while the contribution's reward is held for acknowledgment or
+    /// after the food bank rejected it.
+    pub(crate) fn is_acknowledged(&self, key: &(AccountId, u64)) -> bool {
+        self.acknowledgments.get(key).is_none_or(|pending| {
+            matches!(
+                pending.status,
+                AcknowledgmentStatus::Acknowledged | AcknowledgmentStatus::AutoAccepted
+            )
+        })
+    }
+
+    pub(crate) fn acknowledgment_expiry_due(&self) -> bool {
+        if self.next_acknowledgment_to_expire == self.next_acknowledgment_seq {
+            return false;
+        }
+        let key = self.acknowledgment_order.get(&self.next_acknowledgment_to_expire).unwrap();
+        self.acknowledgments.get(&key).is_some_and(|pending| {
+            pending.status != AcknowledgmentStatus::PendingAck || env::block_timestamp() >= pending.deadline
+        })
+    }
+
+    /// Walks up to `limit` contributions in recording order, accepting those
+    /// past their deadline, and stops at the first that is still open.
+    /// Returns how many were accepted.
+    pub(crate) fn accept_expired_acknowledgments(&mut self, limit: u64) -> u64 {
+        let mut accepted = 0;
+        for _ in 0..limit {
+            if !self.acknowledgment_expiry_due() {
+                break;
+            }
+            let key = self.acknowledgment_order.get(&self.next_acknowledgment_to_expire).unwrap();
+            let mut pending = self.acknowledgments.get(&key).unwrap();
+            if pending.status == AcknowledgmentStatus::PendingAck {
+                self.release_acknowledgment(&mut pending, AcknowledgmentStatus::AutoAccepted);
+                accepted += 1;
+            }
+            self.acknowledgment_order.remove(&self.next_acknowledgment_to_expire);
+            self.next_acknowledgment_to_expire += 1;
+        }
+        accepted
+    }
+
+    fn open_acknowledgment(&self, account_id: &AccountId, contribution_id: u64) -> PendingAcknowledgment {
+        let pending = self
+            .acknowledgments
+            .get(&(account_id.clone(), contribution_id))
+            .expect("Contribution does not need acknowledgment");
+        assert_eq!(
+            pending.status,
+            AcknowledgmentStatus::PendingAck,
+            "Contribution is not pending acknowledgment"
+        );
+        pending
+    }
+
+    /// Pays the held reward unless the contribution was voided meanwhile.
+    fn release_acknowledgment(&mut self, pending: &mut PendingAcknowledgment, status: AcknowledgmentStatus) {
+        pending.status = status;
+        let key = (pending.account_id.clone(), pending.contribution_id);
+        if self.voided_contributions.get(&key).is_none() {
+            self.pay_withheld_reward(&pending.account_id, pending.contribution_id, pending.reward);
+        }
+        self.close_acknowledgment(pending);
+    }
+
+    fn close_acknowledgment(&mut self, pending: &PendingAcknowledgment) {
+        let key = (pending.account_id.clone(), pending.contribution_id);
+        self.acknowledgments.insert(&key, pending);
+        let mut queue = self.pending_acknowledgments.get(&pending.account_id).unwrap_or_default();
+        queue.retain(|id| *id != pending.contribution_id);
+        if queue.is_empty() {
+            self.pending_acknowledgments.remove(&pending.account_id);
+        } else {
+            self.pending_acknowledgments.insert(&pending.account_id, &queue);
+        }
+        ContributionAcknowledged {
+            account_id: &pending.account_id,
+            account_type: self.account_type(&pending.account_id),
+            contribution_id: pending.contribution_id,
+            status: pending.status,
+            reward: pending.reward,
+            rejection_reason: pending.rejection_reason.as_deref(),
+        }
+        .emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const WINDOW: u64 = 1_000;
+
+    /// `food_bank` with one contribution worth 5 tokens awaiting its
+    /// acknowledgment.
+    fn pending_contract() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_acknowledgment_mode(true, WINDOW);
+        contribute(&mut contract, &food_bank(), 100);
+        contract
+    }
+
+    #[test]
+    fn rewards_are_held_until_acknowledged() {
+        let mut contract = pending_contract();
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 0);
+        let pending = contract.get_pending_acknowledgments(food_bank());
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].reward.0, 5);
+
+        set_caller(food_bank(), 0);
+        contract.acknowledge_contribution(0);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 5);
+        assert!(contract.get_pending_acknowledgments(food_bank()).is_empty());
+        assert_eq!(
+            contract.get_acknowledgment(food_bank(), 0).unwrap().status,
+            AcknowledgmentStatus::Acknowledged
+        );
+    }
+
+    #[test]
+    fn rewards_use_the_rate_at_recording() {
+        let mut contract = pending_contract();
+        set_reward_rate_bps(&mut contract, 2_000);
+        set_caller(food_bank(), 0);
+        contract.acknowledge_contribution(0);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution is not pending acknowledgment")]
+    fn contributions_cannot_be_acknowledged_twice() {
+        let mut contract = pending_contract();
+        set_caller(food_bank(), 0);
+        contract.acknowledge_contribution(0);
+        contract.acknowledge_contribution(0);
+    }
+
+    #[test]
+    fn unanswered_contributions_are_accepted_at_the_deadline() {
+        let mut contract = pending_contract();
+        advance_time(WINDOW - 1);
+        assert_eq!(contract.tick().acknowledgments_accepted, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+
+        advance_time(1);
+        assert_eq!(contract.tick().acknowledgments_accepted, 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+        assert_eq!(
+            contract.get_acknowledgment(food_bank(), 0).unwrap().status,
+            AcknowledgmentStatus::AutoAccepted
+        );
+        assert_eq!(contract.tick().acknowledgments_accepted, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+    }
+
+    #[test]
+    fn rejected_contributions_stay_held() {
+        let mut contract = pending_contract();
+        set_caller(food_bank(), 0);
+        contract.reject_contribution(0, "Scores are from last month's batch".to_string());
+        let rejected = contract.get_acknowledgment(food_bank(), 0).unwrap();
+        assert_eq!(rejected.status, AcknowledgmentStatus::Rejected);
+        assert!(contract.get_pending_acknowledgments(food_bank()).is_empty());
+
+        advance_time(WINDOW);
+        assert_eq!(contract.tick().acknowledgments_accepted, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert!(!contract.counts_for_rewards(&(food_bank(), 0)));
+    }
+
+    #[test]
+    fn voided_contributions_are_not_paid_on_acceptance() {
+        let mut contract = pending_contract();
+        contract.void_contribution(food_bank(), 0);
+        advance_time(WINDOW);
+        assert_eq!(contract.tick().acknowledgments_accepted, 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution does not need acknowledgment")]
+    fn only_the_food_bank_can_acknowledge() {
+        let mut contract = pending_contract();
+        set_caller(other_food_bank(), 0);
+        contract.acknowledge_contribution(0);
+    }
+}
@@ -0,0 +1,320 @@
+//! Public activity feeds of recent contributions and redemptions, for
+//! front ends without an indexer. Each feed is a ring of the last
+//! `activity_feed_capacity` entries numbered by a sequence that never
+//! repeats, so a page picks up where the previous one stopped even after
+//! newer entries have evicted older ones. Pages run newest first: pass the
+//! sequence below the last one seen as `from_index` to get the next page.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_ACTIVITY_FEED_CAPACITY: u64 = 500;
+/// Around 150 bytes an entry puts both full feeds at about 3 NEAR of storage.
+pub const MAX_ACTIVITY_FEED_CAPACITY: u64 = 1_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContributionActivity {
+    pub sequence: u64,
+    pub account_id: AccountId,
+    /// The contribution's index in the account's history.
+    pub contribution_id: u64,
+    pub score: u8,
+    /// Tokens paid out when the contribution was recorded; 0 while the
+    /// reward is held for verification or acknowledgment.
+    pub reward: U128,
+    pub timestamp: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RedemptionActivity {
+    pub sequence: u64,
+    pub account_id: AccountId,
+    pub receipt_id: u64,
+    pub option_id: String,
+    pub amount: U128,
+    pub timestamp: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ActivityFeed<T> {
+    entries: Vector<T>,
+    /// Index of the oldest entry once the feed is full.
+    head: u64,
+    latest_sequence: u64,
+}
+
+impl<T: BorshDeserialize + BorshSerialize> ActivityFeed<T> {
+    pub(crate) fn new(prefix: Vec<u8>) -> Self {
+        Self {
+            entries: Vector::new(prefix),
+            head: 0,
+            latest_sequence: 0,
+        }
+    }
+
+    /// Evicts the oldest entry when the feed holds `capacity` of them.
+    fn push(&mut self, capacity: u64, entry: impl FnOnce(u64) -> T) {
+        self.latest_sequence += 1;
+        if capacity == 0 {
+            return;
+        }
+        let entry = entry(self.latest_sequence);
+        if self.entries.len() < capacity {
+            self.entries.push(&entry);
+        } else {
+            self.entries.replace(self.head, &entry);
+            self.head = (self.head + 1) % self.entries.len();
+        }
+    }
+
+    fn oldest_sequence(&self) -> u64 {
+        self.latest_sequence + 1 - self.entries.len()
+    }
+
+    fn get_sequence(&self, sequence: u64) -> T {
+        let offset = sequence - self.oldest_sequence();
+        self.entries.get((self.head + offset) % self.entries.len()).unwrap()
+    }
+
+    /// Newest first, from `from_sequence` down.
+    fn page(&self, from_sequence: Option<u64>, limit: u64) -> Vec<T> {
+        let newest = from_sequence.map_or(self.latest_sequence, |sequence| sequence.min(self.latest_sequence));
+        (self.oldest_sequence()..=newest)
+            .rev()
+            .take(limit as usize)
+            .map(|sequence| self.get_sequence(sequence))
+            .collect()
+    }
+
+    /// Keeps the newest `capacity` entries, laid out from index 0 again so
+    /// pushes can append until the feed is full.
+    fn resize(&mut self, capacity: u64) {
+        let len = self.entries.len();
+        let evicted = len.saturating_sub(capacity);
+        let kept: Vec<T> = (evicted..len)
+            .map(|offset| self.entries.get((self.head + offset) % len).unwrap())
+            .collect();
+        self.entries.clear();
+        self.entries.extend(kept);
+        self.head = 0;
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Applies to both feeds. Shrinking evicts the oldest entries and frees
+    /// their storage; 0 stops recording.
+    pub fn set_activity_feed_capacity(&mut self, capacity: u64) {
+        self.assert_owner_action();
+        assert!(
+            capacity <= MAX_ACTIVITY_FEED_CAPACITY,
+            "Activity feed capacity must not exceed {}",
+            MAX_ACTIVITY_FEED_CAPACITY
+        );
+        self.recent_contributions.resize(capacity);
+        self.recent_redemptions.resize(capacity);
+        self.activity_feed_capacity = capacity;
+    }
+
+    // View functions
+    pub fn get_activity_feed_capacity(&self) -> u64 {
+        self.activity_feed_capacity
+    }
+
+    /// Up to `limit` contributions, newest first, starting at sequence
+    /// `from_index` or at the newest one without it.
+    pub fn get_recent_contributions(&self, from_index: Option<u64>, limit: u64) -> Vec<ContributionActivity> {
+        self.recent_contributions.page(from_index, limit)
+    }
+
+    /// Up to `limit` delivered redemptions, newest first, starting at
+    /// sequence `from_index` or at the newest one without it.
+    pub fn get_recent_redemptions(&self, from_index: Option<u64>, limit: u64) -> Vec<RedemptionActivity> {
+        self.recent_redemptions.page(from_index, limit)
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn record_contribution_activity(
+        &mut self,
+        account_id: &AccountId,
+        contribution_id: u64,
+        score: u8,
+        reward: U128,
+    ) {
+        self.recent_contributions
+            .push(self.activity_feed_capacity, |sequence| ContributionActivity {
+                sequence,
+                account_id: account_id.clone(),
+                contribution_id,
+                score,
+                reward,
+                timestamp: env::block_timestamp(),
+            });
+    }
+
+    pub(crate) fn record_redemption_activity(
+        &mut self,
+        account_id: &AccountId,
+        receipt_id: u64,
+        option_id: &str,
+        amount: U128,
+    ) {
+        self.recent_redemptions
+            .push(self.activity_feed_capacity, |sequence| RedemptionActivity {
+                sequence,
+                account_id: account_id.clone(),
+                receipt_id,
+                option_id: option_id.to_string(),
+                amount,
+                timestamp: env::block_timestamp(),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::PromiseResult;
+
+    fn sequences<T>(entries: &[T], sequence: impl Fn(&T) -> u64) -> Vec<u64> {
+        entries.iter().map(sequence).collect()
+    }
+
+    fn contribution_sequences(contract: &FoodBankToken, from_index: Option<u64>, limit: u64) -> Vec<u64> {
+        sequences(&contract.get_recent_contributions(from_index, limit), |entry| entry.sequence)
+    }
+
+    #[test]
+    fn contributions_come_back_newest_first() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 40);
+        contribute(&mut contract, &other_food_bank(), 60);
+        contribute(&mut contract, &food_bank(), 80);
+
+        let feed = contract.get_recent_contributions(None, 10);
+        assert_eq!(sequences(&feed, |entry| entry.sequence), [3, 2, 1]);
+        assert_eq!(feed[0].account_id, food_bank());
+        assert_eq!(feed[0].contribution_id, 1);
+        assert_eq!(feed[0].score, 80);
+        assert_eq!(feed[0].reward, U128(80));
+        assert_eq!(feed[1].account_id, other_food_bank());
+        assert_eq!(feed[1].contribution_id, 0);
+    }
+
+    #[test]
+    fn pages_continue_below_the_last_sequence() {
+        let mut contract = setup_contract();
+        for score in [20, 30, 40, 50, 60] {
+            contribute(&mut contract, &food_bank(), score);
+        }
+        assert_eq!(contribution_sequences(&contract, None, 2), [5, 4]);
+        assert_eq!(contribution_sequences(&contract, Some(3), 2), [3, 2]);
+        assert_eq!(contribution_sequences(&contract, Some(1), 2), [1]);
+        assert!(contribution_sequences(&contract, Some(0), 2).is_empty());
+    }
+
+    #[test]
+    fn a_full_feed_evicts_the_oldest_entries() {
+        let mut contract = setup_contract();
+        contract.set_activity_feed_capacity(3);
+        for score in [20, 30, 40, 50, 60] {
+            contribute(&mut contract, &food_bank(), score);
+        }
+        let feed = contract.get_recent_contributions(None, 10);
+        assert_eq!(sequences(&feed, |entry| entry.sequence), [5, 4, 3]);
+        assert_eq!(sequences(&feed, |entry| u64::from(entry.score)), [60, 50, 40]);
+    }
+
+    #[test]
+    fn paging_across_an_eviction_keeps_the_sequence() {
+        let mut contract = setup_contract();
+        contract.set_activity_feed_capacity(4);
+        for score in [20, 30, 40, 50] {
+            contribute(&mut contract, &food_bank(), score);
+        }
+        let first = contract.get_recent_contributions(None, 2);
+        assert_eq!(sequences(&first, |entry| entry.sequence), [4, 3]);
+
+        // Two newer contributions evict sequences 1 and 2 meanwhile.
+        contribute(&mut contract, &food_bank(), 60);
+        contribute(&mut contract, &food_bank(), 70);
+        let next = contract.get_recent_contributions(Some(first[1].sequence - 1), 2);
+        assert!(next.is_empty());
+        let feed = contract.get_recent_contributions(Some(4), 10);
+        assert_eq!(sequences(&feed, |entry| entry.sequence), [4, 3]);
+        assert_eq!(feed[1].score, 40);
+    }
+
+    #[test]
+    fn resizing_keeps_the_newest_entries() {
+        let mut contract = setup_contract();
+        contract.set_activity_feed_capacity(3);
+        // Wraps the ring so its head is no longer at index 0.
+        for score in [20, 30, 40, 50, 60] {
+            contribute(&mut contract, &food_bank(), score);
+        }
+        set_caller(owner(), 0);
+        contract.set_activity_feed_capacity(2);
+        assert_eq!(contribution_sequences(&contract, None, 10), [5, 4]);
+
+        contract.set_activity_feed_capacity(3);
+        contribute(&mut contract, &food_bank(), 70);
+        assert_eq!(contribution_sequences(&contract, None, 10), [6, 5, 4]);
+    }
+
+    #[test]
+    fn delivered_redemptions_are_listed() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 1_000);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        let receipt_id = last_receipt_id(&contract);
+        assert!(contract.get_recent_redemptions(None, 10).is_empty());
+
+        resolve_callback(PromiseResult::Successful(Vec::new()));
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            receipt_id,
+            token_charge(100),
+        );
+        let feed = contract.get_recent_redemptions(None, 10);
+        assert_eq!(
+            feed,
+            [RedemptionActivity {
+                sequence: 1,
+                account_id: food_bank(),
+                receipt_id,
+                option_id: "supplier_discount".to_string(),
+                amount: U128(100),
+                timestamp: feed[0].timestamp,
+            }]
+        );
+        assert!(contract.get_recent_contributions(None, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Activity feed capacity must not exceed 1000")]
+    fn the_capacity_is_bounded() {
+        let mut contract = setup_contract();
+        contract.set_activity_feed_capacity(1_001);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn the_capacity_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.set_activity_feed_capacity(10);
+    }
+}
@@ -0,0 +1,191 @@
+//! Keeps any one account's contribution history from growing until writing
+//! it back no longer fits in a transaction's gas. Once an account holds
+//! `max_history_length` live entries, further contributions still pay out
+//! and count towards every aggregate but are stored as compact digests.
+//!
+//! `archive_contributions` drops an account's oldest entries from state,
+//! keeping only the sha256 of their borsh encoding, so a copy exported
+//! beforehand with `export_contributions` can still be verified. Indices
+//! don't shift: the live history starts where the archives end.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::contributions::VersionedContribution;
+use crate::events::{ContributionsArchived, Event};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_MAX_HISTORY_LENGTH: u64 = 5_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ArchiveCheckpoint {
+    /// Index of the first archived entry.
+    pub from_index: u64,
+    pub count: u64,
+    /// sha256 of the archived entries as a borsh `Vec<VersionedContribution>`.
+    pub hash: Base64VecU8,
+    pub archived_at: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn set_max_history_length(&mut self, max_history_length: u64) {
+        self.assert_owner_action();
+        assert!(max_history_length > 0, "History length must be positive");
+        self.max_history_length = max_history_length;
+    }
+
+    /// Archives all but the newest `keep_last` live entries of the account's
+    /// history. Waits for the account's redemptions in flight to resolve.
+    pub fn archive_contributions(&mut self, account_id: AccountId, keep_last: u64) -> ArchiveCheckpoint {
+        self.assert_owner_action();
+        self.assert_no_redemptions_in_flight(&account_id);
+        let mut archived = self.stored_contributions(&account_id);
+        let count = archived.len().saturating_sub(keep_last as usize);
+        assert!(count > 0, "Nothing to archive");
+        let kept = archived.split_off(count);
+        let checkpoint = ArchiveCheckpoint {
+            from_index: self.archived_contribution_count(&account_id),
+            count: count as u64,
+            hash: Base64VecU8(env::sha256(&borsh::to_vec(&archived).unwrap())),
+            archived_at: env::block_timestamp(),
+        };
+        self.store_contributions(&account_id, &kept);
+        let mut checkpoints = self.contribution_archives.get(&account_id).unwrap_or_default();
+        checkpoints.push(checkpoint.clone());
+        self.contribution_archives.insert(&account_id, &checkpoints);
+        ContributionsArchived {
+            account_id: &account_id,
+            checkpoint: &checkpoint,
+        }
+        .emit();
+        checkpoint
+    }
+
+    // View functions
+    pub fn get_max_history_length(&self) -> u64 {
+        self.max_history_length
+    }
+
+    pub fn get_contribution_archives(&self, account_id: AccountId) -> Vec<ArchiveCheckpoint> {
+        self.contribution_archives.get(&account_id).unwrap_or_default()
+    }
+
+    /// The oldest `count` live entries of the account's history, encoded the
+    /// way `archive_contributions` hashes them. Keep a copy before
+    /// archiving them.
+    pub fn export_contributions(&self, account_id: AccountId, count: u64) -> Base64VecU8 {
+        let mut history = self.stored_contributions(&account_id);
+        history.truncate(count as usize);
+        Base64VecU8(borsh::to_vec(&history).unwrap())
+    }
+
+    /// Whether `entries` is exactly what the account's checkpoint at
+    /// `checkpoint_index` archived.
+    pub fn verify_archived_contributions(
+        &self,
+        account_id: AccountId,
+        checkpoint_index: u64,
+        entries: Base64VecU8,
+    ) -> bool {
+        let checkpoints = self.contribution_archives.get(&account_id).unwrap_or_default();
+        let checkpoint = checkpoints
+            .get(checkpoint_index as usize)
+            .expect("Archive checkpoint not found");
+        let decodes = Vec::<VersionedContribution>::try_from_slice(&entries.0)
+            .is_ok_and(|archived| archived.len() as u64 == checkpoint.count);
+        decodes && env::sha256(&entries.0) == checkpoint.hash.0
+    }
+}
+
+impl FoodBankToken {
+    /// Entries archived from the front of the account's history, which is
+    /// also the index of its first live entry.
+    pub(crate) fn archived_contribution_count(&self, account_id: &AccountId) -> u64 {
+        self.contribution_archives
+            .get(account_id)
+            .and_then(|checkpoints| checkpoints.last().map(|last| last.from_index + last.count))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn record(contract: &mut FoodBankToken, times: usize) {
+        for _ in 0..times {
+            contribute(contract, &food_bank(), 80);
+            advance_time(60 * 60 * 1_000_000_000);
+        }
+    }
+
+    #[test]
+    fn full_histories_roll_over_into_digests() {
+        let mut contract = setup_contract();
+        contract.set_max_history_length(2);
+        record(&mut contract, 3);
+
+        let history = contract.stored_contributions(&food_bank());
+        assert!(matches!(history[1], VersionedContribution::V3(_)));
+        let VersionedContribution::V5(digest) = &history[2] else {
+            panic!("expected a digest");
+        };
+        assert_eq!(digest.score, 80);
+        assert_eq!(digest.reward.0, 4);
+        // The digest still paid out and counts towards the aggregates.
+        assert_eq!(contract.get_balance(food_bank()).0, 12);
+        assert_eq!(contract.get_contributions(food_bank())[2].data_quality, 80);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 2).unwrap().reward.0, 4);
+    }
+
+    #[test]
+    fn archived_slices_verify_against_their_checkpoint() {
+        let mut contract = setup_contract();
+        record(&mut contract, 3);
+        let exported = contract.export_contributions(food_bank(), 2);
+
+        let checkpoint = contract.archive_contributions(food_bank(), 1);
+        assert_eq!(checkpoint.from_index, 0);
+        assert_eq!(checkpoint.count, 2);
+        assert_eq!(contract.get_contributions(food_bank()).len(), 1);
+        assert!(contract.verify_archived_contributions(food_bank(), 0, exported.clone()));
+
+        let mut tampered = exported.0;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(!contract.verify_archived_contributions(food_bank(), 0, Base64VecU8(tampered)));
+    }
+
+    #[test]
+    fn indices_continue_after_archiving() {
+        let mut contract = setup_contract();
+        contract.set_max_history_length(2);
+        record(&mut contract, 2);
+        contract.archive_contributions(food_bank(), 0);
+        record(&mut contract, 1);
+
+        // Back under the limit, so the new entry is stored in full.
+        let history = contract.stored_contributions(&food_bank());
+        assert_eq!(history.len(), 1);
+        assert!(matches!(history[0], VersionedContribution::V3(_)));
+        assert!(contract.get_reward_breakdown(food_bank(), 2).is_some());
+
+        contract.archive_contributions(food_bank(), 0);
+        let checkpoints = contract.get_contribution_archives(food_bank());
+        assert_eq!(checkpoints[1].from_index, 2);
+        assert_eq!(contract.archived_contribution_count(&food_bank()), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to archive")]
+    fn archiving_needs_entries_past_keep_last() {
+        let mut contract = setup_contract();
+        record(&mut contract, 1);
+        contract.archive_contributions(food_bank(), 1);
+    }
+}
@@ -0,0 +1,323 @@
+//! Contributions scored by the off-chain evaluation service and submitted
+//! by anyone. The service signs the Borsh-serialized `SignedContribution`
+//! with its ed25519 key; the contract checks the signature against the
+//! attestor key the owner set, accepts each nonce once per key, and records
+//! the contribution for the named account as if a recorder had. After a
+//! rotation the replaced key is still accepted for its grace period, so
+//! payloads signed just before the switch still land.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, near_bindgen, AccountId, CurveType, PublicKey};
+
+use crate::contributions::{ContributionMetricsV2, ContributionMetricsV3};
+use crate::events::{AttestorKeySet, ContributionAttested, Event};
+use crate::metric_rules::RawMetrics;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct SignedContribution {
+    /// The contract the attestation is for, so a payload signed for another
+    /// deployment sharing the attestor key is rejected here.
+    pub contract_id: AccountId,
+    pub account: AccountId,
+    pub metrics: ContributionMetricsV2,
+    /// Each nonce is accepted once per attestor key.
+    pub nonce: u64,
+    /// Accepted strictly before this timestamp.
+    pub expires_at: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Replaces the attestor key. The current key keeps verifying payloads
+    /// for `grace_period_ns`; 0 retires it right away.
+    pub fn set_attestor_key(&mut self, public_key: PublicKey, grace_period_ns: u64) {
+        self.assert_owner_action();
+        assert!(
+            public_key.curve_type() == CurveType::ED25519,
+            "Attestor key must be an ed25519 key"
+        );
+        let previous_valid_until = env::block_timestamp().saturating_add(grace_period_ns);
+        self.previous_attestor_key = self
+            .attestor_key
+            .take()
+            .filter(|_| grace_period_ns > 0)
+            .map(|key| (key, previous_valid_until));
+        AttestorKeySet {
+            public_key: &public_key,
+            previous_valid_until: self.previous_attestor_key.as_ref().map(|(_, until)| *until),
+        }
+        .emit();
+        self.attestor_key = Some(public_key);
+    }
+
+    /// Records the contribution in `payload`, a Borsh-serialized
+    /// `SignedContribution`, if `signature` is the attestor's signature over
+    /// it. The attached deposit pays for the storage, the used nonce
+    /// included, as with `record_contribution`.
+    #[payable]
+    pub fn record_signed_contribution(&mut self, payload: Base64VecU8, signature: Base64VecU8) {
+        let initial_storage = env::storage_usage();
+        let signature: [u8; 64] = signature.0.try_into().expect("Signature must be 64 bytes");
+        let attestor = self.attestor_for(&payload.0, &signature);
+        let contribution =
+            SignedContribution::try_from_slice(&payload.0).expect("Invalid attestation payload");
+        assert_eq!(
+            contribution.contract_id,
+            env::current_account_id(),
+            "Attestation is for another contract"
+        );
+        assert!(env::block_timestamp() < contribution.expires_at, "Attestation has expired");
+        assert!(
+            self.attestation_nonces.insert(&(attestor.clone(), contribution.nonce)),
+            "Attestation nonce {} already used",
+            contribution.nonce
+        );
+        ContributionAttested {
+            account_id: &contribution.account,
+            attestor: &attestor,
+            nonce: contribution.nonce,
+            submitter: &env::predecessor_account_id(),
+        }
+        .emit();
+        let raw = RawMetrics::from(&contribution.metrics);
+        let metrics = self.apply_metric_rules(&contribution.metrics);
+        let badge_deposit = self.internal_record_contribution(
+            contribution.account,
+            ContributionMetricsV3 { metrics, raw }.into(),
+            None,
+            None,
+            None,
+            None,
+        );
+        self.charge_storage(initial_storage, badge_deposit);
+    }
+
+    // View functions
+    pub fn get_attestor_key(&self) -> Option<PublicKey> {
+        self.attestor_key.clone()
+    }
+
+    /// The replaced key and the timestamp it is accepted until.
+    pub fn get_previous_attestor_key(&self) -> Option<(PublicKey, u64)> {
+        self.previous_attestor_key
+            .clone()
+            .filter(|(_, valid_until)| env::block_timestamp() < *valid_until)
+    }
+
+    pub fn is_attestation_nonce_used(&self, public_key: PublicKey, nonce: u64) -> bool {
+        self.attestation_nonces.contains(&(public_key, nonce))
+    }
+}
+
+impl FoodBankToken {
+    /// The accepted attestor key `signature` verifies against.
+    fn attestor_for(&self, payload: &[u8], signature: &[u8; 64]) -> PublicKey {
+        let current = self.attestor_key.clone().expect("No attestor key set");
+        std::iter::once(current)
+            .chain(self.get_previous_attestor_key().map(|(key, _)| key))
+            .find(|key| verify_signature(signature, payload, key))
+            .expect("Invalid attestation signature")
+    }
+}
+
+pub(crate) fn verify_signature(signature: &[u8; 64], message: &[u8], public_key: &PublicKey) -> bool {
+    // The first byte is the curve type.
+    let Ok(public_key) = <[u8; 32]>::try_from(&public_key.as_bytes()[1..]) else {
+        return false;
+    };
+    ed25519_verify(signature, message, &public_key)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn ed25519_verify(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+    // SAFETY: each pointer and length pair describes one borrowed buffer.
+    unsafe {
+        near_sdk::sys::ed25519_verify(
+            signature.len() as u64,
+            signature.as_ptr() as u64,
+            message.len() as u64,
+            message.as_ptr() as u64,
+            public_key.len() as u64,
+            public_key.as_ptr() as u64,
+        ) == 1
+    }
+}
+
+/// The mocked runtime has no ed25519 host function, so native builds check
+/// signatures themselves.
+#[cfg(not(target_arch = "wasm32"))]
+fn ed25519_verify(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+    use ed25519_dalek::Verifier;
+    let (Ok(public_key), Ok(signature)) = (
+        ed25519_dalek::PublicKey::from_bytes(public_key),
+        ed25519_dalek::Signature::try_from(&signature[..]),
+    ) else {
+        return false;
+    };
+    public_key.verify(message, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::storage_credit::storage_cost;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::get_created_receipts;
+
+    const HOUR: u64 = 60 * 60 * 1_000_000_000;
+
+    fn attestor(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    fn public_key(keypair: &Keypair) -> PublicKey {
+        let mut bytes = vec![CurveType::ED25519 as u8];
+        bytes.extend_from_slice(keypair.public.as_bytes());
+        PublicKey::try_from(bytes).unwrap()
+    }
+
+    fn payload(nonce: u64) -> Vec<u8> {
+        borsh::to_vec(&SignedContribution {
+            contract_id: env::current_account_id(),
+            account: food_bank(),
+            metrics: metrics(100, 100, 100),
+            nonce,
+            expires_at: env::block_timestamp() + HOUR,
+        })
+        .unwrap()
+    }
+
+    fn submit(contract: &mut FoodBankToken, keypair: &Keypair, payload: Vec<u8>) {
+        let signature = keypair.sign(&payload).to_bytes().to_vec();
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.record_signed_contribution(Base64VecU8(payload), Base64VecU8(signature));
+    }
+
+    fn with_attestor(keypair: &Keypair) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_attestor_key(public_key(keypair), 0);
+        contract
+    }
+
+    #[test]
+    fn anyone_can_submit_a_signed_contribution() {
+        let keypair = attestor(1);
+        let mut contract = with_attestor(&keypair);
+        submit(&mut contract, &keypair, payload(0));
+        assert_eq!(contract.get_contributions(food_bank()).len(), 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+        assert!(contract.is_attestation_nonce_used(public_key(&keypair), 0));
+        assert_eq!(contract.get_balance(other_food_bank()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid attestation signature")]
+    fn tampered_payloads_are_rejected() {
+        let keypair = attestor(1);
+        let mut contract = with_attestor(&keypair);
+        let payload = payload(0);
+        let signature = keypair.sign(&payload).to_bytes().to_vec();
+        let mut tampered = payload;
+        *tampered.last_mut().unwrap() ^= 1;
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.record_signed_contribution(Base64VecU8(tampered), Base64VecU8(signature));
+    }
+
+    #[test]
+    fn the_nonce_is_charged_with_the_contribution() {
+        let keypair = attestor(1);
+        let mut contract = with_attestor(&keypair);
+        let payload = payload(0);
+        let signature = keypair.sign(&payload).to_bytes().to_vec();
+        set_caller(other_food_bank(), ONE_NEAR);
+        let initial_storage = env::storage_usage();
+        contract.record_signed_contribution(Base64VecU8(payload), Base64VecU8(signature));
+        let refund = get_created_receipts().pop().unwrap();
+        assert_eq!(refund.receiver_id, other_food_bank());
+        assert_eq!(
+            refund.actions,
+            vec![VmAction::Transfer { deposit: ONE_NEAR - storage_cost(initial_storage) }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Attestation is for another contract")]
+    fn payloads_for_other_contracts_are_rejected() {
+        let keypair = attestor(1);
+        let mut contract = with_attestor(&keypair);
+        let payload = borsh::to_vec(&SignedContribution {
+            contract_id: "other-program.near".parse().unwrap(),
+            account: food_bank(),
+            metrics: metrics(100, 100, 100),
+            nonce: 0,
+            expires_at: env::block_timestamp() + HOUR,
+        })
+        .unwrap();
+        submit(&mut contract, &keypair, payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid attestation signature")]
+    fn other_keys_are_rejected() {
+        let mut contract = with_attestor(&attestor(1));
+        submit(&mut contract, &attestor(2), payload(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Attestation nonce 7 already used")]
+    fn nonces_cannot_be_replayed() {
+        let keypair = attestor(1);
+        let mut contract = with_attestor(&keypair);
+        submit(&mut contract, &keypair, payload(7));
+        advance_time(HOUR / 2);
+        submit(&mut contract, &keypair, payload(7));
+    }
+
+    #[test]
+    #[should_panic(expected = "Attestation has expired")]
+    fn expired_payloads_are_rejected() {
+        let keypair = attestor(1);
+        let mut contract = with_attestor(&keypair);
+        let payload = payload(0);
+        advance_time(HOUR);
+        submit(&mut contract, &keypair, payload);
+    }
+
+    #[test]
+    fn the_replaced_key_works_until_its_grace_period_ends() {
+        let old = attestor(1);
+        let new = attestor(2);
+        let mut contract = with_attestor(&old);
+        contract.set_attestor_key(public_key(&new), HOUR);
+        submit(&mut contract, &old, payload(0));
+        advance_time(HOUR / 2);
+        // Nonces are tracked per key.
+        submit(&mut contract, &new, payload(0));
+        assert_eq!(contract.get_contributions(food_bank()).len(), 2);
+        assert_eq!(contract.get_previous_attestor_key().unwrap().1, env::block_timestamp() + HOUR / 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid attestation signature")]
+    fn the_replaced_key_is_rejected_after_its_grace_period() {
+        let old = attestor(1);
+        let mut contract = with_attestor(&old);
+        contract.set_attestor_key(public_key(&attestor(2)), HOUR);
+        advance_time(HOUR);
+        assert!(contract.get_previous_attestor_key().is_none());
+        submit(&mut contract, &old, payload(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "No attestor key set")]
+    fn nothing_is_accepted_without_a_key() {
+        let mut contract = setup_contract();
+        submit(&mut contract, &attestor(1), payload(0));
+    }
+}
@@ -0,0 +1,260 @@
+//! Commemorative NFT badges minted on an external NEP-171 contract when an
+//! account reaches a contribution-count milestone.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::json;
+use near_sdk::{env, is_promise_success, near_bindgen, AccountId, Balance, Gas, Promise};
+
+use crate::events::{BadgeMintFailed, BadgeMinted, Event};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const GAS_FOR_NFT_MINT: Gas = Gas(20_000_000_000_000);
+const GAS_FOR_RESOLVE_BADGE: Gas = Gas(5_000_000_000_000);
+/// Left for the rest of `record_contribution` after the badge promise is
+/// scheduled.
+const GAS_RESERVED_FOR_CONTRIBUTION: Gas = Gas(15_000_000_000_000);
+/// Storage deposit forwarded to `nft_mint`, paid by whoever triggers it.
+pub const BADGE_MINT_DEPOSIT: Balance = 10_000_000_000_000_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BadgeStatus {
+    InFlight,
+    Minted,
+    /// The mint failed or was skipped for lack of gas; the owner can retry.
+    PendingRetry,
+}
+
+pub(crate) fn default_badge_milestones() -> Vec<u64> {
+    vec![10, 50, 100]
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// `None` turns badges off. Milestones are contribution counts.
+    pub fn set_badge_config(&mut self, badge_contract: Option<AccountId>, milestones: Vec<u64>) {
+        self.assert_owner_action();
+        for window in milestones.windows(2) {
+            assert!(
+                window[0] < window[1],
+                "Milestones must be sorted without duplicates"
+            );
+        }
+        self.badge_contract = badge_contract;
+        self.badge_milestones = milestones;
+    }
+
+    /// Re-attempts a badge whose mint failed. The attached deposit must
+    /// cover the mint's storage deposit; from a council action the
+    /// proposer's storage credit covers it instead.
+    #[payable]
+    pub fn retry_badge_mint(&mut self, account_id: AccountId, milestone: u64) -> Promise {
+        self.assert_owner_action();
+        if self.executing_council_action.is_some() {
+            self.charge_storage(env::storage_usage(), BADGE_MINT_DEPOSIT);
+        } else {
+            assert_eq!(
+                env::attached_deposit(),
+                BADGE_MINT_DEPOSIT,
+                "Attach exactly {} yoctoNEAR for the badge mint",
+                BADGE_MINT_DEPOSIT
+            );
+        }
+        assert_eq!(
+            self.badges.get(&(account_id.clone(), milestone)),
+            Some(BadgeStatus::PendingRetry),
+            "Badge is not pending a retry"
+        );
+        let badge_contract = self.badge_contract.clone().expect("No badge contract is set");
+        self.internal_mint_badge(badge_contract, account_id, milestone)
+    }
+
+    #[private]
+    pub fn on_badge_minted(&mut self, account_id: AccountId, milestone: u64) -> bool {
+        let minted = is_promise_success();
+        let status = if minted {
+            BadgeMinted {
+                account_id: &account_id,
+                milestone,
+            }
+            .emit();
+            BadgeStatus::Minted
+        } else {
+            BadgeMintFailed {
+                account_id: &account_id,
+                milestone,
+            }
+            .emit();
+            BadgeStatus::PendingRetry
+        };
+        self.badges.insert(&(account_id, milestone), &status);
+        minted
+    }
+
+    // View functions
+    pub fn get_badge_status(&self, account_id: AccountId, milestone: u64) -> Option<BadgeStatus> {
+        self.badges.get(&(account_id, milestone))
+    }
+
+    pub fn get_badge_config(&self) -> (Option<AccountId>, Vec<u64>) {
+        (self.badge_contract.clone(), self.badge_milestones.clone())
+    }
+}
+
+impl FoodBankToken {
+    /// Schedules the badge for `account_id` if its contribution count just
+    /// reached a milestone, returning the NEAR attached to the mint. Never
+    /// panics: without enough gas the badge is left for a retry.
+    pub(crate) fn maybe_mint_badge(&mut self, account_id: &AccountId, count: u64) -> Balance {
+        let Some(badge_contract) = self.badge_contract.clone() else {
+            return 0;
+        };
+        if !self.badge_milestones.contains(&count) {
+            return 0;
+        }
+        let remaining_gas = env::prepaid_gas() - env::used_gas();
+        let needed = GAS_FOR_NFT_MINT + GAS_FOR_RESOLVE_BADGE + GAS_RESERVED_FOR_CONTRIBUTION;
+        if remaining_gas < needed || env::attached_deposit() < BADGE_MINT_DEPOSIT {
+            self.badges.insert(&(account_id.clone(), count), &BadgeStatus::PendingRetry);
+            BadgeMintFailed {
+                account_id,
+                milestone: count,
+            }
+            .emit();
+            return 0;
+        }
+        self.internal_mint_badge(badge_contract, account_id.clone(), count);
+        BADGE_MINT_DEPOSIT
+    }
+
+    fn internal_mint_badge(
+        &mut self,
+        badge_contract: AccountId,
+        account_id: AccountId,
+        milestone: u64,
+    ) -> Promise {
+        self.badges.insert(&(account_id.clone(), milestone), &BadgeStatus::InFlight);
+        let args = json!({
+            "token_id": format!("{}-{}", account_id, milestone),
+            "receiver_id": account_id,
+            "token_metadata": {
+                "title": format!("{} contributions", milestone),
+                "description": format!(
+                    "Awarded to {} for recording {} contributions",
+                    account_id, milestone
+                ),
+            },
+        });
+        Promise::new(badge_contract)
+            .function_call(
+                "nft_mint".to_string(),
+                args.to_string().into_bytes(),
+                BADGE_MINT_DEPOSIT,
+                GAS_FOR_NFT_MINT,
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_BADGE)
+                    .on_badge_minted(account_id, milestone),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+    use near_sdk::{testing_env, PromiseResult};
+
+    fn badge_contract() -> AccountId {
+        accounts(4)
+    }
+
+    fn setup_badges() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_badge_config(Some(badge_contract()), vec![2, 3]);
+        contract
+    }
+
+    fn nft_mints() -> Vec<String> {
+        get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == badge_contract())
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                VmAction::FunctionCall { function_name, args, deposit, .. } => {
+                    assert_eq!(function_name, "nft_mint");
+                    assert_eq!(deposit, BADGE_MINT_DEPOSIT);
+                    Some(String::from_utf8(args).unwrap())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn milestone_triggers_an_nft_mint() {
+        let mut contract = setup_badges();
+        contribute(&mut contract, &food_bank(), 100);
+        assert!(nft_mints().is_empty());
+
+        contribute(&mut contract, &food_bank(), 100);
+        let mints = nft_mints();
+        assert_eq!(mints.len(), 1);
+        assert!(mints[0].contains("\"token_id\":\"bob-2\""));
+        assert!(mints[0].contains("\"receiver_id\":\"bob\""));
+        assert_eq!(contract.get_badge_status(food_bank(), 2), Some(BadgeStatus::InFlight));
+    }
+
+    #[test]
+    fn callback_records_the_outcome() {
+        let mut contract = setup_badges();
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 100);
+
+        resolve_callback(PromiseResult::Successful(vec![]));
+        assert!(contract.on_badge_minted(food_bank(), 2));
+        assert_eq!(contract.get_badge_status(food_bank(), 2), Some(BadgeStatus::Minted));
+
+        resolve_callback(PromiseResult::Failed);
+        assert!(!contract.on_badge_minted(food_bank(), 3));
+        assert_eq!(contract.get_badge_status(food_bank(), 3), Some(BadgeStatus::PendingRetry));
+    }
+
+    #[test]
+    fn low_gas_leaves_the_badge_for_a_retry() {
+        let mut contract = setup_badges();
+        contribute(&mut contract, &food_bank(), 100);
+        testing_env!(context(owner(), ONE_NEAR)
+            .prepaid_gas(Gas(30_000_000_000_000))
+            .build());
+        contract.record_contribution(food_bank(), metrics(100, 100, 100), None, None, None, None);
+
+        assert_eq!(contract.get_contributions(food_bank()).len(), 2);
+        assert!(nft_mints().is_empty());
+        assert_eq!(contract.get_badge_status(food_bank(), 2), Some(BadgeStatus::PendingRetry));
+
+        set_caller(owner(), BADGE_MINT_DEPOSIT);
+        contract.retry_badge_mint(food_bank(), 2);
+        assert_eq!(nft_mints().len(), 1);
+        assert_eq!(contract.get_badge_status(food_bank(), 2), Some(BadgeStatus::InFlight));
+    }
+
+    #[test]
+    #[should_panic(expected = "Badge is not pending a retry")]
+    fn retry_requires_a_failed_badge() {
+        let mut contract = setup_badges();
+        set_caller(owner(), BADGE_MINT_DEPOSIT);
+        contract.retry_badge_mint(food_bank(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Milestones must be sorted without duplicates")]
+    fn unsorted_milestones_are_rejected() {
+        let mut contract = setup_contract();
+        contract.set_badge_config(Some(badge_contract()), vec![50, 10]);
+    }
+}
@@ -0,0 +1,174 @@
+//! Minting starter balances to a whole cohort in one call. Each call is
+//! capped in entries and total amount so a single compromised key can only
+//! mint so much at once.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, TokensMintedBatch};
+use crate::roles::Role;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_MEMO_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintBatchCap {
+    pub max_entries: u32,
+    pub max_total: U128,
+}
+
+impl Default for MintBatchCap {
+    fn default() -> Self {
+        Self {
+            max_entries: 50,
+            max_total: U128(100_000),
+        }
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Mints every entry or none. A recipient listed more than once gets the
+    /// sum of its amounts. The attached deposit pays for new balances.
+    #[payable]
+    pub fn mint_batch(&mut self, recipients: Vec<(AccountId, U128)>, memo: String) -> U128 {
+        let initial_storage = env::storage_usage();
+        self.assert_not_paused();
+        self.assert_role(Role::Treasurer);
+        assert!(!recipients.is_empty(), "At least one recipient is required");
+        assert!(
+            memo.len() <= MAX_MEMO_LENGTH,
+            "Memo exceeds {} bytes",
+            MAX_MEMO_LENGTH
+        );
+        let cap = self.mint_batch_cap;
+        assert!(
+            recipients.len() <= cap.max_entries as usize,
+            "At most {} entries per batch",
+            cap.max_entries
+        );
+
+        let mut mints: Vec<(AccountId, U128)> = Vec::new();
+        let mut total: Balance = 0;
+        for (account_id, amount) in recipients {
+            total = total.checked_add(amount.0).expect("Batch total overflows");
+            match mints.iter_mut().find(|(existing, _)| *existing == account_id) {
+                Some((_, sum)) => sum.0 += amount.0,
+                None => mints.push((account_id, amount)),
+            }
+        }
+        assert!(
+            total <= cap.max_total.0,
+            "Batch total {} exceeds the cap of {}",
+            total,
+            cap.max_total.0
+        );
+
+        for (account_id, amount) in &mints {
+            let balance = self.balances.get(account_id).unwrap_or(0);
+            self.internal_set_balance(account_id, balance + amount.0);
+        }
+        self.total_supply += total;
+        TokensMintedBatch {
+            actor: &env::predecessor_account_id(),
+            memo: &memo,
+            mints: &mints,
+            total: U128(total),
+        }
+        .record(&mut self.event_buffer);
+
+        self.charge_storage(initial_storage, 0);
+        U128(total)
+    }
+
+    pub fn set_mint_batch_cap(&mut self, cap: MintBatchCap) {
+        self.assert_owner_action();
+        assert!(cap.max_entries > 0, "Batches need at least one entry");
+        self.mint_batch_cap = cap;
+    }
+
+    // View functions
+    pub fn get_mint_batch_cap(&self) -> MintBatchCap {
+        self.mint_batch_cap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn batch(contract: &mut FoodBankToken, recipients: &[(AccountId, u128)]) -> U128 {
+        set_caller(owner(), ONE_NEAR);
+        contract.mint_batch(
+            recipients.iter().map(|(account, amount)| (account.clone(), U128(*amount))).collect(),
+            "Spring cohort".to_string(),
+        )
+    }
+
+    #[test]
+    fn batch_mints_every_recipient() {
+        let mut contract = setup_contract();
+        let supply = contract.get_total_supply().0;
+        assert_eq!(batch(&mut contract, &[(food_bank(), 300), (other_food_bank(), 200)]).0, 500);
+        assert_eq!(contract.get_balance(food_bank()).0, 300);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 200);
+        assert_eq!(contract.get_total_supply().0, supply + 500);
+    }
+
+    #[test]
+    fn duplicate_recipients_get_the_sum() {
+        let mut contract = setup_contract();
+        batch(&mut contract, &[(food_bank(), 300), (other_food_bank(), 50), (food_bank(), 200)]);
+        assert_eq!(contract.get_balance(food_bank()).0, 500);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 50);
+    }
+
+    #[test]
+    fn one_event_covers_the_batch() {
+        let mut contract = setup_contract();
+        batch(&mut contract, &[(food_bank(), 3), (food_bank(), 2)]);
+        let logs = near_sdk::test_utils::get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains(r#""event":"tokens_minted_batch""#));
+        assert!(logs[0].contains(r#""mints":[["bob","5"]]"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch total 100001 exceeds the cap of 100000")]
+    fn total_above_the_cap_is_rejected() {
+        let mut contract = setup_contract();
+        batch(&mut contract, &[(food_bank(), 60_000), (food_bank(), 40_001)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "At most 2 entries per batch")]
+    fn entry_count_above_the_cap_is_rejected() {
+        let mut contract = setup_contract();
+        contract.set_mint_batch_cap(MintBatchCap {
+            max_entries: 2,
+            max_total: U128(1_000),
+        });
+        batch(&mut contract, &[(food_bank(), 1), (food_bank(), 1), (other_food_bank(), 1)]);
+    }
+
+    #[test]
+    fn treasurers_can_mint_batches() {
+        let mut contract = setup_contract();
+        contract.grant_role(other_food_bank(), Role::Treasurer);
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.mint_batch(vec![(food_bank(), U128(10))], String::new());
+        assert_eq!(contract.get_balance(food_bank()).0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Treasurer role")]
+    fn other_callers_cannot_mint_batches() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), ONE_NEAR);
+        contract.mint_batch(vec![(food_bank(), U128(10))], String::new());
+    }
+}
@@ -0,0 +1,323 @@
+//! Versioned storage for reward breakdowns. Each stored breakdown carries
+//! its schema version, so new fields only need a new variant.
+//!
+//! Breakdowns written before versioning live under the original `w` prefix
+//! in the layout of whichever release recorded them. Every release only
+//! appended fields, so exactly one of those layouts decodes an entry to its
+//! last byte, and `LegacyRewardBreakdown` tries them newest first. Legacy
+//! breakdowns are read through the accessors below and move to the
+//! versioned map whenever they are written.
+
+use std::io;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+
+use crate::rate_overrides::RateSource;
+use crate::reward_curve::RewardCurve;
+use crate::{FoodBankToken, RewardBreakdown};
+
+/// The layout written before reward curves.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RewardBreakdownV1 {
+    pub(crate) score: u8,
+    pub(crate) rate_bps: u16,
+    pub(crate) stake_bonus_bps: u16,
+    pub(crate) tier: String,
+    pub(crate) tier_multiplier_bps: u16,
+    pub(crate) campaign_id: Option<u64>,
+    pub(crate) epoch_id: Option<u64>,
+    pub(crate) campaign_bonus: U128,
+    pub(crate) matched: U128,
+    pub(crate) reward: U128,
+}
+
+/// The layout written between reward curves and the per-contribution cap.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RewardBreakdownV2 {
+    pub(crate) score: u8,
+    pub(crate) rate_bps: u16,
+    pub(crate) stake_bonus_bps: u16,
+    pub(crate) tier: String,
+    pub(crate) tier_multiplier_bps: u16,
+    pub(crate) campaign_id: Option<u64>,
+    pub(crate) epoch_id: Option<u64>,
+    pub(crate) campaign_bonus: U128,
+    pub(crate) matched: U128,
+    pub(crate) reward: U128,
+    pub(crate) curve: RewardCurve,
+}
+
+/// The layout written between the per-contribution cap and rate overrides.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RewardBreakdownV3 {
+    pub(crate) score: u8,
+    pub(crate) rate_bps: u16,
+    pub(crate) stake_bonus_bps: u16,
+    pub(crate) tier: String,
+    pub(crate) tier_multiplier_bps: u16,
+    pub(crate) campaign_id: Option<u64>,
+    pub(crate) epoch_id: Option<u64>,
+    pub(crate) campaign_bonus: U128,
+    pub(crate) matched: U128,
+    pub(crate) reward: U128,
+    pub(crate) curve: RewardCurve,
+    pub(crate) uncapped_reward: U128,
+}
+
+/// The layout written between rate overrides and per-account thresholds.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RewardBreakdownV4 {
+    pub(crate) score: u8,
+    pub(crate) rate_bps: u16,
+    pub(crate) stake_bonus_bps: u16,
+    pub(crate) tier: String,
+    pub(crate) tier_multiplier_bps: u16,
+    pub(crate) campaign_id: Option<u64>,
+    pub(crate) epoch_id: Option<u64>,
+    pub(crate) campaign_bonus: U128,
+    pub(crate) matched: U128,
+    pub(crate) reward: U128,
+    pub(crate) curve: RewardCurve,
+    pub(crate) uncapped_reward: U128,
+    pub(crate) rate_source: RateSource,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedRewardBreakdown {
+    V1(RewardBreakdownV1),
+    V2(RewardBreakdownV2),
+    V3(RewardBreakdownV3),
+    V4(RewardBreakdownV4),
+    V5(RewardBreakdown),
+}
+
+impl From<RewardBreakdownV1> for RewardBreakdownV2 {
+    /// Rewards were linear in the score before curves.
+    fn from(breakdown: RewardBreakdownV1) -> Self {
+        Self {
+            score: breakdown.score,
+            rate_bps: breakdown.rate_bps,
+            stake_bonus_bps: breakdown.stake_bonus_bps,
+            tier: breakdown.tier,
+            tier_multiplier_bps: breakdown.tier_multiplier_bps,
+            campaign_id: breakdown.campaign_id,
+            epoch_id: breakdown.epoch_id,
+            campaign_bonus: breakdown.campaign_bonus,
+            matched: breakdown.matched,
+            reward: breakdown.reward,
+            curve: RewardCurve::Linear,
+        }
+    }
+}
+
+impl From<RewardBreakdownV2> for RewardBreakdownV3 {
+    /// Nothing capped the reward yet.
+    fn from(breakdown: RewardBreakdownV2) -> Self {
+        Self {
+            score: breakdown.score,
+            rate_bps: breakdown.rate_bps,
+            stake_bonus_bps: breakdown.stake_bonus_bps,
+            tier: breakdown.tier,
+            tier_multiplier_bps: breakdown.tier_multiplier_bps,
+            campaign_id: breakdown.campaign_id,
+            epoch_id: breakdown.epoch_id,
+            campaign_bonus: breakdown.campaign_bonus,
+            matched: breakdown.matched,
+            uncapped_reward: breakdown.reward,
+            reward: breakdown.reward,
+            curve: breakdown.curve,
+        }
+    }
+}
+
+impl From<RewardBreakdownV3> for RewardBreakdownV4 {
+    /// Which of the static rate and the emission schedule applied wasn't
+    /// recorded, so these read as the static rate.
+    fn from(breakdown: RewardBreakdownV3) -> Self {
+        Self {
+            score: breakdown.score,
+            rate_bps: breakdown.rate_bps,
+            stake_bonus_bps: breakdown.stake_bonus_bps,
+            tier: breakdown.tier,
+            tier_multiplier_bps: breakdown.tier_multiplier_bps,
+            campaign_id: breakdown.campaign_id,
+            epoch_id: breakdown.epoch_id,
+            campaign_bonus: breakdown.campaign_bonus,
+            matched: breakdown.matched,
+            reward: breakdown.reward,
+            curve: breakdown.curve,
+            uncapped_reward: breakdown.uncapped_reward,
+            rate_source: RateSource::Static,
+        }
+    }
+}
+
+impl From<RewardBreakdownV4> for RewardBreakdown {
+    /// The global threshold these were held to wasn't recorded, so they
+    /// read as held to none.
+    fn from(breakdown: RewardBreakdownV4) -> Self {
+        Self {
+            score: breakdown.score,
+            rate_bps: breakdown.rate_bps,
+            stake_bonus_bps: breakdown.stake_bonus_bps,
+            tier: breakdown.tier,
+            tier_multiplier_bps: breakdown.tier_multiplier_bps,
+            campaign_id: breakdown.campaign_id,
+            epoch_id: breakdown.epoch_id,
+            campaign_bonus: breakdown.campaign_bonus,
+            matched: breakdown.matched,
+            reward: breakdown.reward,
+            curve: breakdown.curve,
+            uncapped_reward: breakdown.uncapped_reward,
+            rate_source: breakdown.rate_source,
+            score_threshold: 0,
+        }
+    }
+}
+
+impl VersionedRewardBreakdown {
+    pub fn into_latest(self) -> RewardBreakdown {
+        match self {
+            Self::V1(breakdown) => Self::V2(breakdown.into()).into_latest(),
+            Self::V2(breakdown) => Self::V3(breakdown.into()).into_latest(),
+            Self::V3(breakdown) => Self::V4(breakdown.into()).into_latest(),
+            Self::V4(breakdown) => breakdown.into(),
+            Self::V5(breakdown) => breakdown,
+        }
+    }
+}
+
+/// A breakdown stored under the `w` prefix, in whichever layout decodes all
+/// of its bytes. Written back in the same layout it was read in.
+pub struct LegacyRewardBreakdown(VersionedRewardBreakdown);
+
+impl BorshDeserialize for LegacyRewardBreakdown {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let bytes = std::mem::take(buf);
+        RewardBreakdown::try_from_slice(bytes)
+            .map(VersionedRewardBreakdown::V5)
+            .or_else(|_| RewardBreakdownV4::try_from_slice(bytes).map(VersionedRewardBreakdown::V4))
+            .or_else(|_| RewardBreakdownV3::try_from_slice(bytes).map(VersionedRewardBreakdown::V3))
+            .or_else(|_| RewardBreakdownV2::try_from_slice(bytes).map(VersionedRewardBreakdown::V2))
+            .or_else(|_| RewardBreakdownV1::try_from_slice(bytes).map(VersionedRewardBreakdown::V1))
+            .map(Self)
+    }
+}
+
+impl BorshSerialize for LegacyRewardBreakdown {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match &self.0 {
+            VersionedRewardBreakdown::V1(breakdown) => breakdown.serialize(writer),
+            VersionedRewardBreakdown::V2(breakdown) => breakdown.serialize(writer),
+            VersionedRewardBreakdown::V3(breakdown) => breakdown.serialize(writer),
+            VersionedRewardBreakdown::V4(breakdown) => breakdown.serialize(writer),
+            VersionedRewardBreakdown::V5(breakdown) => breakdown.serialize(writer),
+        }
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn reward_breakdown(&self, key: &(AccountId, u64)) -> Option<RewardBreakdown> {
+        match self.reward_breakdowns.get(key) {
+            Some(breakdown) => Some(breakdown.into_latest()),
+            None => self
+                .legacy_reward_breakdowns
+                .get(key)
+                .map(|breakdown| breakdown.0.into_latest()),
+        }
+    }
+
+    pub(crate) fn has_reward_breakdown(&self, key: &(AccountId, u64)) -> bool {
+        self.reward_breakdowns.contains_key(key) || self.legacy_reward_breakdowns.contains_key(key)
+    }
+
+    /// Writes `breakdown` in its latest layout, dropping any legacy copy.
+    pub(crate) fn store_reward_breakdown(&mut self, key: &(AccountId, u64), breakdown: &RewardBreakdown) {
+        self.reward_breakdowns
+            .insert(key, &VersionedRewardBreakdown::V5(breakdown.clone()));
+        self.legacy_reward_breakdowns.remove_raw(&key.try_to_vec().unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn breakdown_v1(reward: u128) -> RewardBreakdownV1 {
+        RewardBreakdownV1 {
+            score: 80,
+            rate_bps: 500,
+            stake_bonus_bps: 0,
+            tier: "Bronze".to_string(),
+            tier_multiplier_bps: 10_000,
+            campaign_id: None,
+            epoch_id: None,
+            campaign_bonus: U128(0),
+            matched: U128(0),
+            reward: U128(reward),
+        }
+    }
+
+    fn store_legacy(contract: &FoodBankToken, key: &(AccountId, u64), breakdown: &impl BorshSerialize) {
+        let storage_key = [b"w".as_slice(), &key.try_to_vec().unwrap()].concat();
+        near_sdk::env::storage_write(&storage_key, &breakdown.try_to_vec().unwrap());
+        assert!(contract.legacy_reward_breakdowns.contains_key(key));
+    }
+
+    #[test]
+    fn legacy_breakdowns_of_every_layout_are_read() {
+        let contract = setup_contract();
+        let v1 = breakdown_v1(40);
+        let v3 = RewardBreakdownV3::from(RewardBreakdownV2::from(breakdown_v1(50)));
+        store_legacy(&contract, &(food_bank(), 0), &v1);
+        store_legacy(&contract, &(food_bank(), 1), &v3);
+
+        let first = contract.reward_breakdown(&(food_bank(), 0)).unwrap();
+        assert_eq!(first.reward, U128(40));
+        assert_eq!(first.uncapped_reward, U128(40));
+        assert_eq!(first.curve, RewardCurve::Linear);
+        assert_eq!(first.rate_source, RateSource::Static);
+        assert_eq!(first.score_threshold, 0);
+        let second = contract.get_reward_breakdown(food_bank(), 1).unwrap();
+        assert_eq!(second.reward, U128(50));
+        assert_eq!(second.tier, "Bronze");
+    }
+
+    #[test]
+    fn writing_a_legacy_breakdown_moves_it() {
+        let mut contract = setup_contract();
+        let key = (food_bank(), 0);
+        store_legacy(&contract, &key, &breakdown_v1(40));
+
+        let mut breakdown = contract.reward_breakdown(&key).unwrap();
+        breakdown.reward = U128(60);
+        contract.store_reward_breakdown(&key, &breakdown);
+        assert!(!contract.legacy_reward_breakdowns.contains_key(&key));
+        assert!(matches!(
+            contract.reward_breakdowns.get(&key),
+            Some(VersionedRewardBreakdown::V5(_))
+        ));
+        assert_eq!(contract.reward_breakdown(&key).unwrap().reward, U128(60));
+    }
+
+    #[test]
+    fn a_legacy_breakdown_can_be_voided() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 40);
+        let key = (food_bank(), 0);
+        contract.reward_breakdowns.remove(&key);
+        let legacy = RewardBreakdownV1 {
+            score: 40,
+            ..breakdown_v1(40)
+        };
+        store_legacy(&contract, &key, &legacy);
+
+        set_caller(owner(), 0);
+        contract.void_contribution(food_bank(), 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+}
@@ -0,0 +1,230 @@
+//! A price floor for the token: food banks sell tokens back for NEAR held
+//! in a reserve the owner or Treasurer funds. The buyback price never tops
+//! the sale price, so buying from `buy_tokens` and selling straight back
+//! can't drain the reserve.
+
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, is_promise_success, near_bindgen, AccountId, Balance, Gas, Promise,
+};
+
+use crate::events::{BuybackRefunded, BuybackReserveFunded, Event, TokensSold};
+use crate::roles::Role;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const GAS_FOR_RESOLVE_SALE: Gas = Gas(5_000_000_000_000);
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// 0 closes the buyback.
+    pub fn set_buyback_price(&mut self, buyback_price_yocto: U128) {
+        self.assert_owner_action();
+        self.assert_buyback_below_sale(buyback_price_yocto.0, self.token_price_yocto);
+        self.buyback_price_yocto = buyback_price_yocto.0;
+    }
+
+    #[payable]
+    pub fn fund_buyback_reserve(&mut self) -> U128 {
+        self.assert_role(Role::Treasurer);
+        let deposit = env::attached_deposit();
+        assert!(deposit > 0, "Attach NEAR to fund the reserve");
+        self.buyback_reserve += deposit;
+        BuybackReserveFunded {
+            funder: &env::predecessor_account_id(),
+            amount: U128(deposit),
+            reserve: U128(self.buyback_reserve),
+        }
+        .emit();
+        U128(self.buyback_reserve)
+    }
+
+    /// Burns `amount` of the caller's unlocked tokens and sends their value
+    /// at the buyback price from the reserve. The tokens come back if the
+    /// transfer fails.
+    #[payable]
+    pub fn sell_tokens(&mut self, amount: U128) -> Promise {
+        assert_one_yocto();
+        self.assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        self.assert_not_frozen(&account_id);
+        self.assert_not_treasury(&account_id);
+        assert!(self.buyback_price_yocto > 0, "Buyback is closed");
+        assert!(amount.0 > 0, "Amount must be positive");
+        self.assert_registered(&account_id);
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        assert!(
+            self.unlocked_balance(&account_id, balance) >= amount.0,
+            "Insufficient unlocked balance"
+        );
+        let payout = amount.0 * self.buyback_price_yocto;
+        assert!(
+            payout <= self.buyback_reserve,
+            "Buyback reserve holds only {} yoctoNEAR",
+            self.buyback_reserve
+        );
+
+        self.internal_set_balance(&account_id, balance - amount.0);
+        self.total_supply -= amount.0;
+        self.buyback_reserve -= payout;
+        TokensSold {
+            account_id: &account_id,
+            amount,
+            payout: U128(payout),
+        }
+        .emit();
+        Promise::new(account_id.clone()).transfer(payout).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE_SALE)
+                .on_tokens_sold(account_id, amount, U128(payout)),
+        )
+    }
+
+    /// Re-mints the tokens and returns the NEAR to the reserve when the
+    /// transfer failed.
+    #[private]
+    pub fn on_tokens_sold(&mut self, account_id: AccountId, amount: U128, payout: U128) -> bool {
+        if is_promise_success() {
+            return true;
+        }
+        self.mint(account_id.clone(), amount.0);
+        self.buyback_reserve += payout.0;
+        BuybackRefunded {
+            account_id: &account_id,
+            amount,
+            payout,
+        }
+        .emit();
+        false
+    }
+
+    // View functions
+    /// 0 while the buyback is closed.
+    pub fn get_buyback_price(&self) -> U128 {
+        U128(self.buyback_price_yocto)
+    }
+
+    pub fn get_buyback_reserve(&self) -> U128 {
+        U128(self.buyback_reserve)
+    }
+
+    /// The sale and buyback prices, in yoctoNEAR per token.
+    pub fn get_token_prices(&self) -> (U128, U128) {
+        (U128(self.token_price_yocto), U128(self.buyback_price_yocto))
+    }
+}
+
+impl FoodBankToken {
+    /// Without a sale price there is nothing to arbitrage against.
+    pub(crate) fn assert_buyback_below_sale(&self, buyback_price: Balance, sale_price: Balance) {
+        assert!(
+            sale_price == 0 || buyback_price <= sale_price,
+            "Buyback price must not exceed the sale price"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::get_created_receipts;
+    use near_sdk::PromiseResult;
+
+    const PRICE: Balance = ONE_NEAR / 100;
+
+    fn with_buyback(reserve: Balance) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_buyback_price(U128(PRICE));
+        if reserve > 0 {
+            set_caller(owner(), reserve);
+            contract.fund_buyback_reserve();
+        }
+        contract.mint(food_bank(), 100);
+        contract
+    }
+
+    fn sell(contract: &mut FoodBankToken, amount: Balance) {
+        set_caller(food_bank(), 1);
+        contract.sell_tokens(U128(amount));
+    }
+
+    #[test]
+    fn sold_tokens_are_burned_and_paid_from_the_reserve() {
+        let mut contract = with_buyback(ONE_NEAR);
+        sell(&mut contract, 40);
+        assert_eq!(contract.get_balance(food_bank()).0, 60);
+        assert_eq!(contract.get_total_supply().0, INITIAL_SUPPLY + 60);
+        assert_eq!(contract.get_buyback_reserve().0, ONE_NEAR - 40 * PRICE);
+
+        let payout = &get_created_receipts()[0];
+        assert_eq!(payout.receiver_id, food_bank());
+        assert_eq!(payout.actions, vec![VmAction::Transfer { deposit: 40 * PRICE }]);
+    }
+
+    #[test]
+    fn a_failed_transfer_gives_the_tokens_back() {
+        let mut contract = with_buyback(ONE_NEAR);
+        sell(&mut contract, 40);
+        resolve_callback(PromiseResult::Failed);
+        assert!(!contract.on_tokens_sold(food_bank(), U128(40), U128(40 * PRICE)));
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+        assert_eq!(contract.get_buyback_reserve().0, ONE_NEAR);
+    }
+
+    #[test]
+    #[should_panic(expected = "Buyback reserve holds only 0 yoctoNEAR")]
+    fn an_empty_reserve_buys_nothing() {
+        let mut contract = with_buyback(0);
+        sell(&mut contract, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Buyback reserve holds only 500000000000000000000000 yoctoNEAR")]
+    fn sales_beyond_the_reserve_are_rejected_whole() {
+        // Half a NEAR covers 50 tokens.
+        let mut contract = with_buyback(ONE_NEAR / 2);
+        sell(&mut contract, 51);
+    }
+
+    #[test]
+    #[should_panic(expected = "Buyback price must not exceed the sale price")]
+    fn the_buyback_price_stays_below_the_sale_price() {
+        let mut contract = setup_contract();
+        contract.set_token_price(U128(PRICE));
+        contract.set_buyback_price(U128(PRICE + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Buyback price must not exceed the sale price")]
+    fn the_sale_price_cannot_drop_below_the_buyback_price() {
+        let mut contract = with_buyback(0);
+        contract.set_token_price(U128(PRICE - 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient unlocked balance")]
+    fn locked_rewards_cannot_be_sold() {
+        let mut contract = with_buyback(ONE_NEAR);
+        contract.set_reward_lock_duration(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        sell(&mut contract, 101);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Treasurer role")]
+    fn only_the_treasurer_funds_the_reserve() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), ONE_NEAR);
+        contract.fund_buyback_reserve();
+    }
+
+    #[test]
+    fn the_reserve_is_not_available_to_withdraw_near() {
+        let mut contract = with_buyback(ONE_NEAR);
+        set_caller(owner(), 0);
+        let available = contract.get_available_near().0;
+        contract.buyback_reserve = 0;
+        assert_eq!(contract.get_available_near().0 - available, ONE_NEAR);
+    }
+}
@@ -0,0 +1,238 @@
+//! Time-boxed campaigns that pay a bonus on top of the regular reward, drawn
+//! from a fixed pool.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{CampaignClosed, CampaignCreated, Event};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Campaign {
+    pub id: u64,
+    pub name: String,
+    pub start: u64,
+    /// Exclusive.
+    pub end: u64,
+    pub reward_pool: U128,
+    pub distributed: U128,
+    pub bonus_bps: u16,
+    pub closed: bool,
+}
+
+impl Campaign {
+    pub(crate) fn is_active_at(&self, timestamp: u64) -> bool {
+        !self.closed && self.start <= timestamp && timestamp < self.end
+    }
+
+    /// Bonus for `reward`, limited to what is left in the pool.
+    pub(crate) fn bonus_for(&self, reward: Balance) -> Balance {
+        let remaining = self.reward_pool.0 - self.distributed.0;
+        (reward * Balance::from(self.bonus_bps) / 10_000).min(remaining)
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn create_campaign(
+        &mut self,
+        name: String,
+        start: u64,
+        end: u64,
+        reward_pool: U128,
+        bonus_bps: u16,
+    ) -> u64 {
+        self.assert_owner_action();
+        assert!(start < end, "Campaign must end after it starts");
+        assert!(bonus_bps <= 10_000, "Campaign bonus must be <= 10000 bps");
+        let id = self.next_campaign_id;
+        self.next_campaign_id += 1;
+        self.open_campaigns.push(id);
+        CampaignCreated { id, name: &name }.emit();
+        self.campaigns.insert(
+            &id,
+            &Campaign {
+                id,
+                name,
+                start,
+                end,
+                reward_pool,
+                distributed: U128(0),
+                bonus_bps,
+                closed: false,
+            },
+        );
+        id
+    }
+
+    /// Ends a campaign early. Whatever is left in its pool is never minted.
+    pub fn close_campaign(&mut self, campaign_id: u64) {
+        self.assert_owner_action();
+        let campaign = self.campaigns.get(&campaign_id).expect("Campaign not found");
+        assert!(!campaign.closed, "Campaign is already closed");
+        self.internal_close_campaign(campaign);
+    }
+
+    // View functions
+    pub fn get_campaign(&self, campaign_id: u64) -> Option<Campaign> {
+        self.campaigns.get(&campaign_id)
+    }
+
+    pub fn get_active_campaigns(&self) -> Vec<Campaign> {
+        let now = env::block_timestamp();
+        self.campaigns
+            .values()
+            .filter(|campaign| campaign.is_active_at(now))
+            .collect()
+    }
+
+    /// Total reward, bonus included, earned by `account_id` in a campaign.
+    pub fn get_campaign_earnings(&self, campaign_id: u64, account_id: AccountId) -> U128 {
+        U128(self.campaign_earnings.get(&(campaign_id, account_id)).unwrap_or(0))
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn active_campaign(&self, campaign_id: u64) -> Campaign {
+        let campaign = self.campaigns.get(&campaign_id).expect("Campaign not found");
+        assert!(
+            campaign.is_active_at(env::block_timestamp()),
+            "Campaign {} is not active",
+            campaign_id
+        );
+        campaign
+    }
+
+    pub(crate) fn internal_close_campaign(&mut self, mut campaign: Campaign) {
+        campaign.closed = true;
+        self.campaigns.insert(&campaign.id, &campaign);
+        self.open_campaigns.retain(|id| *id != campaign.id);
+        CampaignClosed { id: campaign.id }.emit();
+    }
+
+    pub(crate) fn record_campaign_reward(
+        &mut self,
+        campaign_id: u64,
+        account_id: &AccountId,
+        bonus: Balance,
+        reward: Balance,
+    ) {
+        let mut campaign = self.campaigns.get(&campaign_id).expect("Campaign not found");
+        campaign.distributed = U128(campaign.distributed.0 + bonus);
+        self.campaigns.insert(&campaign_id, &campaign);
+        let key = (campaign_id, account_id.clone());
+        let earned = self.campaign_earnings.get(&key).unwrap_or(0);
+        self.campaign_earnings.insert(&key, &(earned + reward));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    /// A 50% bonus campaign running over the second day.
+    fn setup_campaign(pool: Balance) -> (FoodBankToken, u64) {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        let id = contract.create_campaign(
+            "Summer produce".to_string(),
+            DAY,
+            2 * DAY,
+            U128(pool),
+            5_000,
+        );
+        advance_time(DAY);
+        (contract, id)
+    }
+
+    fn contribute_to(contract: &mut FoodBankToken, campaign_id: u64, score: u8) {
+        set_caller(owner(), ONE_NEAR);
+        let metrics = metrics(score, score, score);
+        contract.record_contribution(food_bank(), metrics, Some(campaign_id), None, None, None);
+    }
+
+    #[test]
+    fn bonus_is_drawn_from_the_pool() {
+        let (mut contract, id) = setup_campaign(1_000);
+        contribute_to(&mut contract, id, 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 150);
+        assert_eq!(contract.get_campaign(id).unwrap().distributed.0, 50);
+        assert_eq!(contract.get_campaign_earnings(id, food_bank()).0, 150);
+
+        let breakdown = contract.get_reward_breakdown(food_bank(), 0).unwrap();
+        assert_eq!(breakdown.campaign_id, Some(id));
+        assert_eq!(breakdown.campaign_bonus.0, 50);
+    }
+
+    #[test]
+    fn bonus_shrinks_to_the_remaining_pool() {
+        let (mut contract, id) = setup_campaign(70);
+        contribute_to(&mut contract, id, 100);
+        contribute_to(&mut contract, id, 100);
+        contribute_to(&mut contract, id, 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 150 + 120 + 100);
+        assert_eq!(contract.get_campaign(id).unwrap().distributed.0, 70);
+    }
+
+    #[test]
+    fn contributions_without_a_campaign_earn_no_bonus() {
+        let (mut contract, id) = setup_campaign(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+        assert_eq!(contract.get_campaign_earnings(id, food_bank()).0, 0);
+    }
+
+    #[test]
+    fn active_campaigns_follow_the_window() {
+        let (contract, id) = setup_campaign(1_000);
+        assert_eq!(contract.get_active_campaigns()[0].id, id);
+        advance_time(DAY);
+        assert!(contract.get_active_campaigns().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign 0 is not active")]
+    fn contributions_after_the_window_are_rejected() {
+        let (mut contract, id) = setup_campaign(1_000);
+        advance_time(DAY);
+        contribute_to(&mut contract, id, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign 0 is not active")]
+    fn contributions_before_the_window_are_rejected() {
+        let mut contract = setup_contract();
+        let id = contract.create_campaign("Later".to_string(), DAY, 2 * DAY, U128(10), 5_000);
+        contribute_to(&mut contract, id, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign 0 is not active")]
+    fn closed_campaigns_reject_contributions() {
+        let (mut contract, id) = setup_campaign(1_000);
+        set_caller(owner(), 0);
+        contract.close_campaign(id);
+        contribute_to(&mut contract, id, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign must end after it starts")]
+    fn empty_window_is_rejected() {
+        let mut contract = setup_contract();
+        contract.create_campaign("Nothing".to_string(), DAY, DAY, U128(10), 5_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn campaigns_are_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.create_campaign("Mine".to_string(), 0, DAY, U128(10), 5_000);
+    }
+}
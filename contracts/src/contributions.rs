@@ -0,0 +1,318 @@
+//! Versioned storage for contribution histories. Each stored entry carries
+//! its schema version, so new fields only need a new variant.
+//!
+//! Histories written before versioning live under the original `c` prefix
+//! as plain `Vec<ContributionMetrics>`. They are read through the same
+//! views, moved over whenever the account records a new contribution, and
+//! can be moved in bulk with `migrate_contributions`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::metric_rules::RawMetrics;
+use crate::{ContributionMetrics, FoodBankToken, FoodBankTokenExt};
+
+pub const MAX_EVIDENCE_CID_LENGTH: usize = 128;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContributionMetricsV2 {
+    pub(crate) data_quality: u8,
+    pub(crate) model_improvement: u8,
+    pub(crate) participation_frequency: u8,
+    pub(crate) timestamp: u64,
+    /// IPFS CID of the evaluation the scores came from.
+    pub(crate) evidence_cid: Option<String>,
+}
+
+/// The metrics callers submit to `record_contribution` and `preview_reward`.
+pub type ContributionMetricsInput = ContributionMetricsV2;
+
+/// A contribution recorded under metric rules: `metrics` holds the 0–100
+/// values it was scored with, `raw` what the pipeline submitted.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct ContributionMetricsV3 {
+    pub(crate) metrics: ContributionMetricsV2,
+    pub(crate) raw: RawMetrics,
+}
+
+/// A contribution scored on named metrics; see `named_metrics.rs`. The
+/// names, values and resulting score are kept inline so later changes to
+/// the metric registry leave it intact.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct ContributionMetricsV4 {
+    pub(crate) metrics: Vec<(String, u8)>,
+    pub(crate) score: u8,
+    pub(crate) timestamp: u64,
+    pub(crate) evidence_cid: Option<String>,
+}
+
+/// What is kept of a contribution recorded once the account's history is
+/// full; see `archives.rs`.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct ContributionDigest {
+    pub(crate) timestamp: u64,
+    pub(crate) score: u8,
+    pub(crate) reward: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedContribution {
+    V1(ContributionMetrics),
+    V2(ContributionMetricsV2),
+    V3(ContributionMetricsV3),
+    V4(ContributionMetricsV4),
+    V5(ContributionDigest),
+}
+
+impl ContributionMetricsV2 {
+    /// The three metrics weighted equally.
+    pub fn score(&self) -> u32 {
+        (u32::from(self.data_quality)
+            + u32::from(self.model_improvement)
+            + u32::from(self.participation_frequency))
+            / 3
+    }
+
+    /// The fixed three under their metric names.
+    pub fn named(&self) -> Vec<(String, u8)> {
+        vec![
+            ("data_quality".to_string(), self.data_quality),
+            ("model_improvement".to_string(), self.model_improvement),
+            ("participation_frequency".to_string(), self.participation_frequency),
+        ]
+    }
+}
+
+impl From<ContributionMetrics> for ContributionMetricsV2 {
+    fn from(metrics: ContributionMetrics) -> Self {
+        Self {
+            data_quality: metrics.data_quality,
+            model_improvement: metrics.model_improvement,
+            participation_frequency: metrics.participation_frequency,
+            timestamp: metrics.timestamp,
+            evidence_cid: None,
+        }
+    }
+}
+
+impl From<ContributionMetrics> for VersionedContribution {
+    fn from(metrics: ContributionMetrics) -> Self {
+        Self::V1(metrics)
+    }
+}
+
+impl From<ContributionMetricsV2> for VersionedContribution {
+    fn from(metrics: ContributionMetricsV2) -> Self {
+        Self::V2(metrics)
+    }
+}
+
+impl From<ContributionMetricsV3> for VersionedContribution {
+    fn from(metrics: ContributionMetricsV3) -> Self {
+        Self::V3(metrics)
+    }
+}
+
+impl From<ContributionMetricsV4> for VersionedContribution {
+    fn from(metrics: ContributionMetricsV4) -> Self {
+        Self::V4(metrics)
+    }
+}
+
+impl From<ContributionDigest> for VersionedContribution {
+    fn from(digest: ContributionDigest) -> Self {
+        Self::V5(digest)
+    }
+}
+
+impl VersionedContribution {
+    /// The metrics on the 0–100 scale the contribution was scored with.
+    /// Named contributions fill in whichever of the fixed three they have
+    /// and leave the rest at 0; digests report their score for all three.
+    pub fn into_latest(self) -> ContributionMetricsV2 {
+        match self {
+            Self::V1(metrics) => metrics.into(),
+            Self::V2(metrics) => metrics,
+            Self::V3(metrics) => metrics.metrics,
+            Self::V4(metrics) => {
+                let value = |name: &str| {
+                    metrics
+                        .metrics
+                        .iter()
+                        .find(|(metric, _)| metric == name)
+                        .map_or(0, |(_, value)| *value)
+                };
+                ContributionMetricsV2 {
+                    data_quality: value("data_quality"),
+                    model_improvement: value("model_improvement"),
+                    participation_frequency: value("participation_frequency"),
+                    timestamp: metrics.timestamp,
+                    evidence_cid: metrics.evidence_cid,
+                }
+            }
+            Self::V5(digest) => ContributionMetricsV2 {
+                data_quality: digest.score,
+                model_improvement: digest.score,
+                participation_frequency: digest.score,
+                timestamp: digest.timestamp,
+                evidence_cid: None,
+            },
+        }
+    }
+
+    /// The 0–100 score the contribution was rewarded for.
+    pub fn score(&self) -> u32 {
+        match self {
+            Self::V1(metrics) => {
+                (u32::from(metrics.data_quality)
+                    + u32::from(metrics.model_improvement)
+                    + u32::from(metrics.participation_frequency))
+                    / 3
+            }
+            Self::V2(metrics) => metrics.score(),
+            Self::V3(metrics) => metrics.metrics.score(),
+            Self::V4(metrics) => u32::from(metrics.score),
+            Self::V5(digest) => u32::from(digest.score),
+        }
+    }
+
+    /// Every metric by name, the fixed three included.
+    pub fn into_named(self) -> Vec<(String, u8)> {
+        match self {
+            Self::V4(metrics) => metrics.metrics,
+            other => other.into_latest().named(),
+        }
+    }
+
+    /// The metrics as submitted. Entries from before metric rules were
+    /// submitted on the 0–100 scale.
+    pub fn into_raw(self) -> RawMetrics {
+        match self {
+            Self::V3(metrics) => metrics.raw,
+            other => RawMetrics::from(&other.into_latest()),
+        }
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Moves up to `limit` legacy histories into versioned storage and
+    /// returns how many are left.
+    pub fn migrate_contributions(&mut self, limit: u64) -> u64 {
+        self.assert_owner_action();
+        let accounts: Vec<AccountId> = self
+            .legacy_contributions
+            .keys()
+            .take(limit as usize)
+            .collect();
+        for account_id in accounts {
+            let history = self.stored_contributions(&account_id);
+            self.store_contributions(&account_id, &history);
+        }
+        self.legacy_contributions.len()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn stored_contributions(&self, account_id: &AccountId) -> Vec<VersionedContribution> {
+        match self.contributions.get(account_id) {
+            Some(history) => history,
+            None => self
+                .legacy_contributions
+                .get(account_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(VersionedContribution::from)
+                .collect(),
+        }
+    }
+
+    /// Writes `history` in versioned form, dropping any legacy copy.
+    pub(crate) fn store_contributions(
+        &mut self,
+        account_id: &AccountId,
+        history: &Vec<VersionedContribution>,
+    ) {
+        self.contributions.insert(account_id, history);
+        self.legacy_contributions.remove(account_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn legacy_metrics(score: u8) -> ContributionMetrics {
+        ContributionMetrics {
+            data_quality: score,
+            model_improvement: score,
+            participation_frequency: score,
+            timestamp: 7,
+        }
+    }
+
+    #[test]
+    fn v1_bytes_read_through_the_versioned_path() {
+        // Bytes as the pre-versioning contract wrote them.
+        let bytes = borsh::to_vec(&vec![legacy_metrics(80)]).unwrap();
+        let legacy: Vec<ContributionMetrics> = BorshDeserialize::try_from_slice(&bytes).unwrap();
+
+        let mut contract = setup_contract();
+        contract.legacy_contributions.insert(&food_bank(), &legacy);
+        let history = contract.get_contributions(food_bank());
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].data_quality, 80);
+        assert_eq!(history[0].timestamp, 7);
+        assert!(history[0].evidence_cid.is_none());
+    }
+
+    #[test]
+    fn new_contributions_move_the_legacy_history() {
+        let mut contract = setup_contract();
+        contract.legacy_contributions.insert(&food_bank(), &vec![legacy_metrics(80)]);
+        contribute(&mut contract, &food_bank(), 100);
+
+        assert!(contract.legacy_contributions.get(&food_bank()).is_none());
+        let stored = contract.contributions.get(&food_bank()).unwrap();
+        assert!(matches!(stored[0], VersionedContribution::V1(_)));
+        assert!(matches!(stored[1], VersionedContribution::V3(_)));
+        assert_eq!(contract.get_contributions(food_bank()).len(), 2);
+    }
+
+    #[test]
+    fn migration_runs_in_pages() {
+        let mut contract = setup_contract();
+        contract.legacy_contributions.insert(&food_bank(), &vec![legacy_metrics(80)]);
+        contract.legacy_contributions.insert(&other_food_bank(), &vec![legacy_metrics(60)]);
+
+        assert_eq!(contract.migrate_contributions(1), 1);
+        assert_eq!(contract.migrate_contributions(1), 0);
+        assert_eq!(contract.get_contributions(food_bank())[0].data_quality, 80);
+        assert_eq!(contract.get_contributions(other_food_bank())[0].data_quality, 60);
+    }
+
+    #[test]
+    fn evidence_cid_is_stored() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        let mut metrics = metrics(100, 100, 100);
+        metrics.evidence_cid = Some("bafybeigdyrzt".to_string());
+        contract.record_contribution(food_bank(), metrics, None, None, None, None);
+        assert_eq!(
+            contract.get_contributions(food_bank())[0].evidence_cid.as_deref(),
+            Some("bafybeigdyrzt")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn migration_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.migrate_contributions(10);
+    }
+}
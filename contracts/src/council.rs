@@ -0,0 +1,958 @@
+//! k-of-n council control over the owner's privileged operations.
+//!
+//! Once the owner configures a council, the operations covered by
+//! `AdminAction` can no longer be called with the owner key alone: a council
+//! member proposes the action, `threshold` distinct members confirm it, and
+//! any member executes it, and the owner no longer holds roles implicitly.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Gas, PublicKey};
+
+use crate::account_types::AccountType;
+use crate::batch_mint::MintBatchCap;
+use crate::donations::TokenRate;
+use crate::epochs::RewardMode;
+use crate::events::{
+    CouncilActionCancelled, CouncilActionConfirmed, CouncilActionExecuted, CouncilActionProposed,
+    CouncilChanged, Event,
+};
+use crate::governance::GovernanceConfig;
+use crate::metric_rules::MetricRules;
+use crate::named_metrics::MetricDefinition;
+use crate::roles::Role;
+use crate::staking::StakeTier;
+use crate::tenure::TenureDiscount;
+use crate::tiers::LifetimeTier;
+use crate::timelock::ParamChange;
+use crate::transfer_policy::TransferPolicy;
+use crate::{BenefitCall, FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AdminAction {
+    /// Applied immediately, like a passed governance proposal.
+    SetRewardRateBps(u16),
+    SetScoreThreshold(u32),
+    ProposeParamChange(ParamChange),
+    CancelParamChange(u64),
+    AddRedemptionOption {
+        option_id: String,
+        name: String,
+        cost: U128,
+        description: String,
+        benefit: Option<BenefitCall>,
+    },
+    FreezeAccount {
+        account_id: AccountId,
+        reason: String,
+    },
+    UnfreezeAccount {
+        account_id: AccountId,
+    },
+    VoidContribution {
+        account_id: AccountId,
+        index: u64,
+    },
+    SetTreasury {
+        account_id: Option<AccountId>,
+        share_bps: u16,
+    },
+    TreasuryTransfer {
+        to: AccountId,
+        amount: U128,
+    },
+    WithdrawNear {
+        amount: U128,
+        to: AccountId,
+    },
+    ProposeOwner(AccountId),
+    SetCouncil {
+        members: Vec<AccountId>,
+        threshold: u32,
+    },
+    /// Starts the upgrade timelock; see `upgrade.rs`.
+    StageCode {
+        code_hash: Base58CryptoHash,
+    },
+    CancelStagedCode,
+    SetMintBatchCap(MintBatchCap),
+    VoidPenalty {
+        account_id: AccountId,
+        index: u64,
+    },
+    GrantRole {
+        account_id: AccountId,
+        role: Role,
+    },
+    RevokeRole {
+        account_id: AccountId,
+        role: Role,
+    },
+    Unpause,
+    SetAccountType {
+        account_id: AccountId,
+        account_type: AccountType,
+    },
+    SetAcknowledgmentMode {
+        required: bool,
+        window_ns: u64,
+    },
+    SetActivityFeedCapacity(u64),
+    SetMaxHistoryLength(u64),
+    SetAttestorKey {
+        public_key: PublicKey,
+        grace_period_ns: u64,
+    },
+    SetBadgeConfig {
+        badge_contract: Option<AccountId>,
+        milestones: Vec<u64>,
+    },
+    SetBuybackPrice(U128),
+    CreateCampaign {
+        name: String,
+        start: u64,
+        end: u64,
+        reward_pool: U128,
+        bonus_bps: u16,
+    },
+    CloseCampaign(u64),
+    SetDisputeWindow(u64),
+    PublishDistribution {
+        root: Base64VecU8,
+        total: U128,
+        expiry_ts: u64,
+    },
+    CloseDistribution(u64),
+    AddAcceptedToken {
+        token_id: AccountId,
+        rate: TokenRate,
+    },
+    RemoveAcceptedToken(AccountId),
+    SetRewardMode(RewardMode),
+    SetEventBufferCapacity(u64),
+    SetFulfillmentWindow(u64),
+    SetGovernanceConfig(GovernanceConfig),
+    SetGrantRefundWindow(u64),
+    AddGuardian(AccountId),
+    RemoveGuardian(AccountId),
+    SetRewardLockDuration(u64),
+    SetTokensPerNear(U128),
+    SweepMatchingPool,
+    SetSourceMetadataLink(Option<String>),
+    SetMetricRules(MetricRules),
+    ReleaseWithheldRewards {
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    },
+    SetMetricDefinition {
+        name: String,
+        definition: MetricDefinition,
+    },
+    RemoveMetricDefinition(String),
+    SetOnboardingBonus(U128),
+    VerifyProfile(AccountId),
+    CreatePromo {
+        code_hash: Base64VecU8,
+        option_id: String,
+        discount_bps: u16,
+        max_uses: u32,
+        expiry_ts: u64,
+    },
+    SetRecognitionPerNear(U128),
+    SetReferrerFor {
+        account_id: AccountId,
+        referrer: AccountId,
+    },
+    SetReferralConfig {
+        bonus_bps: u16,
+        bonus_cap: U128,
+    },
+    SetReputationHalfLife(u64),
+    SetTokenPrice(U128),
+    SetSalesEnabled(bool),
+    SetMaxSupply(Option<U128>),
+    FreezeSnapshot,
+    ReleaseSnapshot,
+    SetStakeTiers(Vec<StakeTier>),
+    SetStakeCooldown(u64),
+    RegisterSupplier {
+        account_id: AccountId,
+        name: String,
+        discount_bps: u16,
+    },
+    DeactivateSupplier(AccountId),
+    SetOptionSupplier {
+        option_id: String,
+        supplier: Option<AccountId>,
+    },
+    SetTenureDiscounts(Vec<TenureDiscount>),
+    SetTierConfig(Vec<LifetimeTier>),
+    SetTransferPolicy(TransferPolicy),
+    AddToTransferAllowlist(AccountId),
+    RemoveFromTransferAllowlist(AccountId),
+    SetOptionTranslation {
+        option_id: String,
+        locale: String,
+        name: String,
+        description: String,
+    },
+    RemoveOptionTranslation {
+        option_id: String,
+        locale: String,
+    },
+    SetRequiredVerifications {
+        category: String,
+        required: u8,
+    },
+    AddVerifier(AccountId),
+    RemoveVerifier(AccountId),
+    SetVestingConfig {
+        threshold: U128,
+        duration_ns: u64,
+    },
+    SetBenefitGas {
+        option_id: String,
+        gas: Gas,
+    },
+    RecalculateRewards {
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+        claw_back: bool,
+    },
+    SetMinContributionInterval(u64),
+    ArchiveContributions {
+        account_id: AccountId,
+        keep_last: u64,
+    },
+    /// The proposer's storage credit pays the mint deposit.
+    RetryBadgeMint {
+        account_id: AccountId,
+        milestone: u64,
+    },
+    ResumeMinting,
+    MigrateContributions(u64),
+    MigrateRedemptionOptions(u64),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CouncilAction {
+    pub id: u64,
+    pub action: AdminAction,
+    pub proposer: AccountId,
+    pub proposed_at: u64,
+    /// Includes the proposer.
+    pub confirmations: Vec<AccountId>,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Replaces the council. An empty council with a zero threshold hands
+    /// the covered operations back to the owner key.
+    pub fn set_council(&mut self, members: Vec<AccountId>, threshold: u32) {
+        self.assert_owner_action();
+        if members.is_empty() {
+            assert!(threshold == 0, "An empty council must have a zero threshold");
+        } else {
+            assert!(
+                threshold >= 1 && threshold as usize <= members.len(),
+                "Threshold must be between 1 and {}",
+                members.len()
+            );
+        }
+        for (index, member) in members.iter().enumerate() {
+            assert!(
+                !members[..index].contains(member),
+                "Duplicate council member {}",
+                member
+            );
+        }
+        CouncilChanged {
+            members: &members,
+            threshold,
+        }
+        .emit();
+        self.council = members;
+        self.council_threshold = threshold;
+    }
+
+    /// Opens an action, counting as the proposer's confirmation. Storage
+    /// the action adds once executed, such as a new option, promo, supplier
+    /// or translation, comes out of the proposer's storage credit; any
+    /// deposit attached here is added to that credit first.
+    #[payable]
+    pub fn propose_action(&mut self, action: AdminAction) -> u64 {
+        let proposer = env::predecessor_account_id();
+        self.assert_council_member(&proposer);
+        if env::attached_deposit() > 0 {
+            self.add_storage_credit(&proposer, env::attached_deposit());
+        }
+        let id = self.next_council_action_id;
+        self.next_council_action_id += 1;
+        CouncilActionProposed {
+            id,
+            proposer: &proposer,
+            action: &action,
+        }
+        .emit();
+        self.council_actions.insert(
+            &id,
+            &CouncilAction {
+                id,
+                action,
+                proposer: proposer.clone(),
+                proposed_at: env::block_timestamp(),
+                confirmations: vec![proposer],
+            },
+        );
+        id
+    }
+
+    pub fn confirm_action(&mut self, action_id: u64) {
+        let account_id = env::predecessor_account_id();
+        self.assert_council_member(&account_id);
+        let mut council_action = self.council_actions.get(&action_id)
+            .expect("Council action not found");
+        assert!(
+            !council_action.confirmations.contains(&account_id),
+            "Action already confirmed by {}",
+            account_id
+        );
+        council_action.confirmations.push(account_id.clone());
+        self.council_actions.insert(&action_id, &council_action);
+        CouncilActionConfirmed {
+            id: action_id,
+            account_id: &account_id,
+            confirmations: self.valid_confirmations(&council_action),
+        }
+        .emit();
+    }
+
+    /// Runs an action once enough current council members have confirmed
+    /// it. Confirmations from accounts that have since left the council
+    /// don't count.
+    pub fn execute_action(&mut self, action_id: u64) {
+        let actor = env::predecessor_account_id();
+        self.assert_council_member(&actor);
+        let council_action = self.council_actions.get(&action_id)
+            .expect("Council action not found");
+        let confirmations = self.valid_confirmations(&council_action);
+        assert!(
+            confirmations >= self.council_threshold,
+            "Action has {} of {} required confirmations",
+            confirmations,
+            self.council_threshold
+        );
+        self.council_actions.remove(&action_id);
+        self.executing_council_action = Some(council_action.proposer.clone());
+        self.apply_admin_action(council_action.action.clone());
+        self.executing_council_action = None;
+        CouncilActionExecuted {
+            id: action_id,
+            actor: &actor,
+            action: &council_action.action,
+        }
+        .emit();
+    }
+
+    /// Withdraws an action that has not been executed yet. Only its
+    /// proposer can cancel it.
+    pub fn cancel_action(&mut self, action_id: u64) {
+        let council_action = self.council_actions.get(&action_id)
+            .expect("Council action not found");
+        assert_eq!(
+            env::predecessor_account_id(),
+            council_action.proposer,
+            "Only the proposer can cancel this action"
+        );
+        self.council_actions.remove(&action_id);
+        CouncilActionCancelled { id: action_id }.emit();
+    }
+
+    // View functions
+    pub fn get_council(&self) -> Vec<AccountId> {
+        self.council.clone()
+    }
+
+    pub fn get_council_threshold(&self) -> u32 {
+        self.council_threshold
+    }
+
+    pub fn get_council_action(&self, action_id: u64) -> Option<CouncilAction> {
+        self.council_actions.get(&action_id)
+    }
+
+    /// Actions that have been neither executed nor cancelled.
+    pub fn get_open_actions(&self, from_index: u64, limit: u64) -> Vec<CouncilAction> {
+        self.council_actions
+            .values()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    /// Guards the operations covered by `AdminAction`: they run from an
+    /// executed council action, or from the owner while no council is set.
+    pub(crate) fn assert_owner_action(&self) {
+        if self.executing_council_action.is_some() {
+            return;
+        }
+        self.assert_owner();
+        assert!(
+            self.council.is_empty(),
+            "This operation must be proposed to the council"
+        );
+    }
+
+    fn assert_council_member(&self, account_id: &AccountId) {
+        assert!(
+            self.council.contains(account_id),
+            "{} is not a council member",
+            account_id
+        );
+    }
+
+    fn valid_confirmations(&self, council_action: &CouncilAction) -> u32 {
+        council_action
+            .confirmations
+            .iter()
+            .filter(|account_id| self.council.contains(account_id))
+            .count() as u32
+    }
+
+    fn apply_admin_action(&mut self, action: AdminAction) {
+        match action {
+            AdminAction::SetRewardRateBps(rate_bps) => {
+                self.apply_param_change(&ParamChange::RewardRateBps(rate_bps));
+            }
+            AdminAction::SetScoreThreshold(threshold) => {
+                self.apply_param_change(&ParamChange::MinScoreThreshold(threshold));
+            }
+            AdminAction::ProposeParamChange(change) => {
+                self.propose_param_change(change);
+            }
+            AdminAction::CancelParamChange(id) => self.cancel_param_change(id),
+            AdminAction::AddRedemptionOption {
+                option_id,
+                name,
+                cost,
+                description,
+                benefit,
+            } => {
+                let initial_storage = env::storage_usage();
+                self.internal_add_redemption_option(option_id, name, cost.0, description, benefit);
+                self.charge_storage(initial_storage, 0);
+            }
+            AdminAction::FreezeAccount { account_id, reason } => {
+                self.freeze_account(account_id, reason);
+            }
+            AdminAction::UnfreezeAccount { account_id } => self.unfreeze_account(account_id),
+            AdminAction::VoidContribution { account_id, index } => {
+                self.void_contribution(account_id, index);
+            }
+            AdminAction::SetTreasury {
+                account_id,
+                share_bps,
+            } => self.set_treasury(account_id, share_bps),
+            AdminAction::TreasuryTransfer { to, amount } => {
+                self.internal_treasury_transfer(to, amount);
+            }
+            AdminAction::WithdrawNear { amount, to } => {
+                self.internal_withdraw_near(amount, to);
+            }
+            AdminAction::ProposeOwner(new_owner) => self.propose_owner(new_owner),
+            AdminAction::SetCouncil { members, threshold } => {
+                self.set_council(members, threshold);
+            }
+            AdminAction::StageCode { code_hash } => self.internal_stage_code(code_hash),
+            AdminAction::CancelStagedCode => self.internal_cancel_staged_code(),
+            AdminAction::SetMintBatchCap(cap) => self.set_mint_batch_cap(cap),
+            AdminAction::VoidPenalty { account_id, index } => {
+                self.void_penalty(account_id, index);
+            }
+            AdminAction::GrantRole { account_id, role } => self.grant_role(account_id, role),
+            AdminAction::RevokeRole { account_id, role } => self.revoke_role(account_id, role),
+            AdminAction::Unpause => self.unpause(),
+            AdminAction::SetAccountType {
+                account_id,
+                account_type,
+            } => self.set_account_type(account_id, account_type),
+            AdminAction::SetAcknowledgmentMode { required, window_ns } => {
+                self.set_acknowledgment_mode(required, window_ns);
+            }
+            AdminAction::SetActivityFeedCapacity(capacity) => self.set_activity_feed_capacity(capacity),
+            AdminAction::SetMaxHistoryLength(max_history_length) => {
+                self.set_max_history_length(max_history_length);
+            }
+            AdminAction::SetAttestorKey {
+                public_key,
+                grace_period_ns,
+            } => self.set_attestor_key(public_key, grace_period_ns),
+            AdminAction::SetBadgeConfig {
+                badge_contract,
+                milestones,
+            } => self.set_badge_config(badge_contract, milestones),
+            AdminAction::SetBuybackPrice(price) => self.set_buyback_price(price),
+            AdminAction::CreateCampaign {
+                name,
+                start,
+                end,
+                reward_pool,
+                bonus_bps,
+            } => {
+                self.create_campaign(name, start, end, reward_pool, bonus_bps);
+            }
+            AdminAction::CloseCampaign(campaign_id) => self.close_campaign(campaign_id),
+            AdminAction::SetDisputeWindow(window_ns) => self.set_dispute_window(window_ns),
+            AdminAction::PublishDistribution { root, total, expiry_ts } => {
+                self.publish_distribution(root, total, expiry_ts);
+            }
+            AdminAction::CloseDistribution(distribution_id) => {
+                self.close_distribution(distribution_id);
+            }
+            AdminAction::AddAcceptedToken { token_id, rate } => self.add_accepted_token(token_id, rate),
+            AdminAction::RemoveAcceptedToken(token_id) => self.remove_accepted_token(token_id),
+            AdminAction::SetRewardMode(mode) => self.set_reward_mode(mode),
+            AdminAction::SetEventBufferCapacity(capacity) => self.set_event_buffer_capacity(capacity),
+            AdminAction::SetFulfillmentWindow(window_ns) => self.set_fulfillment_window(window_ns),
+            AdminAction::SetGovernanceConfig(config) => self.set_governance_config(config),
+            AdminAction::SetGrantRefundWindow(window_ns) => self.set_grant_refund_window(window_ns),
+            AdminAction::AddGuardian(account_id) => self.add_guardian(account_id),
+            AdminAction::RemoveGuardian(account_id) => self.remove_guardian(account_id),
+            AdminAction::SetRewardLockDuration(duration_ns) => self.set_reward_lock_duration(duration_ns),
+            AdminAction::SetTokensPerNear(tokens_per_near) => self.set_tokens_per_near(tokens_per_near),
+            AdminAction::SweepMatchingPool => {
+                self.sweep_matching_pool();
+            }
+            AdminAction::SetSourceMetadataLink(link) => self.set_source_metadata_link(link),
+            AdminAction::SetMetricRules(rules) => self.set_metric_rules(rules),
+            AdminAction::ReleaseWithheldRewards {
+                account_id,
+                from_index,
+                limit,
+            } => {
+                self.release_withheld_rewards(account_id, from_index, limit);
+            }
+            AdminAction::SetMetricDefinition { name, definition } => {
+                self.set_metric_definition(name, definition);
+            }
+            AdminAction::RemoveMetricDefinition(name) => self.remove_metric_definition(name),
+            AdminAction::SetOnboardingBonus(amount) => self.set_onboarding_bonus(amount),
+            AdminAction::VerifyProfile(account_id) => self.verify_profile(account_id),
+            AdminAction::CreatePromo {
+                code_hash,
+                option_id,
+                discount_bps,
+                max_uses,
+                expiry_ts,
+            } => self.create_promo(code_hash, option_id, discount_bps, max_uses, expiry_ts),
+            AdminAction::SetRecognitionPerNear(recognition_per_near) => {
+                self.set_recognition_per_near(recognition_per_near);
+            }
+            AdminAction::SetReferrerFor { account_id, referrer } => {
+                self.set_referrer_for(account_id, referrer);
+            }
+            AdminAction::SetReferralConfig { bonus_bps, bonus_cap } => {
+                self.set_referral_config(bonus_bps, bonus_cap);
+            }
+            AdminAction::SetReputationHalfLife(half_life_ns) => self.set_reputation_half_life(half_life_ns),
+            AdminAction::SetTokenPrice(price) => self.set_token_price(price),
+            AdminAction::SetSalesEnabled(enabled) => self.set_sales_enabled(enabled),
+            AdminAction::SetMaxSupply(max_supply) => self.set_max_supply(max_supply),
+            AdminAction::FreezeSnapshot => {
+                self.freeze_snapshot();
+            }
+            AdminAction::ReleaseSnapshot => self.release_snapshot(),
+            AdminAction::SetStakeTiers(tiers) => self.set_stake_tiers(tiers),
+            AdminAction::SetStakeCooldown(cooldown) => self.set_stake_cooldown(cooldown),
+            AdminAction::RegisterSupplier {
+                account_id,
+                name,
+                discount_bps,
+            } => self.register_supplier(account_id, name, discount_bps),
+            AdminAction::DeactivateSupplier(account_id) => self.deactivate_supplier(account_id),
+            AdminAction::SetOptionSupplier { option_id, supplier } => {
+                self.set_option_supplier(option_id, supplier);
+            }
+            AdminAction::SetTenureDiscounts(discounts) => self.set_tenure_discounts(discounts),
+            AdminAction::SetTierConfig(tiers) => self.set_tier_config(tiers),
+            AdminAction::SetTransferPolicy(policy) => self.set_transfer_policy(policy),
+            AdminAction::AddToTransferAllowlist(account_id) => self.add_to_transfer_allowlist(account_id),
+            AdminAction::RemoveFromTransferAllowlist(account_id) => {
+                self.remove_from_transfer_allowlist(account_id);
+            }
+            AdminAction::SetOptionTranslation {
+                option_id,
+                locale,
+                name,
+                description,
+            } => self.set_option_translation(option_id, locale, name, description),
+            AdminAction::RemoveOptionTranslation { option_id, locale } => {
+                self.remove_option_translation(option_id, locale);
+            }
+            AdminAction::SetRequiredVerifications { category, required } => {
+                self.set_required_verifications(category, required);
+            }
+            AdminAction::AddVerifier(account_id) => self.add_verifier(account_id),
+            AdminAction::RemoveVerifier(account_id) => self.remove_verifier(account_id),
+            AdminAction::SetVestingConfig { threshold, duration_ns } => {
+                self.set_vesting_config(threshold, duration_ns);
+            }
+            AdminAction::SetBenefitGas { option_id, gas } => self.set_benefit_gas(option_id, gas),
+            AdminAction::RecalculateRewards {
+                account_id,
+                from_index,
+                limit,
+                claw_back,
+            } => {
+                self.recalculate_rewards(account_id, from_index, limit, claw_back);
+            }
+            AdminAction::SetMinContributionInterval(interval_ns) => {
+                self.set_min_contribution_interval(interval_ns);
+            }
+            AdminAction::ArchiveContributions { account_id, keep_last } => {
+                self.archive_contributions(account_id, keep_last);
+            }
+            AdminAction::RetryBadgeMint { account_id, milestone } => {
+                self.retry_badge_mint(account_id, milestone);
+            }
+            AdminAction::ResumeMinting => self.set_minting_paused(false),
+            AdminAction::MigrateContributions(limit) => {
+                self.migrate_contributions(limit);
+            }
+            AdminAction::MigrateRedemptionOptions(limit) => {
+                self.migrate_redemption_options(limit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::badges::{BadgeStatus, BADGE_MINT_DEPOSIT};
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+
+    fn members() -> Vec<AccountId> {
+        vec![accounts(2), accounts(3), accounts(4)]
+    }
+
+    fn with_council() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_council(members(), 2);
+        contract
+    }
+
+    fn propose(contract: &mut FoodBankToken, action: AdminAction) -> u64 {
+        set_caller(accounts(2), 0);
+        contract.propose_action(action)
+    }
+
+    #[test]
+    fn action_executes_at_the_threshold() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::SetRewardRateBps(2_000));
+        set_caller(accounts(3), 0);
+        contract.confirm_action(id);
+        contract.execute_action(id);
+
+        assert_eq!(contract.get_config().reward_rate_bps, 2_000);
+        assert!(contract.get_council_action(id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Action has 1 of 2 required confirmations")]
+    fn action_needs_enough_confirmations() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::SetRewardRateBps(2_000));
+        contract.execute_action(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Action already confirmed by charlie")]
+    fn confirmations_must_be_distinct() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::SetRewardRateBps(2_000));
+        contract.confirm_action(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "bob is not a council member")]
+    fn non_members_cannot_confirm() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::SetRewardRateBps(2_000));
+        set_caller(food_bank(), 0);
+        contract.confirm_action(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_is_not_enough() {
+        let mut contract = with_council();
+        contract.freeze_account(food_bank(), "Gaming contributions".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_grant_roles() {
+        let mut contract = with_council();
+        contract.grant_role(food_bank(), Role::Treasurer);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_change_the_supply_cap() {
+        let mut contract = with_council();
+        contract.set_max_supply(Some(U128(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_publish_a_distribution() {
+        let mut contract = with_council();
+        contract.publish_distribution(Base64VecU8(vec![0; 32]), U128(100), u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Treasurer role")]
+    fn owner_loses_its_implicit_roles() {
+        let mut contract = with_council();
+        contract.mint_batch(vec![(food_bank(), U128(100))], "Grant".to_string());
+    }
+
+    #[test]
+    fn council_can_grant_roles() {
+        let mut contract = with_council();
+        let id = propose(
+            &mut contract,
+            AdminAction::GrantRole {
+                account_id: food_bank(),
+                role: Role::Treasurer,
+            },
+        );
+        set_caller(accounts(3), 0);
+        contract.confirm_action(id);
+        contract.execute_action(id);
+        assert!(contract.has_role(food_bank(), Role::Treasurer));
+    }
+
+    #[test]
+    fn council_can_freeze_and_move_treasury_tokens() {
+        let mut contract = setup_contract();
+        let treasury: AccountId = "treasury.near".parse().unwrap();
+        contract.set_treasury(Some(treasury.clone()), 0);
+        contract.mint(treasury, 100);
+        contract.set_council(members(), 2);
+
+        let freeze = propose(
+            &mut contract,
+            AdminAction::FreezeAccount {
+                account_id: food_bank(),
+                reason: "Gaming contributions".to_string(),
+            },
+        );
+        let transfer = propose(
+            &mut contract,
+            AdminAction::TreasuryTransfer {
+                to: other_food_bank(),
+                amount: U128(40),
+            },
+        );
+        assert_eq!(contract.get_open_actions(0, 10).len(), 2);
+
+        set_caller(accounts(4), 0);
+        contract.confirm_action(freeze);
+        contract.confirm_action(transfer);
+        contract.execute_action(freeze);
+        contract.execute_action(transfer);
+        assert!(contract.is_frozen(food_bank()));
+        assert_eq!(contract.get_balance(other_food_bank()).0, 40);
+        assert!(contract.get_open_actions(0, 10).is_empty());
+    }
+
+    #[test]
+    fn departed_members_confirmations_do_not_count() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::SetRewardRateBps(2_000));
+        set_caller(accounts(3), 0);
+        contract.confirm_action(id);
+
+        let change = propose(
+            &mut contract,
+            AdminAction::SetCouncil {
+                members: vec![accounts(2), accounts(4)],
+                threshold: 2,
+            },
+        );
+        set_caller(accounts(4), 0);
+        contract.confirm_action(change);
+        contract.execute_action(change);
+
+        assert_eq!(contract.get_council(), vec![accounts(2), accounts(4)]);
+        assert_eq!(contract.get_council_action(id).unwrap().confirmations.len(), 2);
+        assert_eq!(contract.valid_confirmations(&contract.get_council_action(id).unwrap()), 1);
+    }
+
+    #[test]
+    fn proposer_can_cancel() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::SetRewardRateBps(2_000));
+        contract.cancel_action(id);
+        assert!(contract.get_open_actions(0, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the proposer can cancel this action")]
+    fn others_cannot_cancel() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::SetRewardRateBps(2_000));
+        set_caller(accounts(3), 0);
+        contract.cancel_action(id);
+    }
+
+    fn pantry_option() -> AdminAction {
+        AdminAction::AddRedemptionOption {
+            option_id: "pantry_box".to_string(),
+            name: "Pantry box".to_string(),
+            cost: U128(50),
+            description: "A week of staples".to_string(),
+            benefit: None,
+        }
+    }
+
+    fn confirm_and_execute(contract: &mut FoodBankToken, id: u64) {
+        set_caller(accounts(3), 0);
+        contract.confirm_action(id);
+        contract.execute_action(id);
+    }
+
+    #[test]
+    fn added_entries_are_paid_from_the_proposers_credit() {
+        let mut contract = with_council();
+        set_caller(accounts(2), ONE_NEAR);
+        let id = contract.propose_action(pantry_option());
+        let credit = contract.get_storage_credit(accounts(2)).0;
+        assert!(credit > 0);
+
+        confirm_and_execute(&mut contract, id);
+        assert!(contract.get_redemption_options().iter().any(|(option_id, _)| option_id == "pantry_box"));
+        let spent = credit - contract.get_storage_credit(accounts(2)).0;
+        assert!(spent > 0);
+        assert_eq!(contract.get_storage_credit(accounts(3)).0, 0);
+
+        set_caller(accounts(2), 0);
+        let id = contract.propose_action(AdminAction::RegisterSupplier {
+            account_id: accounts(5),
+            name: "Harvest Wholesale".to_string(),
+            discount_bps: 1_000,
+        });
+        confirm_and_execute(&mut contract, id);
+        assert!(contract.get_supplier(accounts(5)).is_some());
+        assert!(contract.get_storage_credit(accounts(2)).0 < credit - spent);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposer charlie needs")]
+    fn added_entries_need_the_proposers_credit() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, pantry_option());
+        confirm_and_execute(&mut contract, id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposer charlie needs")]
+    fn the_executors_credit_does_not_pay() {
+        let mut contract = with_council();
+        let id = propose(&mut contract, AdminAction::CreatePromo {
+            code_hash: Base64VecU8(env::sha256(b"SPRING")),
+            option_id: "supplier_discount".to_string(),
+            discount_bps: 1_000,
+            max_uses: 10,
+            expiry_ts: u64::MAX,
+        });
+        set_caller(accounts(3), ONE_NEAR);
+        contract.deposit_storage_credit();
+        contract.confirm_action(id);
+        contract.execute_action(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_archive_contributions() {
+        let mut contract = with_council();
+        contract.archive_contributions(food_bank(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_retry_a_badge_mint() {
+        let mut contract = with_council();
+        contract.retry_badge_mint(food_bank(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_resume_minting() {
+        let mut contract = setup_contract();
+        contract.set_minting_paused(true);
+        contract.set_council(members(), 2);
+        contract.set_minting_paused(false);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_migrate_contributions() {
+        let mut contract = with_council();
+        contract.migrate_contributions(10);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn owner_key_alone_cannot_migrate_redemption_options() {
+        let mut contract = with_council();
+        contract.migrate_redemption_options(10);
+    }
+
+    #[test]
+    fn the_council_resumes_minting() {
+        let mut contract = setup_contract();
+        contract.set_minting_paused(true);
+        contract.set_council(members(), 2);
+        let id = propose(&mut contract, AdminAction::ResumeMinting);
+        confirm_and_execute(&mut contract, id);
+        assert!(!contract.minting_paused);
+    }
+
+    #[test]
+    fn a_council_badge_retry_is_paid_from_the_proposers_credit() {
+        let mut contract = setup_contract();
+        contract.set_badge_config(Some(accounts(5)), vec![2]);
+        contract.badges.insert(&(food_bank(), 2), &BadgeStatus::PendingRetry);
+        contract.set_council(members(), 2);
+        set_caller(accounts(2), ONE_NEAR);
+        let action = AdminAction::RetryBadgeMint {
+            account_id: food_bank(),
+            milestone: 2,
+        };
+        let id = contract.propose_action(action);
+        let credit = contract.get_storage_credit(accounts(2)).0;
+
+        confirm_and_execute(&mut contract, id);
+        assert_eq!(contract.get_badge_status(food_bank(), 2), Some(BadgeStatus::InFlight));
+        assert_eq!(contract.get_storage_credit(accounts(2)).0, credit - BADGE_MINT_DEPOSIT);
+    }
+
+    #[test]
+    #[should_panic(expected = "Threshold must be between 1 and 3")]
+    fn threshold_cannot_exceed_the_council() {
+        let mut contract = setup_contract();
+        contract.set_council(members(), 4);
+    }
+}
@@ -0,0 +1,243 @@
+//! Delegating governance weight to a representative. A delegate votes with
+//! its own snapshot balance plus the snapshot balances of its direct
+//! delegators; chains are not followed, so A → B → C gives C only B's own
+//! weight. Delegators can't vote themselves while they delegate, and a
+//! delegator's weight counts at most once per proposal.
+
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, VoteDelegated, VoteUndelegated};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// Keeps a delegate's vote within gas limits.
+pub const MAX_DELEGATORS: usize = 100;
+/// How far `delegate_to` follows the delegate's own delegation looking for
+/// a cycle.
+const MAX_DELEGATION_DEPTH: usize = 16;
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Replaces any earlier delegation by the caller.
+    pub fn delegate_to(&mut self, delegate: AccountId) {
+        let delegator = env::predecessor_account_id();
+        assert!(delegate != delegator, "Cannot delegate to yourself");
+        let mut next = self.delegations.get(&delegate);
+        for _ in 0..MAX_DELEGATION_DEPTH {
+            match next {
+                Some(account_id) => {
+                    assert!(account_id != delegator, "Circular delegation");
+                    next = self.delegations.get(&account_id);
+                }
+                None => break,
+            }
+        }
+        assert!(next.is_none(), "Delegation chain is too long");
+
+        let mut delegators = self.delegators.get(&delegate).unwrap_or_default();
+        assert!(
+            delegators.len() < MAX_DELEGATORS,
+            "Delegate already has {} delegators",
+            MAX_DELEGATORS
+        );
+        self.remove_delegation(&delegator);
+        delegators.push(delegator.clone());
+        self.delegators.insert(&delegate, &delegators);
+        self.delegations.insert(&delegator, &delegate);
+        VoteDelegated {
+            delegator: &delegator,
+            delegate: &delegate,
+        }
+        .emit();
+    }
+
+    pub fn undelegate(&mut self) {
+        let delegator = env::predecessor_account_id();
+        let delegate = self.remove_delegation(&delegator).expect("Account has not delegated");
+        VoteUndelegated {
+            delegator: &delegator,
+            delegate: &delegate,
+        }
+        .emit();
+    }
+
+    // View functions
+    pub fn get_delegate(&self, account_id: AccountId) -> Option<AccountId> {
+        self.delegations.get(&account_id)
+    }
+
+    pub fn get_delegators(&self, delegate: AccountId, from_index: u64, limit: u64) -> Vec<AccountId> {
+        self.delegators
+            .get(&delegate)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    fn remove_delegation(&mut self, delegator: &AccountId) -> Option<AccountId> {
+        let delegate = self.delegations.remove(delegator)?;
+        let mut delegators = self.delegators.get(&delegate).unwrap_or_default();
+        delegators.retain(|account_id| account_id != delegator);
+        if delegators.is_empty() {
+            self.delegators.remove(&delegate);
+        } else {
+            self.delegators.insert(&delegate, &delegators);
+        }
+        Some(delegate)
+    }
+
+    /// Delegators of `delegate` whose weight on `proposal_id` hasn't been
+    /// cast yet, with their snapshot balances.
+    pub(crate) fn uncast_delegated_weight(
+        &self,
+        proposal_id: u64,
+        delegate: &AccountId,
+    ) -> Vec<(AccountId, Balance)> {
+        self.delegators
+            .get(delegate)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|delegator| !self.weight_cast(proposal_id, delegator))
+            .map(|delegator| {
+                let balance = self.snapshot_balance(proposal_id, &delegator);
+                (delegator, balance)
+            })
+            .collect()
+    }
+
+    /// Whether `account_id`'s weight already counts on `proposal_id`, by its
+    /// own vote or a delegate's.
+    pub(crate) fn weight_cast(&self, proposal_id: u64, account_id: &AccountId) -> bool {
+        let key = (proposal_id, account_id.clone());
+        self.votes.get(&key).is_some() || self.delegated_votes.get(&key).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::governance::ProposalAction;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+
+    fn representative() -> AccountId {
+        accounts(3)
+    }
+
+    fn setup_voters() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        contract.mint(other_food_bank(), 200);
+        contract.mint(representative(), 100);
+        contract
+    }
+
+    fn delegate(contract: &mut FoodBankToken, from: AccountId, to: AccountId) {
+        set_caller(from, 0);
+        contract.delegate_to(to);
+    }
+
+    fn propose(contract: &mut FoodBankToken) -> u64 {
+        set_caller(food_bank(), 0);
+        contract.create_proposal(ProposalAction::SetRewardRateBps(1_200), String::new())
+    }
+
+    fn vote_as(contract: &mut FoodBankToken, voter: AccountId, proposal_id: u64) {
+        set_caller(voter, 0);
+        contract.vote(proposal_id, true);
+    }
+
+    #[test]
+    fn delegates_vote_with_their_delegators_weight() {
+        let mut contract = setup_voters();
+        delegate(&mut contract, food_bank(), representative());
+        delegate(&mut contract, other_food_bank(), representative());
+        let id = propose(&mut contract);
+
+        assert_eq!(contract.get_voting_power(id, representative()).0, 600);
+        assert_eq!(contract.get_voting_power(id, food_bank()).0, 0);
+        vote_as(&mut contract, representative(), id);
+        let proposal = near_sdk::serde_json::to_value(contract.get_proposal(id)).unwrap();
+        assert_eq!(proposal["votes_for"], "600");
+        // Every delegator's weight is now cast.
+        assert_eq!(contract.get_voting_power(id, representative()).0, 100);
+        assert_eq!(
+            contract.get_delegators(representative(), 0, 10),
+            vec![food_bank(), other_food_bank()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Account has delegated its vote to danny")]
+    fn delegators_cannot_vote_directly() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract);
+        delegate(&mut contract, food_bank(), representative());
+        vote_as(&mut contract, food_bank(), id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account's weight was already cast by danny")]
+    fn undelegating_does_not_allow_a_second_vote() {
+        let mut contract = setup_voters();
+        delegate(&mut contract, food_bank(), representative());
+        let id = propose(&mut contract);
+        vote_as(&mut contract, representative(), id);
+        set_caller(food_bank(), 0);
+        contract.undelegate();
+        vote_as(&mut contract, food_bank(), id);
+    }
+
+    #[test]
+    fn delegators_who_voted_are_not_counted_again() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract);
+        vote_as(&mut contract, food_bank(), id);
+        delegate(&mut contract, food_bank(), representative());
+        assert_eq!(contract.get_voting_power(id, representative()).0, 100);
+    }
+
+    #[test]
+    fn chains_are_not_followed() {
+        let mut contract = setup_voters();
+        delegate(&mut contract, food_bank(), other_food_bank());
+        delegate(&mut contract, other_food_bank(), representative());
+        let id = propose(&mut contract);
+        assert_eq!(contract.get_voting_power(id, representative()).0, 300);
+        assert_eq!(contract.get_delegate(other_food_bank()), Some(representative()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Circular delegation")]
+    fn circular_delegation_is_rejected() {
+        let mut contract = setup_voters();
+        delegate(&mut contract, food_bank(), other_food_bank());
+        delegate(&mut contract, other_food_bank(), representative());
+        delegate(&mut contract, representative(), food_bank());
+    }
+
+    #[test]
+    fn redelegating_moves_the_weight() {
+        let mut contract = setup_voters();
+        delegate(&mut contract, food_bank(), representative());
+        delegate(&mut contract, food_bank(), other_food_bank());
+        assert!(contract.get_delegators(representative(), 0, 10).is_empty());
+        let id = propose(&mut contract);
+        assert_eq!(contract.get_voting_power(id, other_food_bank()).0, 500);
+
+        set_caller(food_bank(), 0);
+        contract.undelegate();
+        assert_eq!(contract.get_delegate(food_bank()), None);
+        assert_eq!(contract.get_voting_power(id, food_bank()).0, 300);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot delegate to yourself")]
+    fn self_delegation_is_rejected() {
+        let mut contract = setup_voters();
+        delegate(&mut contract, food_bank(), food_bank());
+    }
+}
@@ -0,0 +1,275 @@
+//! Disputes over the scores the pipeline assigned. The account a
+//! contribution belongs to can dispute it once, within the dispute window
+//! after it was recorded; an admin then upholds it, rescores it or voids it.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::contributions::ContributionMetricsV2;
+use crate::events::{ContributionDisputed, DisputeResolved, Event};
+use crate::roles::Role;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_DISPUTE_WINDOW: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+const MAX_DISPUTE_REASON_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Resolution {
+    Uphold,
+    /// Replaces the contribution's metrics and mints or burns the change in
+    /// reward.
+    AdjustScore { new_metrics: ContributionMetricsV2 },
+    /// Claws the reward back as `void_contribution` does.
+    Void,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DisputeStatus {
+    Open,
+    Upheld,
+    Adjusted,
+    Voided,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Dispute {
+    pub account_id: AccountId,
+    pub contribution_id: u64,
+    pub reason: String,
+    pub opened_at: u64,
+    pub status: DisputeStatus,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Disputes the caller's contribution with index `contribution_id`.
+    pub fn dispute_contribution(&mut self, contribution_id: u64, reason: String) {
+        self.assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        assert!(
+            reason.len() <= MAX_DISPUTE_REASON_LENGTH,
+            "Reason exceeds {} bytes",
+            MAX_DISPUTE_REASON_LENGTH
+        );
+        let key = (account_id.clone(), contribution_id);
+        assert!(self.has_reward_breakdown(&key), "Contribution not found");
+        assert!(
+            self.voided_contributions.get(&key).is_none(),
+            "Contribution is already voided"
+        );
+        assert!(self.disputes.get(&key).is_none(), "Contribution has already been disputed");
+        // Contributions recorded before disputes existed have no timestamp
+        // and are treated as outside the window.
+        let now = env::block_timestamp();
+        let recorded_at = self.contribution_recorded_at.get(&key);
+        assert!(
+            recorded_at.is_some_and(|recorded_at| now < recorded_at + self.dispute_window),
+            "The dispute window for this contribution has closed"
+        );
+
+        ContributionDisputed {
+            account_id: &account_id,
+            contribution_id,
+            reason: &reason,
+        }
+        .emit();
+        self.disputes.insert(
+            &key,
+            &Dispute {
+                account_id,
+                contribution_id,
+                reason,
+                opened_at: now,
+                status: DisputeStatus::Open,
+            },
+        );
+        self.open_disputes.insert(&key);
+    }
+
+    pub fn resolve_dispute(
+        &mut self,
+        account_id: AccountId,
+        contribution_id: u64,
+        resolution: Resolution,
+    ) {
+        self.assert_role(Role::Admin);
+        let key = (account_id.clone(), contribution_id);
+        let mut dispute = self.disputes.get(&key).expect("Dispute not found");
+        assert_eq!(dispute.status, DisputeStatus::Open, "Dispute is already resolved");
+
+        dispute.status = match &resolution {
+            Resolution::Uphold => DisputeStatus::Upheld,
+            Resolution::AdjustScore { new_metrics } => {
+                self.assert_valid_metrics(new_metrics);
+                let breakdown = self.reward_breakdown(&key).unwrap();
+                assert!(
+                    breakdown.epoch_id.is_none(),
+                    "Contributions scored towards an epoch cannot be rescored"
+                );
+                let live_index = contribution_id
+                    .checked_sub(self.archived_contribution_count(&account_id))
+                    .expect("Contribution is archived");
+                let mut history = self.stored_contributions(&account_id);
+                history[live_index as usize] = new_metrics.clone().into();
+                self.store_contributions(&account_id, &history);
+                self.rescore_contribution(&account_id, contribution_id, new_metrics.score(), true);
+                DisputeStatus::Adjusted
+            }
+            Resolution::Void => {
+                self.internal_void_contribution(&account_id, contribution_id);
+                DisputeStatus::Voided
+            }
+        };
+        self.disputes.insert(&key, &dispute);
+        self.open_disputes.remove(&key);
+        DisputeResolved {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+            contribution_id,
+            reason: &dispute.reason,
+            resolution: &resolution,
+        }
+        .emit();
+    }
+
+    pub fn set_dispute_window(&mut self, window_ns: u64) {
+        self.assert_owner_action();
+        self.dispute_window = window_ns;
+    }
+
+    // View functions
+    pub fn get_dispute(&self, account_id: AccountId, contribution_id: u64) -> Option<Dispute> {
+        self.disputes.get(&(account_id, contribution_id))
+    }
+
+    /// Unresolved disputes, for the admin queue.
+    pub fn get_open_disputes(&self, from_index: u64, limit: u64) -> Vec<Dispute> {
+        self.open_disputes
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|key| self.disputes.get(&key).unwrap())
+            .collect()
+    }
+
+    pub fn get_dispute_window(&self) -> u64 {
+        self.dispute_window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn dispute(contract: &mut FoodBankToken, contribution_id: u64) {
+        set_caller(food_bank(), 0);
+        contract.dispute_contribution(contribution_id, "Quality was scored too low".to_string());
+    }
+
+    fn resolve(contract: &mut FoodBankToken, resolution: Resolution) {
+        set_caller(owner(), 0);
+        contract.resolve_dispute(food_bank(), 0, resolution);
+    }
+
+    #[test]
+    fn disputes_queue_until_resolved() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 60);
+        contribute(&mut contract, &food_bank(), 80);
+        dispute(&mut contract, 0);
+        dispute(&mut contract, 1);
+
+        let open = contract.get_open_disputes(0, 10);
+        assert_eq!(open.len(), 2);
+        assert_eq!(open[0].reason, "Quality was scored too low");
+        assert_eq!(open[0].status, DisputeStatus::Open);
+
+        resolve(&mut contract, Resolution::Uphold);
+        assert_eq!(contract.get_open_disputes(0, 10).len(), 1);
+        assert_eq!(contract.get_dispute(food_bank(), 0).unwrap().status, DisputeStatus::Upheld);
+        assert_eq!(contract.get_balance(food_bank()).0, 7);
+    }
+
+    #[test]
+    fn adjusting_the_score_mints_the_difference() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 60);
+        dispute(&mut contract, 0);
+        resolve(
+            &mut contract,
+            Resolution::AdjustScore {
+                new_metrics: metrics(90, 90, 90),
+            },
+        );
+
+        assert_eq!(contract.get_balance(food_bank()).0, 4);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 4);
+        assert_eq!(contract.get_contributions(food_bank())[0].data_quality, 90);
+    }
+
+    #[test]
+    fn adjusting_the_score_down_burns_the_difference() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 90);
+        dispute(&mut contract, 0);
+        resolve(
+            &mut contract,
+            Resolution::AdjustScore {
+                new_metrics: metrics(20, 20, 20),
+            },
+        );
+        assert_eq!(contract.get_balance(food_bank()).0, 1);
+    }
+
+    #[test]
+    fn voiding_claws_the_reward_back() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 90);
+        dispute(&mut contract, 0);
+        resolve(&mut contract, Resolution::Void);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert!(contract.is_contribution_voided(food_bank(), 0));
+        assert_eq!(contract.get_dispute(food_bank(), 0).unwrap().status, DisputeStatus::Voided);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution has already been disputed")]
+    fn contributions_can_be_disputed_once() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 60);
+        dispute(&mut contract, 0);
+        resolve(&mut contract, Resolution::Uphold);
+        dispute(&mut contract, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The dispute window for this contribution has closed")]
+    fn old_contributions_cannot_be_disputed() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 60);
+        advance_time(DEFAULT_DISPUTE_WINDOW);
+        dispute(&mut contract, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution not found")]
+    fn only_the_contributor_can_dispute() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &other_food_bank(), 60);
+        dispute(&mut contract, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Admin role")]
+    fn resolution_needs_the_admin_role() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 60);
+        dispute(&mut contract, 0);
+        contract.resolve_dispute(food_bank(), 0, Resolution::Void);
+    }
+}
@@ -0,0 +1,311 @@
+//! One-off bonus distributions computed off-chain and claimed with Merkle
+//! proofs.
+//!
+//! A leaf is `sha256(account_id || amount)`, with the amount as 16
+//! little-endian bytes. Inner nodes hash the two children in ascending byte
+//! order, so proofs carry no left/right flags, and an odd node at the end of
+//! a level moves up unchanged. `merkle::build_tree` builds the matching tree
+//! off-chain.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{DistributionClaimed, DistributionClosed, DistributionPublished, Event};
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Distribution {
+    pub id: u64,
+    pub root: Base64VecU8,
+    pub total: U128,
+    pub claimed: U128,
+    /// Claims are accepted strictly before this timestamp.
+    pub expiry: u64,
+    pub closed: bool,
+}
+
+pub fn leaf_hash(account_id: &AccountId, amount: Balance) -> Vec<u8> {
+    let mut bytes = account_id.as_bytes().to_vec();
+    bytes.extend_from_slice(&amount.to_le_bytes());
+    env::sha256(&bytes)
+}
+
+fn hash_pair(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    env::sha256(&[first, second].concat())
+}
+
+fn compute_root(leaf: Vec<u8>, proof: &[Base64VecU8]) -> Vec<u8> {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(&node, &sibling.0))
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn publish_distribution(&mut self, root: Base64VecU8, total: U128, expiry_ts: u64) -> u64 {
+        self.assert_owner_action();
+        assert_eq!(root.0.len(), 32, "Merkle root must be 32 bytes");
+        assert!(expiry_ts > env::block_timestamp(), "Expiry must be in the future");
+        let id = self.next_distribution_id;
+        self.next_distribution_id += 1;
+        DistributionPublished {
+            id,
+            total,
+            expiry: expiry_ts,
+        }
+        .emit();
+        self.distributions.insert(
+            &id,
+            &Distribution {
+                id,
+                root,
+                total,
+                claimed: U128(0),
+                expiry: expiry_ts,
+                closed: false,
+            },
+        );
+        id
+    }
+
+    /// Mints `amount` to the caller if `proof` shows the caller's leaf is in
+    /// the distribution. Each leaf can be claimed once.
+    pub fn claim(&mut self, distribution_id: u64, amount: U128, proof: Vec<Base64VecU8>) {
+        self.assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        self.assert_not_frozen(&account_id);
+        let mut distribution = self.distributions.get(&distribution_id)
+            .expect("Distribution not found");
+        assert!(!distribution.closed, "Distribution is closed");
+        assert!(
+            env::block_timestamp() < distribution.expiry,
+            "Distribution expired at {}",
+            distribution.expiry
+        );
+
+        let leaf = leaf_hash(&account_id, amount.0);
+        let key = (distribution_id, leaf.clone());
+        assert!(!self.claimed_leaves.contains(&key), "Already claimed");
+        assert!(
+            compute_root(leaf, &proof) == distribution.root.0,
+            "Invalid Merkle proof"
+        );
+        let claimed = distribution.claimed.0 + amount.0;
+        assert!(claimed <= distribution.total.0, "Distribution budget exceeded");
+
+        self.claimed_leaves.insert(&key);
+        distribution.claimed = U128(claimed);
+        self.distributions.insert(&distribution_id, &distribution);
+        self.mint(account_id.clone(), amount.0);
+        self.tag_statement_flow(&account_id, StatementFlow::Reward, amount.0);
+        let stats = &mut self.program_stats;
+        stats.total_rewards_minted = U128(stats.total_rewards_minted.0 + amount.0);
+        self.record_daily_mint(amount.0);
+        DistributionClaimed {
+            id: distribution_id,
+            account_id: &account_id,
+            amount,
+        }
+        .emit();
+    }
+
+    /// Closes an expired distribution. Its unclaimed budget is returned to
+    /// the treasury's reclaimed counter and returned by this call.
+    pub fn close_distribution(&mut self, distribution_id: u64) -> U128 {
+        self.assert_owner_action();
+        let mut distribution = self.distributions.get(&distribution_id)
+            .expect("Distribution not found");
+        assert!(!distribution.closed, "Distribution is already closed");
+        assert!(
+            env::block_timestamp() >= distribution.expiry,
+            "Distribution is open until {}",
+            distribution.expiry
+        );
+        let unclaimed = distribution.total.0 - distribution.claimed.0;
+        distribution.closed = true;
+        self.distributions.insert(&distribution_id, &distribution);
+        self.treasury_reclaimed += unclaimed;
+        DistributionClosed {
+            id: distribution_id,
+            unclaimed: U128(unclaimed),
+        }
+        .emit();
+        U128(unclaimed)
+    }
+
+    // View functions
+    pub fn get_distribution(&self, distribution_id: u64) -> Option<Distribution> {
+        self.distributions.get(&distribution_id)
+    }
+
+    pub fn is_claimed(&self, distribution_id: u64, account_id: AccountId, amount: U128) -> bool {
+        self.claimed_leaves
+            .contains(&(distribution_id, leaf_hash(&account_id, amount.0)))
+    }
+
+    /// Budget of closed distributions that was never claimed.
+    pub fn get_treasury_reclaimed(&self) -> U128 {
+        U128(self.treasury_reclaimed)
+    }
+}
+
+/// Off-chain tree building. Hashes go through `env::sha256`, which outside
+/// the contract runs against near-sdk's mocked environment.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod merkle {
+    use super::*;
+
+    /// Every level of the tree, leaves first and the root last.
+    pub fn build_tree(leaves: &[(AccountId, Balance)]) -> Vec<Vec<Vec<u8>>> {
+        assert!(!leaves.is_empty(), "A tree needs at least one leaf");
+        let mut levels = vec![leaves
+            .iter()
+            .map(|(account_id, amount)| leaf_hash(account_id, *amount))
+            .collect::<Vec<_>>()];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => a.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    pub fn root(levels: &[Vec<Vec<u8>>]) -> Base64VecU8 {
+        Base64VecU8(levels.last().unwrap()[0].clone())
+    }
+
+    /// Proof for the leaf at `index`.
+    pub fn proof(levels: &[Vec<Vec<u8>>], mut index: usize) -> Vec<Base64VecU8> {
+        let mut proof = Vec::new();
+        for level in &levels[..levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(Base64VecU8(sibling.clone()));
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merkle::*;
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+
+    const EXPIRY: u64 = 1_000;
+
+    fn leaves() -> Vec<(AccountId, Balance)> {
+        vec![
+            (accounts(1), 100),
+            (accounts(2), 250),
+            (accounts(3), 75),
+            (accounts(4), 5),
+            (accounts(0), 20),
+        ]
+    }
+
+    fn published() -> (FoodBankToken, Vec<Vec<Vec<u8>>>) {
+        let mut contract = setup_contract();
+        let levels = build_tree(&leaves());
+        contract.publish_distribution(root(&levels), U128(450), EXPIRY);
+        (contract, levels)
+    }
+
+    fn claim(contract: &mut FoodBankToken, levels: &[Vec<Vec<u8>>], index: usize) {
+        let (account_id, amount) = leaves()[index].clone();
+        set_caller(account_id, 0);
+        contract.claim(0, U128(amount), proof(levels, index));
+    }
+
+    #[test]
+    fn every_leaf_verifies_against_the_root() {
+        let levels = build_tree(&leaves());
+        let expected = root(&levels).0;
+        for (index, (account_id, amount)) in leaves().into_iter().enumerate() {
+            let computed = compute_root(leaf_hash(&account_id, amount), &proof(&levels, index));
+            assert_eq!(computed, expected);
+        }
+    }
+
+    #[test]
+    fn single_leaf_tree_is_its_own_root() {
+        let levels = build_tree(&leaves()[..1]);
+        assert_eq!(root(&levels).0, leaf_hash(&accounts(1), 100));
+        assert!(proof(&levels, 0).is_empty());
+    }
+
+    #[test]
+    fn valid_proofs_mint_once() {
+        let (mut contract, levels) = published();
+        claim(&mut contract, &levels, 1);
+        claim(&mut contract, &levels, 4);
+        assert_eq!(contract.get_balance(accounts(2)).0, 250);
+        assert!(contract.is_claimed(0, accounts(2), U128(250)));
+        assert_eq!(contract.get_distribution(0).unwrap().claimed.0, 270);
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed")]
+    fn leaves_cannot_be_claimed_twice() {
+        let (mut contract, levels) = published();
+        claim(&mut contract, &levels, 1);
+        claim(&mut contract, &levels, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Merkle proof")]
+    fn wrong_amount_fails_verification() {
+        let (mut contract, levels) = published();
+        set_caller(accounts(1), 0);
+        contract.claim(0, U128(1_000), proof(&levels, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Distribution budget exceeded")]
+    fn claims_are_limited_to_the_published_total() {
+        let mut contract = setup_contract();
+        let levels = build_tree(&leaves());
+        contract.publish_distribution(root(&levels), U128(300), EXPIRY);
+        claim(&mut contract, &levels, 1);
+        claim(&mut contract, &levels, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Distribution expired at 1000")]
+    fn expired_distributions_reject_claims() {
+        let (mut contract, levels) = published();
+        advance_time(EXPIRY);
+        claim(&mut contract, &levels, 0);
+    }
+
+    #[test]
+    fn closing_returns_the_unclaimed_budget() {
+        let (mut contract, levels) = published();
+        claim(&mut contract, &levels, 0);
+        advance_time(EXPIRY);
+        set_caller(owner(), 0);
+        assert_eq!(contract.close_distribution(0).0, 350);
+        assert_eq!(contract.get_treasury_reclaimed().0, 350);
+        assert!(contract.get_distribution(0).unwrap().closed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Distribution is open until 1000")]
+    fn open_distributions_cannot_be_closed() {
+        let (mut contract, _) = published();
+        contract.close_distribution(0);
+    }
+}
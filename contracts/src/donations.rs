@@ -0,0 +1,210 @@
+//! Sponsor donations in allowlisted NEP-141 tokens, received through
+//! `ft_on_transfer`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, serde_json, AccountId, Balance, PromiseOrValue};
+
+use crate::events::{DonationReceived, Event};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// `per` units of the donated token convert into `tokens` reward tokens.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenRate {
+    pub tokens: U128,
+    pub per: U128,
+}
+
+impl TokenRate {
+    /// Reward tokens for `amount`, rounded down, and the part of `amount`
+    /// actually needed to pay for them.
+    fn convert(&self, amount: Balance) -> (Balance, Balance) {
+        let minted = amount * self.tokens.0 / self.per.0;
+        let used = (minted * self.per.0).div_ceil(self.tokens.0);
+        (minted, used)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde", tag = "action", rename_all = "snake_case")]
+enum DonationMsg {
+    FundMatchingPool,
+    DonateTo { account: AccountId },
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn add_accepted_token(&mut self, token_id: AccountId, rate: TokenRate) {
+        self.assert_owner_action();
+        assert!(rate.tokens.0 > 0 && rate.per.0 > 0, "Rate must be positive");
+        self.accepted_tokens.insert(&token_id, &rate);
+    }
+
+    pub fn remove_accepted_token(&mut self, token_id: AccountId) {
+        self.assert_owner_action();
+        self.accepted_tokens.remove(&token_id).expect("Token is not accepted");
+    }
+
+    /// NEP-141 receiver. `msg` is `{"action": "fund_matching_pool"}` or
+    /// `{"action": "donate_to", "account": "<food bank>"}`. Anything that
+    /// can't be honoured is handed back as unused so the sender is refunded.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        let refund_all = PromiseOrValue::Value(amount);
+        if self.paused {
+            return refund_all;
+        }
+        let Some(rate) = self.accepted_tokens.get(&token_id) else {
+            return refund_all;
+        };
+        let Ok(donation) = serde_json::from_str::<DonationMsg>(&msg) else {
+            return refund_all;
+        };
+        let (minted, used) = rate.convert(amount.0);
+        if minted == 0 {
+            return refund_all;
+        }
+
+        let beneficiary = match donation {
+            DonationMsg::FundMatchingPool => {
+                self.credit_matching_pool(&sender_id, minted);
+                None
+            }
+            DonationMsg::DonateTo { account } => {
+                if self.frozen_accounts.get(&account).is_some() {
+                    return refund_all;
+                }
+                self.mint(account.clone(), minted);
+                Some(account)
+            }
+        };
+        DonationReceived {
+            token_id: &token_id,
+            sender_id: &sender_id,
+            amount: U128(used),
+            tokens: U128(minted),
+            beneficiary: beneficiary.as_ref(),
+        }
+        .emit();
+        PromiseOrValue::Value(U128(amount.0 - used))
+    }
+
+    // View functions
+    pub fn get_accepted_tokens(&self) -> Vec<(AccountId, TokenRate)> {
+        self.accepted_tokens.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+
+    fn usdc() -> AccountId {
+        accounts(4)
+    }
+
+    fn sponsor() -> AccountId {
+        accounts(3)
+    }
+
+    /// One reward token per whole unit of a 6-decimal token.
+    fn setup_donations() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.add_accepted_token(
+            usdc(),
+            TokenRate {
+                tokens: U128(1),
+                per: U128(1_000_000),
+            },
+        );
+        contract
+    }
+
+    fn transfer(
+        contract: &mut FoodBankToken,
+        token_id: AccountId,
+        amount: Balance,
+        msg: &str,
+    ) -> Balance {
+        set_caller(token_id, 0);
+        match contract.ft_on_transfer(sponsor(), U128(amount), msg.to_string()) {
+            PromiseOrValue::Value(unused) => unused.0,
+            PromiseOrValue::Promise(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn donation_mints_to_the_named_food_bank() {
+        let mut contract = setup_donations();
+        let msg = r#"{"action": "donate_to", "account": "bob"}"#;
+        assert_eq!(transfer(&mut contract, usdc(), 2_500_000, msg), 500_000);
+        assert_eq!(contract.get_balance(food_bank()).0, 2);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 2);
+    }
+
+    #[test]
+    fn donation_can_fund_the_matching_pool() {
+        let mut contract = setup_donations();
+        let msg = r#"{"action": "fund_matching_pool"}"#;
+        assert_eq!(transfer(&mut contract, usdc(), 3_000_000, msg), 0);
+        let pool = contract.get_matching_pool().unwrap();
+        assert_eq!(pool.remaining.0, 3);
+        assert_eq!(pool.sponsored.0, 3);
+        assert_eq!(pool.donor, sponsor());
+    }
+
+    #[test]
+    fn unknown_tokens_are_refunded() {
+        let mut contract = setup_donations();
+        let msg = r#"{"action": "fund_matching_pool"}"#;
+        assert_eq!(transfer(&mut contract, accounts(5), 3_000_000, msg), 3_000_000);
+        assert!(contract.get_matching_pool().is_none());
+    }
+
+    #[test]
+    fn malformed_messages_are_refunded() {
+        let mut contract = setup_donations();
+        for msg in ["", "{}", r#"{"action": "donate_to"}"#, r#"{"action": "burn"}"#] {
+            assert_eq!(transfer(&mut contract, usdc(), 3_000_000, msg), 3_000_000);
+        }
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY);
+    }
+
+    #[test]
+    fn amounts_below_one_token_are_refunded() {
+        let mut contract = setup_donations();
+        let msg = r#"{"action": "donate_to", "account": "bob"}"#;
+        assert_eq!(transfer(&mut contract, usdc(), 999_999, msg), 999_999);
+    }
+
+    #[test]
+    fn removed_tokens_are_refunded() {
+        let mut contract = setup_donations();
+        contract.remove_accepted_token(usdc());
+        let msg = r#"{"action": "fund_matching_pool"}"#;
+        assert_eq!(transfer(&mut contract, usdc(), 3_000_000, msg), 3_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn allowlist_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.add_accepted_token(
+            usdc(),
+            TokenRate {
+                tokens: U128(1),
+                per: U128(1),
+            },
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Emission schedule that lowers the reward rate over time without anyone
+//! having to remember to change it. Like the rate itself, it is replaced
+//! through a timelocked `ParamChange::EmissionSchedule`.
+//!
+//! Each entry is `(start_timestamp, rate_bps)` and stays in effect until the
+//! next one starts; an empty schedule falls back to `reward_rate_bps`.
+
+use near_sdk::{env, near_bindgen};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub(crate) const MAX_RATE_BPS: u16 = 10_000;
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    pub fn get_emission_schedule(&self) -> Vec<(u64, u16)> {
+        self.emission_schedule.clone()
+    }
+
+    /// Reward rate in basis points for a contribution recorded now.
+    pub fn get_current_rate(&self) -> u16 {
+        self.rate_bps_at(env::block_timestamp())
+    }
+}
+
+impl FoodBankToken {
+    /// Rate of the last schedule entry started at `timestamp`, or the static
+    /// `reward_rate_bps` before the schedule begins.
+    pub(crate) fn rate_bps_at(&self, timestamp: u64) -> u16 {
+        self.emission_schedule
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= timestamp)
+            .map(|(_, rate_bps)| *rate_bps)
+            .unwrap_or(self.reward_rate_bps)
+    }
+}
+
+pub(crate) fn assert_valid_schedule(schedule: &[(u64, u16)]) {
+    for window in schedule.windows(2) {
+        assert!(
+            window[0].0 < window[1].0,
+            "Emission schedule must be sorted by start time without duplicates"
+        );
+    }
+    for (_, rate_bps) in schedule {
+        assert!(*rate_bps <= MAX_RATE_BPS, "Rate must be <= {} bps", MAX_RATE_BPS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+    use crate::timelock::{ParamChange, DEFAULT_TIMELOCK_DELAY};
+
+    const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    #[test]
+    fn rate_steps_down_across_boundaries() {
+        let mut contract = setup_contract();
+        advance_time(DAY);
+        set_emission_schedule(&mut contract, vec![(2 * DAY, 2_000), (4 * DAY, 1_000)]);
+
+        // Before the schedule starts the static 5% rate applies.
+        assert_eq!(contract.get_current_rate(), 500);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+
+        advance_time(DAY);
+        assert_eq!(contract.get_current_rate(), 2_000);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 25);
+
+        advance_time(2 * DAY - 1);
+        assert_eq!(contract.get_current_rate(), 2_000);
+        advance_time(1);
+        assert_eq!(contract.get_current_rate(), 1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 35);
+    }
+
+    #[test]
+    fn applied_rate_is_recorded_with_the_contribution() {
+        let mut contract = setup_contract();
+        set_emission_schedule(&mut contract, vec![(0, 1_500)]);
+        contribute(&mut contract, &food_bank(), 80);
+
+        let breakdown = contract.get_reward_breakdown(food_bank(), 0).unwrap();
+        assert_eq!(breakdown.score, 80);
+        assert_eq!(breakdown.rate_bps, 1_500);
+        assert_eq!(breakdown.reward.0, 12);
+    }
+
+    #[test]
+    fn clearing_the_schedule_restores_the_static_rate() {
+        let mut contract = setup_contract();
+        set_emission_schedule(&mut contract, vec![(0, 1_500)]);
+        set_emission_schedule(&mut contract, vec![]);
+        assert_eq!(contract.get_current_rate(), 500);
+    }
+
+    #[test]
+    fn a_proposed_schedule_waits_for_the_timelock() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(ParamChange::EmissionSchedule(vec![(0, 1_500)]));
+        assert_eq!(contract.get_current_rate(), 500);
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_emission_schedule(), vec![(0, 1_500)]);
+        assert_eq!(contract.get_current_rate(), 1_500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Emission schedule must be sorted by start time without duplicates")]
+    fn unsorted_schedule_is_rejected() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::EmissionSchedule(vec![(2 * DAY, 2_000), (DAY, 1_000)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Emission schedule must be sorted by start time without duplicates")]
+    fn overlapping_entries_are_rejected() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::EmissionSchedule(vec![(DAY, 2_000), (DAY, 1_000)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Rate must be <= 10000 bps")]
+    fn rate_is_bounded() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::EmissionSchedule(vec![(DAY, 10_001)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn schedule_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.propose_param_change(ParamChange::EmissionSchedule(vec![]));
+    }
+}
@@ -0,0 +1,187 @@
+//! Time-boxed access that redeeming an option grants, such as the
+//! analytics dashboard. Services poll `has_entitlement` instead of keeping
+//! their own record of who paid. Each redemption extends the expiry by the
+//! option's duration, counted from the current expiry while it is still in
+//! the future.
+
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{EntitlementExtended, Event};
+use crate::roles::Role;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const MAX_ENTITLEMENT_KEY_LENGTH: usize = 64;
+pub const ANALYTICS_ACCESS_DURATION: u64 = 90 * 24 * 60 * 60 * 1_000_000_000;
+
+/// What the built-in `analytics_access` option grants.
+pub(crate) fn default_analytics_entitlement() -> (String, u64) {
+    ("analytics".to_string(), ANALYTICS_ACCESS_DURATION)
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// `grant` is the entitlement key and the nanoseconds each redemption
+    /// adds; `None` stops the option granting anything.
+    pub fn set_option_entitlement(&mut self, option_id: String, grant: Option<(String, u64)>) {
+        self.assert_role(Role::Admin);
+        if let Some((key, duration)) = &grant {
+            assert!(
+                !key.is_empty() && key.len() <= MAX_ENTITLEMENT_KEY_LENGTH,
+                "Entitlement key must be 1 to {} bytes",
+                MAX_ENTITLEMENT_KEY_LENGTH
+            );
+            assert!(*duration > 0, "Entitlement duration must be positive");
+        }
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        let mut option = old_option.clone();
+        option.grants_entitlement = grant;
+        self.set_redemption_option(&option_id, Some(old_option), option);
+    }
+
+    // View functions
+    /// Access lasts up to, but not including, the expiry.
+    pub fn has_entitlement(&self, account_id: AccountId, key: String) -> bool {
+        self.get_entitlement_expiry(account_id, key)
+            .is_some_and(|expiry| env::block_timestamp() < expiry)
+    }
+
+    /// Kept after it passes, so services can tell lapsed access from none.
+    pub fn get_entitlement_expiry(&self, account_id: AccountId, key: String) -> Option<u64> {
+        self.entitlements.get(&(account_id, key))
+    }
+}
+
+impl FoodBankToken {
+    /// Extends whatever `option_id` grants after a successful redemption.
+    pub(crate) fn grant_entitlement(&mut self, account_id: &AccountId, option_id: &String) {
+        let Some((key, duration)) = self
+            .redemption_option(option_id)
+            .and_then(|option| option.grants_entitlement)
+        else {
+            return;
+        };
+        let entry = (account_id.clone(), key);
+        let from = self
+            .entitlements
+            .get(&entry)
+            .map_or(env::block_timestamp(), |expiry| expiry.max(env::block_timestamp()));
+        let expires_at = from + duration;
+        self.entitlements.insert(&entry, &expires_at);
+        EntitlementExtended {
+            account_id,
+            key: &entry.1,
+            option_id,
+            expires_at,
+        }
+        .emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fulfillment::RedemptionCharge;
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::PromiseResult;
+
+    fn redeem_analytics(contract: &mut FoodBankToken) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("analytics_access".to_string(), U128(200), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let receipt_id = last_receipt_id(contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "analytics_access".to_string(),
+            receipt_id,
+            RedemptionCharge {
+                list_price: U128(200),
+                ..token_charge(200)
+            },
+        );
+    }
+
+    fn expiry(contract: &FoodBankToken) -> Option<u64> {
+        contract.get_entitlement_expiry(food_bank(), "analytics".to_string())
+    }
+
+    #[test]
+    fn analytics_access_grants_ninety_days() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 400);
+        assert!(!contract.has_entitlement(food_bank(), "analytics".to_string()));
+        redeem_analytics(&mut contract);
+        assert_eq!(expiry(&contract), Some(env::block_timestamp() + ANALYTICS_ACCESS_DURATION));
+        assert!(contract.has_entitlement(food_bank(), "analytics".to_string()));
+    }
+
+    #[test]
+    fn redemptions_stack_from_the_current_expiry() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 400);
+        let start = env::block_timestamp();
+        redeem_analytics(&mut contract);
+        advance_time(ANALYTICS_ACCESS_DURATION / 2);
+        redeem_analytics(&mut contract);
+        assert_eq!(expiry(&contract), Some(start + 2 * ANALYTICS_ACCESS_DURATION));
+    }
+
+    #[test]
+    fn lapsed_access_restarts_from_now() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 400);
+        redeem_analytics(&mut contract);
+        advance_time(2 * ANALYTICS_ACCESS_DURATION);
+        redeem_analytics(&mut contract);
+        assert_eq!(expiry(&contract), Some(env::block_timestamp() + ANALYTICS_ACCESS_DURATION));
+    }
+
+    #[test]
+    fn access_ends_exactly_at_the_expiry() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 400);
+        redeem_analytics(&mut contract);
+        advance_time(ANALYTICS_ACCESS_DURATION - 1);
+        assert!(contract.has_entitlement(food_bank(), "analytics".to_string()));
+        advance_time(1);
+        assert!(!contract.has_entitlement(food_bank(), "analytics".to_string()));
+        assert!(expiry(&contract).is_some());
+    }
+
+    #[test]
+    fn failed_benefits_grant_nothing() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 400);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("analytics_access".to_string(), U128(200), None);
+        resolve_callback(PromiseResult::Failed);
+        let receipt_id = last_receipt_id(&contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "analytics_access".to_string(),
+            receipt_id,
+            RedemptionCharge {
+                list_price: U128(200),
+                ..token_charge(200)
+            },
+        );
+        assert_eq!(expiry(&contract), None);
+    }
+
+    #[test]
+    fn options_without_a_grant_redeem_as_before() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 400);
+        contract.set_option_entitlement("analytics_access".to_string(), None);
+        redeem_analytics(&mut contract);
+        assert_eq!(contract.get_balance(food_bank()).0, 200);
+        assert_eq!(expiry(&contract), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Entitlement duration must be positive")]
+    fn grants_need_a_duration() {
+        let mut contract = setup_contract();
+        contract.set_option_entitlement("supplier_discount".to_string(), Some(("supplier".to_string(), 0)));
+    }
+}
@@ -0,0 +1,325 @@
+//! Epoch reward mode: instead of minting per contribution, a fixed pool per
+//! epoch is split across accounts in proportion to their weighted scores.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{EpochSettled, Event, RewardModeChanged};
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt, RewardBreakdown};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RewardMode {
+    Instant,
+    Epoch { pool: U128, epoch_length_ns: u64 },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Epoch {
+    pub id: u64,
+    pub start: u64,
+    /// Exclusive.
+    pub end: u64,
+    pub pool: U128,
+    pub total_score: u64,
+    pub participant_count: u64,
+    pub settled_count: u64,
+    pub distributed: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EpochScore {
+    pub score: u64,
+    /// Set once the account's share has been minted.
+    pub paid: Option<U128>,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Switches how contributions are rewarded. Epochs already under way
+    /// keep their pool and end time and can still be settled; entering
+    /// epoch mode starts epoch numbering after the last epoch used.
+    pub fn set_reward_mode(&mut self, mode: RewardMode) {
+        self.assert_owner_action();
+        if let RewardMode::Epoch { epoch_length_ns, .. } = mode {
+            assert!(epoch_length_ns > 0, "Epoch length must be positive");
+        }
+        if let Some(current) = self.current_epoch_id() {
+            self.epoch_id_offset = current + 1;
+        }
+        self.epoch_origin = env::block_timestamp();
+        RewardModeChanged { mode: &mode }.emit();
+        self.reward_mode = mode;
+    }
+
+    /// Mints the shares of up to `limit` participants of an ended epoch,
+    /// starting at `from_index`. Anyone may call it; participants that were
+    /// already paid are skipped. Returns how many were paid by this call.
+    pub fn settle_epoch(&mut self, epoch_id: u64, from_index: u64, limit: u64) -> u64 {
+        self.assert_not_paused();
+        let mut epoch = self.epochs.get(&epoch_id).expect("Epoch not found");
+        assert!(
+            env::block_timestamp() >= epoch.end,
+            "Epoch {} ends at {}",
+            epoch_id,
+            epoch.end
+        );
+        let end = epoch.participant_count.min(from_index.saturating_add(limit));
+        let mut paid_count = 0;
+        let mut minted = 0;
+        for index in from_index..end {
+            let account_id = self.epoch_participants.get(&(epoch_id, index)).unwrap();
+            let key = (epoch_id, account_id.clone());
+            let mut entry = self.epoch_scores.get(&key).unwrap();
+            if entry.paid.is_some() {
+                continue;
+            }
+            let share = epoch.share_of(entry.score);
+            entry.paid = Some(U128(share));
+            self.epoch_scores.insert(&key, &entry);
+            if share > 0 {
+                self.mint(account_id.clone(), share);
+                self.tag_statement_flow(&account_id, StatementFlow::Reward, share);
+            }
+            epoch.settled_count += 1;
+            epoch.distributed = U128(epoch.distributed.0 + share);
+            minted += share;
+            paid_count += 1;
+        }
+
+        let stats = &mut self.program_stats;
+        stats.total_rewards_minted = U128(stats.total_rewards_minted.0 + minted);
+        self.record_daily_mint(minted);
+        self.epochs.insert(&epoch_id, &epoch);
+        if paid_count > 0 {
+            EpochSettled {
+                epoch_id,
+                settled: paid_count,
+                settled_count: epoch.settled_count,
+                participant_count: epoch.participant_count,
+                distributed: epoch.distributed,
+            }
+            .emit();
+        }
+        paid_count
+    }
+
+    // View functions
+    pub fn get_reward_mode(&self) -> RewardMode {
+        self.reward_mode.clone()
+    }
+
+    /// The epoch contributions are currently counted towards, if the
+    /// contract is in epoch mode.
+    pub fn get_current_epoch(&self) -> Option<Epoch> {
+        let id = self.current_epoch_id()?;
+        Some(self.epochs.get(&id).unwrap_or_else(|| self.new_epoch(id)))
+    }
+
+    pub fn get_epoch(&self, epoch_id: u64) -> Option<Epoch> {
+        self.epochs.get(&epoch_id)
+    }
+
+    pub fn get_epoch_scores(
+        &self,
+        epoch_id: u64,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(AccountId, EpochScore)> {
+        let count = self.epochs.get(&epoch_id).map_or(0, |epoch| epoch.participant_count);
+        (from_index..count.min(from_index.saturating_add(limit)))
+            .map(|index| {
+                let account_id = self.epoch_participants.get(&(epoch_id, index)).unwrap();
+                let score = self.epoch_scores.get(&(epoch_id, account_id.clone())).unwrap();
+                (account_id, score)
+            })
+            .collect()
+    }
+}
+
+impl Epoch {
+    pub(crate) fn share_of(&self, score: u64) -> Balance {
+        if self.total_score == 0 {
+            return 0;
+        }
+        self.pool.0 * Balance::from(score) / Balance::from(self.total_score)
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn in_epoch_mode(&self) -> bool {
+        matches!(self.reward_mode, RewardMode::Epoch { .. })
+    }
+
+    pub(crate) fn current_epoch_id(&self) -> Option<u64> {
+        match self.reward_mode {
+            RewardMode::Instant => None,
+            RewardMode::Epoch { epoch_length_ns, .. } => {
+                let elapsed = env::block_timestamp() - self.epoch_origin;
+                Some(self.epoch_id_offset + elapsed / epoch_length_ns)
+            }
+        }
+    }
+
+    fn new_epoch(&self, id: u64) -> Epoch {
+        let (pool, length) = match self.reward_mode {
+            RewardMode::Epoch { pool, epoch_length_ns } => (pool, epoch_length_ns),
+            RewardMode::Instant => unreachable!(),
+        };
+        let start = self.epoch_origin + (id - self.epoch_id_offset) * length;
+        Epoch {
+            id,
+            start,
+            end: start + length,
+            pool,
+            total_score: 0,
+            participant_count: 0,
+            settled_count: 0,
+            distributed: U128(0),
+        }
+    }
+
+    /// Counts a contribution towards the current epoch and returns the
+    /// epoch id. The weight applies the stake bonus and tier multiplier to
+    /// the score; scores below the threshold count for nothing.
+    pub(crate) fn record_epoch_score(
+        &mut self,
+        account_id: &AccountId,
+        breakdown: &RewardBreakdown,
+    ) -> u64 {
+        let id = self.current_epoch_id().expect("Not in epoch mode");
+        let mut epoch = self.epochs.get(&id).unwrap_or_else(|| self.new_epoch(id));
+        let weight = if u32::from(breakdown.score) >= breakdown.score_threshold {
+            u64::from(breakdown.score)
+                * (10_000 + u64::from(breakdown.stake_bonus_bps))
+                * u64::from(breakdown.tier_multiplier_bps)
+                / 100_000_000
+        } else {
+            0
+        };
+
+        let key = (id, account_id.clone());
+        let mut entry = match self.epoch_scores.get(&key) {
+            Some(entry) => entry,
+            None => {
+                if epoch.participant_count == 0 {
+                    self.epochs_to_settle.push((id, 0));
+                }
+                self.epoch_participants.insert(&(id, epoch.participant_count), account_id);
+                epoch.participant_count += 1;
+                EpochScore { score: 0, paid: None }
+            }
+        };
+        entry.score += weight;
+        epoch.total_score += weight;
+        self.epoch_scores.insert(&key, &entry);
+        self.epochs.insert(&id, &epoch);
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const EPOCH: u64 = 1_000;
+
+    fn epoch_contract(pool: Balance) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_reward_mode(RewardMode::Epoch {
+            pool: U128(pool),
+            epoch_length_ns: EPOCH,
+        });
+        contract
+    }
+
+    #[test]
+    fn contributions_accumulate_scores_without_minting() {
+        let mut contract = epoch_contract(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 50);
+        contribute(&mut contract, &other_food_bank(), 50);
+
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 0);
+        let epoch = contract.get_current_epoch().unwrap();
+        assert_eq!(epoch.id, 0);
+        assert_eq!(epoch.total_score, 200);
+        let scores = contract.get_epoch_scores(0, 0, 10);
+        assert_eq!(scores[0], (food_bank(), EpochScore { score: 150, paid: None }));
+        assert_eq!(scores[1].1.score, 50);
+    }
+
+    #[test]
+    fn settlement_splits_the_pool_by_score() {
+        let mut contract = epoch_contract(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 50);
+        contribute(&mut contract, &other_food_bank(), 50);
+        advance_time(EPOCH);
+        assert_eq!(contract.get_current_epoch().unwrap().id, 1);
+
+        assert_eq!(contract.settle_epoch(0, 0, 1), 1);
+        assert_eq!(contract.settle_epoch(0, 1, 10), 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 750);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 250);
+        let epoch = contract.get_epoch(0).unwrap();
+        assert_eq!(epoch.settled_count, 2);
+        assert_eq!(epoch.distributed.0, 1_000);
+        assert_eq!(contract.get_program_stats().total_rewards_minted.0, 1_000);
+    }
+
+    #[test]
+    fn settling_twice_pays_nothing_more() {
+        let mut contract = epoch_contract(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(EPOCH);
+        assert_eq!(contract.settle_epoch(0, 0, 10), 1);
+        assert_eq!(contract.settle_epoch(0, 0, 10), 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Epoch 0 ends at 1000")]
+    fn running_epoch_cannot_be_settled() {
+        let mut contract = epoch_contract(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(EPOCH - 1);
+        contract.settle_epoch(0, 0, 10);
+    }
+
+    #[test]
+    fn leaving_epoch_mode_keeps_the_open_epoch_settleable() {
+        let mut contract = epoch_contract(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        contract.set_reward_mode(RewardMode::Instant);
+        assert!(contract.get_current_epoch().is_none());
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+
+        advance_time(EPOCH);
+        contract.settle_epoch(0, 0, 10);
+        assert_eq!(contract.get_balance(food_bank()).0, 1_005);
+
+        // Re-entering epoch mode doesn't reuse epoch 0.
+        contract.set_reward_mode(RewardMode::Epoch {
+            pool: U128(10),
+            epoch_length_ns: EPOCH,
+        });
+        assert_eq!(contract.get_current_epoch().unwrap().id, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn reward_mode_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.set_reward_mode(RewardMode::Instant);
+    }
+}
@@ -0,0 +1,263 @@
+//! Polling export for light indexers. Mints, redemptions and admin changes
+//! are kept, as well as logged, in a ring buffer of the last
+//! `event_buffer_capacity` events, each numbered by a sequence that never
+//! repeats. A poller passes the last sequence it saw to `get_events_since`;
+//! when the buffer has wrapped past it, the page's `oldest_sequence` is
+//! more than one above it and the events in between are gone. Every
+//! buffered event costs storage, so the owner sets the capacity, up to
+//! `MAX_EVENT_BUFFER_CAPACITY`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, serde_json};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_EVENT_BUFFER_CAPACITY: u64 = 100;
+/// Around 300 bytes an event puts the full buffer at about 3 NEAR of storage.
+pub const MAX_EVENT_BUFFER_CAPACITY: u64 = 1_000;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct BufferedEvent {
+    sequence: u64,
+    timestamp: u64,
+    event: String,
+    /// The event's data as JSON, as in its log.
+    data: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct EventBuffer {
+    entries: Vector<BufferedEvent>,
+    /// Index of the oldest entry once the buffer is full.
+    head: u64,
+    capacity: u64,
+    latest_sequence: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExportedEvent {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub event: String,
+    pub data: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventPage {
+    /// In sequence order.
+    pub events: Vec<ExportedEvent>,
+    /// 0 before the first event.
+    pub latest_sequence: u64,
+    /// The oldest event still buffered, or `latest_sequence + 1` while the
+    /// buffer is empty.
+    pub oldest_sequence: u64,
+}
+
+impl EventBuffer {
+    pub(crate) fn new(prefix: Vec<u8>, capacity: u64) -> Self {
+        Self {
+            entries: Vector::new(prefix),
+            head: 0,
+            capacity,
+            latest_sequence: 0,
+        }
+    }
+
+    /// Evicts the oldest event when the buffer is full. With no capacity
+    /// the sequence still advances, so pollers see what they missed.
+    pub(crate) fn push(&mut self, event: &str, data: String) {
+        self.latest_sequence += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        let entry = BufferedEvent {
+            sequence: self.latest_sequence,
+            timestamp: env::block_timestamp(),
+            event: event.to_string(),
+            data,
+        };
+        if self.entries.len() < self.capacity {
+            self.entries.push(&entry);
+        } else {
+            self.entries.replace(self.head, &entry);
+            self.head = (self.head + 1) % self.entries.len();
+        }
+    }
+
+    fn oldest_sequence(&self) -> u64 {
+        self.latest_sequence + 1 - self.entries.len()
+    }
+
+    /// Oldest first.
+    fn ordered(&self) -> impl Iterator<Item = BufferedEvent> + '_ {
+        let len = self.entries.len();
+        (0..len).map(move |offset| self.entries.get((self.head + offset) % len).unwrap())
+    }
+
+    /// Keeps the newest `capacity` events, laid out from index 0 again so
+    /// pushes can append until the buffer is full.
+    fn resize(&mut self, capacity: u64) {
+        let evicted = self.entries.len().saturating_sub(capacity) as usize;
+        let kept: Vec<_> = self.ordered().skip(evicted).collect();
+        self.entries.clear();
+        self.entries.extend(kept);
+        self.head = 0;
+        self.capacity = capacity;
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Shrinking evicts the oldest events and frees their storage; 0 stops
+    /// buffering.
+    pub fn set_event_buffer_capacity(&mut self, capacity: u64) {
+        self.assert_owner_action();
+        assert!(
+            capacity <= MAX_EVENT_BUFFER_CAPACITY,
+            "Event buffer capacity must not exceed {}",
+            MAX_EVENT_BUFFER_CAPACITY
+        );
+        self.event_buffer.resize(capacity);
+    }
+
+    // View functions
+    pub fn get_event_buffer_capacity(&self) -> u64 {
+        self.event_buffer.capacity
+    }
+
+    /// Up to `limit` buffered events with a sequence above `sequence`.
+    pub fn get_events_since(&self, sequence: u64, limit: u64) -> EventPage {
+        let buffer = &self.event_buffer;
+        let oldest_sequence = buffer.oldest_sequence();
+        let skip = sequence.saturating_add(1).saturating_sub(oldest_sequence);
+        let events = buffer
+            .ordered()
+            .skip(skip as usize)
+            .take(limit as usize)
+            .map(|entry| ExportedEvent {
+                sequence: entry.sequence,
+                timestamp: entry.timestamp,
+                event: entry.event,
+                data: serde_json::from_str(&entry.data).unwrap_or_else(|_| env::abort()),
+            })
+            .collect();
+        EventPage {
+            events,
+            latest_sequence: buffer.latest_sequence,
+            oldest_sequence,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn mint(contract: &mut FoodBankToken, count: u128) {
+        for amount in 1..=count {
+            contract.mint(food_bank(), amount);
+        }
+    }
+
+    fn sequences(page: &EventPage) -> Vec<u64> {
+        page.events.iter().map(|event| event.sequence).collect()
+    }
+
+    /// Events recorded by `setup_contract` itself.
+    fn baseline(contract: &FoodBankToken) -> u64 {
+        contract.get_events_since(0, 0).latest_sequence
+    }
+
+    #[test]
+    fn events_come_back_in_sequence_order() {
+        let mut contract = setup_contract();
+        let start = baseline(&contract);
+        mint(&mut contract, 3);
+        contract.pause();
+
+        let page = contract.get_events_since(start, 10);
+        assert_eq!(sequences(&page), [start + 1, start + 2, start + 3, start + 4]);
+        assert_eq!(page.latest_sequence, start + 4);
+        assert_eq!(page.events[2].event, "tokens_minted");
+        assert_eq!(page.events[2].data["amount"], "3");
+        assert_eq!(page.events[3].event, "contract_paused");
+    }
+
+    #[test]
+    fn pages_resume_after_the_last_sequence() {
+        let mut contract = setup_contract();
+        let start = baseline(&contract);
+        mint(&mut contract, 5);
+        let first = contract.get_events_since(start, 2);
+        let last_seen = *sequences(&first).last().unwrap();
+        let second = contract.get_events_since(last_seen, 10);
+        assert_eq!(sequences(&second), [start + 3, start + 4, start + 5]);
+        assert!(contract.get_events_since(start + 5, 10).events.is_empty());
+    }
+
+    #[test]
+    fn a_full_buffer_evicts_the_oldest_events() {
+        let mut contract = setup_contract();
+        contract.set_event_buffer_capacity(3);
+        let start = baseline(&contract);
+        mint(&mut contract, 5);
+
+        let page = contract.get_events_since(0, 10);
+        assert_eq!(sequences(&page), [start + 3, start + 4, start + 5]);
+        assert_eq!(page.oldest_sequence, start + 3);
+        let amounts: Vec<_> = page.events.iter().map(|event| event.data["amount"].clone()).collect();
+        assert_eq!(amounts, ["3", "4", "5"]);
+    }
+
+    #[test]
+    fn a_poller_left_behind_sees_the_gap() {
+        let mut contract = setup_contract();
+        contract.set_event_buffer_capacity(2);
+        let last_seen = baseline(&contract);
+        mint(&mut contract, 4);
+
+        let page = contract.get_events_since(last_seen, 10);
+        assert!(page.oldest_sequence > last_seen + 1);
+        assert_eq!(page.latest_sequence - last_seen, 4);
+        assert_eq!(page.events.len(), 2);
+    }
+
+    #[test]
+    fn resizing_keeps_the_newest_events_in_order() {
+        let mut contract = setup_contract();
+        contract.set_event_buffer_capacity(4);
+        let start = baseline(&contract);
+        // Wraps the ring so its head is no longer at index 0.
+        mint(&mut contract, 6);
+        contract.set_event_buffer_capacity(2);
+        assert_eq!(sequences(&contract.get_events_since(0, 10)), [start + 5, start + 6]);
+
+        contract.set_event_buffer_capacity(3);
+        mint(&mut contract, 2);
+        assert_eq!(sequences(&contract.get_events_since(0, 10)), [start + 6, start + 7, start + 8]);
+    }
+
+    #[test]
+    fn without_capacity_only_the_sequence_moves() {
+        let mut contract = setup_contract();
+        contract.set_event_buffer_capacity(0);
+        let start = baseline(&contract);
+        mint(&mut contract, 2);
+        let page = contract.get_events_since(start, 10);
+        assert!(page.events.is_empty());
+        assert_eq!(page.latest_sequence, start + 2);
+        assert_eq!(page.oldest_sequence, start + 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Event buffer capacity must not exceed 1000")]
+    fn the_capacity_is_bounded() {
+        let mut contract = setup_contract();
+        contract.set_event_buffer_capacity(MAX_EVENT_BUFFER_CAPACITY + 1);
+    }
+}
@@ -0,0 +1,1474 @@
+//! NEP-297 events emitted by the contract.
+
+use near_sdk::serde::Serialize;
+use near_sdk::json_types::{Base58CryptoHash, U128};
+use near_sdk::{env, serde_json, AccountId, PublicKey};
+
+use crate::account_types::AccountType;
+use crate::acknowledgments::AcknowledgmentStatus;
+use crate::archives::ArchiveCheckpoint;
+use crate::council::AdminAction;
+use crate::disputes::Resolution;
+use crate::epochs::RewardMode;
+use crate::event_export::EventBuffer;
+use crate::fulfillment::FulfillmentStatus;
+use crate::governance::{ProposalAction, ProposalStatus};
+use crate::grants::GrantStatus;
+use crate::profiles::OrgType;
+use crate::roles::Role;
+use crate::stats::ProgramStats;
+use crate::timelock::ParamChange;
+use crate::verification::VerificationStatus;
+use crate::vesting::VestingSchedule;
+use crate::RedemptionOptionV5;
+
+pub const EVENT_STANDARD: &str = "needio";
+pub const EVENT_VERSION: &str = "1.0.0";
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, T> {
+    standard: &'static str,
+    version: &'static str,
+    event: &'static str,
+    data: [&'a T; 1],
+}
+
+pub(crate) trait Event: Serialize + Sized {
+    const NAME: &'static str;
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(&EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event: Self::NAME,
+            data: [self],
+        })
+        .unwrap_or_else(|_| env::abort())
+    }
+
+    fn emit(&self) {
+        env::log_str(&format!("EVENT_JSON:{}", self.to_json()));
+    }
+
+    /// Emits the event and keeps it in `buffer` for `get_events_since`.
+    fn record(&self, buffer: &mut EventBuffer) {
+        self.emit();
+        buffer.push(Self::NAME, serde_json::to_string(self).unwrap_or_else(|_| env::abort()));
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct OwnershipProposed<'a> {
+    pub owner: &'a AccountId,
+    pub pending_owner: &'a AccountId,
+}
+
+impl Event for OwnershipProposed<'_> {
+    const NAME: &'static str = "ownership_proposed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct OwnershipTransferred<'a> {
+    pub old_owner: &'a AccountId,
+    pub new_owner: &'a AccountId,
+}
+
+impl Event for OwnershipTransferred<'_> {
+    const NAME: &'static str = "ownership_transferred";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RoleGranted<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub role: Role,
+}
+
+impl Event for RoleGranted<'_> {
+    const NAME: &'static str = "role_granted";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RoleRevoked<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub role: Role,
+}
+
+impl Event for RoleRevoked<'_> {
+    const NAME: &'static str = "role_revoked";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContractPaused<'a> {
+    pub actor: &'a AccountId,
+    pub by_guardian: bool,
+    pub timestamp: u64,
+}
+
+impl Event for ContractPaused<'_> {
+    const NAME: &'static str = "contract_paused";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContractUnpaused<'a> {
+    pub actor: &'a AccountId,
+    pub timestamp: u64,
+}
+
+impl Event for ContractUnpaused<'_> {
+    const NAME: &'static str = "contract_unpaused";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MintingPaused<'a> {
+    pub actor: &'a AccountId,
+    pub timestamp: u64,
+}
+
+impl Event for MintingPaused<'_> {
+    const NAME: &'static str = "minting_paused";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MintingResumed<'a> {
+    pub actor: &'a AccountId,
+    pub timestamp: u64,
+}
+
+impl Event for MintingResumed<'_> {
+    const NAME: &'static str = "minting_resumed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContributionRecorded<'a> {
+    pub account_id: &'a AccountId,
+    pub account_type: AccountType,
+    pub display_name: Option<&'a str>,
+    pub index: u64,
+    pub external_id: Option<&'a str>,
+    pub reward: U128,
+    /// Differs from `reward` when the per-contribution cap applied.
+    pub uncapped_reward: U128,
+    pub stats: &'a ProgramStats,
+}
+
+impl Event for ContributionRecorded<'_> {
+    const NAME: &'static str = "contribution_recorded";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContributionVoided<'a> {
+    pub account_id: &'a AccountId,
+    pub index: u64,
+    pub clawed_back: U128,
+    pub stats: &'a ProgramStats,
+}
+
+impl Event for ContributionVoided<'_> {
+    const NAME: &'static str = "contribution_voided";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContributionsArchived<'a> {
+    pub account_id: &'a AccountId,
+    pub checkpoint: &'a ArchiveCheckpoint,
+}
+
+impl Event for ContributionsArchived<'_> {
+    const NAME: &'static str = "contributions_archived";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct WithheldRewardReleased<'a> {
+    pub account_id: &'a AccountId,
+    pub account_type: AccountType,
+    pub index: u64,
+    pub reward: U128,
+    pub onboarding_bonus: U128,
+}
+
+impl Event for WithheldRewardReleased<'_> {
+    const NAME: &'static str = "withheld_reward_released";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct AttestorKeySet<'a> {
+    pub public_key: &'a PublicKey,
+    /// Until when the replaced key, if any, is still accepted.
+    pub previous_valid_until: Option<u64>,
+}
+
+impl Event for AttestorKeySet<'_> {
+    const NAME: &'static str = "attestor_key_set";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContributionAttested<'a> {
+    pub account_id: &'a AccountId,
+    pub attestor: &'a PublicKey,
+    pub nonce: u64,
+    pub submitter: &'a AccountId,
+}
+
+impl Event for ContributionAttested<'_> {
+    const NAME: &'static str = "contribution_attested";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RelayKeyRegistered<'a> {
+    pub account_id: &'a AccountId,
+    pub public_key: &'a PublicKey,
+}
+
+impl Event for RelayKeyRegistered<'_> {
+    const NAME: &'static str = "relay_key_registered";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RelayKeyRevoked<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub public_key: &'a PublicKey,
+}
+
+impl Event for RelayKeyRevoked<'_> {
+    const NAME: &'static str = "relay_key_revoked";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CallRelayed<'a> {
+    pub account_id: &'a AccountId,
+    pub method: &'a str,
+    pub nonce: u64,
+    pub relayer: &'a AccountId,
+}
+
+impl Event for CallRelayed<'_> {
+    const NAME: &'static str = "call_relayed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RewardAdjusted<'a> {
+    pub account_id: &'a AccountId,
+    pub account_type: AccountType,
+    pub index: u64,
+    pub previous: U128,
+    pub reward: U128,
+}
+
+impl Event for RewardAdjusted<'_> {
+    const NAME: &'static str = "reward_adjusted";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RedemptionCompleted<'a> {
+    pub account_id: &'a AccountId,
+    pub account_type: AccountType,
+    pub display_name: Option<&'a str>,
+    pub option_id: &'a str,
+    pub amount: U128,
+    pub near_copay: U128,
+    pub receipt_id: u64,
+    pub stats: &'a ProgramStats,
+}
+
+impl Event for RedemptionCompleted<'_> {
+    const NAME: &'static str = "redemption_completed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RedemptionRefunded<'a> {
+    pub account_id: &'a AccountId,
+    pub account_type: AccountType,
+    pub display_name: Option<&'a str>,
+    pub option_id: &'a str,
+    pub amount: U128,
+    pub near_copay: U128,
+}
+
+impl Event for RedemptionRefunded<'_> {
+    const NAME: &'static str = "redemption_refunded";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct EntitlementExtended<'a> {
+    pub account_id: &'a AccountId,
+    pub key: &'a str,
+    pub option_id: &'a str,
+    pub expires_at: u64,
+}
+
+impl Event for EntitlementExtended<'_> {
+    const NAME: &'static str = "entitlement_extended";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TokensMinted<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for TokensMinted<'_> {
+    const NAME: &'static str = "tokens_minted";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct OnboardingBonusMinted<'a> {
+    pub account_id: &'a AccountId,
+    pub contribution_id: u64,
+    pub amount: U128,
+    pub memo: &'a str,
+}
+
+impl Event for OnboardingBonusMinted<'_> {
+    const NAME: &'static str = "onboarding_bonus_minted";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TokensMintedBatch<'a> {
+    pub actor: &'a AccountId,
+    pub memo: &'a str,
+    pub mints: &'a [(AccountId, U128)],
+    pub total: U128,
+}
+
+impl Event for TokensMintedBatch<'_> {
+    const NAME: &'static str = "tokens_minted_batch";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TokensStaked<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for TokensStaked<'_> {
+    const NAME: &'static str = "tokens_staked";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TokensUnstaked<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for TokensUnstaked<'_> {
+    const NAME: &'static str = "tokens_unstaked";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct VestingStarted<'a> {
+    pub account_id: &'a AccountId,
+    pub schedule: &'a VestingSchedule,
+}
+
+impl Event for VestingStarted<'_> {
+    const NAME: &'static str = "vesting_started";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct VestedClaimed<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for VestedClaimed<'_> {
+    const NAME: &'static str = "vested_claimed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TierReached<'a> {
+    pub account_id: &'a AccountId,
+    pub tier: &'a str,
+}
+
+impl Event for TierReached<'_> {
+    const NAME: &'static str = "tier_reached";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ReferralRewarded<'a> {
+    pub referrer: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for ReferralRewarded<'_> {
+    const NAME: &'static str = "referral_rewarded";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CampaignCreated<'a> {
+    pub id: u64,
+    pub name: &'a str,
+}
+
+impl Event for CampaignCreated<'_> {
+    const NAME: &'static str = "campaign_created";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CampaignClosed {
+    pub id: u64,
+}
+
+impl Event for CampaignClosed {
+    const NAME: &'static str = "campaign_closed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MatchingPoolFunded<'a> {
+    pub donor: &'a AccountId,
+    pub deposit: U128,
+    pub tokens: U128,
+}
+
+impl Event for MatchingPoolFunded<'_> {
+    const NAME: &'static str = "matching_pool_funded";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MatchingPoolDrawn<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+    pub remaining: U128,
+}
+
+impl Event for MatchingPoolDrawn<'_> {
+    const NAME: &'static str = "matching_pool_drawn";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MatchingPoolSwept<'a> {
+    pub donor: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for MatchingPoolSwept<'_> {
+    const NAME: &'static str = "matching_pool_swept";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TokensPurchased<'a> {
+    pub buyer: &'a AccountId,
+    pub tokens: U128,
+    /// NEAR kept for the tokens; the rest of the deposit was refunded.
+    pub cost: U128,
+    pub refund: U128,
+}
+
+impl Event for TokensPurchased<'_> {
+    const NAME: &'static str = "tokens_purchased";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct SaleProceedsWithdrawn<'a> {
+    pub actor: &'a AccountId,
+    pub to: &'a AccountId,
+    pub amount: U128,
+    pub remaining: U128,
+}
+
+impl Event for SaleProceedsWithdrawn<'_> {
+    const NAME: &'static str = "sale_proceeds_withdrawn";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DonationsWithdrawn<'a> {
+    pub actor: &'a AccountId,
+    pub to: &'a AccountId,
+    pub amount: U128,
+    pub remaining: U128,
+}
+
+impl Event for DonationsWithdrawn<'_> {
+    const NAME: &'static str = "donations_withdrawn";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct BuybackReserveFunded<'a> {
+    pub funder: &'a AccountId,
+    pub amount: U128,
+    pub reserve: U128,
+}
+
+impl Event for BuybackReserveFunded<'_> {
+    const NAME: &'static str = "buyback_reserve_funded";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TokensSold<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+    pub payout: U128,
+}
+
+impl Event for TokensSold<'_> {
+    const NAME: &'static str = "tokens_sold";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct BuybackRefunded<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+    pub payout: U128,
+}
+
+impl Event for BuybackRefunded<'_> {
+    const NAME: &'static str = "buyback_refunded";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DonationReceived<'a> {
+    pub token_id: &'a AccountId,
+    pub sender_id: &'a AccountId,
+    pub amount: U128,
+    pub tokens: U128,
+    /// `None` when the donation went to the matching pool.
+    pub beneficiary: Option<&'a AccountId>,
+}
+
+impl Event for DonationReceived<'_> {
+    const NAME: &'static str = "donation_received";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DonorRecognized<'a> {
+    pub donor: &'a AccountId,
+    pub deposit: U128,
+    /// Matching budget the deposit added.
+    pub matching_tokens: U128,
+    pub recognition: U128,
+    pub total_recognition: U128,
+}
+
+impl Event for DonorRecognized<'_> {
+    const NAME: &'static str = "donor_recognized";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CodeStaged<'a> {
+    pub actor: &'a AccountId,
+    pub code_hash: &'a Base58CryptoHash,
+    pub deploy_after: u64,
+}
+
+impl Event for CodeStaged<'_> {
+    const NAME: &'static str = "code_staged";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CodeUnstaged<'a> {
+    pub actor: &'a AccountId,
+    pub code_hash: &'a Base58CryptoHash,
+}
+
+impl Event for CodeUnstaged<'_> {
+    const NAME: &'static str = "code_unstaged";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CodeDeployed<'a> {
+    pub actor: &'a AccountId,
+    pub code_hash: &'a Base58CryptoHash,
+}
+
+impl Event for CodeDeployed<'_> {
+    const NAME: &'static str = "code_deployed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct BadgeMinted<'a> {
+    pub account_id: &'a AccountId,
+    pub milestone: u64,
+}
+
+impl Event for BadgeMinted<'_> {
+    const NAME: &'static str = "badge_minted";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct BadgeMintFailed<'a> {
+    pub account_id: &'a AccountId,
+    pub milestone: u64,
+}
+
+impl Event for BadgeMintFailed<'_> {
+    const NAME: &'static str = "badge_mint_failed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RewardModeChanged<'a> {
+    pub mode: &'a RewardMode,
+}
+
+impl Event for RewardModeChanged<'_> {
+    const NAME: &'static str = "reward_mode_changed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct EpochSettled {
+    pub epoch_id: u64,
+    /// Participants paid by this call.
+    pub settled: u64,
+    pub settled_count: u64,
+    pub participant_count: u64,
+    pub distributed: U128,
+}
+
+impl Event for EpochSettled {
+    const NAME: &'static str = "epoch_settled";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DistributionPublished {
+    pub id: u64,
+    pub total: U128,
+    pub expiry: u64,
+}
+
+impl Event for DistributionPublished {
+    const NAME: &'static str = "distribution_published";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DistributionClaimed<'a> {
+    pub id: u64,
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for DistributionClaimed<'_> {
+    const NAME: &'static str = "distribution_claimed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DistributionClosed {
+    pub id: u64,
+    pub unclaimed: U128,
+}
+
+impl Event for DistributionClosed {
+    const NAME: &'static str = "distribution_closed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ProfileUpdated<'a> {
+    pub account_id: &'a AccountId,
+    pub name: &'a str,
+    pub region: &'a str,
+    pub org_type: OrgType,
+    pub account_type: AccountType,
+}
+
+impl Event for ProfileUpdated<'_> {
+    const NAME: &'static str = "profile_updated";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ProfileVerified<'a> {
+    pub account_id: &'a AccountId,
+}
+
+impl Event for ProfileVerified<'_> {
+    const NAME: &'static str = "profile_verified";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ProfileCleared<'a> {
+    pub account_id: &'a AccountId,
+}
+
+impl Event for ProfileCleared<'_> {
+    const NAME: &'static str = "profile_cleared";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CouncilChanged<'a> {
+    pub members: &'a [AccountId],
+    pub threshold: u32,
+}
+
+impl Event for CouncilChanged<'_> {
+    const NAME: &'static str = "council_changed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CouncilActionProposed<'a> {
+    pub id: u64,
+    pub proposer: &'a AccountId,
+    pub action: &'a AdminAction,
+}
+
+impl Event for CouncilActionProposed<'_> {
+    const NAME: &'static str = "council_action_proposed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CouncilActionConfirmed<'a> {
+    pub id: u64,
+    pub account_id: &'a AccountId,
+    pub confirmations: u32,
+}
+
+impl Event for CouncilActionConfirmed<'_> {
+    const NAME: &'static str = "council_action_confirmed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CouncilActionExecuted<'a> {
+    pub id: u64,
+    pub actor: &'a AccountId,
+    pub action: &'a AdminAction,
+}
+
+impl Event for CouncilActionExecuted<'_> {
+    const NAME: &'static str = "council_action_executed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct CouncilActionCancelled {
+    pub id: u64,
+}
+
+impl Event for CouncilActionCancelled {
+    const NAME: &'static str = "council_action_cancelled";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct GuardianAdded<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+}
+
+impl Event for GuardianAdded<'_> {
+    const NAME: &'static str = "guardian_added";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct GuardianRemoved<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+}
+
+impl Event for GuardianRemoved<'_> {
+    const NAME: &'static str = "guardian_removed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContributionDisputed<'a> {
+    pub account_id: &'a AccountId,
+    pub contribution_id: u64,
+    pub reason: &'a str,
+}
+
+impl Event for ContributionDisputed<'_> {
+    const NAME: &'static str = "contribution_disputed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DisputeResolved<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub contribution_id: u64,
+    pub reason: &'a str,
+    pub resolution: &'a Resolution,
+}
+
+impl Event for DisputeResolved<'_> {
+    const NAME: &'static str = "dispute_resolved";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct VerificationVoteCast<'a> {
+    pub verifier: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub contribution_id: u64,
+    pub approve: bool,
+    pub note: &'a str,
+}
+
+impl Event for VerificationVoteCast<'_> {
+    const NAME: &'static str = "verification_vote_cast";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContributionVerified<'a> {
+    pub account_id: &'a AccountId,
+    pub account_type: AccountType,
+    pub contribution_id: u64,
+    pub status: VerificationStatus,
+    pub reward: U128,
+}
+
+impl Event for ContributionVerified<'_> {
+    const NAME: &'static str = "contribution_verified";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContributionAcknowledged<'a> {
+    pub account_id: &'a AccountId,
+    pub account_type: AccountType,
+    pub contribution_id: u64,
+    pub status: AcknowledgmentStatus,
+    pub reward: U128,
+    pub rejection_reason: Option<&'a str>,
+}
+
+impl Event for ContributionAcknowledged<'_> {
+    const NAME: &'static str = "contribution_acknowledged";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct FulfillmentUpdated<'a> {
+    pub receipt_id: u64,
+    pub account_id: &'a AccountId,
+    pub option_id: &'a str,
+    pub status: FulfillmentStatus,
+    pub reason: Option<&'a str>,
+}
+
+impl Event for FulfillmentUpdated<'_> {
+    const NAME: &'static str = "fulfillment_updated";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct RedemptionOptionSet<'a> {
+    pub actor: &'a AccountId,
+    pub option_id: &'a str,
+    /// `None` when the option is new.
+    pub old_option: Option<&'a RedemptionOptionV5>,
+    pub new_option: &'a RedemptionOptionV5,
+}
+
+impl Event for RedemptionOptionSet<'_> {
+    const NAME: &'static str = "redemption_option_set";
+}
+
+/// Applied parameter changes, whether through the timelock, the council or
+/// a governance proposal.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ParamChanged<'a> {
+    pub actor: &'a AccountId,
+    pub old_value: &'a ParamChange,
+    pub new_value: &'a ParamChange,
+}
+
+impl Event for ParamChanged<'_> {
+    const NAME: &'static str = "param_changed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TreasuryChanged<'a> {
+    pub actor: &'a AccountId,
+    pub old_account: Option<&'a AccountId>,
+    pub new_account: Option<&'a AccountId>,
+    pub old_share_bps: u16,
+    pub new_share_bps: u16,
+}
+
+impl Event for TreasuryChanged<'_> {
+    const NAME: &'static str = "treasury_changed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct PenaltyRecorded<'a> {
+    pub account_id: &'a AccountId,
+    pub index: u64,
+    pub severity: u8,
+    pub reason: &'a str,
+    pub burned: U128,
+    /// Part of the deduction the balance didn't cover.
+    pub debt: U128,
+}
+
+impl Event for PenaltyRecorded<'_> {
+    const NAME: &'static str = "penalty_recorded";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct PenaltyVoided<'a> {
+    pub account_id: &'a AccountId,
+    pub index: u64,
+    pub refunded: U128,
+    pub debt_forgiven: U128,
+}
+
+impl Event for PenaltyVoided<'_> {
+    const NAME: &'static str = "penalty_voided";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DebtRepaid<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+    pub remaining: U128,
+}
+
+impl Event for DebtRepaid<'_> {
+    const NAME: &'static str = "debt_repaid";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TreasuryTransfer<'a> {
+    pub actor: &'a AccountId,
+    pub to: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for TreasuryTransfer<'_> {
+    const NAME: &'static str = "treasury_transfer";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct NearWithdrawn<'a> {
+    pub actor: &'a AccountId,
+    pub to: &'a AccountId,
+    pub amount: U128,
+}
+
+impl Event for NearWithdrawn<'_> {
+    const NAME: &'static str = "near_withdrawn";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct StorageCreditDeposited<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+    pub credit: U128,
+}
+
+impl Event for StorageCreditDeposited<'_> {
+    const NAME: &'static str = "storage_credit_deposited";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct StorageCreditWithdrawn<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: U128,
+    pub credit: U128,
+}
+
+impl Event for StorageCreditWithdrawn<'_> {
+    const NAME: &'static str = "storage_credit_withdrawn";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ParamChangeProposed<'a> {
+    pub id: u64,
+    pub change: &'a ParamChange,
+    pub execute_after: u64,
+}
+
+impl Event for ParamChangeProposed<'_> {
+    const NAME: &'static str = "param_change_proposed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ParamChangeExecuted<'a> {
+    pub id: u64,
+    pub change: &'a ParamChange,
+}
+
+impl Event for ParamChangeExecuted<'_> {
+    const NAME: &'static str = "param_change_executed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ParamChangeCancelled<'a> {
+    pub id: u64,
+    pub change: &'a ParamChange,
+}
+
+impl Event for ParamChangeCancelled<'_> {
+    const NAME: &'static str = "param_change_cancelled";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ProposalCreated<'a> {
+    pub id: u64,
+    pub proposer: &'a AccountId,
+    pub action: &'a ProposalAction,
+    pub voting_ends_at: u64,
+}
+
+impl Event for ProposalCreated<'_> {
+    const NAME: &'static str = "proposal_created";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ProposalExecuted<'a> {
+    pub id: u64,
+    pub action: &'a ProposalAction,
+    /// Set when the action could not run.
+    pub failure_reason: Option<&'a str>,
+}
+
+impl Event for ProposalExecuted<'_> {
+    const NAME: &'static str = "proposal_executed";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct VoteDelegated<'a> {
+    pub delegator: &'a AccountId,
+    pub delegate: &'a AccountId,
+}
+
+impl Event for VoteDelegated<'_> {
+    const NAME: &'static str = "vote_delegated";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct VoteUndelegated<'a> {
+    pub delegator: &'a AccountId,
+    pub delegate: &'a AccountId,
+}
+
+impl Event for VoteUndelegated<'_> {
+    const NAME: &'static str = "vote_undelegated";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct VoteCast<'a> {
+    pub proposal_id: u64,
+    pub voter: &'a AccountId,
+    pub approve: bool,
+    pub weight: U128,
+}
+
+impl Event for VoteCast<'_> {
+    const NAME: &'static str = "vote_cast";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ProposalFinalized {
+    pub id: u64,
+    pub status: ProposalStatus,
+    pub votes_for: U128,
+    pub votes_against: U128,
+}
+
+impl Event for ProposalFinalized {
+    const NAME: &'static str = "proposal_finalized";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct AccountFrozen<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub reason: &'a str,
+}
+
+impl Event for AccountFrozen<'_> {
+    const NAME: &'static str = "account_frozen";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct AccountUnfrozen<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+}
+
+impl Event for AccountUnfrozen<'_> {
+    const NAME: &'static str = "account_unfrozen";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct AccountTypeSet<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub old_type: AccountType,
+    pub account_type: AccountType,
+}
+
+impl Event for AccountTypeSet<'_> {
+    const NAME: &'static str = "account_type_set";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct SupplierRegistered<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub name: &'a str,
+    pub discount_bps: u16,
+}
+
+impl Event for SupplierRegistered<'_> {
+    const NAME: &'static str = "supplier_registered";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct SupplierDeactivated<'a> {
+    pub actor: &'a AccountId,
+    pub account_id: &'a AccountId,
+}
+
+impl Event for SupplierDeactivated<'_> {
+    const NAME: &'static str = "supplier_deactivated";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct OptionSupplierSet<'a> {
+    pub actor: &'a AccountId,
+    pub option_id: &'a str,
+    /// `None` when the option was unrouted.
+    pub supplier: Option<&'a AccountId>,
+}
+
+impl Event for OptionSupplierSet<'_> {
+    const NAME: &'static str = "option_supplier_set";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct GrantApplicationSubmitted<'a> {
+    pub application_id: u64,
+    pub account_id: &'a AccountId,
+    pub option_id: &'a str,
+    pub receipt_id: u64,
+}
+
+impl Event for GrantApplicationSubmitted<'_> {
+    const NAME: &'static str = "grant_application_submitted";
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct GrantApplicationReviewed<'a> {
+    pub actor: &'a AccountId,
+    pub application_id: u64,
+    pub account_id: &'a AccountId,
+    pub status: GrantStatus,
+    pub note: Option<&'a str>,
+    pub refunded: U128,
+}
+
+impl Event for GrantApplicationReviewed<'_> {
+    const NAME: &'static str = "grant_application_reviewed";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::get_logs;
+
+    fn envelope(event: &str, data: &str) -> String {
+        format!(
+            r#"{{"standard":"needio","version":"1.0.0","event":"{}","data":[{}]}}"#,
+            event, data
+        )
+    }
+
+    fn option(cost: u128) -> RedemptionOptionV5 {
+        RedemptionOptionV5 {
+            name: "tote_bags".to_string(),
+            cost: U128(cost),
+            available: true,
+            description: "Branded tote bags".to_string(),
+            benefit_method: String::new(),
+            benefit_args_template: None,
+            benefit_contract: None,
+            min_contributions: None,
+            prerequisite_option: None,
+            near_copay: None,
+            translations: Vec::new(),
+            grants_entitlement: None,
+            cooldown_ns: None,
+        }
+    }
+
+    #[test]
+    fn role_events() {
+        let granted = RoleGranted {
+            actor: &owner(),
+            account_id: &food_bank(),
+            role: Role::Admin,
+        };
+        assert_eq!(
+            granted.to_json(),
+            envelope("role_granted", r#"{"actor":"alice","account_id":"bob","role":"Admin"}"#)
+        );
+        let revoked = RoleRevoked {
+            actor: &owner(),
+            account_id: &food_bank(),
+            role: Role::Recorder,
+        };
+        assert_eq!(
+            revoked.to_json(),
+            envelope("role_revoked", r#"{"actor":"alice","account_id":"bob","role":"Recorder"}"#)
+        );
+    }
+
+    #[test]
+    fn pause_events() {
+        let paused = ContractPaused {
+            actor: &owner(),
+            by_guardian: false,
+            timestamp: 7,
+        };
+        assert_eq!(
+            paused.to_json(),
+            envelope("contract_paused", r#"{"actor":"alice","by_guardian":false,"timestamp":7}"#)
+        );
+        let unpaused = ContractUnpaused {
+            actor: &owner(),
+            timestamp: 8,
+        };
+        assert_eq!(
+            unpaused.to_json(),
+            envelope("contract_unpaused", r#"{"actor":"alice","timestamp":8}"#)
+        );
+    }
+
+    #[test]
+    fn freeze_events() {
+        let frozen = AccountFrozen {
+            actor: &owner(),
+            account_id: &food_bank(),
+            reason: "Gaming contributions",
+        };
+        assert_eq!(
+            frozen.to_json(),
+            envelope(
+                "account_frozen",
+                r#"{"actor":"alice","account_id":"bob","reason":"Gaming contributions"}"#
+            )
+        );
+        let unfrozen = AccountUnfrozen {
+            actor: &owner(),
+            account_id: &food_bank(),
+        };
+        assert_eq!(
+            unfrozen.to_json(),
+            envelope("account_unfrozen", r#"{"actor":"alice","account_id":"bob"}"#)
+        );
+    }
+
+    #[test]
+    fn ownership_events() {
+        let proposed = OwnershipProposed {
+            owner: &owner(),
+            pending_owner: &food_bank(),
+        };
+        assert_eq!(
+            proposed.to_json(),
+            envelope("ownership_proposed", r#"{"owner":"alice","pending_owner":"bob"}"#)
+        );
+        let transferred = OwnershipTransferred {
+            old_owner: &owner(),
+            new_owner: &food_bank(),
+        };
+        assert_eq!(
+            transferred.to_json(),
+            envelope("ownership_transferred", r#"{"old_owner":"alice","new_owner":"bob"}"#)
+        );
+    }
+
+    #[test]
+    fn treasury_events() {
+        let changed = TreasuryChanged {
+            actor: &owner(),
+            old_account: None,
+            new_account: Some(&other_food_bank()),
+            old_share_bps: 0,
+            new_share_bps: 500,
+        };
+        assert_eq!(
+            changed.to_json(),
+            envelope(
+                "treasury_changed",
+                r#"{"actor":"alice","old_account":null,"new_account":"charlie","old_share_bps":0,"new_share_bps":500}"#
+            )
+        );
+        let transfer = TreasuryTransfer {
+            actor: &owner(),
+            to: &food_bank(),
+            amount: U128(40),
+        };
+        assert_eq!(
+            transfer.to_json(),
+            envelope("treasury_transfer", r#"{"actor":"alice","to":"bob","amount":"40"}"#)
+        );
+        let withdrawn = NearWithdrawn {
+            actor: &owner(),
+            to: &food_bank(),
+            amount: U128(ONE_NEAR),
+        };
+        assert_eq!(
+            withdrawn.to_json(),
+            envelope(
+                "near_withdrawn",
+                r#"{"actor":"alice","to":"bob","amount":"1000000000000000000000000"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn param_changed_event() {
+        let changed = ParamChanged {
+            actor: &owner(),
+            old_value: &ParamChange::RewardRateBps(500),
+            new_value: &ParamChange::RewardRateBps(2_000),
+        };
+        assert_eq!(
+            changed.to_json(),
+            envelope(
+                "param_changed",
+                r#"{"actor":"alice","old_value":{"RewardRateBps":500},"new_value":{"RewardRateBps":2000}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn redemption_option_set_event() {
+        let set = RedemptionOptionSet {
+            actor: &owner(),
+            option_id: "tote_bags",
+            old_option: Some(&option(10)),
+            new_option: &option(12),
+        };
+        let option_json = |cost: u32| {
+            format!(
+                r#"{{"name":"tote_bags","cost":"{}","available":true,"description":"Branded tote bags","benefit_method":"","benefit_args_template":null,"benefit_contract":null,"min_contributions":null,"prerequisite_option":null,"near_copay":null,"translations":[],"grants_entitlement":null,"cooldown_ns":null}}"#,
+                cost
+            )
+        };
+        assert_eq!(
+            set.to_json(),
+            envelope(
+                "redemption_option_set",
+                &format!(
+                    r#"{{"actor":"alice","option_id":"tote_bags","old_option":{},"new_option":{}}}"#,
+                    option_json(10),
+                    option_json(12)
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn admin_changes_are_logged() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.update_redemption_option(
+            "supplier_discount".to_string(),
+            Some(U128(80)),
+            Some("15% discount on supplier purchases".to_string()),
+            None,
+        );
+        contract.set_treasury(Some(other_food_bank()), 500);
+        let logs = get_logs();
+        assert_eq!(logs.len(), 2);
+        assert!(logs[0].contains(r#""event":"redemption_option_set""#));
+        assert!(logs[0].contains(r#""old_option":{"name":"Supplier discount","cost":"100""#));
+        assert!(logs[1].contains(r#""event":"treasury_changed""#));
+    }
+
+    #[test]
+    fn applied_param_changes_are_logged_with_the_old_value() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+        advance_time(crate::timelock::DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert!(get_logs().iter().any(|log| log.contains(
+            r#""data":[{"actor":"alice","old_value":{"RewardRateBps":500},"new_value":{"RewardRateBps":2000}}]"#
+        )));
+    }
+}
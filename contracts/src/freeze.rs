@@ -0,0 +1,184 @@
+//! Freezing accounts under investigation. A frozen account can neither
+//! receive contribution rewards nor spend its tokens. A redemption already
+//! in flight still settles; see `fulfillment.rs`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{AccountFrozen, AccountUnfrozen, Event};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_REASON_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FrozenAccount {
+    reason: String,
+    frozen_at: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn freeze_account(&mut self, account_id: AccountId, reason: String) {
+        self.assert_owner_action();
+        assert!(account_id != self.owner, "The owner account cannot be frozen");
+        assert!(
+            reason.len() <= MAX_REASON_LENGTH,
+            "Reason exceeds {} bytes",
+            MAX_REASON_LENGTH
+        );
+        assert!(
+            self.frozen_accounts.get(&account_id).is_none(),
+            "Account is already frozen"
+        );
+        AccountFrozen {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+            reason: &reason,
+        }
+        .record(&mut self.event_buffer);
+        self.frozen_accounts.insert(
+            &account_id,
+            &FrozenAccount {
+                reason,
+                frozen_at: env::block_timestamp(),
+            },
+        );
+    }
+
+    pub fn unfreeze_account(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        self.frozen_accounts.remove(&account_id)
+            .expect("Account is not frozen");
+        AccountUnfrozen {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    // View functions
+    pub fn is_frozen(&self, account_id: AccountId) -> bool {
+        self.frozen_accounts.get(&account_id).is_some()
+    }
+
+    pub fn get_frozen_accounts(&self, from_index: u64, limit: u64) -> Vec<(AccountId, FrozenAccount)> {
+        self.frozen_accounts
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn assert_not_frozen(&self, account_id: &AccountId) {
+        assert!(
+            self.frozen_accounts.get(account_id).is_none(),
+            "Account {} is frozen",
+            account_id
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::PromiseResult;
+
+    fn freeze(contract: &mut FoodBankToken, account_id: AccountId) {
+        set_caller(owner(), 0);
+        contract.freeze_account(account_id, "Gaming contributions".to_string());
+    }
+
+    #[test]
+    fn a_redemption_in_flight_settles_after_a_freeze() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        let receipt_id = last_receipt_id(&contract);
+        freeze(&mut contract, food_bank());
+
+        resolve_callback(PromiseResult::Failed);
+        let delivered = contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            receipt_id,
+            token_charge(100),
+        );
+        assert!(!delivered);
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+        assert_eq!(contract.get_redemptions_in_flight(food_bank()), 0);
+        assert!(contract.is_frozen(food_bank()));
+    }
+
+    #[test]
+    fn freeze_and_unfreeze() {
+        let mut contract = setup_contract();
+        advance_time(42);
+        freeze(&mut contract, food_bank());
+        assert!(contract.is_frozen(food_bank()));
+        assert!(!contract.is_frozen(other_food_bank()));
+
+        let frozen = contract.get_frozen_accounts(0, 10);
+        assert_eq!(frozen.len(), 1);
+        assert_eq!(frozen[0].0, food_bank());
+        assert_eq!(frozen[0].1.frozen_at, 42);
+
+        contract.unfreeze_account(food_bank());
+        assert!(!contract.is_frozen(food_bank()));
+        contribute(&mut contract, &food_bank(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account bob is frozen")]
+    fn frozen_accounts_cannot_receive_contributions() {
+        let mut contract = setup_contract();
+        freeze(&mut contract, food_bank());
+        contribute(&mut contract, &food_bank(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account bob is frozen")]
+    fn frozen_accounts_cannot_redeem() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        freeze(&mut contract, food_bank());
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The owner account cannot be frozen")]
+    fn owner_cannot_be_frozen() {
+        let mut contract = setup_contract();
+        freeze(&mut contract, owner());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is already frozen")]
+    fn freezing_twice_panics() {
+        let mut contract = setup_contract();
+        freeze(&mut contract, food_bank());
+        freeze(&mut contract, food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is not frozen")]
+    fn unfreezing_an_active_account_panics() {
+        let mut contract = setup_contract();
+        contract.unfreeze_account(food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn freeze_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(other_food_bank(), 0);
+        contract.freeze_account(food_bank(), "Gaming contributions".to_string());
+    }
+}
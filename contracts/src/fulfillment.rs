@@ -0,0 +1,640 @@
+//! Fulfillment receipts. A redemption whose benefit call succeeded is only
+//! Delivered; the redeemer confirms it or reports it unfulfilled within the
+//! fulfillment window, and a Delivered receipt confirms itself once the
+//! window has passed. An admin resolves reported receipts.
+//!
+//! While its benefit call is in flight a receipt is Pending, and the
+//! redeeming account can't be archived or clear its profile, so the
+//! callback settles against the state the redemption started from.
+//! Freezing the account meanwhile is allowed and doesn't change how the
+//! callback settles: a failed call still refunds the tokens, which stay
+//! frozen with the rest of the balance.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, FulfillmentUpdated};
+use crate::roles::Role;
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_FULFILLMENT_WINDOW: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+const MAX_REPORT_REASON_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum FulfillmentStatus {
+    /// The benefit call has not resolved yet.
+    Pending,
+    Delivered,
+    Confirmed,
+    Disputed,
+    /// Re-credited because the benefit call failed, an admin upheld a
+    /// dispute or a grant application was declined.
+    Refunded,
+}
+
+impl FulfillmentStatus {
+    /// The transitions a receipt may make:
+    ///
+    /// - Pending to Delivered or Refunded, when the benefit call resolves;
+    /// - Delivered to Confirmed, or to Disputed when reported in the window;
+    /// - Disputed to Confirmed or Refunded, when an admin resolves it;
+    /// - Delivered to Refunded, when a grant application is declined.
+    ///
+    /// Confirmed and Refunded are final.
+    pub(crate) fn can_become(self, next: FulfillmentStatus) -> bool {
+        use FulfillmentStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Delivered | Refunded)
+                | (Delivered, Confirmed | Disputed | Refunded)
+                | (Disputed, Confirmed | Refunded)
+        )
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Receipt {
+    pub id: u64,
+    pub account_id: AccountId,
+    pub option_id: String,
+    /// Tokens burned, which is what was actually paid.
+    pub amount: U128,
+    pub delivered_at: u64,
+    /// Reports are accepted strictly before this timestamp.
+    pub confirm_by: u64,
+    pub status: FulfillmentStatus,
+    pub reason: Option<String>,
+    /// NEAR attached on top of the tokens; zero for token-only options.
+    pub near_copay: U128,
+    /// Hash of the promo code that discounted the redemption.
+    pub promo_code_hash: Option<Base64VecU8>,
+    /// The option's cost before tenure and promo discounts.
+    pub list_price: U128,
+}
+
+/// What a redemption charged, as recorded on its receipt.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RedemptionCharge {
+    /// Tokens burned.
+    pub amount: U128,
+    /// The option's cost before any discount.
+    pub list_price: U128,
+    pub near_copay: U128,
+    pub promo_code_hash: Option<Base64VecU8>,
+}
+
+/// Per-option outcomes. A receipt that was disputed and then confirmed
+/// counts in both.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FulfillmentStats {
+    pub confirmed: u64,
+    pub disputed: u64,
+    pub refunded: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn confirm_fulfillment(&mut self, receipt_id: u64) {
+        self.internal_confirm_fulfillment(&env::predecessor_account_id(), receipt_id);
+    }
+
+    pub fn report_unfulfilled(&mut self, receipt_id: u64, reason: String) {
+        self.internal_report_unfulfilled(&env::predecessor_account_id(), receipt_id, reason);
+    }
+
+    /// Settles a disputed receipt, either re-crediting the tokens spent or
+    /// confirming the delivery anyway.
+    pub fn resolve_fulfillment(&mut self, receipt_id: u64, refund: bool) {
+        self.assert_role(Role::Admin);
+        let mut receipt = self.receipts.get(&receipt_id).expect("Receipt not found");
+        assert_eq!(receipt.status, FulfillmentStatus::Disputed, "Receipt is not disputed");
+        if refund {
+            self.refund_receipt(&mut receipt);
+        } else {
+            self.set_receipt_status(&mut receipt, FulfillmentStatus::Confirmed);
+        }
+    }
+
+    pub fn set_fulfillment_window(&mut self, window_ns: u64) {
+        self.assert_owner_action();
+        self.fulfillment_window = window_ns;
+    }
+
+    // View functions
+    /// Shows a Delivered receipt whose window has passed as Confirmed.
+    pub fn get_receipt(&self, receipt_id: u64) -> Option<Receipt> {
+        let mut receipt = self.receipts.get(&receipt_id)?;
+        if receipt.is_expired() {
+            receipt.status = FulfillmentStatus::Confirmed;
+        }
+        Some(receipt)
+    }
+
+    pub fn get_fulfillment_stats(&self, option_id: String) -> FulfillmentStats {
+        self.fulfillment_stats.get(&option_id).unwrap_or_default()
+    }
+
+    pub fn get_fulfillment_window(&self) -> u64 {
+        self.fulfillment_window
+    }
+
+    /// Redemptions of the account whose benefit call hasn't resolved.
+    pub fn get_redemptions_in_flight(&self, account_id: AccountId) -> u32 {
+        self.redemptions_in_flight.get(&account_id).unwrap_or(0)
+    }
+}
+
+impl Receipt {
+    fn is_expired(&self) -> bool {
+        self.status == FulfillmentStatus::Delivered && env::block_timestamp() >= self.confirm_by
+    }
+}
+
+impl FoodBankToken {
+    /// `account_id` is the redeemer, whether it called directly or through
+    /// `relay_call`.
+    pub(crate) fn internal_confirm_fulfillment(&mut self, account_id: &AccountId, receipt_id: u64) {
+        let mut receipt = self.settled_receipt(receipt_id);
+        assert_eq!(
+            *account_id,
+            receipt.account_id,
+            "Only the redeemer can confirm this receipt"
+        );
+        if receipt.status == FulfillmentStatus::Confirmed {
+            // Confirmed on expiry by the lookup above.
+            return;
+        }
+        assert_eq!(receipt.status, FulfillmentStatus::Delivered, "Receipt is disputed");
+        self.set_receipt_status(&mut receipt, FulfillmentStatus::Confirmed);
+    }
+
+    pub(crate) fn internal_report_unfulfilled(&mut self, account_id: &AccountId, receipt_id: u64, reason: String) {
+        self.assert_not_paused();
+        assert!(
+            reason.len() <= MAX_REPORT_REASON_LENGTH,
+            "Reason exceeds {} bytes",
+            MAX_REPORT_REASON_LENGTH
+        );
+        let mut receipt = self.settled_receipt(receipt_id);
+        assert_eq!(
+            *account_id,
+            receipt.account_id,
+            "Only the redeemer can report this receipt"
+        );
+        assert!(
+            env::block_timestamp() < receipt.confirm_by,
+            "Fulfillment window closed at {}",
+            receipt.confirm_by
+        );
+        assert_eq!(receipt.status, FulfillmentStatus::Delivered, "Receipt is already disputed");
+        receipt.reason = Some(reason);
+        self.set_receipt_status(&mut receipt, FulfillmentStatus::Disputed);
+    }
+
+    /// Stores the receipt reserved for a redemption: Pending while its
+    /// benefit call is in flight, then as it resolved. Failed calls are kept
+    /// as Refunded so `tick` can walk past them.
+    pub(crate) fn record_receipt(
+        &mut self,
+        id: u64,
+        account_id: &AccountId,
+        option_id: &str,
+        charge: RedemptionCharge,
+        status: FulfillmentStatus,
+    ) {
+        let previous = self.receipts.get(&id).map(|existing| existing.status);
+        if let Some(previous) = previous {
+            assert!(
+                previous.can_become(status),
+                "Receipt {} cannot go from {:?} to {:?}",
+                id,
+                previous,
+                status
+            );
+        }
+        self.count_delivered_redemption(account_id, option_id, previous, status);
+        let now = env::block_timestamp();
+        self.receipts.insert(
+            &id,
+            &Receipt {
+                id,
+                account_id: account_id.clone(),
+                option_id: option_id.to_string(),
+                amount: charge.amount,
+                delivered_at: now,
+                confirm_by: now + self.fulfillment_window,
+                status,
+                reason: None,
+                near_copay: charge.near_copay,
+                promo_code_hash: charge.promo_code_hash,
+                list_price: charge.list_price,
+            },
+        );
+    }
+
+    /// Re-credits the tokens a delivered redemption burned, unless the
+    /// redeemer has already confirmed it. Returns the amount refunded.
+    pub(crate) fn refund_unconfirmed_receipt(&mut self, receipt_id: u64) -> Balance {
+        let mut receipt = self.settled_receipt(receipt_id);
+        match receipt.status {
+            FulfillmentStatus::Delivered | FulfillmentStatus::Disputed => {
+                self.refund_receipt(&mut receipt);
+                receipt.amount.0
+            }
+            _ => 0,
+        }
+    }
+
+    /// The co-pay is not returned.
+    fn refund_receipt(&mut self, receipt: &mut Receipt) {
+        self.mint(receipt.account_id.clone(), receipt.amount.0);
+        self.tag_statement_flow(&receipt.account_id, StatementFlow::Refund, receipt.amount.0);
+        self.revert_option_redemption(&receipt.account_id, &receipt.option_id, receipt.amount.0);
+        self.reset_redemption_cooldown(&receipt.account_id, &receipt.option_id, receipt.delivered_at);
+        self.set_receipt_status(receipt, FulfillmentStatus::Refunded);
+    }
+
+    /// Loads a receipt, first confirming it if its window has passed.
+    fn settled_receipt(&mut self, receipt_id: u64) -> Receipt {
+        let mut receipt = self.receipts.get(&receipt_id).expect("Receipt not found");
+        assert!(receipt.status != FulfillmentStatus::Pending, "Receipt is still pending");
+        if receipt.is_expired() {
+            self.set_receipt_status(&mut receipt, FulfillmentStatus::Confirmed);
+        }
+        receipt
+    }
+
+    fn set_receipt_status(&mut self, receipt: &mut Receipt, status: FulfillmentStatus) {
+        assert!(
+            receipt.status.can_become(status),
+            "Receipt {} cannot go from {:?} to {:?}",
+            receipt.id,
+            receipt.status,
+            status
+        );
+        self.count_delivered_redemption(&receipt.account_id, &receipt.option_id, Some(receipt.status), status);
+        receipt.status = status;
+        self.receipts.insert(&receipt.id, receipt);
+        let mut stats = self.fulfillment_stats.get(&receipt.option_id).unwrap_or_default();
+        match status {
+            FulfillmentStatus::Confirmed => stats.confirmed += 1,
+            FulfillmentStatus::Disputed => stats.disputed += 1,
+            FulfillmentStatus::Refunded => stats.refunded += 1,
+            FulfillmentStatus::Pending | FulfillmentStatus::Delivered => {}
+        }
+        self.fulfillment_stats.insert(&receipt.option_id, &stats);
+        FulfillmentUpdated {
+            receipt_id: receipt.id,
+            account_id: &receipt.account_id,
+            option_id: &receipt.option_id,
+            status,
+            reason: receipt.reason.as_deref(),
+        }
+        .emit();
+    }
+
+    /// Whether the receipt `tick` resumes at can be moved past. A missing
+    /// receipt is skipped: its benefit call was scheduled before receipts
+    /// were stored up front, and if the callback stores it later it still
+    /// shows as Confirmed once its window has passed.
+    pub(crate) fn receipt_expiry_due(&self) -> bool {
+        if self.next_receipt_to_expire == self.next_receipt_id {
+            return false;
+        }
+        self.receipts
+            .get(&self.next_receipt_to_expire)
+            .is_none_or(|receipt| match receipt.status {
+                FulfillmentStatus::Pending => false,
+                FulfillmentStatus::Delivered => receipt.is_expired(),
+                _ => true,
+            })
+    }
+
+    pub(crate) fn begin_redemption_in_flight(&mut self, account_id: &AccountId) {
+        self.pending_redemptions += 1;
+        let count = self.get_redemptions_in_flight(account_id.clone());
+        self.redemptions_in_flight.insert(account_id, &(count + 1));
+    }
+
+    pub(crate) fn end_redemption_in_flight(&mut self, account_id: &AccountId) {
+        self.pending_redemptions = self.pending_redemptions.saturating_sub(1);
+        match self.get_redemptions_in_flight(account_id.clone()) {
+            0 | 1 => self.redemptions_in_flight.remove(account_id),
+            count => self.redemptions_in_flight.insert(account_id, &(count - 1)),
+        };
+    }
+
+    pub(crate) fn assert_no_redemptions_in_flight(&self, account_id: &AccountId) {
+        assert!(
+            self.redemptions_in_flight.get(account_id).is_none(),
+            "Account has redemptions in flight"
+        );
+    }
+
+    /// Walks up to `limit` receipts in delivery order, confirming expired
+    /// ones, and stops at the first that is still open. Returns how many
+    /// were confirmed.
+    pub(crate) fn confirm_expired_receipts(&mut self, limit: u64) -> u64 {
+        let mut confirmed = 0;
+        for _ in 0..limit {
+            if !self.receipt_expiry_due() {
+                break;
+            }
+            if let Some(mut receipt) = self.receipts.get(&self.next_receipt_to_expire) {
+                if receipt.status == FulfillmentStatus::Delivered {
+                    self.set_receipt_status(&mut receipt, FulfillmentStatus::Confirmed);
+                    confirmed += 1;
+                }
+            }
+            self.next_receipt_to_expire += 1;
+        }
+        confirmed
+    }
+
+    /// Delivered and Confirmed receipts are what a prerequisite counts;
+    /// keeps `delivered_redemption_counts` in step as a receipt moves.
+    fn count_delivered_redemption(
+        &mut self,
+        account_id: &AccountId,
+        option_id: &str,
+        previous: Option<FulfillmentStatus>,
+        status: FulfillmentStatus,
+    ) {
+        let holds = |status: FulfillmentStatus| {
+            matches!(status, FulfillmentStatus::Delivered | FulfillmentStatus::Confirmed)
+        };
+        let held = previous.is_some_and(holds);
+        if held == holds(status) {
+            return;
+        }
+        let key = (account_id.clone(), option_id.to_string());
+        let count = self.delivered_redemptions(&key);
+        let count = if held { count.saturating_sub(1) } else { count + 1 };
+        self.delivered_redemption_counts.insert(&key, &count);
+    }
+
+    /// Receipts of the account's redemptions of the option that are
+    /// Delivered or Confirmed. Until the account first redeems the option
+    /// after version 44 its redemption count stands in, since receipts
+    /// stored before weren't walked to count them.
+    pub(crate) fn delivered_redemptions(&self, key: &(AccountId, String)) -> u64 {
+        self.delivered_redemption_counts
+            .get(key)
+            .unwrap_or_else(|| self.account_redemption_counts.get(key).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::PromiseResult;
+
+    fn redeemed() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 200);
+        redeem(&mut contract);
+        contract
+    }
+
+    fn redeem(contract: &mut FoodBankToken) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(contract),
+            token_charge(100),
+        );
+        set_caller(food_bank(), 0);
+    }
+
+    fn report(contract: &mut FoodBankToken) {
+        contract.report_unfulfilled(0, "Discount was not applied".to_string());
+    }
+
+    fn stats(contract: &FoodBankToken) -> FulfillmentStats {
+        contract.get_fulfillment_stats("supplier_discount".to_string())
+    }
+
+    #[test]
+    fn delivery_waits_for_confirmation() {
+        let mut contract = redeemed();
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Delivered);
+        contract.confirm_fulfillment(0);
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Confirmed);
+        assert_eq!(stats(&contract).confirmed, 1);
+    }
+
+    #[test]
+    fn expired_receipts_confirm_lazily() {
+        let mut contract = redeemed();
+        advance_time(DEFAULT_FULFILLMENT_WINDOW);
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Confirmed);
+        assert_eq!(stats(&contract).confirmed, 0);
+        contract.confirm_fulfillment(0);
+        assert_eq!(stats(&contract).confirmed, 1);
+    }
+
+    #[test]
+    fn tick_confirms_expired_receipts() {
+        let mut contract = redeemed();
+        contract.mint(food_bank(), 100);
+        redeem(&mut contract);
+        contract.confirm_fulfillment(1);
+        advance_time(DEFAULT_FULFILLMENT_WINDOW);
+        redeem(&mut contract);
+
+        assert_eq!(contract.tick().receipts_confirmed, 1);
+        assert_eq!(stats(&contract).confirmed, 2);
+        assert_eq!(contract.get_receipt(2).unwrap().status, FulfillmentStatus::Delivered);
+        assert_eq!(contract.tick().receipts_confirmed, 0);
+    }
+
+    #[test]
+    fn tick_skips_receipts_that_were_never_stored() {
+        let mut contract = setup_contract();
+        // Receipt 0 stands for a benefit call scheduled before receipts were
+        // stored up front.
+        contract.next_receipt_id = 1;
+        contract.mint(food_bank(), 100);
+        redeem(&mut contract);
+        advance_time(DEFAULT_FULFILLMENT_WINDOW);
+
+        assert_eq!(contract.tick().receipts_confirmed, 1);
+        assert_eq!(contract.get_receipt(1).unwrap().status, FulfillmentStatus::Confirmed);
+        assert!(!contract.receipt_expiry_due());
+    }
+
+    #[test]
+    fn disputed_receipt_can_be_refunded() {
+        let mut contract = redeemed();
+        report(&mut contract);
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Disputed);
+
+        set_caller(owner(), 0);
+        contract.resolve_fulfillment(0, true);
+        assert_eq!(contract.get_balance(food_bank()).0, 200);
+        assert_eq!(contract.get_option_stats("supplier_discount".to_string()).redemptions_count, 0);
+        assert_eq!(
+            stats(&contract),
+            FulfillmentStats {
+                confirmed: 0,
+                disputed: 1,
+                refunded: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn disputed_receipt_can_be_confirmed_anyway() {
+        let mut contract = redeemed();
+        report(&mut contract);
+        set_caller(owner(), 0);
+        contract.resolve_fulfillment(0, false);
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Confirmed);
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+        assert_eq!(stats(&contract).disputed, 1);
+        assert_eq!(stats(&contract).confirmed, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Fulfillment window closed at 604800000000000")]
+    fn reports_are_rejected_after_the_window() {
+        let mut contract = redeemed();
+        advance_time(DEFAULT_FULFILLMENT_WINDOW);
+        report(&mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the redeemer can confirm this receipt")]
+    fn only_the_redeemer_confirms() {
+        let mut contract = redeemed();
+        set_caller(other_food_bank(), 0);
+        contract.confirm_fulfillment(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Receipt is not disputed")]
+    fn only_disputed_receipts_are_resolved() {
+        let mut contract = redeemed();
+        set_caller(owner(), 0);
+        contract.resolve_fulfillment(0, true);
+    }
+
+    fn redeem_in_flight(contract: &mut FoodBankToken) {
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    fn resolve(contract: &mut FoodBankToken, result: PromiseResult) -> bool {
+        resolve_callback(result);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(contract),
+            token_charge(100),
+        )
+    }
+
+    #[test]
+    fn receipts_are_pending_until_the_callback() {
+        let mut contract = setup_contract();
+        redeem_in_flight(&mut contract);
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Pending);
+        assert_eq!(contract.get_redemptions_in_flight(food_bank()), 1);
+        assert!(!contract.receipt_expiry_due());
+
+        assert!(resolve(&mut contract, PromiseResult::Successful(vec![])));
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Delivered);
+        assert_eq!(contract.get_redemptions_in_flight(food_bank()), 0);
+    }
+
+    #[test]
+    fn a_settled_receipt_ignores_a_late_callback() {
+        let mut contract = setup_contract();
+        redeem_in_flight(&mut contract);
+        assert!(!resolve(&mut contract, PromiseResult::Failed));
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+
+        // A second resolution must not refund again or touch the counters.
+        assert!(!resolve(&mut contract, PromiseResult::Failed));
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Refunded);
+        assert_eq!(contract.get_status().pending_redemptions, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Receipt is still pending")]
+    fn pending_receipts_cannot_be_confirmed() {
+        let mut contract = setup_contract();
+        redeem_in_flight(&mut contract);
+        set_caller(food_bank(), 0);
+        contract.confirm_fulfillment(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account has redemptions in flight")]
+    fn history_is_not_archived_under_a_redemption() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        redeem_in_flight(&mut contract);
+        set_caller(owner(), 0);
+        contract.archive_contributions(food_bank(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account has redemptions in flight")]
+    fn profiles_are_not_cleared_under_a_redemption() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), ONE_NEAR);
+        contract.register_profile(
+            "Lagos Food Bank".to_string(),
+            "Lagos".to_string(),
+            crate::profiles::OrgType::FoodBank,
+            None,
+        );
+        redeem_in_flight(&mut contract);
+        set_caller(food_bank(), 0);
+        contract.clear_profile();
+    }
+
+    #[test]
+    fn archiving_resumes_once_redemptions_resolve() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        redeem_in_flight(&mut contract);
+        resolve(&mut contract, PromiseResult::Successful(vec![]));
+        set_caller(owner(), 0);
+        assert_eq!(contract.archive_contributions(food_bank(), 0).count, 1);
+    }
+
+    #[test]
+    fn only_listed_transitions_are_allowed() {
+        use FulfillmentStatus::*;
+        let all = [Pending, Delivered, Confirmed, Disputed, Refunded];
+        let allowed = [
+            (Pending, Delivered),
+            (Pending, Refunded),
+            (Delivered, Confirmed),
+            (Delivered, Disputed),
+            (Delivered, Refunded),
+            (Disputed, Confirmed),
+            (Disputed, Refunded),
+        ];
+        for from in all {
+            for to in all {
+                assert_eq!(from.can_become(to), allowed.contains(&(from, to)), "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+}
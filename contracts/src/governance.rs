@@ -0,0 +1,720 @@
+//! Token-weighted governance over program parameters. A passed proposal
+//! runs its action through the same internal setters the owner's methods
+//! use; an action that can no longer run, say because its option was
+//! removed, leaves the proposal `ExecutionFailed` instead of failing the
+//! finalize call.
+//!
+//! Voting weight is the voter's balance when the proposal was created. While
+//! a proposal is open, the first change to any balance copies the old value
+//! into `snapshot_balances`, so later mints, redemptions or transfers don't
+//! move votes. Each proposal's id doubles as the id of its snapshot.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, ProposalCreated, ProposalExecuted, ProposalFinalized, VoteCast};
+use crate::timelock::ParamChange;
+use crate::treasury::MAX_TREASURY_SHARE_BPS;
+use crate::{assert_valid_option_id, assert_valid_option_name, FoodBankToken, FoodBankTokenExt};
+
+const MAX_DESCRIPTION_LENGTH: usize = 1_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ProposalAction {
+    SetRewardRateBps(u16),
+    SetMinScoreThreshold(u32),
+    AddRedemptionOption {
+        option_id: String,
+        name: String,
+        cost: U128,
+        description: String,
+    },
+    SetOptionAvailable {
+        option_id: String,
+        available: bool,
+    },
+    /// Keeps the current treasury account.
+    SetTreasuryShareBps(u16),
+    Pause,
+    /// The only action that can be proposed, voted on and finalized while
+    /// the contract is paused.
+    Unpause,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ProposalStatus {
+    Active,
+    Executed,
+    Rejected,
+    ExecutionFailed,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GovernanceConfig {
+    /// Balance an account needs to open a proposal.
+    pub proposal_threshold: U128,
+    /// Total weight that must vote for a result to count.
+    pub quorum: U128,
+    pub voting_period_ns: u64,
+}
+
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        Self {
+            proposal_threshold: U128(100),
+            quorum: U128(1_000),
+            voting_period_ns: 7 * 24 * 60 * 60 * 1_000_000_000,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Proposal {
+    id: u64,
+    proposer: AccountId,
+    action: ProposalAction,
+    description: String,
+    created_at: u64,
+    voting_ends_at: u64,
+    votes_for: U128,
+    votes_against: U128,
+    status: ProposalStatus,
+    /// Why the action could not run, for `ExecutionFailed` proposals.
+    failure_reason: Option<String>,
+}
+
+/// What proposals could change before `ProposalAction`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum ProposalKind {
+    SetRewardRateBps(u16),
+    SetScoreThreshold(u32),
+}
+
+/// The layout proposals were stored with before `ProposalAction`; see
+/// `migration.rs`. Its `Passed` status applied the change on the spot and
+/// sat where `Executed` is now, so it decodes as `Executed`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ProposalV1 {
+    pub(crate) id: u64,
+    pub(crate) proposer: AccountId,
+    pub(crate) kind: ProposalKind,
+    pub(crate) description: String,
+    pub(crate) created_at: u64,
+    pub(crate) voting_ends_at: u64,
+    pub(crate) votes_for: U128,
+    pub(crate) votes_against: U128,
+    pub(crate) status: ProposalStatus,
+}
+
+impl From<ProposalV1> for Proposal {
+    fn from(proposal: ProposalV1) -> Self {
+        let action = match proposal.kind {
+            ProposalKind::SetRewardRateBps(rate_bps) => ProposalAction::SetRewardRateBps(rate_bps),
+            ProposalKind::SetScoreThreshold(threshold) => ProposalAction::SetMinScoreThreshold(threshold),
+        };
+        Self {
+            id: proposal.id,
+            proposer: proposal.proposer,
+            action,
+            description: proposal.description,
+            created_at: proposal.created_at,
+            voting_ends_at: proposal.voting_ends_at,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            status: proposal.status,
+            failure_reason: None,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Vote {
+    approve: bool,
+    weight: U128,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn create_proposal(&mut self, action: ProposalAction, description: String) -> u64 {
+        action.assert_not_paused(self);
+        assert!(
+            description.len() <= MAX_DESCRIPTION_LENGTH,
+            "Description exceeds {} bytes",
+            MAX_DESCRIPTION_LENGTH
+        );
+        let proposer = env::predecessor_account_id();
+        let balance = self.balances.get(&proposer).unwrap_or(0);
+        assert!(
+            balance >= self.governance_config.proposal_threshold.0,
+            "Creating a proposal requires a balance of at least {}",
+            self.governance_config.proposal_threshold.0
+        );
+        self.validate_proposal_action(&action);
+        if let Some(reason) = self.proposal_action_error(&action) {
+            panic!("{}", reason);
+        }
+
+        let now = env::block_timestamp();
+        let id = self.next_proposal_id;
+        self.next_proposal_id += 1;
+        let proposal = Proposal {
+            id,
+            proposer,
+            action,
+            description,
+            created_at: now,
+            voting_ends_at: now + self.governance_config.voting_period_ns,
+            votes_for: U128(0),
+            votes_against: U128(0),
+            status: ProposalStatus::Active,
+            failure_reason: None,
+        };
+        self.proposals.insert(&id, &proposal);
+
+        self.open_snapshots.retain(|(_, ends_at)| *ends_at >= now);
+        self.open_snapshots.push((id, proposal.voting_ends_at));
+
+        ProposalCreated {
+            id,
+            proposer: &proposal.proposer,
+            action: &proposal.action,
+            voting_ends_at: proposal.voting_ends_at,
+        }
+        .emit();
+        id
+    }
+
+    pub fn vote(&mut self, proposal_id: u64, approve: bool) {
+        let mut proposal = self.proposals.get(&proposal_id)
+            .expect("Proposal not found");
+        proposal.action.assert_not_paused(self);
+        assert!(
+            env::block_timestamp() <= proposal.voting_ends_at,
+            "Voting period has ended"
+        );
+        let voter = env::predecessor_account_id();
+        let key = (proposal_id, voter.clone());
+        if let Some(delegate) = self.delegations.get(&voter) {
+            panic!("Account has delegated its vote to {}", delegate);
+        }
+        assert!(self.votes.get(&key).is_none(), "Account has already voted");
+        if let Some(delegate) = self.delegated_votes.get(&key) {
+            panic!("Account's weight was already cast by {}", delegate);
+        }
+
+        let delegated = self.uncast_delegated_weight(proposal_id, &voter);
+        let weight = self.snapshot_balance(proposal_id, &voter)
+            + delegated.iter().map(|(_, balance)| balance).sum::<Balance>();
+        assert!(weight > 0, "Account has no voting power for this proposal");
+        for (delegator, _) in &delegated {
+            self.delegated_votes.insert(&(proposal_id, delegator.clone()), &voter);
+        }
+        if approve {
+            proposal.votes_for = U128(proposal.votes_for.0 + weight);
+        } else {
+            proposal.votes_against = U128(proposal.votes_against.0 + weight);
+        }
+        self.votes.insert(&key, &Vote { approve, weight: U128(weight) });
+        self.proposals.insert(&proposal_id, &proposal);
+
+        VoteCast {
+            proposal_id,
+            voter: &voter,
+            approve,
+            weight: U128(weight),
+        }
+        .emit();
+    }
+
+    /// Closes voting and, if quorum and a simple majority were reached,
+    /// runs the proposed action. Callable by anyone after the deadline.
+    pub fn finalize_proposal(&mut self, proposal_id: u64) -> ProposalStatus {
+        let mut proposal = self.proposals.get(&proposal_id)
+            .expect("Proposal not found");
+        proposal.action.assert_not_paused(self);
+        assert_eq!(proposal.status, ProposalStatus::Active, "Proposal is already finalized");
+        assert!(
+            env::block_timestamp() > proposal.voting_ends_at,
+            "Voting period has not ended"
+        );
+
+        let total_votes = proposal.votes_for.0 + proposal.votes_against.0;
+        let passed = total_votes >= self.governance_config.quorum.0
+            && proposal.votes_for.0 > proposal.votes_against.0;
+        proposal.status = ProposalStatus::Rejected;
+        if passed {
+            let failure_reason = self.proposal_action_error(&proposal.action);
+            if failure_reason.is_none() {
+                self.execute_proposal_action(&proposal.action);
+                proposal.status = ProposalStatus::Executed;
+            } else {
+                proposal.status = ProposalStatus::ExecutionFailed;
+            }
+            ProposalExecuted {
+                id: proposal_id,
+                action: &proposal.action,
+                failure_reason: failure_reason.as_deref(),
+            }
+            .emit();
+            proposal.failure_reason = failure_reason;
+        }
+        self.proposals.insert(&proposal_id, &proposal);
+
+        ProposalFinalized {
+            id: proposal_id,
+            status: proposal.status,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+        }
+        .emit();
+        proposal.status
+    }
+
+    pub fn set_governance_config(&mut self, config: GovernanceConfig) {
+        self.assert_owner_action();
+        assert!(config.voting_period_ns > 0, "Voting period must be positive");
+        self.governance_config = config;
+    }
+
+    // View functions
+    pub fn get_proposal(&self, proposal_id: u64) -> Option<Proposal> {
+        self.proposals.get(&proposal_id)
+    }
+
+    pub fn get_proposals(&self, from_index: u64, limit: u64) -> Vec<Proposal> {
+        self.proposals
+            .values()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    /// The weight `account_id` would vote with on `proposal_id`: its balance
+    /// when the proposal was created plus that of delegators whose weight
+    /// hasn't been cast yet. Zero while the account delegates.
+    pub fn get_voting_power(&self, proposal_id: u64, account_id: AccountId) -> U128 {
+        assert!(self.proposals.get(&proposal_id).is_some(), "Proposal not found");
+        if self.delegations.get(&account_id).is_some() {
+            return U128(0);
+        }
+        let delegated: Balance = self
+            .uncast_delegated_weight(proposal_id, &account_id)
+            .iter()
+            .map(|(_, balance)| balance)
+            .sum();
+        U128(self.snapshot_balance(proposal_id, &account_id) + delegated)
+    }
+
+    pub fn get_vote(&self, proposal_id: u64, account_id: AccountId) -> Option<Vote> {
+        self.votes.get(&(proposal_id, account_id))
+    }
+
+    pub fn get_governance_config(&self) -> GovernanceConfig {
+        self.governance_config.clone()
+    }
+}
+
+impl ProposalAction {
+    fn assert_not_paused(&self, contract: &FoodBankToken) {
+        if *self != ProposalAction::Unpause {
+            contract.assert_not_paused();
+        }
+    }
+}
+
+impl FoodBankToken {
+    /// Checks that don't depend on state that can change while the vote
+    /// runs, with the owner setters' messages.
+    fn validate_proposal_action(&self, action: &ProposalAction) {
+        match action {
+            ProposalAction::SetRewardRateBps(rate_bps) => {
+                self.validate_param_change(&ParamChange::RewardRateBps(*rate_bps));
+            }
+            ProposalAction::SetMinScoreThreshold(threshold) => {
+                self.validate_param_change(&ParamChange::MinScoreThreshold(*threshold));
+            }
+            ProposalAction::AddRedemptionOption { option_id, name, .. } => {
+                assert_valid_option_id(option_id);
+                assert_valid_option_name(name);
+            }
+            ProposalAction::SetTreasuryShareBps(share_bps) => assert!(
+                *share_bps <= MAX_TREASURY_SHARE_BPS,
+                "Treasury share must be <= {} bps",
+                MAX_TREASURY_SHARE_BPS
+            ),
+            ProposalAction::SetOptionAvailable { .. }
+            | ProposalAction::Pause
+            | ProposalAction::Unpause => {}
+        }
+    }
+
+    /// Why `action` would fail if it ran now.
+    fn proposal_action_error(&self, action: &ProposalAction) -> Option<String> {
+        match action {
+            ProposalAction::AddRedemptionOption { option_id, .. }
+                if self.has_redemption_option(option_id) =>
+            {
+                Some(format!("Redemption option {} already exists", option_id))
+            }
+            ProposalAction::SetOptionAvailable { option_id, .. }
+                if !self.has_redemption_option(option_id) =>
+            {
+                Some("Redemption option not found".to_string())
+            }
+            ProposalAction::SetTreasuryShareBps(share_bps)
+                if *share_bps > 0 && self.treasury_account.is_none() =>
+            {
+                Some("A treasury account is required for a non-zero share".to_string())
+            }
+            ProposalAction::Pause if self.paused => Some("Contract is already paused".to_string()),
+            ProposalAction::Unpause if !self.paused => Some("Contract is not paused".to_string()),
+            _ => None,
+        }
+    }
+
+    fn execute_proposal_action(&mut self, action: &ProposalAction) {
+        match action.clone() {
+            ProposalAction::SetRewardRateBps(rate_bps) => {
+                self.apply_param_change(&ParamChange::RewardRateBps(rate_bps));
+            }
+            ProposalAction::SetMinScoreThreshold(threshold) => {
+                self.apply_param_change(&ParamChange::MinScoreThreshold(threshold));
+            }
+            ProposalAction::AddRedemptionOption {
+                option_id,
+                name,
+                cost,
+                description,
+            } => self.internal_add_redemption_option(option_id, name, cost.0, description, None),
+            ProposalAction::SetOptionAvailable { option_id, available } => {
+                self.internal_update_redemption_option(option_id, None, None, Some(available));
+            }
+            ProposalAction::SetTreasuryShareBps(share_bps) => {
+                self.internal_set_treasury(self.treasury_account.clone(), share_bps);
+            }
+            ProposalAction::Pause => self.internal_pause(&env::predecessor_account_id(), false),
+            ProposalAction::Unpause => self.internal_unpause(),
+        }
+    }
+}
+
+impl FoodBankToken {
+    /// Copies `old_balance` into every open snapshot that hasn't seen this
+    /// account change yet. Must run before each balance write.
+    pub(crate) fn record_snapshot_balance(&mut self, account_id: &AccountId, old_balance: Balance) {
+        let now = env::block_timestamp();
+        for (snapshot_id, ends_at) in self.open_snapshots.iter() {
+            if *ends_at < now {
+                continue;
+            }
+            let key = (*snapshot_id, account_id.clone());
+            if self.snapshot_balances.get(&key).is_none() {
+                self.snapshot_balances.insert(&key, &old_balance);
+            }
+        }
+    }
+
+    pub(crate) fn snapshot_balance(&self, snapshot_id: u64, account_id: &AccountId) -> Balance {
+        self.snapshot_balances
+            .get(&(snapshot_id, account_id.clone()))
+            .unwrap_or_else(|| self.balances.get(account_id).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::RedemptionOptionV5;
+
+    const WEEK: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+    fn setup_voters() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 700);
+        contract.mint(other_food_bank(), 400);
+        contract
+    }
+
+    fn propose(contract: &mut FoodBankToken, action: ProposalAction) -> u64 {
+        set_caller(food_bank(), 0);
+        contract.create_proposal(action, "Adjust the program".to_string())
+    }
+
+    fn vote_as(contract: &mut FoodBankToken, voter: AccountId, proposal_id: u64, approve: bool) {
+        set_caller(voter, 0);
+        contract.vote(proposal_id, approve);
+    }
+
+    #[test]
+    fn full_cycle_applies_the_change() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+        vote_as(&mut contract, food_bank(), id, true);
+        vote_as(&mut contract, other_food_bank(), id, false);
+
+        advance_time(WEEK + 1);
+        assert_eq!(contract.finalize_proposal(id), ProposalStatus::Executed);
+        assert_eq!(contract.get_config().reward_rate_bps, 1_200);
+
+        let proposal = contract.get_proposal(id).unwrap();
+        assert_eq!(proposal.votes_for.0, 700);
+        assert_eq!(proposal.votes_against.0, 400);
+    }
+
+    /// Proposes `action`, votes it through with every voter and finalizes.
+    fn pass(contract: &mut FoodBankToken, action: ProposalAction) -> (u64, ProposalStatus) {
+        let id = propose(contract, action);
+        vote_as(contract, food_bank(), id, true);
+        vote_as(contract, other_food_bank(), id, true);
+        advance_time(WEEK + 1);
+        (id, contract.finalize_proposal(id))
+    }
+
+    fn option(contract: &FoodBankToken, option_id: &str) -> Option<RedemptionOptionV5> {
+        contract
+            .get_redemption_options()
+            .into_iter()
+            .find(|(id, _)| id == option_id)
+            .map(|(_, option)| option)
+    }
+
+    #[test]
+    fn passed_threshold_change_is_executed() {
+        let mut contract = setup_voters();
+        let (_, status) = pass(&mut contract, ProposalAction::SetMinScoreThreshold(40));
+        assert_eq!(status, ProposalStatus::Executed);
+        assert_eq!(contract.get_min_score_threshold(), 40);
+    }
+
+    #[test]
+    fn passed_option_is_added() {
+        let mut contract = setup_voters();
+        let action = ProposalAction::AddRedemptionOption {
+            option_id: "cold_storage".to_string(),
+            name: "Cold storage".to_string(),
+            cost: U128(250),
+            description: "A month of shared cold storage".to_string(),
+        };
+        assert_eq!(pass(&mut contract, action).1, ProposalStatus::Executed);
+        let added = option(&contract, "cold_storage").unwrap();
+        assert_eq!(added.cost.0, 250);
+        assert!(added.available);
+    }
+
+    #[test]
+    fn passed_availability_change_is_executed() {
+        let mut contract = setup_voters();
+        let action = ProposalAction::SetOptionAvailable {
+            option_id: "supplier_discount".to_string(),
+            available: false,
+        };
+        assert_eq!(pass(&mut contract, action).1, ProposalStatus::Executed);
+        assert!(!option(&contract, "supplier_discount").unwrap().available);
+    }
+
+    #[test]
+    fn passed_treasury_share_keeps_the_treasury_account() {
+        let mut contract = setup_voters();
+        contract.set_treasury(Some(near_sdk::test_utils::accounts(3)), 100);
+        let (_, status) = pass(&mut contract, ProposalAction::SetTreasuryShareBps(1_500));
+        assert_eq!(status, ProposalStatus::Executed);
+        let config = contract.get_config();
+        assert_eq!(config.treasury_share_bps, 1_500);
+        assert_eq!(config.treasury_account, Some(near_sdk::test_utils::accounts(3)));
+    }
+
+    #[test]
+    fn governance_can_pause_and_unpause() {
+        let mut contract = setup_voters();
+        assert_eq!(pass(&mut contract, ProposalAction::Pause).1, ProposalStatus::Executed);
+        assert!(contract.is_paused());
+
+        assert_eq!(pass(&mut contract, ProposalAction::Unpause).1, ProposalStatus::Executed);
+        assert!(!contract.is_paused());
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract is paused")]
+    fn other_proposals_wait_while_paused() {
+        let mut contract = setup_voters();
+        pass(&mut contract, ProposalAction::Pause);
+        propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+    }
+
+    #[test]
+    fn actions_that_can_no_longer_run_fail_without_panicking() {
+        let mut contract = setup_voters();
+        let action = ProposalAction::AddRedemptionOption {
+            option_id: "cold_storage".to_string(),
+            name: "Cold storage".to_string(),
+            cost: U128(250),
+            description: String::new(),
+        };
+        let first = propose(&mut contract, action.clone());
+        let second = propose(&mut contract, action);
+        for id in [first, second] {
+            vote_as(&mut contract, food_bank(), id, true);
+            vote_as(&mut contract, other_food_bank(), id, true);
+        }
+        advance_time(WEEK + 1);
+
+        assert_eq!(contract.finalize_proposal(first), ProposalStatus::Executed);
+        assert_eq!(contract.finalize_proposal(second), ProposalStatus::ExecutionFailed);
+        let failed = contract.get_proposal(second).unwrap();
+        assert_eq!(
+            failed.failure_reason.as_deref(),
+            Some("Redemption option cold_storage already exists")
+        );
+        let logs = near_sdk::test_utils::get_logs();
+        assert!(logs.iter().any(|log| log.contains(r#""event":"proposal_executed""#)
+            && log.contains("already exists")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Treasury share must be <= 2000 bps")]
+    fn proposals_share_the_owner_setter_validation() {
+        let mut contract = setup_voters();
+        propose(&mut contract, ProposalAction::SetTreasuryShareBps(2_001));
+    }
+
+    #[test]
+    #[should_panic(expected = "Redemption option not found")]
+    fn proposals_that_could_not_run_now_are_rejected() {
+        let mut contract = setup_voters();
+        propose(
+            &mut contract,
+            ProposalAction::SetOptionAvailable {
+                option_id: "cold_storage".to_string(),
+                available: true,
+            },
+        );
+    }
+
+    #[test]
+    fn majority_against_rejects() {
+        let mut contract = setup_voters();
+        contract.mint(other_food_bank(), 400);
+        let id = propose(&mut contract, ProposalAction::SetMinScoreThreshold(50));
+        vote_as(&mut contract, food_bank(), id, true);
+        vote_as(&mut contract, other_food_bank(), id, false);
+
+        advance_time(WEEK + 1);
+        assert_eq!(contract.finalize_proposal(id), ProposalStatus::Rejected);
+        assert_eq!(contract.get_config().min_score_threshold, 10);
+    }
+
+    #[test]
+    fn missing_quorum_rejects() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+        vote_as(&mut contract, food_bank(), id, true);
+
+        advance_time(WEEK + 1);
+        assert_eq!(contract.finalize_proposal(id), ProposalStatus::Rejected);
+        assert_eq!(contract.get_config().reward_rate_bps, 500);
+    }
+
+    #[test]
+    fn voting_weight_is_the_balance_at_creation() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+        contract.mint(other_food_bank(), 5_000);
+
+        vote_as(&mut contract, other_food_bank(), id, true);
+        assert_eq!(contract.get_vote(id, other_food_bank()).unwrap().weight.0, 400);
+    }
+
+    #[test]
+    fn transfers_after_creation_do_not_move_voting_power() {
+        let mut contract = setup_voters();
+        let treasury = near_sdk::test_utils::accounts(3);
+        contract.set_treasury(Some(treasury.clone()), 0);
+        contract.mint(treasury.clone(), 500);
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+
+        set_caller(owner(), 1);
+        contract.treasury_transfer(food_bank(), U128(300));
+        assert_eq!(contract.get_balance(food_bank()).0, 1_000);
+        assert_eq!(contract.get_voting_power(id, food_bank()).0, 700);
+        assert_eq!(contract.get_voting_power(id, treasury.clone()).0, 500);
+
+        vote_as(&mut contract, food_bank(), id, true);
+        vote_as(&mut contract, treasury, id, true);
+        assert_eq!(contract.get_proposal(id).unwrap().votes_for.0, 1_200);
+
+        // A later proposal snapshots the balances as they are now.
+        set_caller(food_bank(), 0);
+        let later = contract.create_proposal(ProposalAction::SetRewardRateBps(900), String::new());
+        assert_eq!(contract.get_voting_power(later, food_bank()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal not found")]
+    fn voting_power_needs_a_proposal() {
+        let contract = setup_voters();
+        contract.get_voting_power(0, food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account has already voted")]
+    fn double_voting_is_rejected() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+        vote_as(&mut contract, food_bank(), id, true);
+        vote_as(&mut contract, food_bank(), id, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting period has ended")]
+    fn votes_after_deadline_are_rejected() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+        advance_time(WEEK + 1);
+        vote_as(&mut contract, other_food_bank(), id, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting period has not ended")]
+    fn finalize_waits_for_the_deadline() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+        contract.finalize_proposal(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal is already finalized")]
+    fn finalize_runs_once() {
+        let mut contract = setup_voters();
+        let id = propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+        advance_time(WEEK + 1);
+        contract.finalize_proposal(id);
+        contract.finalize_proposal(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Creating a proposal requires a balance of at least 100")]
+    fn proposers_need_the_token_threshold() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 99);
+        propose(&mut contract, ProposalAction::SetRewardRateBps(1_200));
+    }
+
+    #[test]
+    fn proposals_are_paginated() {
+        let mut contract = setup_voters();
+        for rate in 1..=3 {
+            propose(&mut contract, ProposalAction::SetRewardRateBps(rate * 100));
+        }
+        let page = contract.get_proposals(1, 5);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].id, 1);
+    }
+}
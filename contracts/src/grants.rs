@@ -0,0 +1,404 @@
+//! Grant applications. Redeeming a grant option opens an application once
+//! the redemption is delivered, which admins move from Submitted through
+//! Shortlisted to Awarded or Declined. Options flagged to refund on decline
+//! re-credit the tokens when the application is declined within the refund
+//! window of its submission, provided the redeemer hasn't confirmed the
+//! receipt in the meantime.
+
+use std::ops::Bound;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{Event, GrantApplicationReviewed, GrantApplicationSubmitted};
+use crate::fulfillment::DEFAULT_FULFILLMENT_WINDOW;
+use crate::roles::Role;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_GRANT_REFUND_WINDOW: u64 = DEFAULT_FULFILLMENT_WINDOW;
+const MAX_DECISION_NOTE_LENGTH: usize = 256;
+
+/// Ordered as listed, which orders the review queue index.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GrantStatus {
+    Submitted,
+    Shortlisted,
+    Awarded,
+    Declined,
+}
+
+impl GrantStatus {
+    /// Submitted and Shortlisted applications can be shortlisted, awarded
+    /// or declined; Awarded and Declined are final.
+    fn can_become(self, next: GrantStatus) -> bool {
+        use GrantStatus::*;
+        matches!(
+            (self, next),
+            (Submitted, Shortlisted | Awarded | Declined) | (Shortlisted, Awarded | Declined)
+        )
+    }
+}
+
+/// Marks an option as a grant option.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GrantOption {
+    pub refund_on_decline: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GrantApplication {
+    pub application_id: u64,
+    pub account_id: AccountId,
+    pub option_id: String,
+    pub receipt_id: u64,
+    pub status: GrantStatus,
+    pub submitted_at: u64,
+    pub decision_note: Option<String>,
+    /// Tokens re-credited when the application was declined.
+    pub refunded: U128,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// `None` stops redemptions of the option opening applications; those
+    /// already open stay reviewable.
+    pub fn set_grant_option(&mut self, option_id: String, grant: Option<GrantOption>) {
+        self.assert_role(Role::Admin);
+        assert!(
+            self.has_redemption_option(&option_id),
+            "Redemption option not found"
+        );
+        match grant {
+            Some(grant) => self.grant_options.insert(&option_id, &grant),
+            None => self.grant_options.remove(&option_id),
+        };
+    }
+
+    pub fn set_grant_refund_window(&mut self, window_ns: u64) {
+        self.assert_owner_action();
+        self.grant_refund_window = window_ns;
+    }
+
+    pub fn review_grant(&mut self, application_id: u64, status: GrantStatus, note: Option<String>) {
+        self.assert_role(Role::Admin);
+        assert!(
+            note.as_ref().map_or(0, String::len) <= MAX_DECISION_NOTE_LENGTH,
+            "Note exceeds {} bytes",
+            MAX_DECISION_NOTE_LENGTH
+        );
+        let mut application = self
+            .grant_applications
+            .get(&application_id)
+            .expect("Grant application not found");
+        assert!(
+            application.status.can_become(status),
+            "Grant application {} cannot go from {:?} to {:?}",
+            application_id,
+            application.status,
+            status
+        );
+        if status == GrantStatus::Declined && self.refunds_on_decline(&application) {
+            let refunded = self.refund_unconfirmed_receipt(application.receipt_id);
+            application.refunded = U128(refunded);
+        }
+        self.grant_applications_by_status.remove(&(application.status, application_id));
+        self.grant_applications_by_status.insert(&(status, application_id), &());
+        application.status = status;
+        application.decision_note = note;
+        self.grant_applications.insert(&application_id, &application);
+        GrantApplicationReviewed {
+            actor: &env::predecessor_account_id(),
+            application_id,
+            account_id: &application.account_id,
+            status,
+            note: application.decision_note.as_deref(),
+            refunded: application.refunded,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    // View functions
+    pub fn get_grant_application(&self, application_id: u64) -> Option<GrantApplication> {
+        self.grant_applications.get(&application_id)
+    }
+
+    /// The account's applications, oldest first.
+    pub fn get_grant_applications_for(
+        &self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<GrantApplication> {
+        self.account_grant_applications
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|id| self.grant_applications.get(&id))
+            .collect()
+    }
+
+    /// The review queue: applications in `status`, oldest first. Paging
+    /// counts only matching applications.
+    pub fn get_grant_applications_by_status(
+        &self,
+        status: GrantStatus,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<GrantApplication> {
+        self.grant_applications_by_status
+            .range((Bound::Included((status, 0)), Bound::Included((status, u64::MAX))))
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|((_, id), ())| self.grant_applications.get(&id))
+            .collect()
+    }
+
+    pub fn get_grant_option(&self, option_id: String) -> Option<GrantOption> {
+        self.grant_options.get(&option_id)
+    }
+
+    pub fn get_grant_refund_window(&self) -> u64 {
+        self.grant_refund_window
+    }
+}
+
+impl FoodBankToken {
+    /// Opens an application for a delivered redemption of a grant option.
+    pub(crate) fn submit_grant_application(&mut self, account_id: &AccountId, option_id: &String, receipt_id: u64) {
+        if self.grant_options.get(option_id).is_none() {
+            return;
+        }
+        let application_id = self.next_grant_application_id;
+        self.next_grant_application_id += 1;
+        self.grant_applications.insert(
+            &application_id,
+            &GrantApplication {
+                application_id,
+                account_id: account_id.clone(),
+                option_id: option_id.clone(),
+                receipt_id,
+                status: GrantStatus::Submitted,
+                submitted_at: env::block_timestamp(),
+                decision_note: None,
+                refunded: U128(0),
+            },
+        );
+        self.grant_applications_by_status
+            .insert(&(GrantStatus::Submitted, application_id), &());
+        let mut ids = self.account_grant_applications.get(account_id).unwrap_or_default();
+        ids.push(application_id);
+        self.account_grant_applications.insert(account_id, &ids);
+        GrantApplicationSubmitted {
+            application_id,
+            account_id,
+            option_id,
+            receipt_id,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    /// The option's flag as it is now, and a decline strictly inside the
+    /// window.
+    fn refunds_on_decline(&self, application: &GrantApplication) -> bool {
+        let flagged = self
+            .grant_options
+            .get(&application.option_id)
+            .is_some_and(|grant| grant.refund_on_decline);
+        flagged && env::block_timestamp() < application.submitted_at.saturating_add(self.grant_refund_window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fulfillment::{FulfillmentStatus, RedemptionCharge};
+    use crate::test_utils::*;
+    use near_sdk::test_utils::get_logs;
+    use near_sdk::PromiseResult;
+
+    const GRANT: &str = "grant_opportunity";
+
+    /// Redeems the grant option and resolves its benefit call, returning
+    /// the application opened.
+    fn apply(contract: &mut FoodBankToken) -> GrantApplication {
+        set_caller(owner(), 0);
+        contract.mint(food_bank(), 500);
+        let receipt_id = contract.next_receipt_id;
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens(GRANT.to_string(), U128(500), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let charge = RedemptionCharge {
+            amount: U128(500),
+            list_price: U128(500),
+            near_copay: U128(0),
+            promo_code_hash: None,
+        };
+        contract.on_redemption_resolved(food_bank(), GRANT.to_string(), receipt_id, charge);
+        let application = contract.get_grant_applications_for(food_bank(), 0, 100).pop().unwrap();
+        assert_eq!(application.receipt_id, receipt_id);
+        application
+    }
+
+    fn review(contract: &mut FoodBankToken, application_id: u64, status: GrantStatus) {
+        set_caller(owner(), 0);
+        contract.review_grant(application_id, status, Some(format!("{:?}", status)));
+    }
+
+    fn with_refunds() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_grant_option(GRANT.to_string(), Some(GrantOption { refund_on_decline: true }));
+        contract
+    }
+
+    #[test]
+    fn redeeming_opens_a_submitted_application() {
+        let mut contract = setup_contract();
+        let application = apply(&mut contract);
+        assert_eq!(application.status, GrantStatus::Submitted);
+        assert_eq!(application.account_id, food_bank());
+        assert!(get_logs().iter().any(|log| log.contains("grant_application_submitted")));
+        assert_eq!(
+            contract.get_grant_applications_by_status(GrantStatus::Submitted, 0, 10),
+            vec![application]
+        );
+    }
+
+    #[test]
+    fn an_application_is_shortlisted_then_awarded() {
+        let mut contract = setup_contract();
+        let id = apply(&mut contract).application_id;
+        review(&mut contract, id, GrantStatus::Shortlisted);
+        assert_eq!(contract.get_grant_applications_by_status(GrantStatus::Shortlisted, 0, 10).len(), 1);
+        review(&mut contract, id, GrantStatus::Awarded);
+        let application = contract.get_grant_application(id).unwrap();
+        assert_eq!(application.status, GrantStatus::Awarded);
+        assert_eq!(application.decision_note.as_deref(), Some("Awarded"));
+        assert!(get_logs()[0].contains(r#""status":"Awarded""#));
+        assert!(contract.get_grant_applications_by_status(GrantStatus::Shortlisted, 0, 10).is_empty());
+    }
+
+    #[test]
+    fn transitions_reach_the_event_export() {
+        let mut contract = setup_contract();
+        let id = apply(&mut contract).application_id;
+        review(&mut contract, id, GrantStatus::Declined);
+        let events: Vec<String> = contract
+            .get_events_since(0, 100)
+            .events
+            .into_iter()
+            .map(|event| event.event)
+            .filter(|event| event.starts_with("grant_application"))
+            .collect();
+        assert_eq!(events, ["grant_application_submitted", "grant_application_reviewed"]);
+    }
+
+    #[test]
+    fn the_queue_pages_over_matching_applications_only() {
+        let mut contract = setup_contract();
+        let ids: Vec<u64> = (0..4).map(|_| apply(&mut contract).application_id).collect();
+        review(&mut contract, ids[1], GrantStatus::Shortlisted);
+        let page = contract.get_grant_applications_by_status(GrantStatus::Submitted, 1, 10);
+        assert_eq!(
+            page.iter().map(|application| application.application_id).collect::<Vec<_>>(),
+            [ids[2], ids[3]]
+        );
+    }
+
+    #[test]
+    fn declining_without_the_flag_keeps_the_tokens_burned() {
+        let mut contract = setup_contract();
+        let id = apply(&mut contract).application_id;
+        review(&mut contract, id, GrantStatus::Declined);
+        assert_eq!(contract.get_grant_application(id).unwrap().refunded.0, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    fn declining_in_the_window_refunds_flagged_options() {
+        let mut contract = with_refunds();
+        let application = apply(&mut contract);
+        review(&mut contract, application.application_id, GrantStatus::Shortlisted);
+        review(&mut contract, application.application_id, GrantStatus::Declined);
+        assert_eq!(contract.get_grant_application(application.application_id).unwrap().refunded.0, 500);
+        assert_eq!(contract.get_balance(food_bank()).0, 500);
+        assert_eq!(
+            contract.get_receipt(application.receipt_id).unwrap().status,
+            FulfillmentStatus::Refunded
+        );
+    }
+
+    #[test]
+    fn declining_after_the_window_refunds_nothing() {
+        let mut contract = with_refunds();
+        contract.set_grant_refund_window(1_000);
+        let id = apply(&mut contract).application_id;
+        advance_time(1_000);
+        review(&mut contract, id, GrantStatus::Declined);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    fn confirmed_receipts_are_not_refunded() {
+        let mut contract = with_refunds();
+        let application = apply(&mut contract);
+        set_caller(food_bank(), 0);
+        contract.confirm_fulfillment(application.receipt_id);
+        review(&mut contract, application.application_id, GrantStatus::Declined);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    fn applications_are_paged_per_account() {
+        let mut contract = setup_contract();
+        let first = apply(&mut contract);
+        let second = apply(&mut contract);
+        assert_eq!(contract.get_grant_applications_for(food_bank(), 1, 10), vec![second]);
+        assert_eq!(contract.get_grant_applications_for(food_bank(), 0, 1), vec![first]);
+        assert!(contract.get_grant_applications_for(other_food_bank(), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn other_options_open_no_application() {
+        let mut contract = setup_contract();
+        contract.set_grant_option(GRANT.to_string(), None);
+        set_caller(owner(), 0);
+        contract.mint(food_bank(), 500);
+        let receipt_id = contract.next_receipt_id;
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens(GRANT.to_string(), U128(500), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let charge = RedemptionCharge {
+            amount: U128(500),
+            list_price: U128(500),
+            near_copay: U128(0),
+            promo_code_hash: None,
+        };
+        contract.on_redemption_resolved(food_bank(), GRANT.to_string(), receipt_id, charge);
+        assert!(contract.get_grant_applications_for(food_bank(), 0, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot go from Awarded to Declined")]
+    fn decisions_are_final() {
+        let mut contract = setup_contract();
+        let id = apply(&mut contract).application_id;
+        review(&mut contract, id, GrantStatus::Awarded);
+        review(&mut contract, id, GrantStatus::Declined);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Admin role")]
+    fn reviews_need_the_admin_role() {
+        let mut contract = setup_contract();
+        let id = apply(&mut contract).application_id;
+        set_caller(food_bank(), 0);
+        contract.review_grant(id, GrantStatus::Awarded, None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Guardians can pause the contract and nothing else. Unpausing stays with
+//! the owner, so a compromised guardian key can cause downtime but cannot
+//! move funds or change parameters.
+
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{Event, GuardianAdded, GuardianRemoved};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn add_guardian(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        assert!(self.guardians.insert(&account_id), "Account is already a guardian");
+        GuardianAdded {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+        }
+        .emit();
+    }
+
+    pub fn remove_guardian(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        assert!(self.guardians.remove(&account_id), "Account is not a guardian");
+        GuardianRemoved {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+        }
+        .emit();
+    }
+
+    // View functions
+    pub fn is_guardian(&self, account_id: AccountId) -> bool {
+        self.guardians.contains(&account_id)
+    }
+
+    pub fn get_guardians(&self) -> Vec<AccountId> {
+        self.guardians.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::timelock::ParamChange;
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    fn guardian() -> AccountId {
+        accounts(3)
+    }
+
+    fn with_guardian() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.add_guardian(guardian());
+        set_caller(guardian(), 0);
+        contract
+    }
+
+    #[test]
+    fn add_and_remove_guardians() {
+        let mut contract = setup_contract();
+        contract.add_guardian(guardian());
+        contract.add_guardian(accounts(4));
+        assert!(contract.is_guardian(guardian()));
+        assert_eq!(contract.get_guardians(), vec![guardian(), accounts(4)]);
+
+        contract.remove_guardian(guardian());
+        assert!(!contract.is_guardian(guardian()));
+        assert_eq!(contract.get_guardians(), vec![accounts(4)]);
+    }
+
+    #[test]
+    fn guardian_can_pause() {
+        let mut contract = with_guardian();
+        contract.pause();
+        assert!(contract.is_paused());
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""actor":"danny""#));
+        assert!(logs[0].contains(r#""by_guardian":true"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn guardian_cannot_unpause() {
+        let mut contract = with_guardian();
+        contract.pause();
+        contract.unpause();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn guardian_cannot_change_the_reward_rate() {
+        let mut contract = with_guardian();
+        contract.propose_param_change(ParamChange::RewardRateBps(10_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn guardian_cannot_move_treasury_tokens() {
+        let mut contract = setup_contract();
+        contract.add_guardian(guardian());
+        contract.set_treasury(Some(accounts(4)), 100);
+        contract.mint(accounts(4), 100);
+        set_caller(guardian(), 1);
+        contract.treasury_transfer(guardian(), U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn guardian_cannot_redirect_the_treasury() {
+        let mut contract = with_guardian();
+        contract.set_treasury(Some(guardian()), 2_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn guardians_are_managed_by_the_owner() {
+        let mut contract = with_guardian();
+        contract.add_guardian(accounts(4));
+    }
+}
@@ -0,0 +1,184 @@
+//! What a deployment starts with. `new` only sets up what its `InitConfig`
+//! asks for, so regional and testnet instances don't have to undo the
+//! built-in options first; `new_default` keeps the original setup for
+//! existing deploy scripts.
+
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::emission::MAX_RATE_BPS;
+use crate::entitlements::default_analytics_entitlement;
+use crate::grants::GrantOption;
+use crate::{BenefitCall, FoodBankToken, FoodBankTokenExt, DEFAULT_MIN_SCORE_THRESHOLD};
+
+/// Rate `new` starts at without a config: 1%.
+pub const INIT_REWARD_RATE_BPS: u16 = 100;
+/// Rate of the original setup: 5%.
+pub const DEFAULT_REWARD_RATE_BPS: u16 = 500;
+/// Built-in `supplier_discount` can be redeemed once every 30 days.
+pub(crate) const SUPPLIER_DISCOUNT_COOLDOWN: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InitConfig {
+    pub reward_rate_bps: u16,
+    pub min_score_threshold: u32,
+    pub redemption_options: Option<Vec<InitialRedemptionOption>>,
+}
+
+impl Default for InitConfig {
+    fn default() -> Self {
+        Self {
+            reward_rate_bps: INIT_REWARD_RATE_BPS,
+            min_score_threshold: DEFAULT_MIN_SCORE_THRESHOLD,
+            redemption_options: None,
+        }
+    }
+}
+
+impl InitConfig {
+    pub(crate) fn assert_valid(&self) {
+        assert!(
+            self.reward_rate_bps <= MAX_RATE_BPS,
+            "Reward rate must be <= {} bps",
+            MAX_RATE_BPS
+        );
+        assert!(self.min_score_threshold <= 100, "Score threshold must be <= 100");
+    }
+}
+
+/// Arguments of `add_redemption_option`, for options created at init.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InitialRedemptionOption {
+    pub option_id: String,
+    pub name: String,
+    pub cost: U128,
+    pub description: String,
+    pub benefit: Option<BenefitCall>,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// The original setup: a 5% rate and the supplier discount, analytics
+    /// access and grant opportunity options.
+    #[init]
+    pub fn new_default(owner: AccountId, total_supply: U128) -> Self {
+        let config = InitConfig {
+            reward_rate_bps: DEFAULT_REWARD_RATE_BPS,
+            min_score_threshold: DEFAULT_MIN_SCORE_THRESHOLD,
+            redemption_options: Some(default_redemption_options()),
+        };
+        let mut contract = Self::new(owner, total_supply, Some(config));
+        let supplier_id = "supplier_discount".to_string();
+        let mut supplier = contract.redemption_option(&supplier_id).unwrap();
+        supplier.cooldown_ns = Some(SUPPLIER_DISCOUNT_COOLDOWN);
+        contract.store_redemption_option(&supplier_id, &supplier);
+        let analytics_id = "analytics_access".to_string();
+        let mut analytics = contract.redemption_option(&analytics_id).unwrap();
+        analytics.grants_entitlement = Some(default_analytics_entitlement());
+        contract.store_redemption_option(&analytics_id, &analytics);
+        contract
+            .grant_options
+            .insert(&"grant_opportunity".to_string(), &GrantOption::default());
+        contract
+    }
+}
+
+/// The call the original contract made for each of its built-in options,
+/// which it matched by their id.
+pub(crate) fn built_in_benefit(option_id: &str) -> Option<BenefitCall> {
+    let method = match option_id {
+        "supplier_discount" => "apply_supplier_discount",
+        "analytics_access" => "grant_analytics_access",
+        "grant_opportunity" => "process_grant_application",
+        _ => return None,
+    };
+    Some(BenefitCall::on_redeemer(method))
+}
+
+fn default_redemption_options() -> Vec<InitialRedemptionOption> {
+    vec![
+        InitialRedemptionOption {
+            option_id: "supplier_discount".to_string(),
+            name: "Supplier discount".to_string(),
+            cost: U128(100),
+            description: "10% discount on supplier purchases".to_string(),
+            benefit: built_in_benefit("supplier_discount"),
+        },
+        InitialRedemptionOption {
+            option_id: "analytics_access".to_string(),
+            name: "Analytics access".to_string(),
+            cost: U128(200),
+            description: "Access to advanced analytics dashboard".to_string(),
+            benefit: built_in_benefit("analytics_access"),
+        },
+        InitialRedemptionOption {
+            option_id: "grant_opportunity".to_string(),
+            name: "Grant opportunity".to_string(),
+            cost: U128(500),
+            description: "Priority consideration for grant programs".to_string(),
+            benefit: built_in_benefit("grant_opportunity"),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn no_config_starts_without_options() {
+        set_caller(owner(), 0);
+        let contract = FoodBankToken::new(owner(), U128(0), None);
+        assert!(contract.get_redemption_options().is_empty());
+        let config = contract.get_config();
+        assert_eq!(config.reward_rate_bps, INIT_REWARD_RATE_BPS);
+        assert_eq!(config.min_score_threshold, DEFAULT_MIN_SCORE_THRESHOLD);
+    }
+
+    #[test]
+    fn config_sets_rate_threshold_and_options() {
+        set_caller(owner(), 0);
+        let config = InitConfig {
+            reward_rate_bps: 250,
+            min_score_threshold: 40,
+            redemption_options: Some(vec![InitialRedemptionOption {
+                option_id: "food_voucher".to_string(),
+                name: "Food voucher".to_string(),
+                cost: U128(50),
+                description: "Voucher for a partner grocer".to_string(),
+                benefit: None,
+            }]),
+        };
+        let contract = FoodBankToken::new(owner(), U128(0), Some(config));
+        let options = contract.get_redemption_options();
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].0, "food_voucher");
+        assert_eq!(options[0].1.cost.0, 50);
+        let config = contract.get_config();
+        assert_eq!(config.reward_rate_bps, 250);
+        assert_eq!(config.min_score_threshold, 40);
+    }
+
+    #[test]
+    fn new_default_keeps_the_original_setup() {
+        set_caller(owner(), 0);
+        let contract = FoodBankToken::new_default(owner(), U128(0));
+        assert_eq!(contract.get_redemption_options().len(), 3);
+        assert_eq!(contract.get_config().reward_rate_bps, DEFAULT_REWARD_RATE_BPS);
+    }
+
+    #[test]
+    #[should_panic(expected = "Score threshold must be <= 100")]
+    fn invalid_thresholds_are_rejected() {
+        set_caller(owner(), 0);
+        let config = InitConfig {
+            min_score_threshold: 101,
+            ..InitConfig::default()
+        };
+        FoodBankToken::new(owner(), U128(0), Some(config));
+    }
+}
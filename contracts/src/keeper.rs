@@ -0,0 +1,195 @@
+//! Housekeeping hook for Croncat agents or any other keeper. `tick` is
+//! permissionless: everything it does is due anyway, and each piece of work
+//! is recorded as done so a repeated call can't do it twice.
+
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, Gas};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// Gas set aside for each unit of work; `tick` stops once less than this is
+/// left.
+const GAS_PER_TICK_UNIT: Gas = Gas(10_000_000_000_000);
+/// Upper bound on units per call, whatever gas is attached.
+pub const MAX_TICK_UNITS: u64 = 20;
+/// Participants settled, or receipts or acknowledgments examined, in one
+/// unit of work.
+const SETTLEMENT_BATCH: u64 = 10;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TickSummary {
+    pub param_changes_executed: Vec<u64>,
+    pub campaigns_closed: Vec<u64>,
+    pub participants_settled: u64,
+    pub receipts_confirmed: u64,
+    pub acknowledgments_accepted: u64,
+    /// Whether due work was left for a later call.
+    pub more_work: bool,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Executes due timelocked changes, closes expired campaigns, settles
+    /// ended epochs, confirms receipts past their fulfillment window and
+    /// accepts contributions past their acknowledgment window, in that
+    /// order, within a bounded amount of work.
+    pub fn tick(&mut self) -> TickSummary {
+        self.assert_not_paused();
+        let now = env::block_timestamp();
+        let mut summary = TickSummary::default();
+        let mut units = 0;
+
+        let due_changes: Vec<u64> = self
+            .pending_changes
+            .iter()
+            .filter(|(_, pending)| now >= pending.execute_after)
+            .map(|(id, _)| id)
+            .collect();
+        for id in due_changes {
+            if !self.take_tick_unit(&mut units) {
+                summary.more_work = true;
+                return summary;
+            }
+            self.execute_param_change(id);
+            summary.param_changes_executed.push(id);
+        }
+
+        let expired: Vec<u64> = self
+            .open_campaigns
+            .iter()
+            .copied()
+            .filter(|id| self.campaigns.get(id).is_some_and(|campaign| now >= campaign.end))
+            .collect();
+        for id in expired {
+            if !self.take_tick_unit(&mut units) {
+                summary.more_work = true;
+                return summary;
+            }
+            let campaign = self.campaigns.get(&id).unwrap();
+            self.internal_close_campaign(campaign);
+            summary.campaigns_closed.push(id);
+        }
+
+        while let Some(&(epoch_id, cursor)) = self.epochs_to_settle.first() {
+            let epoch = self.epochs.get(&epoch_id).unwrap();
+            if now < epoch.end {
+                break;
+            }
+            if !self.take_tick_unit(&mut units) {
+                summary.more_work = true;
+                return summary;
+            }
+            summary.participants_settled += self.settle_epoch(epoch_id, cursor, SETTLEMENT_BATCH);
+            let cursor = cursor + SETTLEMENT_BATCH;
+            if cursor >= epoch.participant_count {
+                self.epochs_to_settle.remove(0);
+            } else {
+                self.epochs_to_settle[0].1 = cursor;
+            }
+        }
+
+        while self.receipt_expiry_due() {
+            if !self.take_tick_unit(&mut units) {
+                summary.more_work = true;
+                return summary;
+            }
+            summary.receipts_confirmed += self.confirm_expired_receipts(SETTLEMENT_BATCH);
+        }
+
+        while self.acknowledgment_expiry_due() {
+            if !self.take_tick_unit(&mut units) {
+                summary.more_work = true;
+                return summary;
+            }
+            summary.acknowledgments_accepted += self.accept_expired_acknowledgments(SETTLEMENT_BATCH);
+        }
+        summary
+    }
+}
+
+impl FoodBankToken {
+    fn take_tick_unit(&self, units: &mut u64) -> bool {
+        let remaining = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+        if *units >= MAX_TICK_UNITS || remaining < GAS_PER_TICK_UNIT.0 {
+            return false;
+        }
+        *units += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epochs::RewardMode;
+    use crate::test_utils::*;
+    use crate::timelock::ParamChange;
+    use near_sdk::json_types::U128;
+
+    const EPOCH: u64 = 1_000;
+
+    #[test]
+    fn tick_with_nothing_due_does_nothing() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+        contract.create_campaign("Harvest".to_string(), 0, 10, U128(100), 1_000);
+        let summary = contract.tick();
+        assert!(summary.param_changes_executed.is_empty());
+        assert!(summary.campaigns_closed.is_empty());
+        assert!(!summary.more_work);
+    }
+
+    #[test]
+    fn tick_executes_due_changes_and_closes_expired_campaigns() {
+        let mut contract = setup_contract();
+        let change = contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+        let campaign = contract.create_campaign("Harvest".to_string(), 0, 10, U128(100), 1_000);
+        advance_time(crate::timelock::DEFAULT_TIMELOCK_DELAY);
+
+        set_caller(food_bank(), 0);
+        let summary = contract.tick();
+        assert_eq!(summary.param_changes_executed, vec![change]);
+        assert_eq!(summary.campaigns_closed, vec![campaign]);
+        assert_eq!(contract.get_config().reward_rate_bps, 2_000);
+        assert!(contract.get_campaign(campaign).unwrap().closed);
+
+        let again = contract.tick();
+        assert!(again.param_changes_executed.is_empty());
+        assert!(again.campaigns_closed.is_empty());
+    }
+
+    #[test]
+    fn tick_settles_ended_epochs_once() {
+        let mut contract = setup_contract();
+        contract.set_reward_mode(RewardMode::Epoch {
+            pool: U128(900),
+            epoch_length_ns: EPOCH,
+        });
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &other_food_bank(), 50);
+        assert_eq!(contract.tick().participants_settled, 0);
+
+        advance_time(EPOCH);
+        assert_eq!(contract.tick().participants_settled, 2);
+        assert_eq!(contract.get_balance(food_bank()).0, 600);
+        assert_eq!(contract.tick().participants_settled, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 600);
+    }
+
+    #[test]
+    fn tick_stops_at_the_unit_cap() {
+        let mut contract = setup_contract();
+        for id in 0..MAX_TICK_UNITS + 1 {
+            contract.create_campaign(format!("Drive {}", id), 0, 10, U128(100), 1_000);
+        }
+        advance_time(10);
+
+        let summary = contract.tick();
+        assert_eq!(summary.campaigns_closed.len() as u64, MAX_TICK_UNITS);
+        assert!(summary.more_work);
+        let summary = contract.tick();
+        assert_eq!(summary.campaigns_closed.len(), 1);
+        assert!(!summary.more_work);
+    }
+}
@@ -1,9 +1,171 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedMap};
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LookupMap, LookupSet, TreeMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base64VecU8, U128};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, Balance, PanicOnDefault, Promise};
+use near_sdk::{
+    assert_one_yocto, env, is_promise_success, log, near_bindgen, AccountId, Balance, Gas,
+    PanicOnDefault, Promise, PromiseOrValue, PublicKey,
+};
 
+use crate::account_types::AccountType;
+use crate::activity::{ActivityFeed, ContributionActivity, RedemptionActivity, DEFAULT_ACTIVITY_FEED_CAPACITY};
+use crate::acknowledgments::{PendingAcknowledgment, DEFAULT_ACKNOWLEDGMENT_WINDOW};
+use crate::archives::{ArchiveCheckpoint, DEFAULT_MAX_HISTORY_LENGTH};
+use crate::badges::{default_badge_milestones, BadgeStatus};
+use crate::batch_mint::MintBatchCap;
+use crate::breakdowns::{LegacyRewardBreakdown, VersionedRewardBreakdown};
+use crate::campaigns::Campaign;
+use crate::contributions::{
+    ContributionDigest, ContributionMetricsInput, ContributionMetricsV2, ContributionMetricsV3, VersionedContribution,
+    MAX_EVIDENCE_CID_LENGTH,
+};
+use crate::council::CouncilAction;
+use crate::disputes::{Dispute, DEFAULT_DISPUTE_WINDOW};
+use crate::distributions::Distribution;
+use crate::donations::TokenRate;
+use crate::epochs::{Epoch, EpochScore, RewardMode};
+use crate::event_export::{EventBuffer, DEFAULT_EVENT_BUFFER_CAPACITY};
+use crate::events::{
+    ContractPaused, ContractUnpaused, ContributionRecorded, ContributionVoided, Event,
+    NearWithdrawn, OwnershipProposed, OwnershipTransferred, RedemptionCompleted,
+    RedemptionOptionSet, RedemptionRefunded, RewardAdjusted, TokensMinted,
+};
+use crate::freeze::FrozenAccount;
+use crate::fulfillment::{
+    FulfillmentStats, FulfillmentStatus, Receipt, RedemptionCharge, DEFAULT_FULFILLMENT_WINDOW,
+};
+use crate::governance::{GovernanceConfig, Proposal, Vote};
+use crate::grants::{GrantApplication, GrantOption, GrantStatus, DEFAULT_GRANT_REFUND_WINDOW};
+use crate::init_config::InitConfig;
+use crate::locks::{RewardLock, DEFAULT_REWARD_LOCK_DURATION};
+use crate::matching::MatchingPool;
+use crate::metadata::ContractSourceMetadata;
+use crate::metric_rules::{MetricRules, RawMetrics};
+use crate::mint_freeze::WithheldReward;
+use crate::monthly::MonthSummary;
+use crate::named_metrics::MetricDefinition;
+use crate::onboarding::OnboardingBonus;
+use crate::options::VersionedRedemptionOption;
+use crate::penalties::Penalty;
+use crate::profiles::Profile;
+use crate::promos::Promo;
+use crate::rate_overrides::RateSource;
+use crate::recognition::DEFAULT_RECOGNITION_PER_NEAR;
+use crate::redemptions::{whole_tgas, RedemptionLimits};
+use crate::regions::RegionStats;
+use crate::referrals::{Referral, DEFAULT_REFERRAL_BONUS_BPS, DEFAULT_REFERRAL_BONUS_CAP};
+use crate::reputation::{CachedReputation, DEFAULT_REPUTATION_HALF_LIFE};
+use crate::reward_curve::RewardCurve;
+use crate::roles::Role;
+use crate::score_thresholds::ThresholdCohort;
+use crate::snapshots::BalanceSnapshot;
+use crate::staking::{default_stake_tiers, Stake, StakeTier, DEFAULT_STAKE_COOLDOWN};
+use crate::statements::{MonthLedger, StatementFlow};
+use crate::stats::{OptionStats, PeriodStats, ProgramStats};
+use crate::suppliers::{supplier_benefit_args, SupplierInfo};
+use crate::tenure::{default_tenure_discounts, TenureDiscount};
+use crate::tiers::{default_tiers, LifetimeTier};
+use crate::translations::LocalizedText;
+use crate::timelock::{PendingChange, DEFAULT_TIMELOCK_DELAY};
+use crate::transfer_policy::TransferPolicy;
+use crate::upgrade::StagedCode;
+use crate::verification::PendingVerification;
+use crate::vesting::{VestingSchedule, DEFAULT_VESTING_DURATION, DEFAULT_VESTING_THRESHOLD};
+
+mod account_types;
+mod activity;
+mod acknowledgments;
+mod archives;
+mod attestations;
+mod badges;
+mod batch_mint;
+mod breakdowns;
+mod buyback;
+mod campaigns;
+mod contributions;
+mod council;
+mod delegation;
+mod disputes;
+mod distributions;
+mod donations;
+mod emission;
+mod entitlements;
+mod epochs;
+mod event_export;
+mod events;
+mod freeze;
+mod fulfillment;
+mod governance;
+mod guardians;
+mod grants;
+mod init_config;
+mod keeper;
+mod locks;
+mod matching;
+mod metadata;
+mod metric_rules;
+mod migration;
+mod mint_freeze;
+mod monthly;
+mod named_metrics;
+mod onboarding;
+mod options;
+mod penalties;
+mod profiles;
+mod promos;
+mod rate_overrides;
+mod recognition;
+mod redemptions;
+mod regions;
+mod referrals;
+mod relay;
+mod reputation;
+mod reward_curve;
+mod roles;
+mod sales;
+mod score_thresholds;
+mod snapshots;
+mod staking;
+mod statements;
+mod stats;
+mod status;
+mod storage_credit;
+mod suppliers;
+mod tenure;
+mod tiers;
+mod timelock;
+mod transfer_policy;
+mod translations;
+mod treasury;
+mod upgrade;
+mod verification;
+mod vesting;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod proptests;
+#[cfg(test)]
+mod test_utils;
+
+/// Builds distribution trees for `publish_distribution` off-chain.
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::distributions::merkle;
+
+const GAS_FOR_RESOLVE_REDEMPTION: Gas = Gas(10_000_000_000_000);
+/// Given to an option's benefit call unless the owner configured otherwise.
+const DEFAULT_GAS_FOR_BENEFIT: Gas = Gas(20_000_000_000_000);
+/// Left for `redeem_tokens` itself after the benefit and resolver gas.
+const GAS_RESERVED_FOR_REDEEM: Gas = Gas(15_000_000_000_000);
+/// Kept on top of the storage stake so withdrawals never leave the contract
+/// unable to pay for its next few writes.
+const STORAGE_SAFETY_MARGIN: Balance = 100_000_000_000_000_000_000_000;
+/// Largest list accepted by the batch views, keeping them well inside the
+/// view gas limit.
+const MAX_BATCH_SIZE: usize = 200;
+const DEFAULT_MIN_CONTRIBUTION_INTERVAL: u64 = 60 * 60 * 1_000_000_000;
+const MAX_EXTERNAL_ID_LENGTH: usize = 64;
+/// Scores are on a 0–100 scale.
+const DEFAULT_MIN_SCORE_THRESHOLD: u32 = 10;
+
+/// Schema of contributions stored before histories were versioned.
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct ContributionMetrics {
@@ -13,215 +175,2951 @@ pub struct ContributionMetrics {
     timestamp: u64,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
+const MAX_OPTION_ID_LENGTH: usize = 64;
+const MAX_OPTION_NAME_LENGTH: usize = 64;
+
+/// The layout options were stored with before versioning; see
+/// `options.rs`.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
 pub struct RedemptionOption {
     name: String,
-    cost: Balance,
+    cost: U128,
+    available: bool,
+    description: String,
+    benefit_method: String,
+    benefit_args_template: Option<String>,
+    benefit_contract: Option<AccountId>,
+}
+
+/// Keyed by a stable option id; `name` is only shown to users.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RedemptionOptionV5 {
+    name: String,
+    /// `U128` borsh-encodes exactly like the `Balance` it replaced, so stored
+    /// options still deserialize.
+    cost: U128,
     available: bool,
     description: String,
+    /// Called on `benefit_contract` when the option is redeemed. Empty for
+    /// options that only burn the tokens.
+    benefit_method: String,
+    /// JSON arguments for the benefit call, with `{account_id}` and
+    /// `{receipt_id}` replaced by the redeemer and the redemption's receipt.
+    /// No arguments are sent without one.
+    benefit_args_template: Option<String>,
+    /// Defaults to the redeeming account.
+    benefit_contract: Option<AccountId>,
+    /// Recorded contributions the redeemer needs, voided ones excluded.
+    min_contributions: Option<u64>,
+    /// Option the redeemer must already hold a delivered or confirmed
+    /// redemption of; one still in flight, disputed or refunded doesn't
+    /// count.
+    prerequisite_option: Option<String>,
+    /// NEAR the redeemer attaches on top of the tokens, forwarded to the
+    /// benefit contract or else the treasury once the benefit is delivered.
+    near_copay: Option<U128>,
+    /// Locale code to translated text, shown in place of `name` and
+    /// `description` by `get_redemption_options_localized`.
+    translations: Vec<(String, LocalizedText)>,
+    /// Entitlement key and duration in nanoseconds a successful redemption
+    /// adds; see `entitlements.rs`.
+    grants_entitlement: Option<(String, u64)>,
+    /// Time an account has to wait after redeeming the option before it can
+    /// redeem it again. Refunded redemptions don't count.
+    cooldown_ns: Option<u64>,
+}
+
+/// Benefit call configured when adding an option; stored flat on the
+/// `RedemptionOptionV5`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BenefitCall {
+    pub method: String,
+    pub args_template: Option<String>,
+    pub contract: Option<AccountId>,
+}
+
+impl BenefitCall {
+    /// `method` called on the redeeming account without arguments, as the
+    /// built-in options do.
+    pub fn on_redeemer(method: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// How the reward for a single contribution was derived.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardBreakdown {
+    /// Average of the three metrics.
+    score: u8,
+    rate_bps: u16,
+    /// Bonus from the contributor's staked balance.
+    stake_bonus_bps: u16,
+    /// Lifetime tier held when the contribution was recorded.
+    tier: String,
+    tier_multiplier_bps: u16,
+    campaign_id: Option<u64>,
+    /// Set when the contribution was scored towards an epoch instead of
+    /// paid directly; the reward is then paid at settlement.
+    epoch_id: Option<u64>,
+    /// Paid out of the campaign pool, included in `reward`.
+    campaign_bonus: U128,
+    /// Paid out of the matching pool, included in `reward`.
+    matched: U128,
+    reward: U128,
+    /// Curve that turned the score into the base reward.
+    curve: RewardCurve,
+    /// `reward` before `max_reward_per_contribution` was applied.
+    uncapped_reward: U128,
+    rate_source: RateSource,
+    /// Minimum score the contributor was held to; see `score_thresholds.rs`.
+    score_threshold: u32,
+}
+
+/// Running totals kept per contributing account.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountStats {
+    contribution_count: u64,
+    /// Sum of the scores of all recorded contributions.
+    total_score: u64,
+    /// Block timestamp of the latest recorded contribution.
+    last_contribution_at: u64,
+}
+
+/// Everything the dashboard needs about the program's settings in one call.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractConfig {
+    owner: AccountId,
+    pending_owner: Option<AccountId>,
+    reward_rate_bps: u16,
+    min_score_threshold: u32,
+    total_supply: U128,
+    paused: bool,
+    timelock_delay: u64,
+    treasury_account: Option<AccountId>,
+    treasury_share_bps: u16,
+    source_metadata: ContractSourceMetadata,
+    min_contribution_interval: u64,
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct FoodBankToken {
     owner: AccountId,
+    pending_owner: Option<AccountId>,
     total_supply: Balance,
     balances: LookupMap<AccountId, Balance>,
-    contributions: UnorderedMap<AccountId, Vec<ContributionMetrics>>,
-    redemption_options: UnorderedMap<String, RedemptionOption>,
-    min_contribution_threshold: Balance,
-    reward_rate: u8, // Percentage of contribution value
+    /// Histories from before versioning; see `contributions.rs`.
+    legacy_contributions: UnorderedMap<AccountId, Vec<ContributionMetrics>>,
+    /// Options from before versioning; see `options.rs`.
+    legacy_redemption_options: UnorderedMap<String, RedemptionOption>,
+    roles: LookupMap<AccountId, Vec<Role>>,
+    role_holders: LookupMap<Role, Vec<AccountId>>,
+    /// Average score (0–100) a contribution needs to earn a reward; a score
+    /// equal to it still earns one.
+    min_score_threshold: u32,
+    /// Basis points of the contribution score minted as its reward.
+    reward_rate_bps: u16,
+    paused: bool,
+    pending_changes: UnorderedMap<u64, PendingChange>,
+    next_param_change_id: u64,
+    timelock_delay: u64,
+    governance_config: GovernanceConfig,
+    proposals: UnorderedMap<u64, Proposal>,
+    next_proposal_id: u64,
+    votes: LookupMap<(u64, AccountId), Vote>,
+    /// Balances as of proposal creation, written lazily on first change.
+    snapshot_balances: LookupMap<(u64, AccountId), Balance>,
+    /// (proposal id, voting end) of snapshots that still need maintaining.
+    open_snapshots: Vec<(u64, u64)>,
+    frozen_accounts: UnorderedMap<AccountId, FrozenAccount>,
+    treasury_account: Option<AccountId>,
+    /// Basis points of each reward minted to the treasury on top of it.
+    treasury_share_bps: u16,
+    /// `(start_timestamp, rate_bps)` entries sorted by start time.
+    emission_schedule: Vec<(u64, u16)>,
+    /// Breakdowns from before versioning; see `breakdowns.rs`.
+    legacy_reward_breakdowns: LookupMap<(AccountId, u64), LegacyRewardBreakdown>,
+    stakes: LookupMap<AccountId, Stake>,
+    stake_tiers: Vec<StakeTier>,
+    stake_cooldown: u64,
+    /// Rewards strictly above this vest instead of minting liquid.
+    vesting_threshold: Balance,
+    vesting_duration: u64,
+    vesting: LookupMap<AccountId, Vec<VestingSchedule>>,
+    account_stats: LookupMap<AccountId, AccountStats>,
+    tiers: Vec<LifetimeTier>,
+    /// Keyed by the referred account.
+    referrals: LookupMap<AccountId, Referral>,
+    referees: LookupMap<AccountId, Vec<AccountId>>,
+    referral_bonus_bps: u16,
+    referral_bonus_cap: Balance,
+    campaigns: UnorderedMap<u64, Campaign>,
+    next_campaign_id: u64,
+    campaign_earnings: LookupMap<(u64, AccountId), Balance>,
+    matching_pool: Option<MatchingPool>,
+    tokens_per_near: Balance,
+    /// NEP-141 contracts whose tokens are accepted as donations.
+    accepted_tokens: UnorderedMap<AccountId, TokenRate>,
+    badge_contract: Option<AccountId>,
+    badge_milestones: Vec<u64>,
+    badges: LookupMap<(AccountId, u64), BadgeStatus>,
+    /// NEP-330 link, pointed at the deployed commit after each release.
+    source_metadata_link: Option<String>,
+    /// Every account that has held tokens or had a contribution recorded.
+    participants: UnorderedSet<AccountId>,
+    program_stats: ProgramStats,
+    /// Block timestamp at which each voided contribution was voided.
+    voided_contributions: LookupMap<(AccountId, u64), u64>,
+    option_stats: LookupMap<String, OptionStats>,
+    redemption_limits: LookupMap<String, RedemptionLimits>,
+    /// Nanoseconds that must pass between two contributions of an account.
+    min_contribution_interval: u64,
+    /// Index of the contribution submitted under each pipeline id.
+    external_ids: LookupMap<(AccountId, String), u64>,
+    contributions: LookupMap<AccountId, Vec<VersionedContribution>>,
+    reward_mode: RewardMode,
+    /// Start of the epoch numbered `epoch_id_offset`.
+    epoch_origin: u64,
+    epoch_id_offset: u64,
+    epochs: LookupMap<u64, Epoch>,
+    epoch_scores: LookupMap<(u64, AccountId), EpochScore>,
+    /// Keyed by (epoch, order of first contribution in the epoch).
+    epoch_participants: LookupMap<(u64, u64), AccountId>,
+    /// Campaigns `tick` still has to close once they expire.
+    open_campaigns: Vec<u64>,
+    /// (epoch, next participant index) of epochs `tick` still has to settle.
+    epochs_to_settle: Vec<(u64, u64)>,
+    distributions: LookupMap<u64, Distribution>,
+    next_distribution_id: u64,
+    /// Keyed by (distribution, leaf hash).
+    claimed_leaves: LookupSet<(u64, Vec<u8>)>,
+    /// Unclaimed budget of closed distributions.
+    treasury_reclaimed: Balance,
+    profiles: UnorderedMap<AccountId, Profile>,
+    region_stats: UnorderedMap<String, RegionStats>,
+    balance_snapshot: Option<BalanceSnapshot>,
+    next_balance_snapshot_id: u64,
+    /// Balances as of a frozen export, keyed by (snapshot id, account) and
+    /// written on first change.
+    exported_balances: LookupMap<(u64, AccountId), Balance>,
+    reward_lock_duration: u64,
+    reward_locks: LookupMap<AccountId, Vec<RewardLock>>,
+    council: Vec<AccountId>,
+    council_threshold: u32,
+    council_actions: UnorderedMap<u64, CouncilAction>,
+    next_council_action_id: u64,
+    /// The proposer of the action `execute_action` is running, set only
+    /// while it runs; storage the action adds is billed to them.
+    #[borsh_skip]
+    executing_council_action: Option<AccountId>,
+    guardians: UnorderedSet<AccountId>,
+    /// Block timestamp each contribution was recorded at, for disputes and
+    /// monthly summaries.
+    contribution_recorded_at: LookupMap<(AccountId, u64), u64>,
+    dispute_window: u64,
+    disputes: LookupMap<(AccountId, u64), Dispute>,
+    open_disputes: UnorderedSet<(AccountId, u64)>,
+    /// Approvals a contribution in each category needs before it pays.
+    required_verifications: LookupMap<String, u8>,
+    verifiers: UnorderedSet<AccountId>,
+    verifications: LookupMap<(AccountId, u64), PendingVerification>,
+    verification_queue: UnorderedSet<(AccountId, u64)>,
+    fulfillment_window: u64,
+    receipts: LookupMap<u64, Receipt>,
+    next_receipt_id: u64,
+    /// Receipts below this id have all left Delivered; `tick` resumes here.
+    next_receipt_to_expire: u64,
+    fulfillment_stats: LookupMap<String, FulfillmentStats>,
+    benefit_gas: LookupMap<String, Gas>,
+    monthly_summaries: LookupMap<(AccountId, String), MonthSummary>,
+    /// Month keys each account has summaries for, oldest first.
+    account_months: LookupMap<AccountId, Vec<String>>,
+    /// Program-wide activity by day index; days without activity are absent.
+    daily_stats: LookupMap<u64, PeriodStats>,
+    /// Delivered or in-flight redemptions per account and option.
+    account_redemption_counts: LookupMap<(AccountId, String), u64>,
+    reputation_half_life: u64,
+    /// Refreshed whenever the account's history changes.
+    reputations: LookupMap<AccountId, CachedReputation>,
+    metric_rules: MetricRules,
+    metric_definitions: UnorderedMap<String, MetricDefinition>,
+    penalties: LookupMap<AccountId, Vec<Penalty>>,
+    /// Penalty shortfalls still owed; absent once repaid.
+    debts: LookupMap<AccountId, Balance>,
+    mint_batch_cap: MintBatchCap,
+    transfer_policy: TransferPolicy,
+    transfer_allowlist: UnorderedSet<AccountId>,
+    reward_curve: RewardCurve,
+    delegations: LookupMap<AccountId, AccountId>,
+    delegators: LookupMap<AccountId, Vec<AccountId>>,
+    /// Delegators' weight cast by their delegate, keyed by proposal.
+    delegated_votes: LookupMap<(u64, AccountId), AccountId>,
+    /// Limits everything one contribution mints; 0 means uncapped.
+    max_reward_per_contribution: Balance,
+    /// Keyed by the sha256 of the code.
+    promos: LookupMap<Vec<u8>, Promo>,
+    /// (code hash, account) pairs that already used a promo.
+    promo_uses: LookupSet<(Vec<u8>, AccountId)>,
+    /// Block timestamp of each account's first contribution; see `tenure.rs`
+    /// for accounts missing here.
+    first_contributions: LookupMap<AccountId, u64>,
+    tenure_discounts: Vec<TenureDiscount>,
+    redemption_options: UnorderedMap<String, VersionedRedemptionOption>,
+    /// Live entries an account's history holds before new contributions are
+    /// stored as digests; see `archives.rs`.
+    max_history_length: u64,
+    contribution_archives: LookupMap<AccountId, Vec<ArchiveCheckpoint>>,
+    /// See `acknowledgments.rs`.
+    acknowledgment_required: bool,
+    acknowledgment_window: u64,
+    acknowledgments: LookupMap<(AccountId, u64), PendingAcknowledgment>,
+    /// Acknowledgments in recording order, for `tick` to accept.
+    acknowledgment_order: LookupMap<u64, (AccountId, u64)>,
+    next_acknowledgment_seq: u64,
+    next_acknowledgment_to_expire: u64,
+    /// Contributions each account still has to respond to.
+    pending_acknowledgments: LookupMap<AccountId, Vec<u64>>,
+    /// Expiry timestamp of each (account, entitlement key); see
+    /// `entitlements.rs`.
+    entitlements: LookupMap<(AccountId, String), u64>,
+    /// When each account last redeemed each option, for option cooldowns.
+    last_redeemed: LookupMap<(AccountId, String), u64>,
+    /// Minted with an account's first contribution; see `onboarding.rs`.
+    onboarding_bonus: Balance,
+    onboarding_bonuses: LookupMap<AccountId, OnboardingBonus>,
+    /// Stops contribution rewards without pausing the contract; see
+    /// `mint_freeze.rs`.
+    minting_paused: bool,
+    withheld_rewards: LookupMap<(AccountId, u64), WithheldReward>,
+    /// Counters backing `get_status`.
+    pending_redemptions: u64,
+    last_contribution_timestamp: Option<u64>,
+    /// Per-account count of `pending_redemptions`; see `fulfillment.rs`.
+    redemptions_in_flight: LookupMap<AccountId, u32>,
+    /// Signs contributions anyone may submit; see `attestations.rs`.
+    attestor_key: Option<PublicKey>,
+    /// The replaced key and the timestamp it is accepted until.
+    previous_attestor_key: Option<(PublicKey, u64)>,
+    attestation_nonces: LookupSet<(PublicKey, u64)>,
+    /// Token sales; see `sales.rs`.
+    token_price_yocto: Balance,
+    sales_enabled: bool,
+    max_supply: Option<Balance>,
+    sale_proceeds: Balance,
+    /// Buyback at a price floor; see `buyback.rs`.
+    buyback_price_yocto: Balance,
+    buyback_reserve: Balance,
+    /// Monthly balance ledgers; see `statements.rs`.
+    statement_ledgers: LookupMap<(AccountId, String), MonthLedger>,
+    statement_months: LookupMap<AccountId, Vec<String>>,
+    /// Recent events for pollers; see `event_export.rs`.
+    event_buffer: EventBuffer,
+    /// Signing keys for relayed calls; see `relay.rs`.
+    relay_keys: LookupMap<PublicKey, AccountId>,
+    relay_nonces: LookupMap<AccountId, u64>,
+    /// Prepaid storage; see `storage_credit.rs`.
+    storage_credits: LookupMap<AccountId, Balance>,
+    total_storage_credit: Balance,
+    /// Negotiated reward rates; see `rate_overrides.rs`.
+    rate_overrides: UnorderedMap<AccountId, u16>,
+    /// Food bank, supplier or donor; see `account_types.rs`.
+    account_types: LookupMap<AccountId, AccountType>,
+    /// Supplier registry and the options routed to it; see `suppliers.rs`.
+    suppliers: UnorderedMap<AccountId, SupplierInfo>,
+    option_suppliers: LookupMap<String, AccountId>,
+    /// Grant options and the applications they opened; see `grants.rs`.
+    grant_options: LookupMap<String, GrantOption>,
+    grant_applications: UnorderedMap<u64, GrantApplication>,
+    account_grant_applications: LookupMap<AccountId, Vec<u64>>,
+    next_grant_application_id: u64,
+    grant_refund_window: u64,
+    /// Score thresholds by account and tenure; see `score_thresholds.rs`.
+    threshold_overrides: LookupMap<AccountId, u32>,
+    threshold_cohorts: Vec<ThresholdCohort>,
+    /// Donor recognition; see `recognition.rs`.
+    recognition_balances: LookupMap<AccountId, Balance>,
+    recognition_donors: Vector<AccountId>,
+    recognition_per_near: Balance,
+    /// Code waiting to be deployed; see `upgrade.rs`.
+    staged_code: Option<StagedCode>,
+    /// Public activity feeds; see `activity.rs`.
+    recent_contributions: ActivityFeed<ContributionActivity>,
+    recent_redemptions: ActivityFeed<RedemptionActivity>,
+    activity_feed_capacity: u64,
+    /// Keyed by (account, index into its contributions).
+    reward_breakdowns: LookupMap<(AccountId, u64), VersionedRewardBreakdown>,
+    /// Donor leaderboard and donated NEAR; see `recognition.rs`.
+    top_donors: Vec<(AccountId, Balance)>,
+    donation_reserve: Balance,
+    /// What option eligibility counts; see `redemptions.rs`.
+    voided_contribution_counts: LookupMap<AccountId, u64>,
+    delivered_redemption_counts: LookupMap<(AccountId, String), u64>,
+    /// Grant application ids by status, for the review queue.
+    grant_applications_by_status: TreeMap<(GrantStatus, u64), ()>,
 }
 
 #[near_bindgen]
 impl FoodBankToken {
+    /// Without a `config` the contract starts with no redemption options
+    /// and a 1% reward rate; see `init_config.rs`.
     #[init]
-    pub fn new(owner: AccountId, total_supply: U128) -> Self {
+    pub fn new(owner: AccountId, total_supply: U128, config: Option<InitConfig>) -> Self {
+        let config = config.unwrap_or_default();
+        config.assert_valid();
         let mut contract = Self {
             owner,
+            pending_owner: None,
             total_supply: total_supply.0,
             balances: LookupMap::new(b"b"),
-            contributions: UnorderedMap::new(b"c"),
-            redemption_options: UnorderedMap::new(b"r"),
-            min_contribution_threshold: 10,  // Minimum contribution to earn rewards
-            reward_rate: 5,  // 5% reward rate
+            legacy_contributions: UnorderedMap::new(b"c"),
+            legacy_redemption_options: UnorderedMap::new(b"r"),
+            roles: LookupMap::new(b"l"),
+            role_holders: LookupMap::new(b"h"),
+            min_score_threshold: config.min_score_threshold,
+            reward_rate_bps: config.reward_rate_bps,
+            paused: false,
+            pending_changes: UnorderedMap::new(b"p"),
+            next_param_change_id: 0,
+            timelock_delay: DEFAULT_TIMELOCK_DELAY,
+            governance_config: GovernanceConfig::default(),
+            proposals: UnorderedMap::new(b"g"),
+            next_proposal_id: 0,
+            votes: LookupMap::new(b"v"),
+            snapshot_balances: LookupMap::new(b"s"),
+            open_snapshots: Vec::new(),
+            frozen_accounts: UnorderedMap::new(b"f"),
+            treasury_account: None,
+            treasury_share_bps: 0,
+            emission_schedule: Vec::new(),
+            legacy_reward_breakdowns: LookupMap::new(b"w"),
+            stakes: LookupMap::new(b"k"),
+            stake_tiers: default_stake_tiers(),
+            stake_cooldown: DEFAULT_STAKE_COOLDOWN,
+            vesting_threshold: DEFAULT_VESTING_THRESHOLD,
+            vesting_duration: DEFAULT_VESTING_DURATION,
+            vesting: LookupMap::new(b"t"),
+            account_stats: LookupMap::new(b"a"),
+            tiers: default_tiers(),
+            referrals: LookupMap::new(b"e"),
+            referees: LookupMap::new(b"n"),
+            referral_bonus_bps: DEFAULT_REFERRAL_BONUS_BPS,
+            referral_bonus_cap: DEFAULT_REFERRAL_BONUS_CAP,
+            campaigns: UnorderedMap::new(b"q"),
+            next_campaign_id: 0,
+            campaign_earnings: LookupMap::new(b"m"),
+            matching_pool: None,
+            tokens_per_near: 0,
+            accepted_tokens: UnorderedMap::new(b"x"),
+            badge_contract: None,
+            badge_milestones: default_badge_milestones(),
+            badges: LookupMap::new(b"d"),
+            source_metadata_link: None,
+            participants: UnorderedSet::new(b"u"),
+            program_stats: ProgramStats::default(),
+            voided_contributions: LookupMap::new(b"o"),
+            option_stats: LookupMap::new(b"y"),
+            redemption_limits: LookupMap::new(b"z"),
+            min_contribution_interval: DEFAULT_MIN_CONTRIBUTION_INTERVAL,
+            external_ids: LookupMap::new(b"j"),
+            contributions: LookupMap::new(b"C"),
+            reward_mode: RewardMode::Instant,
+            epoch_origin: 0,
+            epoch_id_offset: 0,
+            epochs: LookupMap::new(b"E"),
+            epoch_scores: LookupMap::new(b"S"),
+            epoch_participants: LookupMap::new(b"P"),
+            open_campaigns: Vec::new(),
+            epochs_to_settle: Vec::new(),
+            distributions: LookupMap::new(b"D"),
+            next_distribution_id: 0,
+            claimed_leaves: LookupSet::new(b"L"),
+            treasury_reclaimed: 0,
+            profiles: UnorderedMap::new(b"A"),
+            region_stats: UnorderedMap::new(b"R"),
+            balance_snapshot: None,
+            next_balance_snapshot_id: 0,
+            exported_balances: LookupMap::new(b"X"),
+            reward_lock_duration: DEFAULT_REWARD_LOCK_DURATION,
+            reward_locks: LookupMap::new(b"K"),
+            council: Vec::new(),
+            council_threshold: 0,
+            council_actions: UnorderedMap::new(b"M"),
+            next_council_action_id: 0,
+            executing_council_action: None,
+            guardians: UnorderedSet::new(b"G"),
+            contribution_recorded_at: LookupMap::new(b"T"),
+            dispute_window: DEFAULT_DISPUTE_WINDOW,
+            disputes: LookupMap::new(b"I"),
+            open_disputes: UnorderedSet::new(b"O"),
+            required_verifications: LookupMap::new(b"N"),
+            verifiers: UnorderedSet::new(b"V"),
+            verifications: LookupMap::new(b"W"),
+            verification_queue: UnorderedSet::new(b"Q"),
+            fulfillment_window: DEFAULT_FULFILLMENT_WINDOW,
+            receipts: LookupMap::new(b"F"),
+            next_receipt_id: 0,
+            next_receipt_to_expire: 0,
+            fulfillment_stats: LookupMap::new(b"H"),
+            benefit_gas: LookupMap::new(b"B"),
+            monthly_summaries: LookupMap::new(b"U"),
+            account_months: LookupMap::new(b"J"),
+            daily_stats: LookupMap::new(b"Y"),
+            account_redemption_counts: LookupMap::new(b"Z"),
+            reputation_half_life: DEFAULT_REPUTATION_HALF_LIFE,
+            reputations: LookupMap::new(b"i"),
+            metric_rules: MetricRules::default(),
+            metric_definitions: UnorderedMap::new(b"0"),
+            penalties: LookupMap::new(b"1"),
+            debts: LookupMap::new(b"2"),
+            mint_batch_cap: MintBatchCap::default(),
+            transfer_policy: TransferPolicy::Open,
+            transfer_allowlist: UnorderedSet::new(b"3"),
+            reward_curve: RewardCurve::Linear,
+            delegations: LookupMap::new(b"4"),
+            delegators: LookupMap::new(b"5"),
+            delegated_votes: LookupMap::new(b"6"),
+            max_reward_per_contribution: 0,
+            promos: LookupMap::new(b"7"),
+            promo_uses: LookupSet::new(b"8"),
+            first_contributions: LookupMap::new(b"9"),
+            tenure_discounts: default_tenure_discounts(),
+            redemption_options: UnorderedMap::new(b"ro".to_vec()),
+            max_history_length: DEFAULT_MAX_HISTORY_LENGTH,
+            contribution_archives: LookupMap::new(b"ca".to_vec()),
+            acknowledgment_required: false,
+            acknowledgment_window: DEFAULT_ACKNOWLEDGMENT_WINDOW,
+            acknowledgments: LookupMap::new(b"ak".to_vec()),
+            acknowledgment_order: LookupMap::new(b"ao".to_vec()),
+            next_acknowledgment_seq: 0,
+            next_acknowledgment_to_expire: 0,
+            pending_acknowledgments: LookupMap::new(b"ap".to_vec()),
+            entitlements: LookupMap::new(b"en".to_vec()),
+            last_redeemed: LookupMap::new(b"lr".to_vec()),
+            onboarding_bonus: 0,
+            onboarding_bonuses: LookupMap::new(b"ob".to_vec()),
+            minting_paused: false,
+            withheld_rewards: LookupMap::new(b"wr".to_vec()),
+            pending_redemptions: 0,
+            last_contribution_timestamp: None,
+            redemptions_in_flight: LookupMap::new(b"rf".to_vec()),
+            attestor_key: None,
+            previous_attestor_key: None,
+            attestation_nonces: LookupSet::new(b"an".to_vec()),
+            token_price_yocto: 0,
+            sales_enabled: false,
+            max_supply: None,
+            sale_proceeds: 0,
+            buyback_price_yocto: 0,
+            buyback_reserve: 0,
+            statement_ledgers: LookupMap::new(b"sl".to_vec()),
+            statement_months: LookupMap::new(b"sm".to_vec()),
+            event_buffer: EventBuffer::new(b"eb".to_vec(), DEFAULT_EVENT_BUFFER_CAPACITY),
+            relay_keys: LookupMap::new(b"rk".to_vec()),
+            relay_nonces: LookupMap::new(b"rn".to_vec()),
+            storage_credits: LookupMap::new(b"sc".to_vec()),
+            total_storage_credit: 0,
+            rate_overrides: UnorderedMap::new(b"rv".to_vec()),
+            account_types: LookupMap::new(b"at".to_vec()),
+            suppliers: UnorderedMap::new(b"su".to_vec()),
+            option_suppliers: LookupMap::new(b"os".to_vec()),
+            grant_options: LookupMap::new(b"go".to_vec()),
+            grant_applications: UnorderedMap::new(b"ga".to_vec()),
+            account_grant_applications: LookupMap::new(b"gp".to_vec()),
+            next_grant_application_id: 0,
+            grant_refund_window: DEFAULT_GRANT_REFUND_WINDOW,
+            threshold_overrides: LookupMap::new(b"to".to_vec()),
+            threshold_cohorts: Vec::new(),
+            recognition_balances: LookupMap::new(b"rc".to_vec()),
+            recognition_donors: Vector::new(b"rd".to_vec()),
+            recognition_per_near: DEFAULT_RECOGNITION_PER_NEAR,
+            staged_code: None,
+            recent_contributions: ActivityFeed::new(b"fc".to_vec()),
+            recent_redemptions: ActivityFeed::new(b"fr".to_vec()),
+            activity_feed_capacity: DEFAULT_ACTIVITY_FEED_CAPACITY,
+            reward_breakdowns: LookupMap::new(b"rb".to_vec()),
+            top_donors: Vec::new(),
+            donation_reserve: 0,
+            voided_contribution_counts: LookupMap::new(b"vc".to_vec()),
+            delivered_redemption_counts: LookupMap::new(b"dr".to_vec()),
+            grant_applications_by_status: TreeMap::new(b"gs".to_vec()),
         };
 
-        // Initialize redemption options
-        contract.add_redemption_option(
-            "supplier_discount".to_string(),
-            100,  // 100 tokens
-            "10% discount on supplier purchases".to_string(),
-        );
-        contract.add_redemption_option(
-            "analytics_access".to_string(),
-            200,  // 200 tokens
-            "Access to advanced analytics dashboard".to_string(),
-        );
-        contract.add_redemption_option(
-            "grant_opportunity".to_string(),
-            500,  // 500 tokens
-            "Priority consideration for grant programs".to_string(),
-        );
+        for option in config.redemption_options.unwrap_or_default() {
+            contract.internal_add_redemption_option(
+                option.option_id,
+                option.name,
+                option.cost.0,
+                option.description,
+                option.benefit,
+            );
+        }
 
         contract
     }
 
+    /// The attached deposit pays for the storage the new entry occupies; any
+    /// excess is refunded to the caller. A `campaign_id` adds that campaign's
+    /// bonus and must name a campaign that is currently running. `force`
+    /// lets the owner record a correction inside the minimum interval.
+    /// An `external_id` from the pipeline can only be used once per account,
+    /// so retried submissions are rejected instead of recorded twice.
+    /// Contributions in a `category` that requires verification pay nothing
+    /// until verifiers approve them; see `verification.rs`.
     #[payable]
     pub fn record_contribution(
         &mut self,
         food_bank: AccountId,
-        metrics: ContributionMetrics,
+        metrics: ContributionMetricsV2,
+        campaign_id: Option<u64>,
+        force: Option<bool>,
+        external_id: Option<String>,
+        category: Option<String>,
     ) {
-        self.assert_owner();
-        assert!(
-            metrics.data_quality <= 100 &&
-            metrics.model_improvement <= 100 &&
-            metrics.participation_frequency <= 100,
-            "Metrics must be between 0 and 100"
+        self.assert_role(Role::Recorder);
+        let initial_storage = env::storage_usage();
+        let raw = RawMetrics::from(&metrics);
+        let metrics = self.apply_metric_rules(&metrics);
+        let badge_deposit = self.internal_record_contribution(
+            food_bank,
+            ContributionMetricsV3 { metrics, raw }.into(),
+            campaign_id,
+            force,
+            external_id,
+            category,
         );
-
-        // Calculate reward based on metrics
-        let reward = self.calculate_reward(&metrics);
-        
-        // Record contribution
-        let mut contributions = self.contributions.get(&food_bank)
-            .unwrap_or_else(|| Vec::new());
-        contributions.push(metrics);
-        self.contributions.insert(&food_bank, &contributions);
-
-        // Distribute reward tokens
-        if reward > 0 {
-            self.mint(food_bank, reward);
-        }
+        self.charge_storage(initial_storage, badge_deposit);
     }
 
+    /// Requires exactly one yoctoNEAR so that only a full-access key can
+    /// spend tokens, or at least the co-pay for options with one; any excess
+    /// over the co-pay is refunded. A `promo_code` lowers the cost `amount`
+    /// has to cover. Options without a benefit method complete right away.
+    #[payable]
     pub fn redeem_tokens(
         &mut self,
         option_id: String,
         amount: U128,
-    ) -> Promise {
+        promo_code: Option<String>,
+    ) -> PromiseOrValue<bool> {
+        let (near_copay, required_gas) = self.redemption_attachments(&option_id);
+        if near_copay == 0 {
+            assert_one_yocto();
+        } else {
+            assert!(
+                env::attached_deposit() >= near_copay,
+                "Attach at least {} yoctoNEAR to cover the co-pay",
+                near_copay
+            );
+        }
+        if let Some(required_gas) = required_gas {
+            assert!(
+                env::prepaid_gas() >= required_gas,
+                "Attach at least {} TGas to redeem {}",
+                whole_tgas(required_gas),
+                option_id
+            );
+        }
+        self.assert_not_paused();
         let account_id = env::predecessor_account_id();
-        let balance = self.balances.get(&account_id).unwrap_or(0);
-        let amount = amount.0;
+        let promo = promo_code.map(|code| self.valid_promo(&account_id, &option_id, &code));
+        let promo_discount_bps = promo.as_ref().map_or(0, |(_, discount_bps)| *discount_bps);
+        let option = self.internal_redeem(&account_id, &option_id, amount.0, promo_discount_bps);
+        let promo_code_hash = promo.map(|(code_hash, _)| {
+            self.use_promo(&account_id, &code_hash);
+            Base64VecU8(code_hash)
+        });
+        if near_copay > 0 && env::attached_deposit() > near_copay {
+            Promise::new(account_id.clone()).transfer(env::attached_deposit() - near_copay);
+        }
+        // Reserved now so the benefit call can reference it.
+        let receipt_id = self.next_receipt_id;
+        self.next_receipt_id += 1;
+        self.record_statement_redemption(&account_id, receipt_id);
+
+        let charge = RedemptionCharge {
+            amount,
+            list_price: option.cost,
+            near_copay: U128(near_copay),
+            promo_code_hash,
+        };
+        if option.benefit_method.is_empty() {
+            self.complete_redemption(&account_id, &option_id, charge, receipt_id);
+            return PromiseOrValue::Value(true);
+        }
+        self.record_receipt(receipt_id, &account_id, &option_id, charge.clone(), FulfillmentStatus::Pending);
+        self.begin_redemption_in_flight(&account_id);
+        self.process_redemption_benefit(&account_id, &option_id, &option, receipt_id)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_REDEMPTION)
+                    .on_redemption_resolved(account_id, option_id, receipt_id, charge),
+            )
+            .into()
+    }
+
+    /// Re-credits the burned tokens, returns the co-pay and gives back the
+    /// promo use when the benefit call failed. Not guarded by the pause
+    /// switch so refunds still land during an emergency. Does nothing if the
+    /// receipt has already left Pending.
+    #[private]
+    pub fn on_redemption_resolved(
+        &mut self,
+        account_id: AccountId,
+        option_id: String,
+        receipt_id: u64,
+        charge: RedemptionCharge,
+    ) -> bool {
+        // Calls scheduled before receipts were stored up front have none.
+        let pending = self.receipts.get(&receipt_id);
+        if let Some(receipt) = &pending {
+            if receipt.status != FulfillmentStatus::Pending {
+                log!("Receipt {} is already {:?}; ignoring the callback", receipt_id, receipt.status);
+                return false;
+            }
+        }
+        self.end_redemption_in_flight(&account_id);
+        if is_promise_success() {
+            self.complete_redemption(&account_id, &option_id, charge, receipt_id);
+            return true;
+        }
+        let (amount, near_copay) = (charge.amount, charge.near_copay);
+        self.mint(account_id.clone(), amount.0);
+        self.tag_statement_flow(&account_id, StatementFlow::Refund, amount.0);
+        self.revert_option_redemption(&account_id, &option_id, amount.0);
+        let recorded_at = pending.map_or(u64::MAX, |receipt| receipt.delivered_at);
+        self.reset_redemption_cooldown(&account_id, &option_id, recorded_at);
+        if near_copay.0 > 0 {
+            Promise::new(account_id.clone()).transfer(near_copay.0);
+        }
+        if let Some(code_hash) = &charge.promo_code_hash {
+            self.restore_promo(&account_id, &code_hash.0);
+        }
+        self.record_receipt(receipt_id, &account_id, &option_id, charge, FulfillmentStatus::Refunded);
+        let display_name = self.display_name(&account_id);
+        RedemptionRefunded {
+            account_id: &account_id,
+            account_type: self.account_type(&account_id),
+            display_name: display_name.as_deref(),
+            option_id: &option_id,
+            amount,
+            near_copay,
+        }
+        .record(&mut self.event_buffer);
+        false
+    }
+
+    /// Opens the delivery receipt, forwards the co-pay, extends any
+    /// entitlement the option grants and counts a redemption whose benefit
+    /// was delivered.
+    fn complete_redemption(
+        &mut self,
+        account_id: &AccountId,
+        option_id: &String,
+        charge: RedemptionCharge,
+        receipt_id: u64,
+    ) {
+        let (amount, near_copay) = (charge.amount, charge.near_copay);
+        self.record_receipt(receipt_id, account_id, option_id, charge, FulfillmentStatus::Delivered);
+        if near_copay.0 > 0 {
+            if let Some(recipient) = self.copay_recipient(option_id) {
+                Promise::new(recipient).transfer(near_copay.0);
+            }
+        }
+        self.grant_entitlement(account_id, option_id);
+        self.submit_grant_application(account_id, option_id, receipt_id);
+        self.record_region_redemption(account_id, amount.0);
+        self.record_redemption_activity(account_id, receipt_id, option_id, amount);
+        let stats = &mut self.program_stats;
+        stats.total_redemptions += 1;
+        stats.total_tokens_redeemed = U128(stats.total_tokens_redeemed.0 + amount.0);
+        self.record_daily(|stats| {
+            stats.redemptions += 1;
+            stats.tokens_redeemed = U128(stats.tokens_redeemed.0 + amount.0);
+        });
+        let display_name = self.display_name(account_id);
+        RedemptionCompleted {
+            account_id,
+            account_type: self.account_type(account_id),
+            display_name: display_name.as_deref(),
+            option_id,
+            amount,
+            near_copay,
+            receipt_id,
+            stats: &self.program_stats,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    /// The option's supplier or benefit contract, else the treasury.
+    /// Without any of them the co-pay stays on the token contract.
+    fn copay_recipient(&self, option_id: &String) -> Option<AccountId> {
+        self.option_suppliers
+            .get(option_id)
+            .or_else(|| self.redemption_option(option_id).and_then(|option| option.benefit_contract))
+            .or_else(|| self.treasury_account.clone())
+    }
 
-        // Verify redemption option exists and is available
-        let option = self.redemption_options.get(&option_id)
-            .expect("Redemption option not found");
-        assert!(option.available, "This redemption option is not available");
-        assert!(amount >= option.cost, "Insufficient tokens for redemption");
-        assert!(balance >= amount, "Insufficient balance");
+    /// Validates a redemption and burns the tokens. Creating the benefit
+    /// promise is left to the caller.
+    fn internal_redeem(
+        &mut self,
+        account_id: &AccountId,
+        option_id: &String,
+        amount: Balance,
+        promo_discount_bps: u16,
+    ) -> RedemptionOptionV5 {
+        self.check_redeemable(account_id, option_id, Some(amount), promo_discount_bps)
+            .assert_ok(account_id);
+        let balance = self.balances.get(account_id).unwrap_or(0);
+        let option = self.touch_redemption_option(option_id).unwrap();
+        self.compact_reward_locks(account_id);
+        self.backfill_first_contribution(account_id);
 
         // Update balance
-        let new_balance = balance - amount;
-        self.balances.insert(&account_id, &new_balance);
+        self.internal_set_balance(account_id, balance - amount);
+        self.tag_statement_flow(account_id, StatementFlow::Redemption, amount);
         self.total_supply -= amount;
+        self.record_option_redemption(account_id, option_id, amount);
+        self.last_redeemed
+            .insert(&(account_id.clone(), option_id.clone()), &env::block_timestamp());
+
+        option
+    }
+
+    /// Campaign a contribution counts towards, checking the combination is
+    /// allowed in the current reward mode and category.
+    fn contribution_campaign(&self, campaign_id: Option<u64>, verified: bool) -> Option<Campaign> {
+        let epoch_mode = self.in_epoch_mode();
+        assert!(
+            !(epoch_mode && campaign_id.is_some()),
+            "Campaign bonuses are not paid in epoch mode"
+        );
+        if verified {
+            assert!(!epoch_mode, "Verified categories are not supported in epoch mode");
+            assert!(campaign_id.is_none(), "Verified categories earn no campaign bonus");
+        }
+        campaign_id.map(|id| self.active_campaign(id))
+    }
 
-        // Process redemption benefit
-        self.process_redemption_benefit(&account_id, &option)
+    /// The breakdown a contribution recorded now receives, short of the
+    /// epoch it is scored towards. Epoch contributions are paid at
+    /// settlement, and verified categories get no match.
+    fn contribution_breakdown(
+        &self,
+        account_id: &AccountId,
+        score: u32,
+        campaign: Option<&Campaign>,
+        verified: bool,
+    ) -> RewardBreakdown {
+        let mut breakdown = self.calculate_reward(account_id, score, campaign);
+        if self.in_epoch_mode() {
+            breakdown.matched = U128(0);
+            breakdown.reward = U128(0);
+        }
+        if verified {
+            breakdown.reward = U128(breakdown.reward.0 - breakdown.matched.0);
+            breakdown.matched = U128(0);
+        }
+        breakdown
     }
 
-    fn calculate_reward(&self, metrics: &ContributionMetrics) -> Balance {
-        let average_score = (metrics.data_quality as u32 +
-            metrics.model_improvement as u32 +
-            metrics.participation_frequency as u32) / 3;
-        
-        if average_score as Balance >= self.min_contribution_threshold {
-            (average_score as Balance * self.reward_rate as Balance) / 100
+    /// `average_score` is on the 0–100 scale.
+    fn calculate_reward(
+        &self,
+        account_id: &AccountId,
+        average_score: u32,
+        campaign: Option<&Campaign>,
+    ) -> RewardBreakdown {
+        let (rate_bps, rate_source) = self.rate_for(account_id);
+        let stake_bonus_bps = self.stake_bonus_bps(account_id);
+        let tier = self.get_tier(account_id.clone());
+        let (score_threshold, _) = self.resolve_threshold(account_id);
+
+        let reward = if average_score >= score_threshold {
+            reward_for_points(
+                self.reward_curve.points(average_score),
+                rate_bps,
+                stake_bonus_bps,
+                tier.multiplier_bps,
+            )
         } else {
             0
+        };
+        let campaign_bonus = campaign.map_or(0, |campaign| campaign.bonus_for(reward));
+        let matched = self.matching_for(reward + campaign_bonus);
+        let uncapped_reward = reward + campaign_bonus + matched;
+        let (reward, campaign_bonus, matched) =
+            cap_reward_parts(reward, campaign_bonus, matched, self.max_reward_per_contribution);
+        RewardBreakdown {
+            score: average_score as u8,
+            rate_bps,
+            stake_bonus_bps,
+            tier: tier.name,
+            tier_multiplier_bps: tier.multiplier_bps,
+            campaign_id: campaign.map(|campaign| campaign.id),
+            epoch_id: None,
+            campaign_bonus: U128(campaign_bonus),
+            matched: U128(matched),
+            reward: U128(reward + campaign_bonus + matched),
+            curve: self.reward_curve.clone(),
+            uncapped_reward: U128(uncapped_reward),
+            rate_source,
+            score_threshold,
         }
     }
 
     fn process_redemption_benefit(
         &self,
         account_id: &AccountId,
-        option: &RedemptionOption,
+        option_id: &String,
+        option: &RedemptionOptionV5,
+        receipt_id: u64,
     ) -> Promise {
-        match option.name.as_str() {
-            "supplier_discount" => {
-                // Implement supplier discount logic
-                Promise::new(account_id.clone())
-                    .function_call(
-                        "apply_supplier_discount".to_string(),
-                        vec![],
-                        0,
-                        env::prepaid_gas() / 3,
-                    )
-            },
-            "analytics_access" => {
-                // Grant analytics access
-                Promise::new(account_id.clone())
-                    .function_call(
-                        "grant_analytics_access".to_string(),
-                        vec![],
-                        0,
-                        env::prepaid_gas() / 3,
-                    )
-            },
-            "grant_opportunity" => {
-                // Process grant opportunity
-                Promise::new(account_id.clone())
-                    .function_call(
-                        "process_grant_application".to_string(),
-                        vec![],
-                        0,
-                        env::prepaid_gas() / 3,
-                    )
-            },
-            _ => env::panic_str("Invalid redemption option"),
-        }
+        let supplier = self.option_suppliers.get(option_id);
+        let args = match (&option.benefit_args_template, &supplier) {
+            (Some(template), _) => render_benefit_args(template, account_id, receipt_id).into_bytes(),
+            (None, Some(supplier)) => {
+                let discount_bps = self.suppliers.get(supplier).map_or(0, |info| info.discount_bps);
+                supplier_benefit_args(account_id, receipt_id, discount_bps)
+            }
+            (None, None) => Vec::new(),
+        };
+        let contract = supplier
+            .or_else(|| option.benefit_contract.clone())
+            .unwrap_or_else(|| account_id.clone());
+        Promise::new(contract).function_call(
+            option.benefit_method.clone(),
+            args,
+            0,
+            self.benefit_gas(option_id),
+        )
     }
 
     // Admin functions
+    /// Gas the benefit call of `option_id` receives.
+    pub fn set_benefit_gas(&mut self, option_id: String, gas: Gas) {
+        self.assert_owner_action();
+        assert!(
+            self.has_redemption_option(&option_id),
+            "Redemption option not found"
+        );
+        self.benefit_gas.insert(&option_id, &gas);
+    }
+
+    /// Without a `benefit` the option only burns the tokens. `option_id` is
+    /// permanent; it may only use lowercase letters, digits, `_` and `-`.
+    /// The attached deposit, or the caller's storage credit, pays for the
+    /// storage; the excess is refunded.
+    #[payable]
     pub fn add_redemption_option(
         &mut self,
+        option_id: String,
         name: String,
-        cost: Balance,
+        cost: U128,
         description: String,
+        benefit: Option<BenefitCall>,
     ) {
-        self.assert_owner();
-        let option = RedemptionOption {
-            name: name.clone(),
-            cost,
-            available: true,
-            description,
-        };
-        self.redemption_options.insert(&name, &option);
+        self.assert_role(Role::Admin);
+        let initial_storage = env::storage_usage();
+        self.internal_add_redemption_option(option_id, name, cost.0, description, benefit);
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Changes the given fields of an option, keeping its id and benefit.
+    /// A longer description is paid for as in `add_redemption_option`.
+    #[payable]
+    pub fn update_redemption_option(
+        &mut self,
+        option_id: String,
+        cost: Option<U128>,
+        description: Option<String>,
+        available: Option<bool>,
+    ) {
+        self.assert_role(Role::Admin);
+        let initial_storage = env::storage_usage();
+        self.internal_update_redemption_option(option_id, cost, description, available);
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Renames an option for display; its id, and every record keyed by
+    /// it, stays the same. A longer name is paid for as in
+    /// `add_redemption_option`.
+    #[payable]
+    pub fn rename_option_display(&mut self, option_id: String, new_name: String) {
+        self.assert_role(Role::Admin);
+        assert_valid_option_name(&new_name);
+        let initial_storage = env::storage_usage();
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        let mut option = old_option.clone();
+        option.name = new_name;
+        self.set_redemption_option(&option_id, Some(old_option), option);
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Marks a contribution as invalid and burns the reward it paid, and
+    /// any onboarding bonus that came with it, as far as the account's
+    /// liquid balance still covers it.
+    pub fn void_contribution(&mut self, account_id: AccountId, index: u64) -> U128 {
+        self.assert_owner_action();
+        U128(self.internal_void_contribution(&account_id, index))
+    }
+
+    pub fn is_contribution_voided(&self, account_id: AccountId, index: u64) -> bool {
+        self.voided_contributions.get(&(account_id, index)).is_some()
+    }
+
+    /// Recomputes the rewards of up to `limit` contributions starting at
+    /// `from_index` under the current formula and pays out any shortfall.
+    /// Overpaid rewards are only burned back when `claw_back` is set. The
+    /// campaign bonus and matched part are kept as paid, and contributions
+    /// paid through an epoch are skipped. Returns how many contributions were
+    /// adjusted.
+    pub fn recalculate_rewards(
+        &mut self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+        claw_back: bool,
+    ) -> u64 {
+        self.assert_owner_action();
+        self.assert_not_frozen(&account_id);
+        let history = self.stored_contributions(&account_id);
+        let archived = self.archived_contribution_count(&account_id);
+        let mut adjusted = 0;
+        for (index, entry) in history
+            .into_iter()
+            .enumerate()
+            .skip(from_index.saturating_sub(archived) as usize)
+            .take(limit as usize)
+        {
+            let index = archived + index as u64;
+            let key = (account_id.clone(), index);
+            if self.voided_contributions.get(&key).is_some() {
+                continue;
+            }
+            if self.rescore_contribution(&account_id, index, entry.score(), claw_back) {
+                adjusted += 1;
+            }
+        }
+        adjusted
+    }
+
+    pub fn set_min_contribution_interval(&mut self, interval_ns: u64) {
+        self.assert_owner_action();
+        self.min_contribution_interval = interval_ns;
+    }
+
+    /// First step of an ownership transfer. Replaces any earlier proposal
+    /// that has not been accepted yet.
+    pub fn propose_owner(&mut self, new_owner: AccountId) {
+        self.assert_owner_action();
+        OwnershipProposed {
+            owner: &self.owner,
+            pending_owner: &new_owner,
+        }
+        .record(&mut self.event_buffer);
+        self.pending_owner = Some(new_owner);
+    }
+
+    /// Second step of an ownership transfer, callable only by the proposed owner.
+    pub fn accept_ownership(&mut self) {
+        let pending_owner = self.pending_owner.take()
+            .expect("No ownership transfer is pending");
+        assert_eq!(
+            env::predecessor_account_id(),
+            pending_owner,
+            "Only the pending owner can accept ownership"
+        );
+        OwnershipTransferred {
+            old_owner: &self.owner,
+            new_owner: &pending_owner,
+        }
+        .record(&mut self.event_buffer);
+        self.owner = pending_owner;
+    }
+
+    /// Callable by the owner or any guardian.
+    pub fn pause(&mut self) {
+        let actor = env::predecessor_account_id();
+        let by_guardian = actor != self.owner;
+        assert!(
+            !by_guardian || self.guardians.contains(&actor),
+            "Only the owner or a guardian can pause"
+        );
+        self.internal_pause(&actor, by_guardian);
+    }
+
+    pub fn unpause(&mut self) {
+        self.assert_owner_action();
+        self.internal_unpause();
     }
 
-    pub fn update_reward_rate(&mut self, new_rate: u8) {
-        self.assert_owner();
-        assert!(new_rate <= 100, "Reward rate must be <= 100");
-        self.reward_rate = new_rate;
+    /// Sends NEAR that was transferred to the contract by mistake to `to`.
+    /// Only the balance above the storage stake and safety margin can leave.
+    #[payable]
+    pub fn withdraw_near(&mut self, amount: U128, to: AccountId) -> Promise {
+        assert_one_yocto();
+        self.assert_owner_action();
+        self.internal_withdraw_near(amount, to)
     }
 
     // View functions
+    pub fn get_config(&self) -> ContractConfig {
+        ContractConfig {
+            owner: self.owner.clone(),
+            pending_owner: self.pending_owner.clone(),
+            reward_rate_bps: self.reward_rate_bps,
+            min_score_threshold: self.min_score_threshold,
+            total_supply: U128(self.total_supply),
+            paused: self.paused,
+            timelock_delay: self.timelock_delay,
+            treasury_account: self.treasury_account.clone(),
+            treasury_share_bps: self.treasury_share_bps,
+            source_metadata: self.contract_source_metadata(),
+            min_contribution_interval: self.min_contribution_interval,
+        }
+    }
+
+    pub fn get_available_near(&self) -> U128 {
+        U128(self.available_near())
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner.clone()
+    }
+
+    pub fn get_total_supply(&self) -> U128 {
+        U128(self.total_supply)
+    }
+
+    pub fn get_reward_rate_bps(&self) -> u16 {
+        self.reward_rate_bps
+    }
+
+    pub fn get_min_score_threshold(&self) -> u32 {
+        self.min_score_threshold
+    }
+
+    pub fn get_max_reward_per_contribution(&self) -> U128 {
+        U128(self.max_reward_per_contribution)
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     pub fn get_balance(&self, account_id: AccountId) -> U128 {
         U128(self.balances.get(&account_id).unwrap_or(0))
     }
 
-    pub fn get_contributions(&self, account_id: AccountId) -> Vec<ContributionMetrics> {
-        self.contributions.get(&account_id).unwrap_or_else(|| Vec::new())
+    /// `None` for accounts that never held tokens or contributed, as
+    /// opposed to a registered account that spent everything.
+    pub fn get_balance_opt(&self, account_id: AccountId) -> Option<U128> {
+        if !self.is_registered(account_id.clone()) {
+            return None;
+        }
+        Some(self.get_balance(account_id))
+    }
+
+    /// Whether the account has ever held tokens or contributed.
+    pub fn is_registered(&self, account_id: AccountId) -> bool {
+        self.participants.contains(&account_id)
+    }
+
+    /// The account's live history. Archived entries are left out, so the
+    /// first entry returned has the index the archives end at.
+    pub fn get_contributions(&self, account_id: AccountId) -> Vec<ContributionMetricsV2> {
+        self.stored_contributions(&account_id)
+            .into_iter()
+            .map(VersionedContribution::into_latest)
+            .collect()
+    }
+
+    /// The same history as submitted, before metric rules rescaled it.
+    pub fn get_raw_contributions(&self, account_id: AccountId) -> Vec<RawMetrics> {
+        self.stored_contributions(&account_id)
+            .into_iter()
+            .map(VersionedContribution::into_raw)
+            .collect()
+    }
+
+    /// The contribution submitted under `external_id`, with its index.
+    pub fn get_contribution_by_external_id(
+        &self,
+        account_id: AccountId,
+        external_id: String,
+    ) -> Option<(u64, ContributionMetricsV2)> {
+        let index = self.external_ids.get(&(account_id.clone(), external_id))?;
+        let live_index = index.checked_sub(self.archived_contribution_count(&account_id))?;
+        let metrics = self.get_contributions(account_id).into_iter().nth(live_index as usize)?;
+        Some((index, metrics))
+    }
+
+    pub fn get_participants(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        self.participants
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    pub fn get_participant_count(&self) -> u64 {
+        self.participants.len()
+    }
+
+    /// Balances in input order, `None` for accounts that never held tokens.
+    /// Accepts at most `MAX_BATCH_SIZE` accounts.
+    pub fn get_balances(&self, account_ids: Vec<AccountId>) -> Vec<(AccountId, Option<U128>)> {
+        assert_batch_size(account_ids.len());
+        account_ids
+            .into_iter()
+            .map(|account_id| {
+                let balance = self.balances.get(&account_id).map(U128);
+                (account_id, balance)
+            })
+            .collect()
+    }
+
+    pub fn get_account_stats(&self, account_id: AccountId) -> Option<AccountStats> {
+        self.account_stats.get(&account_id)
+    }
+
+    /// Same shape and limit as `get_balances`.
+    pub fn get_account_stats_batch(
+        &self,
+        account_ids: Vec<AccountId>,
+    ) -> Vec<(AccountId, Option<AccountStats>)> {
+        assert_batch_size(account_ids.len());
+        account_ids
+            .into_iter()
+            .map(|account_id| {
+                let stats = self.account_stats.get(&account_id);
+                (account_id, stats)
+            })
+            .collect()
+    }
+
+    pub fn get_reward_breakdown(&self, account_id: AccountId, index: u64) -> Option<RewardBreakdown> {
+        self.reward_breakdown(&(account_id, index))
+    }
+
+    /// The breakdown `record_contribution` would store for these arguments
+    /// right now. For a category that needs verification, `reward` is what
+    /// approval would pay.
+    pub fn preview_reward(
+        &self,
+        account_id: AccountId,
+        metrics: ContributionMetricsInput,
+        category: Option<String>,
+    ) -> RewardBreakdown {
+        self.preview_breakdown(&account_id, &metrics, None, category)
+    }
+
+    /// As `preview_reward`, for a contribution recorded under a campaign.
+    pub fn preview_campaign_reward(
+        &self,
+        account_id: AccountId,
+        metrics: ContributionMetricsInput,
+        campaign_id: u64,
+    ) -> RewardBreakdown {
+        self.preview_breakdown(&account_id, &metrics, Some(campaign_id), None)
+    }
+
+    pub fn get_benefit_gas(&self, option_id: String) -> Gas {
+        self.benefit_gas(&option_id)
     }
 
-    pub fn get_redemption_options(&self) -> Vec<(String, RedemptionOption)> {
-        self.redemption_options.iter().collect()
+    pub fn get_redemption_options(&self) -> Vec<(String, RedemptionOptionV5)> {
+        self.iter_redemption_options().collect()
     }
 
     // Internal helper functions
-    fn mint(&mut self, account_id: AccountId, amount: Balance) {
-        let balance = self.balances.get(&account_id).unwrap_or(0);
-        self.balances.insert(&account_id, &(balance + amount));
-        self.total_supply += amount;
+    fn preview_breakdown(
+        &self,
+        account_id: &AccountId,
+        metrics: &ContributionMetricsInput,
+        campaign_id: Option<u64>,
+        category: Option<String>,
+    ) -> RewardBreakdown {
+        let score = self.apply_metric_rules(metrics).score();
+        let verified = category.is_some_and(|category| self.required_verifications(&category) > 0);
+        let campaign = self.contribution_campaign(campaign_id, verified);
+        let mut breakdown = self.contribution_breakdown(account_id, score, campaign.as_ref(), verified);
+        breakdown.epoch_id = self.current_epoch_id();
+        breakdown
     }
 
-    fn assert_owner(&self) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.owner,
-            "Only the owner can call this method"
+    /// Records an already validated contribution, scored by `record.score()`.
+    /// Shared by the fixed-metric and named-metric entry points, which charge
+    /// the storage from the start of the call plus the returned NEAR spent on
+    /// the caller's behalf.
+    #[must_use]
+    pub(crate) fn internal_record_contribution(
+        &mut self,
+        food_bank: AccountId,
+        record: VersionedContribution,
+        campaign_id: Option<u64>,
+        force: Option<bool>,
+        external_id: Option<String>,
+        category: Option<String>,
+    ) -> Balance {
+        self.assert_not_paused();
+        self.assert_not_frozen(&food_bank);
+        self.assert_can_earn_rewards(&food_bank);
+        if force.unwrap_or(false) {
+            assert!(
+                env::predecessor_account_id() == self.owner,
+                "Only the owner can force a contribution"
+            );
+        } else {
+            self.assert_contribution_interval(&food_bank);
+        }
+        if let Some(external_id) = &external_id {
+            assert!(
+                external_id.len() <= MAX_EXTERNAL_ID_LENGTH,
+                "external_id exceeds {} bytes",
+                MAX_EXTERNAL_ID_LENGTH
+            );
+            assert!(
+                self.external_ids.get(&(food_bank.clone(), external_id.clone())).is_none(),
+                "Duplicate external_id {} for account {}",
+                external_id,
+                food_bank
+            );
+        }
+        let verified_category = category.filter(|category| self.required_verifications(category) > 0);
+        let campaign = self.contribution_campaign(campaign_id, verified_category.is_some());
+        self.participants.insert(&food_bank);
+        self.last_contribution_timestamp = Some(env::block_timestamp());
+
+        let supply_before = self.total_supply;
+
+        // Calculate reward based on the score
+        let mut breakdown = self.contribution_breakdown(
+            &food_bank,
+            record.score(),
+            campaign.as_ref(),
+            verified_category.is_some(),
+        );
+        if self.in_epoch_mode() {
+            breakdown.epoch_id = Some(self.record_epoch_score(&food_bank, &breakdown));
+        }
+        let held = self.holds_for_acknowledgment(verified_category.is_some());
+        let withheld = self.minting_paused && verified_category.is_none() && !held && breakdown.reward.0 > 0;
+        let reward = if verified_category.is_some() || held || withheld { 0 } else { breakdown.reward.0 };
+        let onboarding_limit = self.mint_headroom(breakdown.reward.0);
+        if let Some(id) = campaign_id {
+            self.record_campaign_reward(id, &food_bank, breakdown.campaign_bonus.0, reward);
+        }
+        self.draw_matching_pool(&food_bank, breakdown.matched.0);
+
+        // Record contribution
+        let mut contributions = self.stored_contributions(&food_bank);
+        let index = self.archived_contribution_count(&food_bank) + contributions.len() as u64;
+        if contributions.len() as u64 >= self.max_history_length {
+            contributions.push(
+                ContributionDigest {
+                    timestamp: env::block_timestamp(),
+                    score: breakdown.score,
+                    reward: breakdown.reward,
+                }
+                .into(),
+            );
+        } else {
+            contributions.push(record);
+        }
+        self.store_contributions(&food_bank, &contributions);
+        if let Some(category) = verified_category {
+            self.queue_verification(&food_bank, index, category, breakdown.reward);
+            breakdown.reward = U128(0);
+        } else if held {
+            self.queue_acknowledgment(&food_bank, index, breakdown.reward);
+            breakdown.reward = U128(0);
+        } else if withheld {
+            let amount = breakdown.reward;
+            self.withhold_reward(&food_bank, index, |withheld| withheld.reward = amount);
+            breakdown.reward = U128(0);
+        }
+        self.store_reward_breakdown(&(food_bank.clone(), index), &breakdown);
+        self.contribution_recorded_at.insert(&(food_bank.clone(), index), &env::block_timestamp());
+        self.backfill_first_contribution(&food_bank);
+        self.record_monthly_contribution(&food_bank, index, breakdown.score, breakdown.reward.0);
+        if let Some(external_id) = &external_id {
+            self.external_ids.insert(&(food_bank.clone(), external_id.clone()), &index);
+        }
+        self.add_lifetime_score(&food_bank, u64::from(breakdown.score));
+        self.maybe_pay_onboarding_bonus(&food_bank, index, onboarding_limit);
+        self.refresh_reputation(&food_bank);
+
+        // Distribute reward tokens
+        self.pay_contribution_reward(&food_bank, index, reward);
+        let badge_deposit = self.maybe_mint_badge(&food_bank, index + 1);
+
+        let minted = self.total_supply - supply_before;
+        let stats = &mut self.program_stats;
+        stats.total_contributions_recorded += 1;
+        stats.total_rewards_minted = U128(stats.total_rewards_minted.0 + minted);
+        self.record_daily(|stats| {
+            stats.contributions += 1;
+            stats.rewards_minted = U128(stats.rewards_minted.0 + minted);
+        });
+        self.record_region_contribution(&food_bank, reward);
+        self.record_contribution_activity(&food_bank, index, breakdown.score, U128(reward));
+        let display_name = self.display_name(&food_bank);
+        ContributionRecorded {
+            account_id: &food_bank,
+            account_type: self.account_type(&food_bank),
+            display_name: display_name.as_deref(),
+            index,
+            external_id: external_id.as_deref(),
+            reward: breakdown.reward,
+            uncapped_reward: breakdown.uncapped_reward,
+            stats: &self.program_stats,
+        }
+        .emit();
+        badge_deposit
+    }
+
+    fn internal_add_redemption_option(
+        &mut self,
+        option_id: String,
+        name: String,
+        cost: Balance,
+        description: String,
+        benefit: Option<BenefitCall>,
+    ) {
+        assert_valid_option_id(&option_id);
+        assert_valid_option_name(&name);
+        assert!(
+            !self.has_redemption_option(&option_id),
+            "Redemption option {} already exists",
+            option_id
         );
+        let benefit = benefit.unwrap_or_default();
+        if let Some(template) = &benefit.args_template {
+            let sample = render_benefit_args(template, &env::current_account_id(), 0);
+            assert!(
+                near_sdk::serde_json::from_str::<near_sdk::serde_json::Value>(&sample).is_ok(),
+                "Benefit args template must be valid JSON"
+            );
+        }
+        if benefit.method.is_empty() {
+            assert!(
+                benefit.args_template.is_none() && benefit.contract.is_none(),
+                "Benefit arguments and contract need a benefit method"
+            );
+        }
+        let option = RedemptionOptionV5 {
+            name,
+            cost: U128(cost),
+            available: true,
+            description,
+            benefit_method: benefit.method,
+            benefit_args_template: benefit.args_template,
+            benefit_contract: benefit.contract,
+            min_contributions: None,
+            prerequisite_option: None,
+            near_copay: None,
+            translations: Vec::new(),
+            grants_entitlement: None,
+            cooldown_ns: None,
+        };
+        self.set_redemption_option(&option_id, None, option);
+    }
+
+    pub(crate) fn internal_update_redemption_option(
+        &mut self,
+        option_id: String,
+        cost: Option<U128>,
+        description: Option<String>,
+        available: Option<bool>,
+    ) {
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        let mut option = old_option.clone();
+        if let Some(cost) = cost {
+            option.cost = cost;
+        }
+        if let Some(description) = description {
+            option.description = description;
+        }
+        if let Some(available) = available {
+            option.available = available;
+        }
+        self.set_redemption_option(&option_id, Some(old_option), option);
+    }
+
+    fn set_redemption_option(
+        &mut self,
+        option_id: &String,
+        old_option: Option<RedemptionOptionV5>,
+        option: RedemptionOptionV5,
+    ) {
+        self.store_redemption_option(option_id, &option);
+        RedemptionOptionSet {
+            actor: &env::predecessor_account_id(),
+            option_id,
+            old_option: old_option.as_ref(),
+            new_option: &option,
+        }
+        .record(&mut self.event_buffer);
     }
-} 
\ No newline at end of file
+
+    fn benefit_gas(&self, option_id: &String) -> Gas {
+        self.benefit_gas.get(option_id).unwrap_or(DEFAULT_GAS_FOR_BENEFIT)
+    }
+
+    /// What is left of `max_reward_per_contribution` once a contribution
+    /// has minted `minted`.
+    pub(crate) fn mint_headroom(&self, minted: Balance) -> Balance {
+        if self.max_reward_per_contribution == 0 {
+            return Balance::MAX;
+        }
+        self.max_reward_per_contribution.saturating_sub(minted)
+    }
+
+    /// Mints a contribution reward, vested or locked as configured, with the
+    /// treasury share and any referral bonus on top. Outstanding penalty
+    /// debt is paid from the reward first. Everything minted for the
+    /// contribution stays within `max_reward_per_contribution`: the treasury
+    /// share and then the referral bonus are cut to whatever the reward and
+    /// onboarding bonus leave of it.
+    pub(crate) fn pay_contribution_reward(&mut self, account_id: &AccountId, contribution_id: u64, reward: Balance) {
+        let mut headroom = self.mint_headroom(reward + self.onboarding_bonus_for(account_id, contribution_id));
+        let reward = self.pay_debt_from_reward(account_id, reward);
+        if reward > self.vesting_threshold {
+            self.mint_vesting(account_id, reward);
+        } else if reward > 0 {
+            self.mint(account_id.clone(), reward);
+            self.tag_statement_flow(account_id, StatementFlow::Reward, reward);
+            self.lock_reward(account_id, reward);
+        }
+        headroom -= self.mint_treasury_share(reward, headroom);
+        self.mint_referral_bonus(account_id, reward, headroom);
+    }
+
+    pub(crate) fn assert_valid_metrics(&self, metrics: &ContributionMetricsV2) {
+        assert!(
+            metrics.data_quality <= 100 &&
+            metrics.model_improvement <= 100 &&
+            metrics.participation_frequency <= 100,
+            "Metrics must be between 0 and 100"
+        );
+        if let Some(cid) = &metrics.evidence_cid {
+            assert!(
+                cid.len() <= MAX_EVIDENCE_CID_LENGTH,
+                "evidence_cid exceeds {} bytes",
+                MAX_EVIDENCE_CID_LENGTH
+            );
+        }
+    }
+
+    pub(crate) fn internal_void_contribution(&mut self, account_id: &AccountId, index: u64) -> Balance {
+        let key = (account_id.clone(), index);
+        let breakdown = self.reward_breakdown(&key).expect("Contribution not found");
+        assert!(
+            self.voided_contributions.get(&key).is_none(),
+            "Contribution is already voided"
+        );
+        self.voided_contributions.insert(&key, &env::block_timestamp());
+        let voided = self.voided_contribution_counts.get(account_id).unwrap_or(0);
+        self.voided_contribution_counts.insert(account_id, &(voided + 1));
+        self.void_monthly_contribution(account_id, index, breakdown.score, breakdown.reward.0);
+        self.refresh_reputation(account_id);
+
+        let balance = self.balances.get(account_id).unwrap_or(0);
+        // Whatever is still withheld was never minted and is dropped instead.
+        let withheld_bonus = self.withheld_rewards.remove(&key).map_or(0, |withheld| withheld.onboarding_bonus.0);
+        let owed = breakdown.reward.0 + self.onboarding_bonus_for(account_id, index) - withheld_bonus;
+        let clawed_back = owed.min(balance);
+        self.internal_set_balance(account_id, balance - clawed_back);
+        self.tag_statement_flow(account_id, StatementFlow::Clawback, clawed_back);
+        self.total_supply -= clawed_back;
+
+        let stats = &mut self.program_stats;
+        stats.total_rewards_clawed_back = U128(stats.total_rewards_clawed_back.0 + clawed_back);
+        ContributionVoided {
+            account_id,
+            index,
+            clawed_back: U128(clawed_back),
+            stats: &self.program_stats,
+        }
+        .emit();
+        clawed_back
+    }
+
+    /// Pays out or, with `claw_back`, burns back the difference between a
+    /// contribution's reward and the reward `metrics` earn under the current
+    /// formula, keeping the campaign bonus and matched part. Returns whether
+    /// the reward changed.
+    pub(crate) fn rescore_contribution(
+        &mut self,
+        account_id: &AccountId,
+        index: u64,
+        score: u32,
+        claw_back: bool,
+    ) -> bool {
+        let key = (account_id.clone(), index);
+        if !self.counts_for_rewards(&key) {
+            return false;
+        }
+        let mut breakdown = match self.reward_breakdown(&key) {
+            Some(breakdown) if breakdown.epoch_id.is_none() => breakdown,
+            _ => return false,
+        };
+        let previous = breakdown.reward.0;
+        let fixed = breakdown.campaign_bonus.0 + breakdown.matched.0;
+        let recalculated = self.calculate_reward(account_id, score, None);
+        let base = recalculated.reward.0 - recalculated.matched.0;
+        let reward = base + fixed;
+        if reward == previous || (reward < previous && !claw_back) {
+            return false;
+        }
+
+        if reward > previous {
+            self.mint(account_id.clone(), reward - previous);
+            self.tag_statement_flow(account_id, StatementFlow::Reward, reward - previous);
+            let stats = &mut self.program_stats;
+            stats.total_rewards_minted = U128(stats.total_rewards_minted.0 + reward - previous);
+            self.record_daily_mint(reward - previous);
+        } else {
+            let balance = self.balances.get(account_id).unwrap_or(0);
+            let burned = (previous - reward).min(balance);
+            self.internal_set_balance(account_id, balance - burned);
+            self.tag_statement_flow(account_id, StatementFlow::Clawback, burned);
+            self.total_supply -= burned;
+            let stats = &mut self.program_stats;
+            stats.total_rewards_clawed_back = U128(stats.total_rewards_clawed_back.0 + burned);
+        }
+        self.rescore_monthly_contribution(
+            account_id,
+            index,
+            (breakdown.score, previous),
+            (recalculated.score, reward),
+        );
+        breakdown.score = recalculated.score;
+        breakdown.rate_bps = recalculated.rate_bps;
+        breakdown.stake_bonus_bps = recalculated.stake_bonus_bps;
+        breakdown.tier = recalculated.tier;
+        breakdown.tier_multiplier_bps = recalculated.tier_multiplier_bps;
+        breakdown.reward = U128(reward);
+        self.store_reward_breakdown(&key, &breakdown);
+        RewardAdjusted {
+            account_id,
+            account_type: self.account_type(account_id),
+            index,
+            previous: U128(previous),
+            reward: U128(reward),
+        }
+        .emit();
+        true
+    }
+
+    fn mint(&mut self, account_id: AccountId, amount: Balance) {
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        self.internal_set_balance(&account_id, balance + amount);
+        self.total_supply += amount;
+        TokensMinted {
+            account_id: &account_id,
+            amount: U128(amount),
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    /// For paths that spend an account's tokens, so unknown accounts get a
+    /// clearer error than an insufficient balance.
+    pub(crate) fn assert_registered(&self, account_id: &AccountId) {
+        assert!(self.participants.contains(account_id), "Account not registered");
+    }
+
+    /// Single write path for balances so governance snapshots and balance
+    /// exports stay correct.
+    fn internal_set_balance(&mut self, account_id: &AccountId, balance: Balance) {
+        let old_balance = self.balances.get(account_id).unwrap_or(0);
+        self.record_snapshot_balance(account_id, old_balance);
+        self.record_export_balance(account_id, old_balance);
+        self.record_statement_balance(account_id, old_balance, balance);
+        self.balances.insert(account_id, &balance);
+        if balance > 0 {
+            self.participants.insert(account_id);
+        }
+    }
+
+    fn available_near(&self) -> Balance {
+        let locked = Balance::from(env::storage_usage()) * env::storage_byte_cost()
+            + STORAGE_SAFETY_MARGIN
+            + self.reserved_matching_near()
+            + self.sale_proceeds
+            + self.buyback_reserve
+            + self.donation_reserve
+            + self.total_storage_credit;
+        env::account_balance().saturating_sub(locked)
+    }
+
+    fn assert_contribution_interval(&self, account_id: &AccountId) {
+        if let Some(stats) = self.account_stats.get(account_id) {
+            let earliest = stats.last_contribution_at + self.min_contribution_interval;
+            assert!(
+                env::block_timestamp() >= earliest,
+                "Account {} cannot contribute again before {}",
+                account_id,
+                earliest
+            );
+        }
+    }
+
+    pub(crate) fn internal_withdraw_near(&mut self, amount: U128, to: AccountId) -> Promise {
+        let available = self.available_near();
+        assert!(
+            amount.0 <= available,
+            "Only {} yoctoNEAR is available for withdrawal",
+            available
+        );
+        NearWithdrawn {
+            actor: &env::predecessor_account_id(),
+            to: &to,
+            amount,
+        }
+        .emit();
+        Promise::new(to).transfer(amount.0)
+    }
+
+    pub(crate) fn internal_pause(&mut self, actor: &AccountId, by_guardian: bool) {
+        assert!(!self.paused, "Contract is already paused");
+        self.paused = true;
+        ContractPaused {
+            actor,
+            by_guardian,
+            timestamp: env::block_timestamp(),
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    pub(crate) fn internal_unpause(&mut self) {
+        assert!(self.paused, "Contract is not paused");
+        self.paused = false;
+        ContractUnpaused {
+            actor: &env::predecessor_account_id(),
+            timestamp: env::block_timestamp(),
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    fn assert_not_paused(&self) {
+        assert!(!self.paused, "Contract is paused");
+    }
+
+    fn assert_owner(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only the owner can call this method"
+        );
+    }
+}
+
+fn assert_batch_size(len: usize) {
+    assert!(
+        len <= MAX_BATCH_SIZE,
+        "At most {} accounts can be queried at once",
+        MAX_BATCH_SIZE
+    );
+}
+
+fn assert_valid_option_id(option_id: &str) {
+    assert!(
+        !option_id.is_empty() && option_id.len() <= MAX_OPTION_ID_LENGTH,
+        "Option id must be 1 to {} bytes",
+        MAX_OPTION_ID_LENGTH
+    );
+    assert!(
+        option_id
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_' || byte == b'-'),
+        "Option id may only contain a-z, 0-9, '_' and '-'"
+    );
+}
+
+fn assert_valid_option_name(name: &str) {
+    assert!(!name.is_empty(), "Option name must not be empty");
+    assert!(
+        name.len() <= MAX_OPTION_NAME_LENGTH,
+        "Option name exceeds {} bytes",
+        MAX_OPTION_NAME_LENGTH
+    );
+}
+
+/// Tokens earned for `points` of curve output. A single division, so the
+/// multipliers don't compound the truncation.
+fn reward_for_points(points: Balance, rate_bps: u16, stake_bonus_bps: u16, tier_multiplier_bps: u16) -> Balance {
+    points * Balance::from(rate_bps)
+        * (10_000 + Balance::from(stake_bonus_bps))
+        * Balance::from(tier_multiplier_bps)
+        / 10_000_000_000_000_000
+}
+
+/// Limits a reward and its campaign bonus and match to `cap` in total, 0
+/// meaning no cap. The pool-funded parts are cut first, leaving them in
+/// their pools.
+fn cap_reward_parts(
+    reward: Balance,
+    campaign_bonus: Balance,
+    matched: Balance,
+    cap: Balance,
+) -> (Balance, Balance, Balance) {
+    if cap == 0 {
+        return (reward, campaign_bonus, matched);
+    }
+    let reward = reward.min(cap);
+    let campaign_bonus = campaign_bonus.min(cap - reward);
+    (reward, campaign_bonus, matched.min(cap - reward - campaign_bonus))
+}
+
+fn render_benefit_args(template: &str, account_id: &AccountId, receipt_id: u64) -> String {
+    template
+        .replace("{account_id}", account_id.as_str())
+        .replace("{receipt_id}", &receipt_id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::{testing_env, PromiseResult};
+
+    #[test]
+    fn new_initializes_default_options() {
+        let contract = setup_contract();
+        let options = contract.get_redemption_options();
+        assert_eq!(options.len(), 3);
+
+        let cost_of = |id: &str| {
+            options
+                .iter()
+                .find(|(key, _)| key == id)
+                .map(|(_, option)| option.cost.0)
+                .unwrap()
+        };
+        assert_eq!(cost_of("supplier_discount"), 100);
+        assert_eq!(cost_of("analytics_access"), 200);
+        assert_eq!(cost_of("grant_opportunity"), 500);
+        assert!(options.iter().all(|(_, option)| option.available));
+    }
+
+    #[test]
+    fn new_does_not_require_owner_as_deployer() {
+        set_caller(food_bank(), 0);
+        let contract = FoodBankToken::new_default(owner(), U128(INITIAL_SUPPLY));
+        assert_eq!(contract.get_redemption_options().len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "data_quality must be between 0 and 100")]
+    fn record_contribution_rejects_metrics_over_100() {
+        let mut contract = setup_contract();
+        contract.record_contribution(food_bank(), metrics(101, 50, 50), None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Recorder role")]
+    fn record_contribution_rejects_non_owner() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.record_contribution(food_bank(), metrics(50, 50, 50), None, None, None, None);
+    }
+
+    #[test]
+    fn record_contribution_stores_metrics_and_mints() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+
+        assert_eq!(contract.get_contributions(food_bank()).len(), 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+        assert_eq!(contract.total_supply, INITIAL_SUPPLY + 5);
+    }
+
+    fn with_interval(interval_ns: u64) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_min_contribution_interval(interval_ns);
+        contract
+    }
+
+    #[test]
+    fn contributions_are_accepted_once_the_interval_passes() {
+        let mut contract = with_interval(1_000);
+        advance_time(5);
+        contribute(&mut contract, &food_bank(), 100);
+        // Other accounts are not held back.
+        contribute(&mut contract, &other_food_bank(), 100);
+
+        advance_time(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_contributions(food_bank()).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account bob cannot contribute again before 1005")]
+    fn contributions_inside_the_interval_are_rejected() {
+        let mut contract = with_interval(1_000);
+        advance_time(5);
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(999);
+        contribute(&mut contract, &food_bank(), 100);
+    }
+
+    #[test]
+    fn owner_can_force_a_correction() {
+        let mut contract = with_interval(1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        set_caller(owner(), ONE_NEAR);
+        contract.record_contribution(food_bank(), metrics(50, 50, 50), None, Some(true), None, None);
+        assert_eq!(contract.get_contributions(food_bank()).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can force a contribution")]
+    fn recorders_cannot_force() {
+        let mut contract = with_interval(1_000);
+        contract.grant_role(other_food_bank(), Role::Recorder);
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.record_contribution(food_bank(), metrics(50, 50, 50), None, Some(true), None, None);
+    }
+
+    #[test]
+    fn default_interval_is_an_hour() {
+        set_caller(owner(), 0);
+        let contract = FoodBankToken::new_default(owner(), U128(INITIAL_SUPPLY));
+        assert_eq!(contract.get_config().min_contribution_interval, 60 * 60 * 1_000_000_000);
+    }
+
+    fn contribute_with_id(contract: &mut FoodBankToken, account: &AccountId, external_id: &str) {
+        set_caller(owner(), ONE_NEAR);
+        contract.record_contribution(
+            account.clone(),
+            metrics(100, 100, 100),
+            None,
+            None,
+            Some(external_id.to_string()),
+            None,
+        );
+    }
+
+    #[test]
+    fn external_ids_join_back_to_contributions() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 40);
+        contribute_with_id(&mut contract, &food_bank(), "eval-1");
+        let (index, metrics) = contract
+            .get_contribution_by_external_id(food_bank(), "eval-1".to_string())
+            .unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(metrics.data_quality, 100);
+        assert!(contract
+            .get_contribution_by_external_id(food_bank(), "eval-2".to_string())
+            .is_none());
+    }
+
+    #[test]
+    fn same_external_id_is_allowed_on_different_accounts() {
+        let mut contract = setup_contract();
+        contribute_with_id(&mut contract, &food_bank(), "eval-1");
+        contribute_with_id(&mut contract, &other_food_bank(), "eval-1");
+        assert!(contract
+            .get_contribution_by_external_id(other_food_bank(), "eval-1".to_string())
+            .is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate external_id eval-1 for account bob")]
+    fn duplicate_external_id_is_rejected() {
+        let mut contract = setup_contract();
+        contribute_with_id(&mut contract, &food_bank(), "eval-1");
+        contribute_with_id(&mut contract, &food_bank(), "eval-1");
+    }
+
+    #[test]
+    #[should_panic(expected = "external_id exceeds 64 bytes")]
+    fn long_external_id_is_rejected() {
+        let mut contract = setup_contract();
+        contribute_with_id(&mut contract, &food_bank(), &"x".repeat(65));
+    }
+
+    #[test]
+    fn record_contribution_refunds_unused_deposit() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        let initial_storage = env::storage_usage();
+        contract.record_contribution(food_bank(), metrics(100, 100, 100), None, None, None, None);
+
+        let used = Balance::from(env::storage_usage() - initial_storage);
+        assert!(used > 0);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        let refund = receipts.last().unwrap();
+        assert_eq!(refund.receiver_id, owner());
+        assert_eq!(
+            refund.actions,
+            vec![VmAction::Transfer {
+                deposit: ONE_NEAR - used * env::storage_byte_cost()
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "yoctoNEAR to cover storage")]
+    fn record_contribution_requires_storage_deposit() {
+        let mut contract = setup_contract();
+        contract.record_contribution(food_bank(), metrics(100, 100, 100), None, None, None, None);
+    }
+
+    fn reward_for(contract: &FoodBankToken, metrics: ContributionMetricsV2) -> Balance {
+        contract.calculate_reward(&food_bank(), metrics.score(), None).reward.0
+    }
+
+    #[test]
+    fn reward_is_paid_at_exactly_the_threshold() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+
+        let at_threshold = contract.min_score_threshold as u8;
+        assert_eq!(
+            reward_for(&contract, metrics(at_threshold, at_threshold, at_threshold)),
+            10
+        );
+        let below = at_threshold - 1;
+        assert_eq!(reward_for(&contract, metrics(below, below, below)), 0);
+    }
+
+    #[test]
+    fn only_perfect_scores_pay_at_the_top_of_the_scale() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.min_score_threshold = 100;
+        assert_eq!(reward_for(&contract, metrics(100, 100, 100)), 100);
+        assert_eq!(reward_for(&contract, metrics(100, 100, 99)), 0);
+    }
+
+    #[test]
+    fn reward_truncates_toward_zero() {
+        let contract = setup_contract();
+        // (10 * 5) / 100 truncates to zero even though the threshold is met.
+        assert_eq!(reward_for(&contract, metrics(10, 10, 10)), 0);
+        assert_eq!(reward_for(&contract, metrics(100, 100, 100)), 5);
+    }
+
+    #[test]
+    fn reward_rate_is_applied_in_basis_points() {
+        let mut contract = setup_contract();
+        for (rate_bps, reward) in [(0, 0), (1, 0), (250, 2), (9_999, 99), (10_000, 100)] {
+            set_reward_rate_bps(&mut contract, rate_bps);
+            assert_eq!(reward_for(&contract, metrics(100, 100, 100)), reward);
+        }
+    }
+
+    #[test]
+    fn threshold_change_only_affects_future_contributions() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 20);
+        assert_eq!(contract.get_balance(food_bank()).0, 20);
+
+        let id = contract.propose_param_change(
+            timelock::ParamChange::MinScoreThreshold(30),
+        );
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+
+        contribute(&mut contract, &food_bank(), 20);
+        assert_eq!(contract.get_balance(food_bank()).0, 20);
+        contribute(&mut contract, &food_bank(), 30);
+        assert_eq!(contract.get_balance(food_bank()).0, 50);
+        assert_eq!(contract.get_config().min_score_threshold, 30);
+    }
+
+    #[test]
+    fn config_reflects_contract_state() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 700);
+        contract.pause();
+        contract.propose_owner(food_bank());
+
+        let config = contract.get_config();
+        assert_eq!(config.owner, owner());
+        assert_eq!(config.pending_owner, Some(food_bank()));
+        assert_eq!(config.reward_rate_bps, 700);
+        assert_eq!(config.min_score_threshold, 10);
+        assert_eq!(config.total_supply.0, INITIAL_SUPPLY);
+        assert!(config.paused);
+        assert_eq!(config.timelock_delay, DEFAULT_TIMELOCK_DELAY);
+    }
+
+    #[test]
+    fn basic_views_match_state() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 42);
+        assert_eq!(contract.get_total_supply().0, INITIAL_SUPPLY + 42);
+        assert_eq!(contract.get_reward_rate_bps(), 500);
+        assert_eq!(contract.get_min_score_threshold(), 10);
+        assert_eq!(contract.get_owner(), owner());
+    }
+
+    #[test]
+    fn redemption_costs_cross_json_as_strings() {
+        let mut contract = setup_contract();
+        let large = u128::from(u64::MAX) + 1;
+        let cost: U128 = near_sdk::serde_json::from_str("\"18446744073709551616\"").unwrap();
+        assert_eq!(cost.0, large);
+        set_caller(owner(), ONE_NEAR);
+        contract.add_redemption_option(
+            "pallet".to_string(),
+            "Pallet".to_string(),
+            cost,
+            "A pallet".to_string(),
+            None,
+        );
+
+        let options = near_sdk::serde_json::to_value(contract.get_redemption_options()).unwrap();
+        let pallet = options
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry[0] == "pallet")
+            .unwrap();
+        assert_eq!(pallet[1]["cost"], "18446744073709551616");
+    }
+
+    #[test]
+    fn participants_are_listed_once() {
+        let mut contract = setup_contract();
+        assert_eq!(contract.get_participant_count(), 0);
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 100);
+        contract.mint(food_bank(), 10);
+        contract.mint(other_food_bank(), 10);
+        // A contribution that earns nothing still registers the account.
+        contribute(&mut contract, &accounts(3), 0);
+
+        assert_eq!(contract.get_participant_count(), 3);
+        assert_eq!(
+            contract.get_participants(0, 10),
+            vec![food_bank(), other_food_bank(), accounts(3)]
+        );
+        assert_eq!(contract.get_participants(1, 1), vec![other_food_bank()]);
+    }
+
+    #[test]
+    fn get_balances_distinguishes_unknown_accounts() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 42);
+        let balances = contract.get_balances(vec![food_bank(), other_food_bank(), food_bank()]);
+        assert_eq!(
+            balances,
+            vec![
+                (food_bank(), Some(U128(42))),
+                (other_food_bank(), None),
+                (food_bank(), Some(U128(42))),
+            ]
+        );
+        assert!(contract.get_balances(vec![]).is_empty());
+    }
+
+    #[test]
+    fn get_account_stats_batch_follows_input_order() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 60);
+        let stats = contract.get_account_stats_batch(vec![other_food_bank(), food_bank()]);
+        assert!(stats[0].1.is_none());
+        assert_eq!(stats[1].1.as_ref().unwrap().total_score, 60);
+        assert!(contract.get_account_stats_batch(vec![]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "At most 200 accounts can be queried at once")]
+    fn batch_views_are_capped() {
+        let contract = setup_contract();
+        contract.get_balances(vec![food_bank(); MAX_BATCH_SIZE + 1]);
+    }
+
+    #[test]
+    fn void_claws_back_what_the_balance_covers() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 60);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+
+        set_caller(owner(), 0);
+        assert_eq!(contract.void_contribution(food_bank(), 0).0, 60);
+        assert!(contract.is_contribution_voided(food_bank(), 0));
+        assert!(!contract.is_contribution_voided(food_bank(), 1));
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution is already voided")]
+    fn void_only_once() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        contract.void_contribution(food_bank(), 0);
+        contract.void_contribution(food_bank(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution not found")]
+    fn void_requires_an_existing_contribution() {
+        let mut contract = setup_contract();
+        contract.void_contribution(food_bank(), 0);
+    }
+
+    #[test]
+    fn recalculation_without_formula_change_is_a_no_op() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 60);
+        assert_eq!(contract.recalculate_rewards(food_bank(), 0, 10, true), 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 8);
+    }
+
+    #[test]
+    fn reward_at_the_cap_is_paid_in_full() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        set_max_reward_per_contribution(&mut contract, 80);
+        contribute(&mut contract, &food_bank(), 80);
+        let breakdown = contract.get_reward_breakdown(food_bank(), 0).unwrap();
+        assert_eq!(breakdown.reward.0, 80);
+        assert_eq!(breakdown.uncapped_reward.0, 80);
+        assert_eq!(contract.get_balance(food_bank()).0, 80);
+    }
+
+    #[test]
+    fn reward_above_the_cap_is_clamped() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        set_max_reward_per_contribution(&mut contract, 50);
+        contribute(&mut contract, &food_bank(), 80);
+        let breakdown = contract.get_reward_breakdown(food_bank(), 0).unwrap();
+        assert_eq!(breakdown.reward.0, 50);
+        assert_eq!(breakdown.uncapped_reward.0, 80);
+        assert_eq!(contract.get_balance(food_bank()).0, 50);
+        let logs = near_sdk::test_utils::get_logs();
+        let recorded = logs.iter().find(|log| log.contains("contribution_recorded")).unwrap();
+        assert!(recorded.contains(r#""reward":"50","uncapped_reward":"80""#));
+    }
+
+    #[test]
+    fn the_cap_covers_everything_a_contribution_mints() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        set_max_reward_per_contribution(&mut contract, 100);
+        contract.set_onboarding_bonus(U128(15));
+        contract.set_treasury(Some(accounts(3)), 1_000);
+        set_caller(food_bank(), 0);
+        contract.set_referrer(other_food_bank());
+        contribute(&mut contract, &food_bank(), 80);
+
+        // 80 reward and 15 onboarding bonus leave 5 of the 8 treasury share
+        // and none of the referral bonus.
+        assert_eq!(contract.get_balance(food_bank()).0, 95);
+        assert_eq!(contract.get_balance(accounts(3)).0, 5);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 0);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 100);
+    }
+
+    #[test]
+    fn a_proposed_cap_waits_for_the_timelock() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(timelock::ParamChange::MaxRewardPerContribution(U128(30)));
+        assert_eq!(contract.get_max_reward_per_contribution().0, 0);
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_max_reward_per_contribution().0, 30);
+    }
+
+    #[test]
+    fn lowering_the_cap_applies_to_later_contributions() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        set_max_reward_per_contribution(&mut contract, 100);
+        contribute(&mut contract, &food_bank(), 80);
+        set_max_reward_per_contribution(&mut contract, 30);
+        assert_eq!(contract.get_max_reward_per_contribution().0, 30);
+        contribute(&mut contract, &food_bank(), 80);
+
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 80);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 1).unwrap().reward.0, 30);
+        assert_eq!(contract.get_balance(food_bank()).0, 110);
+    }
+
+    #[test]
+    fn recalculation_pays_the_shortfall() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 60);
+        contribute(&mut contract, &food_bank(), 40);
+        set_reward_rate_bps(&mut contract, 1_000);
+
+        // Only the page starting at index 1 is touched.
+        assert_eq!(contract.recalculate_rewards(food_bank(), 1, 1, false), 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 5 + 6 + 2);
+        let breakdown = contract.get_reward_breakdown(food_bank(), 1).unwrap();
+        assert_eq!(breakdown.reward.0, 6);
+        assert_eq!(breakdown.rate_bps, 1_000);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 5);
+        assert_eq!(contract.get_program_stats().total_rewards_minted.0, 5 + 3 + 2 + 3);
+    }
+
+    #[test]
+    fn lower_rewards_are_only_burned_with_claw_back() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        set_reward_rate_bps(&mut contract, 200);
+
+        assert_eq!(contract.recalculate_rewards(food_bank(), 0, 10, false), 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+        assert_eq!(contract.recalculate_rewards(food_bank(), 0, 10, true), 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 2);
+        assert_eq!(contract.get_program_stats().total_rewards_clawed_back.0, 3);
+    }
+
+    #[test]
+    fn voided_contributions_are_not_recalculated() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        contract.void_contribution(food_bank(), 0);
+        set_reward_rate_bps(&mut contract, 1_000);
+        assert_eq!(contract.recalculate_rewards(food_bank(), 0, 10, false), 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    fn mint_updates_balance_and_total_supply() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 40);
+        contract.mint(food_bank(), 2);
+        contract.mint(other_food_bank(), 8);
+
+        assert_eq!(contract.get_balance(food_bank()).0, 42);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 8);
+        assert_eq!(contract.total_supply, INITIAL_SUPPLY + 50);
+    }
+
+    #[test]
+    fn redeem_burns_tokens() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(120), None);
+
+        assert_eq!(contract.get_balance(food_bank()).0, 30);
+        assert_eq!(contract.total_supply, INITIAL_SUPPLY + 30);
+    }
+
+    #[test]
+    fn ownership_transfer_requires_acceptance() {
+        let mut contract = setup_contract();
+        contract.propose_owner(food_bank());
+        assert_eq!(contract.get_owner(), owner());
+        assert_eq!(contract.get_pending_owner(), Some(food_bank()));
+
+        set_caller(food_bank(), 0);
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), food_bank());
+        assert_eq!(contract.get_pending_owner(), None);
+
+        let logs = near_sdk::test_utils::get_logs();
+        assert!(logs[0].starts_with("EVENT_JSON:"));
+        assert!(logs[0].contains(r#""event":"ownership_transferred""#));
+    }
+
+    #[test]
+    fn propose_owner_overwrites_previous_proposal() {
+        let mut contract = setup_contract();
+        contract.propose_owner(food_bank());
+        contract.propose_owner(other_food_bank());
+        assert_eq!(contract.get_pending_owner(), Some(other_food_bank()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept ownership")]
+    fn accept_ownership_rejects_other_accounts() {
+        let mut contract = setup_contract();
+        contract.propose_owner(food_bank());
+        set_caller(other_food_bank(), 0);
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "No ownership transfer is pending")]
+    fn accept_ownership_requires_a_proposal() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn propose_owner_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.propose_owner(food_bank());
+    }
+
+    #[test]
+    fn failed_benefit_call_refunds_tokens() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        assert_eq!(contract.get_balance(food_bank()).0, 50);
+
+        resolve_callback(PromiseResult::Failed);
+        let delivered = contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+        assert!(!delivered);
+        assert_eq!(contract.get_balance(food_bank()).0, 150);
+        assert_eq!(contract.total_supply, INITIAL_SUPPLY + 150);
+    }
+
+    #[test]
+    fn successful_benefit_call_keeps_tokens_burned() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+
+        resolve_callback(PromiseResult::Successful(vec![]));
+        assert!(contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        ));
+        assert_eq!(contract.get_balance(food_bank()).0, 50);
+    }
+
+    fn with_copay(contract: &mut FoodBankToken, near_copay: Balance) {
+        set_caller(owner(), 0);
+        contract.set_option_near_copay("supplier_discount".to_string(), Some(U128(near_copay)));
+        contract.mint(food_bank(), 100);
+    }
+
+    #[test]
+    fn copay_is_recorded_on_the_receipt() {
+        let mut contract = setup_contract();
+        with_copay(&mut contract, ONE_NEAR);
+        set_caller(food_bank(), 2 * ONE_NEAR);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let receipt_id = last_receipt_id(&contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            receipt_id,
+            RedemptionCharge {
+                near_copay: U128(ONE_NEAR),
+                ..token_charge(100)
+            },
+        );
+        let receipt = contract.get_receipt(receipt_id).unwrap();
+        assert_eq!(receipt.near_copay.0, ONE_NEAR);
+        assert!(near_sdk::test_utils::get_logs()
+            .iter()
+            .any(|log| log.contains(&format!(r#""near_copay":"{}""#, ONE_NEAR))));
+    }
+
+    #[test]
+    fn failed_benefit_call_records_the_returned_copay() {
+        let mut contract = setup_contract();
+        with_copay(&mut contract, ONE_NEAR);
+        set_caller(food_bank(), ONE_NEAR);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+
+        resolve_callback(PromiseResult::Failed);
+        let receipt_id = last_receipt_id(&contract);
+        assert!(!contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            receipt_id,
+            RedemptionCharge {
+                near_copay: U128(ONE_NEAR),
+                ..token_charge(100)
+            },
+        ));
+        let receipt = contract.get_receipt(receipt_id).unwrap();
+        assert_eq!(receipt.status, FulfillmentStatus::Refunded);
+        assert_eq!(receipt.near_copay.0, ONE_NEAR);
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach at least 1000 yoctoNEAR to cover the co-pay")]
+    fn redeem_short_of_the_copay_is_rejected() {
+        let mut contract = setup_contract();
+        with_copay(&mut contract, 1_000);
+        set_caller(food_bank(), 999);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "A co-pay must be positive")]
+    fn zero_copay_is_rejected() {
+        let mut contract = setup_contract();
+        with_copay(&mut contract, 0);
+    }
+
+    #[test]
+    fn pause_blocks_mutations_but_not_views() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        contract.pause();
+        assert!(contract.is_paused());
+        assert_eq!(contract.get_balance(food_bank()).0, 150);
+        assert_eq!(contract.get_redemption_options().len(), 3);
+
+        // Option CRUD stays open so data can be fixed while paused.
+        set_caller(owner(), ONE_NEAR);
+        contract.add_redemption_option(
+            "tote_bags".to_string(),
+            "Tote bags".to_string(),
+            U128(10),
+            "Branded tote bags".to_string(),
+            None,
+        );
+
+        contract.unpause();
+        assert!(!contract.is_paused());
+        contribute(&mut contract, &food_bank(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract is paused")]
+    fn pause_blocks_record_contribution() {
+        let mut contract = setup_contract();
+        contract.pause();
+        contract.record_contribution(food_bank(), metrics(50, 50, 50), None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach at least 45 TGas to redeem supplier_discount")]
+    fn redeem_requires_enough_gas_up_front() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        testing_env!(context(food_bank(), 1)
+            .prepaid_gas(Gas(44 * Gas::ONE_TERA.0))
+            .build());
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach at least 75 TGas to redeem supplier_discount")]
+    fn benefit_gas_is_configurable_per_option() {
+        let mut contract = setup_contract();
+        assert_eq!(contract.get_benefit_gas("supplier_discount".to_string()), DEFAULT_GAS_FOR_BENEFIT);
+        contract.set_benefit_gas("supplier_discount".to_string(), Gas(50 * Gas::ONE_TERA.0));
+        contract.mint(food_bank(), 150);
+        testing_env!(context(food_bank(), 1)
+            .prepaid_gas(Gas(60 * Gas::ONE_TERA.0))
+            .build());
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract is paused")]
+    fn pause_blocks_redeem_tokens() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        contract.pause();
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a guardian can pause")]
+    fn pause_is_owner_or_guardian_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.pause();
+    }
+
+    #[test]
+    fn refund_completes_while_paused() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+
+        set_caller(owner(), 0);
+        contract.pause();
+
+        resolve_callback(PromiseResult::Failed);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+        assert_eq!(contract.get_balance(food_bank()).0, 150);
+        assert!(contract.is_paused());
+    }
+
+    #[test]
+    fn withdraw_near_keeps_storage_stake() {
+        let contract = setup_contract();
+        let locked = Balance::from(env::storage_usage()) * env::storage_byte_cost()
+            + STORAGE_SAFETY_MARGIN;
+        assert_eq!(contract.get_available_near().0, env::account_balance() - locked);
+    }
+
+    #[test]
+    fn withdraw_near_transfers_to_destination() {
+        let mut contract = setup_contract();
+        set_caller(owner(), 1);
+        contract.withdraw_near(U128(ONE_NEAR), other_food_bank());
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, other_food_bank());
+        assert_eq!(receipts[0].actions, vec![VmAction::Transfer { deposit: ONE_NEAR }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "yoctoNEAR is available for withdrawal")]
+    fn withdraw_near_rejects_amount_above_available() {
+        let mut contract = setup_contract();
+        set_caller(owner(), 1);
+        let available = contract.get_available_near().0;
+        contract.withdraw_near(U128(available + 1), other_food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn withdraw_near_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 1);
+        contract.withdraw_near(U128(ONE_NEAR), food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn redeem_requires_one_yocto() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        set_caller(food_bank(), 0);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Redemption option not found")]
+    fn redeem_rejects_unknown_option() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("free_lunch".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "This redemption option is not available")]
+    fn redeem_rejects_unavailable_option() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        let mut option = contract.redemption_option(&"supplier_discount".to_string()).unwrap();
+        option.available = false;
+        contract.store_redemption_option(&"supplier_discount".to_string(), &option);
+
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient tokens for redemption")]
+    fn redeem_rejects_amount_below_cost() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(99), None);
+    }
+
+    #[test]
+    fn spent_accounts_stay_registered() {
+        let mut contract = setup_contract();
+        assert!(!contract.is_registered(food_bank()));
+        assert_eq!(contract.get_balance_opt(food_bank()), None);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        assert!(contract.is_registered(food_bank()));
+        assert_eq!(contract.get_balance_opt(food_bank()), Some(U128(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account not registered")]
+    fn redeem_rejects_unregistered_accounts() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient balance")]
+    fn redeem_rejects_insufficient_balance() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 80);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    fn options_without_a_benefit_method_burn_without_a_call() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.add_redemption_option(
+            "tote_bags".to_string(),
+            "Tote bags".to_string(),
+            U128(10),
+            "Branded tote bags".to_string(),
+            None,
+        );
+        contract.mint(food_bank(), 30);
+        set_caller(food_bank(), 1);
+        let outcome = contract.redeem_tokens("tote_bags".to_string(), U128(10), None);
+        assert!(matches!(outcome, PromiseOrValue::Value(true)));
+        assert_eq!(contract.get_balance(food_bank()).0, 20);
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Delivered);
+        assert_eq!(contract.get_program_stats().total_redemptions, 1);
+    }
+
+    #[test]
+    fn benefit_args_carry_the_redeemer_and_receipt() {
+        let args = render_benefit_args(
+            r#"{"redeemer":"{account_id}","receipt_id":{receipt_id}}"#,
+            &food_bank(),
+            7,
+        );
+        assert_eq!(args, r#"{"redeemer":"bob","receipt_id":7}"#);
+    }
+
+    #[test]
+    fn new_options_redeem_through_the_configured_contract() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.add_redemption_option(
+            "food_voucher".to_string(),
+            "Food voucher".to_string(),
+            U128(50),
+            "Voucher from a partner pantry".to_string(),
+            Some(BenefitCall {
+                method: "issue_voucher".to_string(),
+                args_template: Some(r#"{"redeemer":"{account_id}","receipt_id":{receipt_id}}"#.to_string()),
+                contract: Some(other_food_bank()),
+            }),
+        );
+        contract.mint(food_bank(), 50);
+        set_caller(food_bank(), 1);
+        assert!(matches!(
+            contract.redeem_tokens("food_voucher".to_string(), U128(50), None),
+            PromiseOrValue::Promise(_)
+        ));
+
+        resolve_callback(PromiseResult::Successful(vec![]));
+        assert!(contract.on_redemption_resolved(
+            food_bank(),
+            "food_voucher".to_string(),
+            last_receipt_id(&contract),
+            token_charge(50),
+        ));
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_receipt(0).unwrap().option_id, "food_voucher");
+    }
+
+    #[test]
+    fn failed_benefit_calls_leave_a_refunded_receipt() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Failed);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+        assert_eq!(contract.get_receipt(0).unwrap().status, FulfillmentStatus::Refunded);
+        assert_eq!(contract.get_fulfillment_stats("supplier_discount".to_string()).refunded, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Benefit args template must be valid JSON")]
+    fn malformed_benefit_args_are_rejected() {
+        let mut contract = setup_contract();
+        contract.add_redemption_option(
+            "food_voucher".to_string(),
+            "Food voucher".to_string(),
+            U128(50),
+            "Voucher from a partner pantry".to_string(),
+            Some(BenefitCall {
+                method: "issue_voucher".to_string(),
+                args_template: Some(r#"{"redeemer":{account_id}}"#.to_string()),
+                contract: None,
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Redemption option supplier_discount already exists")]
+    fn duplicate_option_ids_are_rejected() {
+        let mut contract = setup_contract();
+        contract.add_redemption_option(
+            "supplier_discount".to_string(),
+            "Another discount".to_string(),
+            U128(10),
+            "Same id, new name".to_string(),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Option id may only contain a-z, 0-9, '_' and '-'")]
+    fn option_ids_must_be_slugs() {
+        let mut contract = setup_contract();
+        contract.add_redemption_option(
+            "Tote bags".to_string(),
+            "Tote bags".to_string(),
+            U128(10),
+            "Branded tote bags".to_string(),
+            None,
+        );
+    }
+
+    #[test]
+    fn renaming_an_option_keeps_its_id_and_records() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+
+        set_caller(owner(), ONE_NEAR);
+        contract.rename_option_display("supplier_discount".to_string(), "Partner discount".to_string());
+        let option = contract.redemption_option(&"supplier_discount".to_string()).unwrap();
+        assert_eq!(option.name, "Partner discount");
+        assert_eq!(option.benefit_method, "apply_supplier_discount");
+        assert_eq!(contract.get_receipt(0).unwrap().option_id, "supplier_discount");
+        assert_eq!(contract.get_option_stats("supplier_discount".to_string()).redemptions_count, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Option name must not be empty")]
+    fn display_names_cannot_be_empty() {
+        let mut contract = setup_contract();
+        contract.rename_option_display("supplier_discount".to_string(), String::new());
+    }
+
+    #[test]
+    fn updating_an_option_keeps_its_name() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.update_redemption_option("grant_opportunity".to_string(), Some(U128(900)), None, Some(false));
+        let option = contract.redemption_option(&"grant_opportunity".to_string()).unwrap();
+        assert_eq!(option.cost.0, 900);
+        assert!(!option.available);
+        assert_eq!(option.name, "Grant opportunity");
+    }
+
+    /// Previews then records the contribution, returning both breakdowns.
+    fn preview_and_record(
+        contract: &mut FoodBankToken,
+        account: &AccountId,
+        metrics: ContributionMetricsV2,
+        campaign_id: Option<u64>,
+        category: Option<String>,
+    ) -> (RewardBreakdown, RewardBreakdown) {
+        let preview = match campaign_id {
+            Some(id) => contract.preview_campaign_reward(account.clone(), metrics.clone(), id),
+            None => contract.preview_reward(account.clone(), metrics.clone(), category.clone()),
+        };
+        set_caller(owner(), ONE_NEAR);
+        contract.record_contribution(account.clone(), metrics, campaign_id, None, None, category);
+        let index = contract.stored_contributions(account).len() as u64 - 1;
+        (preview, contract.get_reward_breakdown(account.clone(), index).unwrap())
+    }
+
+    #[test]
+    fn preview_matches_the_recorded_reward() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 5_000);
+        contract.mint(other_food_bank(), 500);
+        set_caller(other_food_bank(), 1);
+        contract.stake(U128(500));
+
+        let cases = [(100, 100, 100), (60, 70, 80), (10, 10, 10), (5, 5, 5)];
+        for account in [food_bank(), other_food_bank(), accounts(3)] {
+            for _ in 0..3 {
+                for (quality, improvement, frequency) in cases {
+                    let (preview, recorded) = preview_and_record(
+                        &mut contract,
+                        &account,
+                        metrics(quality, improvement, frequency),
+                        None,
+                        None,
+                    );
+                    assert_eq!(preview, recorded);
+                }
+            }
+        }
+        assert!(contract.get_reward_breakdown(other_food_bank(), 0).unwrap().stake_bonus_bps > 0);
+    }
+
+    #[test]
+    fn preview_includes_campaign_bonuses() {
+        let mut contract = setup_contract();
+        let id = contract.create_campaign("Harvest".to_string(), 0, 1_000, U128(100), 5_000);
+        let (preview, recorded) =
+            preview_and_record(&mut contract, &food_bank(), metrics(100, 100, 100), Some(id), None);
+        assert_eq!(preview, recorded);
+        assert!(preview.campaign_bonus.0 > 0);
+    }
+
+    #[test]
+    fn preview_shows_what_verification_would_pay() {
+        let mut contract = setup_contract();
+        contract.set_required_verifications("food_safety".to_string(), 1);
+        let (preview, recorded) = preview_and_record(
+            &mut contract,
+            &food_bank(),
+            metrics(100, 100, 100),
+            None,
+            Some("food_safety".to_string()),
+        );
+        assert_eq!(recorded.reward.0, 0);
+        assert_eq!(preview.reward, contract.get_verification(food_bank(), 0).unwrap().reward);
+    }
+}
@@ -0,0 +1,209 @@
+//! Cooldown on freshly minted contribution rewards. Each reward is locked
+//! as its own tranche and can't be redeemed until the tranche unlocks.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_REWARD_LOCK_DURATION: u64 = 14 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardLock {
+    pub amount: U128,
+    pub unlocks_at: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Applies to rewards minted from now on. Zero disables the lock.
+    pub fn set_reward_lock_duration(&mut self, duration_ns: u64) {
+        self.assert_owner_action();
+        self.reward_lock_duration = duration_ns;
+    }
+
+    // View functions
+    pub fn get_reward_lock_duration(&self) -> u64 {
+        self.reward_lock_duration
+    }
+
+    pub fn get_locked_balance(&self, account_id: AccountId) -> U128 {
+        U128(self.locked_balance(&account_id))
+    }
+
+    /// Tranches still locked, earliest first.
+    pub fn get_unlock_schedule(&self, account_id: AccountId) -> Vec<RewardLock> {
+        let now = env::block_timestamp();
+        self.reward_locks
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|lock| lock.unlocks_at > now)
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn lock_reward(&mut self, account_id: &AccountId, amount: Balance) {
+        if self.reward_lock_duration == 0 || amount == 0 {
+            return;
+        }
+        let mut locks = self.get_unlock_schedule(account_id.clone());
+        locks.push(RewardLock {
+            amount: U128(amount),
+            unlocks_at: env::block_timestamp() + self.reward_lock_duration,
+        });
+        self.reward_locks.insert(account_id, &locks);
+    }
+
+    pub(crate) fn locked_balance(&self, account_id: &AccountId) -> Balance {
+        self.get_unlock_schedule(account_id.clone())
+            .iter()
+            .map(|lock| lock.amount.0)
+            .sum()
+    }
+
+    /// The part of `balance` reward locks don't hold. Staking, penalties
+    /// and voids can leave more locked than held, so it floors at zero.
+    pub(crate) fn unlocked_balance(&self, account_id: &AccountId, balance: Balance) -> Balance {
+        balance - self.locked_balance(account_id).min(balance)
+    }
+
+    /// When the earliest locked tranche unlocks.
+    pub(crate) fn next_unlock(&self, account_id: &AccountId) -> Option<u64> {
+        self.get_unlock_schedule(account_id.clone())
+            .iter()
+            .map(|lock| lock.unlocks_at)
+            .min()
+    }
+
+    /// Drops tranches that have unlocked, removing the entry once none are
+    /// left.
+    pub(crate) fn compact_reward_locks(&mut self, account_id: &AccountId) {
+        let stored = match self.reward_locks.get(account_id) {
+            Some(stored) => stored,
+            None => return,
+        };
+        let locks = self.get_unlock_schedule(account_id.clone());
+        if locks.is_empty() {
+            self.reward_locks.remove(account_id);
+        } else if locks.len() < stored.len() {
+            self.reward_locks.insert(account_id, &locks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redemptions::RedeemCheck;
+    use crate::test_utils::*;
+
+    const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    fn locked_contract() -> FoodBankToken {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_reward_lock_duration(DEFAULT_REWARD_LOCK_DURATION);
+        contract
+    }
+
+    fn redeem(contract: &mut FoodBankToken) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    fn new_rewards_are_locked_in_tranches() {
+        let mut contract = locked_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(DAY);
+        contribute(&mut contract, &food_bank(), 50);
+
+        assert_eq!(contract.get_locked_balance(food_bank()).0, 150);
+        let schedule = contract.get_unlock_schedule(food_bank());
+        assert_eq!(schedule.len(), 2);
+        assert_eq!(schedule[0].unlocks_at, DEFAULT_REWARD_LOCK_DURATION);
+        assert_eq!(schedule[1].unlocks_at, DAY + DEFAULT_REWARD_LOCK_DURATION);
+
+        advance_time(DEFAULT_REWARD_LOCK_DURATION - DAY);
+        assert_eq!(contract.get_locked_balance(food_bank()).0, 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tokens are locked until 1209600000000000")]
+    fn spending_locked_rewards_is_rejected() {
+        let mut contract = locked_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        redeem(&mut contract);
+    }
+
+    #[test]
+    fn unlocked_rewards_can_be_redeemed() {
+        let mut contract = locked_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        contract.mint(food_bank(), 100);
+        assert_eq!(
+            contract.can_redeem(food_bank(), "supplier_discount".to_string()),
+            RedeemCheck::Ok { cost: U128(100) }
+        );
+        redeem(&mut contract);
+        assert_eq!(
+            contract.can_redeem(food_bank(), "supplier_discount".to_string()),
+            RedeemCheck::FundsLocked {
+                unlocks_at: DEFAULT_REWARD_LOCK_DURATION
+            }
+        );
+
+        advance_time(DEFAULT_REWARD_LOCK_DURATION);
+        redeem(&mut contract);
+        assert!(contract.reward_locks.get(&food_bank()).is_none());
+    }
+
+    #[test]
+    fn zero_duration_locks_nothing() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_reward_lock_duration(0);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_locked_balance(food_bank()).0, 0);
+        assert!(contract.reward_locks.get(&food_bank()).is_none());
+        redeem(&mut contract);
+    }
+
+    #[test]
+    fn more_locked_than_held_reports_the_lock() {
+        let mut contract = locked_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.add_redemption_option(
+            "tote_bags".to_string(),
+            "Tote bags".to_string(),
+            U128(10),
+            "Branded tote bags".to_string(),
+            None,
+        );
+        contribute(&mut contract, &food_bank(), 100);
+        contract.internal_set_balance(&food_bank(), 40);
+        assert_eq!(contract.unlocked_balance(&food_bank(), 40), 0);
+        assert_eq!(
+            contract.can_redeem(food_bank(), "tote_bags".to_string()),
+            RedeemCheck::FundsLocked {
+                unlocks_at: DEFAULT_REWARD_LOCK_DURATION
+            }
+        );
+        let options = contract.get_affordable_options(food_bank(), 0, 10);
+        let (_, _, tote_bags) = options.iter().find(|(option_id, _, _)| option_id == "tote_bags").unwrap();
+        assert_eq!(tote_bags.blocked_by.as_deref(), Some("FundsLocked"));
+        contract.estimate_redeem(food_bank(), "tote_bags".to_string());
+    }
+
+    #[test]
+    fn default_duration_is_fourteen_days() {
+        set_caller(owner(), 0);
+        let contract = FoodBankToken::new_default(owner(), U128(INITIAL_SUPPLY));
+        assert_eq!(contract.get_reward_lock_duration(), 14 * DAY);
+    }
+}
@@ -0,0 +1,272 @@
+//! Donor-funded matching: attached NEAR becomes a token budget that matches
+//! contribution rewards until it runs out.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance, Promise, ONE_NEAR};
+
+use crate::events::{Event, MatchingPoolDrawn, MatchingPoolFunded, MatchingPoolSwept};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchingPool {
+    pub donor: AccountId,
+    /// NEAR received from the donor, in yoctoNEAR.
+    pub deposited: U128,
+    /// Tokens the deposits converted into, sponsored tokens included.
+    pub budget: U128,
+    /// Part of the budget credited by fungible token sponsors. It is spent
+    /// first and is not refundable.
+    pub sponsored: U128,
+    pub remaining: U128,
+}
+
+impl MatchingPool {
+    fn new(donor: AccountId) -> Self {
+        Self {
+            donor,
+            deposited: U128(0),
+            budget: U128(0),
+            sponsored: U128(0),
+            remaining: U128(0),
+        }
+    }
+
+    /// NEAR backing the unspent part of the NEAR-funded budget.
+    pub(crate) fn unspent_near(&self) -> Balance {
+        let near_budget = self.budget.0 - self.sponsored.0;
+        if near_budget == 0 {
+            return 0;
+        }
+        let spent = self.budget.0 - self.remaining.0;
+        let sponsored_left = self.sponsored.0.saturating_sub(spent);
+        self.deposited.0 * (self.remaining.0 - sponsored_left) / near_budget
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Converts the attached NEAR into matching budget at the current rate,
+    /// rounding down. The pool has a single donor until it is swept.
+    #[payable]
+    pub fn fund_matching_pool(&mut self) -> U128 {
+        self.assert_not_paused();
+        let donor = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        assert!(self.tokens_per_near > 0, "Matching rate is not set");
+        let tokens = deposit * self.tokens_per_near / ONE_NEAR;
+        assert!(tokens > 0, "Deposit is too small to fund any tokens");
+
+        let mut pool = self.matching_pool.take()
+            .unwrap_or_else(|| MatchingPool::new(donor.clone()));
+        assert!(pool.donor == donor, "Matching pool is funded by another donor");
+        pool.deposited = U128(pool.deposited.0 + deposit);
+        pool.budget = U128(pool.budget.0 + tokens);
+        pool.remaining = U128(pool.remaining.0 + tokens);
+        MatchingPoolFunded {
+            donor: &pool.donor,
+            deposit: U128(deposit),
+            tokens: U128(tokens),
+        }
+        .emit();
+        self.matching_pool = Some(pool);
+        U128(tokens)
+    }
+
+    /// Applies to deposits made after the change.
+    pub fn set_tokens_per_near(&mut self, tokens_per_near: U128) {
+        self.assert_owner_action();
+        self.tokens_per_near = tokens_per_near.0;
+    }
+
+    /// Closes the pool and returns the NEAR behind its unspent budget to the
+    /// donor.
+    pub fn sweep_matching_pool(&mut self) -> Promise {
+        self.assert_owner_action();
+        let pool = self.matching_pool.take().expect("No matching pool");
+        let refund = pool.unspent_near();
+        MatchingPoolSwept {
+            donor: &pool.donor,
+            amount: U128(refund),
+        }
+        .emit();
+        Promise::new(pool.donor).transfer(refund)
+    }
+
+    // View functions
+    pub fn get_matching_pool(&self) -> Option<MatchingPool> {
+        self.matching_pool.clone()
+    }
+
+    pub fn get_tokens_per_near(&self) -> U128 {
+        U128(self.tokens_per_near)
+    }
+}
+
+impl FoodBankToken {
+    /// Match available for a reward of `reward`.
+    pub(crate) fn matching_for(&self, reward: Balance) -> Balance {
+        self.matching_pool
+            .as_ref()
+            .map_or(0, |pool| reward.min(pool.remaining.0))
+    }
+
+    pub(crate) fn draw_matching_pool(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        let pool = self.matching_pool.as_mut().expect("No matching pool");
+        pool.remaining = U128(pool.remaining.0 - amount);
+        MatchingPoolDrawn {
+            account_id,
+            amount: U128(amount),
+            remaining: pool.remaining,
+        }
+        .emit();
+    }
+
+    /// Adds sponsored tokens to the pool, opening one in the sponsor's name
+    /// when there is none.
+    pub(crate) fn credit_matching_pool(&mut self, sponsor: &AccountId, tokens: Balance) {
+        let pool = self.matching_pool
+            .get_or_insert_with(|| MatchingPool::new(sponsor.clone()));
+        pool.budget = U128(pool.budget.0 + tokens);
+        pool.sponsored = U128(pool.sponsored.0 + tokens);
+        pool.remaining = U128(pool.remaining.0 + tokens);
+    }
+
+    /// Adds donated tokens to the open pool without making the donor its
+    /// donor, and returns how many were added: none without a pool.
+    pub(crate) fn top_up_matching_pool(&mut self, tokens: Balance) -> Balance {
+        let Some(pool) = self.matching_pool.as_mut() else {
+            return 0;
+        };
+        pool.budget = U128(pool.budget.0 + tokens);
+        pool.sponsored = U128(pool.sponsored.0 + tokens);
+        pool.remaining = U128(pool.remaining.0 + tokens);
+        tokens
+    }
+
+    /// NEAR held for the matching pool that must not be withdrawn.
+    pub(crate) fn reserved_matching_near(&self) -> Balance {
+        self.matching_pool.as_ref().map_or(0, MatchingPool::unspent_near)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+
+    fn donor() -> AccountId {
+        accounts(3)
+    }
+
+    fn fund(contract: &mut FoodBankToken, deposit: Balance) -> Balance {
+        set_caller(donor(), deposit);
+        contract.fund_matching_pool().0
+    }
+
+    fn with_pool(deposit: Balance) -> FoodBankToken {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_tokens_per_near(U128(100));
+        fund(&mut contract, deposit);
+        contract
+    }
+
+    #[test]
+    fn conversion_rounds_down() {
+        let mut contract = setup_contract();
+        contract.set_tokens_per_near(U128(3));
+        // 1.5 NEAR at 3 tokens per NEAR is 4.5 tokens.
+        assert_eq!(fund(&mut contract, 3 * ONE_NEAR / 2), 4);
+        let pool = contract.get_matching_pool().unwrap();
+        assert_eq!(pool.budget.0, 4);
+        assert_eq!(pool.deposited.0, 3 * ONE_NEAR / 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit is too small to fund any tokens")]
+    fn dust_deposit_is_rejected() {
+        let mut contract = setup_contract();
+        contract.set_tokens_per_near(U128(3));
+        fund(&mut contract, ONE_NEAR / 3);
+    }
+
+    #[test]
+    fn rewards_are_matched_until_the_pool_runs_out() {
+        // 1.5 NEAR at 100 tokens per NEAR is a 150 token budget.
+        let mut contract = with_pool(3 * ONE_NEAR / 2);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 200);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().matched.0, 100);
+
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 200 + 150);
+        assert_eq!(contract.get_matching_pool().unwrap().remaining.0, 0);
+
+        // An empty pool matches nothing.
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 350 + 100);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 2).unwrap().matched.0, 0);
+    }
+
+    #[test]
+    fn the_reward_cap_cuts_the_match_first() {
+        let mut contract = with_pool(ONE_NEAR);
+        set_caller(owner(), 0);
+        set_max_reward_per_contribution(&mut contract, 120);
+        contribute(&mut contract, &food_bank(), 80);
+
+        let breakdown = contract.get_reward_breakdown(food_bank(), 0).unwrap();
+        assert_eq!(breakdown.matched.0, 40);
+        assert_eq!(breakdown.uncapped_reward.0, 160);
+        assert_eq!(contract.get_balance(food_bank()).0, 120);
+        assert_eq!(contract.get_matching_pool().unwrap().remaining.0, 60);
+    }
+
+    #[test]
+    fn sweep_returns_unspent_near_to_the_donor() {
+        let mut contract = with_pool(2 * ONE_NEAR);
+        contribute(&mut contract, &food_bank(), 100);
+        let reserved = contract.reserved_matching_near();
+        assert_eq!(reserved, ONE_NEAR);
+
+        set_caller(owner(), 0);
+        contract.sweep_matching_pool();
+        let receipts = get_created_receipts();
+        let refund = receipts.last().unwrap();
+        assert_eq!(refund.receiver_id, donor());
+        assert_eq!(refund.actions, vec![VmAction::Transfer { deposit: ONE_NEAR }]);
+        assert!(contract.get_matching_pool().is_none());
+    }
+
+    #[test]
+    fn sponsored_tokens_are_spent_first_and_not_refunded() {
+        let mut contract = with_pool(ONE_NEAR);
+        contract.credit_matching_pool(&other_food_bank(), 50);
+        contribute(&mut contract, &food_bank(), 100);
+        // 50 sponsored and 50 NEAR-funded tokens were spent.
+        assert_eq!(contract.reserved_matching_near(), ONE_NEAR / 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Matching pool is funded by another donor")]
+    fn pool_has_a_single_donor() {
+        let mut contract = with_pool(ONE_NEAR);
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.fund_matching_pool();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn sweep_is_owner_only() {
+        let mut contract = with_pool(ONE_NEAR);
+        contract.sweep_matching_pool();
+    }
+}
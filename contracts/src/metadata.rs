@@ -0,0 +1,80 @@
+//! NEP-330 source metadata, so explorers can tie the deployed code to a
+//! tagged commit.
+
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::near_bindgen;
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Standard {
+    pub standard: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractSourceMetadata {
+    pub version: Option<String>,
+    pub link: Option<String>,
+    pub standards: Vec<Standard>,
+}
+
+/// NEPs the contract implements. Extend this as more of them land.
+const STANDARDS: &[(&str, &str)] = &[("nep297", "1.0.0"), ("nep330", "1.1.0")];
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn set_source_metadata_link(&mut self, link: Option<String>) {
+        self.assert_owner_action();
+        self.source_metadata_link = link;
+    }
+
+    // View functions
+    pub fn contract_source_metadata(&self) -> ContractSourceMetadata {
+        ContractSourceMetadata {
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            link: self.source_metadata_link.clone(),
+            standards: STANDARDS
+                .iter()
+                .map(|(standard, version)| Standard {
+                    standard: standard.to_string(),
+                    version: version.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    #[test]
+    fn metadata_reports_version_and_standards() {
+        let contract = setup_contract();
+        let metadata = contract.contract_source_metadata();
+        assert_eq!(metadata.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(metadata.link, None);
+        let standards: Vec<&str> = metadata.standards.iter().map(|s| s.standard.as_str()).collect();
+        assert_eq!(standards, vec!["nep297", "nep330"]);
+    }
+
+    #[test]
+    fn link_is_owner_updatable_and_shown_in_config() {
+        let mut contract = setup_contract();
+        let link = "https://github.com/Afoxcute/needio/tree/v0.1.0".to_string();
+        contract.set_source_metadata_link(Some(link.clone()));
+        assert_eq!(contract.contract_source_metadata().link, Some(link.clone()));
+        assert_eq!(contract.get_config().source_metadata.link, Some(link));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn link_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.set_source_metadata_link(None);
+    }
+}
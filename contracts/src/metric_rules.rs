@@ -0,0 +1,220 @@
+//! Owner-set maxima for the submitted metrics. Pipelines whose metrics are
+//! not percentages, such as a participation count of up to 31, can have
+//! them rescaled to 0–100 so rewards, tiers and epochs see one scale.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::near_bindgen;
+
+use crate::contributions::{ContributionMetricsV2, MAX_EVIDENCE_CID_LENGTH};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// A maximum of 0 disables the metric: it must be submitted as 0 and counts
+/// as 0 towards the score.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MetricRules {
+    pub max_data_quality: u8,
+    pub max_model_improvement: u8,
+    pub max_participation_frequency: u8,
+    /// Rescale each metric from `0..=max` to `0..=100`, rounding half up.
+    pub normalize: bool,
+}
+
+impl Default for MetricRules {
+    fn default() -> Self {
+        Self {
+            max_data_quality: 100,
+            max_model_improvement: 100,
+            max_participation_frequency: 100,
+            normalize: false,
+        }
+    }
+}
+
+/// Metrics as the pipeline submitted them, before normalization.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RawMetrics {
+    pub data_quality: u8,
+    pub model_improvement: u8,
+    pub participation_frequency: u8,
+}
+
+impl From<&ContributionMetricsV2> for RawMetrics {
+    fn from(metrics: &ContributionMetricsV2) -> Self {
+        Self {
+            data_quality: metrics.data_quality,
+            model_improvement: metrics.model_improvement,
+            participation_frequency: metrics.participation_frequency,
+        }
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Applies to contributions recorded from now on; stored ones keep the
+    /// values they were recorded with.
+    pub fn set_metric_rules(&mut self, rules: MetricRules) {
+        self.assert_owner_action();
+        assert!(
+            rules.normalize
+                || [rules.max_data_quality, rules.max_model_improvement, rules.max_participation_frequency]
+                    .iter()
+                    .all(|max| *max <= 100),
+            "Maxima above 100 need normalize"
+        );
+        self.metric_rules = rules;
+    }
+
+    // View functions
+    pub fn get_metric_rules(&self) -> MetricRules {
+        self.metric_rules
+    }
+}
+
+impl FoodBankToken {
+    /// Checks submitted `metrics` against the rules and returns them on the
+    /// 0–100 scale the reward calculation expects.
+    pub(crate) fn apply_metric_rules(&self, metrics: &ContributionMetricsV2) -> ContributionMetricsV2 {
+        let rules = &self.metric_rules;
+        if let Some(cid) = &metrics.evidence_cid {
+            assert!(
+                cid.len() <= MAX_EVIDENCE_CID_LENGTH,
+                "evidence_cid exceeds {} bytes",
+                MAX_EVIDENCE_CID_LENGTH
+            );
+        }
+        let rule = |name: &str, value: u8, max: u8| {
+            assert!(value <= max, "{} must be between 0 and {}", name, max);
+            if rules.normalize {
+                normalize(value, max)
+            } else {
+                value
+            }
+        };
+        ContributionMetricsV2 {
+            data_quality: rule("data_quality", metrics.data_quality, rules.max_data_quality),
+            model_improvement: rule(
+                "model_improvement",
+                metrics.model_improvement,
+                rules.max_model_improvement,
+            ),
+            participation_frequency: rule(
+                "participation_frequency",
+                metrics.participation_frequency,
+                rules.max_participation_frequency,
+            ),
+            timestamp: metrics.timestamp,
+            evidence_cid: metrics.evidence_cid.clone(),
+        }
+    }
+}
+
+/// `value` out of `max` as a percentage, rounded half up. A disabled metric
+/// (`max` of 0) is always 0.
+pub(crate) fn normalize(value: u8, max: u8) -> u8 {
+    if max == 0 {
+        return 0;
+    }
+    ((u16::from(value) * 100 + u16::from(max) / 2) / u16::from(max)) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn frequency_rules(normalize: bool) -> MetricRules {
+        MetricRules {
+            max_participation_frequency: 31,
+            normalize,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalization_rounds_half_up() {
+        assert_eq!(normalize(15, 31), 48);
+        assert_eq!(normalize(16, 31), 52);
+        assert_eq!(normalize(31, 31), 100);
+        // 1 of 8 is 12.5%.
+        assert_eq!(normalize(1, 8), 13);
+        assert_eq!(normalize(200, 255), 78);
+    }
+
+    #[test]
+    fn normalized_metrics_are_scored_and_raw_values_kept() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.set_metric_rules(frequency_rules(true));
+        contract.record_contribution(food_bank(), metrics(80, 80, 31), None, None, None, None);
+
+        let stored = &contract.get_contributions(food_bank())[0];
+        assert_eq!(stored.participation_frequency, 100);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().score, 86);
+        let raw = contract.get_raw_contributions(food_bank());
+        assert_eq!(raw[0].participation_frequency, 31);
+        assert_eq!(raw[0].data_quality, 80);
+    }
+
+    #[test]
+    #[should_panic(expected = "participation_frequency must be between 0 and 31")]
+    fn metrics_above_their_maximum_are_rejected() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.set_metric_rules(frequency_rules(true));
+        contract.record_contribution(food_bank(), metrics(80, 80, 32), None, None, None, None);
+    }
+
+    #[test]
+    fn a_zero_maximum_disables_the_metric() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.set_metric_rules(MetricRules {
+            max_model_improvement: 0,
+            normalize: true,
+            ..Default::default()
+        });
+        contract.record_contribution(food_bank(), metrics(90, 0, 90), None, None, None, None);
+        assert_eq!(contract.get_contributions(food_bank())[0].model_improvement, 0);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().score, 60);
+    }
+
+    #[test]
+    #[should_panic(expected = "model_improvement must be between 0 and 0")]
+    fn disabled_metrics_must_be_zero() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.set_metric_rules(MetricRules {
+            max_model_improvement: 0,
+            ..Default::default()
+        });
+        contract.record_contribution(food_bank(), metrics(90, 1, 90), None, None, None, None);
+    }
+
+    #[test]
+    fn rule_changes_leave_stored_contributions_alone() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.set_metric_rules(frequency_rules(true));
+        contract.record_contribution(food_bank(), metrics(80, 80, 16), None, None, None, None);
+        contract.set_metric_rules(MetricRules::default());
+        contract.record_contribution(food_bank(), metrics(80, 80, 16), None, None, None, None);
+
+        let stored = contract.get_contributions(food_bank());
+        assert_eq!(stored[0].participation_frequency, 52);
+        assert_eq!(stored[1].participation_frequency, 16);
+        assert_eq!(contract.get_raw_contributions(food_bank())[0].participation_frequency, 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "Maxima above 100 need normalize")]
+    fn maxima_above_100_need_normalization() {
+        let mut contract = setup_contract();
+        contract.set_metric_rules(MetricRules {
+            max_data_quality: 200,
+            ..Default::default()
+        });
+    }
+}
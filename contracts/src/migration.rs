@@ -0,0 +1,1134 @@
+//! One-off rewrites of the stored state for releases that change its
+//! layout. `STATE_VERSION` goes up with every change to the stored layout,
+//! and `migrate` brings state from any earlier version up to it one step
+//! at a time, in the same transaction as the deploy.
+//!
+//! Version 1 is the original contract, whose seven fields are decoded
+//! through the frozen `StateV1` and laid out again as version 2 with every
+//! field added in between at its initial value. Counters and indexes the
+//! original contract never kept, such as `participants` and the program
+//! stats, start empty. Most later releases only appended fields, so their
+//! step appends the new fields' initial values to the stored bytes.
+//! Collections store nothing but their prefix and length, so those steps
+//! encode them through the `Any*` aliases below whatever their element
+//! types. Steps that rewrite the entries of a collection only do so for
+//! the small ones, redemption options and proposals; other collections are
+//! versioned entry by entry.
+
+use std::ops::Range;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, LookupSet, TreeMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Balance, PublicKey};
+
+use crate::acknowledgments::DEFAULT_ACKNOWLEDGMENT_WINDOW;
+use crate::activity::{ActivityFeed, DEFAULT_ACTIVITY_FEED_CAPACITY};
+use crate::archives::DEFAULT_MAX_HISTORY_LENGTH;
+use crate::badges::default_badge_milestones;
+use crate::batch_mint::MintBatchCap;
+use crate::disputes::DEFAULT_DISPUTE_WINDOW;
+use crate::entitlements::default_analytics_entitlement;
+use crate::epochs::RewardMode;
+use crate::event_export::{EventBuffer, DEFAULT_EVENT_BUFFER_CAPACITY};
+use crate::fulfillment::DEFAULT_FULFILLMENT_WINDOW;
+use crate::governance::{GovernanceConfig, Proposal, ProposalV1};
+use crate::grants::{GrantApplication, GrantOption, DEFAULT_GRANT_REFUND_WINDOW};
+use crate::init_config::{built_in_benefit, SUPPLIER_DISCOUNT_COOLDOWN};
+use crate::locks::DEFAULT_REWARD_LOCK_DURATION;
+use crate::matching::MatchingPool;
+use crate::metric_rules::MetricRules;
+use crate::options::{RedemptionOptionV2, VersionedRedemptionOption};
+use crate::recognition::{rank_donor, DEFAULT_RECOGNITION_PER_NEAR};
+use crate::referrals::{DEFAULT_REFERRAL_BONUS_BPS, DEFAULT_REFERRAL_BONUS_CAP};
+use crate::reputation::DEFAULT_REPUTATION_HALF_LIFE;
+use crate::reward_curve::RewardCurve;
+use crate::roles::Role;
+use crate::score_thresholds::ThresholdCohort;
+use crate::snapshots::BalanceSnapshot;
+use crate::staking::{default_stake_tiers, StakeTier, DEFAULT_STAKE_COOLDOWN};
+use crate::tenure::default_tenure_discounts;
+use crate::tiers::{default_tiers, LifetimeTier};
+use crate::timelock::DEFAULT_TIMELOCK_DELAY;
+use crate::transfer_policy::TransferPolicy;
+use crate::upgrade::StagedCode;
+use crate::vesting::{DEFAULT_VESTING_DURATION, DEFAULT_VESTING_THRESHOLD};
+use crate::{
+    ContributionMetrics, FoodBankToken, FoodBankTokenExt, RedemptionOption, DEFAULT_MIN_CONTRIBUTION_INTERVAL,
+};
+
+const STATE_KEY: &[u8] = b"STATE";
+/// The layout this release stores, passed to the next release's `migrate`
+/// when it is deployed through `deploy_staged_code`.
+pub(crate) const STATE_VERSION: u8 = 45;
+
+type AnyLookupMap = LookupMap<(), ()>;
+type AnyLookupSet = LookupSet<()>;
+type AnyUnorderedMap = UnorderedMap<(), ()>;
+type AnyUnorderedSet = UnorderedSet<()>;
+
+/// The state stored by the original contract.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct StateV1 {
+    owner: AccountId,
+    total_supply: Balance,
+    balances: LookupMap<AccountId, Balance>,
+    contributions: UnorderedMap<AccountId, Vec<ContributionMetrics>>,
+    redemption_options: UnorderedMap<String, RedemptionOptionV0>,
+    min_contribution_threshold: Balance,
+    /// Whole percent.
+    reward_rate: u8,
+}
+
+/// Options as the original contract stored them, keyed by their name.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct RedemptionOptionV0 {
+    name: String,
+    cost: Balance,
+    available: bool,
+    description: String,
+}
+
+impl From<RedemptionOptionV0> for RedemptionOption {
+    /// The original contract called a method on the redeemer for each of
+    /// its three built-in options, matched by the name they are keyed by;
+    /// any other option only burned its cost.
+    fn from(option: RedemptionOptionV0) -> Self {
+        let benefit = built_in_benefit(&option.name).unwrap_or_default();
+        Self {
+            name: option.name,
+            cost: U128(option.cost),
+            available: option.available,
+            description: option.description,
+            benefit_method: benefit.method,
+            benefit_args_template: benefit.args_template,
+            benefit_contract: benefit.contract,
+        }
+    }
+}
+
+/// The fields version 2 stored ahead of the score threshold.
+type LeadingFields = (
+    AccountId,
+    Option<AccountId>,
+    Balance,
+    LookupMap<AccountId, Balance>,
+    UnorderedMap<AccountId, Vec<ContributionMetrics>>,
+    UnorderedMap<String, RedemptionOption>,
+    LookupMap<AccountId, Vec<Role>>,
+    LookupMap<Role, Vec<AccountId>>,
+);
+
+/// The fields stored ahead of the options written before versioning.
+type BeforeLegacyOptions = (AccountId, Option<AccountId>, Balance, AnyLookupMap, AnyUnorderedMap);
+
+/// The fields stored ahead of `proposals`.
+type BeforeProposals = (
+    BeforeLegacyOptions,
+    (AnyUnorderedMap, AnyLookupMap, AnyLookupMap, u32, u16, bool, AnyUnorderedMap, u64, u64),
+    GovernanceConfig,
+);
+
+/// The fields stored ahead of `program_stats`.
+type BeforeProgramStats = (
+    BeforeProposals,
+    (AnyUnorderedMap, u64, AnyLookupMap, AnyLookupMap, Vec<(u64, u64)>, AnyUnorderedMap),
+    (Option<AccountId>, u16, Vec<(u64, u16)>, AnyLookupMap, AnyLookupMap, Vec<StakeTier>, u64),
+    (Balance, u64, AnyLookupMap, AnyLookupMap, Vec<LifetimeTier>, AnyLookupMap, AnyLookupMap, u16, Balance),
+    (AnyUnorderedMap, u64, AnyLookupMap, Option<MatchingPool>, Balance, AnyUnorderedMap),
+    (Option<AccountId>, Vec<u64>, AnyLookupMap, Option<String>, AnyUnorderedSet),
+);
+
+/// `ProgramStats` before donations were counted.
+type ProgramStatsV1 = (U128, U128, u64, u64, U128);
+
+/// Legacy options between contribution requirements and co-pays.
+type GatedRedemptionOption = (RedemptionOption, Option<u64>, Option<String>);
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Rewrites state stored by an earlier release into the current layout.
+    /// State already at `STATE_VERSION` is loaded as it is.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(from_version: u8) -> Self {
+        assert!(
+            (1..=STATE_VERSION).contains(&from_version),
+            "Can only migrate from state versions 1 to {}",
+            STATE_VERSION
+        );
+        let mut state = env::storage_read(STATE_KEY).expect("Contract state not found");
+        for version in from_version..STATE_VERSION {
+            state = upgrade_from(version, state);
+        }
+        let mut contract =
+            Self::try_from_slice(&state).expect("State was already migrated or has an unknown layout");
+        contract.configure_built_in_options(from_version);
+        contract
+    }
+}
+
+impl FoodBankToken {
+    /// Sets up the built-in options present in state from before a feature
+    /// the way `new_default` sets them up. The options are stored outside
+    /// the state the steps rewrite, so this runs once it has loaded.
+    fn configure_built_in_options(&mut self, from_version: u8) {
+        // Entitlements.
+        let analytics_id = "analytics_access".to_string();
+        if let Some(mut analytics) = self.redemption_option(&analytics_id).filter(|_| from_version <= 20) {
+            if analytics.grants_entitlement.is_none() {
+                analytics.grants_entitlement = Some(default_analytics_entitlement());
+                self.store_redemption_option(&analytics_id, &analytics);
+            }
+        }
+        // Redemption cooldowns.
+        let supplier_id = "supplier_discount".to_string();
+        if let Some(mut supplier) = self.redemption_option(&supplier_id).filter(|_| from_version <= 21) {
+            if supplier.cooldown_ns.is_none() {
+                supplier.cooldown_ns = Some(SUPPLIER_DISCOUNT_COOLDOWN);
+                self.store_redemption_option(&supplier_id, &supplier);
+            }
+        }
+        // Grants.
+        let grant_id = "grant_opportunity".to_string();
+        if from_version <= 36 && self.has_redemption_option(&grant_id) && self.grant_options.get(&grant_id).is_none() {
+            self.grant_options.insert(&grant_id, &GrantOption::default());
+        }
+    }
+}
+
+/// Rewrites `state` from `version` to the next one.
+fn upgrade_from(version: u8, state: Vec<u8>) -> Vec<u8> {
+    match version {
+        1 => original_to_v2(&state),
+        2 => score_threshold_to_u32(&state),
+        // Reputation scores.
+        3 => appended(state, &(DEFAULT_REPUTATION_HALF_LIFE, AnyLookupMap::new(b"i"))),
+        // Metric bounds.
+        4 => appended(state, &MetricRules::default()),
+        // Named metrics.
+        5 => appended(state, &AnyUnorderedMap::new(b"0")),
+        // Penalties.
+        6 => appended(state, &(AnyLookupMap::new(b"1"), AnyLookupMap::new(b"2"))),
+        // Batch minting.
+        7 => appended(state, &MintBatchCap::default()),
+        // Transfer policy.
+        8 => appended(state, &(TransferPolicy::Open, AnyUnorderedSet::new(b"3"))),
+        // Vote delegation.
+        9 => appended(state, &delegation_fields()),
+        10 => proposals_with_actions(state),
+        11 => reward_curve_before_delegations(state),
+        // Reward cap.
+        12 => appended(state, &Balance::default()),
+        13 => legacy_options_with_requirements(state),
+        14 => legacy_options_with_copays(state),
+        // Promo codes.
+        15 => appended(state, &(AnyLookupMap::new(b"7"), AnyLookupSet::new(b"8"))),
+        // Tenure discounts.
+        16 => appended(state, &(AnyLookupMap::new(b"9"), default_tenure_discounts())),
+        17 => versioned_redemption_options(state),
+        // Contribution archives.
+        18 => appended(state, &(DEFAULT_MAX_HISTORY_LENGTH, AnyLookupMap::new(b"ca".to_vec()))),
+        // Acknowledgments.
+        19 => appended(
+            state,
+            &(
+                false,
+                DEFAULT_ACKNOWLEDGMENT_WINDOW,
+                AnyLookupMap::new(b"ak".to_vec()),
+                AnyLookupMap::new(b"ao".to_vec()),
+                0u64,
+                0u64,
+                AnyLookupMap::new(b"ap".to_vec()),
+            ),
+        ),
+        // Entitlements.
+        20 => appended(state, &AnyLookupMap::new(b"en".to_vec())),
+        // Redemption cooldowns.
+        21 => appended(state, &AnyLookupMap::new(b"lr".to_vec())),
+        // Onboarding bonuses.
+        22 => appended(state, &(Balance::default(), AnyLookupMap::new(b"ob".to_vec()))),
+        // Minting freeze.
+        23 => appended(state, &(false, AnyLookupMap::new(b"wr".to_vec()))),
+        // Status counters, which start from zero.
+        24 => appended(state, &(0u64, None::<u64>)),
+        // In-flight redemptions.
+        25 => appended(state, &AnyLookupMap::new(b"rf".to_vec())),
+        // Attestations.
+        26 => appended(
+            state,
+            &(None::<PublicKey>, None::<(PublicKey, u64)>, AnyLookupSet::new(b"an".to_vec())),
+        ),
+        // Token sales.
+        27 => appended(state, &(Balance::default(), false, None::<Balance>, Balance::default())),
+        // Buybacks.
+        28 => appended(state, &(Balance::default(), Balance::default())),
+        // Statements.
+        29 => appended(state, &(AnyLookupMap::new(b"sl".to_vec()), AnyLookupMap::new(b"sm".to_vec()))),
+        // Event export.
+        30 => appended(state, &EventBuffer::new(b"eb".to_vec(), DEFAULT_EVENT_BUFFER_CAPACITY)),
+        // Relayed calls.
+        31 => appended(state, &(AnyLookupMap::new(b"rk".to_vec()), AnyLookupMap::new(b"rn".to_vec()))),
+        // Storage credit.
+        32 => appended(state, &(AnyLookupMap::new(b"sc".to_vec()), Balance::default())),
+        // Rate overrides.
+        33 => appended(state, &AnyUnorderedMap::new(b"rv".to_vec())),
+        // Account types.
+        34 => appended(state, &AnyLookupMap::new(b"at".to_vec())),
+        // Suppliers.
+        35 => appended(
+            state,
+            &(AnyUnorderedMap::new(b"su".to_vec()), AnyLookupMap::new(b"os".to_vec())),
+        ),
+        // Grants.
+        36 => appended(
+            state,
+            &(
+                AnyLookupMap::new(b"go".to_vec()),
+                AnyUnorderedMap::new(b"ga".to_vec()),
+                AnyLookupMap::new(b"gp".to_vec()),
+                0u64,
+                DEFAULT_GRANT_REFUND_WINDOW,
+            ),
+        ),
+        // Score thresholds.
+        37 => appended(state, &(AnyLookupMap::new(b"to".to_vec()), Vec::<ThresholdCohort>::new())),
+        38 => donations(state),
+        // Staged code.
+        39 => appended(state, &None::<StagedCode>),
+        // Activity feeds.
+        40 => appended(
+            state,
+            &(
+                ActivityFeed::<()>::new(b"fc".to_vec()),
+                ActivityFeed::<()>::new(b"fr".to_vec()),
+                DEFAULT_ACTIVITY_FEED_CAPACITY,
+            ),
+        ),
+        // Versioned reward breakdowns.
+        41 => appended(state, &AnyLookupMap::new(b"rb".to_vec())),
+        // Donor leaderboard and donation reserve.
+        42 => donor_ranking(state),
+        // Eligibility counts.
+        43 => appended(state, &(AnyLookupMap::new(b"vc".to_vec()), AnyLookupMap::new(b"dr".to_vec()))),
+        // Grant review queue index.
+        44 => grant_status_index(state),
+        _ => unreachable!(),
+    }
+}
+
+/// Byte length of the `Leading` fields at the start of `state`.
+fn leading_len<Leading: BorshDeserialize>(state: &[u8]) -> usize {
+    let mut rest = state;
+    Leading::deserialize(&mut rest).expect("State has an unknown layout");
+    state.len() - rest.len()
+}
+
+/// The field stored right after the `Leading` fields, with its byte range.
+fn field_after<Leading: BorshDeserialize, Field: BorshDeserialize>(state: &[u8]) -> (Field, Range<usize>) {
+    let start = leading_len::<Leading>(state);
+    let mut rest = &state[start..];
+    let field = Field::deserialize(&mut rest).expect("State has an unknown layout");
+    (field, start..state.len() - rest.len())
+}
+
+fn appended(mut state: Vec<u8>, fields: &impl BorshSerialize) -> Vec<u8> {
+    state.extend(fields.try_to_vec().unwrap());
+    state
+}
+
+/// `map` with every entry converted, in the same order. The entries are
+/// all read before any is written, since the map returns the value it
+/// replaces decoded as the new layout.
+fn converted<K, Old, New>(mut map: UnorderedMap<K, Old>, convert: impl Fn(Old) -> New) -> UnorderedMap<K, New>
+where
+    K: BorshSerialize + BorshDeserialize,
+    Old: BorshSerialize + BorshDeserialize,
+    New: BorshSerialize + BorshDeserialize,
+{
+    let entries = map.to_vec();
+    map.clear();
+    let mut converted = UnorderedMap::try_from_slice(&map.try_to_vec().unwrap()).unwrap();
+    for (key, value) in entries {
+        converted.insert(&key, &convert(value));
+    }
+    converted
+}
+
+/// `state` with every entry of the map stored after the `Leading` fields
+/// converted.
+fn rewritten_map<Leading, K, Old, New>(state: Vec<u8>, convert: impl Fn(Old) -> New) -> Vec<u8>
+where
+    Leading: BorshDeserialize,
+    K: BorshSerialize + BorshDeserialize,
+    Old: BorshSerialize + BorshDeserialize,
+    New: BorshSerialize + BorshDeserialize,
+{
+    let (map, range) = field_after::<Leading, UnorderedMap<K, Old>>(&state);
+    let map = converted(map, convert).try_to_vec().unwrap();
+    [&state[..range.start], &map, &state[range.end..]].concat()
+}
+
+fn delegation_fields() -> (AnyLookupMap, AnyLookupMap, AnyLookupMap) {
+    (AnyLookupMap::new(b"4"), AnyLookupMap::new(b"5"), AnyLookupMap::new(b"6"))
+}
+
+/// Version 1 to 2: lays the original fields out with everything added up
+/// to version 2, widening the whole-percent rate to basis points and
+/// giving the options their benefit fields.
+fn original_to_v2(state: &[u8]) -> Vec<u8> {
+    let original = StateV1::try_from_slice(state).expect("State has an unknown layout");
+    assert!(
+        original.reward_rate <= 100,
+        "Stored reward rate {} is not a percentage",
+        original.reward_rate
+    );
+    let redemption_options = converted(original.redemption_options, RedemptionOption::from);
+    let governance = (
+        (
+            original.owner,
+            None::<AccountId>,
+            original.total_supply,
+            original.balances,
+            original.contributions,
+            redemption_options,
+            AnyLookupMap::new(b"l"),
+            AnyLookupMap::new(b"h"),
+            original.min_contribution_threshold,
+            u16::from(original.reward_rate) * 100,
+        ),
+        (
+            false,
+            AnyUnorderedMap::new(b"p"),
+            0u64,
+            DEFAULT_TIMELOCK_DELAY,
+            GovernanceConfig::default(),
+            AnyUnorderedMap::new(b"g"),
+            0u64,
+            AnyLookupMap::new(b"v"),
+            AnyLookupMap::new(b"s"),
+            Vec::<(u64, u64)>::new(),
+        ),
+    );
+    let rewards = (
+        (
+            AnyUnorderedMap::new(b"f"),
+            None::<AccountId>,
+            0u16,
+            Vec::<(u64, u16)>::new(),
+            AnyLookupMap::new(b"w"),
+            AnyLookupMap::new(b"k"),
+            default_stake_tiers(),
+            DEFAULT_STAKE_COOLDOWN,
+            DEFAULT_VESTING_THRESHOLD,
+            DEFAULT_VESTING_DURATION,
+        ),
+        (
+            AnyLookupMap::new(b"t"),
+            AnyLookupMap::new(b"a"),
+            default_tiers(),
+            AnyLookupMap::new(b"e"),
+            AnyLookupMap::new(b"n"),
+            DEFAULT_REFERRAL_BONUS_BPS,
+            DEFAULT_REFERRAL_BONUS_CAP,
+            AnyUnorderedMap::new(b"q"),
+            0u64,
+            AnyLookupMap::new(b"m"),
+        ),
+    );
+    let program = (
+        (
+            None::<MatchingPool>,
+            Balance::default(),
+            AnyUnorderedMap::new(b"x"),
+            None::<AccountId>,
+            default_badge_milestones(),
+            AnyLookupMap::new(b"d"),
+            None::<String>,
+            AnyUnorderedSet::new(b"u"),
+            (U128(0), U128(0), 0u64, 0u64, U128(0)),
+            AnyLookupMap::new(b"o"),
+        ),
+        (
+            AnyLookupMap::new(b"y"),
+            AnyLookupMap::new(b"z"),
+            DEFAULT_MIN_CONTRIBUTION_INTERVAL,
+            AnyLookupMap::new(b"j"),
+            AnyLookupMap::new(b"C"),
+            RewardMode::Instant,
+            0u64,
+            0u64,
+            AnyLookupMap::new(b"E"),
+            AnyLookupMap::new(b"S"),
+        ),
+    );
+    let distribution = (
+        (
+            AnyLookupMap::new(b"P"),
+            Vec::<u64>::new(),
+            Vec::<(u64, u64)>::new(),
+            AnyLookupMap::new(b"D"),
+            0u64,
+            AnyLookupSet::new(b"L"),
+            Balance::default(),
+            AnyUnorderedMap::new(b"A"),
+            AnyUnorderedMap::new(b"R"),
+            None::<BalanceSnapshot>,
+        ),
+        (
+            0u64,
+            AnyLookupMap::new(b"X"),
+            DEFAULT_REWARD_LOCK_DURATION,
+            AnyLookupMap::new(b"K"),
+            Vec::<AccountId>::new(),
+            0u32,
+            AnyUnorderedMap::new(b"M"),
+            0u64,
+            AnyUnorderedSet::new(b"G"),
+            AnyLookupMap::new(b"T"),
+        ),
+    );
+    let fulfillment = (
+        (
+            DEFAULT_DISPUTE_WINDOW,
+            AnyLookupMap::new(b"I"),
+            AnyUnorderedSet::new(b"O"),
+            AnyLookupMap::new(b"N"),
+            AnyUnorderedSet::new(b"V"),
+            AnyLookupMap::new(b"W"),
+            AnyUnorderedSet::new(b"Q"),
+            DEFAULT_FULFILLMENT_WINDOW,
+            AnyLookupMap::new(b"F"),
+        ),
+        (
+            0u64,
+            0u64,
+            AnyLookupMap::new(b"H"),
+            AnyLookupMap::new(b"B"),
+            AnyLookupMap::new(b"U"),
+            AnyLookupMap::new(b"J"),
+            AnyLookupMap::new(b"Y"),
+            AnyLookupMap::new(b"Z"),
+        ),
+    );
+    (governance, rewards, program, distribution, fulfillment).try_to_vec().unwrap()
+}
+
+/// Version 2 to 3: narrows the `Balance` threshold to the `u32` score
+/// threshold it always held.
+fn score_threshold_to_u32(state: &[u8]) -> Vec<u8> {
+    let at = leading_len::<LeadingFields>(state);
+    let threshold = Balance::deserialize(&mut &state[at..]).expect("State has an unknown layout");
+    assert!(threshold <= 100, "Stored threshold {} is not a score", threshold);
+    [&state[..at], &(threshold as u32).to_le_bytes(), &state[at + 16..]].concat()
+}
+
+/// Version 10 to 11: proposals carry a `ProposalAction` and a failure
+/// reason.
+fn proposals_with_actions(state: Vec<u8>) -> Vec<u8> {
+    rewritten_map::<BeforeProposals, u64, ProposalV1, Proposal>(state, Proposal::from)
+}
+
+/// Version 11 to 12: the reward curve went in ahead of the delegation
+/// fields, which version 10 had appended.
+fn reward_curve_before_delegations(state: Vec<u8>) -> Vec<u8> {
+    let delegations = delegation_fields().try_to_vec().unwrap();
+    assert!(state.ends_with(&delegations), "State has an unknown layout");
+    let at = state.len() - delegations.len();
+    [&state[..at], &RewardCurve::Linear.try_to_vec().unwrap(), &delegations].concat()
+}
+
+/// Version 13 to 14: options gained contribution requirements, stored
+/// after the fields they had.
+fn legacy_options_with_requirements(state: Vec<u8>) -> Vec<u8> {
+    rewritten_map::<BeforeLegacyOptions, String, RedemptionOption, GatedRedemptionOption>(state, |option| {
+        (option, None, None)
+    })
+}
+
+/// Version 14 to 15: options gained a co-pay, making them the
+/// `RedemptionOptionV2` layout.
+fn legacy_options_with_copays(state: Vec<u8>) -> Vec<u8> {
+    rewritten_map::<BeforeLegacyOptions, String, GatedRedemptionOption, RedemptionOptionV2>(
+        state,
+        |(option, min_contributions, prerequisite_option)| RedemptionOptionV2 {
+            min_contributions,
+            prerequisite_option,
+            ..option.into()
+        },
+    )
+}
+
+/// Version 17 to 18: options moved to the versioned map. The legacy map
+/// is left empty, since `RedemptionOption` is the layout it held before
+/// contribution requirements.
+fn versioned_redemption_options(state: Vec<u8>) -> Vec<u8> {
+    let (mut legacy, range) = field_after::<BeforeLegacyOptions, UnorderedMap<String, RedemptionOptionV2>>(&state);
+    let mut options = UnorderedMap::<String, VersionedRedemptionOption>::new(b"ro".to_vec());
+    for (option_id, option) in legacy.to_vec() {
+        options.insert(&option_id, &VersionedRedemptionOption::V2(option));
+    }
+    legacy.clear();
+    let legacy = legacy.try_to_vec().unwrap();
+    appended([&state[..range.start], &legacy, &state[range.end..]].concat(), &options)
+}
+
+/// Version 38 to 39: program stats count donated NEAR, after the totals
+/// they had, and donor recognition is appended.
+fn donations(state: Vec<u8>) -> Vec<u8> {
+    let (_, stats) = field_after::<BeforeProgramStats, ProgramStatsV1>(&state);
+    let state = [&state[..stats.end], &U128(0).try_to_vec().unwrap(), &state[stats.end..]].concat();
+    appended(
+        state,
+        &(
+            AnyLookupMap::new(b"rc".to_vec()),
+            Vector::<()>::new(b"rd".to_vec()),
+            DEFAULT_RECOGNITION_PER_NEAR,
+        ),
+    )
+}
+
+/// Version 42 to 43: the donor leaderboard is kept ranked, starting from
+/// the donors recognized so far, and a donation reserve is appended.
+/// Donations made before it existed were never set aside, so it starts
+/// empty.
+fn donor_ranking(state: Vec<u8>) -> Vec<u8> {
+    let recognition = LookupMap::<AccountId, Balance>::new(b"rc".to_vec());
+    let mut top_donors = Vec::new();
+    // The registry is read entry by entry from its prefix, in the order of
+    // first donations, since its length sits in the middle of the state.
+    for index in 0u64.. {
+        let key = [b"rd".as_slice(), &index.to_le_bytes()].concat();
+        let Some(donor) = env::storage_read(&key) else {
+            break;
+        };
+        let donor = AccountId::try_from_slice(&donor).unwrap();
+        rank_donor(&mut top_donors, &donor, recognition.get(&donor).unwrap_or(0));
+    }
+    appended(state, &(top_donors, Balance::default()))
+}
+
+fn grant_status_index(state: Vec<u8>) -> Vec<u8> {
+    let mut index = TreeMap::new(b"gs".to_vec());
+    // The applications are read from the values of their map's prefix,
+    // like the donor registry above.
+    for position in 0u64.. {
+        let key = [b"gav".as_slice(), &position.to_le_bytes()].concat();
+        let Some(application) = env::storage_read(&key) else {
+            break;
+        };
+        let application = GrantApplication::try_from_slice(&application).unwrap();
+        index.insert(&(application.status, application.application_id), &());
+    }
+    appended(state, &index)
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::serde_json::{self, json, Value};
+
+    use near_sdk::test_utils::accounts;
+    use near_sdk::PromiseResult;
+
+    use super::*;
+    use crate::governance::{ProposalKind, ProposalStatus};
+    use crate::grants::GrantStatus;
+    use crate::init_config::InitConfig;
+    use crate::test_utils::*;
+
+    /// `contract` as version 3 stored it: the fields up to
+    /// `account_redemption_counts`, with the program stats counting no
+    /// donations.
+    fn state_v3(contract: &FoodBankToken) -> Vec<u8> {
+        let stats = &contract.program_stats;
+        let c = contract;
+        [
+            (
+                &c.owner,
+                &c.pending_owner,
+                &c.total_supply,
+                &c.balances,
+                &c.legacy_contributions,
+                &c.legacy_redemption_options,
+                &c.roles,
+                &c.role_holders,
+                &c.min_score_threshold,
+                &c.reward_rate_bps,
+                &c.paused,
+                &c.pending_changes,
+                &c.next_param_change_id,
+                &c.timelock_delay,
+                &c.governance_config,
+                &c.proposals,
+                &c.next_proposal_id,
+                &c.votes,
+                &c.snapshot_balances,
+                &c.open_snapshots,
+            )
+                .try_to_vec()
+                .unwrap(),
+            (
+                &c.frozen_accounts,
+                &c.treasury_account,
+                &c.treasury_share_bps,
+                &c.emission_schedule,
+                &c.legacy_reward_breakdowns,
+                &c.stakes,
+                &c.stake_tiers,
+                &c.stake_cooldown,
+                &c.vesting_threshold,
+                &c.vesting_duration,
+                &c.vesting,
+                &c.account_stats,
+                &c.tiers,
+                &c.referrals,
+                &c.referees,
+                &c.referral_bonus_bps,
+                &c.referral_bonus_cap,
+                &c.campaigns,
+                &c.next_campaign_id,
+                &c.campaign_earnings,
+            )
+                .try_to_vec()
+                .unwrap(),
+            (
+                &c.matching_pool,
+                &c.tokens_per_near,
+                &c.accepted_tokens,
+                &c.badge_contract,
+                &c.badge_milestones,
+                &c.badges,
+                &c.source_metadata_link,
+                &c.participants,
+                (
+                    &stats.total_rewards_minted,
+                    &stats.total_tokens_redeemed,
+                    &stats.total_contributions_recorded,
+                    &stats.total_redemptions,
+                    &stats.total_rewards_clawed_back,
+                ),
+                &c.voided_contributions,
+                &c.option_stats,
+                &c.redemption_limits,
+                &c.min_contribution_interval,
+                &c.external_ids,
+                &c.contributions,
+                &c.reward_mode,
+                &c.epoch_origin,
+                &c.epoch_id_offset,
+                &c.epochs,
+                &c.epoch_scores,
+            )
+                .try_to_vec()
+                .unwrap(),
+            (
+                &c.epoch_participants,
+                &c.open_campaigns,
+                &c.epochs_to_settle,
+                &c.distributions,
+                &c.next_distribution_id,
+                &c.claimed_leaves,
+                &c.treasury_reclaimed,
+                &c.profiles,
+                &c.region_stats,
+                &c.balance_snapshot,
+                &c.next_balance_snapshot_id,
+                &c.exported_balances,
+                &c.reward_lock_duration,
+                &c.reward_locks,
+                &c.council,
+                &c.council_threshold,
+                &c.council_actions,
+                &c.next_council_action_id,
+                &c.guardians,
+                &c.contribution_recorded_at,
+            )
+                .try_to_vec()
+                .unwrap(),
+            (
+                &c.dispute_window,
+                &c.disputes,
+                &c.open_disputes,
+                &c.required_verifications,
+                &c.verifiers,
+                &c.verifications,
+                &c.verification_queue,
+                &c.fulfillment_window,
+                &c.receipts,
+                &c.next_receipt_id,
+                &c.next_receipt_to_expire,
+                &c.fulfillment_stats,
+                &c.benefit_gas,
+                &c.monthly_summaries,
+                &c.account_months,
+                &c.daily_stats,
+                &c.account_redemption_counts,
+            )
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat()
+    }
+
+    /// `state_v3` with the threshold as a `Balance`, as version 2 stored it.
+    fn state_v2(contract: &FoodBankToken) -> Vec<u8> {
+        let state = state_v3(contract);
+        let at = leading_len::<LeadingFields>(&state);
+        let threshold = Balance::from(contract.min_score_threshold).to_le_bytes();
+        [&state[..at], &threshold, &state[at + 4..]].concat()
+    }
+
+    /// The seven fields the original contract stored, with no accounts or
+    /// options yet.
+    fn original_state(reward_rate: u8) -> StateV1 {
+        StateV1 {
+            owner: owner(),
+            total_supply: INITIAL_SUPPLY,
+            balances: LookupMap::new(b"b"),
+            contributions: UnorderedMap::new(b"c"),
+            redemption_options: UnorderedMap::new(b"r"),
+            min_contribution_threshold: 10,
+            reward_rate,
+        }
+    }
+
+    /// A contract with every field past version 3 at its initial value, so
+    /// migrating its version 3 state gives it back byte for byte.
+    fn fresh_contract() -> FoodBankToken {
+        set_caller(owner(), 0);
+        FoodBankToken::new(owner(), U128(INITIAL_SUPPLY), None)
+    }
+
+    fn migrate_from(version: u8, state: &[u8]) -> FoodBankToken {
+        env::storage_write(STATE_KEY, state);
+        FoodBankToken::migrate(version)
+    }
+
+    fn assert_same_state(migrated: &FoodBankToken, contract: &FoodBankToken) {
+        assert_eq!(migrated.try_to_vec().unwrap(), contract.try_to_vec().unwrap());
+    }
+
+    /// Migrates the original state of an empty contract stored with
+    /// `percent`, which comes out as `new` would have set it up.
+    fn assert_migrates(percent: u8, rate_bps: u16) {
+        set_caller(owner(), 0);
+        let config = InitConfig {
+            reward_rate_bps: rate_bps,
+            min_score_threshold: 10,
+            redemption_options: None,
+        };
+        let contract = FoodBankToken::new(owner(), U128(INITIAL_SUPPLY), Some(config));
+
+        let migrated = migrate_from(1, &original_state(percent).try_to_vec().unwrap());
+        assert_eq!(migrated.get_reward_rate_bps(), rate_bps);
+        assert_eq!(migrated.get_min_score_threshold(), 10);
+        assert_same_state(&migrated, &contract);
+    }
+
+    #[test]
+    fn whole_percent_rate_becomes_basis_points() {
+        assert_migrates(5, 500);
+    }
+
+    #[test]
+    fn zero_rate_stays_zero() {
+        assert_migrates(0, 0);
+    }
+
+    #[test]
+    fn full_rate_migrates_to_10000_bps() {
+        assert_migrates(100, 10_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Stored reward rate 101 is not a percentage")]
+    fn rates_above_100_percent_are_rejected() {
+        migrate_from(1, &original_state(101).try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn original_accounts_and_options_are_kept() {
+        set_caller(owner(), 0);
+        let mut original = original_state(5);
+        original.balances.insert(&food_bank(), &42);
+        let metrics = ContributionMetrics {
+            data_quality: 80,
+            model_improvement: 60,
+            participation_frequency: 70,
+            timestamp: 1_000,
+        };
+        original.contributions.insert(&food_bank(), &vec![metrics]);
+        original.redemption_options.insert(
+            &"tote_bags".to_string(),
+            &RedemptionOptionV0 {
+                name: "Tote bags".to_string(),
+                cost: 10,
+                available: true,
+                description: "Reusable bags".to_string(),
+            },
+        );
+        original.redemption_options.insert(
+            &"supplier_discount".to_string(),
+            &RedemptionOptionV0 {
+                name: "supplier_discount".to_string(),
+                cost: 100,
+                available: true,
+                description: "10% discount on supplier purchases".to_string(),
+            },
+        );
+
+        let migrated = migrate_from(1, &original.try_to_vec().unwrap());
+        assert_eq!(migrated.get_owner(), owner());
+        assert_eq!(migrated.get_total_supply(), U128(INITIAL_SUPPLY));
+        assert_eq!(migrated.get_balance(food_bank()).0, 42);
+        let contributions = migrated.get_contributions(food_bank());
+        assert_eq!(contributions.len(), 1);
+        assert_eq!(contributions[0].data_quality, 80);
+        assert_eq!(contributions[0].timestamp, 1_000);
+        assert_eq!(migrated.legacy_redemption_options.len(), 0);
+        let option = migrated.redemption_option(&"tote_bags".to_string()).unwrap();
+        assert_eq!(option.name, "Tote bags");
+        assert_eq!(option.cost, U128(10));
+        assert!(option.available);
+        assert!(option.benefit_method.is_empty());
+        assert_eq!(option.benefit_contract, None);
+        let supplier = migrated.redemption_option(&"supplier_discount".to_string()).unwrap();
+        assert_eq!(supplier.cost, U128(100));
+        assert_eq!(supplier.benefit_method, "apply_supplier_discount");
+        assert_eq!(supplier.benefit_args_template, None);
+        assert_eq!(supplier.benefit_contract, None);
+    }
+
+    /// The original setup's state: its three options, keyed by their names.
+    fn original_with_built_ins() -> StateV1 {
+        set_caller(owner(), 0);
+        let mut original = original_state(5);
+        for (name, cost) in [("supplier_discount", 100), ("analytics_access", 200), ("grant_opportunity", 500)] {
+            original.redemption_options.insert(
+                &name.to_string(),
+                &RedemptionOptionV0 {
+                    name: name.to_string(),
+                    cost,
+                    available: true,
+                    description: String::new(),
+                },
+            );
+        }
+        original
+    }
+
+    /// Redeems `option_id` for `cost` and resolves its benefit call.
+    fn redeem(contract: &mut FoodBankToken, option_id: &str, cost: Balance) -> u64 {
+        set_caller(owner(), 0);
+        contract.mint(food_bank(), cost);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens(option_id.to_string(), U128(cost), None);
+        let receipt_id = last_receipt_id(contract);
+        resolve_callback(PromiseResult::Successful(Vec::new()));
+        contract.on_redemption_resolved(food_bank(), option_id.to_string(), receipt_id, token_charge(cost));
+        receipt_id
+    }
+
+    #[test]
+    fn upgraded_grant_redemptions_open_applications() {
+        let mut migrated = migrate_from(1, &original_with_built_ins().try_to_vec().unwrap());
+        assert_eq!(migrated.get_grant_option("grant_opportunity".to_string()), Some(GrantOption::default()));
+        let receipt_id = redeem(&mut migrated, "grant_opportunity", 500);
+        let applications = migrated.get_grant_applications_for(food_bank(), 0, 10);
+        assert_eq!(applications.len(), 1);
+        assert_eq!(applications[0].receipt_id, receipt_id);
+        assert_eq!(applications[0].status, GrantStatus::Submitted);
+    }
+
+    #[test]
+    fn upgraded_analytics_redemptions_grant_access() {
+        let mut migrated = migrate_from(1, &original_with_built_ins().try_to_vec().unwrap());
+        let (key, duration) = default_analytics_entitlement();
+        redeem(&mut migrated, "analytics_access", 200);
+        assert!(migrated.has_entitlement(food_bank(), key.clone()));
+        assert_eq!(
+            migrated.get_entitlement_expiry(food_bank(), key),
+            Some(env::block_timestamp() + duration)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "This redemption option is available again at")]
+    fn upgraded_supplier_discounts_have_their_cooldown() {
+        let mut migrated = migrate_from(1, &original_with_built_ins().try_to_vec().unwrap());
+        let supplier = migrated.redemption_option(&"supplier_discount".to_string()).unwrap();
+        assert_eq!(supplier.cooldown_ns, Some(SUPPLIER_DISCOUNT_COOLDOWN));
+        redeem(&mut migrated, "supplier_discount", 100);
+        redeem(&mut migrated, "supplier_discount", 100);
+    }
+
+    #[test]
+    fn balance_threshold_becomes_a_score_threshold() {
+        let mut contract = fresh_contract();
+        contract.min_score_threshold = 40;
+        contract.reward_rate_bps = 250;
+
+        let migrated = migrate_from(2, &state_v2(&contract));
+        assert_eq!(migrated.get_min_score_threshold(), 40);
+        assert_eq!(migrated.get_reward_rate_bps(), 250);
+        assert_same_state(&migrated, &contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a score")]
+    fn migrated_state_is_not_migrated_twice() {
+        migrate_from(2, &state_v3(&fresh_contract()));
+    }
+
+    #[test]
+    fn version_3_gains_every_later_field_at_its_initial_value() {
+        let contract = fresh_contract();
+        let migrated = migrate_from(3, &state_v3(&contract));
+        assert_same_state(&migrated, &contract);
+    }
+
+    #[test]
+    fn stored_proposals_gain_their_action() {
+        let mut contract = fresh_contract();
+        let mut old = UnorderedMap::<u64, ProposalV1>::new(b"g");
+        old.insert(
+            &0,
+            &ProposalV1 {
+                id: 0,
+                proposer: food_bank(),
+                kind: ProposalKind::SetRewardRateBps(1_200),
+                description: "Raise the rate".to_string(),
+                created_at: 1,
+                voting_ends_at: 2,
+                votes_for: U128(700),
+                votes_against: U128(400),
+                status: ProposalStatus::Executed,
+            },
+        );
+        contract.proposals = UnorderedMap::try_from_slice(&old.try_to_vec().unwrap()).unwrap();
+        contract.next_proposal_id = 1;
+
+        let migrated = migrate_from(3, &state_v3(&contract));
+        let proposal = serde_json::to_value(migrated.get_proposal(0).unwrap()).unwrap();
+        assert_eq!(proposal["action"], json!({ "SetRewardRateBps": 1_200 }));
+        assert_eq!(proposal["status"], "Executed");
+        assert_eq!(proposal["failure_reason"], Value::Null);
+        assert_eq!(proposal["votes_for"], "700");
+    }
+
+    #[test]
+    fn legacy_options_move_to_the_versioned_map() {
+        let mut contract = fresh_contract();
+        contract.legacy_redemption_options.insert(
+            &"tote_bags".to_string(),
+            &RedemptionOption {
+                name: "Tote bags".to_string(),
+                cost: U128(10),
+                available: true,
+                description: "Reusable bags".to_string(),
+                benefit_method: String::new(),
+                benefit_args_template: None,
+                benefit_contract: None,
+            },
+        );
+
+        let migrated = migrate_from(3, &state_v3(&contract));
+        assert_eq!(migrated.legacy_redemption_options.len(), 0);
+        assert_eq!(migrated.redemption_options.len(), 1);
+        let option = migrated.redemption_option(&"tote_bags".to_string()).unwrap();
+        assert_eq!(option.name, "Tote bags");
+        assert_eq!(option.cost, U128(10));
+        assert_eq!(option.min_contributions, None);
+        assert_eq!(option.near_copay, None);
+    }
+
+    #[test]
+    fn program_stats_start_counting_donations() {
+        let mut contract = fresh_contract();
+        contract.program_stats.total_contributions_recorded = 3;
+        contract.program_stats.total_rewards_clawed_back = U128(20);
+
+        let migrated = migrate_from(3, &state_v3(&contract));
+        assert_eq!(migrated.get_program_stats(), contract.get_program_stats());
+        assert_eq!(migrated.get_program_stats().total_donated_near, U128(0));
+    }
+
+    #[test]
+    fn recognized_donors_are_ranked() {
+        let mut contract = fresh_contract();
+        for (donor, recognition) in [(food_bank(), 100), (other_food_bank(), 300), (accounts(3), 100)] {
+            contract.recognition_donors.push(&donor);
+            contract.recognition_balances.insert(&donor, &recognition);
+        }
+        let state = contract.try_to_vec().unwrap();
+        let appended = (
+            (Vec::<(AccountId, Balance)>::new(), Balance::default()),
+            (AnyLookupMap::new(b"vc".to_vec()), AnyLookupMap::new(b"dr".to_vec())),
+            empty_grant_index(),
+        )
+            .try_to_vec()
+            .unwrap();
+        let state_v42 = state.strip_suffix(appended.as_slice()).unwrap();
+
+        let migrated = migrate_from(42, state_v42);
+        assert_eq!(
+            migrated.get_donor_leaderboard(0, 10),
+            vec![(other_food_bank(), U128(300)), (food_bank(), U128(100)), (accounts(3), U128(100))]
+        );
+        assert_eq!(migrated.get_donation_reserve(), U128(0));
+    }
+
+    fn empty_grant_index() -> TreeMap<(GrantStatus, u64), ()> {
+        TreeMap::new(b"gs".to_vec())
+    }
+
+    #[test]
+    fn grant_applications_are_indexed_by_status() {
+        let mut contract = fresh_contract();
+        let statuses = [GrantStatus::Awarded, GrantStatus::Submitted, GrantStatus::Submitted];
+        for (application_id, status) in (0u64..).zip(statuses) {
+            contract.grant_applications.insert(
+                &application_id,
+                &GrantApplication {
+                    application_id,
+                    account_id: food_bank(),
+                    option_id: "grant_opportunity".to_string(),
+                    receipt_id: application_id,
+                    status,
+                    submitted_at: 1,
+                    decision_note: None,
+                    refunded: U128(0),
+                },
+            );
+        }
+        let state = contract.try_to_vec().unwrap();
+        let appended = empty_grant_index().try_to_vec().unwrap();
+        let state_v44 = state.strip_suffix(appended.as_slice()).unwrap();
+
+        let migrated = migrate_from(44, state_v44);
+        let ids = |status| -> Vec<u64> {
+            migrated
+                .get_grant_applications_by_status(status, 0, 10)
+                .iter()
+                .map(|application| application.application_id)
+                .collect()
+        };
+        assert_eq!(ids(GrantStatus::Submitted), [1, 2]);
+        assert_eq!(ids(GrantStatus::Awarded), [0]);
+        assert!(ids(GrantStatus::Declined).is_empty());
+    }
+
+    #[test]
+    fn current_state_is_kept_as_it_is() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 42);
+        let migrated = migrate_from(STATE_VERSION, &contract.try_to_vec().unwrap());
+        assert_same_state(&migrated, &contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can only migrate from state versions 1 to 45")]
+    fn unknown_versions_are_rejected() {
+        FoodBankToken::migrate(46);
+    }
+}
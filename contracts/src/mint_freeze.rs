@@ -0,0 +1,216 @@
+//! Stopping reward emission without the full pause, so food banks can keep
+//! redeeming what they hold during an investigation. While minting is
+//! paused contributions are still recorded, but the reward they earn, and
+//! any onboarding bonus, is withheld. Rewards released from verification or
+//! acknowledgment in the meantime are withheld the same way. Once minting
+//! resumes, the owner releases them as calculated at the time.
+//!
+//! Epoch payouts are settled separately and are not held back.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{Event, MintingPaused, MintingResumed, WithheldRewardReleased};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithheldReward {
+    pub reward: U128,
+    pub onboarding_bonus: U128,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Guardians may pause minting; resuming it is an owner action.
+    pub fn set_minting_paused(&mut self, paused: bool) {
+        let actor = env::predecessor_account_id();
+        if paused {
+            assert!(
+                actor == self.owner || self.guardians.contains(&actor),
+                "Only the owner or a guardian can pause minting"
+            );
+            assert!(!self.minting_paused, "Minting is already paused");
+            self.minting_paused = true;
+            MintingPaused {
+                actor: &actor,
+                timestamp: env::block_timestamp(),
+            }
+            .record(&mut self.event_buffer);
+        } else {
+            self.assert_owner_action();
+            assert!(self.minting_paused, "Minting is not paused");
+            self.minting_paused = false;
+            MintingResumed {
+                actor: &actor,
+                timestamp: env::block_timestamp(),
+            }
+            .record(&mut self.event_buffer);
+        }
+    }
+
+    /// Pays the rewards withheld from the account's contributions at
+    /// indices `from_index` to `from_index + limit`, skipping voided ones.
+    /// Returns how many were released.
+    pub fn release_withheld_rewards(&mut self, account_id: AccountId, from_index: u64, limit: u64) -> u64 {
+        self.assert_owner_action();
+        assert!(!self.minting_paused, "Minting is paused");
+        let mut released = 0;
+        for index in from_index..from_index.saturating_add(limit) {
+            let key = (account_id.clone(), index);
+            let Some(withheld) = self.withheld_rewards.remove(&key) else {
+                continue;
+            };
+            if self.voided_contributions.get(&key).is_some() {
+                continue;
+            }
+            if withheld.reward.0 > 0 {
+                self.pay_withheld_reward(&account_id, index, withheld.reward);
+            }
+            if withheld.onboarding_bonus.0 > 0 {
+                self.mint_onboarding_bonus(&account_id, index, withheld.onboarding_bonus);
+                let stats = &mut self.program_stats;
+                stats.total_rewards_minted =
+                    U128(stats.total_rewards_minted.0 + withheld.onboarding_bonus.0);
+                self.record_daily_mint(withheld.onboarding_bonus.0);
+            }
+            WithheldRewardReleased {
+                account_id: &account_id,
+                account_type: self.account_type(&account_id),
+                index,
+                reward: withheld.reward,
+                onboarding_bonus: withheld.onboarding_bonus,
+            }
+            .emit();
+            released += 1;
+        }
+        released
+    }
+
+    // View functions
+    pub fn is_minting_paused(&self) -> bool {
+        self.minting_paused
+    }
+
+    /// Set while the contribution's reward is withheld and not yet released.
+    pub fn get_withheld_reward(&self, account_id: AccountId, index: u64) -> Option<WithheldReward> {
+        self.withheld_rewards.get(&(account_id, index))
+    }
+}
+
+impl FoodBankToken {
+    /// Adds to what is withheld from the contribution at `index`.
+    pub(crate) fn withhold_reward(&mut self, account_id: &AccountId, index: u64, update: impl FnOnce(&mut WithheldReward)) {
+        let key = (account_id.clone(), index);
+        let mut withheld = self.withheld_rewards.get(&key).unwrap_or(WithheldReward {
+            reward: U128(0),
+            onboarding_bonus: U128(0),
+        });
+        update(&mut withheld);
+        self.withheld_rewards.insert(&key, &withheld);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn frozen() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_minting_paused(true);
+        contract
+    }
+
+    #[test]
+    fn contributions_are_recorded_without_minting() {
+        let mut contract = frozen();
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_contributions(food_bank()).len(), 1);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 0);
+        assert_eq!(
+            contract.get_withheld_reward(food_bank(), 0),
+            Some(WithheldReward {
+                reward: U128(5),
+                onboarding_bonus: U128(0),
+            })
+        );
+
+        // Redemptions still work.
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    fn withheld_rewards_are_released_across_pages() {
+        let mut contract = frozen();
+        for _ in 0..3 {
+            contribute(&mut contract, &food_bank(), 100);
+        }
+        contract.set_minting_paused(false);
+        assert_eq!(contract.release_withheld_rewards(food_bank(), 0, 2), 2);
+        assert_eq!(contract.get_balance(food_bank()).0, 10);
+        assert!(contract.get_withheld_reward(food_bank(), 2).is_some());
+
+        assert_eq!(contract.release_withheld_rewards(food_bank(), 2, 2), 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 15);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 2).unwrap().reward.0, 5);
+        assert_eq!(contract.get_program_stats().total_rewards_minted.0, 15);
+    }
+
+    #[test]
+    fn rewards_cannot_be_released_twice() {
+        let mut contract = frozen();
+        contribute(&mut contract, &food_bank(), 100);
+        contract.set_minting_paused(false);
+        assert_eq!(contract.release_withheld_rewards(food_bank(), 0, 10), 1);
+        assert_eq!(contract.release_withheld_rewards(food_bank(), 0, 10), 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+    }
+
+    #[test]
+    fn onboarding_bonuses_are_withheld_too() {
+        let mut contract = frozen();
+        contract.set_onboarding_bonus(U128(50));
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        contract.set_minting_paused(false);
+        contract.release_withheld_rewards(food_bank(), 0, 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 55);
+    }
+
+    #[test]
+    fn voiding_before_release_claws_back_nothing() {
+        let mut contract = frozen();
+        contract.set_onboarding_bonus(U128(50));
+        contribute(&mut contract, &food_bank(), 100);
+        contract.mint(food_bank(), 20);
+        assert_eq!(contract.void_contribution(food_bank(), 0).0, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 20);
+        contract.set_minting_paused(false);
+        assert_eq!(contract.release_withheld_rewards(food_bank(), 0, 1), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting is paused")]
+    fn release_waits_for_minting_to_resume() {
+        let mut contract = frozen();
+        contribute(&mut contract, &food_bank(), 100);
+        contract.release_withheld_rewards(food_bank(), 0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn guardians_cannot_resume_minting() {
+        let mut contract = setup_contract();
+        contract.add_guardian(other_food_bank());
+        set_caller(other_food_bank(), 0);
+        contract.set_minting_paused(true);
+        contract.set_minting_paused(false);
+    }
+}
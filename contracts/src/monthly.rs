@@ -0,0 +1,240 @@
+//! Per-account contribution summaries by calendar month (UTC), keyed like
+//! `"2025-03"` after the block timestamp the contribution was recorded at.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, Balance};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub(crate) const NANOS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MonthSummary {
+    pub contributions: u64,
+    pub total_reward: U128,
+    pub avg_score: u8,
+    /// Kept so voids and rescores can move the average.
+    #[serde(skip)]
+    score_total: u64,
+}
+
+impl Default for MonthSummary {
+    fn default() -> Self {
+        Self {
+            contributions: 0,
+            total_reward: U128(0),
+            avg_score: 0,
+            score_total: 0,
+        }
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    pub fn get_monthly_summary(&self, account_id: AccountId, month_key: String) -> Option<MonthSummary> {
+        self.monthly_summaries.get(&(account_id, month_key))
+    }
+
+    /// Months with contributions, oldest first.
+    pub fn get_monthly_summaries(
+        &self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, MonthSummary)> {
+        self.account_months
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|month| {
+                let summary = self.monthly_summaries.get(&(account_id.clone(), month.clone())).unwrap();
+                (month, summary)
+            })
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn record_monthly_contribution(
+        &mut self,
+        account_id: &AccountId,
+        index: u64,
+        score: u8,
+        reward: Balance,
+    ) {
+        let month = match self.contribution_recorded_at.get(&(account_id.clone(), index)) {
+            Some(recorded_at) => month_key(recorded_at),
+            None => return,
+        };
+        let mut months = self.account_months.get(account_id).unwrap_or_default();
+        if months.last() != Some(&month) {
+            months.push(month);
+            self.account_months.insert(account_id, &months);
+        }
+        self.update_month(account_id, index, |summary| {
+            summary.contributions += 1;
+            summary.score_total += u64::from(score);
+            summary.total_reward = U128(summary.total_reward.0 + reward);
+        });
+    }
+
+    /// Reward paid after the contribution itself was counted.
+    pub(crate) fn record_monthly_reward(&mut self, account_id: &AccountId, index: u64, reward: Balance) {
+        self.update_month(account_id, index, |summary| {
+            summary.total_reward = U128(summary.total_reward.0 + reward);
+        });
+    }
+
+    /// Replaces a contribution's `(score, reward)` in its month.
+    pub(crate) fn rescore_monthly_contribution(
+        &mut self,
+        account_id: &AccountId,
+        index: u64,
+        previous: (u8, Balance),
+        current: (u8, Balance),
+    ) {
+        self.update_month(account_id, index, |summary| {
+            summary.score_total = summary.score_total - u64::from(previous.0) + u64::from(current.0);
+            summary.total_reward = U128(summary.total_reward.0 - previous.1 + current.1);
+        });
+    }
+
+    /// Applies `update` to the month contribution `index` was recorded in.
+    /// Contributions recorded before timestamps were kept have no month.
+    fn update_month(
+        &mut self,
+        account_id: &AccountId,
+        index: u64,
+        update: impl FnOnce(&mut MonthSummary),
+    ) {
+        let recorded_at = match self.contribution_recorded_at.get(&(account_id.clone(), index)) {
+            Some(recorded_at) => recorded_at,
+            None => return,
+        };
+        let key = (account_id.clone(), month_key(recorded_at));
+        let mut summary = self.monthly_summaries.get(&key).unwrap_or_default();
+        update(&mut summary);
+        summary.avg_score = summary
+            .score_total
+            .checked_div(summary.contributions)
+            .unwrap_or(0) as u8;
+        self.monthly_summaries.insert(&key, &summary);
+    }
+
+    /// Takes a voided contribution back out of its month.
+    pub(crate) fn void_monthly_contribution(
+        &mut self,
+        account_id: &AccountId,
+        index: u64,
+        score: u8,
+        reward: Balance,
+    ) {
+        self.update_month(account_id, index, |summary| {
+            summary.contributions -= 1;
+            summary.score_total -= u64::from(score);
+            summary.total_reward = U128(summary.total_reward.0.saturating_sub(reward));
+        });
+    }
+}
+
+/// `"YYYY-MM"` of a nanosecond Unix timestamp, in UTC.
+pub(crate) fn month_key(timestamp_ns: u64) -> String {
+    let (year, month) = year_month(timestamp_ns / NANOS_PER_DAY);
+    format!("{:04}-{:02}", year, month)
+}
+
+/// Year and month of a day count since 1970-01-01, using the proleptic
+/// Gregorian calendar (Howard Hinnant's `civil_from_days`).
+fn year_month(days: u64) -> (u64, u64) {
+    // Shifted so eras start on 0000-03-01 and leap days fall at year end.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Nanosecond timestamp of midnight UTC, `days_since_epoch` days after
+    /// 1970-01-01.
+    const fn midnight(days_since_epoch: u64) -> u64 {
+        days_since_epoch * NANOS_PER_DAY
+    }
+
+    #[test]
+    fn month_keys_follow_the_calendar() {
+        assert_eq!(month_key(0), "1970-01");
+        // 2025-03-15
+        assert_eq!(month_key(midnight(20_162)), "2025-03");
+        // 2023-12-31 23:59:59.999999999, then 2024-01-01
+        assert_eq!(month_key(midnight(19_723) - 1), "2023-12");
+        assert_eq!(month_key(midnight(19_723)), "2024-01");
+    }
+
+    #[test]
+    fn leap_days_stay_in_february() {
+        // 2024-02-29 and 2024-03-01
+        assert_eq!(month_key(midnight(19_782)), "2024-02");
+        assert_eq!(month_key(midnight(19_783)), "2024-03");
+        // 2000 is a leap year: 2000-02-29, then 2000-03-01.
+        assert_eq!(month_key(midnight(11_016)), "2000-02");
+        assert_eq!(month_key(midnight(11_017)), "2000-03");
+        // 2100 is not: 2100-02-28 is followed by 2100-03-01.
+        assert_eq!(month_key(midnight(47_540)), "2100-02");
+        assert_eq!(month_key(midnight(47_541)), "2100-03");
+    }
+
+    #[test]
+    fn contributions_are_summarized_by_month() {
+        let mut contract = setup_contract();
+        advance_time(midnight(20_148)); // 2025-03-01
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 60);
+        advance_time(midnight(31)); // 2025-04-01
+        contribute(&mut contract, &food_bank(), 80);
+
+        let march = contract.get_monthly_summary(food_bank(), "2025-03".to_string()).unwrap();
+        assert_eq!(march.contributions, 2);
+        assert_eq!(march.total_reward.0, 8);
+        assert_eq!(march.avg_score, 80);
+
+        let months = contract.get_monthly_summaries(food_bank(), 0, 10);
+        assert_eq!(
+            months.iter().map(|(month, _)| month.as_str()).collect::<Vec<_>>(),
+            vec!["2025-03", "2025-04"]
+        );
+        assert_eq!(months[1].1.total_reward.0, 4);
+        assert_eq!(contract.get_monthly_summaries(food_bank(), 1, 10).len(), 1);
+        assert!(contract.get_monthly_summary(other_food_bank(), "2025-03".to_string()).is_none());
+    }
+
+    #[test]
+    fn voiding_a_contribution_decrements_its_month() {
+        let mut contract = setup_contract();
+        advance_time(midnight(20_148));
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 60);
+        advance_time(midnight(31));
+        contract.void_contribution(food_bank(), 0);
+
+        let march = contract.get_monthly_summary(food_bank(), "2025-03".to_string()).unwrap();
+        assert_eq!(march.contributions, 1);
+        assert_eq!(march.total_reward.0, 3);
+        assert_eq!(march.avg_score, 60);
+    }
+}
@@ -0,0 +1,264 @@
+//! Contributions scored on any set of named metrics from an owner-managed
+//! registry, so new metrics don't need new struct fields. Each definition
+//! gives the metric's maximum and its weight in the score: every value is
+//! rescaled to 0–100 and the score is their weighted average.
+//!
+//! `record_contribution` keeps taking the fixed three metrics, which count
+//! as `data_quality`, `model_improvement` and `participation_frequency`
+//! weighted equally. Named contributions store their names, values and
+//! score inline, so editing or removing a definition never changes them.
+
+use std::collections::BTreeMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::contributions::{ContributionMetricsV4, MAX_EVIDENCE_CID_LENGTH};
+use crate::metric_rules::normalize;
+use crate::roles::Role;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_METRIC_NAME_LENGTH: usize = 32;
+const MAX_METRICS_PER_CONTRIBUTION: usize = 16;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MetricDefinition {
+    pub max: u8,
+    pub weight: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NamedContribution {
+    /// Each a registered metric, at most once.
+    pub metrics: Vec<(String, u8)>,
+    pub timestamp: u64,
+    /// IPFS CID of the evaluation the scores came from.
+    pub evidence_cid: Option<String>,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Same as `record_contribution`, with the score computed from `metrics`
+    /// and the current definitions.
+    #[payable]
+    pub fn record_named_contribution(
+        &mut self,
+        food_bank: AccountId,
+        contribution: NamedContribution,
+        campaign_id: Option<u64>,
+        force: Option<bool>,
+        external_id: Option<String>,
+        category: Option<String>,
+    ) {
+        self.assert_role(Role::Recorder);
+        let initial_storage = env::storage_usage();
+        if let Some(cid) = &contribution.evidence_cid {
+            assert!(
+                cid.len() <= MAX_EVIDENCE_CID_LENGTH,
+                "evidence_cid exceeds {} bytes",
+                MAX_EVIDENCE_CID_LENGTH
+            );
+        }
+        let score = self.named_score(&contribution.metrics);
+        let record = ContributionMetricsV4 {
+            metrics: contribution.metrics,
+            score,
+            timestamp: contribution.timestamp,
+            evidence_cid: contribution.evidence_cid,
+        };
+        let badge_deposit = self.internal_record_contribution(
+            food_bank,
+            record.into(),
+            campaign_id,
+            force,
+            external_id,
+            category,
+        );
+        self.charge_storage(initial_storage, badge_deposit);
+    }
+
+    /// Adds a metric or replaces its definition. Only later contributions
+    /// are scored with the change.
+    pub fn set_metric_definition(&mut self, name: String, definition: MetricDefinition) {
+        self.assert_owner_action();
+        assert!(
+            !name.is_empty() && name.len() <= MAX_METRIC_NAME_LENGTH,
+            "Metric name must be 1 to {} bytes",
+            MAX_METRIC_NAME_LENGTH
+        );
+        assert!(definition.max > 0, "Metric max must be positive");
+        assert!(definition.weight > 0, "Metric weight must be positive");
+        self.metric_definitions.insert(&name, &definition);
+    }
+
+    /// Stops the metric being accepted; contributions that used it keep it.
+    pub fn remove_metric_definition(&mut self, name: String) {
+        self.assert_owner_action();
+        self.metric_definitions
+            .remove(&name)
+            .expect("Metric definition not found");
+    }
+
+    // View functions
+    pub fn get_metric_definitions(&self) -> Vec<(String, MetricDefinition)> {
+        self.metric_definitions.iter().collect()
+    }
+
+    /// Every contribution's metrics by name, including those recorded with
+    /// the fixed three.
+    pub fn get_named_contributions(&self, account_id: AccountId) -> Vec<BTreeMap<String, u8>> {
+        self.stored_contributions(&account_id)
+            .into_iter()
+            .map(|contribution| contribution.into_named().into_iter().collect())
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    /// Weighted average of the rescaled values, rounded down like the
+    /// fixed-metric score.
+    fn named_score(&self, metrics: &[(String, u8)]) -> u8 {
+        assert!(!metrics.is_empty(), "At least one metric is required");
+        assert!(
+            metrics.len() <= MAX_METRICS_PER_CONTRIBUTION,
+            "At most {} metrics per contribution",
+            MAX_METRICS_PER_CONTRIBUTION
+        );
+        let mut weighted = 0u64;
+        let mut total_weight = 0u64;
+        for (index, (name, value)) in metrics.iter().enumerate() {
+            assert!(
+                metrics[..index].iter().all(|(other, _)| other != name),
+                "Metric {} is given twice",
+                name
+            );
+            let definition = self
+                .metric_definitions
+                .get(name)
+                .unwrap_or_else(|| panic!("Unknown metric {}", name));
+            assert!(
+                *value <= definition.max,
+                "{} must be between 0 and {}",
+                name,
+                definition.max
+            );
+            weighted += u64::from(normalize(*value, definition.max)) * u64::from(definition.weight);
+            total_weight += u64::from(definition.weight);
+        }
+        (weighted / total_weight) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn define(contract: &mut FoodBankToken, name: &str, max: u8, weight: u16) {
+        contract.set_metric_definition(name.to_string(), MetricDefinition { max, weight });
+    }
+
+    fn named(metrics: &[(&str, u8)]) -> NamedContribution {
+        NamedContribution {
+            metrics: metrics.iter().map(|(name, value)| (name.to_string(), *value)).collect(),
+            timestamp: 0,
+            evidence_cid: None,
+        }
+    }
+
+    fn record_named(contract: &mut FoodBankToken, metrics: &[(&str, u8)]) {
+        set_caller(owner(), ONE_NEAR);
+        contract.record_named_contribution(food_bank(), named(metrics), None, None, None, None);
+    }
+
+    #[test]
+    fn score_is_the_weighted_average_of_present_metrics() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        define(&mut contract, "freshness_score", 100, 3);
+        define(&mut contract, "coverage", 10, 1);
+        // (90 * 3 + 50 * 1) / 4
+        record_named(&mut contract, &[("freshness_score", 90), ("coverage", 5)]);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().score, 80);
+        assert_eq!(contract.get_balance(food_bank()).0, 80);
+
+        record_named(&mut contract, &[("coverage", 10)]);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 1).unwrap().score, 100);
+    }
+
+    #[test]
+    fn views_return_metrics_by_name() {
+        let mut contract = setup_contract();
+        define(&mut contract, "coverage", 10, 1);
+        contribute(&mut contract, &food_bank(), 60);
+        record_named(&mut contract, &[("coverage", 7)]);
+
+        let history = contract.get_named_contributions(food_bank());
+        assert_eq!(history[0].get("data_quality"), Some(&60));
+        assert_eq!(history[0].len(), 3);
+        assert_eq!(history[1].get("coverage"), Some(&7));
+        assert_eq!(history[1].len(), 1);
+        // The fixed-metric view fills in what the named contribution lacks.
+        assert_eq!(contract.get_contributions(food_bank())[1].data_quality, 0);
+    }
+
+    #[test]
+    fn removing_a_definition_keeps_historic_records() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 5_000);
+        define(&mut contract, "coverage", 10, 1);
+        record_named(&mut contract, &[("coverage", 5)]);
+        contract.remove_metric_definition("coverage".to_string());
+
+        assert_eq!(contract.get_named_contributions(food_bank())[0].get("coverage"), Some(&5));
+        assert!(contract.get_metric_definitions().is_empty());
+        // Recalculating uses the stored score, not the missing definition.
+        set_reward_rate_bps(&mut contract, 10_000);
+        assert_eq!(contract.recalculate_rewards(food_bank(), 0, 10, false), 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown metric coverage")]
+    fn unregistered_metrics_are_rejected() {
+        let mut contract = setup_contract();
+        record_named(&mut contract, &[("coverage", 5)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "coverage must be between 0 and 10")]
+    fn values_above_the_definition_max_are_rejected() {
+        let mut contract = setup_contract();
+        define(&mut contract, "coverage", 10, 1);
+        record_named(&mut contract, &[("coverage", 11)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Metric coverage is given twice")]
+    fn duplicate_metrics_are_rejected() {
+        let mut contract = setup_contract();
+        define(&mut contract, "coverage", 10, 1);
+        record_named(&mut contract, &[("coverage", 1), ("coverage", 2)]);
+    }
+
+    #[test]
+    fn definitions_can_be_replaced() {
+        let mut contract = setup_contract();
+        define(&mut contract, "coverage", 10, 1);
+        define(&mut contract, "coverage", 20, 2);
+        assert_eq!(
+            contract.get_metric_definitions(),
+            vec![("coverage".to_string(), MetricDefinition { max: 20, weight: 2 })]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Metric definition not found")]
+    fn removing_an_unknown_definition_fails() {
+        let mut contract = setup_contract();
+        contract.remove_metric_definition("coverage".to_string());
+    }
+}
@@ -0,0 +1,155 @@
+//! Welcome bonus minted alongside an account's first recorded contribution.
+//! "First" is read from the account's aggregates rather than its history,
+//! so migrating or archiving the history can't pay the bonus again.
+//! Voiding the contribution that earned it claws the bonus back with the
+//! reward.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, OnboardingBonusMinted};
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// Set on the bonus event so indexers can tell it from contribution rewards.
+pub const ONBOARDING_BONUS_MEMO: &str = "onboarding_bonus";
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OnboardingBonus {
+    /// Index of the contribution that earned it.
+    pub contribution_id: u64,
+    pub amount: U128,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// 0 turns the bonus off. Accounts that already contributed never get it.
+    pub fn set_onboarding_bonus(&mut self, amount: U128) {
+        self.assert_owner_action();
+        self.onboarding_bonus = amount.0;
+    }
+
+    // View functions
+    pub fn get_onboarding_bonus(&self) -> U128 {
+        U128(self.onboarding_bonus)
+    }
+
+    pub fn get_onboarding_bonus_paid(&self, account_id: AccountId) -> Option<OnboardingBonus> {
+        self.onboarding_bonuses.get(&account_id)
+    }
+}
+
+impl FoodBankToken {
+    /// Mints the bonus, at most `limit`, if the contribution at
+    /// `contribution_id`, already counted in the account's aggregates, is
+    /// its first.
+    pub(crate) fn maybe_pay_onboarding_bonus(&mut self, account_id: &AccountId, contribution_id: u64, limit: Balance) {
+        let first = self
+            .account_stats
+            .get(account_id)
+            .is_some_and(|stats| stats.contribution_count == 1);
+        let amount = self.onboarding_bonus.min(limit);
+        if !first || amount == 0 || self.onboarding_bonuses.get(account_id).is_some() {
+            return;
+        }
+        let bonus = OnboardingBonus {
+            contribution_id,
+            amount: U128(amount),
+        };
+        self.onboarding_bonuses.insert(account_id, &bonus);
+        if self.minting_paused {
+            self.withhold_reward(account_id, contribution_id, |withheld| {
+                withheld.onboarding_bonus = bonus.amount
+            });
+        } else {
+            self.mint_onboarding_bonus(account_id, contribution_id, bonus.amount);
+        }
+    }
+
+    pub(crate) fn mint_onboarding_bonus(&mut self, account_id: &AccountId, contribution_id: u64, amount: U128) {
+        self.mint(account_id.clone(), amount.0);
+        self.tag_statement_flow(account_id, StatementFlow::Reward, amount.0);
+        OnboardingBonusMinted {
+            account_id,
+            contribution_id,
+            amount,
+            memo: ONBOARDING_BONUS_MEMO,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    /// The bonus paid with the contribution at `contribution_id`, if any.
+    pub(crate) fn onboarding_bonus_for(&self, account_id: &AccountId, contribution_id: u64) -> Balance {
+        self.onboarding_bonuses
+            .get(account_id)
+            .filter(|bonus| bonus.contribution_id == contribution_id)
+            .map_or(0, |bonus| bonus.amount.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn with_bonus(amount: u128) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_onboarding_bonus(U128(amount));
+        contract
+    }
+
+    #[test]
+    fn first_contribution_earns_the_bonus_once() {
+        let mut contract = with_bonus(50);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 55);
+        assert_eq!(
+            contract.get_onboarding_bonus_paid(food_bank()),
+            Some(OnboardingBonus {
+                contribution_id: 0,
+                amount: U128(50),
+            })
+        );
+        assert_eq!(contract.get_program_stats().total_rewards_minted.0, 55);
+
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 60);
+    }
+
+    #[test]
+    fn a_zero_bonus_pays_nothing() {
+        let mut contract = with_bonus(0);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+        assert!(contract.get_onboarding_bonus_paid(food_bank()).is_none());
+
+        // Turning it on later doesn't reach accounts that already started.
+        contract.set_onboarding_bonus(U128(50));
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 10);
+    }
+
+    #[test]
+    fn voiding_the_first_contribution_claws_back_the_bonus() {
+        let mut contract = with_bonus(50);
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.void_contribution(food_bank(), 0).0, 55);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+
+        // Later voids only take their own reward.
+        assert_eq!(contract.void_contribution(food_bank(), 1).0, 5);
+    }
+
+    #[test]
+    fn archiving_does_not_pay_the_bonus_again() {
+        let mut contract = with_bonus(50);
+        contribute(&mut contract, &food_bank(), 100);
+        contract.archive_contributions(food_bank(), 0);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 60);
+    }
+}
@@ -0,0 +1,391 @@
+//! Versioned storage for redemption options. Each stored option carries its
+//! schema version, so new fields only need a new variant instead of
+//! breaking the options already stored.
+//!
+//! Options written before versioning live under the original `r` prefix as
+//! plain `RedemptionOption`s. Every read goes through the accessors below,
+//! which upgrade to the latest layout on the way out; an option is written
+//! back in its latest form whenever it is touched, and can be moved in bulk
+//! with `migrate_redemption_options`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::translations::LocalizedText;
+use crate::{FoodBankToken, FoodBankTokenExt, RedemptionOption, RedemptionOptionV5};
+
+/// The layout written between versioning and translations.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct RedemptionOptionV2 {
+    pub(crate) name: String,
+    pub(crate) cost: U128,
+    pub(crate) available: bool,
+    pub(crate) description: String,
+    pub(crate) benefit_method: String,
+    pub(crate) benefit_args_template: Option<String>,
+    pub(crate) benefit_contract: Option<AccountId>,
+    pub(crate) min_contributions: Option<u64>,
+    pub(crate) prerequisite_option: Option<String>,
+    pub(crate) near_copay: Option<U128>,
+}
+
+/// The layout written between translations and entitlements.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct RedemptionOptionV3 {
+    pub(crate) name: String,
+    pub(crate) cost: U128,
+    pub(crate) available: bool,
+    pub(crate) description: String,
+    pub(crate) benefit_method: String,
+    pub(crate) benefit_args_template: Option<String>,
+    pub(crate) benefit_contract: Option<AccountId>,
+    pub(crate) min_contributions: Option<u64>,
+    pub(crate) prerequisite_option: Option<String>,
+    pub(crate) near_copay: Option<U128>,
+    pub(crate) translations: Vec<(String, LocalizedText)>,
+}
+
+/// The layout written between entitlements and cooldowns.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct RedemptionOptionV4 {
+    pub(crate) name: String,
+    pub(crate) cost: U128,
+    pub(crate) available: bool,
+    pub(crate) description: String,
+    pub(crate) benefit_method: String,
+    pub(crate) benefit_args_template: Option<String>,
+    pub(crate) benefit_contract: Option<AccountId>,
+    pub(crate) min_contributions: Option<u64>,
+    pub(crate) prerequisite_option: Option<String>,
+    pub(crate) near_copay: Option<U128>,
+    pub(crate) translations: Vec<(String, LocalizedText)>,
+    pub(crate) grants_entitlement: Option<(String, u64)>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub enum VersionedRedemptionOption {
+    V1(RedemptionOption),
+    V2(RedemptionOptionV2),
+    V3(RedemptionOptionV3),
+    V4(RedemptionOptionV4),
+    V5(RedemptionOptionV5),
+}
+
+impl From<RedemptionOption> for RedemptionOptionV2 {
+    fn from(option: RedemptionOption) -> Self {
+        Self {
+            name: option.name,
+            cost: option.cost,
+            available: option.available,
+            description: option.description,
+            benefit_method: option.benefit_method,
+            benefit_args_template: option.benefit_args_template,
+            benefit_contract: option.benefit_contract,
+            min_contributions: None,
+            prerequisite_option: None,
+            near_copay: None,
+        }
+    }
+}
+
+impl From<RedemptionOptionV2> for RedemptionOptionV3 {
+    fn from(option: RedemptionOptionV2) -> Self {
+        Self {
+            name: option.name,
+            cost: option.cost,
+            available: option.available,
+            description: option.description,
+            benefit_method: option.benefit_method,
+            benefit_args_template: option.benefit_args_template,
+            benefit_contract: option.benefit_contract,
+            min_contributions: option.min_contributions,
+            prerequisite_option: option.prerequisite_option,
+            near_copay: option.near_copay,
+            translations: Vec::new(),
+        }
+    }
+}
+
+impl From<RedemptionOptionV3> for RedemptionOptionV4 {
+    fn from(option: RedemptionOptionV3) -> Self {
+        Self {
+            name: option.name,
+            cost: option.cost,
+            available: option.available,
+            description: option.description,
+            benefit_method: option.benefit_method,
+            benefit_args_template: option.benefit_args_template,
+            benefit_contract: option.benefit_contract,
+            min_contributions: option.min_contributions,
+            prerequisite_option: option.prerequisite_option,
+            near_copay: option.near_copay,
+            translations: option.translations,
+            grants_entitlement: None,
+        }
+    }
+}
+
+impl From<RedemptionOptionV4> for RedemptionOptionV5 {
+    fn from(option: RedemptionOptionV4) -> Self {
+        Self {
+            name: option.name,
+            cost: option.cost,
+            available: option.available,
+            description: option.description,
+            benefit_method: option.benefit_method,
+            benefit_args_template: option.benefit_args_template,
+            benefit_contract: option.benefit_contract,
+            min_contributions: option.min_contributions,
+            prerequisite_option: option.prerequisite_option,
+            near_copay: option.near_copay,
+            translations: option.translations,
+            grants_entitlement: option.grants_entitlement,
+            cooldown_ns: None,
+        }
+    }
+}
+
+impl From<RedemptionOptionV3> for RedemptionOptionV5 {
+    fn from(option: RedemptionOptionV3) -> Self {
+        RedemptionOptionV4::from(option).into()
+    }
+}
+
+impl From<RedemptionOption> for RedemptionOptionV5 {
+    fn from(option: RedemptionOption) -> Self {
+        RedemptionOptionV3::from(RedemptionOptionV2::from(option)).into()
+    }
+}
+
+impl VersionedRedemptionOption {
+    pub fn into_latest(self) -> RedemptionOptionV5 {
+        match self {
+            Self::V1(option) => option.into(),
+            Self::V2(option) => RedemptionOptionV3::from(option).into(),
+            Self::V3(option) => option.into(),
+            Self::V4(option) => option.into(),
+            Self::V5(option) => option,
+        }
+    }
+
+    fn is_latest(&self) -> bool {
+        matches!(self, Self::V5(_))
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Moves up to `limit` legacy options into versioned storage and returns
+    /// how many are left.
+    pub fn migrate_redemption_options(&mut self, limit: u64) -> u64 {
+        self.assert_owner_action();
+        let legacy: Vec<(String, RedemptionOption)> = self
+            .legacy_redemption_options
+            .iter()
+            .take(limit as usize)
+            .collect();
+        for (option_id, option) in legacy {
+            self.redemption_options
+                .insert(&option_id, &VersionedRedemptionOption::V1(option));
+            self.legacy_redemption_options.remove(&option_id);
+        }
+        self.legacy_redemption_options.len()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn redemption_option(&self, option_id: &String) -> Option<RedemptionOptionV5> {
+        match self.redemption_options.get(option_id) {
+            Some(option) => Some(option.into_latest()),
+            None => self.legacy_redemption_options.get(option_id).map(Into::into),
+        }
+    }
+
+    pub(crate) fn has_redemption_option(&self, option_id: &String) -> bool {
+        self.redemption_option(option_id).is_some()
+    }
+
+    /// Legacy options first, then versioned ones in insertion order.
+    pub(crate) fn iter_redemption_options(
+        &self,
+    ) -> impl Iterator<Item = (String, RedemptionOptionV5)> + '_ {
+        self.legacy_redemption_options
+            .iter()
+            .map(|(option_id, option)| (option_id, option.into()))
+            .chain(
+                self.redemption_options
+                    .iter()
+                    .map(|(option_id, option)| (option_id, option.into_latest())),
+            )
+    }
+
+    /// Reads an option, writing it back in its latest layout if it isn't
+    /// stored that way yet.
+    pub(crate) fn touch_redemption_option(&mut self, option_id: &String) -> Option<RedemptionOptionV5> {
+        if let Some(option) = self.redemption_options.get(option_id) {
+            if option.is_latest() {
+                return Some(option.into_latest());
+            }
+        }
+        let option = self.redemption_option(option_id)?;
+        self.store_redemption_option(option_id, &option);
+        Some(option)
+    }
+
+    /// Writes `option` in its latest layout, dropping any legacy copy.
+    pub(crate) fn store_redemption_option(&mut self, option_id: &String, option: &RedemptionOptionV5) {
+        self.redemption_options
+            .insert(option_id, &VersionedRedemptionOption::V5(option.clone()));
+        self.legacy_redemption_options.remove(option_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const OPTION_ID: &str = "tote_bags";
+
+    fn legacy_option() -> RedemptionOption {
+        RedemptionOption {
+            name: "Tote bags".to_string(),
+            cost: U128(40),
+            available: true,
+            description: "Branded tote bags".to_string(),
+            benefit_method: String::new(),
+            benefit_args_template: None,
+            benefit_contract: None,
+        }
+    }
+
+    fn option_id() -> String {
+        OPTION_ID.to_string()
+    }
+
+    fn stored_version(contract: &FoodBankToken) -> Option<bool> {
+        contract.redemption_options.get(&option_id()).map(|option| option.is_latest())
+    }
+
+    /// Runs every public method that reads or writes an option against
+    /// `option_id`, which is stored in some earlier layout.
+    fn exercise(contract: &mut FoodBankToken) {
+        let account_id: AccountId = food_bank();
+        contract.mint(account_id.clone(), 100);
+        assert!(contract.get_redemption_options().iter().any(|(id, _)| id == OPTION_ID));
+        assert_eq!(contract.get_affordable_options(account_id.clone(), 0, 10).len(), 4);
+        assert_eq!(contract.get_options_by_popularity(10).len(), 4);
+        assert_eq!(
+            contract.can_redeem(account_id.clone(), option_id()),
+            crate::redemptions::RedeemCheck::Ok { cost: U128(40) }
+        );
+
+        set_caller(account_id, 1);
+        contract.redeem_tokens(option_id(), U128(40), None);
+        assert_eq!(contract.get_balance(food_bank()).0, 60);
+        assert_eq!(stored_version(contract), Some(true));
+
+        set_caller(owner(), ONE_NEAR);
+        contract.update_redemption_option(option_id(), Some(U128(30)), None, None);
+        contract.set_option_eligibility(option_id(), Some(0), None);
+        contract.set_option_near_copay(option_id(), None);
+        contract.set_redemption_limits(option_id(), Default::default());
+        let option = contract.redemption_option(&option_id()).unwrap();
+        assert_eq!(option.cost.0, 30);
+        assert_eq!(option.min_contributions, Some(0));
+    }
+
+    #[test]
+    fn legacy_options_upgrade_when_touched() {
+        let mut contract = setup_contract();
+        // Bytes as the pre-versioning contract wrote them.
+        let bytes = borsh::to_vec(&legacy_option()).unwrap();
+        let legacy = RedemptionOption::try_from_slice(&bytes).unwrap();
+        contract.legacy_redemption_options.insert(&option_id(), &legacy);
+        assert_eq!(stored_version(&contract), None);
+
+        exercise(&mut contract);
+        assert!(contract.legacy_redemption_options.get(&option_id()).is_none());
+    }
+
+    #[test]
+    fn v1_options_upgrade_in_place() {
+        let mut contract = setup_contract();
+        let bytes = borsh::to_vec(&VersionedRedemptionOption::V1(legacy_option())).unwrap();
+        let stored = VersionedRedemptionOption::try_from_slice(&bytes).unwrap();
+        contract.redemption_options.insert(&option_id(), &stored);
+        assert_eq!(stored_version(&contract), Some(false));
+
+        exercise(&mut contract);
+    }
+
+    #[test]
+    fn v2_options_upgrade_in_place() {
+        let mut contract = setup_contract();
+        let option = RedemptionOptionV2 {
+            min_contributions: Some(0),
+            ..legacy_option().into()
+        };
+        let bytes = borsh::to_vec(&VersionedRedemptionOption::V2(option)).unwrap();
+        let stored = VersionedRedemptionOption::try_from_slice(&bytes).unwrap();
+        contract.redemption_options.insert(&option_id(), &stored);
+        assert_eq!(stored_version(&contract), Some(false));
+        assert_eq!(contract.redemption_option(&option_id()).unwrap().min_contributions, Some(0));
+        assert!(contract.redemption_option(&option_id()).unwrap().translations.is_empty());
+
+        exercise(&mut contract);
+    }
+
+    #[test]
+    fn v3_options_keep_their_translations() {
+        let mut contract = setup_contract();
+        let text = LocalizedText {
+            name: "Bolsas".to_string(),
+            description: "Bolsas de tela".to_string(),
+        };
+        let option = RedemptionOptionV3 {
+            translations: vec![("es".to_string(), text.clone())],
+            ..RedemptionOptionV2::from(legacy_option()).into()
+        };
+        let bytes = borsh::to_vec(&VersionedRedemptionOption::V3(option)).unwrap();
+        let stored = VersionedRedemptionOption::try_from_slice(&bytes).unwrap();
+        contract.redemption_options.insert(&option_id(), &stored);
+        assert_eq!(stored_version(&contract), Some(false));
+        let latest = contract.redemption_option(&option_id()).unwrap();
+        assert_eq!(latest.translations, vec![("es".to_string(), text)]);
+        assert!(latest.grants_entitlement.is_none());
+
+        exercise(&mut contract);
+    }
+
+    #[test]
+    fn v4_options_keep_their_entitlement_without_a_cooldown() {
+        let mut contract = setup_contract();
+        let option = RedemptionOptionV4 {
+            grants_entitlement: Some(("analytics".to_string(), 1_000)),
+            ..RedemptionOptionV3::from(RedemptionOptionV2::from(legacy_option())).into()
+        };
+        let bytes = borsh::to_vec(&VersionedRedemptionOption::V4(option)).unwrap();
+        let stored = VersionedRedemptionOption::try_from_slice(&bytes).unwrap();
+        contract.redemption_options.insert(&option_id(), &stored);
+        assert_eq!(stored_version(&contract), Some(false));
+        let latest = contract.redemption_option(&option_id()).unwrap();
+        assert_eq!(latest.grants_entitlement, Some(("analytics".to_string(), 1_000)));
+        assert_eq!(latest.cooldown_ns, None);
+
+        exercise(&mut contract);
+    }
+
+    #[test]
+    fn legacy_options_can_be_migrated_in_bulk() {
+        let mut contract = setup_contract();
+        contract.legacy_redemption_options.insert(&option_id(), &legacy_option());
+        contract.legacy_redemption_options.insert(&"mugs".to_string(), &legacy_option());
+
+        assert_eq!(contract.migrate_redemption_options(1), 1);
+        assert_eq!(contract.migrate_redemption_options(10), 0);
+        assert_eq!(contract.get_redemption_options().len(), 5);
+        assert_eq!(contract.redemption_option(&"mugs".to_string()).unwrap().name, "Tote bags");
+    }
+}
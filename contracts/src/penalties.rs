@@ -0,0 +1,296 @@
+//! Penalties for submissions that cost the program rework: the mirror image
+//! of an instant reward. The deduction is burned from the account's unlocked
+//! balance, and whatever that can't cover becomes a debt that the account's
+//! next contribution rewards pay off before anything is credited.
+//! Redemptions stay blocked until the debt is repaid.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{DebtRepaid, Event, PenaltyRecorded, PenaltyVoided};
+use crate::roles::Role;
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_PENALTY_REASON_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PenaltyStatus {
+    Applied,
+    Voided,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Penalty {
+    pub severity: u8,
+    pub reason: String,
+    /// What the severity called for: the reward a contribution scoring
+    /// `severity` would earn at the base rate.
+    pub amount: U128,
+    /// Part of `amount` burned when the penalty was recorded; the rest was
+    /// added to the account's debt.
+    pub burned: U128,
+    pub recorded_at: u64,
+    pub status: PenaltyStatus,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// `severity` is on the same 0–100 scale as contribution scores. The
+    /// attached deposit pays for the stored record.
+    #[payable]
+    pub fn record_penalty(&mut self, account_id: AccountId, severity: u8, reason: String) -> u64 {
+        let initial_storage = env::storage_usage();
+        self.assert_not_paused();
+        self.assert_role(Role::Recorder);
+        assert!(severity <= 100, "Severity must be between 0 and 100");
+        assert!(
+            reason.len() <= MAX_PENALTY_REASON_LENGTH,
+            "Reason exceeds {} bytes",
+            MAX_PENALTY_REASON_LENGTH
+        );
+
+        let amount = Balance::from(severity) * Balance::from(self.rate_bps_at(env::block_timestamp()))
+            / 10_000;
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        let burned = amount.min(self.unlocked_balance(&account_id, balance));
+        self.internal_set_balance(&account_id, balance - burned);
+        self.tag_statement_flow(&account_id, StatementFlow::Penalty, burned);
+        self.total_supply -= burned;
+        let debt = amount - burned;
+        if debt > 0 {
+            self.debts.insert(&account_id, &(self.debt(&account_id) + debt));
+        }
+
+        let mut penalties = self.penalties.get(&account_id).unwrap_or_default();
+        let index = penalties.len() as u64;
+        penalties.push(Penalty {
+            severity,
+            reason,
+            amount: U128(amount),
+            burned: U128(burned),
+            recorded_at: env::block_timestamp(),
+            status: PenaltyStatus::Applied,
+        });
+        self.penalties.insert(&account_id, &penalties);
+        PenaltyRecorded {
+            account_id: &account_id,
+            index,
+            severity,
+            reason: &penalties[index as usize].reason,
+            burned: U128(burned),
+            debt: U128(debt),
+        }
+        .emit();
+
+        self.charge_storage(initial_storage, 0);
+        index
+    }
+
+    /// Refunds what the penalty has collected so far and forgives the part
+    /// of its debt that is still outstanding.
+    pub fn void_penalty(&mut self, account_id: AccountId, index: u64) -> U128 {
+        self.assert_owner_action();
+        let mut penalties = self.penalties.get(&account_id).unwrap_or_default();
+        let penalty = penalties.get_mut(index as usize).expect("Penalty not found");
+        assert_eq!(penalty.status, PenaltyStatus::Applied, "Penalty is already voided");
+        penalty.status = PenaltyStatus::Voided;
+
+        let debt = self.debt(&account_id);
+        let forgiven = (penalty.amount.0 - penalty.burned.0).min(debt);
+        let refunded = penalty.amount.0 - forgiven;
+        self.penalties.insert(&account_id, &penalties);
+        self.set_debt(&account_id, debt - forgiven);
+        if refunded > 0 {
+            self.mint(account_id.clone(), refunded);
+            self.tag_statement_flow(&account_id, StatementFlow::Refund, refunded);
+        }
+        PenaltyVoided {
+            account_id: &account_id,
+            index,
+            refunded: U128(refunded),
+            debt_forgiven: U128(forgiven),
+        }
+        .emit();
+        U128(refunded)
+    }
+
+    /// Pays the caller's debt from their unlocked balance, as far as it
+    /// covers it.
+    pub fn settle_debt(&mut self) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        let repaid = self.debt(&account_id).min(self.unlocked_balance(&account_id, balance));
+        self.internal_set_balance(&account_id, balance - repaid);
+        self.tag_statement_flow(&account_id, StatementFlow::Penalty, repaid);
+        self.total_supply -= repaid;
+        self.repay_debt(&account_id, repaid);
+        U128(self.debt(&account_id))
+    }
+
+    // View functions
+    pub fn get_debt(&self, account_id: AccountId) -> U128 {
+        U128(self.debt(&account_id))
+    }
+
+    pub fn get_penalties(&self, account_id: AccountId) -> Vec<Penalty> {
+        self.penalties.get(&account_id).unwrap_or_default()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn debt(&self, account_id: &AccountId) -> Balance {
+        self.debts.get(account_id).unwrap_or(0)
+    }
+
+    /// Puts up to all of `reward` towards the account's debt and returns
+    /// what is left to credit.
+    pub(crate) fn pay_debt_from_reward(&mut self, account_id: &AccountId, reward: Balance) -> Balance {
+        let repaid = self.debt(account_id).min(reward);
+        self.repay_debt(account_id, repaid);
+        reward - repaid
+    }
+
+    fn repay_debt(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        let remaining = self.debt(account_id) - amount;
+        self.set_debt(account_id, remaining);
+        DebtRepaid {
+            account_id,
+            amount: U128(amount),
+            remaining: U128(remaining),
+        }
+        .emit();
+    }
+
+    fn set_debt(&mut self, account_id: &AccountId, debt: Balance) {
+        if debt == 0 {
+            self.debts.remove(account_id);
+        } else {
+            self.debts.insert(account_id, &debt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn penalize(contract: &mut FoodBankToken, severity: u8) -> u64 {
+        set_caller(owner(), ONE_NEAR);
+        contract.record_penalty(food_bank(), severity, "Corrupted upload".to_string())
+    }
+
+    #[test]
+    fn penalties_burn_from_the_balance() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.mint(food_bank(), 50);
+        penalize(&mut contract, 30);
+
+        assert_eq!(contract.get_balance(food_bank()).0, 20);
+        assert_eq!(contract.get_debt(food_bank()).0, 0);
+        let penalty = &contract.get_penalties(food_bank())[0];
+        assert_eq!(penalty.amount.0, 30);
+        assert_eq!(penalty.status, PenaltyStatus::Applied);
+    }
+
+    #[test]
+    fn locked_rewards_are_not_burned() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_reward_lock_duration(crate::locks::DEFAULT_REWARD_LOCK_DURATION);
+        contract.mint(food_bank(), 20);
+        contribute(&mut contract, &food_bank(), 60);
+        penalize(&mut contract, 50);
+
+        assert_eq!(contract.get_balance(food_bank()).0, 60);
+        assert_eq!(contract.get_locked_balance(food_bank()).0, 60);
+        assert_eq!(contract.get_penalties(food_bank())[0].burned.0, 20);
+        assert_eq!(contract.get_debt(food_bank()).0, 30);
+        set_caller(food_bank(), 0);
+        assert_eq!(contract.settle_debt().0, 30);
+        assert_eq!(contract.get_balance(food_bank()).0, 60);
+    }
+
+    #[test]
+    fn shortfall_becomes_debt_repaid_by_later_rewards() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.mint(food_bank(), 20);
+        penalize(&mut contract, 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_debt(food_bank()).0, 80);
+        assert_eq!(contract.get_penalties(food_bank())[0].burned.0, 20);
+
+        contribute(&mut contract, &food_bank(), 60);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_debt(food_bank()).0, 20);
+
+        contribute(&mut contract, &food_bank(), 50);
+        assert_eq!(contract.get_balance(food_bank()).0, 30);
+        assert_eq!(contract.get_debt(food_bank()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Outstanding debt of 80 must be repaid before redeeming")]
+    fn debt_blocks_redemptions() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.mint(food_bank(), 20);
+        penalize(&mut contract, 100);
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    fn debt_can_be_settled_from_the_balance() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        penalize(&mut contract, 40);
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 0);
+        assert_eq!(contract.settle_debt().0, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 60);
+    }
+
+    #[test]
+    fn voiding_refunds_what_was_collected_and_forgives_the_rest() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.mint(food_bank(), 20);
+        let index = penalize(&mut contract, 100);
+        // 60 of the 80 debt is repaid from this reward.
+        contribute(&mut contract, &food_bank(), 60);
+
+        assert_eq!(contract.void_penalty(food_bank(), index).0, 80);
+        assert_eq!(contract.get_debt(food_bank()).0, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 80);
+        assert_eq!(contract.get_penalties(food_bank())[0].status, PenaltyStatus::Voided);
+    }
+
+    #[test]
+    #[should_panic(expected = "Penalty is already voided")]
+    fn penalties_are_voided_once() {
+        let mut contract = setup_contract();
+        let index = penalize(&mut contract, 10);
+        contract.void_penalty(food_bank(), index);
+        contract.void_penalty(food_bank(), index);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Recorder role")]
+    fn penalties_need_the_recorder_role() {
+        let mut contract = setup_contract();
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.record_penalty(food_bank(), 10, "Corrupted upload".to_string());
+    }
+}
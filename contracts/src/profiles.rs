@@ -0,0 +1,256 @@
+//! Self-registered participant profiles, so reports and events can show a
+//! name instead of a raw account ID.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance, Promise};
+
+use crate::account_types::AccountType;
+use crate::events::{Event, ProfileCleared, ProfileUpdated, ProfileVerified};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const MAX_PROFILE_NAME_LENGTH: usize = 64;
+pub const MAX_REGION_LENGTH: usize = 64;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OrgType {
+    FoodBank,
+    Pantry,
+    Shelter,
+    Supplier,
+    Other,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Profile {
+    pub name: String,
+    pub region: String,
+    pub org_type: OrgType,
+    /// Set by the owner; cleared whenever the profile changes.
+    pub verified: bool,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Creates or replaces the caller's profile. The attached deposit pays
+    /// for any storage added; the excess is refunded. `account_type` is
+    /// fixed at the first registration and defaults to
+    /// `DEFAULT_ACCOUNT_TYPE`, a food bank.
+    #[payable]
+    pub fn register_profile(
+        &mut self,
+        name: String,
+        region: String,
+        org_type: OrgType,
+        account_type: Option<AccountType>,
+    ) {
+        let initial_storage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        self.register_account_type(&account_id, account_type);
+        self.internal_set_profile(
+            &account_id,
+            Profile {
+                name,
+                region,
+                org_type,
+                verified: false,
+            },
+        );
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Changes the given fields of the caller's profile.
+    #[payable]
+    pub fn update_profile(
+        &mut self,
+        name: Option<String>,
+        region: Option<String>,
+        org_type: Option<OrgType>,
+    ) {
+        let initial_storage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        let mut profile = self.profiles.get(&account_id).expect("Profile not found");
+        if let Some(name) = name {
+            profile.name = name;
+        }
+        if let Some(region) = region {
+            profile.region = region;
+        }
+        if let Some(org_type) = org_type {
+            profile.org_type = org_type;
+        }
+        profile.verified = false;
+        self.internal_set_profile(&account_id, profile);
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Removes the caller's profile and refunds the storage it used. Not
+    /// while a redemption is in flight: it is attributed to the profile's
+    /// region once delivered.
+    pub fn clear_profile(&mut self) {
+        let initial_storage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        self.assert_no_redemptions_in_flight(&account_id);
+        let profile = self.profiles.remove(&account_id).expect("Profile not found");
+        self.move_region_participant(Some(&profile.region), None);
+        ProfileCleared { account_id: &account_id }.emit();
+        let freed = initial_storage.saturating_sub(env::storage_usage());
+        let refund = Balance::from(freed) * env::storage_byte_cost();
+        if refund > 0 {
+            Promise::new(account_id).transfer(refund);
+        }
+    }
+
+    pub fn verify_profile(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        let mut profile = self.profiles.get(&account_id).expect("Profile not found");
+        profile.verified = true;
+        self.profiles.insert(&account_id, &profile);
+        ProfileVerified { account_id: &account_id }.emit();
+    }
+
+    // View functions
+    pub fn get_profile(&self, account_id: AccountId) -> Option<Profile> {
+        self.profiles.get(&account_id)
+    }
+
+    pub fn get_profiles(&self, from_index: u64, limit: u64) -> Vec<(AccountId, Profile)> {
+        self.profiles
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    fn internal_set_profile(&mut self, account_id: &AccountId, profile: Profile) {
+        assert!(!profile.name.is_empty(), "Profile name must not be empty");
+        assert!(
+            profile.name.len() <= MAX_PROFILE_NAME_LENGTH,
+            "Profile name exceeds {} bytes",
+            MAX_PROFILE_NAME_LENGTH
+        );
+        assert!(
+            profile.region.len() <= MAX_REGION_LENGTH,
+            "Region exceeds {} bytes",
+            MAX_REGION_LENGTH
+        );
+        ProfileUpdated {
+            account_id,
+            name: &profile.name,
+            region: &profile.region,
+            org_type: profile.org_type,
+            account_type: self.account_type(account_id),
+        }
+        .emit();
+        let previous = self.profiles.insert(account_id, &profile);
+        self.move_region_participant(
+            previous.as_ref().map(|previous| &previous.region),
+            Some(&profile.region),
+        );
+    }
+
+    /// Name to show for `account_id` in events, if it registered one.
+    pub(crate) fn display_name(&self, account_id: &AccountId) -> Option<String> {
+        self.profiles.get(account_id).map(|profile| profile.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn register(contract: &mut FoodBankToken, name: &str) {
+        set_caller(food_bank(), ONE_NEAR);
+        contract.register_profile(name.to_string(), "Lagos".to_string(), OrgType::FoodBank, None);
+    }
+
+    #[test]
+    fn register_and_verify() {
+        let mut contract = setup_contract();
+        register(&mut contract, "Lagos Food Bank");
+        let profile = contract.get_profile(food_bank()).unwrap();
+        assert_eq!(profile.name, "Lagos Food Bank");
+        assert_eq!(profile.org_type, OrgType::FoodBank);
+        assert!(!profile.verified);
+
+        set_caller(owner(), 0);
+        contract.verify_profile(food_bank());
+        assert!(contract.get_profile(food_bank()).unwrap().verified);
+    }
+
+    #[test]
+    fn re_registering_updates_in_place() {
+        let mut contract = setup_contract();
+        register(&mut contract, "Lagos Food Bank");
+        set_caller(owner(), 0);
+        contract.verify_profile(food_bank());
+        register(&mut contract, "Lagos Food Bank Network");
+
+        let profiles = contract.get_profiles(0, 10);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].1.name, "Lagos Food Bank Network");
+        assert!(!profiles[0].1.verified);
+    }
+
+    #[test]
+    fn update_changes_only_the_given_fields() {
+        let mut contract = setup_contract();
+        register(&mut contract, "Lagos Food Bank");
+        contract.update_profile(None, Some("Abuja".to_string()), Some(OrgType::Pantry));
+        let profile = contract.get_profile(food_bank()).unwrap();
+        assert_eq!(profile.name, "Lagos Food Bank");
+        assert_eq!(profile.region, "Abuja");
+        assert_eq!(profile.org_type, OrgType::Pantry);
+    }
+
+    #[test]
+    fn clear_removes_the_profile() {
+        let mut contract = setup_contract();
+        register(&mut contract, "Lagos Food Bank");
+        set_caller(food_bank(), 0);
+        contract.clear_profile();
+        assert!(contract.get_profile(food_bank()).is_none());
+        assert!(contract.get_profiles(0, 10).is_empty());
+    }
+
+    #[test]
+    fn contribution_events_carry_the_display_name() {
+        let mut contract = setup_contract();
+        register(&mut contract, "Lagos Food Bank");
+        contribute(&mut contract, &food_bank(), 100);
+        let logs = near_sdk::test_utils::get_logs();
+        let recorded = logs
+            .iter()
+            .find(|log| log.contains("contribution_recorded"))
+            .unwrap();
+        assert!(recorded.contains(r#""display_name":"Lagos Food Bank""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "yoctoNEAR to cover storage")]
+    fn registration_requires_a_storage_deposit() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.register_profile("Lagos Food Bank".to_string(), "Lagos".to_string(), OrgType::FoodBank, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Profile name exceeds 64 bytes")]
+    fn long_names_are_rejected() {
+        let mut contract = setup_contract();
+        register(&mut contract, &"x".repeat(65));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn verification_is_owner_only() {
+        let mut contract = setup_contract();
+        register(&mut contract, "Lagos Food Bank");
+        contract.verify_profile(food_bank());
+    }
+}
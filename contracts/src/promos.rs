@@ -0,0 +1,250 @@
+//! Discount codes for a redemption option. Only the sha256 of a code is
+//! stored, so codes handed out off-chain never appear in state; the
+//! plaintext only shows up in the redeeming transaction. Each account can
+//! use a given code once.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Promo {
+    pub option_id: String,
+    pub discount_bps: u16,
+    pub max_uses: u32,
+    pub remaining_uses: u32,
+    /// Codes are accepted strictly before this timestamp.
+    pub expiry: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// `code_hash` is the sha256 of the code redeemers will submit. The
+    /// attached deposit, or the caller's storage credit, pays for the
+    /// storage.
+    #[payable]
+    pub fn create_promo(
+        &mut self,
+        code_hash: Base64VecU8,
+        option_id: String,
+        discount_bps: u16,
+        max_uses: u32,
+        expiry_ts: u64,
+    ) {
+        self.assert_owner_action();
+        assert_eq!(code_hash.0.len(), 32, "Promo code hash must be 32 bytes");
+        assert!(self.promos.get(&code_hash.0).is_none(), "Promo already exists");
+        assert!(
+            self.has_redemption_option(&option_id),
+            "Redemption option not found"
+        );
+        assert!(
+            discount_bps > 0 && discount_bps <= 10_000,
+            "Discount must be between 1 and 10000 bps"
+        );
+        assert!(max_uses > 0, "A promo needs at least one use");
+        assert!(expiry_ts > env::block_timestamp(), "Expiry must be in the future");
+        let initial_storage = env::storage_usage();
+        self.promos.insert(
+            &code_hash.0,
+            &Promo {
+                option_id,
+                discount_bps,
+                max_uses,
+                remaining_uses: max_uses,
+                expiry: expiry_ts,
+            },
+        );
+        self.charge_storage(initial_storage, 0);
+    }
+
+    // View functions
+    pub fn get_promo_status(&self, code_hash: Base64VecU8) -> Option<Promo> {
+        self.promos.get(&code_hash.0)
+    }
+}
+
+impl FoodBankToken {
+    /// Hash and discount of `code` if `account_id` may use it on
+    /// `option_id` right now.
+    pub(crate) fn valid_promo(
+        &self,
+        account_id: &AccountId,
+        option_id: &str,
+        code: &str,
+    ) -> (Vec<u8>, u16) {
+        let code_hash = env::sha256(code.as_bytes());
+        let promo = self.promos.get(&code_hash).expect("Promo code not found");
+        assert!(
+            promo.option_id == option_id,
+            "Promo code is not valid for {}",
+            option_id
+        );
+        assert!(
+            env::block_timestamp() < promo.expiry,
+            "Promo code expired at {}",
+            promo.expiry
+        );
+        assert!(promo.remaining_uses > 0, "Promo code has no uses left");
+        assert!(
+            !self.promo_uses.contains(&(code_hash.clone(), account_id.clone())),
+            "Promo code was already used by {}",
+            account_id
+        );
+        (code_hash, promo.discount_bps)
+    }
+
+    pub(crate) fn use_promo(&mut self, account_id: &AccountId, code_hash: &[u8]) {
+        let mut promo = self.promos.get(&code_hash.to_vec()).unwrap();
+        promo.remaining_uses -= 1;
+        self.promos.insert(&code_hash.to_vec(), &promo);
+        self.promo_uses.insert(&(code_hash.to_vec(), account_id.clone()));
+    }
+
+    /// Gives the use back after the redemption it paid for was refunded.
+    pub(crate) fn restore_promo(&mut self, account_id: &AccountId, code_hash: &[u8]) {
+        if let Some(mut promo) = self.promos.get(&code_hash.to_vec()) {
+            promo.remaining_uses += 1;
+            self.promos.insert(&code_hash.to_vec(), &promo);
+        }
+        self.promo_uses.remove(&(code_hash.to_vec(), account_id.clone()));
+    }
+}
+
+/// `cost` less `discount_bps`, rounded in the redeemer's favour.
+pub(crate) fn discounted_cost(cost: Balance, discount_bps: u16) -> Balance {
+    cost * Balance::from(10_000 - discount_bps) / 10_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fulfillment::{FulfillmentStatus, RedemptionCharge};
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::PromiseResult;
+
+    const CODE: &str = "SPRING20";
+    const EXPIRY: u64 = 1_000_000_000_000;
+
+    fn hash(code: &str) -> Base64VecU8 {
+        Base64VecU8(env::sha256(code.as_bytes()))
+    }
+
+    fn setup_promo(max_uses: u32) -> FoodBankToken {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.create_promo(hash(CODE), "analytics_access".to_string(), 2_000, max_uses, EXPIRY);
+        contract.mint(food_bank(), 400);
+        contract.mint(other_food_bank(), 400);
+        contract
+    }
+
+    fn redeem(contract: &mut FoodBankToken, account_id: AccountId, amount: u128, code: &str) {
+        set_caller(account_id, 1);
+        contract.redeem_tokens("analytics_access".to_string(), U128(amount), Some(code.to_string()));
+    }
+
+    #[test]
+    fn promo_reduces_the_charged_cost() {
+        let mut contract = setup_promo(50);
+        redeem(&mut contract, food_bank(), 160, CODE);
+        assert_eq!(contract.get_balance(food_bank()).0, 240);
+
+        let promo = contract.get_promo_status(hash(CODE)).unwrap();
+        assert_eq!(promo.remaining_uses, 49);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let receipt_id = last_receipt_id(&contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "analytics_access".to_string(),
+            receipt_id,
+            RedemptionCharge {
+                list_price: U128(200),
+                promo_code_hash: Some(hash(CODE)),
+                ..token_charge(160)
+            },
+        );
+        let receipt = contract.get_receipt(receipt_id).unwrap();
+        assert_eq!(receipt.amount.0, 160);
+        assert_eq!(receipt.promo_code_hash, Some(hash(CODE)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient tokens for redemption")]
+    fn amount_below_the_discounted_cost_is_rejected() {
+        let mut contract = setup_promo(50);
+        redeem(&mut contract, food_bank(), 159, CODE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Promo code not found")]
+    fn unknown_codes_are_rejected() {
+        let mut contract = setup_promo(50);
+        redeem(&mut contract, food_bank(), 160, "WINTER20");
+    }
+
+    #[test]
+    #[should_panic(expected = "Promo code is not valid for supplier_discount")]
+    fn codes_for_another_option_are_rejected() {
+        let mut contract = setup_promo(50);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), Some(CODE.to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Promo code expired at 1000000000000")]
+    fn expired_codes_are_rejected() {
+        let mut contract = setup_promo(50);
+        advance_time(EXPIRY);
+        redeem(&mut contract, food_bank(), 160, CODE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Promo code has no uses left")]
+    fn exhausted_codes_are_rejected() {
+        let mut contract = setup_promo(1);
+        redeem(&mut contract, food_bank(), 160, CODE);
+        redeem(&mut contract, other_food_bank(), 160, CODE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Promo code was already used by bob")]
+    fn codes_are_one_use_per_account() {
+        let mut contract = setup_promo(50);
+        redeem(&mut contract, food_bank(), 160, CODE);
+        redeem(&mut contract, food_bank(), 160, CODE);
+    }
+
+    #[test]
+    fn refunded_redemptions_give_the_use_back() {
+        let mut contract = setup_promo(1);
+        redeem(&mut contract, food_bank(), 160, CODE);
+        resolve_callback(PromiseResult::Failed);
+        let receipt_id = last_receipt_id(&contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "analytics_access".to_string(),
+            receipt_id,
+            RedemptionCharge {
+                list_price: U128(200),
+                promo_code_hash: Some(hash(CODE)),
+                ..token_charge(160)
+            },
+        );
+        assert_eq!(contract.get_receipt(receipt_id).unwrap().status, FulfillmentStatus::Refunded);
+        assert_eq!(contract.get_promo_status(hash(CODE)).unwrap().remaining_uses, 1);
+        redeem(&mut contract, food_bank(), 160, CODE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Promo already exists")]
+    fn codes_cannot_be_recreated() {
+        let mut contract = setup_promo(50);
+        contract.create_promo(hash(CODE), "analytics_access".to_string(), 5_000, 10, EXPIRY);
+    }
+}
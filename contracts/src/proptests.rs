@@ -0,0 +1,335 @@
+//! Property tests for the reward and redemption arithmetic: basis-point
+//! rates, multipliers, caps and the splits of pools and costs. They drive
+//! the pure helpers directly and the contract through the mocked runtime,
+//! so they only build natively.
+
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::accounts;
+use near_sdk::{AccountId, Balance};
+use proptest::prelude::*;
+
+use crate::campaigns::Campaign;
+use crate::contributions::ContributionMetricsV2;
+use crate::epochs::Epoch;
+use crate::promos::discounted_cost;
+use crate::redemptions::RedeemCheck;
+use crate::reward_curve::RewardCurve;
+use crate::test_utils::*;
+use crate::treasury::MAX_TREASURY_SHARE_BPS;
+use crate::{cap_reward_parts, reward_for_points, FoodBankToken};
+
+fn treasury() -> AccountId {
+    accounts(3)
+}
+
+fn holders() -> [AccountId; 3] {
+    [food_bank(), other_food_bank(), treasury()]
+}
+
+fn metrics_strategy() -> impl Strategy<Value = ContributionMetricsV2> {
+    (0u8..=100, 0u8..=100, 0u8..=100).prop_map(|(data_quality, model_improvement, participation_frequency)| {
+        metrics(data_quality, model_improvement, participation_frequency)
+    })
+}
+
+/// Valid curves whose payouts don't fall as the score rises.
+fn curve_strategy() -> impl Strategy<Value = RewardCurve> {
+    let steps = (1usize..=5).prop_flat_map(|len| {
+        (
+            prop::collection::btree_set(0u32..=100, len),
+            prop::collection::vec(0u16..=10_000, len),
+        )
+    });
+    prop_oneof![
+        Just(RewardCurve::Linear),
+        Just(RewardCurve::Quadratic),
+        steps.prop_map(|(scores, mut payouts)| {
+            payouts.sort_unstable();
+            RewardCurve::Step(scores.into_iter().zip(payouts).collect())
+        }),
+    ]
+}
+
+fn amount_strategy() -> impl Strategy<Value = Balance> {
+    0u128..=10_000
+}
+
+/// Proptest runs every case on the same thread, and so in the same mocked
+/// blockchain; each case starts again from empty storage.
+fn fresh_contract() -> FoodBankToken {
+    near_sdk::mock::with_mocked_blockchain(|blockchain| blockchain.take_storage());
+    setup_contract()
+}
+
+/// A contract with the given reward settings and no caller-specific
+/// bonuses.
+fn configured(rate_bps: u16, threshold: u32, curve: RewardCurve, cap: Balance) -> FoodBankToken {
+    let mut contract = fresh_contract();
+    set_reward_rate_bps(&mut contract, rate_bps);
+    contract.min_score_threshold = threshold;
+    contract.reward_curve = curve;
+    contract.max_reward_per_contribution = cap;
+    contract
+}
+
+fn reward_for(contract: &FoodBankToken, metrics: &ContributionMetricsV2, campaign: Option<&Campaign>) -> Balance {
+    contract.calculate_reward(&food_bank(), metrics.score(), campaign).reward.0
+}
+
+fn campaign(bonus_bps: u16, pool: Balance) -> Campaign {
+    Campaign {
+        id: 0,
+        name: "drive".to_string(),
+        start: 0,
+        end: u64::MAX,
+        reward_pool: U128(pool),
+        distributed: U128(0),
+        bonus_bps,
+        closed: false,
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Contribute(usize, ContributionMetricsV2),
+    Redeem(usize, Balance),
+    Stake(usize, Balance),
+    Unstake(usize, Balance),
+    TreasuryTransfer(usize, Balance),
+    Void(usize, u64),
+    ClaimVested(usize),
+    AdvanceTime(u64),
+}
+
+/// Food bank indices exclude the treasury, which can only move tokens with
+/// `treasury_transfer`.
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0usize..2, metrics_strategy()).prop_map(|(holder, metrics)| Op::Contribute(holder, metrics)),
+        (0usize..2, 1u128..=200).prop_map(|(holder, amount)| Op::Redeem(holder, amount)),
+        (0usize..2, 1u128..=200).prop_map(|(holder, amount)| Op::Stake(holder, amount)),
+        (0usize..2, 1u128..=200).prop_map(|(holder, amount)| Op::Unstake(holder, amount)),
+        (0usize..2, 1u128..=50).prop_map(|(holder, amount)| Op::TreasuryTransfer(holder, amount)),
+        (0usize..2, 0u64..8).prop_map(|(holder, index)| Op::Void(holder, index)),
+        (0usize..2).prop_map(Op::ClaimVested),
+        (1u64..=30 * 24 * 60 * 60 * 1_000_000_000).prop_map(Op::AdvanceTime),
+    ]
+}
+
+/// Applies `op` when its preconditions hold, so every sequence is one the
+/// contract accepts.
+fn apply(contract: &mut FoodBankToken, op: &Op) {
+    match op {
+        Op::Contribute(holder, metrics) => {
+            set_caller(owner(), ONE_NEAR);
+            let metrics = metrics.clone();
+            contract.record_contribution(holders()[*holder].clone(), metrics, None, None, None, None);
+        }
+        Op::Redeem(holder, amount) => {
+            let account = holders()[*holder].clone();
+            let burn = "burn".to_string();
+            if let RedeemCheck::Ok { .. } = contract.check_redeemable(&account, &burn, Some(*amount), 0) {
+                set_caller(account, 1);
+                contract.redeem_tokens(burn, U128(*amount), None);
+            }
+        }
+        Op::Stake(holder, amount) => {
+            let account = holders()[*holder].clone();
+            if contract.get_balance(account.clone()).0 >= *amount {
+                set_caller(account, 1);
+                contract.stake(U128(*amount));
+            }
+        }
+        Op::Unstake(holder, amount) => {
+            let account = holders()[*holder].clone();
+            let unlocked = contract
+                .stakes
+                .get(&account)
+                .is_some_and(|stake| near_sdk::env::block_timestamp() >= stake.unlocks_at);
+            if unlocked && contract.get_staked_balance(account.clone()).0 >= *amount {
+                set_caller(account, 1);
+                contract.unstake(U128(*amount));
+            }
+        }
+        Op::TreasuryTransfer(holder, amount) => {
+            if contract.get_balance(treasury()).0 >= *amount {
+                set_caller(owner(), 1);
+                contract.treasury_transfer(holders()[*holder].clone(), U128(*amount));
+            }
+        }
+        Op::Void(holder, index) => {
+            let account = holders()[*holder].clone();
+            let recorded = contract.has_reward_breakdown(&(account.clone(), *index));
+            if recorded && !contract.is_contribution_voided(account.clone(), *index) {
+                set_caller(owner(), 0);
+                contract.void_contribution(account, *index);
+            }
+        }
+        Op::ClaimVested(holder) => {
+            let account = holders()[*holder].clone();
+            if contract.get_claimable(account.clone()).0 > 0 {
+                set_caller(account, 0);
+                contract.claim_vested();
+            }
+        }
+        Op::AdvanceTime(nanos) => advance_time(*nanos),
+    }
+}
+
+/// Tokens held by the test accounts, in their balances, stakes and
+/// unclaimed vesting schedules.
+fn held(contract: &FoodBankToken) -> Balance {
+    holders()
+        .into_iter()
+        .map(|account| {
+            let vesting: Balance = contract
+                .get_vesting_schedules(account.clone())
+                .iter()
+                .map(|schedule| schedule.total.0 - schedule.claimed.0)
+                .sum();
+            contract.get_balance(account.clone()).0 + contract.get_staked_balance(account).0 + vesting
+        })
+        .sum()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn reward_never_falls_when_a_metric_rises(
+        base in metrics_strategy(),
+        metric in 0usize..3,
+        raise in 0u8..=100,
+        rate_bps in 0u16..=10_000,
+        threshold in 0u32..=100,
+        curve in curve_strategy(),
+    ) {
+        let contract = configured(rate_bps, threshold, curve, 0);
+        let mut raised = base.clone();
+        let value = match metric {
+            0 => &mut raised.data_quality,
+            1 => &mut raised.model_improvement,
+            _ => &mut raised.participation_frequency,
+        };
+        *value = value.saturating_add(raise).min(100);
+        prop_assert!(reward_for(&contract, &raised, None) >= reward_for(&contract, &base, None));
+    }
+
+    #[test]
+    fn reward_never_exceeds_the_cap(
+        metrics in metrics_strategy(),
+        rate_bps in 0u16..=10_000,
+        curve in curve_strategy(),
+        cap in 1u128..=200,
+        bonus_bps in 0u16..=10_000,
+        pool in amount_strategy(),
+        treasury_share_bps in 0u16..=MAX_TREASURY_SHARE_BPS,
+        referral_bonus_bps in 0u16..=10_000,
+        onboarding_bonus in amount_strategy(),
+    ) {
+        let mut contract = configured(rate_bps, 0, curve, cap);
+        let campaign = campaign(bonus_bps, pool);
+        prop_assert!(reward_for(&contract, &metrics, Some(&campaign)) <= cap);
+        prop_assert!(reward_for(&contract, &metrics, None) <= cap);
+
+        // The treasury share, referral and onboarding bonuses count too.
+        contract.set_treasury(Some(treasury()), treasury_share_bps);
+        contract.set_referral_config(referral_bonus_bps, U128(Balance::MAX));
+        contract.set_onboarding_bonus(U128(onboarding_bonus));
+        set_caller(food_bank(), 0);
+        contract.set_referrer(other_food_bank());
+        let supply_before = contract.total_supply;
+        set_caller(owner(), ONE_NEAR);
+        contract.record_contribution(food_bank(), metrics, None, None, None, None);
+        prop_assert!(contract.total_supply - supply_before <= cap);
+    }
+
+    #[test]
+    fn points_scale_up_to_the_full_rate(
+        points in 0u128..=1_000_000,
+        rate_bps in 0u16..=10_000,
+    ) {
+        // No bonus and a 1x tier pay at most the rate on a perfect score.
+        let reward = reward_for_points(points, rate_bps, 0, 10_000);
+        prop_assert!(reward <= Balance::from(rate_bps) * 100);
+        prop_assert!(reward_for_points(points, rate_bps, 0, 10_000) <= reward_for_points(points + 1, rate_bps, 0, 10_000));
+    }
+
+    #[test]
+    fn capping_only_takes_away(
+        reward in amount_strategy(),
+        campaign_bonus in amount_strategy(),
+        matched in amount_strategy(),
+        cap in amount_strategy(),
+    ) {
+        let (capped_reward, capped_bonus, capped_match) = cap_reward_parts(reward, campaign_bonus, matched, cap);
+        prop_assert!(capped_reward <= reward && capped_bonus <= campaign_bonus && capped_match <= matched);
+        let total = capped_reward + capped_bonus + capped_match;
+        if cap > 0 {
+            prop_assert!(total <= cap);
+            prop_assert_eq!(total, (reward + campaign_bonus + matched).min(cap));
+        } else {
+            prop_assert_eq!(total, reward + campaign_bonus + matched);
+        }
+    }
+
+    #[test]
+    fn discounts_never_raise_the_cost(
+        cost in amount_strategy(),
+        tenure_bps in 0u16..=10_000,
+        promo_bps in 0u16..=10_000,
+    ) {
+        let once = discounted_cost(cost, tenure_bps);
+        prop_assert!(once <= cost);
+        prop_assert!(discounted_cost(once, promo_bps) <= once);
+    }
+
+    #[test]
+    fn epoch_shares_never_exceed_the_pool(
+        pool in amount_strategy(),
+        scores in prop::collection::vec(0u64..=100, 1..20),
+    ) {
+        let epoch = Epoch {
+            id: 0,
+            start: 0,
+            end: 1,
+            pool: U128(pool),
+            total_score: scores.iter().sum(),
+            participant_count: scores.len() as u64,
+            settled_count: 0,
+            distributed: U128(0),
+        };
+        let paid: Balance = scores.iter().map(|score| epoch.share_of(*score)).sum();
+        prop_assert!(paid <= pool);
+    }
+
+    #[test]
+    fn campaign_bonuses_stay_inside_the_pool(
+        reward in amount_strategy(),
+        bonus_bps in 0u16..=10_000,
+        pool in amount_strategy(),
+    ) {
+        let bonus = campaign(bonus_bps, pool).bonus_for(reward);
+        prop_assert!(bonus <= pool && bonus <= reward);
+    }
+
+    #[test]
+    fn supply_matches_what_accounts_hold(
+        rate_bps in 0u16..=10_000,
+        treasury_share_bps in 0u16..=2_000,
+        vesting_threshold in 0u128..=100,
+        ops in prop::collection::vec(op_strategy(), 1..30),
+    ) {
+        let mut contract = fresh_contract();
+        set_reward_rate_bps(&mut contract, rate_bps);
+        contract.vesting_threshold = vesting_threshold;
+        contract.set_treasury(Some(treasury()), treasury_share_bps);
+        set_caller(owner(), ONE_NEAR);
+        contract.add_redemption_option("burn".to_string(), "Burn".to_string(), U128(1), String::new(), None);
+        for op in &ops {
+            apply(&mut contract, op);
+            // The initial supply is not held by any account.
+            prop_assert_eq!(contract.total_supply, INITIAL_SUPPLY + held(&contract), "after {:?}", op);
+        }
+    }
+}
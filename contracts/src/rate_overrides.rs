@@ -0,0 +1,178 @@
+//! Negotiated reward rates for pilot partners. An account's override
+//! replaces the static rate and the emission schedule for its
+//! contributions; stake bonuses, tiers, campaigns, matching and the
+//! per-contribution cap apply on top as usual. Penalties stay scaled by the
+//! program-wide rate. Overrides are set through a timelocked
+//! `ParamChange::AccountRateOverride`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// Where the rate a reward was calculated at came from.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RateSource {
+    Static,
+    EmissionSchedule,
+    AccountOverride,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountRate {
+    pub rate_bps: u16,
+    pub source: RateSource,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    /// The rate a contribution by `account_id` recorded now is paid at.
+    pub fn get_rate_for(&self, account_id: AccountId) -> AccountRate {
+        let (rate_bps, source) = self.rate_for(&account_id);
+        AccountRate { rate_bps, source }
+    }
+
+    pub fn get_rate_overrides(&self, from_index: u64, limit: u64) -> Vec<(AccountId, u16)> {
+        self.rate_overrides
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn rate_for(&self, account_id: &AccountId) -> (u16, RateSource) {
+        if let Some(rate_bps) = self.rate_overrides.get(account_id) {
+            return (rate_bps, RateSource::AccountOverride);
+        }
+        let now = env::block_timestamp();
+        let scheduled = self.emission_schedule.iter().any(|(start, _)| *start <= now);
+        let source = if scheduled {
+            RateSource::EmissionSchedule
+        } else {
+            RateSource::Static
+        };
+        (self.rate_bps_at(now), source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::timelock::{ParamChange, DEFAULT_TIMELOCK_DELAY};
+
+    fn partner() -> AccountId {
+        food_bank()
+    }
+
+    fn override_change(account_id: AccountId, rate_bps: Option<u16>) -> ParamChange {
+        ParamChange::AccountRateOverride { account_id, rate_bps }
+    }
+
+    fn with_override(rate_bps: u16) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.apply_param_change(&override_change(partner(), Some(rate_bps)));
+        contract
+    }
+
+    #[test]
+    fn a_proposed_override_waits_for_the_timelock() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(override_change(partner(), Some(1_000)));
+        assert_eq!(contract.get_rate_for(partner()).source, RateSource::Static);
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_rate_for(partner()).rate_bps, 1_000);
+    }
+
+    #[test]
+    fn a_higher_override_pays_the_partner_more() {
+        let mut contract = with_override(1_000);
+        contribute(&mut contract, &partner(), 100);
+        contribute(&mut contract, &other_food_bank(), 100);
+        assert_eq!(contract.get_balance(partner()).0, 10);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 5);
+
+        let breakdown = contract.get_reward_breakdown(partner(), 0).unwrap();
+        assert_eq!(breakdown.rate_bps, 1_000);
+        assert_eq!(breakdown.rate_source, RateSource::AccountOverride);
+        let others = contract.get_reward_breakdown(other_food_bank(), 0).unwrap();
+        assert_eq!(others.rate_source, RateSource::Static);
+    }
+
+    #[test]
+    fn a_lower_override_pays_less() {
+        let mut contract = with_override(200);
+        contribute(&mut contract, &partner(), 100);
+        assert_eq!(contract.get_balance(partner()).0, 2);
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_the_emission_schedule() {
+        let mut contract = with_override(1_000);
+        set_emission_schedule(&mut contract, vec![(0, 300)]);
+        assert_eq!(
+            contract.get_rate_for(partner()),
+            AccountRate {
+                rate_bps: 1_000,
+                source: RateSource::AccountOverride
+            }
+        );
+        assert_eq!(contract.get_rate_for(other_food_bank()).source, RateSource::EmissionSchedule);
+        assert_eq!(contract.get_rate_for(other_food_bank()).rate_bps, 300);
+    }
+
+    #[test]
+    fn removing_the_override_restores_the_program_rate() {
+        let mut contract = with_override(1_000);
+        contribute(&mut contract, &partner(), 100);
+        contract.apply_param_change(&override_change(partner(), None));
+        contribute(&mut contract, &partner(), 100);
+
+        assert_eq!(contract.get_balance(partner()).0, 15);
+        assert_eq!(contract.get_reward_breakdown(partner(), 0).unwrap().rate_bps, 1_000);
+        let after = contract.get_reward_breakdown(partner(), 1).unwrap();
+        assert_eq!((after.rate_bps, after.rate_source), (500, RateSource::Static));
+        assert!(contract.get_rate_overrides(0, 10).is_empty());
+    }
+
+    #[test]
+    fn the_reward_cap_still_applies() {
+        let mut contract = with_override(1_000);
+        set_max_reward_per_contribution(&mut contract, 7);
+        contribute(&mut contract, &partner(), 100);
+        let breakdown = contract.get_reward_breakdown(partner(), 0).unwrap();
+        assert_eq!(breakdown.reward.0, 7);
+        assert_eq!(breakdown.uncapped_reward.0, 10);
+    }
+
+    #[test]
+    fn overrides_are_listed_in_pages() {
+        let mut contract = with_override(1_000);
+        contract.apply_param_change(&override_change(other_food_bank(), Some(750)));
+        assert_eq!(contract.get_rate_overrides(0, 10), vec![(partner(), 1_000), (other_food_bank(), 750)]);
+        assert_eq!(contract.get_rate_overrides(1, 10), vec![(other_food_bank(), 750)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rate must be <= 10000 bps")]
+    fn overrides_are_bounded() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(override_change(partner(), Some(10_001)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn overrides_are_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(partner(), 0);
+        contract.propose_param_change(override_change(partner(), Some(1_000)));
+    }
+}
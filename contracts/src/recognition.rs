@@ -0,0 +1,310 @@
+//! NEAR donations with on-chain recognition. `donate` credits the donor
+//! recognition at the rate in force at the time and, when a matching pool
+//! is open, tops it up at `tokens_per_near` with budget that like
+//! sponsored tokens is spent first and never refunded. The donated NEAR,
+//! less the storage the donation adds, is set aside as the donation
+//! reserve: `withdraw_near` never touches it and only the Treasurer role
+//! can withdraw it. Recognition is kept apart from token balances: it
+//! counts towards no supply, can't be moved and can't be redeemed, and a
+//! later rate change leaves what donors already hold as it is.
+//! Recognition only grows, so the leaderboard is kept ranked as donations
+//! come in and holds the top `MAX_LEADERBOARD_LENGTH` donors.
+
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, near_bindgen, AccountId, Balance, Promise, ONE_NEAR};
+
+use crate::events::{DonationsWithdrawn, DonorRecognized, Event};
+use crate::roles::Role;
+use crate::storage_credit::storage_cost;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_RECOGNITION_PER_NEAR: Balance = 100;
+/// 0.01 NEAR, enough to cover the storage a first donation adds.
+pub const MIN_DONATION: Balance = 10u128.pow(22);
+pub const MAX_LEADERBOARD_LENGTH: usize = 100;
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Returns the recognition credited. While the contract is paused the
+    /// deposit is sent straight back and nothing is credited.
+    #[payable]
+    pub fn donate(&mut self) -> U128 {
+        let initial_storage = env::storage_usage();
+        let donor = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        assert!(
+            deposit >= MIN_DONATION,
+            "Donations must be at least {} yoctoNEAR",
+            MIN_DONATION
+        );
+        if self.paused {
+            log!("Contract is paused; refunding {} yoctoNEAR", deposit);
+            Promise::new(donor).transfer(deposit);
+            return U128(0);
+        }
+        let tokens = deposit * self.tokens_per_near / ONE_NEAR;
+        let tokens = if tokens > 0 { self.top_up_matching_pool(tokens) } else { 0 };
+        let recognition = deposit * self.recognition_per_near / ONE_NEAR;
+        let held = self.recognition_balances.get(&donor);
+        if held.is_none() {
+            self.recognition_donors.push(&donor);
+        }
+        let total_recognition = held.unwrap_or(0) + recognition;
+        self.recognition_balances.insert(&donor, &total_recognition);
+        rank_donor(&mut self.top_donors, &donor, total_recognition);
+        let stats = &mut self.program_stats;
+        stats.total_donated_near = U128(stats.total_donated_near.0 + deposit);
+        self.donation_reserve += deposit.saturating_sub(storage_cost(initial_storage));
+        DonorRecognized {
+            donor: &donor,
+            deposit: U128(deposit),
+            matching_tokens: U128(tokens),
+            recognition: U128(recognition),
+            total_recognition: U128(total_recognition),
+        }
+        .emit();
+        U128(recognition)
+    }
+
+    /// Applies to donations made after the change.
+    pub fn set_recognition_per_near(&mut self, recognition_per_near: U128) {
+        self.assert_owner_action();
+        self.recognition_per_near = recognition_per_near.0;
+    }
+
+    #[payable]
+    pub fn withdraw_donations(&mut self, amount: U128, to: AccountId) -> Promise {
+        assert_one_yocto();
+        self.assert_role(Role::Treasurer);
+        assert!(
+            amount.0 <= self.donation_reserve,
+            "Only {} yoctoNEAR of donations is available",
+            self.donation_reserve
+        );
+        self.donation_reserve -= amount.0;
+        DonationsWithdrawn {
+            actor: &env::predecessor_account_id(),
+            to: &to,
+            amount,
+            remaining: U128(self.donation_reserve),
+        }
+        .emit();
+        Promise::new(to).transfer(amount.0)
+    }
+
+    // View functions
+    pub fn get_recognition(&self, account_id: AccountId) -> U128 {
+        U128(self.recognition_balances.get(&account_id).unwrap_or(0))
+    }
+
+    pub fn get_recognition_per_near(&self) -> U128 {
+        U128(self.recognition_per_near)
+    }
+
+    pub fn get_donation_reserve(&self) -> U128 {
+        U128(self.donation_reserve)
+    }
+
+    /// The top donors by recognition, highest first; of two donors with the
+    /// same recognition, the one that reached it first ranks higher.
+    pub fn get_donor_leaderboard(&self, from_index: u64, limit: u64) -> Vec<(AccountId, U128)> {
+        self.top_donors
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|(donor, recognition)| (donor.clone(), U128(*recognition)))
+            .collect()
+    }
+
+    /// Every donor, in the order of their first donation.
+    pub fn get_donors(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        (from_index..self.recognition_donors.len().min(from_index.saturating_add(limit)))
+            .map(|index| self.recognition_donors.get(index).unwrap())
+            .collect()
+    }
+}
+
+/// Moves `donor` to its place for `recognition` in the ranking, dropping
+/// whoever falls below the last place.
+pub(crate) fn rank_donor(top_donors: &mut Vec<(AccountId, Balance)>, donor: &AccountId, recognition: Balance) {
+    top_donors.retain(|(ranked, _)| ranked != donor);
+    let at = top_donors.partition_point(|(_, ranked)| *ranked >= recognition);
+    if at < MAX_LEADERBOARD_LENGTH {
+        top_donors.insert(at, (donor.clone(), recognition));
+        top_donors.truncate(MAX_LEADERBOARD_LENGTH);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs};
+
+    fn donor() -> AccountId {
+        accounts(3)
+    }
+
+    fn donate(contract: &mut FoodBankToken, donor: AccountId, deposit: Balance) -> Balance {
+        set_caller(donor, deposit);
+        contract.donate().0
+    }
+
+    #[test]
+    fn donations_earn_recognition_and_top_up_the_pool() {
+        let mut contract = setup_contract();
+        contract.set_tokens_per_near(U128(50));
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.fund_matching_pool();
+        assert_eq!(donate(&mut contract, donor(), 2 * ONE_NEAR), 200);
+        assert_eq!(contract.get_recognition(donor()).0, 200);
+
+        let pool = contract.get_matching_pool().unwrap();
+        assert_eq!(pool.donor, other_food_bank());
+        assert_eq!(pool.deposited.0, ONE_NEAR);
+        assert_eq!(pool.remaining.0, 150);
+        assert_eq!(pool.sponsored.0, 100);
+        assert_eq!(contract.get_total_supply().0, INITIAL_SUPPLY);
+        assert_eq!(contract.get_balance(donor()).0, 0);
+        assert_eq!(contract.get_program_stats().total_donated_near.0, 2 * ONE_NEAR);
+        assert!(get_logs()[0].contains(r#""matching_tokens":"100","recognition":"200""#));
+    }
+
+    #[test]
+    fn donations_open_no_pool() {
+        let mut contract = setup_contract();
+        contract.set_tokens_per_near(U128(50));
+        donate(&mut contract, donor(), ONE_NEAR);
+        assert!(contract.get_matching_pool().is_none());
+        assert!(get_logs()[0].contains(r#""matching_tokens":"0""#));
+
+        // The donor can still open a pool of their own.
+        set_caller(donor(), ONE_NEAR);
+        assert_eq!(contract.fund_matching_pool().0, 50);
+    }
+
+    #[test]
+    fn donations_are_kept_from_withdraw_near() {
+        let mut contract = setup_contract();
+        set_caller(donor(), ONE_NEAR);
+        let available = contract.get_available_near().0;
+        let storage = env::storage_usage();
+        contract.donate();
+        let storage_cost = Balance::from(env::storage_usage() - storage) * env::storage_byte_cost();
+        assert_eq!(contract.get_donation_reserve().0, ONE_NEAR - storage_cost);
+        assert_eq!(contract.get_available_near().0, available - ONE_NEAR);
+    }
+
+    #[test]
+    fn the_treasurer_withdraws_donations() {
+        let mut contract = setup_contract();
+        donate(&mut contract, donor(), ONE_NEAR);
+        let reserve = contract.get_donation_reserve().0;
+        set_caller(owner(), 0);
+        contract.grant_role(other_food_bank(), Role::Treasurer);
+        set_caller(other_food_bank(), 1);
+        contract.withdraw_donations(U128(ONE_NEAR / 2), other_food_bank());
+        assert_eq!(contract.get_donation_reserve().0, reserve - ONE_NEAR / 2);
+
+        let transfer = get_created_receipts().pop().unwrap();
+        assert_eq!(transfer.receiver_id, other_food_bank());
+        assert_eq!(transfer.actions, vec![VmAction::Transfer { deposit: ONE_NEAR / 2 }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "yoctoNEAR of donations is available")]
+    fn donations_cannot_be_overdrawn() {
+        let mut contract = setup_contract();
+        donate(&mut contract, donor(), ONE_NEAR);
+        set_caller(owner(), 1);
+        contract.withdraw_donations(U128(ONE_NEAR), owner());
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Treasurer role")]
+    fn donors_cannot_withdraw_donations() {
+        let mut contract = setup_contract();
+        donate(&mut contract, donor(), ONE_NEAR);
+        set_caller(donor(), 1);
+        contract.withdraw_donations(U128(1), donor());
+    }
+
+    #[test]
+    fn rate_changes_leave_past_recognition_alone() {
+        let mut contract = setup_contract();
+        donate(&mut contract, donor(), ONE_NEAR);
+        set_caller(owner(), 0);
+        contract.set_recognition_per_near(U128(300));
+        assert_eq!(contract.get_recognition(donor()).0, 100);
+        assert_eq!(donate(&mut contract, donor(), ONE_NEAR), 300);
+        assert_eq!(contract.get_recognition(donor()).0, 400);
+    }
+
+    #[test]
+    fn paused_donations_are_refunded_in_full() {
+        let mut contract = setup_contract();
+        contract.paused = true;
+        assert_eq!(donate(&mut contract, donor(), ONE_NEAR), 0);
+        let refund = get_created_receipts().pop().unwrap();
+        assert_eq!(refund.receiver_id, donor());
+        assert_eq!(refund.actions, vec![VmAction::Transfer { deposit: ONE_NEAR }]);
+        assert_eq!(contract.get_recognition(donor()).0, 0);
+        assert!(contract.get_donor_leaderboard(0, 10).is_empty());
+        assert_eq!(contract.get_program_stats().total_donated_near.0, 0);
+        assert_eq!(contract.get_donation_reserve().0, 0);
+    }
+
+    #[test]
+    fn the_leaderboard_ranks_donors_by_recognition() {
+        let mut contract = setup_contract();
+        donate(&mut contract, donor(), ONE_NEAR);
+        donate(&mut contract, accounts(4), 3 * ONE_NEAR);
+        donate(&mut contract, accounts(5), ONE_NEAR);
+        donate(&mut contract, donor(), ONE_NEAR);
+        assert_eq!(
+            contract.get_donor_leaderboard(0, 10),
+            vec![(accounts(4), U128(300)), (donor(), U128(200)), (accounts(5), U128(100))]
+        );
+        assert_eq!(contract.get_donor_leaderboard(1, 1), vec![(donor(), U128(200))]);
+        assert_eq!(contract.get_donors(0, 10), vec![donor(), accounts(4), accounts(5)]);
+        assert_eq!(contract.get_donors(2, 10), vec![accounts(5)]);
+    }
+
+    #[test]
+    fn the_leaderboard_keeps_the_top_donors() {
+        let mut contract = setup_contract();
+        let donors: Vec<AccountId> = (0..=MAX_LEADERBOARD_LENGTH)
+            .map(|index| format!("donor{}.near", index).parse().unwrap())
+            .collect();
+        for donor in &donors {
+            donate(&mut contract, donor.clone(), ONE_NEAR);
+        }
+        // The last donor tied with a full leaderboard and didn't make it.
+        let leaderboard = contract.get_donor_leaderboard(0, 200);
+        assert_eq!(leaderboard.len(), MAX_LEADERBOARD_LENGTH);
+        assert!(!leaderboard.iter().any(|(ranked, _)| ranked == &donors[MAX_LEADERBOARD_LENGTH]));
+
+        donate(&mut contract, donors[MAX_LEADERBOARD_LENGTH].clone(), ONE_NEAR);
+        let leaderboard = contract.get_donor_leaderboard(0, 200);
+        assert_eq!(leaderboard[0], (donors[MAX_LEADERBOARD_LENGTH].clone(), U128(200)));
+        assert_eq!(leaderboard.len(), MAX_LEADERBOARD_LENGTH);
+        assert!(!leaderboard.iter().any(|(ranked, _)| ranked == &donors[MAX_LEADERBOARD_LENGTH - 1]));
+        assert_eq!(contract.get_donors(0, 200).len(), MAX_LEADERBOARD_LENGTH + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Donations must be at least 10000000000000000000000 yoctoNEAR")]
+    fn donations_need_the_minimum_deposit() {
+        let mut contract = setup_contract();
+        donate(&mut contract, donor(), MIN_DONATION - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn the_rate_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(donor(), 0);
+        contract.set_recognition_per_near(U128(1));
+    }
+}
@@ -0,0 +1,1013 @@
+//! Redemption validation shared by `redeem_tokens` and the `can_redeem`,
+//! `get_affordable_options` and `estimate_redeem` views, plus optional
+//! per-option limits and availability windows.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance, Gas};
+
+use crate::account_types::AccountType;
+use crate::promos::discounted_cost;
+use crate::roles::Role;
+use crate::{
+    FoodBankToken, FoodBankTokenExt, RedemptionOptionV5, GAS_FOR_RESOLVE_REDEMPTION, GAS_RESERVED_FOR_REDEEM,
+};
+
+pub const MAX_OPTIONS_PER_PAGE: u64 = 50;
+
+/// Kept apart from the option so limits change without rewriting it.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RedemptionLimits {
+    /// Cap on redemptions of the option across all accounts. Refunded
+    /// redemptions don't count.
+    pub max_redemptions: Option<u64>,
+    /// Cap on redemptions of the option by a single account.
+    pub max_per_account: Option<u64>,
+    pub available_from: Option<u64>,
+    /// Exclusive.
+    pub available_until: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "status")]
+pub enum RedeemCheck {
+    Ok { cost: U128 },
+    OptionNotFound,
+    OptionUnavailable,
+    /// The account has never held tokens or contributed.
+    NotRegistered,
+    InsufficientBalance { missing: U128 },
+    /// The amount offered is below the option's cost.
+    AmountBelowCost { cost: U128 },
+    LimitReached,
+    AccountLimitReached,
+    NotEnoughContributions { required: u64, recorded: u64 },
+    PrerequisiteMissing { option_id: String },
+    NotInWindow,
+    /// The account redeemed the option too recently.
+    CooldownActive { available_at: u64 },
+    AccountFrozen,
+    /// The balance only covers the amount with rewards still under
+    /// cooldown.
+    FundsLocked { unlocks_at: u64 },
+    TreasuryAccount,
+    /// Only food banks redeem program options.
+    AccountTypeNotAllowed { account_type: AccountType },
+    /// The supplier the option's benefit call is routed to is inactive.
+    SupplierInactive { supplier: AccountId },
+    /// A penalty left a debt that must be repaid first.
+    OutstandingDebt { debt: U128 },
+}
+
+/// Where an account stands on one option. `blocked_by` names the first
+/// check other than the balance that stops the redemption, as the
+/// `RedeemCheck` status it fails with.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OptionEligibility {
+    /// What the account would be charged, after its tenure discount.
+    pub price: U128,
+    pub affordable: bool,
+    pub missing: U128,
+    pub blocked_by: Option<String>,
+    /// When the option's cooldown ends for the account, while it lasts.
+    pub available_at: Option<u64>,
+}
+
+/// What a wallet should attach to `redeem_tokens` for one option.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RedeemEstimate {
+    /// Exactly one yoctoNEAR for token-only options, else the co-pay; any
+    /// more than the co-pay is refunded.
+    pub required_deposit: U128,
+    /// Enforced for options with a benefit call. Others only need the
+    /// gas budgeted for the redemption itself.
+    pub min_gas_tgas: u64,
+    /// After the account's tenure discount, without a promo code.
+    pub token_cost: U128,
+    pub check: RedeemCheck,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn set_redemption_limits(&mut self, option_id: String, limits: RedemptionLimits) {
+        self.assert_role(Role::Admin);
+        assert!(
+            self.has_redemption_option(&option_id),
+            "Redemption option not found"
+        );
+        if let (Some(from), Some(until)) = (limits.available_from, limits.available_until) {
+            assert!(from < until, "Availability window is empty");
+        }
+        self.redemption_limits.insert(&option_id, &limits);
+    }
+
+    /// Replaces both eligibility requirements of `option_id`; `None` drops
+    /// a requirement.
+    pub fn set_option_eligibility(
+        &mut self,
+        option_id: String,
+        min_contributions: Option<u64>,
+        prerequisite_option: Option<String>,
+    ) {
+        self.assert_role(Role::Admin);
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        if let Some(prerequisite) = &prerequisite_option {
+            assert!(*prerequisite != option_id, "An option cannot be its own prerequisite");
+            assert!(
+                self.has_redemption_option(prerequisite),
+                "Prerequisite option {} not found",
+                prerequisite
+            );
+        }
+        let mut option = old_option.clone();
+        option.min_contributions = min_contributions;
+        option.prerequisite_option = prerequisite_option;
+        self.set_redemption_option(&option_id, Some(old_option), option);
+    }
+
+    /// NEAR redeemers attach on top of the token cost; `None` makes the
+    /// option token-only again.
+    pub fn set_option_near_copay(&mut self, option_id: String, near_copay: Option<U128>) {
+        self.assert_role(Role::Admin);
+        assert!(
+            near_copay.is_none_or(|copay| copay.0 > 0),
+            "A co-pay must be positive"
+        );
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        let mut option = old_option.clone();
+        option.near_copay = near_copay;
+        self.set_redemption_option(&option_id, Some(old_option), option);
+    }
+
+    /// Time an account waits between redemptions of the option. Applies to
+    /// every later check, including for redemptions made before the change.
+    pub fn set_option_cooldown(&mut self, option_id: String, cooldown_ns: Option<u64>) {
+        self.assert_role(Role::Admin);
+        assert!(
+            cooldown_ns.is_none_or(|cooldown| cooldown > 0),
+            "A cooldown must be positive"
+        );
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        let mut option = old_option.clone();
+        option.cooldown_ns = cooldown_ns;
+        self.set_redemption_option(&option_id, Some(old_option), option);
+    }
+
+    // View functions
+    pub fn get_redemption_limits(&self, option_id: String) -> RedemptionLimits {
+        self.redemption_limits.get(&option_id).unwrap_or_default()
+    }
+
+    /// Whether `account_id` could redeem `option_id` at its discounted cost
+    /// right now.
+    pub fn can_redeem(&self, account_id: AccountId, option_id: String) -> RedeemCheck {
+        self.check_redeemable(&account_id, &option_id, None, 0)
+    }
+
+    /// Redemption options with `account_id`'s eligibility for each, at most
+    /// `MAX_OPTIONS_PER_PAGE` per page.
+    pub fn get_affordable_options(
+        &self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, RedemptionOptionV5, OptionEligibility)> {
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        self.iter_redemption_options()
+            .skip(from_index as usize)
+            .take(limit.min(MAX_OPTIONS_PER_PAGE) as usize)
+            .map(|(option_id, option)| {
+                let check = self.check_redeemable(&account_id, &option_id, None, 0);
+                let price = self.redemption_cost(&account_id, &option, 0);
+                let eligibility = OptionEligibility {
+                    price: U128(price),
+                    affordable: balance >= price,
+                    missing: U128(price.saturating_sub(balance)),
+                    blocked_by: check.blocking_reason().map(str::to_string),
+                    available_at: self.cooldown_ends_at(&account_id, &option_id, &option),
+                };
+                (option_id, option, eligibility)
+            })
+            .collect()
+    }
+
+    /// The deposit, gas and tokens a redemption of `option_id` by
+    /// `account_id` needs, derived from the requirements `redeem_tokens`
+    /// enforces, along with whether it would pass the other checks.
+    pub fn estimate_redeem(&self, account_id: AccountId, option_id: String) -> RedeemEstimate {
+        let (near_copay, required_gas) = self.redemption_attachments(&option_id);
+        let token_cost = self
+            .redemption_option(&option_id)
+            .map_or(0, |option| self.redemption_cost(&account_id, &option, 0));
+        RedeemEstimate {
+            required_deposit: U128(near_copay.max(1)),
+            min_gas_tgas: whole_tgas(required_gas.unwrap_or(GAS_RESERVED_FOR_REDEEM)),
+            token_cost: U128(token_cost),
+            check: self.check_redeemable(&account_id, &option_id, None, 0),
+        }
+    }
+}
+
+impl FoodBankToken {
+    /// The co-pay `redeem_tokens` requires for `option_id`, or 0 when it
+    /// takes exactly one yoctoNEAR, and the gas it requires up front for
+    /// options with a benefit call.
+    pub(crate) fn redemption_attachments(&self, option_id: &String) -> (Balance, Option<Gas>) {
+        self.redemption_option(option_id).map_or((0, None), |option| {
+            let near_copay = option.near_copay.map_or(0, |copay| copay.0);
+            let required_gas = (!option.benefit_method.is_empty()).then(|| {
+                self.benefit_gas(option_id) + GAS_FOR_RESOLVE_REDEMPTION + GAS_RESERVED_FOR_REDEEM
+            });
+            (near_copay, required_gas)
+        })
+    }
+
+    /// What `account_id` pays for `option`. The tenure discount applies
+    /// first and a promo discount to what is left, so 10% and 20% off take
+    /// 28% off the cost rather than 30%.
+    pub(crate) fn redemption_cost(
+        &self,
+        account_id: &AccountId,
+        option: &RedemptionOptionV5,
+        promo_discount_bps: u16,
+    ) -> Balance {
+        let cost = discounted_cost(option.cost.0, self.tenure_discount_bps(account_id));
+        discounted_cost(cost, promo_discount_bps)
+    }
+
+    /// Validation behind every redemption. `amount` defaults to the
+    /// discounted cost.
+    pub(crate) fn check_redeemable(
+        &self,
+        account_id: &AccountId,
+        option_id: &String,
+        amount: Option<Balance>,
+        promo_discount_bps: u16,
+    ) -> RedeemCheck {
+        if self.frozen_accounts.get(account_id).is_some() {
+            return RedeemCheck::AccountFrozen;
+        }
+        if self.treasury_account.as_ref() == Some(account_id) {
+            return RedeemCheck::TreasuryAccount;
+        }
+        let account_type = self.account_type(account_id);
+        if !account_type.can_redeem() {
+            return RedeemCheck::AccountTypeNotAllowed { account_type };
+        }
+        let debt = self.debt(account_id);
+        if debt > 0 {
+            return RedeemCheck::OutstandingDebt { debt: U128(debt) };
+        }
+        let option = match self.redemption_option(option_id) {
+            Some(option) => option,
+            None => return RedeemCheck::OptionNotFound,
+        };
+        if !option.available {
+            return RedeemCheck::OptionUnavailable;
+        }
+        if let Some(supplier) = self.inactive_option_supplier(option_id) {
+            return RedeemCheck::SupplierInactive { supplier };
+        }
+
+        let limits = self.get_redemption_limits(option_id.clone());
+        let now = env::block_timestamp();
+        if limits.available_from.is_some_and(|from| now < from)
+            || limits.available_until.is_some_and(|until| now >= until)
+        {
+            return RedeemCheck::NotInWindow;
+        }
+        let redemptions = self.get_option_stats(option_id.clone()).redemptions_count;
+        if limits.max_redemptions.is_some_and(|max| redemptions >= max) {
+            return RedeemCheck::LimitReached;
+        }
+        let account_redemptions = self
+            .account_redemption_counts
+            .get(&(account_id.clone(), option_id.clone()))
+            .unwrap_or(0);
+        if limits.max_per_account.is_some_and(|max| account_redemptions >= max) {
+            return RedeemCheck::AccountLimitReached;
+        }
+        if let Some(available_at) = self.cooldown_ends_at(account_id, option_id, &option) {
+            return RedeemCheck::CooldownActive { available_at };
+        }
+        if let Some(required) = option.min_contributions {
+            let voided = self.voided_contribution_counts.get(account_id).unwrap_or(0);
+            let recorded = self
+                .account_stats
+                .get(account_id)
+                .map_or(0, |stats| stats.contribution_count - voided);
+            if recorded < required {
+                return RedeemCheck::NotEnoughContributions { required, recorded };
+            }
+        }
+        if let Some(prerequisite) = &option.prerequisite_option {
+            if self.delivered_redemptions(&(account_id.clone(), prerequisite.clone())) == 0 {
+                return RedeemCheck::PrerequisiteMissing {
+                    option_id: prerequisite.clone(),
+                };
+            }
+        }
+
+        let cost = U128(self.redemption_cost(account_id, &option, promo_discount_bps));
+        let amount = amount.unwrap_or(cost.0);
+        if amount < cost.0 {
+            return RedeemCheck::AmountBelowCost { cost };
+        }
+        if !self.participants.contains(account_id) {
+            return RedeemCheck::NotRegistered;
+        }
+        let balance = self.balances.get(account_id).unwrap_or(0);
+        if balance < amount {
+            return RedeemCheck::InsufficientBalance {
+                missing: U128(amount - balance),
+            };
+        }
+        if self.unlocked_balance(account_id, balance) < amount {
+            return RedeemCheck::FundsLocked {
+                unlocks_at: self.next_unlock(account_id).unwrap(),
+            };
+        }
+        RedeemCheck::Ok { cost }
+    }
+
+    /// End of the account's cooldown on `option`, if it hasn't passed yet.
+    fn cooldown_ends_at(
+        &self,
+        account_id: &AccountId,
+        option_id: &str,
+        option: &RedemptionOptionV5,
+    ) -> Option<u64> {
+        let cooldown = option.cooldown_ns?;
+        let last = self.last_redeemed.get(&(account_id.clone(), option_id.to_string()))?;
+        let available_at = last.saturating_add(cooldown);
+        (env::block_timestamp() < available_at).then_some(available_at)
+    }
+
+    /// Forgets the account's last redemption of the option after a
+    /// redemption recorded at `recorded_at` was refunded, since no benefit
+    /// was received. A later redemption keeps its cooldown.
+    pub(crate) fn reset_redemption_cooldown(&mut self, account_id: &AccountId, option_id: &str, recorded_at: u64) {
+        let key = (account_id.clone(), option_id.to_string());
+        if self.last_redeemed.get(&key).is_some_and(|last| last <= recorded_at) {
+            self.last_redeemed.remove(&key);
+        }
+    }
+}
+
+/// Rounded up, so attaching the figure is always enough.
+pub(crate) fn whole_tgas(gas: Gas) -> u64 {
+    gas.0.div_ceil(Gas::ONE_TERA.0)
+}
+
+impl RedeemCheck {
+    fn blocking_reason(&self) -> Option<&'static str> {
+        match self {
+            RedeemCheck::Ok { .. }
+            | RedeemCheck::NotRegistered
+            | RedeemCheck::InsufficientBalance { .. } => None,
+            RedeemCheck::OptionNotFound => Some("OptionNotFound"),
+            RedeemCheck::OptionUnavailable => Some("OptionUnavailable"),
+            RedeemCheck::AmountBelowCost { .. } => Some("AmountBelowCost"),
+            RedeemCheck::LimitReached => Some("LimitReached"),
+            RedeemCheck::AccountLimitReached => Some("AccountLimitReached"),
+            RedeemCheck::NotEnoughContributions { .. } => Some("NotEnoughContributions"),
+            RedeemCheck::PrerequisiteMissing { .. } => Some("PrerequisiteMissing"),
+            RedeemCheck::NotInWindow => Some("NotInWindow"),
+            RedeemCheck::CooldownActive { .. } => Some("CooldownActive"),
+            RedeemCheck::AccountFrozen => Some("AccountFrozen"),
+            RedeemCheck::FundsLocked { .. } => Some("FundsLocked"),
+            RedeemCheck::TreasuryAccount => Some("TreasuryAccount"),
+            RedeemCheck::AccountTypeNotAllowed { .. } => Some("AccountTypeNotAllowed"),
+            RedeemCheck::SupplierInactive { .. } => Some("SupplierInactive"),
+            RedeemCheck::OutstandingDebt { .. } => Some("OutstandingDebt"),
+        }
+    }
+}
+
+impl RedeemCheck {
+    /// Panics with the message `redeem_tokens` has always used for each
+    /// failure.
+    pub(crate) fn assert_ok(&self, account_id: &AccountId) {
+        match self {
+            RedeemCheck::Ok { .. } => {}
+            RedeemCheck::AccountFrozen => panic!("Account {} is frozen", account_id),
+            RedeemCheck::TreasuryAccount => {
+                panic!("Treasury tokens can only be moved with treasury_transfer")
+            }
+            RedeemCheck::AccountTypeNotAllowed { account_type } => {
+                panic!("{:?} accounts cannot redeem program options", account_type)
+            }
+            RedeemCheck::SupplierInactive { supplier } => {
+                panic!("Supplier {} is not active", supplier)
+            }
+            RedeemCheck::OutstandingDebt { debt } => {
+                panic!("Outstanding debt of {} must be repaid before redeeming", debt.0)
+            }
+            RedeemCheck::OptionNotFound => panic!("Redemption option not found"),
+            RedeemCheck::OptionUnavailable => panic!("This redemption option is not available"),
+            RedeemCheck::NotInWindow => panic!("This redemption option is outside its availability window"),
+            RedeemCheck::CooldownActive { available_at } => {
+                panic!("This redemption option is available again at {}", available_at)
+            }
+            RedeemCheck::LimitReached => panic!("This redemption option has reached its limit"),
+            RedeemCheck::AccountLimitReached => {
+                panic!("This account has reached its limit for this redemption option")
+            }
+            RedeemCheck::NotEnoughContributions { required, .. } => {
+                panic!("This redemption option requires {} recorded contributions", required)
+            }
+            RedeemCheck::PrerequisiteMissing { option_id } => {
+                panic!("This redemption option requires a prior {} redemption", option_id)
+            }
+            RedeemCheck::AmountBelowCost { .. } => panic!("Insufficient tokens for redemption"),
+            RedeemCheck::NotRegistered => panic!("Account not registered"),
+            RedeemCheck::InsufficientBalance { .. } => panic!("Insufficient balance"),
+            RedeemCheck::FundsLocked { unlocks_at } => {
+                panic!("Tokens are locked until {}", unlocks_at)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::{testing_env, PromiseResult, ONE_NEAR};
+
+    fn check(contract: &FoodBankToken) -> RedeemCheck {
+        contract.can_redeem(food_bank(), "supplier_discount".to_string())
+    }
+
+    fn limit(contract: &mut FoodBankToken, limits: RedemptionLimits) {
+        set_caller(owner(), 0);
+        contract.set_redemption_limits("supplier_discount".to_string(), limits);
+    }
+
+    fn redeem(contract: &mut FoodBankToken) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(contract),
+            token_charge(100),
+        );
+    }
+
+    #[test]
+    fn redeemable_option_reports_its_cost() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+    }
+
+    #[test]
+    fn missing_tokens_are_reported() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 40);
+        assert_eq!(check(&contract), RedeemCheck::InsufficientBalance { missing: U128(60) });
+    }
+
+    #[test]
+    fn unknown_accounts_are_not_registered() {
+        let contract = setup_contract();
+        assert_eq!(check(&contract), RedeemCheck::NotRegistered);
+    }
+
+    #[test]
+    fn unknown_and_unavailable_options_are_reported() {
+        let mut contract = setup_contract();
+        assert_eq!(
+            contract.can_redeem(food_bank(), "free_lunch".to_string()),
+            RedeemCheck::OptionNotFound
+        );
+        let mut option = contract.redemption_option(&"supplier_discount".to_string()).unwrap();
+        option.available = false;
+        contract.store_redemption_option(&"supplier_discount".to_string(), &option);
+        assert_eq!(check(&contract), RedeemCheck::OptionUnavailable);
+    }
+
+    #[test]
+    fn frozen_and_treasury_accounts_are_reported() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        contract.freeze_account(food_bank(), "Gaming contributions".to_string());
+        assert_eq!(check(&contract), RedeemCheck::AccountFrozen);
+
+        contract.set_treasury(Some(other_food_bank()), 0);
+        assert_eq!(
+            contract.can_redeem(other_food_bank(), "supplier_discount".to_string()),
+            RedeemCheck::TreasuryAccount
+        );
+    }
+
+    #[test]
+    fn amount_below_cost_is_reported() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        assert_eq!(
+            contract.check_redeemable(&food_bank(), &"supplier_discount".to_string(), Some(99), 0),
+            RedeemCheck::AmountBelowCost { cost: U128(100) }
+        );
+    }
+
+    #[test]
+    fn availability_window_is_enforced() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        advance_time(1_000);
+        limit(
+            &mut contract,
+            RedemptionLimits {
+                available_from: Some(2_000),
+                available_until: Some(3_000),
+                ..Default::default()
+            },
+        );
+        assert_eq!(check(&contract), RedeemCheck::NotInWindow);
+        advance_time(1_000);
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+        advance_time(1_000);
+        assert_eq!(check(&contract), RedeemCheck::NotInWindow);
+    }
+
+    #[test]
+    fn redemption_limit_is_shared_by_all_accounts() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        limit(
+            &mut contract,
+            RedemptionLimits {
+                max_redemptions: Some(2),
+                ..Default::default()
+            },
+        );
+        redeem(&mut contract);
+        redeem(&mut contract);
+        assert_eq!(check(&contract), RedeemCheck::LimitReached);
+    }
+
+    #[test]
+    #[should_panic(expected = "This redemption option has reached its limit")]
+    fn redeem_tokens_uses_the_same_checks() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        limit(
+            &mut contract,
+            RedemptionLimits {
+                max_redemptions: Some(1),
+                ..Default::default()
+            },
+        );
+        redeem(&mut contract);
+        redeem(&mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "Availability window is empty")]
+    fn empty_window_is_rejected() {
+        let mut contract = setup_contract();
+        limit(
+            &mut contract,
+            RedemptionLimits {
+                available_from: Some(10),
+                available_until: Some(10),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn eligibility(contract: &FoodBankToken, account_id: AccountId, option_id: &str) -> OptionEligibility {
+        contract
+            .get_affordable_options(account_id, 0, 10)
+            .into_iter()
+            .find(|(id, _, _)| id == option_id)
+            .unwrap()
+            .2
+    }
+
+    #[test]
+    fn per_account_limits_block_only_that_account() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 400);
+        contract.mint(other_food_bank(), 400);
+        limit(
+            &mut contract,
+            RedemptionLimits {
+                max_per_account: Some(1),
+                ..Default::default()
+            },
+        );
+        redeem(&mut contract);
+
+        assert_eq!(
+            eligibility(&contract, food_bank(), "supplier_discount"),
+            OptionEligibility {
+                price: U128(100),
+                affordable: true,
+                missing: U128(0),
+                blocked_by: Some("AccountLimitReached".to_string()),
+                available_at: None,
+            }
+        );
+        assert_eq!(eligibility(&contract, food_bank(), "analytics_access").blocked_by, None);
+        assert_eq!(
+            eligibility(&contract, food_bank(), "grant_opportunity"),
+            OptionEligibility {
+                price: U128(500),
+                affordable: false,
+                missing: U128(200),
+                blocked_by: None,
+                available_at: None,
+            }
+        );
+        assert_eq!(eligibility(&contract, other_food_bank(), "supplier_discount").blocked_by, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "This account has reached its limit for this redemption option")]
+    fn redeem_tokens_enforces_per_account_limits() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        limit(
+            &mut contract,
+            RedemptionLimits {
+                max_per_account: Some(1),
+                ..Default::default()
+            },
+        );
+        redeem(&mut contract);
+        redeem(&mut contract);
+    }
+
+    #[test]
+    fn refunds_free_the_account_slot() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        limit(
+            &mut contract,
+            RedemptionLimits {
+                max_per_account: Some(1),
+                ..Default::default()
+            },
+        );
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Failed);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+    }
+
+    #[test]
+    fn affordable_options_are_paginated() {
+        let contract = setup_contract();
+        assert_eq!(contract.get_affordable_options(food_bank(), 0, 2).len(), 2);
+        assert_eq!(contract.get_affordable_options(food_bank(), 2, 10).len(), 1);
+    }
+
+    fn require(contract: &mut FoodBankToken, option_id: &str, min: Option<u64>, prerequisite: Option<&str>) {
+        set_caller(owner(), 0);
+        contract.set_option_eligibility(option_id.to_string(), min, prerequisite.map(str::to_string));
+    }
+
+    fn redeem_analytics(contract: &mut FoodBankToken) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("analytics_access".to_string(), U128(200), None);
+    }
+
+    #[test]
+    fn exactly_meeting_min_contributions_is_enough() {
+        let mut contract = setup_contract();
+        require(&mut contract, "grant_opportunity", Some(5), None);
+        for _ in 0..4 {
+            contribute(&mut contract, &food_bank(), 100);
+        }
+        contract.mint(food_bank(), 500);
+        assert_eq!(
+            contract.can_redeem(food_bank(), "grant_opportunity".to_string()),
+            RedeemCheck::NotEnoughContributions { required: 5, recorded: 4 }
+        );
+        assert_eq!(
+            eligibility(&contract, food_bank(), "grant_opportunity").blocked_by.as_deref(),
+            Some("NotEnoughContributions")
+        );
+
+        contribute(&mut contract, &food_bank(), 100);
+        assert!(matches!(
+            contract.can_redeem(food_bank(), "grant_opportunity".to_string()),
+            RedeemCheck::Ok { .. }
+        ));
+    }
+
+    #[test]
+    fn voided_contributions_do_not_count_towards_the_minimum() {
+        let mut contract = setup_contract();
+        require(&mut contract, "grant_opportunity", Some(2), None);
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 100);
+        contract.void_contribution(food_bank(), 0);
+        contract.mint(food_bank(), 500);
+        assert_eq!(
+            contract.can_redeem(food_bank(), "grant_opportunity".to_string()),
+            RedeemCheck::NotEnoughContributions { required: 2, recorded: 1 }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "This redemption option requires a prior supplier_discount redemption")]
+    fn missing_prerequisite_blocks_redemption() {
+        let mut contract = setup_contract();
+        require(&mut contract, "analytics_access", None, Some("supplier_discount"));
+        contract.mint(food_bank(), 300);
+        assert_eq!(
+            eligibility(&contract, food_bank(), "analytics_access").blocked_by.as_deref(),
+            Some("PrerequisiteMissing")
+        );
+        redeem_analytics(&mut contract);
+    }
+
+    #[test]
+    fn refunded_prerequisite_redemptions_do_not_count() {
+        let mut contract = setup_contract();
+        require(&mut contract, "analytics_access", None, Some("supplier_discount"));
+        contract.mint(food_bank(), 300);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Failed);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+        assert_eq!(
+            contract.can_redeem(food_bank(), "analytics_access".to_string()),
+            RedeemCheck::PrerequisiteMissing {
+                option_id: "supplier_discount".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn prerequisite_redemptions_still_in_flight_do_not_count() {
+        let mut contract = setup_contract();
+        require(&mut contract, "analytics_access", None, Some("supplier_discount"));
+        contract.mint(food_bank(), 300);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        let missing = RedeemCheck::PrerequisiteMissing {
+            option_id: "supplier_discount".to_string(),
+        };
+        assert_eq!(contract.can_redeem(food_bank(), "analytics_access".to_string()), missing);
+
+        resolve_callback(PromiseResult::Successful(vec![]));
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+        assert!(matches!(
+            contract.can_redeem(food_bank(), "analytics_access".to_string()),
+            RedeemCheck::Ok { .. }
+        ));
+    }
+
+    #[test]
+    fn disputed_prerequisite_redemptions_do_not_count() {
+        let mut contract = setup_contract();
+        require(&mut contract, "analytics_access", None, Some("supplier_discount"));
+        contract.mint(food_bank(), 300);
+        redeem(&mut contract);
+        let receipt_id = last_receipt_id(&contract);
+        set_caller(food_bank(), 0);
+        contract.report_unfulfilled(receipt_id, "Never arrived".to_string());
+        assert_eq!(
+            contract.can_redeem(food_bank(), "analytics_access".to_string()),
+            RedeemCheck::PrerequisiteMissing {
+                option_id: "supplier_discount".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn confirmed_prerequisite_redemptions_unlock_the_option() {
+        let mut contract = setup_contract();
+        require(&mut contract, "analytics_access", None, Some("supplier_discount"));
+        contract.mint(food_bank(), 300);
+        redeem(&mut contract);
+        set_caller(food_bank(), 0);
+        contract.confirm_fulfillment(last_receipt_id(&contract));
+
+        redeem_analytics(&mut contract);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Prerequisite option free_lunch not found")]
+    fn prerequisites_must_exist() {
+        let mut contract = setup_contract();
+        require(&mut contract, "analytics_access", None, Some("free_lunch"));
+    }
+
+    #[test]
+    #[should_panic(expected = "An option cannot be its own prerequisite")]
+    fn options_cannot_require_themselves() {
+        let mut contract = setup_contract();
+        require(&mut contract, "analytics_access", None, Some("analytics_access"));
+    }
+
+    const COOLDOWN: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+    fn with_cooldown() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_option_cooldown("supplier_discount".to_string(), Some(COOLDOWN));
+        contract.mint(food_bank(), 400);
+        contract
+    }
+
+    #[test]
+    fn supplier_discount_cools_down_for_thirty_days_by_default() {
+        set_caller(owner(), 0);
+        let contract = FoodBankToken::new_default(owner(), U128(0));
+        let option = contract.redemption_option(&"supplier_discount".to_string()).unwrap();
+        assert_eq!(option.cooldown_ns, Some(COOLDOWN));
+    }
+
+    #[test]
+    fn cooldown_ends_exactly_at_its_boundary() {
+        let mut contract = with_cooldown();
+        let available_at = env::block_timestamp() + COOLDOWN;
+        redeem(&mut contract);
+        assert_eq!(check(&contract), RedeemCheck::CooldownActive { available_at });
+        let cooling = eligibility(&contract, food_bank(), "supplier_discount");
+        assert_eq!(cooling.blocked_by.as_deref(), Some("CooldownActive"));
+        assert_eq!(cooling.available_at, Some(available_at));
+        assert_eq!(eligibility(&contract, other_food_bank(), "supplier_discount").available_at, None);
+
+        advance_time(COOLDOWN - 1);
+        assert_eq!(check(&contract), RedeemCheck::CooldownActive { available_at });
+        advance_time(1);
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+        redeem(&mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "This redemption option is available again at 2592000000000000")]
+    fn redeem_tokens_enforces_the_cooldown() {
+        let mut contract = with_cooldown();
+        redeem(&mut contract);
+        redeem(&mut contract);
+    }
+
+    #[test]
+    fn refunded_redemptions_reset_the_cooldown() {
+        let mut contract = with_cooldown();
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Failed);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+
+        redeem(&mut contract);
+        set_caller(food_bank(), 0);
+        contract.report_unfulfilled(last_receipt_id(&contract), "Never arrived".to_string());
+        set_caller(owner(), 0);
+        contract.resolve_fulfillment(last_receipt_id(&contract), true);
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+    }
+
+    #[test]
+    fn a_late_refund_keeps_a_newer_cooldown() {
+        let mut contract = with_cooldown();
+        redeem(&mut contract);
+        let disputed = last_receipt_id(&contract);
+        set_caller(food_bank(), 0);
+        contract.report_unfulfilled(disputed, "Never arrived".to_string());
+
+        advance_time(COOLDOWN);
+        let available_at = env::block_timestamp() + COOLDOWN;
+        redeem(&mut contract);
+        set_caller(owner(), 0);
+        contract.resolve_fulfillment(disputed, true);
+        assert_eq!(check(&contract), RedeemCheck::CooldownActive { available_at });
+    }
+
+    #[test]
+    fn cooldown_changes_apply_to_later_checks() {
+        let mut contract = with_cooldown();
+        redeem(&mut contract);
+        set_caller(owner(), 0);
+        contract.set_option_cooldown("supplier_discount".to_string(), Some(COOLDOWN / 2));
+        advance_time(COOLDOWN / 2);
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+        contract.set_option_cooldown("supplier_discount".to_string(), None);
+        redeem(&mut contract);
+        assert_eq!(check(&contract), RedeemCheck::Ok { cost: U128(100) });
+    }
+
+    /// Panic message of redeeming `option_id` with `deposit` and `tgas`,
+    /// or `None` if the redemption went through.
+    fn redeem_with(contract: &mut FoodBankToken, option_id: &str, deposit: Balance, tgas: u64) -> Option<String> {
+        testing_env!(context(food_bank(), deposit)
+            .prepaid_gas(Gas(tgas * Gas::ONE_TERA.0))
+            .build());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.redeem_tokens(option_id.to_string(), U128(100), None);
+        }));
+        result.err().map(|panic| *panic.downcast::<String>().unwrap())
+    }
+
+    /// `redeem_tokens` turns down a yoctoNEAR short of a co-pay and, with
+    /// `gas_enforced`, a TGas short of the estimate, and accepts the
+    /// estimated deposit. Short of one yocto aborts the mock instead of
+    /// panicking, and the mock's gas costs aren't the chain's, so the exact
+    /// gas is left to `redeem_estimate_is_enough_to_redeem` in the sandbox
+    /// suite, which can't run until the tree has manifests.
+    fn assert_estimate_is_enforced(contract: &mut FoodBankToken, option_id: &str, gas_enforced: bool) {
+        let estimate = contract.estimate_redeem(food_bank(), option_id.to_string());
+        let (deposit, tgas) = (estimate.required_deposit.0, estimate.min_gas_tgas);
+        if deposit > 1 {
+            let message = redeem_with(contract, option_id, deposit - 1, tgas).unwrap();
+            assert!(message.contains("to cover the co-pay"), "{}", message);
+        }
+        if gas_enforced {
+            let message = redeem_with(contract, option_id, deposit, tgas - 1).unwrap();
+            assert!(message.contains(&format!("Attach at least {} TGas", tgas)), "{}", message);
+        }
+        assert_eq!(redeem_with(contract, option_id, deposit, 300), None);
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+    }
+
+    #[test]
+    fn token_only_options_take_one_yocto() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 200);
+        set_caller(owner(), ONE_NEAR);
+        contract.add_redemption_option(
+            "tote_bags".to_string(),
+            "Tote bags".to_string(),
+            U128(100),
+            "Branded tote bags".to_string(),
+            None,
+        );
+        let estimate = contract.estimate_redeem(food_bank(), "tote_bags".to_string());
+        assert_eq!(estimate.required_deposit, U128(1));
+        assert_eq!(estimate.min_gas_tgas, whole_tgas(GAS_RESERVED_FOR_REDEEM));
+        assert_eq!(estimate.token_cost, U128(100));
+        assert_eq!(estimate.check, RedeemCheck::Ok { cost: U128(100) });
+        assert_estimate_is_enforced(&mut contract, "tote_bags", false);
+    }
+
+    #[test]
+    fn benefit_options_need_the_benefit_and_resolver_gas() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 200);
+        let estimate = contract.estimate_redeem(food_bank(), "supplier_discount".to_string());
+        assert_eq!(estimate.required_deposit, U128(1));
+        assert_eq!(estimate.min_gas_tgas, 45);
+        assert_estimate_is_enforced(&mut contract, "supplier_discount", true);
+    }
+
+    #[test]
+    fn copay_and_benefit_gas_settings_are_reflected() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 200);
+        contract.set_benefit_gas("supplier_discount".to_string(), Gas(50 * Gas::ONE_TERA.0 + 1));
+        contract.set_option_near_copay("supplier_discount".to_string(), Some(U128(ONE_NEAR)));
+        let estimate = contract.estimate_redeem(food_bank(), "supplier_discount".to_string());
+        assert_eq!(estimate.required_deposit, U128(ONE_NEAR));
+        assert_eq!(estimate.min_gas_tgas, 76);
+        assert_estimate_is_enforced(&mut contract, "supplier_discount", true);
+    }
+
+    #[test]
+    fn estimates_carry_the_eligibility_check() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 40);
+        let estimate = contract.estimate_redeem(food_bank(), "supplier_discount".to_string());
+        assert_eq!(estimate.token_cost, U128(100));
+        assert_eq!(estimate.check, RedeemCheck::InsufficientBalance { missing: U128(60) });
+
+        let missing = contract.estimate_redeem(food_bank(), "no_such_option".to_string());
+        assert_eq!(missing.required_deposit, U128(1));
+        assert_eq!(missing.token_cost, U128(0));
+        assert_eq!(missing.check, RedeemCheck::OptionNotFound);
+    }
+}
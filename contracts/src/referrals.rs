@@ -0,0 +1,209 @@
+//! Referral bonuses paid to the partner who recruited a food bank, once its
+//! first reward is minted.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, ReferralRewarded};
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_REFERRAL_BONUS_BPS: u16 = 2_000;
+pub const DEFAULT_REFERRAL_BONUS_CAP: Balance = 100;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Referral {
+    referrer: AccountId,
+    bonus_paid: bool,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Names the caller's referrer. Only possible once, before the caller's
+    /// first contribution is recorded.
+    pub fn set_referrer(&mut self, referrer: AccountId) {
+        self.assert_not_paused();
+        self.internal_set_referrer(env::predecessor_account_id(), referrer);
+    }
+
+    pub fn set_referrer_for(&mut self, account_id: AccountId, referrer: AccountId) {
+        self.assert_owner_action();
+        self.internal_set_referrer(account_id, referrer);
+    }
+
+    pub fn set_referral_config(&mut self, bonus_bps: u16, bonus_cap: U128) {
+        self.assert_owner_action();
+        assert!(bonus_bps <= 10_000, "Referral bonus must be <= 10000 bps");
+        self.referral_bonus_bps = bonus_bps;
+        self.referral_bonus_cap = bonus_cap.0;
+    }
+
+    // View functions
+    pub fn get_referrer(&self, account_id: AccountId) -> Option<AccountId> {
+        self.referrals.get(&account_id).map(|referral| referral.referrer)
+    }
+
+    pub fn get_referrals(&self, referrer: AccountId, from_index: u64, limit: u64) -> Vec<AccountId> {
+        self.referees
+            .get(&referrer)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    pub fn get_referral_config(&self) -> (u16, U128) {
+        (self.referral_bonus_bps, U128(self.referral_bonus_cap))
+    }
+}
+
+impl FoodBankToken {
+    fn internal_set_referrer(&mut self, account_id: AccountId, referrer: AccountId) {
+        assert!(account_id != referrer, "Cannot refer yourself");
+        assert!(
+            self.account_stats.get(&account_id).is_none(),
+            "Referrer cannot be set after the first contribution"
+        );
+        assert!(self.referrals.get(&account_id).is_none(), "Referrer is already set");
+        // Chains are acyclic, so walking up from the referrer terminates.
+        let mut ancestor = Some(referrer.clone());
+        while let Some(current) = ancestor {
+            assert!(current != account_id, "Referral cycles are not allowed");
+            ancestor = self.get_referrer(current);
+        }
+
+        self.referrals.insert(
+            &account_id,
+            &Referral {
+                referrer: referrer.clone(),
+                bonus_paid: false,
+            },
+        );
+        let mut referees = self.referees.get(&referrer).unwrap_or_default();
+        referees.push(account_id);
+        self.referees.insert(&referrer, &referees);
+    }
+
+    /// Pays the referrer of `account_id` once, on its first non-zero reward,
+    /// at most `limit`.
+    pub(crate) fn mint_referral_bonus(&mut self, account_id: &AccountId, reward: Balance, limit: Balance) {
+        if reward == 0 {
+            return;
+        }
+        let Some(mut referral) = self.referrals.get(account_id) else {
+            return;
+        };
+        if referral.bonus_paid {
+            return;
+        }
+        referral.bonus_paid = true;
+        self.referrals.insert(account_id, &referral);
+
+        let bonus = (reward * Balance::from(self.referral_bonus_bps) / 10_000)
+            .min(self.referral_bonus_cap)
+            .min(limit);
+        if bonus > 0 {
+            ReferralRewarded {
+                referrer: &referral.referrer,
+                account_id,
+                amount: U128(bonus),
+            }
+            .emit();
+            self.mint(referral.referrer.clone(), bonus);
+            self.tag_statement_flow(&referral.referrer, StatementFlow::Reward, bonus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+
+    fn refer(contract: &mut FoodBankToken, account_id: AccountId, referrer: AccountId) {
+        set_caller(account_id, 0);
+        contract.set_referrer(referrer);
+    }
+
+    #[test]
+    fn referrer_is_paid_once_on_the_first_reward() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        refer(&mut contract, food_bank(), other_food_bank());
+
+        // No reward below the threshold, so no bonus yet.
+        contribute(&mut contract, &food_bank(), 5);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 0);
+
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 20);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 20);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 220);
+    }
+
+    #[test]
+    fn bonus_is_capped() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_referral_config(5_000, U128(30));
+        refer(&mut contract, food_bank(), other_food_bank());
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 30);
+    }
+
+    #[test]
+    fn referrals_are_listed_per_referrer() {
+        let mut contract = setup_contract();
+        refer(&mut contract, food_bank(), other_food_bank());
+        refer(&mut contract, accounts(3), other_food_bank());
+        assert_eq!(contract.get_referrer(food_bank()), Some(other_food_bank()));
+        assert_eq!(
+            contract.get_referrals(other_food_bank(), 0, 10),
+            vec![food_bank(), accounts(3)]
+        );
+        assert_eq!(contract.get_referrals(other_food_bank(), 1, 10), vec![accounts(3)]);
+    }
+
+    #[test]
+    fn owner_can_set_a_referrer() {
+        let mut contract = setup_contract();
+        contract.set_referrer_for(food_bank(), other_food_bank());
+        assert_eq!(contract.get_referrer(food_bank()), Some(other_food_bank()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot refer yourself")]
+    fn self_referral_is_rejected() {
+        let mut contract = setup_contract();
+        refer(&mut contract, food_bank(), food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Referral cycles are not allowed")]
+    fn referral_cycle_is_rejected() {
+        let mut contract = setup_contract();
+        refer(&mut contract, food_bank(), other_food_bank());
+        refer(&mut contract, accounts(3), food_bank());
+        refer(&mut contract, other_food_bank(), accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Referrer cannot be set after the first contribution")]
+    fn referrer_is_fixed_after_the_first_contribution() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        refer(&mut contract, food_bank(), other_food_bank());
+    }
+
+    #[test]
+    #[should_panic(expected = "Referrer is already set")]
+    fn referrer_can_only_be_set_once() {
+        let mut contract = setup_contract();
+        refer(&mut contract, food_bank(), other_food_bank());
+        refer(&mut contract, food_bank(), accounts(3));
+    }
+}
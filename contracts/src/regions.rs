@@ -0,0 +1,182 @@
+//! Per-region aggregates for profiled accounts.
+//!
+//! Activity counts towards the region on the account's profile at the time
+//! it lands. When an account changes region its history stays where it was
+//! recorded; only its participant count moves.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, Balance};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RegionStats {
+    /// Profiles currently in the region.
+    pub participant_count: u64,
+    pub total_contributions: u64,
+    pub total_rewards_minted: U128,
+    pub total_redeemed: U128,
+}
+
+impl Default for RegionStats {
+    fn default() -> Self {
+        Self {
+            participant_count: 0,
+            total_contributions: 0,
+            total_rewards_minted: U128(0),
+            total_redeemed: U128(0),
+        }
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn get_region_stats(&self, region: String) -> Option<RegionStats> {
+        self.region_stats.get(&region)
+    }
+
+    pub fn get_all_region_stats(&self, from_index: u64, limit: u64) -> Vec<(String, RegionStats)> {
+        self.region_stats
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    fn update_region(&mut self, region: &String, update: impl FnOnce(&mut RegionStats)) {
+        let mut stats = self.region_stats.get(region).unwrap_or_default();
+        update(&mut stats);
+        self.region_stats.insert(region, &stats);
+    }
+
+    /// Moves an account's participant count from `from` to `to`.
+    pub(crate) fn move_region_participant(&mut self, from: Option<&String>, to: Option<&String>) {
+        if from == to {
+            return;
+        }
+        if let Some(region) = from {
+            self.update_region(region, |stats| stats.participant_count -= 1);
+        }
+        if let Some(region) = to {
+            self.update_region(region, |stats| stats.participant_count += 1);
+        }
+    }
+
+    fn profile_region(&self, account_id: &AccountId) -> Option<String> {
+        self.profiles.get(account_id).map(|profile| profile.region)
+    }
+
+    pub(crate) fn record_region_contribution(&mut self, account_id: &AccountId, reward: Balance) {
+        if let Some(region) = self.profile_region(account_id) {
+            self.update_region(&region, |stats| {
+                stats.total_contributions += 1;
+                stats.total_rewards_minted = U128(stats.total_rewards_minted.0 + reward);
+            });
+        }
+    }
+
+    /// Reward paid after the contribution itself was counted.
+    pub(crate) fn record_region_reward(&mut self, account_id: &AccountId, reward: Balance) {
+        if let Some(region) = self.profile_region(account_id) {
+            self.update_region(&region, |stats| {
+                stats.total_rewards_minted = U128(stats.total_rewards_minted.0 + reward);
+            });
+        }
+    }
+
+    pub(crate) fn record_region_redemption(&mut self, account_id: &AccountId, amount: Balance) {
+        if let Some(region) = self.profile_region(account_id) {
+            self.update_region(&region, |stats| {
+                stats.total_redeemed = U128(stats.total_redeemed.0 + amount);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::OrgType;
+    use crate::test_utils::*;
+    use near_sdk::PromiseResult;
+
+    fn register(contract: &mut FoodBankToken, account_id: AccountId, region: &str) {
+        set_caller(account_id, ONE_NEAR);
+        contract.register_profile("Food Bank".to_string(), region.to_string(), OrgType::FoodBank, None);
+    }
+
+    fn stats(contract: &FoodBankToken, region: &str) -> RegionStats {
+        contract.get_region_stats(region.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn contributions_and_redemptions_count_towards_the_region() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        register(&mut contract, food_bank(), "Lagos");
+        register(&mut contract, other_food_bank(), "Lagos");
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &other_food_bank(), 50);
+
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(&contract),
+            token_charge(100),
+        );
+
+        assert_eq!(
+            stats(&contract, "Lagos"),
+            RegionStats {
+                participant_count: 2,
+                total_contributions: 2,
+                total_rewards_minted: U128(150),
+                total_redeemed: U128(100),
+            }
+        );
+    }
+
+    #[test]
+    fn activity_before_registration_is_not_counted() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        register(&mut contract, food_bank(), "Lagos");
+        assert_eq!(stats(&contract, "Lagos").total_contributions, 0);
+        assert_eq!(stats(&contract, "Lagos").participant_count, 1);
+    }
+
+    #[test]
+    fn changing_region_counts_from_the_change_forward() {
+        let mut contract = setup_contract();
+        register(&mut contract, food_bank(), "Lagos");
+        contribute(&mut contract, &food_bank(), 100);
+        set_caller(food_bank(), ONE_NEAR);
+        contract.update_profile(None, Some("Abuja".to_string()), None);
+        contribute(&mut contract, &food_bank(), 100);
+
+        let lagos = stats(&contract, "Lagos");
+        assert_eq!(lagos.participant_count, 0);
+        assert_eq!(lagos.total_contributions, 1);
+        let abuja = stats(&contract, "Abuja");
+        assert_eq!(abuja.participant_count, 1);
+        assert_eq!(abuja.total_contributions, 1);
+        assert_eq!(contract.get_all_region_stats(0, 10).len(), 2);
+    }
+
+    #[test]
+    fn clearing_a_profile_leaves_the_region() {
+        let mut contract = setup_contract();
+        register(&mut contract, food_bank(), "Lagos");
+        set_caller(food_bank(), 0);
+        contract.clear_profile();
+        assert_eq!(stats(&contract, "Lagos").participant_count, 0);
+    }
+}
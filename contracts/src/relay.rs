@@ -0,0 +1,407 @@
+//! Relayed calls for food banks without NEAR for gas. A food bank registers
+//! an ed25519 signing key once, with an ordinary transaction; after that it
+//! signs a Borsh-serialized `RelayPayload` off-chain and anyone can submit
+//! it with `relay_call`, which runs the named method as that food bank.
+//! Only the methods in `RELAYABLE_METHODS` can be relayed, none of which
+//! move tokens out of the account. Nonces must rise with every call, so a
+//! payload runs at most once, even if the key is registered again later.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::Deserialize;
+use near_sdk::{env, near_bindgen, serde_json, AccountId, CurveType, PublicKey};
+
+use crate::attestations::verify_signature;
+use crate::events::{CallRelayed, Event, RelayKeyRegistered, RelayKeyRevoked};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const RELAYABLE_METHODS: [&str; 4] = [
+    "acknowledge_contribution",
+    "reject_contribution",
+    "confirm_fulfillment",
+    "report_unfulfilled",
+];
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RelayPayload {
+    pub method: String,
+    /// SHA-256 of the JSON arguments passed to `relay_call`.
+    pub args_hash: [u8; 32],
+    /// Must be above the last nonce relayed for the account.
+    pub nonce: u64,
+    /// Accepted strictly before this timestamp.
+    pub expires_at: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct ContributionArgs {
+    contribution_id: u64,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct ReceiptArgs {
+    receipt_id: u64,
+    reason: Option<String>,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Lets `public_key` sign relayed calls for the caller. The attached
+    /// deposit pays for the storage.
+    #[payable]
+    pub fn register_relay_key(&mut self, public_key: PublicKey) {
+        let initial_storage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        assert!(
+            public_key.curve_type() == CurveType::ED25519,
+            "Relay key must be an ed25519 key"
+        );
+        assert!(
+            self.relay_keys.get(&public_key).is_none(),
+            "Relay key is already registered"
+        );
+        self.relay_keys.insert(&public_key, &account_id);
+        // Relayed calls then only update the nonce, adding no storage.
+        if self.relay_nonces.get(&account_id).is_none() {
+            self.relay_nonces.insert(&account_id, &0);
+        }
+        RelayKeyRegistered {
+            account_id: &account_id,
+            public_key: &public_key,
+        }
+        .emit();
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Callable by the account the key signs for, or by the owner when the
+    /// key is compromised.
+    pub fn revoke_relay_key(&mut self, public_key: PublicKey) {
+        let actor = env::predecessor_account_id();
+        let account_id = self.relay_keys.get(&public_key).expect("Relay key is not registered");
+        assert!(
+            actor == account_id || actor == self.owner,
+            "Only the key's account or the owner can revoke it"
+        );
+        self.relay_keys.remove(&public_key);
+        RelayKeyRevoked {
+            actor: &actor,
+            account_id: &account_id,
+            public_key: &public_key,
+        }
+        .emit();
+    }
+
+    /// Runs `payload.method` with `args` as `account_id`, if `signature` is
+    /// the signature over `payload` by a relay key `account_id` registered.
+    pub fn relay_call(
+        &mut self,
+        account_id: AccountId,
+        public_key: PublicKey,
+        payload: Base64VecU8,
+        signature: Base64VecU8,
+        args: String,
+    ) {
+        assert!(
+            self.relay_keys.get(&public_key).as_ref() == Some(&account_id),
+            "Relay key is not registered for {}",
+            account_id
+        );
+        let signature: [u8; 64] = signature.0.try_into().expect("Signature must be 64 bytes");
+        assert!(
+            verify_signature(&signature, &payload.0, &public_key),
+            "Invalid relay signature"
+        );
+        let call = RelayPayload::try_from_slice(&payload.0).expect("Invalid relay payload");
+        assert!(env::block_timestamp() < call.expires_at, "Relayed call has expired");
+        assert!(
+            env::sha256(args.as_bytes()) == call.args_hash,
+            "Arguments do not match the signed hash"
+        );
+        let last_nonce = self.relay_nonces.get(&account_id).unwrap_or(0);
+        assert!(call.nonce > last_nonce, "Relay nonce must be above {}", last_nonce);
+        self.relay_nonces.insert(&account_id, &call.nonce);
+        CallRelayed {
+            account_id: &account_id,
+            method: &call.method,
+            nonce: call.nonce,
+            relayer: &env::predecessor_account_id(),
+        }
+        .emit();
+
+        match call.method.as_str() {
+            "acknowledge_contribution" => {
+                let args: ContributionArgs = parse_args(&args);
+                self.internal_acknowledge_contribution(&account_id, args.contribution_id);
+            }
+            "reject_contribution" => {
+                let args: ContributionArgs = parse_args(&args);
+                let reason = args.reason.expect("Missing reason");
+                self.internal_reject_contribution(&account_id, args.contribution_id, reason);
+            }
+            "confirm_fulfillment" => {
+                let args: ReceiptArgs = parse_args(&args);
+                self.internal_confirm_fulfillment(&account_id, args.receipt_id);
+            }
+            "report_unfulfilled" => {
+                let args: ReceiptArgs = parse_args(&args);
+                let reason = args.reason.expect("Missing reason");
+                self.internal_report_unfulfilled(&account_id, args.receipt_id, reason);
+            }
+            method => panic!("Method {} cannot be relayed", method),
+        }
+    }
+
+    // View functions
+    pub fn get_relay_key_account(&self, public_key: PublicKey) -> Option<AccountId> {
+        self.relay_keys.get(&public_key)
+    }
+
+    /// 0 before the account's first relayed call.
+    pub fn get_relay_nonce(&self, account_id: AccountId) -> u64 {
+        self.relay_nonces.get(&account_id).unwrap_or(0)
+    }
+
+    pub fn get_relayable_methods(&self) -> Vec<String> {
+        RELAYABLE_METHODS.iter().map(|method| method.to_string()).collect()
+    }
+}
+
+fn parse_args<'a, T: Deserialize<'a>>(args: &'a str) -> T {
+    serde_json::from_str(args).expect("Invalid relayed arguments")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acknowledgments::AcknowledgmentStatus;
+    use crate::fulfillment::FulfillmentStatus;
+    use crate::test_utils::*;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use near_sdk::json_types::U128;
+    use near_sdk::PromiseResult;
+
+    const HOUR: u64 = 60 * 60 * 1_000_000_000;
+
+    fn signer(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    fn public_key(keypair: &Keypair) -> PublicKey {
+        let mut bytes = vec![CurveType::ED25519 as u8];
+        bytes.extend_from_slice(keypair.public.as_bytes());
+        PublicKey::try_from(bytes).unwrap()
+    }
+
+    /// `food_bank` with a registered relay key, and one contribution whose
+    /// reward is held for its acknowledgment.
+    fn relaying(keypair: &Keypair) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_acknowledgment_mode(true, HOUR);
+        contribute(&mut contract, &food_bank(), 100);
+        set_caller(food_bank(), ONE_NEAR);
+        contract.register_relay_key(public_key(keypair));
+        contract
+    }
+
+    fn signed(keypair: &Keypair, method: &str, args: &str, nonce: u64) -> (Base64VecU8, Base64VecU8) {
+        let payload = borsh::to_vec(&RelayPayload {
+            method: method.to_string(),
+            args_hash: env::sha256(args.as_bytes()).try_into().unwrap(),
+            nonce,
+            expires_at: env::block_timestamp() + HOUR,
+        })
+        .unwrap();
+        let signature = keypair.sign(&payload).to_bytes().to_vec();
+        (Base64VecU8(payload), Base64VecU8(signature))
+    }
+
+    fn relay(contract: &mut FoodBankToken, keypair: &Keypair, method: &str, args: &str, nonce: u64) {
+        let (payload, signature) = signed(keypair, method, args, nonce);
+        set_caller(other_food_bank(), 0);
+        contract.relay_call(food_bank(), public_key(keypair), payload, signature, args.to_string());
+    }
+
+    const ACKNOWLEDGE: &str = r#"{"contribution_id":0}"#;
+
+    #[test]
+    fn a_relayed_acknowledgment_pays_the_food_bank() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        relay(&mut contract, &keypair, "acknowledge_contribution", ACKNOWLEDGE, 1);
+        assert_eq!(contract.get_balance(food_bank()).0, 5);
+        assert_eq!(
+            contract.get_acknowledgment(food_bank(), 0).unwrap().status,
+            AcknowledgmentStatus::Acknowledged
+        );
+        assert_eq!(contract.get_relay_nonce(food_bank()), 1);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 0);
+    }
+
+    #[test]
+    fn a_relayed_confirmation_settles_the_receipt() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let receipt_id = last_receipt_id(&contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            receipt_id,
+            token_charge(100),
+        );
+
+        let args = format!(r#"{{"receipt_id":{}}}"#, receipt_id);
+        relay(&mut contract, &keypair, "confirm_fulfillment", &args, 1);
+        assert_eq!(contract.get_receipt(receipt_id).unwrap().status, FulfillmentStatus::Confirmed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relay nonce must be above 1")]
+    fn payloads_cannot_be_replayed() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        let (payload, signature) = signed(&keypair, "acknowledge_contribution", ACKNOWLEDGE, 1);
+        set_caller(other_food_bank(), 0);
+        contract.relay_call(
+            food_bank(),
+            public_key(&keypair),
+            payload.clone(),
+            signature.clone(),
+            ACKNOWLEDGE.to_string(),
+        );
+        contract.relay_call(food_bank(), public_key(&keypair), payload, signature, ACKNOWLEDGE.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Relay nonce must be above 5")]
+    fn nonces_must_rise() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        contribute(&mut contract, &food_bank(), 100);
+        relay(&mut contract, &keypair, "acknowledge_contribution", ACKNOWLEDGE, 5);
+        relay(&mut contract, &keypair, "acknowledge_contribution", r#"{"contribution_id":1}"#, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relay key is not registered for bob")]
+    fn revoked_keys_are_rejected() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        set_caller(food_bank(), 0);
+        contract.revoke_relay_key(public_key(&keypair));
+        assert!(contract.get_relay_key_account(public_key(&keypair)).is_none());
+        relay(&mut contract, &keypair, "acknowledge_contribution", ACKNOWLEDGE, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relay nonce must be above 1")]
+    fn registering_a_key_again_does_not_reopen_old_payloads() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        let (payload, signature) = signed(&keypair, "acknowledge_contribution", ACKNOWLEDGE, 1);
+        set_caller(other_food_bank(), 0);
+        contract.relay_call(
+            food_bank(),
+            public_key(&keypair),
+            payload.clone(),
+            signature.clone(),
+            ACKNOWLEDGE.to_string(),
+        );
+        set_caller(food_bank(), 0);
+        contract.revoke_relay_key(public_key(&keypair));
+        set_caller(food_bank(), ONE_NEAR);
+        contract.register_relay_key(public_key(&keypair));
+        set_caller(other_food_bank(), 0);
+        contract.relay_call(food_bank(), public_key(&keypair), payload, signature, ACKNOWLEDGE.to_string());
+    }
+
+    #[test]
+    fn the_owner_can_revoke_a_compromised_key() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        set_caller(owner(), 0);
+        contract.revoke_relay_key(public_key(&keypair));
+        assert!(contract.get_relay_key_account(public_key(&keypair)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the key's account or the owner can revoke it")]
+    fn others_cannot_revoke_the_key() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        set_caller(other_food_bank(), 0);
+        contract.revoke_relay_key(public_key(&keypair));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid relay signature")]
+    fn payloads_signed_by_another_key_are_rejected() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        let (payload, signature) = signed(&signer(2), "acknowledge_contribution", ACKNOWLEDGE, 1);
+        set_caller(other_food_bank(), 0);
+        contract.relay_call(food_bank(), public_key(&keypair), payload, signature, ACKNOWLEDGE.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Relay key is not registered for charlie")]
+    fn keys_only_sign_for_their_account() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        let (payload, signature) = signed(&keypair, "acknowledge_contribution", ACKNOWLEDGE, 1);
+        contract.relay_call(
+            other_food_bank(),
+            public_key(&keypair),
+            payload,
+            signature,
+            ACKNOWLEDGE.to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Arguments do not match the signed hash")]
+    fn arguments_are_bound_to_the_signature() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        let (payload, signature) = signed(&keypair, "acknowledge_contribution", ACKNOWLEDGE, 1);
+        set_caller(other_food_bank(), 0);
+        let args = r#"{"contribution_id":1}"#.to_string();
+        contract.relay_call(food_bank(), public_key(&keypair), payload, signature, args);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relayed call has expired")]
+    fn expired_payloads_are_rejected() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        let (payload, signature) = signed(&keypair, "acknowledge_contribution", ACKNOWLEDGE, 1);
+        advance_time(HOUR);
+        set_caller(other_food_bank(), 0);
+        contract.relay_call(food_bank(), public_key(&keypair), payload, signature, ACKNOWLEDGE.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Method stake cannot be relayed")]
+    fn only_listed_methods_can_be_relayed() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        relay(&mut contract, &keypair, "stake", r#"{"amount":"5"}"#, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relay key is already registered")]
+    fn a_key_signs_for_one_account() {
+        let keypair = signer(1);
+        let mut contract = relaying(&keypair);
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.register_relay_key(public_key(&keypair));
+    }
+}
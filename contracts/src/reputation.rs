@@ -0,0 +1,175 @@
+//! Recency-weighted reputation: the sum of an account's recent contribution
+//! scores, each halved for every half-life that has passed since it was
+//! recorded, so old contributions fade out instead of counting forever.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::monthly::NANOS_PER_DAY;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_REPUTATION_HALF_LIFE: u64 = 90 * NANOS_PER_DAY;
+/// Reputation is fixed point: a fresh score of 100 is worth 100 000.
+pub const REPUTATION_SCALE: u64 = 1_000;
+/// Only the latest contributions count, keeping the view's gas bounded.
+const MAX_REPUTATION_ENTRIES: usize = 50;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CachedReputation {
+    pub value: u64,
+    pub computed_at: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn set_reputation_half_life(&mut self, half_life_ns: u64) {
+        self.assert_owner_action();
+        assert!(half_life_ns > 0, "Half-life must be positive");
+        self.reputation_half_life = half_life_ns;
+    }
+
+    // View functions
+    pub fn get_reputation_half_life(&self) -> u64 {
+        self.reputation_half_life
+    }
+
+    /// Reputation as of now, recomputed from the account's latest
+    /// contributions.
+    pub fn get_reputation(&self, account_id: AccountId) -> u64 {
+        self.compute_reputation(&account_id, env::block_timestamp())
+    }
+
+    /// The value cached at the account's last contribution, decayed to now.
+    /// Cheap enough for other contract logic to read.
+    pub fn get_cached_reputation(&self, account_id: AccountId) -> u64 {
+        self.reputations.get(&account_id).map_or(0, |cached| {
+            decay(
+                cached.value,
+                env::block_timestamp().saturating_sub(cached.computed_at),
+                self.reputation_half_life,
+            )
+        })
+    }
+}
+
+impl FoodBankToken {
+    fn compute_reputation(&self, account_id: &AccountId, now: u64) -> u64 {
+        let history = self.stored_contributions(account_id);
+        let skip = history.len().saturating_sub(MAX_REPUTATION_ENTRIES);
+        let archived = self.archived_contribution_count(account_id) as usize;
+        history
+            .into_iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(index, contribution)| (archived + index, contribution))
+            .filter(|(index, _)| !self.is_contribution_voided(account_id.clone(), *index as u64))
+            .map(|(index, contribution)| {
+                let score = u64::from(contribution.score());
+                let recorded_at = self
+                    .contribution_recorded_at
+                    .get(&(account_id.clone(), index as u64))
+                    .unwrap_or_else(|| contribution.into_latest().timestamp);
+                decay(
+                    score * REPUTATION_SCALE,
+                    now.saturating_sub(recorded_at),
+                    self.reputation_half_life,
+                )
+            })
+            .sum()
+    }
+
+    /// Recomputes the cached reputation after the account's history changed.
+    pub(crate) fn refresh_reputation(&mut self, account_id: &AccountId) {
+        let now = env::block_timestamp();
+        let cached = CachedReputation {
+            value: self.compute_reputation(account_id, now),
+            computed_at: now,
+        };
+        self.reputations.insert(account_id, &cached);
+    }
+}
+
+/// `value` halved once per whole `half_life` in `age`, interpolated
+/// linearly within the current half-life.
+fn decay(value: u64, age: u64, half_life: u64) -> u64 {
+    let halvings = age / half_life;
+    if halvings >= u64::from(u64::BITS) {
+        return 0;
+    }
+    let halved = value >> halvings;
+    let into_half_life = u128::from(age % half_life);
+    halved - (u128::from(halved) * into_half_life / (2 * u128::from(half_life))) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const HALF_LIFE: u64 = DEFAULT_REPUTATION_HALF_LIFE;
+
+    #[test]
+    fn scores_halve_every_half_life() {
+        let fresh = 100 * REPUTATION_SCALE;
+        assert_eq!(decay(fresh, 0, HALF_LIFE), 100_000);
+        assert_eq!(decay(fresh, HALF_LIFE, HALF_LIFE), 50_000);
+        assert_eq!(decay(fresh, 2 * HALF_LIFE, HALF_LIFE), 25_000);
+        assert_eq!(decay(fresh, HALF_LIFE / 2, HALF_LIFE), 75_000);
+        assert_eq!(decay(fresh, 64 * HALF_LIFE, HALF_LIFE), 0);
+    }
+
+    #[test]
+    fn reputation_sums_decayed_contributions() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_reputation(food_bank()), 100_000);
+
+        advance_time(HALF_LIFE);
+        contribute(&mut contract, &food_bank(), 60);
+        assert_eq!(contract.get_reputation(food_bank()), 110_000);
+
+        advance_time(HALF_LIFE);
+        assert_eq!(contract.get_reputation(food_bank()), 55_000);
+        assert_eq!(contract.get_reputation(other_food_bank()), 0);
+    }
+
+    #[test]
+    fn voided_contributions_do_not_count() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 60);
+        contract.void_contribution(food_bank(), 0);
+        assert_eq!(contract.get_reputation(food_bank()), 60_000);
+    }
+
+    #[test]
+    fn cache_is_refreshed_on_contribution_and_decays() {
+        let mut contract = setup_contract();
+        assert_eq!(contract.get_cached_reputation(food_bank()), 0);
+        contribute(&mut contract, &food_bank(), 80);
+        assert_eq!(contract.reputations.get(&food_bank()).unwrap().value, 80_000);
+
+        advance_time(2 * HALF_LIFE);
+        assert_eq!(contract.get_cached_reputation(food_bank()), 20_000);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_cached_reputation(food_bank()), 120_000);
+    }
+
+    #[test]
+    fn half_life_is_configurable() {
+        let mut contract = setup_contract();
+        contract.set_reputation_half_life(NANOS_PER_DAY);
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(NANOS_PER_DAY);
+        assert_eq!(contract.get_reputation(food_bank()), 50_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Half-life must be positive")]
+    fn zero_half_life_is_rejected() {
+        let mut contract = setup_contract();
+        contract.set_reputation_half_life(0);
+    }
+}
@@ -0,0 +1,150 @@
+//! How a contribution's score turns into a share of the full reward. Every
+//! curve pays the whole rate at a score of 100; they differ below it. The
+//! curve is replaced through a timelocked `ParamChange::RewardCurve`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, Balance};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_STEPS: usize = 10;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RewardCurve {
+    /// The score times the rate.
+    Linear,
+    /// Scaled by score² / 100, so excellent contributions earn most.
+    Quadratic,
+    /// `(min_score, payout_bps)` pairs in ascending order: a score pays
+    /// `payout_bps` of the full reward for the highest step it reaches,
+    /// and nothing below the first step.
+    Step(Vec<(u32, u16)>),
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    pub fn get_reward_curve(&self) -> RewardCurve {
+        self.reward_curve.clone()
+    }
+}
+
+impl RewardCurve {
+    pub(crate) fn validate(&self) {
+        if let RewardCurve::Step(steps) = self {
+            assert!(
+                !steps.is_empty() && steps.len() <= MAX_STEPS,
+                "A step curve needs 1 to {} steps",
+                MAX_STEPS
+            );
+            for (index, (min_score, payout_bps)) in steps.iter().enumerate() {
+                assert!(*min_score <= 100, "Step scores must be <= 100");
+                assert!(*payout_bps <= 10_000, "Step payouts must be <= 10000 bps");
+                assert!(
+                    index == 0 || steps[index - 1].0 < *min_score,
+                    "Steps must be sorted by score without overlapping"
+                );
+            }
+        }
+    }
+
+    /// The share of the full reward `score` earns, scaled so a score of 100
+    /// is worth 1 000 000.
+    pub(crate) fn points(&self, score: u32) -> Balance {
+        let score = Balance::from(score);
+        match self {
+            RewardCurve::Linear => score * 10_000,
+            RewardCurve::Quadratic => score * score * 100,
+            RewardCurve::Step(steps) => steps
+                .iter()
+                .rev()
+                .find(|(min_score, _)| score >= Balance::from(*min_score))
+                .map_or(0, |(_, payout_bps)| Balance::from(*payout_bps) * 100),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::timelock::{ParamChange, DEFAULT_TIMELOCK_DELAY};
+
+    const SCORES: [u32; 4] = [0, 10, 50, 100];
+
+    fn steps() -> RewardCurve {
+        RewardCurve::Step(vec![(10, 2_000), (50, 6_000), (90, 10_000)])
+    }
+
+    /// Rewards for `SCORES` under `curve` at a 100% rate with no threshold.
+    fn rewards(curve: RewardCurve) -> Vec<u128> {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.min_score_threshold = 0;
+        set_reward_curve(&mut contract, curve);
+        SCORES
+            .iter()
+            .map(|score| contract.calculate_reward(&food_bank(), *score, None).reward.0)
+            .collect()
+    }
+
+    #[test]
+    fn linear_pays_the_score_times_the_rate() {
+        assert_eq!(rewards(RewardCurve::Linear), vec![0, 10, 50, 100]);
+    }
+
+    #[test]
+    fn quadratic_favours_high_scores() {
+        assert_eq!(rewards(RewardCurve::Quadratic), vec![0, 1, 25, 100]);
+    }
+
+    #[test]
+    fn step_pays_the_reached_step() {
+        assert_eq!(rewards(steps()), vec![0, 20, 60, 100]);
+    }
+
+    #[test]
+    fn a_proposed_curve_waits_for_the_timelock() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(ParamChange::RewardCurve(RewardCurve::Quadratic));
+        assert_eq!(contract.get_reward_curve(), RewardCurve::Linear);
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_reward_curve(), RewardCurve::Quadratic);
+    }
+
+    #[test]
+    fn breakdown_records_the_curve() {
+        let mut contract = setup_contract();
+        set_reward_curve(&mut contract, RewardCurve::Quadratic);
+        contribute(&mut contract, &food_bank(), 80);
+        let breakdown = contract.get_reward_breakdown(food_bank(), 0).unwrap();
+        assert_eq!(breakdown.curve, RewardCurve::Quadratic);
+        // 80² / 100 at the default 5% rate.
+        assert_eq!(breakdown.reward.0, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Steps must be sorted by score without overlapping")]
+    fn unsorted_steps_are_rejected() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::RewardCurve(RewardCurve::Step(vec![(50, 5_000), (10, 1_000)])));
+    }
+
+    #[test]
+    #[should_panic(expected = "Steps must be sorted by score without overlapping")]
+    fn overlapping_steps_are_rejected() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::RewardCurve(RewardCurve::Step(vec![(10, 1_000), (10, 5_000)])));
+    }
+
+    #[test]
+    #[should_panic(expected = "Step payouts must be <= 10000 bps")]
+    fn step_payouts_above_the_full_reward_are_rejected() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::RewardCurve(RewardCurve::Step(vec![(10, 10_001)])));
+    }
+}
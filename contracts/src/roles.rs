@@ -0,0 +1,187 @@
+//! Role-based access control. The owner implicitly holds every role while
+//! no council is set; see `council.rs`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{Event, RoleGranted, RoleRevoked};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// Manages redemption options.
+    Admin,
+    /// Records contributions on behalf of the data pipeline.
+    Recorder,
+    /// Manages fees and the treasury.
+    Treasurer,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_owner_action();
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        assert!(!roles.contains(&role), "Account already has this role");
+        roles.push(role);
+        self.roles.insert(&account_id, &roles);
+
+        let mut holders = self.role_holders.get(&role).unwrap_or_default();
+        holders.push(account_id.clone());
+        self.role_holders.insert(&role, &holders);
+
+        RoleGranted {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+            role,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_owner_action();
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        assert!(roles.contains(&role), "Account does not have this role");
+        roles.retain(|held| *held != role);
+        if roles.is_empty() {
+            self.roles.remove(&account_id);
+        } else {
+            self.roles.insert(&account_id, &roles);
+        }
+
+        let mut holders = self.role_holders.get(&role).unwrap_or_default();
+        holders.retain(|holder| *holder != account_id);
+        self.role_holders.insert(&role, &holders);
+
+        RoleRevoked {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+            role,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    // View functions
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        self.internal_has_role(&account_id, role)
+    }
+
+    pub fn get_role_holders(&self, role: Role) -> Vec<AccountId> {
+        self.role_holders.get(&role).unwrap_or_default()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn internal_has_role(&self, account_id: &AccountId, role: Role) -> bool {
+        self.roles
+            .get(account_id)
+            .is_some_and(|roles| roles.contains(&role))
+    }
+
+    /// Passes for the owner and for holders of `role`.
+    pub(crate) fn assert_role(&self, role: Role) {
+        let caller = env::predecessor_account_id();
+        assert!(
+            (caller == self.owner && self.council.is_empty()) || self.internal_has_role(&caller, role),
+            "Caller does not have the {:?} role",
+            role
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+
+    #[test]
+    fn grant_and_revoke_roles() {
+        let mut contract = setup_contract();
+        contract.grant_role(food_bank(), Role::Recorder);
+        contract.grant_role(other_food_bank(), Role::Recorder);
+        contract.grant_role(food_bank(), Role::Admin);
+
+        assert!(contract.has_role(food_bank(), Role::Recorder));
+        assert!(contract.has_role(food_bank(), Role::Admin));
+        assert!(!contract.has_role(food_bank(), Role::Treasurer));
+        assert_eq!(
+            contract.get_role_holders(Role::Recorder),
+            vec![food_bank(), other_food_bank()]
+        );
+
+        contract.revoke_role(food_bank(), Role::Recorder);
+        assert!(!contract.has_role(food_bank(), Role::Recorder));
+        assert!(contract.has_role(food_bank(), Role::Admin));
+        assert_eq!(contract.get_role_holders(Role::Recorder), vec![other_food_bank()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account already has this role")]
+    fn grant_role_rejects_duplicates() {
+        let mut contract = setup_contract();
+        contract.grant_role(food_bank(), Role::Admin);
+        contract.grant_role(food_bank(), Role::Admin);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn grant_role_is_owner_only() {
+        let mut contract = setup_contract();
+        contract.grant_role(food_bank(), Role::Admin);
+        set_caller(food_bank(), 0);
+        contract.grant_role(food_bank(), Role::Recorder);
+    }
+
+    #[test]
+    fn recorder_can_record_contributions() {
+        let mut contract = setup_contract();
+        contract.grant_role(other_food_bank(), Role::Recorder);
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.record_contribution(food_bank(), metrics(100, 100, 100), None, None, None, None);
+        assert_eq!(contract.get_contributions(food_bank()).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Recorder role")]
+    fn record_contribution_rejects_admin_without_recorder() {
+        let mut contract = setup_contract();
+        contract.grant_role(other_food_bank(), Role::Admin);
+        contract.grant_role(other_food_bank(), Role::Treasurer);
+        set_caller(other_food_bank(), 0);
+        contract.record_contribution(food_bank(), metrics(100, 100, 100), None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Admin role")]
+    fn add_redemption_option_rejects_recorder_without_admin() {
+        let mut contract = setup_contract();
+        contract.grant_role(other_food_bank(), Role::Recorder);
+        contract.grant_role(other_food_bank(), Role::Treasurer);
+        set_caller(other_food_bank(), 0);
+        contract.add_redemption_option(
+            "tote_bags".to_string(),
+            "Tote bags".to_string(),
+            U128(10),
+            "Branded tote bags".to_string(),
+            None,
+        );
+    }
+
+    #[test]
+    fn admin_can_add_redemption_options() {
+        let mut contract = setup_contract();
+        contract.grant_role(other_food_bank(), Role::Admin);
+        set_caller(other_food_bank(), ONE_NEAR);
+        contract.add_redemption_option(
+            "tote_bags".to_string(),
+            "Tote bags".to_string(),
+            U128(10),
+            "Branded tote bags".to_string(),
+            None,
+        );
+        assert_eq!(contract.get_redemption_options().len(), 4);
+    }
+}
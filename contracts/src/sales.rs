@@ -0,0 +1,243 @@
+//! Token sales for suppliers who want to sponsor redemptions. Attached NEAR
+//! buys whole tokens at the owner's price; the NEAR is kept as sale proceeds,
+//! which only the Treasurer role can withdraw and `withdraw_near` never
+//! touches. A rejected purchase panics, so the runtime returns the whole
+//! deposit.
+
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, near_bindgen, AccountId, Balance, Promise};
+
+use crate::events::{Event, SaleProceedsWithdrawn, TokensPurchased};
+use crate::roles::Role;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// 1 milliNEAR, enough to cover the storage a first purchase adds.
+pub const MIN_TOKEN_PRICE_YOCTO: Balance = 10u128.pow(21);
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn set_token_price(&mut self, token_price_yocto: U128) {
+        self.assert_owner_action();
+        assert!(
+            token_price_yocto.0 >= MIN_TOKEN_PRICE_YOCTO,
+            "Token price must be at least {} yoctoNEAR",
+            MIN_TOKEN_PRICE_YOCTO
+        );
+        self.assert_buyback_below_sale(self.buyback_price_yocto, token_price_yocto.0);
+        self.token_price_yocto = token_price_yocto.0;
+    }
+
+    pub fn set_sales_enabled(&mut self, enabled: bool) {
+        self.assert_owner_action();
+        assert!(!enabled || self.token_price_yocto > 0, "Token price is not set");
+        self.sales_enabled = enabled;
+    }
+
+    /// Purchases can't take the total supply above `max_supply`; `None`
+    /// lifts the cap.
+    pub fn set_max_supply(&mut self, max_supply: Option<U128>) {
+        self.assert_owner_action();
+        self.max_supply = max_supply.map(|max_supply| max_supply.0);
+    }
+
+    /// Mints as many whole tokens as the attached deposit pays for and
+    /// refunds the remainder.
+    #[payable]
+    pub fn buy_tokens(&mut self) -> U128 {
+        self.assert_not_paused();
+        assert!(self.sales_enabled, "Token sales are disabled");
+        let buyer = env::predecessor_account_id();
+        self.assert_not_frozen(&buyer);
+        let deposit = env::attached_deposit();
+        let tokens = deposit / self.token_price_yocto;
+        assert!(tokens > 0, "Deposit is below the token price");
+        if let Some(max_supply) = self.max_supply {
+            assert!(
+                self.total_supply + tokens <= max_supply,
+                "Purchase would exceed the max supply of {}",
+                max_supply
+            );
+        }
+        let cost = tokens * self.token_price_yocto;
+        let refund = deposit - cost;
+        self.sale_proceeds += cost;
+        self.mint(buyer.clone(), tokens);
+        TokensPurchased {
+            buyer: &buyer,
+            tokens: U128(tokens),
+            cost: U128(cost),
+            refund: U128(refund),
+        }
+        .emit();
+        if refund > 0 {
+            Promise::new(buyer).transfer(refund);
+        }
+        U128(tokens)
+    }
+
+    #[payable]
+    pub fn withdraw_sale_proceeds(&mut self, amount: U128, to: AccountId) -> Promise {
+        assert_one_yocto();
+        self.assert_role(Role::Treasurer);
+        assert!(
+            amount.0 <= self.sale_proceeds,
+            "Only {} yoctoNEAR of sale proceeds is available",
+            self.sale_proceeds
+        );
+        self.sale_proceeds -= amount.0;
+        SaleProceedsWithdrawn {
+            actor: &env::predecessor_account_id(),
+            to: &to,
+            amount,
+            remaining: U128(self.sale_proceeds),
+        }
+        .emit();
+        Promise::new(to).transfer(amount.0)
+    }
+
+    // View functions
+    /// 0 until the owner sets a price.
+    pub fn get_token_price(&self) -> U128 {
+        U128(self.token_price_yocto)
+    }
+
+    pub fn is_sales_enabled(&self) -> bool {
+        self.sales_enabled
+    }
+
+    pub fn get_max_supply(&self) -> Option<U128> {
+        self.max_supply.map(U128)
+    }
+
+    pub fn get_sale_proceeds(&self) -> U128 {
+        U128(self.sale_proceeds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+
+    const PRICE: Balance = ONE_NEAR / 10;
+
+    fn supplier() -> AccountId {
+        accounts(3)
+    }
+
+    fn on_sale() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_token_price(U128(PRICE));
+        contract.set_sales_enabled(true);
+        contract
+    }
+
+    fn buy(contract: &mut FoodBankToken, deposit: Balance) -> Balance {
+        set_caller(supplier(), deposit);
+        contract.buy_tokens().0
+    }
+
+    #[test]
+    fn the_deposit_buys_whole_tokens_and_the_dust_is_refunded() {
+        let mut contract = on_sale();
+        assert_eq!(buy(&mut contract, 25 * PRICE + PRICE / 2), 25);
+        assert_eq!(contract.get_balance(supplier()).0, 25);
+        assert_eq!(contract.get_total_supply().0, INITIAL_SUPPLY + 25);
+        assert_eq!(contract.get_sale_proceeds().0, 25 * PRICE);
+
+        let refund = get_created_receipts().pop().unwrap();
+        assert_eq!(refund.receiver_id, supplier());
+        assert_eq!(refund.actions, vec![VmAction::Transfer { deposit: PRICE / 2 }]);
+    }
+
+    #[test]
+    fn an_exact_deposit_refunds_nothing() {
+        let mut contract = on_sale();
+        buy(&mut contract, 3 * PRICE);
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit is below the token price")]
+    fn dust_deposits_are_rejected() {
+        let mut contract = on_sale();
+        buy(&mut contract, PRICE - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token sales are disabled")]
+    fn sales_are_off_by_default() {
+        let mut contract = setup_contract();
+        contract.set_token_price(U128(PRICE));
+        buy(&mut contract, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract is paused")]
+    fn sales_stop_while_paused() {
+        let mut contract = on_sale();
+        contract.pause();
+        buy(&mut contract, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Purchase would exceed the max supply")]
+    fn purchases_respect_the_max_supply() {
+        let mut contract = on_sale();
+        contract.set_max_supply(Some(U128(INITIAL_SUPPLY + 10)));
+        buy(&mut contract, 10 * PRICE);
+        buy(&mut contract, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token price must be at least")]
+    fn the_price_has_a_floor() {
+        let mut contract = setup_contract();
+        contract.set_token_price(U128(MIN_TOKEN_PRICE_YOCTO - 1));
+    }
+
+    #[test]
+    fn the_treasurer_withdraws_proceeds() {
+        let mut contract = on_sale();
+        buy(&mut contract, 10 * PRICE);
+        set_caller(owner(), 0);
+        contract.grant_role(other_food_bank(), Role::Treasurer);
+        set_caller(other_food_bank(), 1);
+        contract.withdraw_sale_proceeds(U128(4 * PRICE), other_food_bank());
+        assert_eq!(contract.get_sale_proceeds().0, 6 * PRICE);
+
+        let transfer = get_created_receipts().pop().unwrap();
+        assert_eq!(transfer.receiver_id, other_food_bank());
+        assert_eq!(transfer.actions, vec![VmAction::Transfer { deposit: 4 * PRICE }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only 1000000000000000000000000 yoctoNEAR of sale proceeds is available")]
+    fn proceeds_cannot_be_overdrawn() {
+        let mut contract = on_sale();
+        buy(&mut contract, 10 * PRICE);
+        set_caller(owner(), 1);
+        contract.withdraw_sale_proceeds(U128(10 * PRICE + 1), owner());
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the Treasurer role")]
+    fn buyers_cannot_withdraw_proceeds() {
+        let mut contract = on_sale();
+        buy(&mut contract, 10 * PRICE);
+        set_caller(supplier(), 1);
+        contract.withdraw_sale_proceeds(U128(PRICE), supplier());
+    }
+
+    #[test]
+    fn proceeds_are_not_available_to_withdraw_near() {
+        let mut contract = on_sale();
+        buy(&mut contract, 10 * PRICE);
+        set_caller(owner(), 0);
+        let available = contract.get_available_near().0;
+        contract.sale_proceeds = 0;
+        assert_eq!(contract.get_available_near().0 - available, 10 * PRICE);
+    }
+}
@@ -0,0 +1,236 @@
+//! Minimum scores that differ by account. An account's own override comes
+//! first, then the cohort its tenure falls in, then the program-wide
+//! `min_score_threshold`. Cohorts let new food banks earn for modest scores
+//! while veterans are held to a higher bar. Tenure is counted as for
+//! loyalty discounts, so an account's first contribution falls in the
+//! cohort starting at zero.
+//!
+//! Cohorts must be sorted by `min_tenure`; an empty list holds every account
+//! without an override to the global threshold, and so do tenures below the
+//! first cohort. Both are changed through timelocked `ParamChange`s.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_THRESHOLD_COHORTS: usize = 10;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ThresholdCohort {
+    /// Nanoseconds since the first contribution at which the cohort starts.
+    pub min_tenure: u64,
+    pub threshold: u32,
+}
+
+/// Which rule the threshold an account is held to came from.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ThresholdSource {
+    Global,
+    Cohort,
+    AccountOverride,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountThreshold {
+    pub threshold: u32,
+    pub source: ThresholdSource,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    pub fn get_threshold_cohorts(&self) -> Vec<ThresholdCohort> {
+        self.threshold_cohorts.clone()
+    }
+
+    /// The minimum score a contribution by `account_id` recorded now must
+    /// reach to earn a reward.
+    pub fn get_threshold_for(&self, account_id: AccountId) -> AccountThreshold {
+        let (threshold, source) = self.resolve_threshold(&account_id);
+        AccountThreshold { threshold, source }
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn resolve_threshold(&self, account_id: &AccountId) -> (u32, ThresholdSource) {
+        if let Some(threshold) = self.threshold_overrides.get(account_id) {
+            return (threshold, ThresholdSource::AccountOverride);
+        }
+        let tenure = self
+            .first_contribution_at(account_id)
+            .map_or(0, |first| env::block_timestamp().saturating_sub(first));
+        self.threshold_cohorts
+            .iter()
+            .rev()
+            .find(|cohort| tenure >= cohort.min_tenure)
+            .map_or((self.min_score_threshold, ThresholdSource::Global), |cohort| {
+                (cohort.threshold, ThresholdSource::Cohort)
+            })
+    }
+}
+
+pub(crate) fn assert_valid_cohorts(cohorts: &[ThresholdCohort]) {
+    assert!(
+        cohorts.len() <= MAX_THRESHOLD_COHORTS,
+        "At most {} threshold cohorts",
+        MAX_THRESHOLD_COHORTS
+    );
+    for window in cohorts.windows(2) {
+        assert!(
+            window[0].min_tenure < window[1].min_tenure,
+            "Threshold cohorts must be sorted by min_tenure without duplicates"
+        );
+    }
+    for cohort in cohorts {
+        assert!(cohort.threshold <= 100, "Score threshold must be <= 100");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::timelock::{ParamChange, DEFAULT_TIMELOCK_DELAY};
+
+    const DAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    fn set_cohorts(contract: &mut FoodBankToken, cohorts: Vec<ThresholdCohort>) {
+        contract.apply_param_change(&ParamChange::ThresholdCohorts(cohorts));
+    }
+
+    fn set_override(contract: &mut FoodBankToken, threshold: Option<u32>) {
+        contract.apply_param_change(&override_change(threshold));
+    }
+
+    fn override_change(threshold: Option<u32>) -> ParamChange {
+        ParamChange::ThresholdOverride {
+            account_id: food_bank(),
+            threshold,
+        }
+    }
+
+    fn with_cohorts() -> FoodBankToken {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        set_cohorts(
+            &mut contract,
+            vec![
+                ThresholdCohort { min_tenure: 0, threshold: 5 },
+                ThresholdCohort { min_tenure: 90 * DAY_NS, threshold: 20 },
+            ],
+        );
+        contract
+    }
+
+    fn last_breakdown(contract: &FoodBankToken) -> crate::RewardBreakdown {
+        let count = contract.get_account_stats(food_bank()).unwrap().contribution_count;
+        contract.get_reward_breakdown(food_bank(), count - 1).unwrap()
+    }
+
+    #[test]
+    fn new_accounts_earn_for_modest_scores() {
+        let mut contract = with_cohorts();
+        contribute(&mut contract, &food_bank(), 8);
+        assert_eq!(contract.get_balance(food_bank()).0, 8);
+        assert_eq!(last_breakdown(&contract).score_threshold, 5);
+    }
+
+    #[test]
+    fn veterans_are_held_to_the_higher_bar_from_the_boundary() {
+        let mut contract = with_cohorts();
+        contribute(&mut contract, &food_bank(), 15);
+        advance_time(90 * DAY_NS - 1);
+        assert_eq!(contract.get_threshold_for(food_bank()).threshold, 5);
+        contribute(&mut contract, &food_bank(), 15);
+        assert_eq!(contract.get_balance(food_bank()).0, 30);
+
+        advance_time(1);
+        assert_eq!(
+            contract.get_threshold_for(food_bank()),
+            AccountThreshold {
+                threshold: 20,
+                source: ThresholdSource::Cohort
+            }
+        );
+        contribute(&mut contract, &food_bank(), 15);
+        assert_eq!(contract.get_balance(food_bank()).0, 30);
+        assert_eq!(last_breakdown(&contract).score_threshold, 20);
+    }
+
+    #[test]
+    fn an_override_beats_the_cohort() {
+        let mut contract = with_cohorts();
+        set_override(&mut contract, Some(50));
+        assert_eq!(contract.get_threshold_for(food_bank()).source, ThresholdSource::AccountOverride);
+        contribute(&mut contract, &food_bank(), 40);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+
+        set_override(&mut contract, None);
+        contribute(&mut contract, &food_bank(), 40);
+        assert_eq!(contract.get_balance(food_bank()).0, 40);
+    }
+
+    #[test]
+    fn without_a_matching_cohort_the_global_threshold_applies() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        set_cohorts(&mut contract, vec![ThresholdCohort { min_tenure: DAY_NS, threshold: 5 }]);
+        assert_eq!(
+            contract.get_threshold_for(food_bank()),
+            AccountThreshold {
+                threshold: 10,
+                source: ThresholdSource::Global
+            }
+        );
+        contribute(&mut contract, &food_bank(), 8);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(last_breakdown(&contract).score_threshold, 10);
+    }
+
+    #[test]
+    fn proposed_thresholds_wait_for_the_timelock() {
+        let mut contract = setup_contract();
+        let cohorts = contract.propose_param_change(ParamChange::ThresholdCohorts(vec![ThresholdCohort {
+            min_tenure: 0,
+            threshold: 5,
+        }]));
+        let threshold_override = contract.propose_param_change(override_change(Some(50)));
+        assert_eq!(contract.get_threshold_for(food_bank()).source, ThresholdSource::Global);
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(cohorts);
+        assert_eq!(contract.get_threshold_for(other_food_bank()).threshold, 5);
+        contract.execute_param_change(threshold_override);
+        assert_eq!(contract.get_threshold_for(food_bank()).threshold, 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "Threshold cohorts must be sorted by min_tenure without duplicates")]
+    fn cohorts_must_be_sorted() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::ThresholdCohorts(vec![
+            ThresholdCohort { min_tenure: 90 * DAY_NS, threshold: 20 },
+            ThresholdCohort { min_tenure: 0, threshold: 5 },
+        ]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Score threshold must be <= 100")]
+    fn thresholds_are_bounded() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(override_change(Some(101)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn cohorts_are_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.propose_param_change(ParamChange::ThresholdCohorts(Vec::new()));
+    }
+}
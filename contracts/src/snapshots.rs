@@ -0,0 +1,152 @@
+//! Point-in-time balance exports for off-chain accounting.
+//!
+//! `freeze_snapshot` doesn't stop transfers or mints. Instead, the first
+//! change to an account's balance while a snapshot is frozen copies the old
+//! balance aside, so pages read across many calls all describe the same
+//! moment. Accounts that first appear after the freeze are left out.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::{assert_batch_size, FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BalanceSnapshot {
+    pub id: u64,
+    pub block_height: u64,
+    pub timestamp: u64,
+    /// Participants covered by the export.
+    pub holder_count: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn freeze_snapshot(&mut self) -> BalanceSnapshot {
+        self.assert_owner_action();
+        assert!(self.balance_snapshot.is_none(), "A snapshot is already frozen");
+        let snapshot = BalanceSnapshot {
+            id: self.next_balance_snapshot_id,
+            block_height: env::block_height(),
+            timestamp: env::block_timestamp(),
+            holder_count: self.participants.len(),
+        };
+        self.next_balance_snapshot_id += 1;
+        self.balance_snapshot = Some(snapshot.clone());
+        snapshot
+    }
+
+    pub fn release_snapshot(&mut self) {
+        self.assert_owner_action();
+        self.balance_snapshot.take().expect("No snapshot is frozen");
+    }
+
+    // View functions
+    pub fn get_snapshot_info(&self) -> Option<BalanceSnapshot> {
+        self.balance_snapshot.clone()
+    }
+
+    /// A page of holders and balances, as of the frozen snapshot if there is
+    /// one and live otherwise. Pages are limited to `MAX_BATCH_SIZE` (200)
+    /// entries, which stays well inside the view gas limit.
+    pub fn get_balance_snapshot(&self, from_index: u64, limit: u64) -> Vec<(AccountId, U128)> {
+        assert_batch_size(limit as usize);
+        let holders = self.participants.as_vector();
+        let count = self
+            .balance_snapshot
+            .as_ref()
+            .map_or(holders.len(), |snapshot| snapshot.holder_count);
+        (from_index..count.min(from_index.saturating_add(limit)))
+            .map(|index| {
+                let account_id = holders.get(index).unwrap();
+                let balance = self.exported_balance(&account_id);
+                (account_id, U128(balance))
+            })
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn record_export_balance(&mut self, account_id: &AccountId, old_balance: Balance) {
+        if let Some(snapshot) = &self.balance_snapshot {
+            let key = (snapshot.id, account_id.clone());
+            if self.exported_balances.get(&key).is_none() {
+                self.exported_balances.insert(&key, &old_balance);
+            }
+        }
+    }
+
+    fn exported_balance(&self, account_id: &AccountId) -> Balance {
+        self.balance_snapshot
+            .as_ref()
+            .and_then(|snapshot| self.exported_balances.get(&(snapshot.id, account_id.clone())))
+            .unwrap_or_else(|| self.balances.get(account_id).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+
+    fn holders() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 100);
+        contract.mint(other_food_bank(), 200);
+        contract
+    }
+
+    #[test]
+    fn live_export_pages_through_every_holder() {
+        let contract = holders();
+        assert_eq!(contract.get_balance_snapshot(0, 1), vec![(food_bank(), U128(100))]);
+        assert_eq!(contract.get_balance_snapshot(1, 2), vec![(other_food_bank(), U128(200))]);
+    }
+
+    #[test]
+    fn mints_during_a_snapshot_do_not_change_exported_pages() {
+        let mut contract = holders();
+        let snapshot = contract.freeze_snapshot();
+        assert_eq!(snapshot.holder_count, 2);
+        let before = contract.get_balance_snapshot(0, 10);
+
+        contract.mint(food_bank(), 50);
+        contract.mint(accounts(3), 10);
+        assert_eq!(contract.get_balance_snapshot(0, 10), before);
+        assert_eq!(contract.get_balance(food_bank()).0, 150);
+
+        contract.release_snapshot();
+        let live = contract.get_balance_snapshot(0, 10);
+        assert_eq!(live.len(), 3);
+        assert_eq!(live[0], (food_bank(), U128(150)));
+    }
+
+    #[test]
+    fn each_snapshot_starts_from_current_balances() {
+        let mut contract = holders();
+        contract.freeze_snapshot();
+        contract.mint(food_bank(), 50);
+        contract.release_snapshot();
+
+        contract.freeze_snapshot();
+        assert_eq!(contract.get_balance_snapshot(0, 1), vec![(food_bank(), U128(150))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "A snapshot is already frozen")]
+    fn only_one_snapshot_at_a_time() {
+        let mut contract = holders();
+        contract.freeze_snapshot();
+        contract.freeze_snapshot();
+    }
+
+    #[test]
+    #[should_panic(expected = "At most 200 accounts can be queried at once")]
+    fn pages_are_capped() {
+        let contract = holders();
+        contract.get_balance_snapshot(0, 201);
+    }
+}
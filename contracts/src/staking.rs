@@ -0,0 +1,282 @@
+//! Locking tokens for a boosted contribution reward. Staked tokens leave the
+//! spendable balance and can only come back after a cooldown.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, near_bindgen, AccountId, Balance};
+
+use crate::emission::MAX_RATE_BPS;
+use crate::events::{Event, TokensStaked, TokensUnstaked};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_STAKE_COOLDOWN: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Stake {
+    pub(crate) amount: Balance,
+    /// Restarted by every new stake.
+    pub(crate) unlocks_at: u64,
+}
+
+/// Reward bonus for contributors with at least `min_staked` tokens staked.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakeTier {
+    pub min_staked: U128,
+    pub bonus_bps: u16,
+}
+
+pub(crate) fn default_stake_tiers() -> Vec<StakeTier> {
+    vec![
+        StakeTier { min_staked: U128(100), bonus_bps: 1_000 },
+        StakeTier { min_staked: U128(500), bonus_bps: 2_500 },
+    ]
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    #[payable]
+    pub fn stake(&mut self, amount: U128) {
+        assert_one_yocto();
+        self.assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        self.assert_not_frozen(&account_id);
+        self.assert_not_treasury(&account_id);
+        assert!(amount.0 > 0, "Amount must be positive");
+        self.assert_registered(&account_id);
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        assert!(balance >= amount.0, "Insufficient balance");
+        assert!(
+            self.unlocked_balance(&account_id, balance) >= amount.0,
+            "Tokens are locked until {}",
+            self.next_unlock(&account_id).unwrap_or_default()
+        );
+
+        let staked = self.stakes.get(&account_id).map_or(0, |stake| stake.amount);
+        self.internal_set_balance(&account_id, balance - amount.0);
+        self.stakes.insert(
+            &account_id,
+            &Stake {
+                amount: staked + amount.0,
+                unlocks_at: env::block_timestamp() + self.stake_cooldown,
+            },
+        );
+        TokensStaked {
+            account_id: &account_id,
+            amount,
+        }
+        .emit();
+    }
+
+    #[payable]
+    pub fn unstake(&mut self, amount: U128) {
+        assert_one_yocto();
+        self.assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        self.assert_not_frozen(&account_id);
+        let stake = self.stakes.get(&account_id).expect("No tokens staked");
+        assert!(
+            env::block_timestamp() >= stake.unlocks_at,
+            "Stake is locked until {}",
+            stake.unlocks_at
+        );
+        assert!(stake.amount >= amount.0, "Insufficient staked balance");
+
+        if stake.amount == amount.0 {
+            self.stakes.remove(&account_id);
+        } else {
+            self.stakes.insert(
+                &account_id,
+                &Stake {
+                    amount: stake.amount - amount.0,
+                    unlocks_at: stake.unlocks_at,
+                },
+            );
+        }
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        self.internal_set_balance(&account_id, balance + amount.0);
+        TokensUnstaked {
+            account_id: &account_id,
+            amount,
+        }
+        .emit();
+    }
+
+    /// Tiers must be sorted by `min_staked`; the highest one reached applies.
+    pub fn set_stake_tiers(&mut self, tiers: Vec<StakeTier>) {
+        self.assert_owner_action();
+        for window in tiers.windows(2) {
+            assert!(
+                window[0].min_staked.0 < window[1].min_staked.0,
+                "Stake tiers must be sorted by min_staked without duplicates"
+            );
+        }
+        for tier in &tiers {
+            assert!(tier.bonus_bps <= MAX_RATE_BPS, "Bonus must be <= {} bps", MAX_RATE_BPS);
+        }
+        self.stake_tiers = tiers;
+    }
+
+    /// Applies to stakes made after the change.
+    pub fn set_stake_cooldown(&mut self, cooldown: u64) {
+        self.assert_owner_action();
+        self.stake_cooldown = cooldown;
+    }
+
+    // View functions
+    pub fn get_staked_balance(&self, account_id: AccountId) -> U128 {
+        U128(self.staked_balance(&account_id))
+    }
+
+    pub fn get_stake_tiers(&self) -> Vec<StakeTier> {
+        self.stake_tiers.clone()
+    }
+
+    pub fn get_stake_cooldown(&self) -> u64 {
+        self.stake_cooldown
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn staked_balance(&self, account_id: &AccountId) -> Balance {
+        self.stakes.get(account_id).map_or(0, |stake| stake.amount)
+    }
+
+    /// Bonus in basis points earned by `account_id`'s current stake.
+    pub(crate) fn stake_bonus_bps(&self, account_id: &AccountId) -> u16 {
+        let staked = self.staked_balance(account_id);
+        self.stake_tiers
+            .iter()
+            .rev()
+            .find(|tier| staked >= tier.min_staked.0)
+            .map_or(0, |tier| tier.bonus_bps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn stake(contract: &mut FoodBankToken, amount: Balance) {
+        set_caller(food_bank(), 1);
+        contract.stake(U128(amount));
+    }
+
+    #[test]
+    fn stake_moves_tokens_out_of_the_balance() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        stake(&mut contract, 120);
+        assert_eq!(contract.get_balance(food_bank()).0, 180);
+        assert_eq!(contract.get_staked_balance(food_bank()).0, 120);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 300);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tokens are locked until")]
+    fn locked_rewards_cannot_be_staked() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_reward_lock_duration(crate::locks::DEFAULT_REWARD_LOCK_DURATION);
+        contract.mint(food_bank(), 50);
+        contribute(&mut contract, &food_bank(), 100);
+        stake(&mut contract, 51);
+    }
+
+    #[test]
+    fn unstake_after_cooldown_returns_tokens() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        stake(&mut contract, 120);
+
+        advance_time(DEFAULT_STAKE_COOLDOWN);
+        contract.unstake(U128(20));
+        assert_eq!(contract.get_staked_balance(food_bank()).0, 100);
+        contract.unstake(U128(100));
+        assert_eq!(contract.get_staked_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_balance(food_bank()).0, 300);
+    }
+
+    #[test]
+    #[should_panic(expected = "Stake is locked until")]
+    fn unstake_during_cooldown_panics() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        stake(&mut contract, 120);
+        advance_time(DEFAULT_STAKE_COOLDOWN - 1);
+        contract.unstake(U128(120));
+    }
+
+    #[test]
+    #[should_panic(expected = "Stake is locked until")]
+    fn new_stake_restarts_the_cooldown() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 300);
+        stake(&mut contract, 100);
+        advance_time(DEFAULT_STAKE_COOLDOWN);
+        stake(&mut contract, 1);
+        contract.unstake(U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account not registered")]
+    fn unregistered_accounts_cannot_stake() {
+        let mut contract = setup_contract();
+        stake(&mut contract, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient balance")]
+    fn staked_tokens_cannot_be_redeemed() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 150);
+        stake(&mut contract, 100);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    fn bonus_applies_at_tier_boundaries() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.mint(food_bank(), 1_000);
+
+        stake(&mut contract, 99);
+        assert_eq!(contract.stake_bonus_bps(&food_bank()), 0);
+        stake(&mut contract, 1);
+        assert_eq!(contract.stake_bonus_bps(&food_bank()), 1_000);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 900 + 110);
+
+        stake(&mut contract, 399);
+        assert_eq!(contract.stake_bonus_bps(&food_bank()), 1_000);
+        stake(&mut contract, 1);
+        assert_eq!(contract.stake_bonus_bps(&food_bank()), 2_500);
+        let breakdown = contract.calculate_reward(&food_bank(), metrics(100, 100, 100).score(), None);
+        assert_eq!(breakdown.stake_bonus_bps, 2_500);
+        assert_eq!(breakdown.reward.0, 125);
+    }
+
+    #[test]
+    fn tiers_are_owner_configurable() {
+        let mut contract = setup_contract();
+        let tiers = vec![StakeTier { min_staked: U128(10), bonus_bps: 500 }];
+        contract.set_stake_tiers(tiers.clone());
+        assert_eq!(contract.get_stake_tiers(), tiers);
+
+        contract.mint(food_bank(), 10);
+        stake(&mut contract, 10);
+        assert_eq!(contract.stake_bonus_bps(&food_bank()), 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Stake tiers must be sorted by min_staked without duplicates")]
+    fn unsorted_tiers_are_rejected() {
+        let mut contract = setup_contract();
+        contract.set_stake_tiers(vec![
+            StakeTier { min_staked: U128(500), bonus_bps: 2_500 },
+            StakeTier { min_staked: U128(100), bonus_bps: 1_000 },
+        ]);
+    }
+}
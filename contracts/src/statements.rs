@@ -0,0 +1,312 @@
+//! Monthly account statements for food bank finance teams. Every balance
+//! change lands in the account's ledger for the month it happens in (UTC,
+//! keyed like `"2025-03"`); the first change of a month opens that month's
+//! ledger at the balance it found. The paths finance teams ask about tag
+//! their change with a `StatementFlow`, and whatever isn't tagged (staking,
+//! vesting, sales, donations) is reported as other flows, so every
+//! statement reconciles:
+//!
+//! ```text
+//! opening + rewards + refunds + transfers in + other in
+//!     - redeemed - transfers out - penalties - clawbacks - other out
+//!     == closing
+//! ```
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::fulfillment::FulfillmentStatus;
+use crate::monthly::month_key;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// Balance changes a statement reports on their own line.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum StatementFlow {
+    /// Contribution rewards and bonuses, vesting claims and payouts.
+    Reward,
+    /// Redemptions and penalties given back.
+    Refund,
+    TransferIn,
+    TransferOut,
+    Redemption,
+    /// Penalty burns and debt repaid from the balance.
+    Penalty,
+    /// Rewards taken back by voids and rescores.
+    Clawback,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, PartialEq, Eq, Debug)]
+pub struct MonthLedger {
+    opening_balance: Balance,
+    closing_balance: Balance,
+    inflows: Balance,
+    outflows: Balance,
+    rewards_earned: Balance,
+    refunds: Balance,
+    transfers_in: Balance,
+    transfers_out: Balance,
+    redeemed: Balance,
+    penalties: Balance,
+    rewards_clawed_back: Balance,
+    redemption_receipts: Vec<u64>,
+}
+
+impl MonthLedger {
+    fn carried(balance: Balance) -> Self {
+        Self {
+            opening_balance: balance,
+            closing_balance: balance,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StatementRedemption {
+    pub receipt_id: u64,
+    pub option_id: String,
+    pub amount: U128,
+    pub status: FulfillmentStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Statement {
+    pub month: String,
+    pub opening_balance: U128,
+    pub rewards_earned: U128,
+    pub refunds: U128,
+    pub transfers_in: U128,
+    pub other_in: U128,
+    /// Redemptions started this month. Refunds of any of them are counted
+    /// in `refunds` for the month they happened.
+    pub redemptions: Vec<StatementRedemption>,
+    pub redeemed: U128,
+    pub transfers_out: U128,
+    pub penalties: U128,
+    pub rewards_clawed_back: U128,
+    pub other_out: U128,
+    pub closing_balance: U128,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    /// `None` for months before the account's first balance change since
+    /// statements were kept. A month without changes carries the previous
+    /// closing balance.
+    pub fn get_statement(&self, account_id: AccountId, month_key: String) -> Option<Statement> {
+        let key = (account_id.clone(), month_key.clone());
+        let ledger = match self.statement_ledgers.get(&key) {
+            Some(ledger) => ledger,
+            None => {
+                let months = self.statement_months.get(&account_id).unwrap_or_default();
+                let previous = months.iter().rev().find(|month| **month < month_key)?;
+                let closing = self
+                    .statement_ledgers
+                    .get(&(account_id, previous.clone()))
+                    .unwrap()
+                    .closing_balance;
+                MonthLedger::carried(closing)
+            }
+        };
+        let redemptions = ledger
+            .redemption_receipts
+            .iter()
+            .filter_map(|id| self.receipts.get(id))
+            .map(|receipt| StatementRedemption {
+                receipt_id: receipt.id,
+                option_id: receipt.option_id,
+                amount: receipt.amount,
+                status: receipt.status,
+            })
+            .collect();
+        Some(Statement {
+            month: month_key,
+            opening_balance: U128(ledger.opening_balance),
+            rewards_earned: U128(ledger.rewards_earned),
+            refunds: U128(ledger.refunds),
+            transfers_in: U128(ledger.transfers_in),
+            other_in: U128(ledger.inflows - ledger.rewards_earned - ledger.refunds - ledger.transfers_in),
+            redemptions,
+            redeemed: U128(ledger.redeemed),
+            transfers_out: U128(ledger.transfers_out),
+            penalties: U128(ledger.penalties),
+            rewards_clawed_back: U128(ledger.rewards_clawed_back),
+            other_out: U128(
+                ledger.outflows
+                    - ledger.redeemed
+                    - ledger.transfers_out
+                    - ledger.penalties
+                    - ledger.rewards_clawed_back,
+            ),
+            closing_balance: U128(ledger.closing_balance),
+        })
+    }
+}
+
+impl FoodBankToken {
+    /// Called by `internal_set_balance` for every change.
+    pub(crate) fn record_statement_balance(&mut self, account_id: &AccountId, old: Balance, new: Balance) {
+        self.update_statement(account_id, old, |ledger| {
+            if new > old {
+                ledger.inflows += new - old;
+            } else {
+                ledger.outflows += old - new;
+            }
+            ledger.closing_balance = new;
+        });
+    }
+
+    /// Tags `amount` of a change already recorded this month. Call it after
+    /// the balance is set.
+    pub(crate) fn tag_statement_flow(&mut self, account_id: &AccountId, flow: StatementFlow, amount: Balance) {
+        let balance = self.balances.get(account_id).unwrap_or(0);
+        self.update_statement(account_id, balance, |ledger| {
+            let total = match flow {
+                StatementFlow::Reward => &mut ledger.rewards_earned,
+                StatementFlow::Refund => &mut ledger.refunds,
+                StatementFlow::TransferIn => &mut ledger.transfers_in,
+                StatementFlow::TransferOut => &mut ledger.transfers_out,
+                StatementFlow::Redemption => &mut ledger.redeemed,
+                StatementFlow::Penalty => &mut ledger.penalties,
+                StatementFlow::Clawback => &mut ledger.rewards_clawed_back,
+            };
+            *total += amount;
+        });
+    }
+
+    pub(crate) fn record_statement_redemption(&mut self, account_id: &AccountId, receipt_id: u64) {
+        let balance = self.balances.get(account_id).unwrap_or(0);
+        self.update_statement(account_id, balance, |ledger| ledger.redemption_receipts.push(receipt_id));
+    }
+
+    /// Applies `update` to this month's ledger, opening it at `balance` on
+    /// the month's first change.
+    fn update_statement(&mut self, account_id: &AccountId, balance: Balance, update: impl FnOnce(&mut MonthLedger)) {
+        let month = month_key(env::block_timestamp());
+        let key = (account_id.clone(), month.clone());
+        let mut ledger = self.statement_ledgers.get(&key).unwrap_or_else(|| {
+            let mut months = self.statement_months.get(account_id).unwrap_or_default();
+            months.push(month);
+            self.statement_months.insert(account_id, &months);
+            MonthLedger::carried(balance)
+        });
+        update(&mut ledger);
+        self.statement_ledgers.insert(&key, &ledger);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monthly::NANOS_PER_DAY;
+    use crate::test_utils::*;
+    use near_sdk::PromiseResult;
+
+    /// 2025-03-01T00:00:00Z.
+    const MARCH_2025: u64 = 1_740_787_200 * 1_000_000_000;
+
+    fn in_march() -> FoodBankToken {
+        let contract = setup_contract();
+        advance_time(MARCH_2025 - env::block_timestamp());
+        contract
+    }
+
+    fn reconciles(statement: &Statement) -> bool {
+        let inflows = statement.rewards_earned.0
+            + statement.refunds.0
+            + statement.transfers_in.0
+            + statement.other_in.0;
+        let outflows = statement.redeemed.0
+            + statement.transfers_out.0
+            + statement.penalties.0
+            + statement.rewards_clawed_back.0
+            + statement.other_out.0;
+        statement.opening_balance.0 + inflows - outflows == statement.closing_balance.0
+    }
+
+    fn redeem(contract: &mut FoodBankToken, result: PromiseResult) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        resolve_callback(result);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            last_receipt_id(contract),
+            token_charge(100),
+        );
+    }
+
+    #[test]
+    fn a_month_of_mixed_activity_reconciles() {
+        let mut contract = in_march();
+        set_caller(owner(), 0);
+        contract.set_min_contribution_interval(0);
+        contract.set_reward_lock_duration(0);
+        contract.set_treasury(Some(other_food_bank()), 0);
+        contract.mint(other_food_bank(), 300);
+        set_caller(owner(), 1);
+        contract.treasury_transfer(food_bank(), U128(250));
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 100);
+        redeem(&mut contract, PromiseResult::Successful(vec![]));
+        set_caller(owner(), 0);
+        contract.set_option_cooldown("supplier_discount".to_string(), None);
+        redeem(&mut contract, PromiseResult::Failed);
+        set_caller(owner(), ONE_NEAR);
+        contract.record_penalty(food_bank(), 40, "Late report".to_string());
+        set_caller(owner(), 0);
+        contract.void_contribution(food_bank(), 1);
+        set_caller(food_bank(), 1);
+        contract.stake(U128(50));
+
+        let statement = contract.get_statement(food_bank(), "2025-03".to_string()).unwrap();
+        assert!(reconciles(&statement), "{:?}", statement);
+        assert_eq!(statement.opening_balance.0, 0);
+        assert_eq!(statement.transfers_in.0, 250);
+        assert_eq!(statement.rewards_earned.0, 10);
+        assert_eq!(statement.redeemed.0, 200);
+        assert_eq!(statement.refunds.0, 100);
+        assert_eq!(statement.penalties.0, 2);
+        assert_eq!(statement.rewards_clawed_back.0, 5);
+        assert_eq!(statement.other_out.0, 50);
+        assert_eq!(statement.closing_balance.0, contract.get_balance(food_bank()).0);
+        let statuses: Vec<_> = statement.redemptions.iter().map(|redemption| redemption.status).collect();
+        assert_eq!(statuses, [FulfillmentStatus::Delivered, FulfillmentStatus::Refunded]);
+
+        let treasury = contract.get_statement(other_food_bank(), "2025-03".to_string()).unwrap();
+        assert!(reconciles(&treasury));
+        assert_eq!(treasury.transfers_out.0, 250);
+        assert_eq!(treasury.other_in.0, 300);
+    }
+
+    #[test]
+    fn the_next_month_opens_at_the_previous_close() {
+        let mut contract = in_march();
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(31 * NANOS_PER_DAY);
+        contribute(&mut contract, &food_bank(), 100);
+
+        let march = contract.get_statement(food_bank(), "2025-03".to_string()).unwrap();
+        let april = contract.get_statement(food_bank(), "2025-04".to_string()).unwrap();
+        assert_eq!(april.opening_balance, march.closing_balance);
+        assert_eq!(april.rewards_earned.0, 5);
+        assert_eq!(april.closing_balance.0, 10);
+        assert!(reconciles(&march) && reconciles(&april));
+    }
+
+    #[test]
+    fn quiet_months_carry_the_balance() {
+        let mut contract = in_march();
+        contribute(&mut contract, &food_bank(), 100);
+        let may = contract.get_statement(food_bank(), "2025-05".to_string()).unwrap();
+        assert_eq!(may.opening_balance.0, 5);
+        assert_eq!(may.closing_balance.0, 5);
+        assert!(may.redemptions.is_empty());
+        assert!(contract.get_statement(food_bank(), "2025-02".to_string()).is_none());
+    }
+}
@@ -0,0 +1,365 @@
+//! Counters for reporting. Program-wide ones only ever grow, with clawbacks
+//! counted separately; per-option ones are corrected when a redemption is
+//! refunded. Program-wide activity is also bucketed by UTC day, numbered
+//! from the Unix epoch.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::monthly::NANOS_PER_DAY;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const MAX_STATS_DAYS: u64 = 90;
+pub const MAX_STATS_WEEKS: u64 = 12;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProgramStats {
+    /// Everything minted by contributions: rewards, treasury shares and
+    /// referral bonuses.
+    pub total_rewards_minted: U128,
+    pub total_tokens_redeemed: U128,
+    pub total_contributions_recorded: u64,
+    /// Redemptions whose benefit was delivered; refunded ones don't count.
+    pub total_redemptions: u64,
+    pub total_rewards_clawed_back: U128,
+    /// NEAR received through `donate`, in yoctoNEAR.
+    pub total_donated_near: U128,
+}
+
+/// Usage of a redemption option, kept apart from the option itself so
+/// editing the option doesn't reset it.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OptionStats {
+    pub redemptions_count: u64,
+    pub total_tokens_spent: U128,
+}
+
+/// Activity in one day or week. Redemption counters only include delivered
+/// benefits.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PeriodStats {
+    pub contributions: u64,
+    pub rewards_minted: U128,
+    pub redemptions: u64,
+    pub tokens_redeemed: U128,
+}
+
+impl Default for PeriodStats {
+    fn default() -> Self {
+        Self {
+            contributions: 0,
+            rewards_minted: U128(0),
+            redemptions: 0,
+            tokens_redeemed: U128(0),
+        }
+    }
+}
+
+impl PeriodStats {
+    fn add(&mut self, other: &PeriodStats) {
+        self.contributions += other.contributions;
+        self.rewards_minted = U128(self.rewards_minted.0 + other.rewards_minted.0);
+        self.redemptions += other.redemptions;
+        self.tokens_redeemed = U128(self.tokens_redeemed.0 + other.tokens_redeemed.0);
+    }
+}
+
+impl Default for OptionStats {
+    fn default() -> Self {
+        Self {
+            redemptions_count: 0,
+            total_tokens_spent: U128(0),
+        }
+    }
+}
+
+impl Default for ProgramStats {
+    fn default() -> Self {
+        Self {
+            total_rewards_minted: U128(0),
+            total_tokens_redeemed: U128(0),
+            total_contributions_recorded: 0,
+            total_redemptions: 0,
+            total_rewards_clawed_back: U128(0),
+            total_donated_near: U128(0),
+        }
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    pub fn get_program_stats(&self) -> ProgramStats {
+        self.program_stats.clone()
+    }
+
+    pub fn get_option_stats(&self, option_id: String) -> OptionStats {
+        self.option_stats.get(&option_id).unwrap_or_default()
+    }
+
+    /// Options with the most redemptions first.
+    pub fn get_options_by_popularity(&self, limit: u64) -> Vec<(String, OptionStats)> {
+        let mut options: Vec<(String, OptionStats)> = self
+            .iter_redemption_options()
+            .map(|(option_id, _)| {
+                let stats = self.get_option_stats(option_id.clone());
+                (option_id, stats)
+            })
+            .collect();
+        options.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.redemptions_count));
+        options.truncate(limit as usize);
+        options
+    }
+
+    /// Days from `from_day` to `to_day` inclusive that had any activity,
+    /// as `(day index, stats)`. The day index is the block timestamp divided
+    /// by one day.
+    pub fn get_daily_stats(&self, from_day: u64, to_day: u64) -> Vec<(u64, PeriodStats)> {
+        assert_period_range(from_day, to_day, MAX_STATS_DAYS);
+        (from_day..=to_day)
+            .filter_map(|day| self.daily_stats.get(&day).map(|stats| (day, stats)))
+            .collect()
+    }
+
+    /// Like `get_daily_stats`, rolled up into weeks of seven day indices
+    /// (week `n` covers days `7n` to `7n + 6`).
+    pub fn get_weekly_stats(&self, from_week: u64, to_week: u64) -> Vec<(u64, PeriodStats)> {
+        assert_period_range(from_week, to_week, MAX_STATS_WEEKS);
+        (from_week..=to_week)
+            .filter_map(|week| {
+                let days = self.get_daily_stats(week * 7, week * 7 + 6);
+                if days.is_empty() {
+                    return None;
+                }
+                let mut total = PeriodStats::default();
+                days.iter().for_each(|(_, stats)| total.add(stats));
+                Some((week, total))
+            })
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn record_option_redemption(
+        &mut self,
+        account_id: &AccountId,
+        option_id: &String,
+        amount: Balance,
+    ) {
+        let mut stats = self.get_option_stats(option_id.clone());
+        stats.redemptions_count += 1;
+        stats.total_tokens_spent = U128(stats.total_tokens_spent.0 + amount);
+        self.option_stats.insert(option_id, &stats);
+        let key = (account_id.clone(), option_id.clone());
+        let count = self.account_redemption_counts.get(&key).unwrap_or(0);
+        if self.delivered_redemption_counts.get(&key).is_none() {
+            // Starts from the redemptions before this one; see
+            // `delivered_redemptions`.
+            self.delivered_redemption_counts.insert(&key, &count);
+        }
+        self.account_redemption_counts.insert(&key, &(count + 1));
+    }
+
+    /// Adds to today's bucket.
+    pub(crate) fn record_daily(&mut self, update: impl FnOnce(&mut PeriodStats)) {
+        let day = env::block_timestamp() / NANOS_PER_DAY;
+        let mut stats = self.daily_stats.get(&day).unwrap_or_default();
+        update(&mut stats);
+        self.daily_stats.insert(&day, &stats);
+    }
+
+    pub(crate) fn record_daily_mint(&mut self, amount: Balance) {
+        if amount > 0 {
+            self.record_daily(|stats| stats.rewards_minted = U128(stats.rewards_minted.0 + amount));
+        }
+    }
+
+    pub(crate) fn revert_option_redemption(
+        &mut self,
+        account_id: &AccountId,
+        option_id: &String,
+        amount: Balance,
+    ) {
+        let mut stats = self.get_option_stats(option_id.clone());
+        stats.redemptions_count = stats.redemptions_count.saturating_sub(1);
+        stats.total_tokens_spent = U128(stats.total_tokens_spent.0.saturating_sub(amount));
+        self.option_stats.insert(option_id, &stats);
+        let key = (account_id.clone(), option_id.clone());
+        let count = self.account_redemption_counts.get(&key).unwrap_or(0);
+        self.account_redemption_counts.insert(&key, &count.saturating_sub(1));
+    }
+}
+
+fn assert_period_range(from: u64, to: u64, max: u64) {
+    assert!(from <= to, "Range is empty");
+    assert!(to - from < max, "At most {} periods can be queried at once", max);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::PromiseResult;
+
+    fn redeem(contract: &mut FoodBankToken, result: PromiseResult) {
+        redeem_option(contract, "supplier_discount", 100, result);
+    }
+
+    fn redeem_option(
+        contract: &mut FoodBankToken,
+        option_id: &str,
+        amount: Balance,
+        result: PromiseResult,
+    ) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens(option_id.to_string(), U128(amount), None);
+        resolve_callback(result);
+        contract.on_redemption_resolved(
+            food_bank(),
+            option_id.to_string(),
+            last_receipt_id(contract),
+            token_charge(amount),
+        );
+    }
+
+    #[test]
+    fn counters_follow_a_scripted_sequence() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_treasury(Some(other_food_bank()), 1_000);
+        for _ in 0..3 {
+            contribute(&mut contract, &food_bank(), 100);
+        }
+        redeem(&mut contract, PromiseResult::Successful(vec![]));
+        redeem(&mut contract, PromiseResult::Failed);
+
+        set_caller(owner(), 0);
+        contract.void_contribution(food_bank(), 0);
+
+        assert_eq!(
+            contract.get_program_stats(),
+            ProgramStats {
+                total_rewards_minted: U128(330),
+                total_tokens_redeemed: U128(100),
+                total_contributions_recorded: 3,
+                total_redemptions: 1,
+                total_rewards_clawed_back: U128(100),
+                total_donated_near: U128(0),
+            }
+        );
+        assert_eq!(contract.get_total_supply().0, INITIAL_SUPPLY + 330 - 100 - 100);
+    }
+
+    #[test]
+    fn option_stats_count_redemptions_and_refunds() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 1_000);
+        redeem(&mut contract, PromiseResult::Successful(vec![]));
+        redeem_option(&mut contract, "supplier_discount", 120, PromiseResult::Successful(vec![]));
+        assert_eq!(
+            contract.get_option_stats("supplier_discount".to_string()),
+            OptionStats {
+                redemptions_count: 2,
+                total_tokens_spent: U128(220),
+            }
+        );
+
+        // The refund takes the failed redemption back out.
+        redeem(&mut contract, PromiseResult::Failed);
+        let stats = contract.get_option_stats("supplier_discount".to_string());
+        assert_eq!(stats.redemptions_count, 2);
+        assert_eq!(stats.total_tokens_spent.0, 220);
+    }
+
+    #[test]
+    fn option_stats_survive_option_updates() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 1_000);
+        redeem(&mut contract, PromiseResult::Successful(vec![]));
+        set_caller(owner(), ONE_NEAR);
+        contract.update_redemption_option(
+            "supplier_discount".to_string(),
+            Some(U128(80)),
+            Some("15% discount on supplier purchases".to_string()),
+            None,
+        );
+        assert_eq!(contract.get_option_stats("supplier_discount".to_string()).redemptions_count, 1);
+    }
+
+    #[test]
+    fn options_are_ranked_by_redemptions() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 1_000);
+        redeem_option(&mut contract, "analytics_access", 200, PromiseResult::Successful(vec![]));
+        redeem_option(&mut contract, "analytics_access", 200, PromiseResult::Successful(vec![]));
+        redeem(&mut contract, PromiseResult::Successful(vec![]));
+
+        let ranked = contract.get_options_by_popularity(2);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].0, "analytics_access");
+        assert_eq!(ranked[0].1.redemptions_count, 2);
+        assert_eq!(ranked[1].0, "supplier_discount");
+    }
+
+    #[test]
+    fn daily_stats_split_at_midnight() {
+        let mut contract = setup_contract();
+        contract.mint(food_bank(), 1_000);
+        advance_time(3 * NANOS_PER_DAY - 1);
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(1);
+        contribute(&mut contract, &food_bank(), 60);
+        redeem(&mut contract, PromiseResult::Successful(vec![]));
+        // Refunded redemptions aren't counted.
+        redeem(&mut contract, PromiseResult::Failed);
+
+        let days = contract.get_daily_stats(0, 10);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].0, 2);
+        assert_eq!(days[0].1.contributions, 1);
+        assert_eq!(days[0].1.rewards_minted.0, 5);
+        assert_eq!(days[0].1.redemptions, 0);
+        assert_eq!(
+            days[1],
+            (
+                3,
+                PeriodStats {
+                    contributions: 1,
+                    rewards_minted: U128(3),
+                    redemptions: 1,
+                    tokens_redeemed: U128(100),
+                }
+            )
+        );
+        assert!(contract.get_daily_stats(4, 10).is_empty());
+    }
+
+    #[test]
+    fn weekly_stats_roll_up_days() {
+        let mut contract = setup_contract();
+        advance_time(7 * NANOS_PER_DAY);
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(6 * NANOS_PER_DAY);
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(NANOS_PER_DAY);
+        contribute(&mut contract, &food_bank(), 60);
+
+        let weeks = contract.get_weekly_stats(0, 2);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!((weeks[0].0, weeks[0].1.contributions), (1, 2));
+        assert_eq!(weeks[0].1.rewards_minted.0, 10);
+        assert_eq!((weeks[1].0, weeks[1].1.contributions), (2, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "At most 90 periods can be queried at once")]
+    fn daily_range_is_capped() {
+        let contract = setup_contract();
+        contract.get_daily_stats(0, MAX_STATS_DAYS);
+    }
+}
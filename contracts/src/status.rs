@@ -0,0 +1,116 @@
+//! One cheap call for uptime checks and the dashboard footer. Everything
+//! here is a stored flag or counter, so reading it never walks a
+//! collection.
+
+use near_sdk::json_types::U128;
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+/// Field names are relied on by monitors; rename them only with a new
+/// view.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractStatus {
+    pub version: String,
+    pub paused: bool,
+    pub minting_paused: bool,
+    /// Tokens can't move between accounts while the contract is paused.
+    pub transfers_enabled: bool,
+    pub total_supply: U128,
+    pub participant_count: u64,
+    pub option_count: u64,
+    /// Redemptions waiting for their benefit call to resolve.
+    pub pending_redemptions: u64,
+    pub last_contribution_timestamp: Option<u64>,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    // View functions
+    pub fn get_status(&self) -> ContractStatus {
+        ContractStatus {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            paused: self.paused,
+            minting_paused: self.minting_paused,
+            transfers_enabled: !self.paused,
+            total_supply: U128(self.total_supply),
+            participant_count: self.participants.len(),
+            option_count: self.redemption_options.len(),
+            pending_redemptions: self.pending_redemptions,
+            last_contribution_timestamp: self.last_contribution_timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::{env, PromiseResult};
+
+    #[test]
+    fn json_field_names_are_pinned() {
+        let contract = setup_contract();
+        let status = near_sdk::serde_json::to_value(contract.get_status()).unwrap();
+        let mut fields: Vec<&str> = status.as_object().unwrap().keys().map(String::as_str).collect();
+        fields.sort_unstable();
+        assert_eq!(
+            fields,
+            [
+                "last_contribution_timestamp",
+                "minting_paused",
+                "option_count",
+                "participant_count",
+                "paused",
+                "pending_redemptions",
+                "total_supply",
+                "transfers_enabled",
+                "version",
+            ]
+        );
+        assert_eq!(status["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(status["total_supply"], INITIAL_SUPPLY.to_string());
+    }
+
+    #[test]
+    fn status_follows_contributions_and_redemptions() {
+        let mut contract = setup_contract();
+        let status = contract.get_status();
+        assert_eq!(status.participant_count, 0);
+        assert_eq!(status.option_count, 3);
+        assert_eq!(status.last_contribution_timestamp, None);
+
+        contribute(&mut contract, &food_bank(), 100);
+        let status = contract.get_status();
+        assert_eq!(status.participant_count, 1);
+        assert_eq!(status.last_contribution_timestamp, Some(env::block_timestamp()));
+
+        contract.mint(food_bank(), 100);
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+        assert_eq!(contract.get_status().pending_redemptions, 1);
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let receipt_id = last_receipt_id(&contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "supplier_discount".to_string(),
+            receipt_id,
+            token_charge(100),
+        );
+        assert_eq!(contract.get_status().pending_redemptions, 0);
+    }
+
+    #[test]
+    fn pause_flags_are_reported() {
+        let mut contract = setup_contract();
+        contract.set_minting_paused(true);
+        let status = contract.get_status();
+        assert!(!status.paused && status.minting_paused && status.transfers_enabled);
+
+        contract.pause();
+        let status = contract.get_status();
+        assert!(status.paused && !status.transfers_enabled);
+    }
+}
@@ -0,0 +1,270 @@
+//! Prepaid storage. Methods that grow the contract's storage charge the
+//! caller for it through `charge_storage`: from the attached deposit when
+//! it covers the cost, otherwise the shortfall is drawn from the caller's
+//! storage credit. Admins who add options, promos and translations often
+//! can deposit credit once instead of attaching NEAR to every call. Credit
+//! is held apart from `withdraw_near`, and its holder can take back what
+//! is left. Council actions that add entries are paid from their
+//! proposer's credit.
+
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, near_bindgen, AccountId, Balance, Promise, StorageUsage};
+
+use crate::events::{Event, StorageCreditDeposited, StorageCreditWithdrawn};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Adds the attached deposit, less the storage the credit entry itself
+    /// takes, to the caller's credit.
+    #[payable]
+    pub fn deposit_storage_credit(&mut self) -> U128 {
+        U128(self.add_storage_credit(&env::predecessor_account_id(), env::attached_deposit()))
+    }
+
+    #[payable]
+    pub fn withdraw_storage_credit(&mut self, amount: U128) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let credit = self.storage_credits.get(&account_id).unwrap_or(0);
+        assert!(
+            amount.0 <= credit,
+            "Only {} yoctoNEAR of storage credit is available",
+            credit
+        );
+        self.set_storage_credit(&account_id, credit - amount.0);
+        StorageCreditWithdrawn {
+            account_id: &account_id,
+            amount,
+            credit: U128(credit - amount.0),
+        }
+        .emit();
+        Promise::new(account_id).transfer(amount.0)
+    }
+
+    // View functions
+    pub fn get_storage_credit(&self, account_id: AccountId) -> U128 {
+        U128(self.storage_credits.get(&account_id).unwrap_or(0))
+    }
+}
+
+impl FoodBankToken {
+    /// Adds `deposit`, less the storage a new credit entry takes, to the
+    /// account's credit and returns the new credit.
+    pub(crate) fn add_storage_credit(&mut self, account_id: &AccountId, deposit: Balance) -> Balance {
+        let initial_storage = env::storage_usage();
+        let credit = self.storage_credits.get(account_id).unwrap_or(0);
+        self.storage_credits.insert(account_id, &credit);
+        let entry_cost = storage_cost(initial_storage);
+        assert!(
+            deposit > entry_cost,
+            "Must attach more than {} yoctoNEAR to open a storage credit",
+            entry_cost
+        );
+        let credit = credit + deposit - entry_cost;
+        self.storage_credits.insert(account_id, &credit);
+        self.total_storage_credit += deposit - entry_cost;
+        StorageCreditDeposited {
+            account_id,
+            amount: U128(deposit - entry_cost),
+            credit: U128(credit),
+        }
+        .emit();
+        credit
+    }
+
+    /// Takes the cost of storage added since `initial_storage`, plus any
+    /// `extra` NEAR spent on the caller's behalf, out of the attached deposit
+    /// and refunds the rest to the caller. A deposit that falls short is
+    /// topped up from the caller's storage credit; the panic names what the
+    /// caller must attach given the credit they hold. Inside an executed
+    /// council action the proposer's credit pays instead, since nothing is
+    /// attached to `execute_action`.
+    pub(crate) fn charge_storage(&mut self, initial_storage: StorageUsage, extra: Balance) {
+        let required = storage_cost(initial_storage) + extra;
+        if let Some(proposer) = self.executing_council_action.clone() {
+            let credit = self.storage_credits.get(&proposer).unwrap_or(0);
+            assert!(
+                credit >= required,
+                "Proposer {} needs {} yoctoNEAR of storage credit to cover storage",
+                proposer,
+                required
+            );
+            self.set_storage_credit(&proposer, credit - required);
+            return;
+        }
+        let attached = env::attached_deposit();
+        let account_id = env::predecessor_account_id();
+        if attached >= required {
+            let refund = attached - required;
+            if refund > 0 {
+                Promise::new(account_id).transfer(refund);
+            }
+            return;
+        }
+        let credit = self.storage_credits.get(&account_id).unwrap_or(0);
+        let shortfall = required - attached;
+        assert!(
+            credit >= shortfall,
+            "Must attach {} yoctoNEAR to cover storage",
+            required - credit
+        );
+        self.set_storage_credit(&account_id, credit - shortfall);
+    }
+
+    /// An emptied credit keeps its entry, so the next deposit doesn't pay
+    /// for it again.
+    fn set_storage_credit(&mut self, account_id: &AccountId, credit: Balance) {
+        let previous = self.storage_credits.get(account_id).unwrap_or(0);
+        self.storage_credits.insert(account_id, &credit);
+        self.total_storage_credit = self.total_storage_credit + credit - previous;
+    }
+}
+
+/// The cost of the storage added since `initial_storage`.
+pub(crate) fn storage_cost(initial_storage: StorageUsage) -> Balance {
+    let used = env::storage_usage().saturating_sub(initial_storage);
+    Balance::from(used) * env::storage_byte_cost()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::get_created_receipts;
+
+    const LONG_DESCRIPTION: usize = 1_000;
+
+    fn add_option(contract: &mut FoodBankToken, deposit: Balance) {
+        set_caller(owner(), deposit);
+        contract.add_redemption_option(
+            "pantry_restock".to_string(),
+            "Pantry restock".to_string(),
+            U128(100),
+            "x".repeat(LONG_DESCRIPTION),
+            None,
+        );
+    }
+
+    /// What adding the option costs, measured on a contract that is thrown
+    /// away with the mocked storage.
+    fn option_cost() -> Balance {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        let initial_storage = env::storage_usage();
+        contract.add_redemption_option(
+            "pantry_restock".to_string(),
+            "Pantry restock".to_string(),
+            U128(100),
+            "x".repeat(LONG_DESCRIPTION),
+            None,
+        );
+        let cost = storage_cost(initial_storage);
+        near_sdk::mock::with_mocked_blockchain(|blockchain| blockchain.take_storage());
+        cost
+    }
+
+    fn with_credit(contract: &mut FoodBankToken, deposit: Balance) -> Balance {
+        set_caller(owner(), deposit);
+        contract.deposit_storage_credit().0
+    }
+
+    #[test]
+    fn the_excess_deposit_is_refunded() {
+        let cost = option_cost();
+        let mut contract = setup_contract();
+        add_option(&mut contract, ONE_NEAR);
+        let refund = get_created_receipts().pop().unwrap();
+        assert_eq!(refund.receiver_id, owner());
+        assert_eq!(refund.actions, vec![VmAction::Transfer { deposit: ONE_NEAR - cost }]);
+    }
+
+    #[test]
+    fn an_exact_deposit_refunds_nothing() {
+        let cost = option_cost();
+        let mut contract = setup_contract();
+        add_option(&mut contract, cost);
+        assert!(get_created_receipts().is_empty());
+        assert_eq!(contract.get_storage_credit(owner()).0, 0);
+    }
+
+    #[test]
+    fn the_panic_names_the_required_deposit() {
+        let cost = option_cost();
+        let mut contract = setup_contract();
+        let expected = format!("Must attach {} yoctoNEAR to cover storage", cost);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            add_option(&mut contract, cost - 1);
+        }));
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains(&expected), "{}", message);
+    }
+
+    #[test]
+    fn a_short_deposit_is_topped_up_from_credit() {
+        let cost = option_cost();
+        let mut contract = setup_contract();
+        let credit = with_credit(&mut contract, ONE_NEAR);
+        add_option(&mut contract, cost / 2);
+        assert_eq!(contract.get_storage_credit(owner()).0, credit - (cost - cost / 2));
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    fn the_panic_accounts_for_the_credit_held() {
+        let cost = option_cost();
+        let mut contract = setup_contract();
+        let credit = with_credit(&mut contract, cost / 2);
+        let expected = format!("Must attach {} yoctoNEAR to cover storage", cost - credit);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            add_option(&mut contract, 0);
+        }));
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains(&expected), "{}", message);
+    }
+
+    #[test]
+    fn promos_are_charged_for_their_storage() {
+        let mut contract = setup_contract();
+        let credit = with_credit(&mut contract, ONE_NEAR);
+        set_caller(owner(), 0);
+        contract.create_promo(
+            near_sdk::json_types::Base64VecU8(vec![7; 32]),
+            "analytics_access".to_string(),
+            1_000,
+            5,
+            env::block_timestamp() + 1,
+        );
+        assert!(contract.get_storage_credit(owner()).0 < credit);
+    }
+
+    #[test]
+    fn credit_can_be_withdrawn() {
+        let mut contract = setup_contract();
+        let credit = with_credit(&mut contract, ONE_NEAR);
+        set_caller(owner(), 1);
+        contract.withdraw_storage_credit(U128(credit));
+        assert_eq!(contract.get_storage_credit(owner()).0, 0);
+        let transfer = get_created_receipts().pop().unwrap();
+        assert_eq!(transfer.actions, vec![VmAction::Transfer { deposit: credit }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "of storage credit is available")]
+    fn credit_cannot_be_overdrawn() {
+        let mut contract = setup_contract();
+        let credit = with_credit(&mut contract, ONE_NEAR);
+        set_caller(owner(), 1);
+        contract.withdraw_storage_credit(U128(credit + 1));
+    }
+
+    #[test]
+    fn credit_is_not_available_to_withdraw_near() {
+        let mut contract = setup_contract();
+        let credit = with_credit(&mut contract, ONE_NEAR);
+        let available = contract.get_available_near().0;
+        contract.total_storage_credit = 0;
+        assert_eq!(contract.get_available_near().0 - available, credit);
+    }
+}
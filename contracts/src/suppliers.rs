@@ -0,0 +1,285 @@
+//! Suppliers that honor supplier discounts. An option routed to a supplier
+//! sends its benefit call to the supplier's contract instead of the
+//! redeeming food bank, with the food bank and the redemption's receipt in
+//! the arguments. A failed call refunds the redemption like any other
+//! benefit. Options can't be redeemed while their supplier is inactive.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, serde_json, AccountId};
+
+use crate::events::{Event, OptionSupplierSet, SupplierDeactivated, SupplierRegistered};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const MAX_SUPPLIER_NAME_LENGTH: usize = 64;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SupplierInfo {
+    pub name: String,
+    /// Discount the supplier gives on a redemption, passed along in the
+    /// benefit call.
+    pub discount_bps: u16,
+    pub active: bool,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Adds a supplier or updates one, leaving it active. The attached
+    /// deposit, or the caller's storage credit, pays for the storage.
+    #[payable]
+    pub fn register_supplier(&mut self, account_id: AccountId, name: String, discount_bps: u16) {
+        self.assert_owner_action();
+        assert!(
+            !name.is_empty() && name.len() <= MAX_SUPPLIER_NAME_LENGTH,
+            "Supplier name must be 1 to {} bytes",
+            MAX_SUPPLIER_NAME_LENGTH
+        );
+        assert!(discount_bps <= 10_000, "Discount must be <= 10000 bps");
+        let initial_storage = env::storage_usage();
+        SupplierRegistered {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+            name: &name,
+            discount_bps,
+        }
+        .record(&mut self.event_buffer);
+        self.suppliers.insert(
+            &account_id,
+            &SupplierInfo {
+                name,
+                discount_bps,
+                active: true,
+            },
+        );
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Options routed to the supplier stop being redeemable until it is
+    /// registered again. Calls already in flight still resolve.
+    pub fn deactivate_supplier(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        let mut supplier = self.suppliers.get(&account_id).expect("Supplier not found");
+        assert!(supplier.active, "Supplier is already inactive");
+        supplier.active = false;
+        self.suppliers.insert(&account_id, &supplier);
+        SupplierDeactivated {
+            actor: &env::predecessor_account_id(),
+            account_id: &account_id,
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    /// Routes the benefit call of `option_id` to `supplier`; `None` sends
+    /// it back to the option's own benefit contract.
+    pub fn set_option_supplier(&mut self, option_id: String, supplier: Option<AccountId>) {
+        self.assert_owner_action();
+        let option = self.redemption_option(&option_id).expect("Redemption option not found");
+        assert!(
+            !option.benefit_method.is_empty(),
+            "Option {} has no benefit call to route",
+            option_id
+        );
+        match &supplier {
+            Some(account_id) => {
+                assert!(self.suppliers.get(account_id).is_some(), "Supplier not found");
+                self.option_suppliers.insert(&option_id, account_id);
+            }
+            None => {
+                self.option_suppliers.remove(&option_id);
+            }
+        }
+        OptionSupplierSet {
+            actor: &env::predecessor_account_id(),
+            option_id: &option_id,
+            supplier: supplier.as_ref(),
+        }
+        .record(&mut self.event_buffer);
+    }
+
+    // View functions
+    pub fn get_supplier(&self, account_id: AccountId) -> Option<SupplierInfo> {
+        self.suppliers.get(&account_id)
+    }
+
+    /// Inactive suppliers included.
+    pub fn get_suppliers(&self, from_index: u64, limit: u64) -> Vec<(AccountId, SupplierInfo)> {
+        self.suppliers
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    pub fn get_option_supplier(&self, option_id: String) -> Option<AccountId> {
+        self.option_suppliers.get(&option_id)
+    }
+}
+
+impl FoodBankToken {
+    /// The supplier `option_id` is routed to, unless it can't serve
+    /// redemptions right now.
+    pub(crate) fn inactive_option_supplier(&self, option_id: &String) -> Option<AccountId> {
+        let supplier = self.option_suppliers.get(option_id)?;
+        let active = self.suppliers.get(&supplier).is_some_and(|info| info.active);
+        (!active).then_some(supplier)
+    }
+}
+
+/// Arguments of a benefit call routed to a supplier without an args
+/// template of its own.
+pub(crate) fn supplier_benefit_args(food_bank: &AccountId, receipt_id: u64, discount_bps: u16) -> Vec<u8> {
+    serde_json::json!({
+        "account_id": food_bank,
+        "receipt_id": receipt_id,
+        "discount_bps": discount_bps,
+    })
+    .to_string()
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redemptions::RedeemCheck;
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+    use near_sdk::PromiseResult;
+
+    fn supplier() -> AccountId {
+        accounts(4)
+    }
+
+    fn with_supplier() -> FoodBankToken {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.register_supplier(supplier(), "Fresh Farms".to_string(), 1_500);
+        set_caller(owner(), 0);
+        contract.set_option_supplier("supplier_discount".to_string(), Some(supplier()));
+        contract.mint(food_bank(), 100);
+        contract
+    }
+
+    fn redeem(contract: &mut FoodBankToken) {
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    fn the_benefit_call_goes_to_the_supplier() {
+        let mut contract = with_supplier();
+        let receipt_id = contract.next_receipt_id;
+        redeem(&mut contract);
+
+        let call = get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == supplier())
+            .expect("no call to the supplier");
+        let VmAction::FunctionCall { function_name, args, .. } = &call.actions[0] else {
+            panic!("expected a function call");
+        };
+        assert_eq!(function_name, "apply_supplier_discount");
+        let args: serde_json::Value = serde_json::from_slice(args).unwrap();
+        assert_eq!(
+            args,
+            serde_json::json!({"account_id": food_bank(), "receipt_id": receipt_id, "discount_bps": 1_500})
+        );
+    }
+
+    #[test]
+    fn a_failed_supplier_call_refunds_the_food_bank() {
+        let mut contract = with_supplier();
+        let receipt_id = contract.next_receipt_id;
+        redeem(&mut contract);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        resolve_callback(PromiseResult::Failed);
+        let charge = crate::fulfillment::RedemptionCharge {
+            amount: U128(100),
+            list_price: U128(100),
+            near_copay: U128(0),
+            promo_code_hash: None,
+        };
+        assert!(!contract.on_redemption_resolved(food_bank(), "supplier_discount".to_string(), receipt_id, charge));
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+    }
+
+    #[test]
+    fn an_inactive_supplier_blocks_the_redemption() {
+        let mut contract = with_supplier();
+        contract.deactivate_supplier(supplier());
+        assert_eq!(
+            contract.can_redeem(food_bank(), "supplier_discount".to_string()),
+            RedeemCheck::SupplierInactive { supplier: supplier() }
+        );
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| redeem(&mut contract)));
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("Supplier eugene is not active"), "{}", message);
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+    }
+
+    #[test]
+    fn registering_again_reactivates_the_supplier() {
+        let mut contract = with_supplier();
+        contract.deactivate_supplier(supplier());
+        set_caller(owner(), ONE_NEAR);
+        contract.register_supplier(supplier(), "Fresh Farms".to_string(), 2_000);
+        assert_eq!(
+            contract.get_supplier(supplier()),
+            Some(SupplierInfo {
+                name: "Fresh Farms".to_string(),
+                discount_bps: 2_000,
+                active: true,
+            })
+        );
+        redeem(&mut contract);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+    }
+
+    #[test]
+    fn options_without_a_supplier_still_call_the_redeemer() {
+        let mut contract = with_supplier();
+        set_caller(owner(), 0);
+        contract.set_option_supplier("supplier_discount".to_string(), None);
+        redeem(&mut contract);
+        assert!(get_created_receipts().iter().any(|receipt| receipt.receiver_id == food_bank()));
+        assert_eq!(contract.get_option_supplier("supplier_discount".to_string()), None);
+    }
+
+    #[test]
+    fn suppliers_are_listed_in_pages() {
+        let mut contract = with_supplier();
+        set_caller(owner(), ONE_NEAR);
+        contract.register_supplier(accounts(5), "Grain Co".to_string(), 500);
+        set_caller(owner(), 0);
+        contract.deactivate_supplier(accounts(5));
+        let suppliers = contract.get_suppliers(0, 10);
+        assert_eq!(suppliers.len(), 2);
+        assert!(!suppliers[1].1.active);
+        assert_eq!(contract.get_suppliers(1, 10)[0].0, accounts(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "Supplier not found")]
+    fn options_can_only_be_routed_to_registered_suppliers() {
+        let mut contract = setup_contract();
+        contract.set_option_supplier("supplier_discount".to_string(), Some(supplier()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Discount must be <= 10000 bps")]
+    fn discounts_are_bounded() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.register_supplier(supplier(), "Fresh Farms".to_string(), 10_001);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn the_registry_is_owner_managed() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), ONE_NEAR);
+        contract.register_supplier(supplier(), "Fresh Farms".to_string(), 1_500);
+    }
+}
@@ -0,0 +1,210 @@
+//! Loyalty discounts on redemptions for long-standing food banks. Tenure is
+//! counted from an account's first recorded contribution. Accounts that
+//! contributed before first contributions were tracked are backfilled from
+//! their history the first time they are looked at.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_TENURE_DISCOUNTS: usize = 10;
+const YEAR_NS: u64 = 365 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TenureDiscount {
+    /// Nanoseconds since the first contribution at which the discount starts.
+    pub min_tenure: u64,
+    pub discount_bps: u16,
+}
+
+pub(crate) fn default_tenure_discounts() -> Vec<TenureDiscount> {
+    vec![TenureDiscount { min_tenure: YEAR_NS, discount_bps: 1_000 }]
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Discounts must be sorted by `min_tenure`; an empty list turns
+    /// loyalty discounts off.
+    pub fn set_tenure_discounts(&mut self, discounts: Vec<TenureDiscount>) {
+        self.assert_owner_action();
+        assert!(
+            discounts.len() <= MAX_TENURE_DISCOUNTS,
+            "At most {} tenure discounts",
+            MAX_TENURE_DISCOUNTS
+        );
+        for window in discounts.windows(2) {
+            assert!(
+                window[0].min_tenure < window[1].min_tenure,
+                "Tenure discounts must be sorted by min_tenure without duplicates"
+            );
+        }
+        for discount in &discounts {
+            assert!(
+                discount.discount_bps <= 10_000,
+                "Tenure discounts must be <= 10000 bps"
+            );
+        }
+        self.tenure_discounts = discounts;
+    }
+
+    // View functions
+    pub fn get_tenure_discounts(&self) -> Vec<TenureDiscount> {
+        self.tenure_discounts.clone()
+    }
+
+    pub fn get_first_contribution_at(&self, account_id: AccountId) -> Option<u64> {
+        self.first_contribution_at(&account_id)
+    }
+
+    pub fn get_tenure_discount_bps(&self, account_id: AccountId) -> u16 {
+        self.tenure_discount_bps(&account_id)
+    }
+}
+
+impl FoodBankToken {
+    /// Block timestamp of the account's first contribution, falling back to
+    /// the metrics timestamp for contributions recorded before block
+    /// timestamps were kept.
+    pub(crate) fn first_contribution_at(&self, account_id: &AccountId) -> Option<u64> {
+        self.first_contributions
+            .get(account_id)
+            .or_else(|| self.contribution_recorded_at.get(&(account_id.clone(), 0)))
+            .or_else(|| {
+                self.stored_contributions(account_id)
+                    .into_iter()
+                    .next()
+                    .map(|contribution| contribution.into_latest().timestamp)
+            })
+    }
+
+    /// Stores the account's first contribution time if it isn't yet.
+    pub(crate) fn backfill_first_contribution(&mut self, account_id: &AccountId) {
+        if self.first_contributions.get(account_id).is_some() {
+            return;
+        }
+        if let Some(at) = self.first_contribution_at(account_id) {
+            self.first_contributions.insert(account_id, &at);
+        }
+    }
+
+    pub(crate) fn tenure_discount_bps(&self, account_id: &AccountId) -> u16 {
+        let Some(first) = self.first_contribution_at(account_id) else {
+            return 0;
+        };
+        let tenure = env::block_timestamp().saturating_sub(first);
+        self.tenure_discounts
+            .iter()
+            .rev()
+            .find(|discount| tenure >= discount.min_tenure)
+            .map_or(0, |discount| discount.discount_bps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fulfillment::RedemptionCharge;
+    use crate::test_utils::*;
+    use near_sdk::json_types::{Base64VecU8, U128};
+    use near_sdk::PromiseResult;
+
+    /// `food_bank` a year after its first contribution, holding 1005 tokens.
+    fn veteran() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(YEAR_NS);
+        contract.mint(food_bank(), 1_000);
+        contract
+    }
+
+    #[test]
+    fn a_year_of_tenure_earns_ten_percent_off() {
+        let mut contract = setup_contract();
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(YEAR_NS - 1);
+        assert_eq!(contract.get_tenure_discount_bps(food_bank()), 0);
+        advance_time(1);
+        assert_eq!(contract.get_tenure_discount_bps(food_bank()), 1_000);
+        assert_eq!(contract.get_tenure_discount_bps(other_food_bank()), 0);
+    }
+
+    #[test]
+    fn can_redeem_shows_the_discounted_price() {
+        let contract = veteran();
+        assert_eq!(
+            contract.can_redeem(food_bank(), "analytics_access".to_string()),
+            crate::redemptions::RedeemCheck::Ok { cost: U128(180) }
+        );
+        let options = contract.get_affordable_options(food_bank(), 0, 10);
+        let (_, _, eligibility) = options.iter().find(|(id, _, _)| id == "analytics_access").unwrap();
+        assert_eq!(eligibility.price.0, 180);
+    }
+
+    #[test]
+    fn receipts_record_list_price_and_price_paid() {
+        let mut contract = veteran();
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("analytics_access".to_string(), U128(180), None);
+        assert_eq!(contract.get_balance(food_bank()).0, 825);
+
+        resolve_callback(PromiseResult::Successful(vec![]));
+        let receipt_id = last_receipt_id(&contract);
+        contract.on_redemption_resolved(
+            food_bank(),
+            "analytics_access".to_string(),
+            receipt_id,
+            RedemptionCharge {
+                list_price: U128(200),
+                ..token_charge(180)
+            },
+        );
+        let receipt = contract.get_receipt(receipt_id).unwrap();
+        assert_eq!(receipt.list_price.0, 200);
+        assert_eq!(receipt.amount.0, 180);
+    }
+
+    #[test]
+    fn tenure_and_promo_discounts_multiply() {
+        let mut contract = veteran();
+        let code_hash = Base64VecU8(env::sha256(b"SPRING20"));
+        let expiry = env::block_timestamp() + 1_000;
+        contract.create_promo(code_hash, "analytics_access".to_string(), 2_000, 10, expiry);
+        // 200 less 10% is 180, less 20% of that is 144.
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("analytics_access".to_string(), U128(144), Some("SPRING20".to_string()));
+        assert_eq!(contract.get_balance(food_bank()).0, 861);
+    }
+
+    #[test]
+    fn histories_from_before_tracking_are_backfilled() {
+        let mut contract = veteran();
+        let recorded_at = contract.contribution_recorded_at.get(&(food_bank(), 0)).unwrap();
+        contract.first_contributions.remove(&food_bank());
+        assert_eq!(contract.get_first_contribution_at(food_bank()), Some(recorded_at));
+
+        set_caller(food_bank(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(90), None);
+        assert_eq!(contract.first_contributions.get(&food_bank()), Some(recorded_at));
+    }
+
+    #[test]
+    fn later_contributions_keep_the_first_timestamp() {
+        let mut contract = veteran();
+        let first = contract.get_first_contribution_at(food_bank());
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_first_contribution_at(food_bank()), first);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tenure discounts must be sorted by min_tenure without duplicates")]
+    fn unsorted_tenure_discounts_are_rejected() {
+        let mut contract = setup_contract();
+        contract.set_tenure_discounts(vec![
+            TenureDiscount { min_tenure: YEAR_NS, discount_bps: 1_000 },
+            TenureDiscount { min_tenure: YEAR_NS, discount_bps: 2_000 },
+        ]);
+    }
+}
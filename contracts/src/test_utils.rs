@@ -0,0 +1,136 @@
+//! Shared fixtures for the unit tests in this crate.
+
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId, Balance, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+use crate::contributions::ContributionMetricsV2;
+use crate::fulfillment::RedemptionCharge;
+use crate::reward_curve::RewardCurve;
+use crate::timelock::ParamChange;
+use crate::FoodBankToken;
+
+pub use near_sdk::ONE_NEAR;
+
+pub const INITIAL_SUPPLY: Balance = 1_000_000;
+
+pub fn contract_account() -> AccountId {
+    accounts(5)
+}
+
+pub fn owner() -> AccountId {
+    accounts(0)
+}
+
+pub fn food_bank() -> AccountId {
+    accounts(1)
+}
+
+pub fn other_food_bank() -> AccountId {
+    accounts(2)
+}
+
+/// Context with the given predecessor and attached deposit. The mocked block
+/// timestamp carries over from the current context.
+pub fn context(predecessor: AccountId, deposit: Balance) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .block_timestamp(env::block_timestamp())
+        .current_account_id(contract_account())
+        .signer_account_id(predecessor.clone())
+        .predecessor_account_id(predecessor)
+        .attached_deposit(deposit);
+    builder
+}
+
+/// Switches the mocked environment to a new predecessor, keeping storage.
+pub fn set_caller(predecessor: AccountId, deposit: Balance) {
+    testing_env!(context(predecessor, deposit).build());
+}
+
+/// Moves the mocked clock forward, keeping the current caller.
+pub fn advance_time(nanos: u64) {
+    let mut builder = context(env::predecessor_account_id(), env::attached_deposit());
+    builder.block_timestamp(env::block_timestamp() + nanos);
+    testing_env!(builder.build());
+}
+
+/// Calls back into the contract as itself, with `result` as the outcome of
+/// the promise being resolved.
+pub fn resolve_callback(result: PromiseResult) {
+    testing_env!(
+        context(contract_account(), 0).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![result],
+    );
+}
+
+/// Freshly initialized contract owned by `owner()`, with the owner as caller.
+/// Most tests record contributions back to back and spend the rewards right
+/// away, so the minimum interval and the reward lock are lifted.
+pub fn setup_contract() -> FoodBankToken {
+    set_caller(owner(), 0);
+    let mut contract = FoodBankToken::new_default(owner(), U128(INITIAL_SUPPLY));
+    contract.min_contribution_interval = 0;
+    contract.reward_lock_duration = 0;
+    let supplier_discount = "supplier_discount".to_string();
+    let mut option = contract.redemption_option(&supplier_discount).unwrap();
+    option.cooldown_ns = None;
+    contract.store_redemption_option(&supplier_discount, &option);
+    contract
+}
+
+pub fn metrics(
+    data_quality: u8,
+    model_improvement: u8,
+    participation_frequency: u8,
+) -> ContributionMetricsV2 {
+    ContributionMetricsV2 {
+        data_quality,
+        model_improvement,
+        participation_frequency,
+        timestamp: 0,
+        evidence_cid: None,
+    }
+}
+
+/// Records a contribution as the owner, leaving the owner as caller.
+pub fn contribute(contract: &mut FoodBankToken, account: &AccountId, score: u8) {
+    set_caller(owner(), ONE_NEAR);
+    contract.record_contribution(account.clone(), metrics(score, score, score), None, None, None, None);
+}
+
+/// Sets the reward rate directly, for tests that don't exercise the timelock.
+pub fn set_reward_rate_bps(contract: &mut FoodBankToken, rate_bps: u16) {
+    contract.reward_rate_bps = rate_bps;
+}
+
+/// Applies the schedule as an executed `ParamChange` would.
+pub fn set_emission_schedule(contract: &mut FoodBankToken, schedule: Vec<(u64, u16)>) {
+    contract.apply_param_change(&ParamChange::EmissionSchedule(schedule));
+}
+
+pub fn set_max_reward_per_contribution(contract: &mut FoodBankToken, max_reward: Balance) {
+    contract.apply_param_change(&ParamChange::MaxRewardPerContribution(U128(max_reward)));
+}
+
+pub fn set_reward_curve(contract: &mut FoodBankToken, curve: RewardCurve) {
+    contract.apply_param_change(&ParamChange::RewardCurve(curve));
+}
+
+/// Receipt reserved by the most recent `redeem_tokens` call.
+pub fn last_receipt_id(contract: &FoodBankToken) -> u64 {
+    contract.next_receipt_id - 1
+}
+
+/// A token-only charge of `amount` at list price.
+pub fn token_charge(amount: Balance) -> RedemptionCharge {
+    RedemptionCharge {
+        amount: U128(amount),
+        list_price: U128(amount),
+        near_copay: U128(0),
+        promo_code_hash: None,
+    }
+}
@@ -0,0 +1,180 @@
+//! Lifetime tiers: a reward multiplier earned by an account's cumulative
+//! contribution score.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{Event, TierReached};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const MAX_TIER_MULTIPLIER_BPS: u16 = 30_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LifetimeTier {
+    pub name: String,
+    /// Lifetime score at which the tier starts.
+    pub min_score: u64,
+    /// 10000 pays the unmodified reward.
+    pub multiplier_bps: u16,
+}
+
+pub(crate) fn default_tiers() -> Vec<LifetimeTier> {
+    vec![
+        LifetimeTier { name: "Bronze".to_string(), min_score: 0, multiplier_bps: 10_000 },
+        LifetimeTier { name: "Silver".to_string(), min_score: 1_000, multiplier_bps: 11_000 },
+        LifetimeTier { name: "Gold".to_string(), min_score: 5_000, multiplier_bps: 12_500 },
+    ]
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Tiers must start at a score of 0 and be sorted by `min_score`.
+    /// Rewards already paid keep the tier they were computed with.
+    pub fn set_tier_config(&mut self, tiers: Vec<LifetimeTier>) {
+        self.assert_owner_action();
+        assert!(
+            tiers.first().is_some_and(|tier| tier.min_score == 0),
+            "The first tier must start at a score of 0"
+        );
+        for window in tiers.windows(2) {
+            assert!(
+                window[0].min_score < window[1].min_score,
+                "Tiers must be sorted by min_score without duplicates"
+            );
+        }
+        for tier in &tiers {
+            assert!(
+                tier.multiplier_bps <= MAX_TIER_MULTIPLIER_BPS,
+                "Multiplier must be <= {} bps",
+                MAX_TIER_MULTIPLIER_BPS
+            );
+        }
+        self.tiers = tiers;
+    }
+
+    // View functions
+    pub fn get_tier(&self, account_id: AccountId) -> LifetimeTier {
+        self.tier_for_score(self.lifetime_score(&account_id)).clone()
+    }
+
+    pub fn get_tier_config(&self) -> Vec<LifetimeTier> {
+        self.tiers.clone()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn tier_for_score(&self, score: u64) -> &LifetimeTier {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| score >= tier.min_score)
+            .expect("The first tier starts at a score of 0")
+    }
+
+    /// Adds `score` to the account's lifetime total, announcing a new tier
+    /// when the total crosses into one.
+    pub(crate) fn add_lifetime_score(&mut self, account_id: &AccountId, score: u64) {
+        let mut stats = self.account_stats.get(account_id).unwrap_or_default();
+        let old_tier = self.tier_for_score(stats.total_score).name.clone();
+        stats.total_score += score;
+        stats.contribution_count += 1;
+        stats.last_contribution_at = env::block_timestamp();
+        self.account_stats.insert(account_id, &stats);
+
+        let new_tier = self.tier_for_score(stats.total_score);
+        if new_tier.name != old_tier {
+            TierReached {
+                account_id,
+                tier: &new_tier.name,
+            }
+            .emit();
+        }
+    }
+
+    fn lifetime_score(&self, account_id: &AccountId) -> u64 {
+        self.account_stats.get(account_id).map_or(0, |stats| stats.total_score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::get_logs;
+
+    fn small_tiers() -> Vec<LifetimeTier> {
+        vec![
+            LifetimeTier { name: "Bronze".to_string(), min_score: 0, multiplier_bps: 10_000 },
+            LifetimeTier { name: "Silver".to_string(), min_score: 150, multiplier_bps: 20_000 },
+        ]
+    }
+
+    #[test]
+    fn tier_applies_from_the_next_contribution() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_tier_config(small_tiers());
+
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_tier(food_bank()).name, "Bronze");
+        contribute(&mut contract, &food_bank(), 100);
+        assert!(get_logs().iter().any(|log| log.contains("\"tier_reached\"")));
+        assert_eq!(contract.get_tier(food_bank()).name, "Silver");
+        // The contribution that crossed the boundary was paid at Bronze.
+        assert_eq!(contract.get_balance(food_bank()).0, 200);
+
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 400);
+        let breakdown = contract.get_reward_breakdown(food_bank(), 2).unwrap();
+        assert_eq!(breakdown.tier, "Silver");
+        assert_eq!(breakdown.tier_multiplier_bps, 20_000);
+    }
+
+    #[test]
+    fn threshold_changes_only_affect_future_rewards() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 100);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_tier(food_bank()).name, "Bronze");
+
+        contract.set_tier_config(small_tiers());
+        assert_eq!(contract.get_tier(food_bank()).name, "Silver");
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 1).unwrap().tier, "Bronze");
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 400);
+    }
+
+    #[test]
+    fn default_tiers_start_at_bronze() {
+        let contract = setup_contract();
+        assert_eq!(contract.get_tier_config(), default_tiers());
+        assert_eq!(contract.get_tier(food_bank()).name, "Bronze");
+    }
+
+    #[test]
+    #[should_panic(expected = "The first tier must start at a score of 0")]
+    fn tiers_must_start_at_zero() {
+        let mut contract = setup_contract();
+        contract.set_tier_config(small_tiers().split_off(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Tiers must be sorted by min_score without duplicates")]
+    fn unsorted_tiers_are_rejected() {
+        let mut contract = setup_contract();
+        let mut tiers = small_tiers();
+        tiers[1].min_score = 0;
+        contract.set_tier_config(tiers);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn tier_config_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.set_tier_config(default_tiers());
+    }
+}
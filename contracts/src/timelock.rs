@@ -0,0 +1,316 @@
+//! Delayed execution of sensitive parameter changes, so watchers have time
+//! to react to a proposal made with a compromised owner key.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{
+    Event, ParamChangeCancelled, ParamChangeExecuted, ParamChangeProposed, ParamChanged,
+};
+use crate::emission::{assert_valid_schedule, MAX_RATE_BPS};
+use crate::reward_curve::RewardCurve;
+use crate::score_thresholds::{assert_valid_cohorts, ThresholdCohort};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 48 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ParamChange {
+    /// Out of 10 000, so 250 is a 2.5% reward rate.
+    RewardRateBps(u16),
+    /// Average score, on the 0–100 scale, a contribution needs to earn a
+    /// reward.
+    MinScoreThreshold(u32),
+    /// Delay in nanoseconds applied to proposals made after this executes.
+    TimelockDelay(u64),
+    /// Replaces the emission schedule; see `emission.rs`.
+    EmissionSchedule(Vec<(u64, u16)>),
+    /// Applies to rewards calculated from then on, including recalculations.
+    RewardCurve(RewardCurve),
+    /// Caps everything one contribution mints: its reward, the onboarding
+    /// bonus, treasury share and referral bonus. 0 removes the cap.
+    MaxRewardPerContribution(U128),
+    /// `None` removes the override; see `rate_overrides.rs`. Contributions
+    /// already recorded keep the rate they were paid at.
+    AccountRateOverride {
+        account_id: AccountId,
+        rate_bps: Option<u16>,
+    },
+    /// Replaces the tenure cohorts; see `score_thresholds.rs`.
+    ThresholdCohorts(Vec<ThresholdCohort>),
+    /// `None` removes the account's override.
+    ThresholdOverride {
+        account_id: AccountId,
+        threshold: Option<u32>,
+    },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingChange {
+    id: u64,
+    change: ParamChange,
+    proposed_at: u64,
+    pub(crate) execute_after: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Queues `change` for execution once the timelock delay has passed.
+    pub fn propose_param_change(&mut self, change: ParamChange) -> u64 {
+        self.assert_owner_action();
+        self.validate_param_change(&change);
+
+        let id = self.next_param_change_id;
+        self.next_param_change_id += 1;
+        let now = env::block_timestamp();
+        let pending = PendingChange {
+            id,
+            change,
+            proposed_at: now,
+            execute_after: now + self.timelock_delay,
+        };
+        ParamChangeProposed {
+            id,
+            change: &pending.change,
+            execute_after: pending.execute_after,
+        }
+        .emit();
+        self.pending_changes.insert(&id, &pending);
+        id
+    }
+
+    /// Applies a queued change. Anyone may execute a change whose delay has
+    /// passed; the owner can still cancel it until then.
+    pub fn execute_param_change(&mut self, proposal_id: u64) {
+        let pending = self.pending_changes.get(&proposal_id)
+            .expect("Parameter change not found");
+        let now = env::block_timestamp();
+        assert!(
+            now >= pending.execute_after,
+            "Timelock has not expired, change is executable at {}",
+            pending.execute_after
+        );
+        self.pending_changes.remove(&proposal_id);
+        self.apply_param_change(&pending.change);
+        ParamChangeExecuted {
+            id: proposal_id,
+            change: &pending.change,
+        }
+        .emit();
+    }
+
+    pub fn cancel_param_change(&mut self, proposal_id: u64) {
+        self.assert_owner_action();
+        let pending = self.pending_changes.remove(&proposal_id)
+            .expect("Parameter change not found");
+        ParamChangeCancelled {
+            id: proposal_id,
+            change: &pending.change,
+        }
+        .emit();
+    }
+
+    // View functions
+    pub fn get_pending_changes(&self) -> Vec<PendingChange> {
+        self.pending_changes.values().collect()
+    }
+
+    pub fn get_timelock_delay(&self) -> u64 {
+        self.timelock_delay
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn validate_param_change(&self, change: &ParamChange) {
+        match change {
+            ParamChange::RewardRateBps(rate_bps) => {
+                assert!(*rate_bps <= MAX_RATE_BPS, "Reward rate must be <= {} bps", MAX_RATE_BPS);
+            }
+            ParamChange::MinScoreThreshold(threshold) => {
+                assert!(*threshold <= 100, "Score threshold must be <= 100");
+            }
+            ParamChange::TimelockDelay(_) => {}
+            ParamChange::EmissionSchedule(schedule) => assert_valid_schedule(schedule),
+            ParamChange::RewardCurve(curve) => curve.validate(),
+            ParamChange::MaxRewardPerContribution(_) => {}
+            ParamChange::AccountRateOverride { rate_bps, .. } => {
+                if let Some(rate_bps) = rate_bps {
+                    assert!(*rate_bps <= MAX_RATE_BPS, "Rate must be <= {} bps", MAX_RATE_BPS);
+                }
+            }
+            ParamChange::ThresholdCohorts(cohorts) => assert_valid_cohorts(cohorts),
+            ParamChange::ThresholdOverride { threshold, .. } => {
+                if let Some(threshold) = threshold {
+                    assert!(*threshold <= 100, "Score threshold must be <= 100");
+                }
+            }
+        }
+    }
+
+    pub(crate) fn apply_param_change(&mut self, change: &ParamChange) {
+        self.validate_param_change(change);
+        let old_value = match change {
+            ParamChange::RewardRateBps(_) => ParamChange::RewardRateBps(self.reward_rate_bps),
+            ParamChange::MinScoreThreshold(_) => ParamChange::MinScoreThreshold(self.min_score_threshold),
+            ParamChange::TimelockDelay(_) => ParamChange::TimelockDelay(self.timelock_delay),
+            ParamChange::EmissionSchedule(_) => ParamChange::EmissionSchedule(self.emission_schedule.clone()),
+            ParamChange::RewardCurve(_) => ParamChange::RewardCurve(self.reward_curve.clone()),
+            ParamChange::MaxRewardPerContribution(_) => {
+                ParamChange::MaxRewardPerContribution(U128(self.max_reward_per_contribution))
+            }
+            ParamChange::AccountRateOverride { account_id, .. } => ParamChange::AccountRateOverride {
+                account_id: account_id.clone(),
+                rate_bps: self.rate_overrides.get(account_id),
+            },
+            ParamChange::ThresholdCohorts(_) => ParamChange::ThresholdCohorts(self.threshold_cohorts.clone()),
+            ParamChange::ThresholdOverride { account_id, .. } => ParamChange::ThresholdOverride {
+                account_id: account_id.clone(),
+                threshold: self.threshold_overrides.get(account_id),
+            },
+        };
+        ParamChanged {
+            actor: &env::predecessor_account_id(),
+            old_value: &old_value,
+            new_value: change,
+        }
+        .record(&mut self.event_buffer);
+        match change {
+            ParamChange::RewardRateBps(rate_bps) => self.reward_rate_bps = *rate_bps,
+            ParamChange::MinScoreThreshold(threshold) => self.min_score_threshold = *threshold,
+            ParamChange::TimelockDelay(delay) => self.timelock_delay = *delay,
+            ParamChange::EmissionSchedule(schedule) => self.emission_schedule = schedule.clone(),
+            ParamChange::RewardCurve(curve) => self.reward_curve = curve.clone(),
+            ParamChange::MaxRewardPerContribution(max_reward) => self.max_reward_per_contribution = max_reward.0,
+            ParamChange::AccountRateOverride { account_id, rate_bps } => match rate_bps {
+                Some(rate_bps) => {
+                    self.rate_overrides.insert(account_id, rate_bps);
+                }
+                None => {
+                    self.rate_overrides.remove(account_id);
+                }
+            },
+            ParamChange::ThresholdCohorts(cohorts) => self.threshold_cohorts = cohorts.clone(),
+            ParamChange::ThresholdOverride { account_id, threshold } => match threshold {
+                Some(threshold) => {
+                    self.threshold_overrides.insert(account_id, threshold);
+                }
+                None => {
+                    self.threshold_overrides.remove(account_id);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn change_executes_after_delay() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+        assert_eq!(contract.get_pending_changes().len(), 1);
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_config().reward_rate_bps, 2_000);
+        assert!(contract.get_pending_changes().is_empty());
+    }
+
+    #[test]
+    fn change_is_executable_exactly_at_the_boundary() {
+        let mut contract = setup_contract();
+        advance_time(1_000);
+        let id = contract.propose_param_change(ParamChange::MinScoreThreshold(40));
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_config().min_score_threshold, 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "Timelock has not expired, change is executable at 172800000001000")]
+    fn change_rejected_before_delay() {
+        let mut contract = setup_contract();
+        advance_time(1_000);
+        let id = contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+
+        advance_time(DEFAULT_TIMELOCK_DELAY - 1);
+        contract.execute_param_change(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Parameter change not found")]
+    fn cancelled_change_cannot_execute() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+        contract.cancel_param_change(id);
+        assert!(contract.get_pending_changes().is_empty());
+
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+    }
+
+    #[test]
+    fn delay_change_applies_to_later_proposals() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(ParamChange::TimelockDelay(10));
+        advance_time(DEFAULT_TIMELOCK_DELAY);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_timelock_delay(), 10);
+
+        let id = contract.propose_param_change(ParamChange::RewardRateBps(900));
+        advance_time(10);
+        contract.execute_param_change(id);
+        assert_eq!(contract.get_config().reward_rate_bps, 900);
+    }
+
+    #[test]
+    fn reward_rate_round_trips_across_the_bps_range() {
+        let mut contract = setup_contract();
+        for rate_bps in [0, 1, 9_999, 10_000] {
+            let id = contract.propose_param_change(ParamChange::RewardRateBps(rate_bps));
+            advance_time(DEFAULT_TIMELOCK_DELAY);
+            contract.execute_param_change(id);
+            assert_eq!(contract.get_reward_rate_bps(), rate_bps);
+            assert_eq!(contract.get_config().reward_rate_bps, rate_bps);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Reward rate must be <= 10000 bps")]
+    fn invalid_change_rejected_at_proposal() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::RewardRateBps(10_001));
+    }
+
+    #[test]
+    #[should_panic(expected = "Score threshold must be <= 100")]
+    fn threshold_is_bounded_by_the_score_scale() {
+        let mut contract = setup_contract();
+        contract.propose_param_change(ParamChange::MinScoreThreshold(101));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn proposals_are_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn cancel_is_owner_only() {
+        let mut contract = setup_contract();
+        let id = contract.propose_param_change(ParamChange::RewardRateBps(2_000));
+        set_caller(food_bank(), 0);
+        contract.cancel_param_change(id);
+    }
+}
@@ -0,0 +1,202 @@
+//! Which accounts may move tokens between each other. Compliance can keep
+//! tokens inside the program by restricting transfers to verified food
+//! banks or to an owner-managed allowlist. Minting and redemptions are not
+//! transfers and ignore the policy.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TransferPolicy {
+    Open,
+    /// Both parties need a verified profile.
+    RegisteredOnly,
+    /// Both parties need to be on the allowlist.
+    AllowlistOnly,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    pub fn set_transfer_policy(&mut self, policy: TransferPolicy) {
+        self.assert_owner_action();
+        self.transfer_policy = policy;
+    }
+
+    /// Only consulted under `AllowlistOnly`, so the list can be prepared
+    /// before switching to it.
+    pub fn add_to_transfer_allowlist(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        self.transfer_allowlist.insert(&account_id);
+    }
+
+    pub fn remove_from_transfer_allowlist(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        assert!(
+            self.transfer_allowlist.remove(&account_id),
+            "Account is not on the transfer allowlist"
+        );
+    }
+
+    // View functions
+    pub fn get_transfer_policy(&self) -> TransferPolicy {
+        self.transfer_policy
+    }
+
+    pub fn get_transfer_allowlist(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        self.transfer_allowlist
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    pub fn is_transfer_allowed(&self, from: AccountId, to: AccountId) -> bool {
+        self.transfer_party_allowed(&from) && self.transfer_party_allowed(&to)
+    }
+}
+
+impl FoodBankToken {
+    fn transfer_party_allowed(&self, account_id: &AccountId) -> bool {
+        match self.transfer_policy {
+            TransferPolicy::Open => true,
+            TransferPolicy::RegisteredOnly => self
+                .profiles
+                .get(account_id)
+                .is_some_and(|profile| profile.verified),
+            TransferPolicy::AllowlistOnly => self.transfer_allowlist.contains(account_id),
+        }
+    }
+
+    /// Called by every path that moves tokens from one account to another.
+    pub(crate) fn assert_transfer_allowed(&self, from: &AccountId, to: &AccountId) {
+        assert!(
+            self.transfer_party_allowed(from),
+            "Sender {} is not allowed to transfer under the {:?} policy",
+            from,
+            self.transfer_policy
+        );
+        assert!(
+            self.transfer_party_allowed(to),
+            "Receiver {} is not allowed to receive transfers under the {:?} policy",
+            to,
+            self.transfer_policy
+        );
+        self.assert_can_receive_transfers(to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::OrgType;
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::accounts;
+
+    fn treasury() -> AccountId {
+        accounts(3)
+    }
+
+    fn with_treasury_balance() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_treasury(Some(treasury()), 0);
+        contract.mint(treasury(), 100);
+        contract
+    }
+
+    fn transfer(contract: &mut FoodBankToken, amount: u128) {
+        set_caller(owner(), 1);
+        contract.treasury_transfer(food_bank(), U128(amount));
+    }
+
+    fn register_verified(contract: &mut FoodBankToken, account_id: AccountId) {
+        set_caller(account_id.clone(), ONE_NEAR);
+        contract.register_profile("Harbor Pantry".to_string(), "north".to_string(), OrgType::FoodBank, None);
+        set_caller(owner(), 0);
+        contract.verify_profile(account_id);
+    }
+
+    #[test]
+    fn open_policy_allows_everyone() {
+        let mut contract = with_treasury_balance();
+        assert_eq!(contract.get_transfer_policy(), TransferPolicy::Open);
+        assert!(contract.is_transfer_allowed(treasury(), food_bank()));
+        transfer(&mut contract, 40);
+        assert_eq!(contract.get_balance(food_bank()).0, 40);
+    }
+
+    #[test]
+    fn registered_only_needs_verified_profiles() {
+        let mut contract = with_treasury_balance();
+        contract.set_transfer_policy(TransferPolicy::RegisteredOnly);
+        register_verified(&mut contract, treasury());
+        assert!(!contract.is_transfer_allowed(treasury(), food_bank()));
+
+        register_verified(&mut contract, food_bank());
+        assert!(contract.is_transfer_allowed(treasury(), food_bank()));
+        transfer(&mut contract, 40);
+        assert_eq!(contract.get_balance(food_bank()).0, 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver bob is not allowed to receive transfers under the RegisteredOnly policy")]
+    fn unverified_receivers_are_rejected() {
+        let mut contract = with_treasury_balance();
+        contract.set_transfer_policy(TransferPolicy::RegisteredOnly);
+        register_verified(&mut contract, treasury());
+        set_caller(food_bank(), ONE_NEAR);
+        contract.register_profile("Harbor Pantry".to_string(), "north".to_string(), OrgType::FoodBank, None);
+        transfer(&mut contract, 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "Sender danny is not allowed to transfer under the AllowlistOnly policy")]
+    fn senders_off_the_allowlist_are_rejected() {
+        let mut contract = with_treasury_balance();
+        contract.set_transfer_policy(TransferPolicy::AllowlistOnly);
+        contract.add_to_transfer_allowlist(food_bank());
+        transfer(&mut contract, 40);
+    }
+
+    #[test]
+    fn allowlist_only_needs_both_parties_listed() {
+        let mut contract = with_treasury_balance();
+        contract.set_transfer_policy(TransferPolicy::AllowlistOnly);
+        contract.add_to_transfer_allowlist(treasury());
+        contract.add_to_transfer_allowlist(food_bank());
+        assert!(contract.is_transfer_allowed(treasury(), food_bank()));
+        assert!(!contract.is_transfer_allowed(treasury(), other_food_bank()));
+
+        contract.remove_from_transfer_allowlist(food_bank());
+        assert!(!contract.is_transfer_allowed(treasury(), food_bank()));
+        assert_eq!(contract.get_transfer_allowlist(0, 10), vec![treasury()]);
+    }
+
+    #[test]
+    fn flipping_the_policy_unblocks_a_rejected_transfer() {
+        let mut contract = with_treasury_balance();
+        contract.set_transfer_policy(TransferPolicy::AllowlistOnly);
+        let rejected = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            transfer(&mut contract, 40);
+        }));
+        assert!(rejected.is_err());
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+
+        set_caller(owner(), 0);
+        contract.set_transfer_policy(TransferPolicy::Open);
+        transfer(&mut contract, 40);
+        assert_eq!(contract.get_balance(food_bank()).0, 40);
+    }
+
+    #[test]
+    fn minting_ignores_the_policy() {
+        let mut contract = setup_contract();
+        contract.set_transfer_policy(TransferPolicy::AllowlistOnly);
+        contribute(&mut contract, &food_bank(), 100);
+        assert!(contract.get_balance(food_bank()).0 > 0);
+    }
+}
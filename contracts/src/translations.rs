@@ -0,0 +1,222 @@
+//! Per-locale names and descriptions for redemption options. Translations
+//! are stored on the option itself; the localized view falls back to the
+//! option's default text for locales it has no translation for.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, Balance, Promise};
+
+use crate::redemptions::MAX_OPTIONS_PER_PAGE;
+use crate::{assert_valid_option_name, FoodBankToken, FoodBankTokenExt, RedemptionOptionV5};
+
+/// Long enough for tags like `zh-Hant-TW`.
+pub const MAX_LOCALE_LENGTH: usize = 16;
+pub const MAX_TRANSLATED_DESCRIPTION_LENGTH: usize = 512;
+pub const MAX_TRANSLATIONS_PER_OPTION: usize = 20;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LocalizedText {
+    pub name: String,
+    pub description: String,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Adds or replaces the `locale` text of an option. The attached deposit
+    /// pays for any storage added; the excess is refunded.
+    #[payable]
+    pub fn set_option_translation(
+        &mut self,
+        option_id: String,
+        locale: String,
+        name: String,
+        description: String,
+    ) {
+        self.assert_owner_action();
+        assert_valid_locale(&locale);
+        assert_valid_option_name(&name);
+        assert!(
+            description.len() <= MAX_TRANSLATED_DESCRIPTION_LENGTH,
+            "Description exceeds {} bytes",
+            MAX_TRANSLATED_DESCRIPTION_LENGTH
+        );
+        let initial_storage = env::storage_usage();
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        let mut option = old_option.clone();
+        let text = LocalizedText { name, description };
+        match option.translations.iter_mut().find(|(code, _)| *code == locale) {
+            Some((_, existing)) => *existing = text,
+            None => {
+                assert!(
+                    option.translations.len() < MAX_TRANSLATIONS_PER_OPTION,
+                    "An option can have at most {} translations",
+                    MAX_TRANSLATIONS_PER_OPTION
+                );
+                option.translations.push((locale, text));
+            }
+        }
+        self.set_redemption_option(&option_id, Some(old_option), option);
+        self.charge_storage(initial_storage, 0);
+    }
+
+    /// Removes the `locale` text of an option and refunds the storage it
+    /// used.
+    pub fn remove_option_translation(&mut self, option_id: String, locale: String) {
+        self.assert_owner_action();
+        let initial_storage = env::storage_usage();
+        let old_option = self.redemption_option(&option_id).expect("Redemption option not found");
+        let mut option = old_option.clone();
+        let before = option.translations.len();
+        option.translations.retain(|(code, _)| *code != locale);
+        assert!(
+            option.translations.len() < before,
+            "Option has no {} translation",
+            locale
+        );
+        self.set_redemption_option(&option_id, Some(old_option), option);
+        let freed = initial_storage.saturating_sub(env::storage_usage());
+        let refund = Balance::from(freed) * env::storage_byte_cost();
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    // View functions
+
+    /// Redemption options with `name` and `description` in `locale` where
+    /// a translation exists, at most `MAX_OPTIONS_PER_PAGE` per page.
+    pub fn get_redemption_options_localized(
+        &self,
+        locale: String,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, RedemptionOptionV5)> {
+        self.iter_redemption_options()
+            .skip(from_index as usize)
+            .take(limit.min(MAX_OPTIONS_PER_PAGE) as usize)
+            .map(|(option_id, mut option)| {
+                if let Some((_, text)) = option.translations.iter().find(|(code, _)| *code == locale) {
+                    option.name = text.name.clone();
+                    option.description = text.description.clone();
+                }
+                (option_id, option)
+            })
+            .collect()
+    }
+}
+
+/// BCP 47-style codes: ASCII letters, digits and `-`.
+fn assert_valid_locale(locale: &str) {
+    assert!(
+        !locale.is_empty() && locale.len() <= MAX_LOCALE_LENGTH,
+        "Locale must be 1 to {} bytes",
+        MAX_LOCALE_LENGTH
+    );
+    assert!(
+        locale.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-'),
+        "Locale may only contain ASCII letters, digits and '-'"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn translate(contract: &mut FoodBankToken, locale: &str, name: &str) {
+        set_caller(owner(), ONE_NEAR);
+        contract.set_option_translation(
+            "supplier_discount".to_string(),
+            locale.to_string(),
+            name.to_string(),
+            format!("{} description", name),
+        );
+    }
+
+    fn localized_name(contract: &FoodBankToken, locale: &str) -> String {
+        let options = contract.get_redemption_options_localized(locale.to_string(), 0, 10);
+        let (_, option) = options.iter().find(|(id, _)| id == "supplier_discount").unwrap();
+        option.name.clone()
+    }
+
+    #[test]
+    fn translated_text_is_shown_for_its_locale() {
+        let mut contract = setup_contract();
+        translate(&mut contract, "es", "Descuento de proveedor");
+        let options = contract.get_redemption_options_localized("es".to_string(), 0, 10);
+        let (_, option) = options.iter().find(|(id, _)| id == "supplier_discount").unwrap();
+        assert_eq!(option.name, "Descuento de proveedor");
+        assert_eq!(option.description, "Descuento de proveedor description");
+    }
+
+    #[test]
+    fn unknown_locales_fall_back_to_the_default_text() {
+        let mut contract = setup_contract();
+        let default_name = contract.redemption_option(&"supplier_discount".to_string()).unwrap().name;
+        translate(&mut contract, "es", "Descuento de proveedor");
+        assert_eq!(localized_name(&contract, "fr"), default_name);
+        // Options without any translation fall back too.
+        let options = contract.get_redemption_options_localized("es".to_string(), 0, 10);
+        let (_, analytics) = options.iter().find(|(id, _)| id == "analytics_access").unwrap();
+        assert_eq!(
+            analytics.name,
+            contract.redemption_option(&"analytics_access".to_string()).unwrap().name
+        );
+    }
+
+    #[test]
+    fn translations_are_replaced_and_removed() {
+        let mut contract = setup_contract();
+        translate(&mut contract, "es", "Descuento");
+        translate(&mut contract, "es", "Descuento de proveedor");
+        let option = contract.redemption_option(&"supplier_discount".to_string()).unwrap();
+        assert_eq!(option.translations.len(), 1);
+        assert_eq!(localized_name(&contract, "es"), "Descuento de proveedor");
+
+        contract.remove_option_translation("supplier_discount".to_string(), "es".to_string());
+        let option = contract.redemption_option(&"supplier_discount".to_string()).unwrap();
+        assert!(option.translations.is_empty());
+        assert_eq!(localized_name(&contract, "es"), option.name);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach")]
+    fn storage_must_be_paid_for() {
+        let mut contract = setup_contract();
+        set_caller(owner(), 0);
+        contract.set_option_translation(
+            "supplier_discount".to_string(),
+            "es".to_string(),
+            "Descuento de proveedor".to_string(),
+            String::new(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Locale may only contain ASCII letters, digits and '-'")]
+    fn malformed_locales_are_rejected() {
+        let mut contract = setup_contract();
+        translate(&mut contract, "es_ES", "Descuento");
+    }
+
+    #[test]
+    #[should_panic(expected = "Description exceeds 512 bytes")]
+    fn long_descriptions_are_rejected() {
+        let mut contract = setup_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.set_option_translation(
+            "supplier_discount".to_string(),
+            "es".to_string(),
+            "Descuento".to_string(),
+            "x".repeat(MAX_TRANSLATED_DESCRIPTION_LENGTH + 1),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Option has no fr translation")]
+    fn removing_a_missing_translation_fails() {
+        let mut contract = setup_contract();
+        contract.remove_option_translation("supplier_discount".to_string(), "fr".to_string());
+    }
+}
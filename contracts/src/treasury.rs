@@ -0,0 +1,258 @@
+//! A program treasury that receives a share of every contribution reward,
+//! minted on top of the food bank's reward.
+
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, TreasuryChanged, TreasuryTransfer};
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const MAX_TREASURY_SHARE_BPS: u16 = 2_000;
+const BPS_DENOMINATOR: Balance = 10_000;
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Sets where the treasury share is minted and how large it is, in basis
+    /// points of each reward. Moving the treasury carries its balance along.
+    pub fn set_treasury(&mut self, account_id: Option<AccountId>, share_bps: u16) {
+        self.assert_owner_action();
+        self.internal_set_treasury(account_id, share_bps);
+    }
+
+    /// The only way treasury tokens leave the treasury account.
+    #[payable]
+    pub fn treasury_transfer(&mut self, to: AccountId, amount: U128) {
+        assert_one_yocto();
+        self.assert_owner_action();
+        self.internal_treasury_transfer(to, amount);
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn internal_set_treasury(&mut self, account_id: Option<AccountId>, share_bps: u16) {
+        assert!(
+            share_bps <= MAX_TREASURY_SHARE_BPS,
+            "Treasury share must be <= {} bps",
+            MAX_TREASURY_SHARE_BPS
+        );
+        assert!(
+            share_bps == 0 || account_id.is_some(),
+            "A treasury account is required for a non-zero share"
+        );
+        if let Some(old) = self.treasury_account.clone() {
+            if account_id.as_ref() != Some(&old) {
+                let balance = self.balances.get(&old).unwrap_or(0);
+                match &account_id {
+                    Some(new) => {
+                        let new_balance = self.balances.get(new).unwrap_or(0);
+                        self.internal_set_balance(&old, 0);
+                        self.internal_set_balance(new, new_balance + balance);
+                        self.tag_statement_flow(&old, StatementFlow::TransferOut, balance);
+                        self.tag_statement_flow(new, StatementFlow::TransferIn, balance);
+                    }
+                    None => assert!(
+                        balance == 0,
+                        "Treasury balance must be transferred before removing the treasury"
+                    ),
+                }
+            }
+        }
+        TreasuryChanged {
+            actor: &env::predecessor_account_id(),
+            old_account: self.treasury_account.as_ref(),
+            new_account: account_id.as_ref(),
+            old_share_bps: self.treasury_share_bps,
+            new_share_bps: share_bps,
+        }
+        .record(&mut self.event_buffer);
+        self.treasury_account = account_id;
+        self.treasury_share_bps = share_bps;
+    }
+
+    pub(crate) fn internal_treasury_transfer(&mut self, to: AccountId, amount: U128) {
+        self.assert_not_paused();
+        let treasury = self.treasury_account.clone()
+            .expect("No treasury account is set");
+        assert!(to != treasury, "Cannot transfer to the treasury itself");
+        self.assert_transfer_allowed(&treasury, &to);
+        self.assert_registered(&treasury);
+        let balance = self.balances.get(&treasury).unwrap_or(0);
+        assert!(balance >= amount.0, "Insufficient treasury balance");
+        let to_balance = self.balances.get(&to).unwrap_or(0);
+        self.internal_set_balance(&treasury, balance - amount.0);
+        self.internal_set_balance(&to, to_balance + amount.0);
+        self.tag_statement_flow(&treasury, StatementFlow::TransferOut, amount.0);
+        self.tag_statement_flow(&to, StatementFlow::TransferIn, amount.0);
+        TreasuryTransfer {
+            actor: &env::predecessor_account_id(),
+            to: &to,
+            amount,
+        }
+        .emit();
+    }
+
+    /// `reward * treasury_share_bps / 10000`, rounded down. The food bank
+    /// still receives the full reward.
+    pub(crate) fn treasury_share(&self, reward: Balance) -> Balance {
+        if self.treasury_account.is_none() {
+            return 0;
+        }
+        reward * Balance::from(self.treasury_share_bps) / BPS_DENOMINATOR
+    }
+
+    /// Mints the share of `reward`, at most `limit`, and returns it.
+    pub(crate) fn mint_treasury_share(&mut self, reward: Balance, limit: Balance) -> Balance {
+        let share = self.treasury_share(reward).min(limit);
+        if share > 0 {
+            let treasury = self.treasury_account.clone().unwrap();
+            self.mint(treasury, share);
+        }
+        share
+    }
+
+    pub(crate) fn assert_not_treasury(&self, account_id: &AccountId) {
+        assert!(
+            self.treasury_account.as_ref() != Some(account_id),
+            "Treasury tokens can only be moved with treasury_transfer"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    fn treasury() -> AccountId {
+        accounts(3)
+    }
+
+    fn with_treasury(share_bps: u16) -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_treasury(Some(treasury()), share_bps);
+        contract
+    }
+
+    #[test]
+    fn treasury_receives_share_on_top_of_reward() {
+        let mut contract = with_treasury(1_000);
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 100);
+
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+        assert_eq!(contract.get_balance(treasury()).0, 10);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 110);
+    }
+
+    #[test]
+    fn share_rounds_down_at_small_rewards() {
+        let mut contract = with_treasury(MAX_TREASURY_SHARE_BPS);
+        // At the default 5% rate a score of 20 pays 1 and a score of 60 pays
+        // 3; a 20% share of either rounds down to nothing.
+        contribute(&mut contract, &food_bank(), 20);
+        contribute(&mut contract, &food_bank(), 60);
+        assert_eq!(contract.get_balance(food_bank()).0, 4);
+        assert_eq!(contract.get_balance(treasury()).0, 0);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 4);
+
+        // A score of 100 pays 5, of which 20% is exactly 1.
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 9);
+        assert_eq!(contract.get_balance(treasury()).0, 1);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 10);
+    }
+
+    #[test]
+    fn both_mints_are_logged() {
+        let mut contract = with_treasury(1_000);
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 100);
+
+        let minted: Vec<String> = get_logs()
+            .into_iter()
+            .filter(|log| log.contains("\"tokens_minted\""))
+            .collect();
+        assert_eq!(minted.len(), 2);
+        assert!(minted[0].contains("\"account_id\":\"bob\",\"amount\":\"100\""));
+        assert!(minted[1].contains("\"account_id\":\"danny\",\"amount\":\"10\""));
+    }
+
+    #[test]
+    fn no_share_without_a_treasury() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 100);
+    }
+
+    #[test]
+    fn treasury_transfer_moves_tokens() {
+        let mut contract = with_treasury(1_000);
+        set_reward_rate_bps(&mut contract, 10_000);
+        contribute(&mut contract, &food_bank(), 100);
+
+        set_caller(owner(), 1);
+        contract.treasury_transfer(other_food_bank(), U128(4));
+        assert_eq!(contract.get_balance(treasury()).0, 6);
+        assert_eq!(contract.get_balance(other_food_bank()).0, 4);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 110);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient treasury balance")]
+    fn treasury_transfer_rejects_overdraw() {
+        let mut contract = with_treasury(1_000);
+        contract.mint(treasury(), 5);
+        set_caller(owner(), 1);
+        contract.treasury_transfer(other_food_bank(), U128(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account not registered")]
+    fn treasury_transfer_needs_a_registered_treasury() {
+        let mut contract = with_treasury(1_000);
+        set_caller(owner(), 1);
+        contract.treasury_transfer(other_food_bank(), U128(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn treasury_transfer_is_owner_only() {
+        let mut contract = with_treasury(1_000);
+        set_caller(treasury(), 1);
+        contract.treasury_transfer(other_food_bank(), U128(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Treasury tokens can only be moved with treasury_transfer")]
+    fn treasury_cannot_redeem() {
+        let mut contract = with_treasury(1_000);
+        contract.mint(treasury(), 150);
+        set_caller(treasury(), 1);
+        contract.redeem_tokens("supplier_discount".to_string(), U128(100), None);
+    }
+
+    #[test]
+    fn moving_the_treasury_carries_its_balance() {
+        let mut contract = with_treasury(1_000);
+        contract.mint(treasury(), 30);
+        contract.set_treasury(Some(accounts(4)), 1_000);
+        assert_eq!(contract.get_balance(treasury()).0, 0);
+        assert_eq!(contract.get_balance(accounts(4)).0, 30);
+    }
+
+    #[test]
+    #[should_panic(expected = "Treasury share must be <= 2000 bps")]
+    fn share_is_capped() {
+        with_treasury(2_001);
+    }
+
+    #[test]
+    #[should_panic(expected = "A treasury account is required for a non-zero share")]
+    fn share_requires_an_account() {
+        let mut contract = setup_contract();
+        contract.set_treasury(None, 1_000);
+    }
+}
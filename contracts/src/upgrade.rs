@@ -0,0 +1,256 @@
+//! Upgrades without a full-access key. The owner, or the council once one
+//! is configured, stages the SHA-256 hash of the new code, and after the
+//! timelock delay anyone can deploy code matching it; until then the hash
+//! can be cancelled. Watchers compare the staged hash with the audited
+//! build. The deploy calls `migrate` on the new code in the same receipt, so
+//! a release whose `migrate` rejects the stored state leaves the old code in
+//! place. The hash stays staged until a callback sees the batch succeed, so
+//! a failed deploy can be retried once the stored state is fixed.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, is_promise_success, near_bindgen, serde_json, AccountId, CryptoHash, Gas, Promise};
+
+use crate::events::{CodeDeployed, CodeStaged, CodeUnstaged, Event};
+use crate::migration::STATE_VERSION;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const GAS_FOR_MIGRATE: Gas = Gas(100 * Gas::ONE_TERA.0);
+const GAS_FOR_RESOLVE_DEPLOY: Gas = Gas(10 * Gas::ONE_TERA.0);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StagedCode {
+    /// SHA-256 of the code, as shown for account code hashes.
+    pub code_hash: Base58CryptoHash,
+    pub staged_at: u64,
+    pub deploy_after: u64,
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Replaces any hash already staged and restarts the delay.
+    pub fn stage_code(&mut self, code_hash: Base58CryptoHash) {
+        self.assert_owner_action();
+        self.internal_stage_code(code_hash);
+    }
+
+    /// Takes the raw wasm as the call's input rather than JSON arguments,
+    /// deploys it and migrates the state with it. Anyone may deploy once
+    /// the delay has passed.
+    pub fn deploy_staged_code(&mut self) -> Promise {
+        let staged = self.staged_code.clone().expect("No code is staged");
+        assert!(
+            env::block_timestamp() >= staged.deploy_after,
+            "Timelock has not expired, code is deployable at {}",
+            staged.deploy_after
+        );
+        let code = env::input().expect("Expected the code as input");
+        assert!(code_hash(&code) == staged.code_hash, "Code does not match the staged hash");
+        let args = serde_json::json!({ "from_version": STATE_VERSION }).to_string().into_bytes();
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call("migrate".to_string(), args, 0, GAS_FOR_MIGRATE)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_DEPLOY)
+                    .on_code_deployed(env::predecessor_account_id(), staged.code_hash),
+            )
+    }
+
+    /// Runs on whichever code the batch left in place: the new code once
+    /// `migrate` succeeded, or the old code when it failed and took the
+    /// deploy down with it. Every release therefore keeps this method.
+    #[private]
+    pub fn on_code_deployed(&mut self, actor: AccountId, code_hash: Base58CryptoHash) -> bool {
+        let deployed = is_promise_success();
+        if deployed {
+            if self.staged_code.as_ref().is_some_and(|staged| staged.code_hash == code_hash) {
+                self.staged_code = None;
+            }
+            CodeDeployed {
+                actor: &actor,
+                code_hash: &code_hash,
+            }
+            .record(&mut self.event_buffer);
+        }
+        deployed
+    }
+
+    pub fn cancel_staged_code(&mut self) {
+        self.assert_owner_action();
+        self.internal_cancel_staged_code();
+    }
+
+    // View functions
+    pub fn get_staged_code(&self) -> Option<StagedCode> {
+        self.staged_code.clone()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn internal_stage_code(&mut self, code_hash: Base58CryptoHash) {
+        let staged_at = env::block_timestamp();
+        let staged = StagedCode {
+            code_hash,
+            staged_at,
+            deploy_after: staged_at + self.timelock_delay,
+        };
+        CodeStaged {
+            actor: &env::predecessor_account_id(),
+            code_hash: &staged.code_hash,
+            deploy_after: staged.deploy_after,
+        }
+        .record(&mut self.event_buffer);
+        self.staged_code = Some(staged);
+    }
+
+    pub(crate) fn internal_cancel_staged_code(&mut self) {
+        let staged = self.staged_code.take().expect("No code is staged");
+        CodeUnstaged {
+            actor: &env::predecessor_account_id(),
+            code_hash: &staged.code_hash,
+        }
+        .record(&mut self.event_buffer);
+    }
+}
+
+fn code_hash(code: &[u8]) -> Base58CryptoHash {
+    let hash: CryptoHash = env::sha256(code).try_into().unwrap();
+    hash.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::council::AdminAction;
+    use crate::test_utils::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+    use near_sdk::{testing_env, PromiseResult};
+
+    const CODE: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn staged() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.stage_code(code_hash(CODE));
+        contract
+    }
+
+    fn deploy(contract: &mut FoodBankToken, code: &[u8]) {
+        let mut builder = context(accounts(5), 0);
+        builder.context.input = code.to_vec();
+        testing_env!(builder.build());
+        contract.deploy_staged_code();
+    }
+
+    #[test]
+    fn staging_exposes_the_hash_and_delay() {
+        let contract = staged();
+        let staged = contract.get_staged_code().unwrap();
+        assert_eq!(staged.code_hash, code_hash(CODE));
+        assert_eq!(staged.deploy_after, staged.staged_at + contract.get_timelock_delay());
+    }
+
+    fn latest_event(contract: &FoodBankToken) -> String {
+        let page = contract.get_events_since(0, u64::MAX);
+        page.events.last().unwrap().event.clone()
+    }
+
+    #[test]
+    fn deploying_after_the_delay_migrates_the_new_code() {
+        let mut contract = staged();
+        advance_time(contract.get_timelock_delay());
+        deploy(&mut contract, CODE);
+
+        let receipts = get_created_receipts();
+        let receipt = &receipts[0];
+        assert_eq!(receipt.receiver_id, contract_account());
+        assert_eq!(receipt.actions[0], VmAction::DeployContract { code: CODE.to_vec() });
+        let VmAction::FunctionCall { function_name, args, .. } = &receipt.actions[1] else {
+            panic!("expected the migrate call");
+        };
+        assert_eq!(function_name, "migrate");
+        assert_eq!(args, format!(r#"{{"from_version":{}}}"#, STATE_VERSION).as_bytes());
+        // Nothing is recorded until the batch has gone through.
+        assert!(contract.get_staged_code().is_some());
+        assert_eq!(latest_event(&contract), "code_staged");
+    }
+
+    #[test]
+    fn a_successful_deploy_clears_the_hash() {
+        let mut contract = staged();
+        resolve_callback(PromiseResult::Successful(Vec::new()));
+        assert!(contract.on_code_deployed(accounts(5), code_hash(CODE)));
+        assert_eq!(contract.get_staged_code(), None);
+        assert_eq!(latest_event(&contract), "code_deployed");
+    }
+
+    #[test]
+    fn a_failed_migration_keeps_the_hash_staged() {
+        let mut contract = staged();
+        resolve_callback(PromiseResult::Failed);
+        assert!(!contract.on_code_deployed(accounts(5), code_hash(CODE)));
+        assert_eq!(contract.get_staged_code().unwrap().code_hash, code_hash(CODE));
+        assert_eq!(latest_event(&contract), "code_staged");
+    }
+
+    #[test]
+    fn a_deploy_leaves_a_newer_hash_staged() {
+        let mut contract = staged();
+        let newer = code_hash(b"\0asm\x01\0\0\0\0");
+        contract.stage_code(newer);
+        resolve_callback(PromiseResult::Successful(Vec::new()));
+        contract.on_code_deployed(accounts(5), code_hash(CODE));
+        assert_eq!(contract.get_staged_code().unwrap().code_hash, newer);
+    }
+
+    #[test]
+    #[should_panic(expected = "Timelock has not expired")]
+    fn deploying_waits_for_the_delay() {
+        let mut contract = staged();
+        advance_time(contract.get_timelock_delay() - 1);
+        deploy(&mut contract, CODE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Code does not match the staged hash")]
+    fn only_the_staged_code_deploys() {
+        let mut contract = staged();
+        advance_time(contract.get_timelock_delay());
+        deploy(&mut contract, b"\0asm\x01\0\0\0\0");
+    }
+
+    #[test]
+    #[should_panic(expected = "No code is staged")]
+    fn cancelled_code_cannot_be_deployed() {
+        let mut contract = staged();
+        contract.cancel_staged_code();
+        advance_time(contract.get_timelock_delay());
+        deploy(&mut contract, CODE);
+    }
+
+    #[test]
+    #[should_panic(expected = "This operation must be proposed to the council")]
+    fn a_council_stages_through_its_actions() {
+        let mut contract = setup_contract();
+        contract.set_council(vec![accounts(2), accounts(3)], 1);
+        set_caller(accounts(2), 0);
+        let id = contract.propose_action(AdminAction::StageCode {
+            code_hash: code_hash(CODE),
+        });
+        contract.execute_action(id);
+        assert!(contract.get_staged_code().is_some());
+        set_caller(owner(), 0);
+        contract.stage_code(code_hash(CODE));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn staging_is_owner_only() {
+        let mut contract = setup_contract();
+        set_caller(food_bank(), 0);
+        contract.stage_code(code_hash(CODE));
+    }
+}
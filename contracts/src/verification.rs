@@ -0,0 +1,364 @@
+//! Independent verification of high-value contributions. In a category that
+//! requires verification, a recorded contribution pays nothing until enough
+//! distinct verifiers approve it; it is then paid the reward calculated when
+//! it was submitted.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{ContributionVerified, Event, VerificationVoteCast};
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+const MAX_NOTE_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum VerificationStatus {
+    PendingVerification,
+    Verified,
+    Rejected,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VerificationVote {
+    pub verifier: AccountId,
+    pub approve: bool,
+    pub note: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingVerification {
+    pub account_id: AccountId,
+    pub contribution_id: u64,
+    pub category: String,
+    pub recorder: AccountId,
+    pub submitted_at: u64,
+    /// Approvals needed, as configured at submission.
+    pub required: u8,
+    /// Calculated at submission and paid on approval.
+    pub reward: U128,
+    pub votes: Vec<VerificationVote>,
+    pub status: VerificationStatus,
+}
+
+impl PendingVerification {
+    fn count(&self, approve: bool) -> usize {
+        self.votes.iter().filter(|vote| vote.approve == approve).count()
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Zero turns verification off for the category. Contributions already
+    /// waiting keep the requirement they were submitted under.
+    pub fn set_required_verifications(&mut self, category: String, required: u8) {
+        self.assert_owner_action();
+        if required == 0 {
+            self.required_verifications.remove(&category);
+        } else {
+            self.required_verifications.insert(&category, &required);
+        }
+    }
+
+    pub fn add_verifier(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        assert!(self.verifiers.insert(&account_id), "Account is already a verifier");
+    }
+
+    pub fn remove_verifier(&mut self, account_id: AccountId) {
+        self.assert_owner_action();
+        assert!(self.verifiers.remove(&account_id), "Account is not a verifier");
+    }
+
+    /// Records the caller's vote on a contribution awaiting verification.
+    /// It is paid once approvals reach the requirement, and rejected once
+    /// the remaining verifiers could no longer approve it.
+    pub fn verify_contribution(
+        &mut self,
+        account_id: AccountId,
+        contribution_id: u64,
+        approve: bool,
+        note: String,
+    ) -> VerificationStatus {
+        self.assert_not_paused();
+        let verifier = env::predecessor_account_id();
+        assert!(self.verifiers.contains(&verifier), "Caller is not a verifier");
+        assert!(
+            note.len() <= MAX_NOTE_LENGTH,
+            "Note exceeds {} bytes",
+            MAX_NOTE_LENGTH
+        );
+        let key = (account_id.clone(), contribution_id);
+        let mut pending = self.verifications.get(&key)
+            .expect("Contribution does not need verification");
+        assert_eq!(
+            pending.status,
+            VerificationStatus::PendingVerification,
+            "Contribution is not pending verification"
+        );
+        assert!(
+            verifier != pending.recorder,
+            "Recorders cannot verify their own submissions"
+        );
+        assert!(verifier != account_id, "Accounts cannot verify their own contributions");
+        assert!(
+            pending.votes.iter().all(|vote| vote.verifier != verifier),
+            "Verifier {} has already voted",
+            verifier
+        );
+        assert!(
+            self.voided_contributions.get(&key).is_none(),
+            "Contribution is already voided"
+        );
+
+        VerificationVoteCast {
+            verifier: &verifier,
+            account_id: &account_id,
+            contribution_id,
+            approve,
+            note: &note,
+        }
+        .emit();
+        pending.votes.push(VerificationVote {
+            verifier,
+            approve,
+            note,
+        });
+
+        let required = usize::from(pending.required);
+        let max_rejections = (self.verifiers.len() as usize).saturating_sub(required);
+        if pending.count(true) >= required {
+            pending.status = VerificationStatus::Verified;
+            self.pay_verified_contribution(&pending);
+        } else if pending.count(false) > max_rejections {
+            pending.status = VerificationStatus::Rejected;
+        }
+        if pending.status != VerificationStatus::PendingVerification {
+            self.verification_queue.remove(&key);
+            ContributionVerified {
+                account_id: &account_id,
+                account_type: self.account_type(&account_id),
+                contribution_id,
+                status: pending.status,
+                reward: pending.reward,
+            }
+            .emit();
+        }
+        self.verifications.insert(&key, &pending);
+        pending.status
+    }
+
+    // View functions
+    pub fn get_required_verifications(&self, category: String) -> u8 {
+        self.required_verifications(&category)
+    }
+
+    pub fn is_verifier(&self, account_id: AccountId) -> bool {
+        self.verifiers.contains(&account_id)
+    }
+
+    pub fn get_verifiers(&self) -> Vec<AccountId> {
+        self.verifiers.to_vec()
+    }
+
+    pub fn get_verification(&self, account_id: AccountId, contribution_id: u64) -> Option<PendingVerification> {
+        self.verifications.get(&(account_id, contribution_id))
+    }
+
+    /// Contributions still waiting for votes, in no set order: resolving one
+    /// moves the last entry into its slot, so pages can shift between calls.
+    pub fn get_pending_verifications(&self, from_index: u64, limit: u64) -> Vec<PendingVerification> {
+        self.verification_queue
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|key| self.verifications.get(&key).unwrap())
+            .collect()
+    }
+}
+
+impl FoodBankToken {
+    pub(crate) fn required_verifications(&self, category: &str) -> u8 {
+        self.required_verifications.get(&category.to_string()).unwrap_or(0)
+    }
+
+    pub(crate) fn queue_verification(
+        &mut self,
+        account_id: &AccountId,
+        contribution_id: u64,
+        category: String,
+        reward: U128,
+    ) {
+        let key = (account_id.clone(), contribution_id);
+        self.verifications.insert(
+            &key,
+            &PendingVerification {
+                account_id: account_id.clone(),
+                contribution_id,
+                required: self.required_verifications(&category),
+                category,
+                recorder: env::predecessor_account_id(),
+                submitted_at: env::block_timestamp(),
+                reward,
+                votes: Vec::new(),
+                status: VerificationStatus::PendingVerification,
+            },
+        );
+        self.verification_queue.insert(&key);
+    }
+
+    /// False for contributions whose verification or acknowledgment is
+    /// pending or failed, so nothing recomputes a reward they were never
+    /// paid.
+    pub(crate) fn counts_for_rewards(&self, key: &(AccountId, u64)) -> bool {
+        self.verifications
+            .get(key)
+            .is_none_or(|pending| pending.status == VerificationStatus::Verified)
+            && self.is_acknowledged(key)
+    }
+
+    fn pay_verified_contribution(&mut self, pending: &PendingVerification) {
+        self.pay_withheld_reward(&pending.account_id, pending.contribution_id, pending.reward);
+    }
+
+    /// Pays a reward held back when the contribution was recorded and
+    /// restores it on the contribution's breakdown.
+    pub(crate) fn pay_withheld_reward(&mut self, account_id: &AccountId, contribution_id: u64, reward: U128) {
+        if self.minting_paused {
+            self.withhold_reward(account_id, contribution_id, |withheld| withheld.reward = reward);
+            return;
+        }
+        let key = (account_id.clone(), contribution_id);
+        let mut breakdown = self.reward_breakdown(&key).unwrap();
+        breakdown.reward = reward;
+        self.store_reward_breakdown(&key, &breakdown);
+
+        let supply_before = self.total_supply;
+        self.pay_contribution_reward(account_id, contribution_id, reward.0);
+        let minted = self.total_supply - supply_before;
+        let stats = &mut self.program_stats;
+        stats.total_rewards_minted = U128(stats.total_rewards_minted.0 + minted);
+        self.record_daily_mint(minted);
+        self.record_region_reward(account_id, reward.0);
+        self.record_monthly_reward(account_id, contribution_id, reward.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+
+    const CATEGORY: &str = "cold_chain_audit";
+
+    /// Two of three verifiers, with the owner recording.
+    fn verified_contract() -> FoodBankToken {
+        let mut contract = setup_contract();
+        contract.set_required_verifications(CATEGORY.to_string(), 2);
+        for verifier in [accounts(3), accounts(4), accounts(5)] {
+            contract.add_verifier(verifier);
+        }
+        set_reward_rate_bps(&mut contract, 1_000);
+        set_caller(owner(), ONE_NEAR);
+        contract.record_contribution(
+            food_bank(),
+            metrics(100, 100, 100),
+            None,
+            None,
+            None,
+            Some(CATEGORY.to_string()),
+        );
+        contract
+    }
+
+    fn vote(contract: &mut FoodBankToken, verifier: AccountId, approve: bool) -> VerificationStatus {
+        set_caller(verifier, 0);
+        contract.verify_contribution(food_bank(), 0, approve, "Checked the logger export".to_string())
+    }
+
+    #[test]
+    fn submission_waits_with_zero_reward() {
+        let contract = verified_contract();
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 0);
+        let queue = contract.get_pending_verifications(0, 10);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].status, VerificationStatus::PendingVerification);
+        assert_eq!(queue[0].reward.0, 10);
+    }
+
+    #[test]
+    fn quorum_pays_the_reward_from_submission_time() {
+        let mut contract = verified_contract();
+        set_reward_rate_bps(&mut contract, 5_000);
+        assert_eq!(vote(&mut contract, accounts(3), true), VerificationStatus::PendingVerification);
+        assert_eq!(vote(&mut contract, accounts(4), true), VerificationStatus::Verified);
+
+        assert_eq!(contract.get_balance(food_bank()).0, 10);
+        assert_eq!(contract.get_reward_breakdown(food_bank(), 0).unwrap().reward.0, 10);
+        assert_eq!(contract.get_program_stats().total_rewards_minted.0, 10);
+        assert!(contract.get_pending_verifications(0, 10).is_empty());
+    }
+
+    #[test]
+    fn rejection_majority_rejects() {
+        let mut contract = verified_contract();
+        vote(&mut contract, accounts(3), false);
+        vote(&mut contract, accounts(4), true);
+        assert_eq!(vote(&mut contract, accounts(5), false), VerificationStatus::Rejected);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert!(contract.get_pending_verifications(0, 10).is_empty());
+    }
+
+    #[test]
+    fn other_categories_pay_immediately() {
+        let mut contract = verified_contract();
+        set_caller(owner(), ONE_NEAR);
+        contract.record_contribution(
+            food_bank(),
+            metrics(100, 100, 100),
+            None,
+            None,
+            None,
+            Some("surplus_pickup".to_string()),
+        );
+        assert_eq!(contract.get_balance(food_bank()).0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Verifier danny has already voted")]
+    fn verifiers_vote_once() {
+        let mut contract = verified_contract();
+        vote(&mut contract, accounts(3), true);
+        vote(&mut contract, accounts(3), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Recorders cannot verify their own submissions")]
+    fn recorders_cannot_verify_their_submissions() {
+        let mut contract = verified_contract();
+        contract.add_verifier(owner());
+        vote(&mut contract, owner(), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not a verifier")]
+    fn only_verifiers_vote() {
+        let mut contract = verified_contract();
+        vote(&mut contract, other_food_bank(), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution is not pending verification")]
+    fn settled_contributions_take_no_more_votes() {
+        let mut contract = verified_contract();
+        vote(&mut contract, accounts(3), true);
+        vote(&mut contract, accounts(4), true);
+        vote(&mut contract, accounts(5), true);
+    }
+}
@@ -0,0 +1,221 @@
+//! Linear vesting for rewards large enough to be dumped straight into
+//! redemptions.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+use crate::events::{Event, VestedClaimed, VestingStarted};
+use crate::statements::StatementFlow;
+use crate::{FoodBankToken, FoodBankTokenExt};
+
+pub const DEFAULT_VESTING_THRESHOLD: Balance = 1_000;
+pub const DEFAULT_VESTING_DURATION: u64 = 90 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingSchedule {
+    pub total: U128,
+    pub claimed: U128,
+    pub start: u64,
+    pub duration: u64,
+}
+
+impl VestingSchedule {
+    fn vested_at(&self, timestamp: u64) -> Balance {
+        let elapsed = timestamp.saturating_sub(self.start);
+        if elapsed >= self.duration {
+            self.total.0
+        } else {
+            self.total.0 * Balance::from(elapsed) / Balance::from(self.duration)
+        }
+    }
+
+    fn claimable_at(&self, timestamp: u64) -> Balance {
+        self.vested_at(timestamp) - self.claimed.0
+    }
+}
+
+#[near_bindgen]
+impl FoodBankToken {
+    /// Rewards strictly above `threshold` vest over `duration_ns`. Existing
+    /// schedules keep their original terms.
+    pub fn set_vesting_config(&mut self, threshold: U128, duration_ns: u64) {
+        self.assert_owner_action();
+        assert!(duration_ns > 0, "Vesting duration must be positive");
+        self.vesting_threshold = threshold.0;
+        self.vesting_duration = duration_ns;
+    }
+
+    /// Moves everything vested so far into the liquid balance. Panics when
+    /// nothing is claimable.
+    pub fn claim_vested(&mut self) -> U128 {
+        self.assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        self.assert_not_frozen(&account_id);
+        let now = env::block_timestamp();
+        let mut schedules = self.vesting.get(&account_id).unwrap_or_default();
+
+        let mut claimed = 0;
+        for schedule in schedules.iter_mut() {
+            let claimable = schedule.claimable_at(now);
+            schedule.claimed = U128(schedule.claimed.0 + claimable);
+            claimed += claimable;
+        }
+        assert!(claimed > 0, "Nothing to claim");
+
+        schedules.retain(|schedule| schedule.claimed != schedule.total);
+        if schedules.is_empty() {
+            self.vesting.remove(&account_id);
+        } else {
+            self.vesting.insert(&account_id, &schedules);
+        }
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        self.internal_set_balance(&account_id, balance + claimed);
+        self.tag_statement_flow(&account_id, StatementFlow::Reward, claimed);
+        VestedClaimed {
+            account_id: &account_id,
+            amount: U128(claimed),
+        }
+        .emit();
+        U128(claimed)
+    }
+
+    // View functions
+    pub fn get_vesting_schedules(&self, account_id: AccountId) -> Vec<VestingSchedule> {
+        self.vesting.get(&account_id).unwrap_or_default()
+    }
+
+    pub fn get_claimable(&self, account_id: AccountId) -> U128 {
+        let now = env::block_timestamp();
+        U128(
+            self.get_vesting_schedules(account_id)
+                .iter()
+                .map(|schedule| schedule.claimable_at(now))
+                .sum(),
+        )
+    }
+
+    pub fn get_vesting_config(&self) -> (U128, u64) {
+        (U128(self.vesting_threshold), self.vesting_duration)
+    }
+}
+
+impl FoodBankToken {
+    /// Mints `amount` into a new schedule starting now. The tokens count
+    /// towards total supply straight away.
+    pub(crate) fn mint_vesting(&mut self, account_id: &AccountId, amount: Balance) {
+        let schedule = VestingSchedule {
+            total: U128(amount),
+            claimed: U128(0),
+            start: env::block_timestamp(),
+            duration: self.vesting_duration,
+        };
+        VestingStarted {
+            account_id,
+            schedule: &schedule,
+        }
+        .emit();
+        let mut schedules = self.vesting.get(account_id).unwrap_or_default();
+        schedules.push(schedule);
+        self.vesting.insert(account_id, &schedules);
+        self.total_supply += amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    /// Every perfect contribution vests 100 tokens over 10 days.
+    fn setup_vesting() -> FoodBankToken {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_vesting_config(U128(99), 10 * DAY);
+        contract
+    }
+
+    fn claim(contract: &mut FoodBankToken) -> Balance {
+        set_caller(food_bank(), 0);
+        contract.claim_vested().0
+    }
+
+    #[test]
+    fn rewards_at_the_threshold_stay_liquid() {
+        let mut contract = setup_contract();
+        set_reward_rate_bps(&mut contract, 10_000);
+        contract.set_vesting_config(U128(100), 10 * DAY);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+        assert!(contract.get_vesting_schedules(food_bank()).is_empty());
+    }
+
+    #[test]
+    fn large_rewards_vest_linearly() {
+        let mut contract = setup_vesting();
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_balance(food_bank()).0, 0);
+        assert_eq!(contract.get_config().total_supply.0, INITIAL_SUPPLY + 100);
+
+        advance_time(3 * DAY);
+        assert_eq!(contract.get_claimable(food_bank()).0, 30);
+        assert_eq!(claim(&mut contract), 30);
+        assert_eq!(contract.get_balance(food_bank()).0, 30);
+        assert_eq!(contract.get_claimable(food_bank()).0, 0);
+        assert_eq!(contract.get_vesting_schedules(food_bank())[0].claimed.0, 30);
+    }
+
+    #[test]
+    fn overlapping_schedules_vest_independently() {
+        let mut contract = setup_vesting();
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(5 * DAY);
+        contribute(&mut contract, &food_bank(), 100);
+        assert_eq!(contract.get_vesting_schedules(food_bank()).len(), 2);
+
+        advance_time(5 * DAY);
+        assert_eq!(claim(&mut contract), 100 + 50);
+        // The first schedule is fully claimed and dropped.
+        let schedules = contract.get_vesting_schedules(food_bank());
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].start, 5 * DAY);
+    }
+
+    #[test]
+    fn claiming_after_full_vest_releases_everything() {
+        let mut contract = setup_vesting();
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(30 * DAY);
+        assert_eq!(claim(&mut contract), 100);
+        assert!(contract.get_vesting_schedules(food_bank()).is_empty());
+        assert_eq!(contract.get_balance(food_bank()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn claim_with_nothing_claimable_panics() {
+        let mut contract = setup_vesting();
+        contribute(&mut contract, &food_bank(), 100);
+        advance_time(DAY);
+        claim(&mut contract);
+        claim(&mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn claim_without_schedules_panics() {
+        let mut contract = setup_vesting();
+        claim(&mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vesting duration must be positive")]
+    fn zero_duration_is_rejected() {
+        let mut contract = setup_contract();
+        contract.set_vesting_config(U128(10), 0);
+    }
+}
@@ -0,0 +1,62 @@
+//! Stand-in for the partner contracts that receive redemption benefit calls.
+//! Every call is recorded so integration tests can assert on it, and the stub
+//! can be told to panic to exercise the failure path. It has no manifest
+//! yet, so `tests/integration.rs` can't build it.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BenefitCall {
+    pub method: String,
+    pub caller: AccountId,
+    pub args: String,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct BenefitsStub {
+    calls: Vec<BenefitCall>,
+    fail: bool,
+}
+
+#[near_bindgen]
+impl BenefitsStub {
+    pub fn set_fail(&mut self, fail: bool) {
+        self.fail = fail;
+    }
+
+    pub fn apply_supplier_discount(&mut self) {
+        self.record("apply_supplier_discount");
+    }
+
+    pub fn grant_analytics_access(&mut self) {
+        self.record("grant_analytics_access");
+    }
+
+    pub fn process_grant_application(&mut self) {
+        self.record("process_grant_application");
+    }
+
+    /// Called with JSON arguments, which `record` keeps verbatim.
+    pub fn issue_voucher(&mut self) {
+        self.record("issue_voucher");
+    }
+
+    pub fn get_calls(&self) -> Vec<BenefitCall> {
+        self.calls.clone()
+    }
+
+    fn record(&mut self, method: &str) {
+        assert!(!self.fail, "Benefit stub configured to fail");
+        let args = String::from_utf8(env::input().unwrap_or_default())
+            .unwrap_or_else(|_| env::panic_str("Benefit args are not UTF-8"));
+        self.calls.push(BenefitCall {
+            method: method.to_string(),
+            caller: env::predecessor_account_id(),
+            args,
+        });
+    }
+}
@@ -0,0 +1,827 @@
+//! Sandbox tests for the cross-contract redemption path and other behaviour
+//! that depends on real balances or other contracts.
+//!
+//! The built-in options call their benefit method on the redeeming account,
+//! so each food bank here is a dev account with the `benefits_stub` contract
+//! deployed on it.
+//!
+//! Not run yet: this tree has no Cargo manifests, and `compile_project`
+//! needs one for the token, `benefits_stub` and `mock_ft` alike, plus
+//! `workspaces`, `tokio`, `anyhow` and `serde_json` as dev-dependencies of
+//! the token. Once those exist, `cargo test --test integration` runs the
+//! suite; the first run downloads the near-sandbox binary. Until then the
+//! paths below are only covered by the unit tests in `src/`.
+
+use near_sdk::json_types::U128;
+use serde_json::json;
+use workspaces::network::Sandbox;
+use workspaces::{Account, Contract, Worker};
+
+const TOKEN_PATH: &str = "./";
+const STUB_PATH: &str = "./tests/benefits_stub";
+const MOCK_FT_PATH: &str = "./tests/mock_ft";
+/// Covers a contribution's storage; the unused part is refunded.
+const STORAGE_DEPOSIT: u128 = 10u128.pow(22);
+/// Enough sandbox blocks (~1s each) to clear the default 48h timelock.
+const TIMELOCK_BLOCKS: u64 = 48 * 60 * 60 + 60;
+
+struct Env {
+    worker: Worker<Sandbox>,
+    owner: Account,
+    token: Contract,
+}
+
+async fn init() -> anyhow::Result<Env> {
+    let worker = workspaces::sandbox().await?;
+    let wasm = workspaces::compile_project(TOKEN_PATH).await?;
+    let token = worker.dev_deploy(&wasm).await?;
+    let owner = worker.dev_create_account().await?;
+
+    token
+        .call("new_default")
+        .args_json(json!({ "owner": owner.id(), "total_supply": "0" }))
+        .transact()
+        .await?
+        .into_result()?;
+    // One point of score per token keeps the arithmetic below readable.
+    let proposal_id: u64 = owner
+        .call(token.id(), "propose_param_change")
+        .args_json(json!({ "change": { "RewardRateBps": 10000 } }))
+        .transact()
+        .await?
+        .json()?;
+    // `fund` records contributions back to back, and the tests redeem the
+    // rewards right away.
+    owner
+        .call(token.id(), "set_min_contribution_interval")
+        .args_json(json!({ "interval_ns": 0 }))
+        .transact()
+        .await?
+        .into_result()?;
+    owner
+        .call(token.id(), "set_reward_lock_duration")
+        .args_json(json!({ "duration_ns": 0 }))
+        .transact()
+        .await?
+        .into_result()?;
+    worker.fast_forward(TIMELOCK_BLOCKS).await?;
+    owner
+        .call(token.id(), "execute_param_change")
+        .args_json(json!({ "proposal_id": proposal_id }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(Env { worker, owner, token })
+}
+
+/// Dev account with the benefits stub deployed on it.
+async fn food_bank(env: &Env) -> anyhow::Result<Contract> {
+    let stub_wasm = workspaces::compile_project(STUB_PATH).await?;
+    let account = env.worker.dev_create_account().await?;
+    Ok(account.deploy(&stub_wasm).await?.into_result()?)
+}
+
+/// Each perfect-score contribution mints 100 tokens at a 100% reward rate.
+async fn fund(env: &Env, food_bank: &Contract, contributions: u32) -> anyhow::Result<()> {
+    for _ in 0..contributions {
+        env.owner
+            .call(env.token.id(), "record_contribution")
+            .args_json(json!({
+                "food_bank": food_bank.id(),
+                "metrics": {
+                    "data_quality": 100,
+                    "model_improvement": 100,
+                    "participation_frequency": 100,
+                    "timestamp": 0,
+                },
+            }))
+            .deposit(STORAGE_DEPOSIT)
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    Ok(())
+}
+
+async fn balance(env: &Env, account: &Contract) -> anyhow::Result<u128> {
+    let balance: U128 = env
+        .token
+        .view("get_balance")
+        .args_json(json!({ "account_id": account.id() }))
+        .await?
+        .json()?;
+    Ok(balance.0)
+}
+
+async fn stub_calls(stub: &Contract) -> anyhow::Result<Vec<serde_json::Value>> {
+    Ok(stub.view("get_calls").await?.json()?)
+}
+
+#[tokio::test]
+async fn redeeming_each_option_calls_the_benefit_method() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 8).await?;
+    assert_eq!(balance(&env, &food_bank).await?, 800);
+
+    let cases = [
+        ("supplier_discount", 100, "apply_supplier_discount"),
+        ("analytics_access", 200, "grant_analytics_access"),
+        ("grant_opportunity", 500, "process_grant_application"),
+    ];
+    let mut expected_balance = 800;
+    for (option_id, cost, method) in cases {
+        food_bank
+            .as_account()
+            .call(env.token.id(), "redeem_tokens")
+            .args_json(json!({ "option_id": option_id, "amount": cost.to_string() }))
+            .deposit(1)
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        expected_balance -= cost;
+        assert_eq!(balance(&env, &food_bank).await?, expected_balance);
+
+        let calls = stub_calls(&food_bank).await?;
+        let last = calls.last().expect("benefit method was not called");
+        assert_eq!(last["method"], method);
+        assert_eq!(last["caller"], env.token.id().as_str());
+        assert_eq!(last["args"], "");
+    }
+    assert_eq!(stub_calls(&food_bank).await?.len(), cases.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn configured_deployment_has_only_its_own_options() -> anyhow::Result<()> {
+    let worker = workspaces::sandbox().await?;
+    let wasm = workspaces::compile_project(TOKEN_PATH).await?;
+    let token = worker.dev_deploy(&wasm).await?;
+    let owner = worker.dev_create_account().await?;
+    token
+        .call("new")
+        .args_json(json!({
+            "owner": owner.id(),
+            "total_supply": "0",
+            "config": {
+                "reward_rate_bps": 10000,
+                "min_score_threshold": 50,
+                "redemption_options": [{
+                    "option_id": "food_voucher",
+                    "name": "Food voucher",
+                    "cost": "100",
+                    "description": "Voucher for a partner grocer",
+                    "benefit": null,
+                }],
+            },
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let options: Vec<(String, serde_json::Value)> = token.view("get_redemption_options").await?.json()?;
+    let ids: Vec<&str> = options.iter().map(|(id, _)| id.as_str()).collect();
+    assert_eq!(ids, ["food_voucher"]);
+    let config: serde_json::Value = token.view("get_config").await?.json()?;
+    assert_eq!(config["reward_rate_bps"], 10000);
+    assert_eq!(config["min_score_threshold"], 50);
+
+    // Bare `new` starts with no options at all.
+    let bare = worker.dev_deploy(&wasm).await?;
+    bare.call("new")
+        .args_json(json!({ "owner": owner.id(), "total_supply": "0" }))
+        .transact()
+        .await?
+        .into_result()?;
+    let options: Vec<(String, serde_json::Value)> = bare.view("get_redemption_options").await?.json()?;
+    assert!(options.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn added_option_calls_its_configured_contract() -> anyhow::Result<()> {
+    let env = init().await?;
+    let partner = food_bank(&env).await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 1).await?;
+    env.owner
+        .call(env.token.id(), "add_redemption_option")
+        .args_json(json!({
+            "option_id": "food_voucher",
+            "name": "Food voucher",
+            "cost": "100",
+            "description": "Voucher from a partner pantry",
+            "benefit": {
+                "method": "issue_voucher",
+                "args_template": r#"{"redeemer":"{account_id}","receipt_id":{receipt_id}}"#,
+                "contract": partner.id(),
+            },
+        }))
+        .deposit(10 * STORAGE_DEPOSIT)
+        .transact()
+        .await?
+        .into_result()?;
+
+    let outcome = food_bank
+        .as_account()
+        .call(env.token.id(), "redeem_tokens")
+        .args_json(json!({ "option_id": "food_voucher", "amount": "100" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(outcome.json::<bool>()?);
+    assert_eq!(balance(&env, &food_bank).await?, 0);
+    assert!(stub_calls(&food_bank).await?.is_empty());
+
+    let calls = stub_calls(&partner).await?;
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0]["method"], "issue_voucher");
+    assert_eq!(calls[0]["caller"], env.token.id().as_str());
+    let args: serde_json::Value = serde_json::from_str(calls[0]["args"].as_str().unwrap())?;
+    assert_eq!(args, json!({ "redeemer": food_bank.id(), "receipt_id": 0 }));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_benefit_call_refunds_tokens() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 2).await?;
+    food_bank
+        .call("set_fail")
+        .args_json(json!({ "fail": true }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let outcome = food_bank
+        .as_account()
+        .call(env.token.id(), "redeem_tokens")
+        .args_json(json!({ "option_id": "supplier_discount", "amount": "100" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?;
+    // The resolver reports whether the benefit was delivered.
+    assert!(!outcome.json::<bool>()?);
+    assert!(stub_calls(&food_bank).await?.is_empty());
+    assert_eq!(balance(&env, &food_bank).await?, 200);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_freeze_during_the_benefit_call_leaves_the_refund_intact() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 2).await?;
+    food_bank
+        .call("set_fail")
+        .args_json(json!({ "fail": true }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // The redemption is only sent, so the freeze goes in while its benefit
+    // call and callback are still to run.
+    let redemption = food_bank
+        .as_account()
+        .call(env.token.id(), "redeem_tokens")
+        .args_json(json!({ "option_id": "supplier_discount", "amount": "100" }))
+        .deposit(1)
+        .max_gas()
+        .transact_async()
+        .await?;
+    env.owner
+        .call(env.token.id(), "freeze_account")
+        .args_json(json!({ "account_id": food_bank.id(), "reason": "Under review" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let outcome = redemption.await?;
+    assert!(!outcome.json::<bool>()?);
+    assert_eq!(balance(&env, &food_bank).await?, 200);
+    let frozen: bool = env
+        .token
+        .view("is_frozen")
+        .args_json(json!({ "account_id": food_bank.id() }))
+        .await?
+        .json()?;
+    assert!(frozen);
+    let receipt: serde_json::Value = env
+        .token
+        .view("get_receipt")
+        .args_json(json!({ "receipt_id": 0 }))
+        .await?
+        .json()?;
+    assert_eq!(receipt["status"], "Refunded");
+    let in_flight: u32 = env
+        .token
+        .view("get_redemptions_in_flight")
+        .args_json(json!({ "account_id": food_bank.id() }))
+        .await?
+        .json()?;
+    assert_eq!(in_flight, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn redeem_without_one_yocto_is_rejected() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 2).await?;
+
+    let outcome = food_bank
+        .as_account()
+        .call(env.token.id(), "redeem_tokens")
+        .args_json(json!({ "option_id": "supplier_discount", "amount": "100" }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(outcome.is_failure());
+    assert!(format!("{:?}", outcome.into_result().unwrap_err())
+        .contains("Requires attached deposit of exactly 1 yoctoNEAR"));
+    assert!(stub_calls(&food_bank).await?.is_empty());
+    assert_eq!(balance(&env, &food_bank).await?, 200);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn redeem_with_too_little_gas_fails_early() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 2).await?;
+
+    let outcome = food_bank
+        .as_account()
+        .call(env.token.id(), "redeem_tokens")
+        .args_json(json!({ "option_id": "supplier_discount", "amount": "100" }))
+        .deposit(1)
+        .gas(30_000_000_000_000)
+        .transact()
+        .await?;
+    assert!(outcome.is_failure());
+    assert!(format!("{:?}", outcome.into_result().unwrap_err())
+        .contains("Attach at least 45 TGas to redeem supplier_discount"));
+    assert!(stub_calls(&food_bank).await?.is_empty());
+    assert_eq!(balance(&env, &food_bank).await?, 200);
+
+    Ok(())
+}
+
+const ONE_NEAR: u128 = 10u128.pow(24);
+/// Upper bound on the gas a redemption costs the redeemer.
+const GAS_FEES: u128 = ONE_NEAR / 10;
+
+/// Puts a one NEAR co-pay on `supplier_discount` and returns the treasury
+/// account it is forwarded to.
+async fn with_copay(env: &Env) -> anyhow::Result<Account> {
+    let treasury = env.worker.dev_create_account().await?;
+    env.owner
+        .call(env.token.id(), "set_treasury")
+        .args_json(json!({ "account_id": treasury.id(), "share_bps": 0 }))
+        .transact()
+        .await?
+        .into_result()?;
+    env.owner
+        .call(env.token.id(), "set_option_near_copay")
+        .args_json(json!({ "option_id": "supplier_discount", "near_copay": ONE_NEAR.to_string() }))
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(treasury)
+}
+
+async fn redeem_with_copay(
+    env: &Env,
+    food_bank: &Contract,
+    deposit: u128,
+) -> anyhow::Result<workspaces::result::ExecutionFinalResult> {
+    Ok(food_bank
+        .as_account()
+        .call(env.token.id(), "redeem_tokens")
+        .args_json(json!({ "option_id": "supplier_discount", "amount": "100" }))
+        .deposit(deposit)
+        .max_gas()
+        .transact()
+        .await?)
+}
+
+#[tokio::test]
+async fn exact_copay_is_forwarded_to_the_treasury() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 1).await?;
+    let treasury = with_copay(&env).await?;
+    let before = treasury.view_account().await?.balance;
+
+    let outcome = redeem_with_copay(&env, &food_bank, ONE_NEAR).await?;
+    assert!(outcome.json::<bool>()?);
+    assert_eq!(balance(&env, &food_bank).await?, 0);
+    assert_eq!(treasury.view_account().await?.balance, before + ONE_NEAR);
+
+    let receipt: serde_json::Value = env
+        .token
+        .view("get_receipt")
+        .args_json(json!({ "receipt_id": 0 }))
+        .await?
+        .json()?;
+    assert_eq!(receipt["amount"], "100");
+    assert_eq!(receipt["near_copay"], ONE_NEAR.to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn copay_overpayment_is_refunded() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 1).await?;
+    let treasury = with_copay(&env).await?;
+    let treasury_before = treasury.view_account().await?.balance;
+    let before = food_bank.view_account().await?.balance;
+
+    let outcome = redeem_with_copay(&env, &food_bank, 3 * ONE_NEAR).await?;
+    assert!(outcome.json::<bool>()?);
+    assert_eq!(treasury.view_account().await?.balance, treasury_before + ONE_NEAR);
+    let spent = before - food_bank.view_account().await?.balance;
+    assert!((ONE_NEAR..ONE_NEAR + GAS_FEES).contains(&spent));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn redeem_estimate_is_enough_to_redeem() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 1).await?;
+    with_copay(&env).await?;
+
+    let estimate: serde_json::Value = env
+        .token
+        .view("estimate_redeem")
+        .args_json(json!({ "account_id": food_bank.id(), "option_id": "supplier_discount" }))
+        .await?
+        .json()?;
+    assert_eq!(estimate["required_deposit"], ONE_NEAR.to_string());
+    assert_eq!(estimate["check"]["status"], "Ok");
+    let min_gas_tgas = estimate["min_gas_tgas"].as_u64().unwrap();
+
+    let outcome = food_bank
+        .as_account()
+        .call(env.token.id(), "redeem_tokens")
+        .args_json(json!({ "option_id": "supplier_discount", "amount": estimate["token_cost"] }))
+        .deposit(ONE_NEAR)
+        .gas(min_gas_tgas * 1_000_000_000_000)
+        .transact()
+        .await?;
+    assert!(outcome.json::<bool>()?);
+    assert_eq!(stub_calls(&food_bank).await?.len(), 1);
+    assert_eq!(balance(&env, &food_bank).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn copay_underpayment_is_rejected_before_burning() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 1).await?;
+    with_copay(&env).await?;
+
+    let outcome = redeem_with_copay(&env, &food_bank, ONE_NEAR - 1).await?;
+    assert!(outcome.is_failure());
+    assert!(format!("{:?}", outcome.into_result().unwrap_err())
+        .contains("Attach at least 1000000000000000000000000 yoctoNEAR to cover the co-pay"));
+    assert!(stub_calls(&food_bank).await?.is_empty());
+    assert_eq!(balance(&env, &food_bank).await?, 100);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_benefit_call_refunds_tokens_and_copay() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 1).await?;
+    let treasury = with_copay(&env).await?;
+    food_bank
+        .call("set_fail")
+        .args_json(json!({ "fail": true }))
+        .transact()
+        .await?
+        .into_result()?;
+    let treasury_before = treasury.view_account().await?.balance;
+    let before = food_bank.view_account().await?.balance;
+
+    let outcome = redeem_with_copay(&env, &food_bank, ONE_NEAR).await?;
+    assert!(!outcome.json::<bool>()?);
+    assert_eq!(balance(&env, &food_bank).await?, 100);
+    assert_eq!(treasury.view_account().await?.balance, treasury_before);
+    assert!(before - food_bank.view_account().await?.balance < GAS_FEES);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn withdraw_near_leaves_storage_covered() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    let stray = 5 * 10u128.pow(24);
+    env.owner
+        .transfer_near(env.token.id(), stray)
+        .await?
+        .into_result()?;
+
+    let available: U128 = env.token.view("get_available_near").await?.json()?;
+    assert!(available.0 >= stray);
+    let before = env.owner.view_account().await?.balance;
+    env.owner
+        .call(env.token.id(), "withdraw_near")
+        .args_json(json!({ "amount": available, "to": env.owner.id() }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(env.owner.view_account().await?.balance > before + stray - 10u128.pow(23));
+
+    let outcome = env
+        .owner
+        .call(env.token.id(), "withdraw_near")
+        .args_json(json!({ "amount": "1000000000000000000000000", "to": env.owner.id() }))
+        .deposit(1)
+        .transact()
+        .await?;
+    assert!(outcome.is_failure());
+
+    // The contract can still pay for new state.
+    fund(&env, &food_bank, 1).await?;
+    assert_eq!(balance(&env, &food_bank).await?, 100);
+
+    Ok(())
+}
+
+/// Puts tokens on sale at a tenth of a NEAR each.
+async fn open_sale(env: &Env) -> anyhow::Result<()> {
+    env.owner
+        .call(env.token.id(), "set_token_price")
+        .args_json(json!({ "token_price_yocto": (ONE_NEAR / 10).to_string() }))
+        .transact()
+        .await?
+        .into_result()?;
+    env.owner
+        .call(env.token.id(), "set_sales_enabled")
+        .args_json(json!({ "enabled": true }))
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bought_tokens_are_paid_for_and_proceeds_withdrawn() -> anyhow::Result<()> {
+    let env = init().await?;
+    open_sale(&env).await?;
+    let supplier = food_bank(&env).await?;
+    let before = supplier.view_account().await?.balance;
+
+    let tokens: U128 = supplier
+        .as_account()
+        .call(env.token.id(), "buy_tokens")
+        .deposit(ONE_NEAR + ONE_NEAR / 20)
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(tokens.0, 10);
+    assert_eq!(balance(&env, &supplier).await?, 10);
+    // The half-token remainder came back.
+    let spent = before - supplier.view_account().await?.balance;
+    assert!((ONE_NEAR..ONE_NEAR + GAS_FEES).contains(&spent));
+
+    let receiver = env.worker.dev_create_account().await?;
+    let receiver_before = receiver.view_account().await?.balance;
+    env.owner
+        .call(env.token.id(), "withdraw_sale_proceeds")
+        .args_json(json!({ "amount": ONE_NEAR.to_string(), "to": receiver.id() }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(receiver.view_account().await?.balance, receiver_before + ONE_NEAR);
+    let proceeds: U128 = env.token.view("get_sale_proceeds").await?.json()?;
+    assert_eq!(proceeds.0, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejected_purchase_returns_the_deposit() -> anyhow::Result<()> {
+    let env = init().await?;
+    open_sale(&env).await?;
+    env.owner
+        .call(env.token.id(), "pause")
+        .transact()
+        .await?
+        .into_result()?;
+    let supplier = food_bank(&env).await?;
+    let before = supplier.view_account().await?.balance;
+
+    let outcome = supplier
+        .as_account()
+        .call(env.token.id(), "buy_tokens")
+        .deposit(ONE_NEAR)
+        .transact()
+        .await?;
+    assert!(outcome.is_failure());
+    assert_eq!(balance(&env, &supplier).await?, 0);
+    assert!(before - supplier.view_account().await?.balance < GAS_FEES);
+
+    Ok(())
+}
+
+/// Mock NEP-141 token with `amount` minted to a fresh sponsor account. When
+/// `accepted`, the token is allowlisted at one reward token per 1_000_000
+/// units.
+async fn sponsor_token(
+    env: &Env,
+    amount: u128,
+    accepted: bool,
+) -> anyhow::Result<(Contract, Account)> {
+    let wasm = workspaces::compile_project(MOCK_FT_PATH).await?;
+    let ft = env.worker.dev_deploy(&wasm).await?;
+    let sponsor = env.worker.dev_create_account().await?;
+    ft.call("mint")
+        .args_json(json!({ "account_id": sponsor.id(), "amount": amount.to_string() }))
+        .transact()
+        .await?
+        .into_result()?;
+    if accepted {
+        env.owner
+            .call(env.token.id(), "add_accepted_token")
+            .args_json(json!({
+                "token_id": ft.id(),
+                "rate": { "tokens": "1", "per": "1000000" },
+            }))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    Ok((ft, sponsor))
+}
+
+async fn donate(
+    env: &Env,
+    ft: &Contract,
+    sponsor: &Account,
+    amount: u128,
+    msg: &str,
+) -> anyhow::Result<()> {
+    sponsor
+        .call(ft.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": env.token.id(),
+            "amount": amount.to_string(),
+            "msg": msg,
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(())
+}
+
+async fn ft_balance(ft: &Contract, account: &Account) -> anyhow::Result<u128> {
+    let balance: U128 = ft
+        .view("ft_balance_of")
+        .args_json(json!({ "account_id": account.id() }))
+        .await?
+        .json()?;
+    Ok(balance.0)
+}
+
+#[tokio::test]
+async fn ft_donation_mints_to_the_named_food_bank() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    let (ft, sponsor) = sponsor_token(&env, 5_000_000, true).await?;
+
+    let msg = json!({ "action": "donate_to", "account": food_bank.id() }).to_string();
+    donate(&env, &ft, &sponsor, 2_500_000, &msg).await?;
+    assert_eq!(balance(&env, &food_bank).await?, 2);
+    // The half unit that didn't convert is returned.
+    assert_eq!(ft_balance(&ft, &sponsor).await?, 3_000_000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ft_donation_funds_the_matching_pool() -> anyhow::Result<()> {
+    let env = init().await?;
+    let (ft, sponsor) = sponsor_token(&env, 5_000_000, true).await?;
+
+    donate(&env, &ft, &sponsor, 3_000_000, r#"{"action": "fund_matching_pool"}"#).await?;
+    let pool: serde_json::Value = env.token.view("get_matching_pool").await?.json()?;
+    assert_eq!(pool["remaining"], "3");
+    assert_eq!(ft_balance(&ft, &sponsor).await?, 2_000_000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ft_donation_from_unknown_token_is_refunded() -> anyhow::Result<()> {
+    let env = init().await?;
+    let (ft, sponsor) = sponsor_token(&env, 5_000_000, false).await?;
+
+    donate(&env, &ft, &sponsor, 3_000_000, r#"{"action": "fund_matching_pool"}"#).await?;
+    assert_eq!(ft_balance(&ft, &sponsor).await?, 5_000_000);
+    let pool: Option<serde_json::Value> = env.token.view("get_matching_pool").await?.json()?;
+    assert!(pool.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ft_donation_with_malformed_msg_is_refunded() -> anyhow::Result<()> {
+    let env = init().await?;
+    let (ft, sponsor) = sponsor_token(&env, 5_000_000, true).await?;
+
+    donate(&env, &ft, &sponsor, 3_000_000, "not json").await?;
+    assert_eq!(ft_balance(&ft, &sponsor).await?, 5_000_000);
+
+    Ok(())
+}
+
+/// `wasm` with an extra custom section, so it hashes differently but runs
+/// the same.
+fn with_custom_section(wasm: &[u8], name: &str) -> Vec<u8> {
+    let payload = [&[name.len() as u8], name.as_bytes()].concat();
+    [wasm, &[0, payload.len() as u8], &payload].concat()
+}
+
+#[tokio::test]
+async fn staged_code_deploys_after_the_timelock_and_keeps_state() -> anyhow::Result<()> {
+    let env = init().await?;
+    let food_bank = food_bank(&env).await?;
+    fund(&env, &food_bank, 1).await?;
+
+    let wasm = workspaces::compile_project(TOKEN_PATH).await?;
+    let upgraded = with_custom_section(&wasm, "needio.upgrade");
+    // Watchers get the same hash from any account running the build.
+    let reference = env.worker.dev_deploy(&upgraded).await?;
+    let code_hash = reference.view_account().await?.code_hash.to_string();
+    env.owner
+        .call(env.token.id(), "stage_code")
+        .args_json(json!({ "code_hash": code_hash }))
+        .transact()
+        .await?
+        .into_result()?;
+    let staged: serde_json::Value = env.token.view("get_staged_code").await?.json()?;
+    assert_eq!(staged["code_hash"], code_hash);
+
+    let early = env
+        .owner
+        .call(env.token.id(), "deploy_staged_code")
+        .args(upgraded.clone())
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(early.is_failure());
+
+    env.worker.fast_forward(TIMELOCK_BLOCKS).await?;
+    env.owner
+        .call(env.token.id(), "deploy_staged_code")
+        .args(upgraded.clone())
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(env.token.view_code().await?, upgraded);
+    assert_eq!(balance(&env, &food_bank).await?, 100);
+    let rate: u16 = env.token.view("get_reward_rate_bps").await?.json()?;
+    assert_eq!(rate, 10000);
+    let staged: Option<serde_json::Value> = env.token.view("get_staged_code").await?.json()?;
+    assert!(staged.is_none());
+    // Recorded by the new code's callback once `migrate` went through.
+    let page: serde_json::Value = env
+        .token
+        .view("get_events_since")
+        .args_json(json!({ "sequence": 0, "limit": 1_000 }))
+        .await?
+        .json()?;
+    let last = page["events"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last["event"], "code_deployed");
+    assert_eq!(last["data"]["code_hash"], code_hash);
+
+    Ok(())
+}
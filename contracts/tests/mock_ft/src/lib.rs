@@ -0,0 +1,98 @@
+//! Minimal NEP-141 token for the donation integration tests: balances,
+//! `ft_transfer_call` and the refund of whatever the receiver reports as
+//! unused. Storage registration and metadata are left out. Like
+//! `benefits_stub`, it has no manifest yet.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    env, ext_contract, near_bindgen, serde_json, AccountId, Balance, Gas, Promise,
+    PromiseOrValue, PromiseResult,
+};
+
+const GAS_FOR_FT_ON_TRANSFER: Gas = Gas(50_000_000_000_000);
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+#[allow(dead_code)]
+#[ext_contract(ext_receiver)]
+trait FungibleTokenReceiver {
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String)
+        -> PromiseOrValue<U128>;
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MockFt {
+    balances: LookupMap<AccountId, Balance>,
+}
+
+impl Default for MockFt {
+    fn default() -> Self {
+        Self {
+            balances: LookupMap::new(b"b"),
+        }
+    }
+}
+
+#[near_bindgen]
+impl MockFt {
+    pub fn mint(&mut self, account_id: AccountId, amount: U128) {
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        self.balances.insert(&account_id, &(balance + amount.0));
+    }
+
+    pub fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        U128(self.balances.get(&account_id).unwrap_or(0))
+    }
+
+    #[payable]
+    pub fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        #[allow(unused_variables)] memo: Option<String>,
+        msg: String,
+    ) -> Promise {
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, amount.0);
+        ext_receiver::ext(receiver_id.clone())
+            .with_static_gas(GAS_FOR_FT_ON_TRANSFER)
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .ft_resolve_transfer(sender_id, receiver_id, amount),
+            )
+    }
+
+    /// Returns the amount that stayed with the receiver.
+    #[private]
+    pub fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        let unused = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<U128>(&value)
+                .map_or(amount.0, |unused| unused.0.min(amount.0)),
+            _ => amount.0,
+        };
+        let refund = unused.min(self.balances.get(&receiver_id).unwrap_or(0));
+        if refund > 0 {
+            self.internal_transfer(&receiver_id, &sender_id, refund);
+        }
+        U128(amount.0 - refund)
+    }
+}
+
+impl MockFt {
+    fn internal_transfer(&mut self, from: &AccountId, to: &AccountId, amount: Balance) {
+        let from_balance = self.balances.get(from).unwrap_or(0);
+        assert!(from_balance >= amount, "Insufficient balance");
+        self.balances.insert(from, &(from_balance - amount));
+        let to_balance = self.balances.get(to).unwrap_or(0);
+        self.balances.insert(to, &(to_balance + amount));
+    }
+}